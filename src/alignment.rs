@@ -0,0 +1,156 @@
+//! Manifest entries checked against the files a commit changes
+//!
+//! An entry whose file the commit doesn't change describes some other commit,
+//! and a changed file no entry covers has no recorded intent. `gip commit`
+//! warns about the first and rejects the second, unless
+//! `[validation] unlisted_files` in `.gip/config.toml` says `warn` or `off`.
+
+use crate::manifest::{Manifest, CHANGE_ADD, CHANGE_DELETE, CHANGE_MODIFY};
+use crate::pathspec::PathsConfig;
+use serde::Deserialize;
+
+/// How `gip commit` treats changed files without an entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Enforcement {
+    Off,
+    Warn,
+    #[default]
+    Error,
+}
+
+/// A file a commit changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedFile {
+    /// Status letter: `A`, `M`, `D`, `R`, `C`, `T`
+    pub status: char,
+    pub path: String,
+    /// Source of a rename or copy
+    pub old_path: Option<String>,
+}
+
+impl ChangedFile {
+    /// How the change to this file is described in a manifest
+    pub fn change_type(&self) -> &'static str {
+        match self.status {
+            'A' | 'C' => CHANGE_ADD,
+            'D' => CHANGE_DELETE,
+            _ => CHANGE_MODIFY,
+        }
+    }
+}
+
+/// What doesn't line up between a manifest and the changed files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Alignment {
+    /// Entries, as `file::symbol`, whose file isn't changed
+    pub untouched: Vec<String>,
+    /// Changed files no entry covers
+    pub unlisted: Vec<String>,
+}
+
+/// Parse `git commit --porcelain -z` output into the files the commit changes
+///
+/// Each record is `XY <path>`, where `X` is what the commit does to the path;
+/// a rename or copy is followed by its source. Paths the commit leaves alone
+/// (`X` blank, `?` untracked, `!` ignored) are skipped.
+pub fn parse_porcelain(output: &str) -> Vec<ChangedFile> {
+    let mut files = Vec::new();
+    let mut records = output.split('\0').filter(|r| !r.is_empty());
+    while let Some(record) = records.next() {
+        let (Some(status), Some(path)) = (record.chars().next(), record.get(3..)) else {
+            continue;
+        };
+        // Either column can be a rename, and then the source comes next
+        let renamed = record[..3].contains(['R', 'C']);
+        let old_path = renamed.then(|| records.next().unwrap_or_default());
+        if matches!(status, ' ' | '?' | '!') {
+            continue;
+        }
+        files.push(ChangedFile {
+            status,
+            path: path.to_string(),
+            old_path: old_path.map(str::to_string),
+        });
+    }
+    files
+}
+
+/// Compare the entries of a manifest with the files a commit changes
+///
+/// An entry covers a file when its anchor names the file, a directory above
+/// it, or a glob matching it. A renamed file is covered through either name.
+pub fn check(manifest: &Manifest, files: &[ChangedFile], paths: &PathsConfig) -> Alignment {
+    let names =
+        |file: &ChangedFile| std::iter::once(file.path.clone()).chain(file.old_path.clone());
+    let covers = |anchor: &str, file: &ChangedFile| names(file).any(|p| paths.matches(anchor, &p));
+
+    let untouched = manifest
+        .entries
+        .iter()
+        .filter(|e| !files.iter().any(|f| covers(&e.anchor.file, f)))
+        .map(|e| format!("{}::{}", e.anchor.file, e.anchor.symbol))
+        .collect();
+    let unlisted = files
+        .iter()
+        .filter(|f| !manifest.entries.iter().any(|e| covers(&e.anchor.file, f)))
+        .map(|f| f.path.clone())
+        .collect();
+    Alignment {
+        untouched,
+        unlisted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::Anchor;
+    use crate::stage;
+
+    #[test]
+    fn test_parse_porcelain() {
+        let files = parse_porcelain(
+            "M  src/pay.rs\0R  src/new.rs\0src/old.rs\0 M src/cart.rs\0D  gone.md\0?? tmp.txt\0",
+        );
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].status, 'M');
+        assert_eq!(files[1].path, "src/new.rs");
+        assert_eq!(files[1].old_path.as_deref(), Some("src/old.rs"));
+        assert_eq!(files[2].path, "gone.md");
+        assert_eq!(files[0].change_type(), CHANGE_MODIFY);
+        assert_eq!(files[2].change_type(), CHANGE_DELETE);
+        assert!(parse_porcelain("").is_empty());
+    }
+
+    #[test]
+    fn test_check() {
+        let mut manifest = Manifest::new("HEAD".to_string());
+        for (file, symbol) in [
+            ("src/pay.rs", "charge"),
+            ("src/old.rs", "run"),
+            ("docs/**", "*"),
+            ("src/cart.rs", "total"),
+        ] {
+            let anchor = Anchor {
+                file: file.to_string(),
+                symbol: symbol.to_string(),
+                hunk_id: "H#1".to_string(),
+            };
+            manifest
+                .entries
+                .push(stage::entry_for(anchor, CHANGE_MODIFY, "Why", "feature"));
+        }
+        let files = parse_porcelain(
+            "M  src/pay.rs\0R  src/new.rs\0src/old.rs\0A  docs/guide.md\0M  Cargo.lock\0",
+        );
+
+        let alignment = check(&manifest, &files, &PathsConfig::default());
+        assert_eq!(alignment.untouched, vec!["src/cart.rs::total"]);
+        assert_eq!(alignment.unlisted, vec!["Cargo.lock"]);
+
+        let ignore_case = PathsConfig { ignore_case: true };
+        let upper = parse_porcelain("M  SRC/PAY.RS\0");
+        assert!(check(&manifest, &upper, &ignore_case).unlisted.is_empty());
+    }
+}
@@ -0,0 +1,228 @@
+//! Built-in regex-based analyzers for common languages
+//!
+//! These are intentionally heuristic: they recognize definition lines, not full
+//! syntax trees, which is enough to anchor manifest entries to symbols.
+
+use super::{Analyzer, Symbol};
+use regex::Regex;
+
+/// Identifiers that look like calls in definition patterns but never name a symbol
+const NON_SYMBOLS: &[&str] = &[
+    "if", "for", "while", "switch", "catch", "return", "new", "else", "match", "function",
+];
+
+/// Analyzer driven by a list of `(kind, pattern)` rules with a `name` capture group
+pub struct RegexAnalyzer {
+    name: &'static str,
+    rules: Vec<(&'static str, Regex)>,
+}
+
+impl RegexAnalyzer {
+    fn new(name: &'static str, rules: &[(&'static str, &str)]) -> Self {
+        Self {
+            name,
+            rules: rules
+                .iter()
+                .map(|(kind, pattern)| {
+                    (
+                        *kind,
+                        Regex::new(pattern).expect("built-in analyzer pattern is valid"),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Analyzer for Rust sources
+    pub fn rust() -> Self {
+        Self::new(
+            "rust",
+            &[
+                (
+                    "fn",
+                    r#"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:const\s+)?(?:async\s+)?(?:unsafe\s+)?(?:extern\s+"[^"]*"\s+)?fn\s+(?P<name>[A-Za-z_]\w*)"#,
+                ),
+                (
+                    "type",
+                    r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:struct|enum|trait|union|type)\s+(?P<name>[A-Za-z_]\w*)",
+                ),
+                (
+                    "mod",
+                    r"^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+(?P<name>[A-Za-z_]\w*)",
+                ),
+                (
+                    "const",
+                    r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:const|static)\s+(?:mut\s+)?(?P<name>[A-Z_][A-Z0-9_]*)\s*:",
+                ),
+            ],
+        )
+    }
+
+    /// Analyzer for Python sources
+    pub fn python() -> Self {
+        Self::new(
+            "python",
+            &[
+                ("def", r"^\s*(?:async\s+)?def\s+(?P<name>[A-Za-z_]\w*)"),
+                ("class", r"^\s*class\s+(?P<name>[A-Za-z_]\w*)"),
+            ],
+        )
+    }
+
+    /// Analyzer for TypeScript and JavaScript sources
+    pub fn typescript() -> Self {
+        Self::new(
+            "typescript",
+            &[
+                (
+                    "function",
+                    r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*(?P<name>[\w$]+)",
+                ),
+                (
+                    "class",
+                    r"^\s*(?:export\s+)?(?:default\s+)?(?:abstract\s+)?class\s+(?P<name>[\w$]+)",
+                ),
+                (
+                    "interface",
+                    r"^\s*(?:export\s+)?(?:interface|type|enum)\s+(?P<name>[\w$]+)",
+                ),
+                (
+                    "function",
+                    r"^\s*(?:export\s+)?(?:const|let|var)\s+(?P<name>[\w$]+)\s*(?::[^=]+)?=\s*(?:async\s*)?(?:\([^)]*\)|[\w$]+)\s*(?::[^=]+)?=>",
+                ),
+                (
+                    "method",
+                    r"^\s+(?:(?:public|private|protected|static|async|readonly|override|get|set)\s+)*(?P<name>[\w$]+)\s*\([^)]*\)\s*(?::\s*[^{]+)?\{\s*$",
+                ),
+            ],
+        )
+    }
+
+    /// Analyzer for Go sources
+    pub fn go() -> Self {
+        Self::new(
+            "go",
+            &[
+                ("func", r"^func\s+(?:\([^)]*\)\s*)?(?P<name>[A-Za-z_]\w*)"),
+                ("type", r"^type\s+(?P<name>[A-Za-z_]\w*)"),
+            ],
+        )
+    }
+
+    /// Analyzer for Java sources
+    pub fn java() -> Self {
+        Self::new(
+            "java",
+            &[
+                (
+                    "class",
+                    r"^\s*(?:(?:public|private|protected|abstract|final|static|sealed)\s+)*(?:class|interface|enum|record)\s+(?P<name>\w+)",
+                ),
+                (
+                    "method",
+                    r"^\s*(?:(?:public|private|protected|static|final|abstract|synchronized|native|default)\s+)*(?:<[^>]+>\s+)?[\w<>\[\],.?]+\s+(?P<name>\w+)\s*\([^;]*$",
+                ),
+            ],
+        )
+    }
+}
+
+impl Analyzer for RegexAnalyzer {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn detect_symbols(&self, source: &str) -> Vec<Symbol> {
+        let mut symbols = Vec::new();
+
+        for (idx, line) in source.lines().enumerate() {
+            for (kind, re) in &self.rules {
+                let name = match re.captures(line).and_then(|c| c.name("name")) {
+                    Some(m) => m.as_str(),
+                    None => continue,
+                };
+                if NON_SYMBOLS.contains(&name) {
+                    continue;
+                }
+
+                symbols.push(Symbol {
+                    name: name.to_string(),
+                    kind: kind.to_string(),
+                    line: idx + 1,
+                    signature: signature_of(line),
+                });
+                // The first matching rule wins for a line
+                break;
+            }
+        }
+
+        symbols
+    }
+}
+
+/// Trim a definition line down to its signature
+fn signature_of(line: &str) -> String {
+    line.trim()
+        .trim_end_matches('{')
+        .trim_end_matches(':')
+        .trim_end()
+        .to_string()
+}
+
+/// Built-in analyzer for a file extension
+pub fn for_extension(extension: &str) -> Option<RegexAnalyzer> {
+    match extension {
+        "rs" => Some(RegexAnalyzer::rust()),
+        "py" | "pyi" => Some(RegexAnalyzer::python()),
+        "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs" => Some(RegexAnalyzer::typescript()),
+        "go" => Some(RegexAnalyzer::go()),
+        "java" => Some(RegexAnalyzer::java()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(analyzer: &RegexAnalyzer, source: &str) -> Vec<String> {
+        analyzer
+            .detect_symbols(source)
+            .into_iter()
+            .map(|s| s.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_rust_symbols_and_signature() {
+        let source = "pub struct Cart {}\n\nimpl Cart {\n    pub async fn total(&self, tax: f64) -> f64 {\n        0.0\n    }\n}\n";
+        let rust = RegexAnalyzer::rust();
+        assert_eq!(names(&rust, source), vec!["Cart", "total"]);
+        assert_eq!(
+            rust.extract_signature(source, "Cart::total").unwrap(),
+            "pub async fn total(&self, tax: f64) -> f64"
+        );
+        assert_eq!(
+            rust.enclosing_symbol_at_line(source, 5).unwrap().name,
+            "total"
+        );
+    }
+
+    #[test]
+    fn test_typescript_symbols() {
+        let source = "export class Api {\n  async fetch(id: string): Promise<User> {\n    if (id) {\n    }\n  }\n}\nexport const load = async (x) => x;\n";
+        assert_eq!(
+            names(&RegexAnalyzer::typescript(), source),
+            vec!["Api", "fetch", "load"]
+        );
+    }
+
+    #[test]
+    fn test_go_and_java_symbols() {
+        let go = "type Server struct {}\n\nfunc (s *Server) Start() error {\n\treturn nil\n}\n";
+        assert_eq!(names(&RegexAnalyzer::go(), go), vec!["Server", "Start"]);
+
+        let java = "public class Cart {\n    public List<Item> items(int limit) {\n        return repo.find(limit);\n    }\n}\n";
+        assert_eq!(names(&RegexAnalyzer::java(), java), vec!["Cart", "items"]);
+    }
+}
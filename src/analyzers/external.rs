@@ -0,0 +1,178 @@
+//! External-process analyzer plugins
+//!
+//! An executable at `.gip/analyzers/<extension>` (for example `.gip/analyzers/zig`)
+//! handles files with that extension. Gip writes a JSON request to its stdin:
+//!
+//! ```json
+//! { "operation": "detectSymbols", "source": "<file contents>" }
+//! ```
+//!
+//! and expects a JSON array of symbols on stdout:
+//!
+//! ```json
+//! [{ "name": "main", "kind": "fn", "line": 3, "signature": "pub fn main() void" }]
+//! ```
+//!
+//! Signature extraction and enclosing-symbol lookup are derived from that list.
+//! Plugins git tracks only run in trusted repositories, see [`crate::trust`].
+
+use super::{Analyzer, Symbol};
+use crate::trust;
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Request sent to an analyzer plugin
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginRequest<'a> {
+    operation: &'a str,
+    source: &'a str,
+}
+
+/// Analyzer backed by an external executable
+pub struct ExternalAnalyzer {
+    name: String,
+    program: PathBuf,
+}
+
+impl ExternalAnalyzer {
+    /// Look up a plugin for a file extension in `<gip_dir>/analyzers/`
+    pub fn find(gip_dir: &Path, extension: &str) -> Option<Self> {
+        let dir = gip_dir.join("analyzers");
+        [extension.to_string(), format!("{}.exe", extension)]
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+            .filter(|program| trust::allows_program(program))
+            .map(|program| Self {
+                name: extension.to_string(),
+                program,
+            })
+    }
+
+    fn invoke(&self, operation: &str, source: &str) -> Result<Vec<Symbol>> {
+        let request = serde_json::to_string(&PluginRequest { operation, source })?;
+
+        let mut child = Command::new(&self.program)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("Failed to start analyzer {:?}", self.program))?;
+
+        let mut stdin = child.stdin.take().context("Failed to write to analyzer")?;
+        // Written from a thread so a plugin streaming its output before it has
+        // read the whole request can't stall both sides
+        let writer = std::thread::spawn(move || stdin.write_all(request.as_bytes()));
+        let output = child.wait_with_output()?;
+        let _ = writer.join();
+        if !output.status.success() {
+            anyhow::bail!("Analyzer {:?} exited with {}", self.program, output.status);
+        }
+
+        let symbols: Vec<Symbol> = serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("Analyzer {:?} returned invalid JSON", self.program))?;
+        // Lines are 1-based; a plugin reporting 0 is wrong about that symbol
+        let (symbols, invalid): (Vec<_>, Vec<_>) = symbols.into_iter().partition(|s| s.line > 0);
+        for s in invalid {
+            eprintln!(
+                "{}",
+                format!(
+                    "Warning: Analyzer {:?} reported {} at line 0; ignored",
+                    self.program, s.name
+                )
+                .yellow()
+            );
+        }
+        Ok(symbols)
+    }
+}
+
+impl Analyzer for ExternalAnalyzer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn detect_symbols(&self, source: &str) -> Vec<Symbol> {
+        match self.invoke("detectSymbols", source) {
+            Ok(symbols) => symbols,
+            Err(e) => {
+                eprintln!("{}", format!("Warning: {}", e).yellow());
+                Vec::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_plugin_by_extension() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let analyzers = temp_dir.path().join("analyzers");
+        std::fs::create_dir_all(&analyzers).unwrap();
+        std::fs::write(analyzers.join("zig"), "#!/bin/sh\n").unwrap();
+
+        let plugin = ExternalAnalyzer::find(temp_dir.path(), "zig").unwrap();
+        assert_eq!(plugin.name(), "zig");
+        assert!(ExternalAnalyzer::find(temp_dir.path(), "nim").is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_protocol() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let analyzers = temp_dir.path().join("analyzers");
+        std::fs::create_dir_all(&analyzers).unwrap();
+        let script = analyzers.join("zig");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\ncat > /dev/null\necho '[{\"name\":\"main\",\"kind\":\"fn\",\"line\":1,\"signature\":\"pub fn main() void\"},{\"name\":\"bad\",\"kind\":\"fn\",\"line\":0,\"signature\":\"fn bad()\"}]'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let plugin = ExternalAnalyzer::find(temp_dir.path(), "zig").unwrap();
+        let source = "pub fn main() void {\n    run();\n}\n";
+        assert_eq!(
+            plugin.extract_signature(source, "main").unwrap(),
+            "pub fn main() void"
+        );
+        assert_eq!(
+            plugin.enclosing_symbol_at_line(source, 2).unwrap().name,
+            "main"
+        );
+        // The symbol reported at line 0 is dropped
+        assert_eq!(plugin.detect_symbols(source).len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_writing_before_reading() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let analyzers = temp_dir.path().join("analyzers");
+        std::fs::create_dir_all(&analyzers).unwrap();
+        let script = analyzers.join("zig");
+        // More output than a pipe holds, all of it before reading the request
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nhead -c 300000 /dev/zero | tr '\\0' ' '\ncat > /dev/null\necho '[]'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let plugin = ExternalAnalyzer::find(temp_dir.path(), "zig").unwrap();
+        let source = "fn run() {}\n".repeat(30_000);
+        assert!(plugin.invoke("detectSymbols", &source).unwrap().is_empty());
+    }
+}
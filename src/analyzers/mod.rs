@@ -0,0 +1,120 @@
+//! Language analyzers - locate symbols and signatures in source files
+//!
+//! Built-in analyzers cover Rust, Python, TypeScript/JavaScript, Go, and Java.
+//! Teams can add other languages without forking gip by dropping an executable
+//! into `.gip/analyzers/` (see [`external`]).
+
+pub mod builtin;
+pub mod external;
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A symbol definition found in a source file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Symbol {
+    pub name: String,
+    /// Kind of definition, e.g. `fn`, `class`, `method`
+    pub kind: String,
+    /// 1-based line of the definition
+    pub line: usize,
+    /// The definition line, trimmed of its body opener
+    pub signature: String,
+}
+
+/// Language-specific symbol analysis
+pub trait Analyzer {
+    /// Short name of the language
+    fn name(&self) -> &str;
+
+    /// Detect all symbol definitions in a source file, in line order
+    fn detect_symbols(&self, source: &str) -> Vec<Symbol>;
+
+    /// Extract the signature of a symbol, if it is defined in the source
+    fn extract_signature(&self, source: &str, symbol: &str) -> Option<String> {
+        let name = crate::symbols::base_name(symbol);
+        self.detect_symbols(source)
+            .into_iter()
+            .find(|s| s.name == name)
+            .map(|s| s.signature)
+    }
+
+    /// Find the innermost symbol enclosing a 1-based line
+    ///
+    /// The default uses indentation: the closest definition at or above the line
+    /// that is indented less than the line itself (or is the line).
+    fn enclosing_symbol_at_line(&self, source: &str, line: usize) -> Option<Symbol> {
+        let lines: Vec<&str> = source.lines().collect();
+        let target = lines.get(line.checked_sub(1)?)?;
+        let target_indent = indentation(target);
+
+        self.detect_symbols(source)
+            .into_iter()
+            .rev()
+            .filter(|s| s.line <= line)
+            .find(|s| {
+                s.line == line
+                    || s.line
+                        .checked_sub(1)
+                        .and_then(|i| lines.get(i))
+                        .is_some_and(|l| indentation(l) < target_indent)
+            })
+    }
+}
+
+/// Width of a line's leading whitespace
+pub fn indentation(line: &str) -> usize {
+    line.chars().take_while(|c| c.is_whitespace()).count()
+}
+
+/// Find the analyzer responsible for a file
+///
+/// External analyzers in `<gip_dir>/analyzers/` take precedence over built-ins so
+/// teams can override them.
+pub fn analyzer_for(path: &Path, gip_dir: Option<&Path>) -> Option<Box<dyn Analyzer>> {
+    let extension = path.extension()?.to_str()?;
+
+    if let Some(dir) = gip_dir {
+        if let Some(external) = external::ExternalAnalyzer::find(dir, extension) {
+            return Some(Box::new(external));
+        }
+    }
+
+    builtin::for_extension(extension).map(|a| Box::new(a) as Box<dyn Analyzer>)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyzer_for_extension() {
+        assert_eq!(
+            analyzer_for(Path::new("src/main.rs"), None).unwrap().name(),
+            "rust"
+        );
+        assert_eq!(
+            analyzer_for(Path::new("app/views.py"), None)
+                .unwrap()
+                .name(),
+            "python"
+        );
+        assert!(analyzer_for(Path::new("README.md"), None).is_none());
+    }
+
+    #[test]
+    fn test_enclosing_symbol_by_indentation() {
+        let source =
+            "class Cart:\n    def total(self):\n        return sum(self.items)\n\nTAX = 0.2\n";
+        let analyzer = analyzer_for(Path::new("cart.py"), None).unwrap();
+
+        let sym = analyzer.enclosing_symbol_at_line(source, 3).unwrap();
+        assert_eq!(sym.name, "total");
+
+        let sym = analyzer.enclosing_symbol_at_line(source, 2).unwrap();
+        assert_eq!(sym.name, "total");
+
+        assert!(analyzer.enclosing_symbol_at_line(source, 5).is_none());
+    }
+}
@@ -0,0 +1,83 @@
+//! Gutter text for `gip annotate`
+//!
+//! Each line of a file is labelled with the intent of the commit that last
+//! changed it: the commit, its behaviorClass tags and a shortened rationale.
+//! Consecutive lines with the same label form a block that is labelled once.
+
+use crate::manifest::Entry;
+
+/// Default width of the gutter column, in characters
+pub const GUTTER_WIDTH: usize = 48;
+
+/// Label for a line, given its commit and the manifest entries explaining it
+pub fn label(short_commit: &str, entries: &[&Entry], width: usize) -> String {
+    let Some(entry) = entries.first() else {
+        return short_commit.to_string();
+    };
+
+    let mut text = short_commit.to_string();
+    if !entry.behavior_class.is_empty() {
+        text.push_str(&format!(" [{}]", entry.behavior_class.join(",")));
+    }
+    let rationale = entry.rationale.lines().next().unwrap_or_default().trim();
+    text.push(' ');
+    text.push_str(rationale);
+
+    shorten(&text, width)
+}
+
+/// Cut text to at most `width` characters, marking the cut with `…`
+pub fn shorten(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Blank out labels repeating the line above, so each block is labelled once
+pub fn dedupe(labels: Vec<String>) -> Vec<String> {
+    let mut previous: Option<String> = None;
+    labels
+        .into_iter()
+        .map(|label| {
+            if previous.as_ref() == Some(&label) {
+                String::new()
+            } else {
+                previous = Some(label.clone());
+                label
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn entry(rationale: &str, classes: &[&str]) -> Entry {
+        test_support::entry(json!({ "rationale": rationale, "behaviorClass": classes }))
+    }
+
+    #[test]
+    fn test_label() {
+        let e = entry("Retry on timeout\nDetails follow", &["bugfix", "perf"]);
+        assert_eq!(
+            label("abc12345", &[&e], 80),
+            "abc12345 [bugfix,perf] Retry on timeout"
+        );
+        assert_eq!(label("abc12345", &[&e], 20), "abc12345 [bugfix,pe…");
+        assert_eq!(label("abc12345", &[], 80), "abc12345");
+    }
+
+    #[test]
+    fn test_dedupe_blocks() {
+        let labels = vec!["a", "a", "b", "a"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(dedupe(labels), vec!["a", "", "b", "a"]);
+    }
+}
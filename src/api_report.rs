@@ -0,0 +1,201 @@
+//! API surface changelog from signature deltas
+//!
+//! Compiles every `signatureDelta` recorded in manifests into one report per
+//! symbol, from its first `before` to its latest `after`. Signature changes the
+//! analyzers see in the code but no manifest documents are reported separately.
+
+use crate::analyzers::Analyzer;
+use crate::manifest::{Entry, Manifest};
+use crate::symbols;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// One recorded signature change
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiChange {
+    pub commit: String,
+    pub before: String,
+    pub after: String,
+    pub breaking: bool,
+    pub rationale: String,
+}
+
+/// All recorded signature changes of one symbol, oldest first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolReport {
+    pub file: String,
+    pub symbol: String,
+    pub before: String,
+    pub after: String,
+    pub changes: Vec<ApiChange>,
+}
+
+/// A signature change found in the code without a matching signature delta
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndocumentedChange {
+    pub commit: String,
+    pub file: String,
+    pub symbol: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// Compile signature deltas from manifests ordered oldest first
+pub fn compile(manifests: &[Manifest]) -> Vec<SymbolReport> {
+    let mut reports: BTreeMap<(String, String), SymbolReport> = BTreeMap::new();
+
+    for manifest in manifests {
+        for entry in &manifest.entries {
+            let Some(ref delta) = entry.signature_delta else {
+                continue;
+            };
+
+            let change = ApiChange {
+                commit: manifest.commit.clone(),
+                before: delta.before.clone(),
+                after: delta.after.clone(),
+                breaking: entry.compatibility.as_ref().is_some_and(|c| c.breaking),
+                rationale: entry.rationale.clone(),
+            };
+
+            reports
+                .entry((entry.anchor.file.clone(), entry.anchor.symbol.clone()))
+                .and_modify(|r| {
+                    r.after = change.after.clone();
+                    r.changes.push(change.clone());
+                })
+                .or_insert_with(|| SymbolReport {
+                    file: entry.anchor.file.clone(),
+                    symbol: entry.anchor.symbol.clone(),
+                    before: change.before.clone(),
+                    after: change.after.clone(),
+                    changes: vec![change],
+                });
+        }
+    }
+
+    reports.into_values().collect()
+}
+
+/// Whether a signature belongs to the public API
+///
+/// Heuristic across languages: explicit visibility keywords decide, otherwise
+/// names starting with `_` are private.
+pub fn is_public(signature: &str, symbol: &str) -> bool {
+    let words: Vec<&str> = signature
+        .split(|c: char| c.is_whitespace() || c == '(')
+        .collect();
+    if signature.contains("pub(") || words.iter().any(|w| matches!(*w, "private" | "protected")) {
+        return false;
+    }
+    if words
+        .iter()
+        .any(|w| matches!(*w, "pub" | "public" | "export"))
+    {
+        return true;
+    }
+    !symbols::base_name(symbol).starts_with('_')
+}
+
+/// Signatures that differ between two versions of a file, as `(name, before, after)`
+pub fn changed_signatures(
+    analyzer: &dyn Analyzer,
+    before: &str,
+    after: &str,
+) -> Vec<(String, String, String)> {
+    let old: HashMap<String, String> = analyzer
+        .detect_symbols(before)
+        .into_iter()
+        .map(|s| (s.name, s.signature))
+        .collect();
+
+    analyzer
+        .detect_symbols(after)
+        .into_iter()
+        .filter_map(|s| {
+            let previous = old.get(&s.name)?;
+            (normalize(previous) != normalize(&s.signature))
+                .then(|| (s.name, previous.clone(), s.signature))
+        })
+        .collect()
+}
+
+fn normalize(signature: &str) -> String {
+    signature.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Whether a manifest entry documents a signature change of `name` in `file`
+pub fn documents(entry: &Entry, file: &str, name: &str) -> bool {
+    entry.anchor.file == file
+        && symbols::base_name(&entry.anchor.symbol) == name
+        && entry.signature_delta.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzers::builtin::RegexAnalyzer;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn manifest(commit: &str, before: &str, after: &str, breaking: bool) -> Manifest {
+        test_support::manifest(
+            commit,
+            json!({
+                "anchor": { "file": "src/pay.rs", "symbol": "charge" },
+                "rationale": format!("change {}", commit),
+                "signatureDelta": { "before": before, "after": after },
+                "behaviorClass": ["feature"],
+                "compatibility": { "breaking": breaking }
+            }),
+        )
+    }
+
+    #[test]
+    fn test_compile_spans_first_before_to_last_after() {
+        let reports = compile(&[
+            manifest("a", "fn charge()", "fn charge(amount: u32)", true),
+            manifest(
+                "b",
+                "fn charge(amount: u32)",
+                "fn charge(amount: u64)",
+                false,
+            ),
+        ]);
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].before, "fn charge()");
+        assert_eq!(reports[0].after, "fn charge(amount: u64)");
+        assert_eq!(reports[0].changes.len(), 2);
+        assert!(reports[0].changes[0].breaking);
+    }
+
+    #[test]
+    fn test_is_public() {
+        assert!(is_public("pub fn charge(&self)", "charge"));
+        assert!(!is_public("pub(crate) fn charge(&self)", "charge"));
+        assert!(!is_public("fn charge(&self)", "_charge"));
+        assert!(is_public("export function charge()", "charge"));
+        assert!(!is_public("private void charge()", "charge"));
+        assert!(is_public("def charge(self)", "Cart.charge"));
+    }
+
+    #[test]
+    fn test_changed_signatures() {
+        let analyzer = RegexAnalyzer::rust();
+        let before = "pub fn charge(a: u32) {\n}\nfn helper() {\n}\n";
+        let after = "pub fn charge(a: u64) {\n}\nfn  helper() {\n}\nfn added() {\n}\n";
+
+        assert_eq!(
+            changed_signatures(&analyzer, before, after),
+            vec![(
+                "charge".to_string(),
+                "pub fn charge(a: u32)".to_string(),
+                "pub fn charge(a: u64)".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Compliance archives of every manifest
+//!
+//! An archive is a gzipped tarball that can be read without git: each
+//! manifest as `manifests/<sha>.json` and `manifests/<sha>.toon`, and an
+//! `index.json` listing the commits with their date, subject and files.
+//! Unlike a bundle, it is meant to be kept or analyzed, not imported back.
+
+use crate::git;
+use crate::manifest::{self, Manifest};
+use anyhow::{Context, Result};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use std::fs::File;
+use std::path::Path;
+
+/// Value of the index's `format` field identifying a gip archive
+pub const ARCHIVE_FORMAT: &str = "gip-archive";
+
+/// Current archive layout version
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Directory inside the archive holding the manifests
+const MANIFEST_DIR: &str = "manifests";
+
+/// `index.json` at the root of an archive
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveIndex {
+    pub format: String,
+    pub version: u32,
+    pub created_at: String,
+    pub commits: Vec<IndexEntry>,
+}
+
+/// One archived manifest
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexEntry {
+    pub commit: String,
+    /// Author date, RFC 3339; empty for commits missing from the repository
+    pub date: String,
+    pub subject: String,
+    pub entries: usize,
+    /// Files anchored by the entries, in order of first appearance
+    pub files: Vec<String>,
+    /// Paths of the two copies inside the archive
+    pub json: String,
+    pub toon: String,
+}
+
+impl IndexEntry {
+    fn new(manifest: &Manifest, cwd: Option<&Path>) -> Self {
+        let log = git::run_git_cmd(&["log", "-1", "--format=%aI%x00%s", &manifest.commit], cwd)
+            .unwrap_or_default();
+        let (date, subject) = log.split_once('\0').unwrap_or(("", ""));
+
+        let mut files: Vec<String> = Vec::new();
+        for entry in &manifest.entries {
+            if !files.contains(&entry.anchor.file) {
+                files.push(entry.anchor.file.clone());
+            }
+        }
+        IndexEntry {
+            commit: manifest.commit.clone(),
+            date: date.to_string(),
+            subject: subject.to_string(),
+            entries: manifest.entries.len(),
+            files,
+            json: format!("{}/{}.json", MANIFEST_DIR, manifest.commit),
+            toon: format!("{}/{}.toon", MANIFEST_DIR, manifest.commit),
+        }
+    }
+}
+
+/// Write `manifests`, each with its `commit` set, to a tar.gz at `output`
+pub fn write(output: &Path, manifests: &[Manifest], cwd: Option<&Path>) -> Result<ArchiveIndex> {
+    let file =
+        File::create(output).with_context(|| format!("Failed to create archive {:?}", output))?;
+    let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    let mtime = chrono::Utc::now();
+
+    let mut commits = Vec::new();
+    for m in manifests {
+        let entry = IndexEntry::new(m, cwd);
+        append(&mut tar, &entry.json, &serde_json::to_vec_pretty(m)?, mtime)?;
+        let toon = manifest::serialize_manifest_toon(m)
+            .with_context(|| format!("Failed to write manifest of {} as TOON", m.commit))?;
+        append(&mut tar, &entry.toon, toon.as_bytes(), mtime)?;
+        commits.push(entry);
+    }
+
+    let index = ArchiveIndex {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        created_at: mtime.to_rfc3339(),
+        commits,
+    };
+    append(
+        &mut tar,
+        "index.json",
+        &serde_json::to_vec_pretty(&index)?,
+        mtime,
+    )?;
+    tar.into_inner()?
+        .finish()
+        .with_context(|| format!("Failed to write archive {:?}", output))?;
+    Ok(index)
+}
+
+fn append<W: std::io::Write>(
+    tar: &mut tar::Builder<W>,
+    path: &str,
+    contents: &[u8],
+    mtime: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime.timestamp().max(0) as u64);
+    header.set_cksum();
+    tar.append_data(&mut header, path, contents)
+        .with_context(|| format!("Failed to add {} to the archive", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_write_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("context.tar.gz");
+        let m = test_support::manifest(
+            "abc123",
+            serde_json::json!({ "anchor": { "file": "src/pay.rs", "symbol": "charge" } }),
+        );
+
+        let index = write(&output, &[m], Some(dir.path())).unwrap();
+        assert_eq!(index.commits[0].files, vec!["src/pay.rs"]);
+        assert_eq!(index.commits[0].subject, "");
+
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(&output).unwrap()));
+        let mut names = Vec::new();
+        let mut index_json = String::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let name = entry.path().unwrap().display().to_string();
+            if name == "index.json" {
+                entry.read_to_string(&mut index_json).unwrap();
+            }
+            names.push(name);
+        }
+        assert_eq!(
+            names,
+            [
+                "manifests/abc123.json",
+                "manifests/abc123.toon",
+                "index.json"
+            ]
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&index_json).unwrap();
+        assert_eq!(parsed["format"], ARCHIVE_FORMAT);
+        assert_eq!(parsed["commits"][0]["entries"], 1);
+    }
+}
@@ -0,0 +1,109 @@
+//! Crash-safe file writes
+//!
+//! `fs::write` truncates the target before writing, so a crash or a full disk
+//! can leave a half-written conflicted file or manifest behind. [`write`]
+//! writes a temporary file in the same directory, flushes it to disk, and
+//! renames it over the target, so readers see either the old or the new
+//! content. The target's permissions are kept.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Replace `path` with `contents` atomically
+pub fn write<C: AsRef<[u8]>>(path: &Path, contents: C) -> io::Result<()> {
+    let tmp = temp_path(path);
+    let result = write_temp(path, &tmp, contents.as_ref()).and_then(|()| {
+        fs::rename(&tmp, path)?;
+        sync_dir(path);
+        Ok(())
+    });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Copy `from` over `to` atomically
+pub fn copy(from: &Path, to: &Path) -> io::Result<()> {
+    write(to, fs::read(from)?)
+}
+
+fn write_temp(path: &Path, tmp: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(tmp)?;
+    file.write_all(contents)?;
+    if let Ok(meta) = fs::metadata(path) {
+        file.set_permissions(meta.permissions())?;
+    }
+    file.sync_all()
+}
+
+/// Sibling of `path` for the pending write, unique to this process
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.gip-tmp-{}", name, std::process::id()))
+}
+
+/// Persist the rename itself; best effort, and not possible on Windows
+fn sync_dir(path: &Path) {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        if let Ok(d) = File::open(dir) {
+            let _ = d.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_replaces_content_without_leftovers() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("pay.rs");
+        fs::write(&path, "old").unwrap();
+
+        write(&path, "new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 1);
+
+        let copy_path = temp_dir.path().join("copy.rs");
+        copy(&path, &copy_path).unwrap();
+        assert_eq!(fs::read_to_string(&copy_path).unwrap(), "new");
+    }
+
+    #[test]
+    fn test_failed_write_keeps_original() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("missing-dir").join("pay.rs");
+        assert!(write(&path, "new").is_err());
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("run.sh");
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        write(&path, "#!/bin/sh\necho hi\n").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+}
@@ -0,0 +1,204 @@
+//! Append-only audit log of gip operations
+//!
+//! Every operation that changes or publishes intent is recorded as one JSON
+//! object per line in `.gip/audit.log`. The log is never rewritten, only
+//! appended to, so it can be handed to reviewers as-is.
+
+use crate::git;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// Audit log file name inside `.gip/`
+pub const AUDIT_FILE: &str = "audit.log";
+
+/// Kind of audited operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuditAction {
+    /// A commit was created with a manifest attached
+    CommitWithManifest,
+    /// A commit was created with `--force` and no manifest
+    ForcedCommit,
+    /// Conflict markers were enriched during a merge or rebase
+    Enrichment,
+    /// A conflict was resolved automatically from intent
+    AutoResolution,
+    /// Context notes were pushed to a remote
+    NotesPush,
+    /// Notes on unreachable commits were removed by `gip gc`
+    NotesPrune,
+    /// A branch policy was bypassed with `--override`
+    PolicyOverride,
+    /// The manifest of an existing commit was edited with `gip amend-context`
+    ContextAmend,
+    /// Conflict hunks were resolved by hand with `gip resolve`
+    ManualResolution,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::CommitWithManifest => "commit-with-manifest",
+            AuditAction::ForcedCommit => "forced-commit",
+            AuditAction::Enrichment => "enrichment",
+            AuditAction::AutoResolution => "auto-resolution",
+            AuditAction::NotesPush => "notes-push",
+            AuditAction::NotesPrune => "notes-prune",
+            AuditAction::PolicyOverride => "policy-override",
+            AuditAction::ContextAmend => "context-amend",
+            AuditAction::ManualResolution => "manual-resolution",
+        }
+    }
+}
+
+/// A single audit log record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    pub timestamp: DateTime<Utc>,
+    pub action: AuditAction,
+    /// Git user email of whoever ran the operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Free-form details, e.g. the remote or the number of enriched files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Append an event to `<gip_dir>/audit.log`
+pub fn append(gip_dir: &Path, event: &AuditEvent) -> Result<()> {
+    fs::create_dir_all(gip_dir).context("Failed to create .gip directory")?;
+    let path = gip_dir.join(AUDIT_FILE);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open audit log {:?}", path))?;
+    writeln!(file, "{}", serde_json::to_string(event)?)
+        .with_context(|| format!("Failed to write audit log {:?}", path))?;
+
+    Ok(())
+}
+
+/// Record an operation in the current repository's audit log
+///
+/// Auditing never aborts the operation being audited; failures are printed as
+/// warnings.
+pub fn record(action: AuditAction, commit: Option<&str>, detail: Option<String>) {
+    let result = git::get_gip_dir().and_then(|gip_dir| {
+        let event = AuditEvent {
+            timestamp: Utc::now(),
+            action,
+            user: git::run_git_cmd(&["config", "user.email"], None).ok(),
+            commit: commit.map(str::to_string),
+            detail,
+        };
+        append(&gip_dir, &event)
+    });
+
+    if let Err(e) = result {
+        eprintln!(
+            "{}",
+            format!("Warning: Failed to write audit log: {}", e).yellow()
+        );
+    }
+}
+
+/// Read all events from `<gip_dir>/audit.log`, oldest first
+pub fn read(gip_dir: &Path) -> Result<Vec<AuditEvent>> {
+    let path = gip_dir.join(AUDIT_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read audit log {:?}", path))?;
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str(line)
+                .with_context(|| format!("Invalid audit log record on line {}", idx + 1))
+        })
+        .collect()
+}
+
+/// Parse a `--since` value: an RFC 3339 timestamp or a `YYYY-MM-DD` date
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").with_context(|| {
+        format!(
+            "Invalid date '{}'. Use YYYY-MM-DD or an RFC 3339 timestamp",
+            value
+        )
+    })?;
+    Ok(date
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(action: AuditAction, timestamp: &str) -> AuditEvent {
+        AuditEvent {
+            timestamp: parse_since(timestamp).unwrap(),
+            action,
+            user: Some("dev@example.com".to_string()),
+            commit: Some("abc123".to_string()),
+            detail: None,
+        }
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let first = event(AuditAction::CommitWithManifest, "2024-01-01");
+        let second = event(AuditAction::NotesPush, "2024-01-02T10:00:00Z");
+
+        append(temp_dir.path(), &first).unwrap();
+        append(temp_dir.path(), &second).unwrap();
+
+        let content = fs::read_to_string(temp_dir.path().join(AUDIT_FILE)).unwrap();
+        assert_eq!(content.lines().count(), 2);
+        assert!(content.contains(r#""action":"commit-with-manifest""#));
+
+        assert_eq!(read(temp_dir.path()).unwrap(), vec![first, second]);
+    }
+
+    #[test]
+    fn test_read_missing_log() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert!(read(temp_dir.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        assert_eq!(
+            parse_since("2024-03-05").unwrap().to_rfc3339(),
+            "2024-03-05T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_since("2024-03-05T12:00:00+02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2024-03-05T10:00:00+00:00"
+        );
+        assert!(parse_since("last week").is_err());
+    }
+}
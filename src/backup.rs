@@ -0,0 +1,119 @@
+//! Snapshots of the notes ref
+//!
+//! Operations that rewrite or delete many notes (`gip gc`, `gip migrate`,
+//! `gip remap`, `gip import-bundle`) first copy `refs/notes/gip` to
+//! `refs/gip/backup/<timestamp>`. `gip restore-notes` rolls back to one of them.
+
+use crate::git;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::*;
+use std::path::Path;
+
+/// Namespace holding the snapshots
+pub const BACKUP_PREFIX: &str = "refs/gip/backup/";
+
+/// Snapshot ref name for a point in time
+pub fn backup_ref(at: DateTime<Utc>) -> String {
+    format!("{}{}", BACKUP_PREFIX, at.format("%Y%m%dT%H%M%SZ"))
+}
+
+/// Expand a snapshot name (`20240101T120000Z`) to its full ref
+pub fn resolve(name: &str) -> String {
+    if name.starts_with("refs/") {
+        name.to_string()
+    } else {
+        format!("{}{}", BACKUP_PREFIX, name)
+    }
+}
+
+/// Copy `refs/notes/gip` to a new snapshot ref
+///
+/// Returns the snapshot ref, or `None` when there are no notes to back up.
+pub fn snapshot(cwd: Option<&Path>) -> Result<Option<String>> {
+    let notes_ref = git::notes_ref_full();
+    let Ok(notes) = git::run_git_cmd(&["rev-parse", "--verify", "-q", &notes_ref], cwd) else {
+        return Ok(None);
+    };
+
+    let base = backup_ref(Utc::now());
+    let mut name = base.clone();
+    let mut n = 1;
+    while git::run_git_cmd(&["rev-parse", "--verify", "-q", &name], cwd).is_ok() {
+        n += 1;
+        name = format!("{}-{}", base, n);
+    }
+
+    git::run_git_cmd(&["update-ref", &name, &notes], cwd)
+        .with_context(|| format!("Failed to back up {}", notes_ref))?;
+    Ok(Some(name))
+}
+
+/// Snapshot the notes before a destructive operation, reporting where to
+pub fn snapshot_before(operation: &str) -> Result<()> {
+    if let Some(name) = snapshot(None)? {
+        println!(
+            "{}",
+            format!(
+                "Backed up notes to {} before {} (undo with: gip restore-notes {})",
+                name,
+                operation,
+                name.trim_start_matches(BACKUP_PREFIX)
+            )
+            .dimmed()
+        );
+    }
+    Ok(())
+}
+
+/// Snapshot refs, newest first
+pub fn list(cwd: Option<&Path>) -> Result<Vec<String>> {
+    let output = git::run_git_cmd(
+        &[
+            "for-each-ref",
+            "--sort=-refname",
+            "--format=%(refname)",
+            BACKUP_PREFIX,
+        ],
+        cwd,
+    )?;
+    Ok(output.lines().map(str::to_string).collect())
+}
+
+/// Point `refs/notes/gip` at a snapshot
+///
+/// The current notes are snapshotted first, so a restore can itself be undone.
+/// Returns the snapshot of the replaced notes, if there were any.
+pub fn restore(name: &str, cwd: Option<&Path>) -> Result<Option<String>> {
+    let backup = resolve(name);
+    let target = git::run_git_cmd(&["rev-parse", "--verify", "-q", &backup], cwd)
+        .with_context(|| format!("No notes backup named {}", name))?;
+
+    let previous = snapshot(cwd)?;
+    let notes_ref = git::notes_ref_full();
+    git::run_git_cmd(&["update-ref", &notes_ref, &target], cwd)
+        .with_context(|| format!("Failed to restore {}", notes_ref))?;
+    Ok(previous)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_ref_names() {
+        let at = DateTime::parse_from_rfc3339("2024-03-05T14:07:09Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(backup_ref(at), "refs/gip/backup/20240305T140709Z");
+
+        assert_eq!(
+            resolve("20240305T140709Z"),
+            "refs/gip/backup/20240305T140709Z"
+        );
+        assert_eq!(
+            resolve("refs/gip/backup/20240305T140709Z"),
+            "refs/gip/backup/20240305T140709Z"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Intent-aware bisection
+//!
+//! Ranks the commits left in a bisect range by how likely they are to have
+//! introduced a regression, judged from their manifests: features and
+//! performance work touch behaviour, documentation changes almost never do.
+
+use crate::manifest::{
+    Manifest, BEHAVIOR_BUGFIX, BEHAVIOR_CONFIG, BEHAVIOR_DOCS, BEHAVIOR_FEATURE,
+    BEHAVIOR_MIGRATION, BEHAVIOR_PERF, BEHAVIOR_REFACTOR, BEHAVIOR_SECURITY, BEHAVIOR_VALIDATION,
+};
+use std::cmp::Reverse;
+
+/// Score for commits without a manifest: unknown, so mid-range
+const UNKNOWN_SCORE: u32 = 20;
+
+/// A commit in the bisect range with its risk score
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suspect {
+    pub commit: String,
+    pub score: u32,
+    pub summary: String,
+}
+
+/// How likely a behavior class is to cause a regression, from 0 to 5
+pub fn class_weight(class: &str) -> u32 {
+    match class {
+        BEHAVIOR_FEATURE | BEHAVIOR_PERF => 5,
+        BEHAVIOR_BUGFIX | BEHAVIOR_REFACTOR | BEHAVIOR_MIGRATION => 4,
+        BEHAVIOR_SECURITY | BEHAVIOR_VALIDATION => 3,
+        BEHAVIOR_CONFIG => 2,
+        BEHAVIOR_DOCS => 0,
+        _ => 1,
+    }
+}
+
+/// Risk score of a commit's manifest
+///
+/// The riskiest behavior class dominates; breaking changes and the number of
+/// touched symbols break ties.
+pub fn risk_score(manifest: &Manifest) -> u32 {
+    let classes = manifest
+        .entries
+        .iter()
+        .flat_map(|e| e.behavior_class.iter())
+        .chain(
+            manifest
+                .global_intent
+                .iter()
+                .flat_map(|g| g.behavior_class.iter()),
+        );
+    let weight = classes.map(|c| class_weight(c)).max().unwrap_or(1);
+
+    let breaking = manifest
+        .entries
+        .iter()
+        .any(|e| e.compatibility.as_ref().is_some_and(|c| c.breaking));
+    let symbols = manifest.entries.len().min(9) as u32;
+
+    weight * 10 + if breaking { 5 } else { 0 } + symbols / 2
+}
+
+/// One-line description of a commit's intent: classes and first rationale
+pub fn summary(manifest: &Manifest) -> String {
+    let mut classes: Vec<&str> = Vec::new();
+    for class in manifest
+        .entries
+        .iter()
+        .flat_map(|e| e.behavior_class.iter())
+    {
+        if !classes.contains(&class.as_str()) {
+            classes.push(class);
+        }
+    }
+
+    let rationale = manifest
+        .global_intent
+        .as_ref()
+        .map(|g| g.rationale.as_str())
+        .or_else(|| manifest.entries.first().map(|e| e.rationale.as_str()))
+        .unwrap_or_default();
+    format!("[{}] {}", classes.join(","), rationale)
+}
+
+/// Rank commits by risk, riskiest first; ties keep the given order
+pub fn rank(commits: Vec<(String, Option<Manifest>)>) -> Vec<Suspect> {
+    let mut suspects: Vec<Suspect> = commits
+        .into_iter()
+        .map(|(commit, manifest)| match manifest {
+            Some(m) => Suspect {
+                commit,
+                score: risk_score(&m),
+                summary: summary(&m),
+            },
+            None => Suspect {
+                commit,
+                score: UNKNOWN_SCORE,
+                summary: "(no manifest)".to_string(),
+            },
+        })
+        .collect();
+    suspects.sort_by_key(|s| Reverse(s.score));
+    suspects
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn manifest(classes: &[&str], breaking: bool) -> Manifest {
+        test_support::manifest(
+            "abc",
+            json!({
+                "rationale": "Cache lookups",
+                "behaviorClass": classes,
+                "compatibility": { "breaking": breaking }
+            }),
+        )
+    }
+
+    #[test]
+    fn test_risk_score_orders_classes() {
+        let perf = risk_score(&manifest(&["perf"], false));
+        let docs = risk_score(&manifest(&["docs"], false));
+        assert!(perf > docs);
+        assert!(risk_score(&manifest(&["perf"], true)) > perf);
+        assert!(risk_score(&manifest(&["docs", "feature"], false)) > docs);
+    }
+
+    #[test]
+    fn test_rank() {
+        let ranked = rank(vec![
+            ("docs".to_string(), Some(manifest(&["docs"], false))),
+            ("none".to_string(), None),
+            ("feature".to_string(), Some(manifest(&["feature"], false))),
+        ]);
+        let order: Vec<&str> = ranked.iter().map(|s| s.commit.as_str()).collect();
+        assert_eq!(order, ["feature", "none", "docs"]);
+        assert_eq!(ranked[0].summary, "[feature] Cache lookups");
+    }
+}
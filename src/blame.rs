@@ -0,0 +1,312 @@
+//! Line-level blame for intent lookups
+//!
+//! Resolves a line of the working tree to the commit that last changed it,
+//! together with the line's path and number in that commit, so the commit's
+//! manifest entry for the enclosing symbol can be found.
+//!
+//! [`locate`] does the whole lookup for one position and is what `gip why`,
+//! `gip context --at` and the language server share.
+
+use crate::analyzers;
+use crate::git;
+use crate::manifest::{self, Entry, GlobalIntent, Manifest};
+use crate::symbols;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Commit SHA git blame reports for uncommitted lines
+const UNCOMMITTED: &str = "0000000000000000000000000000000000000000";
+
+/// Where a line came from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlamedLine {
+    pub commit: String,
+    /// Path of the file in `commit` (differs from today's path after a rename)
+    pub path: String,
+    /// 1-based line number in `commit`
+    pub line: usize,
+    pub summary: String,
+}
+
+impl BlamedLine {
+    pub fn is_committed(&self) -> bool {
+        self.commit != UNCOMMITTED
+    }
+}
+
+/// The recorded intent behind a line of the working tree
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineContext {
+    pub file: String,
+    pub line: usize,
+    /// Where the line came from; `None` while it is uncommitted
+    pub origin: Option<BlamedLine>,
+    /// Symbol enclosing the line in the origin commit
+    pub symbol: Option<String>,
+    /// Whether the origin commit has a manifest
+    pub has_manifest: bool,
+    /// Entries explaining the line, see [`entries_for_line`]
+    pub entries: Vec<Entry>,
+    pub global_intent: Option<GlobalIntent>,
+}
+
+/// Look up the intent behind `file:line`
+///
+/// Only a failing blame is an error; an uncommitted line or a commit without
+/// a manifest yields a context with nothing in it.
+pub fn locate(
+    file: &str,
+    line: usize,
+    cwd: Option<&Path>,
+    gip_dir: Option<&Path>,
+) -> Result<LineContext> {
+    let blamed = blame_line(file, line, cwd)?;
+    let mut context = LineContext {
+        file: file.to_string(),
+        line,
+        origin: None,
+        symbol: None,
+        has_manifest: false,
+        entries: Vec::new(),
+        global_intent: None,
+    };
+    if !blamed.is_committed() {
+        return Ok(context);
+    }
+
+    context.symbol = source_at(&blamed.commit, &blamed.path, cwd)
+        .and_then(|source| enclosing_symbol(&source, &blamed.path, blamed.line, gip_dir));
+    if let Ok(manifest) = manifest::load(&blamed.commit, cwd) {
+        context.has_manifest = true;
+        context.entries = entries_for_line(&manifest, &blamed.path, context.symbol.as_deref())
+            .into_iter()
+            .cloned()
+            .collect();
+        context.global_intent = manifest.global_intent;
+    }
+    context.origin = Some(blamed);
+    Ok(context)
+}
+
+/// Parse a `<file>:<line>` target
+pub fn parse_target(target: &str) -> Result<(String, usize)> {
+    let (file, line) = target
+        .rsplit_once(':')
+        .with_context(|| format!("Expected <file>:<line>, got '{}'", target))?;
+    let line: usize = line
+        .parse()
+        .ok()
+        .filter(|l| *l > 0)
+        .with_context(|| format!("Invalid line number '{}'", line))?;
+    Ok((file.to_string(), line))
+}
+
+/// Blame every line of a file
+pub fn blame_file(path: &str, cwd: Option<&Path>) -> Result<Vec<BlamedLine>> {
+    let output = git::run_git_cmd(&["blame", "--porcelain", "--", path], cwd)?;
+    Ok(parse_porcelain(&output))
+}
+
+/// Blame a single line
+pub fn blame_line(path: &str, line: usize, cwd: Option<&Path>) -> Result<BlamedLine> {
+    let range = format!("{},{}", line, line);
+    let output = git::run_git_cmd(&["blame", "--porcelain", "-L", &range, "--", path], cwd)?;
+    parse_porcelain(&output)
+        .into_iter()
+        .next()
+        .with_context(|| format!("No blame information for {}:{}", path, line))
+}
+
+/// Parse `git blame --porcelain` output
+///
+/// Commit details (summary, filename) are printed only on a commit's first
+/// occurrence, so they are remembered per commit.
+pub fn parse_porcelain(output: &str) -> Vec<BlamedLine> {
+    let mut lines = Vec::new();
+    let mut details: HashMap<String, (String, String)> = HashMap::new();
+    let mut current: Option<(String, usize)> = None;
+    let mut summary = String::new();
+    let mut filename = String::new();
+
+    for raw in output.lines() {
+        if raw.starts_with('\t') {
+            // The line's content ends each record
+            if let Some((commit, line)) = current.take() {
+                let (summary, path) = details
+                    .entry(commit.clone())
+                    .or_insert_with(|| (summary.clone(), filename.clone()))
+                    .clone();
+                lines.push(BlamedLine {
+                    commit,
+                    path,
+                    line,
+                    summary,
+                });
+            }
+        } else if let Some(rest) = raw.strip_prefix("summary ") {
+            summary = rest.to_string();
+        } else if let Some(rest) = raw.strip_prefix("filename ") {
+            filename = rest.to_string();
+        } else if current.is_none() {
+            let mut parts = raw.split_whitespace();
+            if let (Some(sha), Some(orig)) = (parts.next(), parts.next()) {
+                if sha.len() == 40 && sha.chars().all(|c| c.is_ascii_hexdigit()) {
+                    if let Ok(orig) = orig.parse() {
+                        current = Some((sha.to_string(), orig));
+                        summary.clear();
+                        filename.clear();
+                    }
+                }
+            }
+        }
+    }
+
+    lines
+}
+
+/// Content of `path` as of `commit`
+pub fn source_at(commit: &str, path: &str, cwd: Option<&Path>) -> Option<String> {
+    git::run_git_cmd(&["show", &format!("{}:{}", commit, path)], cwd).ok()
+}
+
+/// Name of the symbol enclosing a line of `path`, given the file's content
+pub fn enclosing_symbol(
+    source: &str,
+    path: &str,
+    line: usize,
+    gip_dir: Option<&Path>,
+) -> Option<String> {
+    let analyzer = analyzers::analyzer_for(Path::new(path), gip_dir)?;
+    analyzer
+        .enclosing_symbol_at_line(source, line)
+        .map(|s| s.name)
+}
+
+/// Pick the manifest entries that explain a line
+///
+/// Entries for the enclosing symbol win; otherwise every entry for the file
+/// is returned.
+pub fn entries_for_line<'a>(
+    manifest: &'a Manifest,
+    path: &str,
+    symbol: Option<&str>,
+) -> Vec<&'a Entry> {
+    let in_file: Vec<&Entry> = manifest
+        .entries
+        .iter()
+        .filter(|e| e.anchor.file == path)
+        .collect();
+
+    if let Some(symbol) = symbol {
+        let exact: Vec<&Entry> = in_file
+            .iter()
+            .copied()
+            .filter(|e| symbols::base_name(&e.anchor.symbol) == symbol)
+            .collect();
+        if !exact.is_empty() {
+            return exact;
+        }
+    }
+
+    in_file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::init_repo;
+
+    const PORCELAIN: &str = "\
+1111111111111111111111111111111111111111 10 42 2
+author Dev
+summary fix: retry on timeout
+filename src/old_name.rs
+\tretry();
+1111111111111111111111111111111111111111 11 43
+\tOk(())
+0000000000000000000000000000000000000000 44 44 1
+author Not Committed Yet
+summary Version of src/pay.rs from src/pay.rs
+filename src/pay.rs
+\tdbg!(x);
+";
+
+    #[test]
+    fn test_parse_porcelain() {
+        let lines = parse_porcelain(PORCELAIN);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            BlamedLine {
+                commit: "1".repeat(40),
+                path: "src/old_name.rs".to_string(),
+                line: 11,
+                summary: "fix: retry on timeout".to_string(),
+            }
+        );
+        assert!(lines[0].is_committed());
+        assert!(!lines[2].is_committed());
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(
+            parse_target("src/a:b.rs:12").unwrap(),
+            ("src/a:b.rs".to_string(), 12)
+        );
+        assert!(parse_target("src/lib.rs").is_err());
+        assert!(parse_target("src/lib.rs:0").is_err());
+    }
+
+    #[test]
+    fn test_entries_for_line() {
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "abc",
+            "entries": [
+                { "anchor": { "file": "src/pay.rs", "symbol": "Gateway::charge", "hunkId": "H#1" },
+                  "changeType": "modify", "rationale": "a", "behaviorClass": [], "contract": {} },
+                { "anchor": { "file": "src/pay.rs", "symbol": "refund", "hunkId": "H#2" },
+                  "changeType": "modify", "rationale": "b", "behaviorClass": [], "contract": {} },
+                { "anchor": { "file": "src/other.rs", "symbol": "charge", "hunkId": "H#3" },
+                  "changeType": "modify", "rationale": "c", "behaviorClass": [], "contract": {} }
+            ]
+        }))
+        .unwrap();
+
+        let hits = entries_for_line(&manifest, "src/pay.rs", Some("charge"));
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].rationale, "a");
+
+        assert_eq!(
+            entries_for_line(&manifest, "src/pay.rs", Some("unknown")).len(),
+            2
+        );
+        assert_eq!(entries_for_line(&manifest, "src/pay.rs", None).len(), 2);
+    }
+
+    #[test]
+    fn test_locate() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Some(temp_dir.path());
+        init_repo(temp_dir.path());
+        let file = temp_dir.path().join("pay.rs");
+        std::fs::write(&file, "fn charge() {\n    call();\n}\n").unwrap();
+        git::run_git_cmd(&["add", "pay.rs"], dir).unwrap();
+        git::run_git_cmd(&["commit", "-q", "-m", "feat: charge"], dir).unwrap();
+        std::fs::write(&file, "fn charge() {\n    call();\n}\n// todo\n").unwrap();
+
+        let context = locate("pay.rs", 2, dir, None).unwrap();
+        let origin = context.origin.unwrap();
+        assert_eq!((origin.line, origin.summary.as_str()), (2, "feat: charge"));
+        assert_eq!(context.symbol.as_deref(), Some("charge"));
+        assert!(!context.has_manifest);
+
+        let context = locate("pay.rs", 4, dir, None).unwrap();
+        assert!(context.origin.is_none());
+        assert!(locate("pay.rs", 9, dir, None).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+//! Portable manifest bundles
+//!
+//! A bundle is a single TOON file holding manifests keyed by commit SHA. It
+//! moves context between mirrors, or backs it up, without relying on
+//! `refs/notes/gip` being pushed or fetched.
+
+use crate::errors::ErrorCode;
+use crate::git;
+use crate::manifest::{self, schema, Manifest, ManifestFormat};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+use toon_format::{decode, encode_default, DecodeOptions};
+
+/// Value of the `format` field identifying a gip bundle
+pub const BUNDLE_FORMAT: &str = "gip-bundle";
+
+/// Current bundle layout version
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// A set of manifests, each keyed by its `commit` field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bundle {
+    pub format: String,
+    pub version: u32,
+    pub manifests: Vec<Manifest>,
+}
+
+/// Outcome of importing a bundle
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Commits that already had a note
+    pub skipped: usize,
+    /// Commits that don't exist in this repository
+    pub missing: usize,
+}
+
+impl Bundle {
+    pub fn new(manifests: Vec<Manifest>) -> Self {
+        Bundle {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            manifests,
+        }
+    }
+
+    /// Read the manifests of the given commits from git notes
+    ///
+    /// Manifests are exported as stored, without schema migration.
+    pub fn export(commits: &[String], cwd: Option<&Path>) -> Result<Self> {
+        let manifests = commits
+            .iter()
+            .map(|sha| {
+                let mut m = manifest::load_raw(sha, cwd)
+                    .with_context(|| format!("Failed to read manifest of {}", sha))?;
+                m.commit = sha.clone();
+                Ok(m)
+            })
+            .collect::<Result<_>>()?;
+        Ok(Bundle::new(manifests))
+    }
+
+    pub fn to_toon(&self) -> Result<String> {
+        Ok(encode_default(self)?)
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        let opts = DecodeOptions::new().with_strict(false);
+        let bundle: Bundle = decode(content, &opts).context("Failed to parse gip bundle")?;
+
+        if bundle.format != BUNDLE_FORMAT {
+            anyhow::bail!("Not a gip bundle (format '{}')", bundle.format);
+        }
+        if bundle.version > BUNDLE_VERSION {
+            anyhow::bail!(
+                "Bundle version {} is newer than supported version {}",
+                bundle.version,
+                BUNDLE_VERSION
+            );
+        }
+        Ok(bundle)
+    }
+
+    /// Read externally produced records, one manifest JSON object per line
+    ///
+    /// Each record names its commit in `commit` (a SHA or any revision), which
+    /// is resolved to a SHA in the repository at `cwd`. All lines are checked
+    /// against the manifest schema before anything is read, so a bad batch
+    /// leaves no partial import. Records for the same commit are combined in
+    /// file order, however each names it; a revision that doesn't resolve is
+    /// kept as written and reported missing on import.
+    pub fn from_jsonl(content: &str, cwd: Option<&Path>) -> Result<Self> {
+        let mut problems = Vec::new();
+        let mut manifests: Vec<Manifest> = Vec::new();
+
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let errors = match serde_json::from_str::<Value>(line) {
+                Ok(document) => schema::validate(&document)?,
+                Err(e) => vec![e.to_string()],
+            };
+            if !errors.is_empty() {
+                problems.extend(errors.iter().map(|e| format!("line {}: {}", i + 1, e)));
+                continue;
+            }
+
+            let mut record = ManifestFormat::Json.parse_manifest(line)?;
+            let commit_ref = format!("{}^{{commit}}", record.commit);
+            if let Ok(sha) = git::run_git_cmd(&["rev-parse", "--verify", "-q", &commit_ref], cwd) {
+                record.commit = sha;
+            }
+            match manifests.iter_mut().find(|m| m.commit == record.commit) {
+                Some(m) => *m = manifest::combine(&[m.clone(), record], &m.commit),
+                None => manifests.push(record),
+            }
+        }
+
+        if !problems.is_empty() {
+            anyhow::bail!(ErrorCode::ManifestInvalid.error(format!(
+                "Records are not valid manifests:\n  - {}",
+                problems.join("\n  - ")
+            )));
+        }
+        Ok(Bundle::new(manifests))
+    }
+
+    /// Attach the bundled manifests as git notes
+    ///
+    /// Existing notes are kept unless `overwrite` is set.
+    pub fn import(&self, overwrite: bool, cwd: Option<&Path>) -> Result<ImportSummary> {
+        let existing: HashSet<String> = git::list_noted_commits(cwd)?.into_iter().collect();
+        let mut summary = ImportSummary::default();
+
+        for m in &self.manifests {
+            let commit_ref = format!("{}^{{commit}}", m.commit);
+            let sha = match git::run_git_cmd(&["rev-parse", "--verify", "-q", &commit_ref], cwd) {
+                Ok(sha) => sha,
+                Err(_) => {
+                    summary.missing += 1;
+                    continue;
+                }
+            };
+
+            if !overwrite && existing.contains(&sha) {
+                summary.skipped += 1;
+                continue;
+            }
+
+            // Imported records may name their commit by any revision
+            let mut m = m.clone();
+            m.commit = sha.clone();
+            manifest::save(&m, &sha, cwd)?;
+            summary.imported += 1;
+        }
+
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use crate::test_support::init_repo;
+    use serde_json::json;
+
+    fn manifest(commit: &str) -> Manifest {
+        test_support::manifest(commit, json!({}))
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let bundle = Bundle::new(vec![manifest("aaaa"), manifest("bbbb")]);
+        let parsed = Bundle::parse(&bundle.to_toon().unwrap()).unwrap();
+        assert_eq!(parsed, bundle);
+    }
+
+    #[test]
+    fn test_from_jsonl() {
+        let mut later = manifest("aaaa");
+        later.entries[0].anchor.symbol = "stop".to_string();
+        let lines: Vec<String> = [manifest("aaaa"), manifest("bbbb"), later]
+            .iter()
+            .map(|m| serde_json::to_string(m).unwrap())
+            .collect();
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Some(temp_dir.path());
+        git::run_git_cmd(&["init", "-q"], dir).unwrap();
+        let bundle = Bundle::from_jsonl(&format!("{}\n\n", lines.join("\n")), dir).unwrap();
+        assert_eq!(bundle.manifests.len(), 2);
+        assert_eq!(bundle.manifests[0].commit, "aaaa");
+        assert_eq!(bundle.manifests[0].entries.len(), 2);
+
+        let bad = format!("{}\n{{\"commit\": \"cccc\"}}\nnot json", lines[0]);
+        let err = Bundle::from_jsonl(&bad, dir).unwrap_err().to_string();
+        assert!(err.contains("line 2"), "{}", err);
+        assert!(err.contains("line 3"), "{}", err);
+    }
+
+    #[test]
+    fn test_from_jsonl_combines_revisions_of_one_commit() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Some(temp_dir.path());
+        init_repo(temp_dir.path());
+        git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", "init"], dir).unwrap();
+        let sha = git::run_git_cmd(&["rev-parse", "HEAD"], dir).unwrap();
+
+        let mut by_sha = manifest(&sha);
+        by_sha.entries[0].anchor.symbol = "stop".to_string();
+        let lines: Vec<String> = [manifest("HEAD"), by_sha]
+            .iter()
+            .map(|m| serde_json::to_string(m).unwrap())
+            .collect();
+
+        let bundle = Bundle::from_jsonl(&lines.join("\n"), dir).unwrap();
+        assert_eq!(bundle.manifests.len(), 1);
+        assert_eq!(bundle.manifests[0].commit, sha);
+        assert_eq!(bundle.manifests[0].entries.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_rejects_other_formats() {
+        let mut bundle = Bundle::new(vec![]);
+        bundle.format = "something-else".to_string();
+        assert!(Bundle::parse(&bundle.to_toon().unwrap()).is_err());
+
+        let mut bundle = Bundle::new(vec![]);
+        bundle.version = BUNDLE_VERSION + 1;
+        assert!(Bundle::parse(&bundle.to_toon().unwrap()).is_err());
+    }
+}
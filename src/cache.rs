@@ -0,0 +1,240 @@
+//! Manifest note cache
+//!
+//! Enrichment, blame and reports load the same commit's manifest many times.
+//! Within one process `git notes show` runs at most once per commit; the
+//! cache is dropped whenever gip runs a git command that may rewrite notes
+//! (see [`git::write_generation`]).
+//!
+//! With `[cache] persistent = true` in `.gip/config.toml`, notes are also kept
+//! on disk in `.gip/cache/notes/<tip>/`, where `<tip>` is the commit of
+//! `refs/notes/gip`. Any change to the notes moves the tip, so later processes
+//! never see stale notes; directories for old tips are removed.
+
+use crate::atomic;
+use crate::config::Config;
+use crate::git;
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// `[cache]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// Keep notes on disk between runs
+    pub persistent: bool,
+}
+
+/// Directory inside `.gip/` holding cached notes, one subdirectory per notes tip
+pub const NOTES_CACHE_DIR: &str = "cache/notes";
+
+/// Marks a commit known to have no note in the on-disk cache
+const ABSENT_SUFFIX: &str = ".none";
+
+#[derive(Default)]
+struct State {
+    generation: u64,
+    /// Note text by repository and commit; `None` when the commit has no note
+    notes: HashMap<(PathBuf, String), Option<String>>,
+    /// On-disk cache directory for the current tip, once looked up
+    disk: Option<Option<PathBuf>>,
+}
+
+fn state() -> &'static Mutex<State> {
+    static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+    STATE.get_or_init(Mutex::default)
+}
+
+/// Whether `rev` is a full commit id, which always names the same commit
+pub fn is_full_sha(rev: &str) -> bool {
+    matches!(rev.len(), 40 | 64) && rev.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// The gip note of a commit, from the cache when possible
+///
+/// Fails like [`git::get_note`] when the commit has no note.
+pub fn note(commit_sha: &str, repo_path: Option<&Path>) -> Result<String> {
+    // Refs like HEAD move, so only full ids are cached
+    if !is_full_sha(commit_sha) {
+        return git::get_note(commit_sha, repo_path);
+    }
+
+    let key = (
+        repo_path.map(Path::to_path_buf).unwrap_or_default(),
+        commit_sha.to_string(),
+    );
+    let mut state = state().lock().unwrap_or_else(|e| e.into_inner());
+    if state.generation != git::write_generation() {
+        *state = State {
+            generation: git::write_generation(),
+            ..State::default()
+        };
+    }
+
+    let cached = match state.notes.get(&key) {
+        Some(hit) => hit.clone(),
+        None => {
+            // The persistent cache only serves the current repository
+            let disk = match repo_path {
+                None => state.disk.get_or_insert_with(disk_dir).clone(),
+                Some(_) => None,
+            };
+            let loaded = match disk.as_deref().and_then(|dir| read_disk(dir, commit_sha)) {
+                Some(hit) => hit,
+                None => {
+                    let fetched = fetch(commit_sha, repo_path)?;
+                    if let Some(ref dir) = disk {
+                        write_disk(dir, commit_sha, fetched.as_deref());
+                    }
+                    fetched
+                }
+            };
+            state.notes.insert(key, loaded.clone());
+            loaded
+        }
+    };
+
+    cached.ok_or_else(|| anyhow::anyhow!("no note found for object {}", commit_sha))
+}
+
+/// Load the notes of many commits at once, so later [`note`] calls for them
+/// are answered from memory
+///
+/// One `git notes list` and one `git cat-file --batch` replace a
+/// `git notes show` per commit. Commits that aren't full ids are skipped.
+pub fn prefetch(commits: &[String], repo_path: Option<&Path>) -> Result<()> {
+    let repo = repo_path.map(Path::to_path_buf).unwrap_or_default();
+    let wanted: Vec<&String> = {
+        let mut state = state().lock().unwrap_or_else(|e| e.into_inner());
+        if state.generation != git::write_generation() {
+            *state = State {
+                generation: git::write_generation(),
+                ..State::default()
+            };
+        }
+        commits
+            .iter()
+            .filter(|c| is_full_sha(c) && !state.notes.contains_key(&(repo.clone(), c.to_string())))
+            .collect()
+    };
+    if wanted.is_empty() {
+        return Ok(());
+    }
+
+    let blobs: HashMap<String, String> = git::list_note_blobs(repo_path)?.into_iter().collect();
+    let (noted, blob_ids): (Vec<&String>, Vec<String>) = wanted
+        .iter()
+        .filter_map(|c| blobs.get(c.as_str()).map(|blob| (*c, blob.clone())))
+        .unzip();
+    let contents = git::cat_blobs(&blob_ids, repo_path)?;
+
+    let mut state = state().lock().unwrap_or_else(|e| e.into_inner());
+    for commit in &wanted {
+        state.notes.insert((repo.clone(), commit.to_string()), None);
+    }
+    for (commit, content) in noted.into_iter().zip(contents) {
+        state
+            .notes
+            .insert((repo.clone(), commit.clone()), Some(content));
+    }
+    Ok(())
+}
+
+/// Read a note from git; `Ok(None)` when the commit has none
+fn fetch(commit_sha: &str, repo_path: Option<&Path>) -> Result<Option<String>> {
+    match git::get_note(commit_sha, repo_path) {
+        Ok(note) => Ok(Some(note)),
+        Err(e) if e.to_string().contains("no note found") => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The on-disk cache directory for the current notes tip, if enabled
+fn disk_dir() -> Option<PathBuf> {
+    let gip_dir = git::get_gip_dir().ok()?;
+    if !Config::load(&gip_dir).ok()?.cache.persistent {
+        return None;
+    }
+    let tip = git::run_git_cmd(
+        &["rev-parse", "-q", "--verify", &git::notes_ref_full()],
+        None,
+    )
+    .ok()?;
+
+    let root = gip_dir.join(NOTES_CACHE_DIR);
+    let dir = root.join(&tip);
+    if !dir.exists() {
+        // Notes for older tips can never be served again
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&dir).ok()?;
+    }
+    Some(dir)
+}
+
+fn read_disk(dir: &Path, commit_sha: &str) -> Option<Option<String>> {
+    if dir
+        .join(format!("{}{}", commit_sha, ABSENT_SUFFIX))
+        .exists()
+    {
+        return Some(None);
+    }
+    fs::read_to_string(dir.join(commit_sha)).ok().map(Some)
+}
+
+/// Best effort: a failed write only costs a later `git notes show`
+fn write_disk(dir: &Path, commit_sha: &str, note: Option<&str>) {
+    let _ = match note {
+        Some(text) => atomic::write(&dir.join(commit_sha), text),
+        None => atomic::write(&dir.join(format!("{}{}", commit_sha, ABSENT_SUFFIX)), ""),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::init_repo;
+
+    #[test]
+    fn test_full_sha() {
+        assert!(is_full_sha("0123456789abcdef0123456789abcdef01234567"));
+        assert!(!is_full_sha("0123456"));
+        assert!(!is_full_sha("HEAD"));
+    }
+
+    #[test]
+    fn test_note_cache_follows_rewrites() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Some(temp_dir.path());
+        init_repo(temp_dir.path());
+        git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", "init"], dir).unwrap();
+        let sha = git::run_git_cmd(&["rev-parse", "HEAD"], dir).unwrap();
+
+        assert!(note(&sha, dir).is_err());
+        git::add_note(&sha, "first", dir).unwrap();
+        assert_eq!(note(&sha, dir).unwrap(), "first");
+        git::add_note(&sha, "second", dir).unwrap();
+        assert_eq!(note(&sha, dir).unwrap(), "second");
+    }
+
+    #[test]
+    fn test_prefetch() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Some(temp_dir.path());
+        init_repo(temp_dir.path());
+        let mut commits = Vec::new();
+        for message in ["one", "two", "three"] {
+            git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", message], dir).unwrap();
+            commits.push(git::run_git_cmd(&["rev-parse", "HEAD"], dir).unwrap());
+        }
+        git::add_note(&commits[0], "first\nnote", dir).unwrap();
+        git::add_note(&commits[2], "third", dir).unwrap();
+
+        prefetch(&commits, dir).unwrap();
+        assert_eq!(note(&commits[0], dir).unwrap(), "first\nnote");
+        assert!(note(&commits[1], dir).is_err());
+        assert_eq!(note(&commits[2], dir).unwrap(), "third");
+    }
+}
@@ -0,0 +1,236 @@
+use crate::atomic;
+use crate::commands::commit;
+use crate::errors::ErrorCode;
+use crate::git;
+use crate::lock::PendingLock;
+use crate::manifest::{Anchor, Manifest, BEHAVIOR_FEATURE};
+use crate::stage::{self, FileDiff};
+use crate::template;
+use crate::{say, status};
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::io::{BufRead, Write};
+
+/// Patch handed to `git apply --cached`, inside `.gip/`
+const PATCH_FILE: &str = "add.patch";
+
+/// `git add`, recording a rationale and class for each hunk staged with `-p`
+///
+/// Without `-p`/`--patch` the arguments go to `git add` unchanged.
+pub fn run(args: &[String]) -> Result<()> {
+    if !args.iter().any(|a| a == "-p" || a == "--patch") {
+        let mut git_args = vec!["add".to_string()];
+        git_args.extend_from_slice(args);
+        return crate::commands::passthrough::run(&git_args);
+    }
+    let paths: Vec<&str> = args
+        .iter()
+        .map(|a| a.as_str())
+        .filter(|a| !matches!(*a, "-p" | "--patch" | "--"))
+        .collect();
+    if let Some(option) = paths.iter().find(|a| a.starts_with('-')) {
+        anyhow::bail!(ErrorCode::Usage.error(format!(
+            "gip add --patch doesn't take {}; pass only paths",
+            option
+        )));
+    }
+
+    let root = git::get_repo_root()?;
+    let gip_dir = root.join(".gip");
+    let files = stage::read_diff(&[], &paths)?;
+    if files.iter().all(|f| f.hunks.is_empty()) {
+        say!("No unstaged changes");
+        return Ok(());
+    }
+
+    let (manifest_path, _) = commit::find_manifest(&gip_dir)?;
+    let mut pending = stage::load_pending(&gip_dir, &manifest_path)?;
+    let mut class = template::TemplateVars::detect()
+        .branch
+        .as_deref()
+        .and_then(template::class_from_branch)
+        .unwrap_or(BEHAVIOR_FEATURE)
+        .to_string();
+
+    // Hunks chosen per file, and their entries
+    let mut chosen: Vec<(usize, Vec<usize>)> = Vec::new();
+    let mut entries = Vec::new();
+    let mut stop = false;
+    for (f, file) in files.iter().enumerate() {
+        if file.hunks.is_empty() {
+            say!(
+                "{} {} has no text hunks; stage it with git add",
+                "•".yellow(),
+                file.path
+            );
+            continue;
+        }
+        let source = fs::read_to_string(root.join(&file.path)).ok();
+        let mut picked = Vec::new();
+        for (h, hunk) in file.hunks.iter().enumerate() {
+            show_hunk(file, h);
+            let answer = loop {
+                let Some(answer) = ask(&format!(
+                    "Stage this hunk [y,n,d,q,?] ({}/{})?",
+                    h + 1,
+                    file.hunks.len()
+                ))?
+                else {
+                    break "q".to_string();
+                };
+                if matches!(answer.as_str(), "y" | "n" | "d" | "q") {
+                    break answer;
+                }
+                say!("y - stage this hunk and describe it");
+                say!("n - leave this hunk unstaged");
+                say!("d - leave this and the file's later hunks unstaged");
+                say!("q - stop; hunks already chosen are staged");
+            };
+            match answer.as_str() {
+                "n" => continue,
+                "d" => break,
+                "q" => {
+                    stop = true;
+                    break;
+                }
+                _ => {}
+            }
+
+            let symbol = stage::hunk_symbol(file, hunk, source.as_deref(), &gip_dir);
+            let previous = stage::existing_entry(&pending, &file.path, &symbol)
+                .map(|e| e.rationale.clone())
+                .filter(|r| !r.contains(template::PLACEHOLDER));
+            let Some(rationale) = ask_rationale(&symbol, previous.as_deref())? else {
+                stop = true;
+                break;
+            };
+            let Some(answer) = ask_class(&class)? else {
+                stop = true;
+                break;
+            };
+            class = answer;
+
+            let anchor = Anchor {
+                file: file.path.clone(),
+                symbol,
+                hunk_id: format!("H#{}", h + 1),
+            };
+            let entry = stage::entry_for(anchor, file.change_type(), &rationale, &class);
+            let entry = template::apply_fragments(&entry, &gip_dir)?;
+            stage::record(&mut pending, entry.clone());
+            entries.push(entry);
+            picked.push(h);
+        }
+        if !picked.is_empty() {
+            chosen.push((f, picked));
+        }
+        if stop {
+            break;
+        }
+    }
+
+    if chosen.is_empty() {
+        say!("Nothing staged");
+        return Ok(());
+    }
+
+    // Hunk positions refer to the index, so each file is applied in one go
+    let patch_path = gip_dir.join(PATCH_FILE);
+    fs::create_dir_all(&gip_dir).context("Failed to create .gip directory")?;
+    let mut staged = 0;
+    for (f, hunks) in &chosen {
+        atomic::write(&patch_path, files[*f].patch(hunks))?;
+        let applied = git::run_git_cmd(
+            &["apply", "--cached", &patch_path.to_string_lossy()],
+            Some(&root),
+        );
+        let _ = fs::remove_file(&patch_path);
+        applied.with_context(|| format!("Failed to stage hunks of {}", files[*f].path))?;
+        staged += hunks.len();
+    }
+
+    // Re-read under the lock in case the manifest changed while we asked
+    let _lock = PendingLock::acquire(&gip_dir)?;
+    let (manifest_path, format) = commit::find_manifest(&gip_dir)?;
+    let mut manifest = stage::load_pending(&gip_dir, &manifest_path)?;
+    for entry in entries {
+        stage::record(&mut manifest, entry);
+    }
+    atomic::write(&manifest_path, format.serialize_manifest(&manifest)?)
+        .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+
+    let label = manifest_path.strip_prefix(&root).unwrap_or(&manifest_path);
+    status!(
+        "{}",
+        format!(
+            "✓ Staged {} hunk(s) and recorded their intent in {}",
+            staged,
+            label.display()
+        )
+        .green()
+    );
+    Ok(())
+}
+
+fn show_hunk(file: &FileDiff, index: usize) {
+    let hunk = &file.hunks[index];
+    if index == 0 {
+        say!("{}", format!("--- {}", file.path).bold());
+    }
+    say!("{}", hunk.header.cyan());
+    for line in &hunk.lines {
+        match line.chars().next() {
+            Some('+') => say!("{}", line.green()),
+            Some('-') => say!("{}", line.red()),
+            _ => say!("{}", line),
+        }
+    }
+}
+
+/// A one-line rationale; empty input keeps `previous` when there is one
+fn ask_rationale(symbol: &str, previous: Option<&str>) -> Result<Option<String>> {
+    loop {
+        let question = match previous {
+            Some(previous) => format!("Why change {}? [{}]", symbol, previous),
+            None => format!("Why change {}?", symbol),
+        };
+        let Some(answer) = ask(&question)? else {
+            return Ok(None);
+        };
+        match (answer.is_empty(), previous) {
+            (false, _) => return Ok(Some(answer)),
+            (true, Some(previous)) => return Ok(Some(previous.to_string())),
+            (true, None) => say!("A rationale is required"),
+        }
+    }
+}
+
+/// A behavior class; empty input keeps the last one
+fn ask_class(last: &str) -> Result<Option<String>> {
+    let classes = Manifest::all_behavior_classes();
+    loop {
+        let Some(answer) = ask(&format!("Class ({}) [{}]", classes.join(", "), last))? else {
+            return Ok(None);
+        };
+        if answer.is_empty() {
+            return Ok(Some(last.to_string()));
+        }
+        if classes.contains(&answer.as_str()) {
+            return Ok(Some(answer));
+        }
+        say!("Unknown class '{}'", answer);
+    }
+}
+
+/// Read one trimmed line after a question; `None` at end of input
+fn ask(question: &str) -> Result<Option<String>> {
+    eprint!("{} ", question.blue().bold());
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer)? == 0 {
+        eprintln!();
+        return Ok(None);
+    }
+    Ok(Some(answer.trim().to_string()))
+}
@@ -0,0 +1,136 @@
+use crate::git;
+use crate::manifest;
+use crate::merge;
+use crate::patch::{self, AmState};
+use crate::status;
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::path::Path;
+
+/// Options that resume or end an `am` session instead of starting one
+const SESSION_OPTIONS: &[&str] = &[
+    "--continue",
+    "--skip",
+    "--resolved",
+    "-r",
+    "--abort",
+    "--quit",
+];
+
+pub fn run(args: &[String]) -> Result<()> {
+    let gip_dir = git::get_gip_dir()?;
+
+    if !args.iter().any(|a| SESSION_OPTIONS.contains(&a.as_str())) {
+        let mut state = AmState {
+            start: git::get_current_commit()?,
+            patches: Vec::new(),
+        };
+        // Values of options like `-C <n>` are skipped as they aren't files
+        for file in args
+            .iter()
+            .filter(|a| !a.starts_with('-') && Path::new(a).is_file())
+        {
+            let content = fs::read_to_string(file)
+                .with_context(|| format!("Failed to read patch {}", file))?;
+            state.patches.extend(patch::extract(&content)?);
+        }
+        if !state.patches.is_empty() {
+            println!(
+                "{}",
+                format!("Found {} embedded manifest(s)", state.patches.len()).cyan()
+            );
+        }
+        state.save(&gip_dir)?;
+    }
+
+    let mut git_args = vec!["am".to_string()];
+    git_args.extend_from_slice(args);
+    let status = git::run_git_status(&git_args)?;
+
+    if args.iter().any(|a| a == "--abort" || a == "--quit") {
+        AmState::clear(&gip_dir)?;
+        return exit_with(status);
+    }
+
+    let Some(mut state) = AmState::load(&gip_dir)? else {
+        return exit_with(status);
+    };
+
+    let attached = attach(&mut state)?;
+    if attached > 0 {
+        status!(
+            "{}",
+            format!("✓ Attached {} manifest(s) to applied commits", attached).green()
+        );
+    }
+
+    let in_progress = git::run_git_cmd(&["am", "--show-current-patch"], None);
+    match in_progress {
+        Ok(current) => {
+            state.save(&gip_dir)?;
+            enrich(&current)?;
+        }
+        Err(_) => AmState::clear(&gip_dir)?,
+    }
+
+    exit_with(status)
+}
+
+/// Note the applied commits whose subjects match a patch manifest
+fn attach(state: &mut AmState) -> Result<usize> {
+    let range = format!("{}..HEAD", state.start);
+    let applied = git::run_git_cmd(&["rev-list", "--reverse", &range], None)?;
+
+    let mut attached = 0;
+    for sha in applied.lines() {
+        let subject = git::run_git_cmd(&["log", "-1", "--format=%s", sha], None)?;
+        let Some(mut m) = state.take(&subject) else {
+            continue;
+        };
+        if git::get_note(sha, None).is_ok() {
+            continue;
+        }
+        m.commit = sha.to_string();
+        manifest::save(&m, sha, None)?;
+        attached += 1;
+    }
+
+    state.start = git::get_current_commit()?;
+    Ok(attached)
+}
+
+/// Enrich conflicts left by a `git am -3` that stopped on a patch
+fn enrich(current_patch: &str) -> Result<()> {
+    let conflicted = git::run_git_cmd(&["diff", "--name-only", "--diff-filter=U"], None)?;
+    if conflicted.is_empty() {
+        return Ok(());
+    }
+
+    let Some(theirs) = patch::extract(current_patch)?.into_iter().next() else {
+        return Ok(());
+    };
+
+    println!(
+        "{}",
+        "Patch conflict detected. Enriching markers...".yellow()
+    );
+    let ours_sha = git::get_current_commit()?;
+    let ours = manifest::load(&ours_sha, None).ok();
+    let theirs_sha = theirs.manifest.commit.clone();
+    let count =
+        merge::enrich_all_conflicts_with(&ours_sha, ours, &theirs_sha, Some(theirs.manifest))?;
+    status!(
+        "{}",
+        format!("✓ Enriched {} conflicted files with context", count).green()
+    );
+    println!("Resolve, then run: gip am --continue");
+    Ok(())
+}
+
+fn exit_with(status: std::process::ExitStatus) -> Result<()> {
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
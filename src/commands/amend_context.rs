@@ -0,0 +1,137 @@
+use crate::atomic;
+use crate::audit::{self, AuditAction};
+use crate::config::Config;
+use crate::errors::ErrorCode;
+use crate::git;
+use crate::manifest::{self, schema, Manifest, ManifestFormat};
+use crate::rules;
+use crate::status;
+use crate::template;
+use anyhow::{Context, Result};
+use colored::*;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory inside `.gip/` holding manifests being amended
+const AMEND_DIR: &str = "amend";
+
+pub fn run(commit: &str) -> Result<()> {
+    let sha = git::run_git_cmd(
+        &[
+            "rev-parse",
+            "--verify",
+            "-q",
+            &format!("{}^{{commit}}", commit),
+        ],
+        None,
+    )
+    .with_context(|| format!("Unknown commit '{}'", commit))?;
+    let short = &sha[..8];
+    let gip_dir = git::get_gip_dir()?;
+
+    // A draft left by a rejected attempt is reopened instead of the note
+    let draft = draft_path(&gip_dir, &sha);
+    if !draft.exists() {
+        let current = match manifest::load(&sha, None) {
+            Ok(m) => m,
+            Err(_) => Manifest::new(sha.clone()),
+        };
+        fs::create_dir_all(draft.parent().expect("draft path has a parent"))?;
+        atomic::write(&draft, manifest::serialize_manifest_toon(&current)?)?;
+    }
+
+    git::run_editor(&draft)?;
+
+    let content = fs::read_to_string(&draft)
+        .with_context(|| format!("Failed to read {}", draft.display()))?;
+    let mut amended = match validate(&content, &gip_dir) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("{}", format!("✗ {:#}", e).red());
+            anyhow::bail!(ErrorCode::ManifestInvalid.error(format!(
+                "Context of {} not amended. Your edits are kept; run `gip amend-context {}` again to fix them.",
+                short, commit
+            )));
+        }
+    };
+    amended.commit = sha.clone();
+
+    if manifest::load(&sha, None).ok().as_ref() == Some(&amended) {
+        fs::remove_file(&draft)?;
+        println!("No changes to the context of {}", short);
+        return Ok(());
+    }
+
+    // Only the note changes; the commit and its SHA stay as they are
+    let shared = remote_branches_containing(&sha);
+    if !shared.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "Warning: {} is already on {}. Others keep the old context until you run `gip push`.",
+                short,
+                shared.join(", ")
+            )
+            .yellow()
+        );
+    }
+
+    manifest::save(&amended, &sha, None)?;
+    fs::remove_file(&draft)?;
+
+    let detail = if shared.is_empty() {
+        "context amended".to_string()
+    } else {
+        format!("context amended; already on {}", shared.join(", "))
+    };
+    audit::record(AuditAction::ContextAmend, Some(&sha), Some(detail));
+
+    status!("{}", format!("✓ Amended the context of {}", short).green());
+    Ok(())
+}
+
+fn draft_path(gip_dir: &Path, sha: &str) -> PathBuf {
+    gip_dir.join(AMEND_DIR).join(format!("{}.toon", sha))
+}
+
+/// Check an edited manifest the way `gip commit` would
+fn validate(content: &str, gip_dir: &Path) -> Result<Manifest> {
+    if content.contains(template::PLACEHOLDER) {
+        anyhow::bail!(
+            "Manifest contains placeholder text '{}'",
+            template::PLACEHOLDER
+        );
+    }
+
+    let document: Value = ManifestFormat::Toon.parse(content)?;
+    let errors = schema::validate(&document)?;
+    if !errors.is_empty() {
+        anyhow::bail!("Manifest is not valid:\n  - {}", errors.join("\n  - "));
+    }
+
+    let manifest = ManifestFormat::Toon.parse_manifest(content)?;
+    let violations = rules::check(&manifest, &Config::load(gip_dir)?.validation)?;
+    let mut problems: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+    problems.extend(template::missing_required(&manifest));
+    if !problems.is_empty() {
+        anyhow::bail!(
+            "Manifest breaks repository rules:\n  - {}",
+            problems.join("\n  - ")
+        );
+    }
+
+    Ok(manifest)
+}
+
+/// Remote-tracking branches that already have the commit
+fn remote_branches_containing(sha: &str) -> Vec<String> {
+    git::run_git_cmd(&["branch", "-r", "--contains", sha], None)
+        .map(|out| {
+            out.lines()
+                .map(|l| l.trim().to_string())
+                .filter(|l| !l.is_empty() && !l.contains(" -> "))
+                .collect()
+        })
+        .unwrap_or_default()
+}
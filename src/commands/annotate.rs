@@ -0,0 +1,76 @@
+use crate::annotate::{self, GUTTER_WIDTH};
+use crate::blame::{self, BlamedLine};
+use crate::git;
+use crate::manifest::{self, Manifest};
+use anyhow::{Context, Result};
+use colored::*;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+pub fn run(path: &str, width: Option<usize>) -> Result<()> {
+    let width = width.unwrap_or(GUTTER_WIDTH);
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    let blamed = blame::blame_file(path, None)?;
+
+    let gip_dir = git::get_gip_dir().ok();
+    let noted: HashSet<String> = git::list_noted_commits(None)?.into_iter().collect();
+    let mut manifests: HashMap<String, Option<Manifest>> = HashMap::new();
+    let mut sources: HashMap<(String, String), Option<String>> = HashMap::new();
+
+    let labels: Vec<String> = blamed
+        .iter()
+        .map(|b| {
+            if !b.is_committed() {
+                return "(uncommitted)".to_string();
+            }
+
+            let short = &b.commit[..8];
+            let manifest = manifests
+                .entry(b.commit.clone())
+                .or_insert_with(|| {
+                    noted
+                        .contains(&b.commit)
+                        .then(|| manifest::load(&b.commit, None).ok())
+                        .flatten()
+                })
+                .as_ref();
+            let Some(manifest) = manifest else {
+                return short.to_string();
+            };
+
+            let source = sources
+                .entry((b.commit.clone(), b.path.clone()))
+                .or_insert_with(|| blame::source_at(&b.commit, &b.path, None));
+            let symbol = source
+                .as_deref()
+                .and_then(|s| blame::enclosing_symbol(s, &b.path, b.line, gip_dir.as_deref()));
+            let entries = blame::entries_for_line(manifest, &b.path, symbol.as_deref());
+            annotate::label(short, &entries, width)
+        })
+        .collect();
+
+    let rows = annotate::dedupe(labels)
+        .into_iter()
+        .zip(content.lines())
+        .zip(&blamed);
+    for (idx, ((label, text), b)) in rows.enumerate() {
+        println!(
+            "{} {} {:>4} {}",
+            gutter(&label, width, b),
+            "│".dimmed(),
+            idx + 1,
+            text
+        );
+    }
+
+    Ok(())
+}
+
+fn gutter(label: &str, width: usize, line: &BlamedLine) -> ColoredString {
+    let padded = format!("{:<width$}", label, width = width);
+    if line.is_committed() {
+        padded.cyan()
+    } else {
+        padded.dimmed()
+    }
+}
@@ -0,0 +1,169 @@
+use crate::analyzers;
+use crate::api_report::{self, SymbolReport, UndocumentedChange};
+use crate::blame;
+use crate::git;
+use crate::manifest::{self, Manifest};
+use crate::output;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Result document of `gip api-report`
+#[derive(Serialize)]
+struct ApiReportResult<'a> {
+    symbols: &'a [SymbolReport],
+    undocumented: &'a [UndocumentedChange],
+}
+
+pub fn run(range: &str, public_only: bool, fail_on_undocumented: bool) -> Result<()> {
+    let commits = git::rev_list(range, &["--reverse".to_string()], None)?;
+    let noted: HashSet<String> = git::list_noted_commits(None)?.into_iter().collect();
+
+    let manifests: HashMap<String, Manifest> = commits
+        .iter()
+        .filter(|sha| noted.contains(*sha))
+        .filter_map(|sha| {
+            let mut m = manifest::load(sha, None).ok()?;
+            m.commit = sha.clone();
+            Some((sha.clone(), m))
+        })
+        .collect();
+    let ordered: Vec<Manifest> = commits
+        .iter()
+        .filter_map(|sha| manifests.get(sha).cloned())
+        .collect();
+
+    let reports: Vec<SymbolReport> = api_report::compile(&ordered)
+        .into_iter()
+        .filter(|r| !public_only || api_report::is_public(&r.after, &r.symbol))
+        .collect();
+
+    let undocumented = if fail_on_undocumented {
+        find_undocumented(&commits, &manifests, public_only)?
+    } else {
+        Vec::new()
+    };
+
+    if output::is_human() {
+        print_markdown(range, &reports, &undocumented);
+    }
+    output::emit(
+        "api-report",
+        &ApiReportResult {
+            symbols: &reports,
+            undocumented: &undocumented,
+        },
+    )?;
+
+    if !undocumented.is_empty() {
+        anyhow::bail!(
+            "{} signature change(s) have no signatureDelta in their manifest",
+            undocumented.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Signature changes in the code of `commits` that no manifest documents
+fn find_undocumented(
+    commits: &[String],
+    manifests: &HashMap<String, Manifest>,
+    public_only: bool,
+) -> Result<Vec<UndocumentedChange>> {
+    let gip_dir = git::get_gip_dir().ok();
+    let mut found = Vec::new();
+
+    for sha in commits {
+        let parent = format!("{}^", sha);
+        // Root and merge commits have no single parent to compare against
+        if git::run_git_cmd(
+            &["rev-parse", "--verify", "-q", &format!("{}^2", sha)],
+            None,
+        )
+        .is_ok()
+            || git::run_git_cmd(&["rev-parse", "--verify", "-q", &parent], None).is_err()
+        {
+            continue;
+        }
+
+        let files = git::run_git_cmd(
+            &["diff-tree", "--no-commit-id", "--name-only", "-r", sha],
+            None,
+        )?;
+        for file in files.lines() {
+            let Some(analyzer) = analyzers::analyzer_for(Path::new(file), gip_dir.as_deref())
+            else {
+                continue;
+            };
+            let (Some(before), Some(after)) = (
+                blame::source_at(&parent, file, None),
+                blame::source_at(sha, file, None),
+            ) else {
+                continue;
+            };
+
+            for (name, old, new) in
+                api_report::changed_signatures(analyzer.as_ref(), &before, &after)
+            {
+                if public_only && !api_report::is_public(&new, &name) {
+                    continue;
+                }
+                let documented = manifests.get(sha).is_some_and(|m| {
+                    m.entries
+                        .iter()
+                        .any(|e| api_report::documents(e, file, &name))
+                });
+                if !documented {
+                    found.push(UndocumentedChange {
+                        commit: sha.clone(),
+                        file: file.to_string(),
+                        symbol: name,
+                        before: old,
+                        after: new,
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+fn print_markdown(range: &str, reports: &[SymbolReport], undocumented: &[UndocumentedChange]) {
+    println!("# API changes in {}", range);
+
+    if reports.is_empty() {
+        println!("\nNo signature changes recorded.");
+    }
+
+    for report in reports {
+        println!("\n## `{}` ({})\n", report.symbol, report.file);
+        println!("- Before: `{}`", report.before);
+        println!("- After: `{}`", report.after);
+        for change in &report.changes {
+            let breaking = if change.breaking { " **breaking**" } else { "" };
+            println!(
+                "  - {}{}: {}",
+                change.commit.get(..8).unwrap_or(&change.commit),
+                breaking,
+                change.rationale.lines().next().unwrap_or_default()
+            );
+        }
+    }
+
+    if !undocumented.is_empty() {
+        println!("\n## Undocumented signature changes\n");
+        for change in undocumented {
+            println!(
+                "- {} `{}` ({}): `{}` → `{}`",
+                change.commit.get(..8).unwrap_or(&change.commit),
+                change.symbol,
+                change.file,
+                change.before,
+                change.after
+            );
+        }
+    }
+}
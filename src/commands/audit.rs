@@ -0,0 +1,56 @@
+use crate::audit::{self, AuditEvent};
+use crate::git;
+use anyhow::Result;
+use colored::*;
+
+/// Subcommands of `gip audit`
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum AuditCommand {
+    /// Show recorded operations, oldest first
+    Show {
+        /// Only show operations at or after this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long)]
+        since: Option<String>,
+    },
+}
+
+pub fn run(command: AuditCommand) -> Result<()> {
+    match command {
+        AuditCommand::Show { since } => show(since),
+    }
+}
+
+fn show(since: Option<String>) -> Result<()> {
+    let since = since.as_deref().map(audit::parse_since).transpose()?;
+
+    let events: Vec<AuditEvent> = audit::read(&git::get_gip_dir()?)?
+        .into_iter()
+        .filter(|e| match since {
+            Some(since) => e.timestamp >= since,
+            None => true,
+        })
+        .collect();
+
+    if events.is_empty() {
+        println!("{}", "No audited operations found".yellow());
+        return Ok(());
+    }
+
+    for event in &events {
+        println!(
+            "{}  {:<22} {:<10} {} {}",
+            event.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            event.action.as_str().cyan(),
+            event
+                .commit
+                .as_deref()
+                .map(|c| &c[..c.len().min(8)])
+                .unwrap_or("-")
+                .yellow(),
+            event.user.as_deref().unwrap_or("-"),
+            event.detail.as_deref().unwrap_or("")
+        );
+    }
+
+    Ok(())
+}
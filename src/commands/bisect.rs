@@ -0,0 +1,110 @@
+use crate::bisect;
+use crate::git;
+use crate::manifest;
+use anyhow::Result;
+use colored::*;
+
+/// Suspects listed by `--suspect`
+const MAX_SUSPECTS: usize = 10;
+
+pub fn run(suspect: bool, args: &[String]) -> Result<()> {
+    if !args.is_empty() || !suspect {
+        let mut git_args = vec!["bisect".to_string()];
+        git_args.extend_from_slice(args);
+        crate::commands::passthrough::run(&git_args)?;
+    }
+
+    if git::run_git_cmd(&["bisect", "log"], None).is_err() {
+        return Ok(());
+    }
+
+    let Ok(bad) = git::run_git_cmd(&["rev-parse", "-q", "--verify", "refs/bisect/bad"], None)
+    else {
+        if suspect {
+            println!("Mark a bad commit first: gip bisect bad <rev>");
+        }
+        return Ok(());
+    };
+    let remaining = remaining(&bad)?;
+
+    if remaining.is_empty() {
+        print_intent("First bad commit", &bad);
+    } else if suspect {
+        suggest(remaining)?;
+    } else if let Ok(sha) = git::get_current_commit() {
+        // git checks out the next candidate after start/good/bad/skip
+        print_intent("Testing", &sha);
+    }
+    Ok(())
+}
+
+/// Commits that may still be the first bad one
+fn remaining(bad: &str) -> Result<Vec<String>> {
+    let good = git::run_git_cmd(
+        &[
+            "for-each-ref",
+            "--format=^%(objectname)",
+            "refs/bisect/good-*",
+        ],
+        None,
+    )?;
+    let skipped = git::run_git_cmd(
+        &[
+            "for-each-ref",
+            "--format=%(objectname)",
+            "refs/bisect/skip-*",
+        ],
+        None,
+    )?;
+    let good: Vec<String> = good.lines().map(|l| l.to_string()).collect();
+
+    Ok(git::rev_list(bad, &good, None)?
+        .into_iter()
+        .filter(|sha| sha != bad && !skipped.lines().any(|s| s == sha))
+        .collect())
+}
+
+fn print_intent(label: &str, sha: &str) {
+    let intent = match manifest::load(sha, None) {
+        Ok(m) => bisect::summary(&m),
+        Err(_) => "(no manifest)".to_string(),
+    };
+    println!(
+        "{} {} {}",
+        format!("{}:", label).cyan(),
+        sha[..sha.len().min(8)].yellow(),
+        intent
+    );
+}
+
+/// Rank the remaining commits and check out the most suspicious one
+fn suggest(remaining: Vec<String>) -> Result<()> {
+    let candidates = remaining
+        .into_iter()
+        .map(|sha| {
+            let m = manifest::load(&sha, None).ok();
+            (sha, m)
+        })
+        .collect();
+    let suspects = bisect::rank(candidates);
+
+    println!(
+        "{}",
+        format!("{} commit(s) left, most suspicious first:", suspects.len()).cyan()
+    );
+    for s in suspects.iter().take(MAX_SUSPECTS) {
+        println!("  {:>3}  {}  {}", s.score, &s.commit[..8], s.summary);
+    }
+
+    let top = &suspects[0].commit;
+    git::run_git_cmd(&["checkout", "-q", top], None)?;
+    println!(
+        "{}",
+        format!(
+            "✓ Checked out {}; test it and mark it with gip bisect good|bad",
+            &top[..8]
+        )
+        .green()
+    );
+    Ok(())
+}
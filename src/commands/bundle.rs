@@ -0,0 +1,191 @@
+use crate::archive;
+use crate::backup;
+use crate::bundle::{Bundle, ImportSummary};
+use crate::git;
+use crate::status;
+use anyhow::{Context, Result};
+use colored::*;
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+pub fn export(output: &Path, range: Option<&str>) -> Result<()> {
+    let commits = noted_commits(range)?;
+
+    status!(
+        "{}",
+        format!("Exporting {} manifests...", commits.len()).cyan()
+    );
+
+    let bundle = Bundle::export(&commits, None)?;
+    fs::write(output, bundle.to_toon()?)
+        .with_context(|| format!("Failed to write bundle {:?}", output))?;
+
+    status!(
+        "{}",
+        format!(
+            "✓ Wrote {} manifests to {}",
+            commits.len(),
+            output.display()
+        )
+        .green()
+    );
+    Ok(())
+}
+
+/// `gip export --archive`: every manifest, readable without git
+pub fn archive(output: &Path, range: Option<&str>) -> Result<()> {
+    let commits = noted_commits(range)?;
+    status!(
+        "{}",
+        format!("Archiving {} manifests...", commits.len()).cyan()
+    );
+
+    let manifests = Bundle::export(&commits, None)?.manifests;
+    let index = archive::write(output, &manifests, None)?;
+
+    status!(
+        "{}",
+        format!(
+            "✓ Wrote {} manifests and index.json to {}",
+            index.commits.len(),
+            output.display()
+        )
+        .green()
+    );
+    Ok(())
+}
+
+/// Commits with a note, limited to `range` if given
+fn noted_commits(range: Option<&str>) -> Result<Vec<String>> {
+    let noted = git::list_noted_commits(None)?;
+    Ok(match range {
+        Some(range) => {
+            let in_range: HashSet<String> = git::rev_list(range, &[], None)?.into_iter().collect();
+            noted.into_iter().filter(|c| in_range.contains(c)).collect()
+        }
+        None => noted,
+    })
+}
+
+pub fn import(input: &Path, force: bool) -> Result<()> {
+    let content =
+        fs::read_to_string(input).with_context(|| format!("Failed to read bundle {:?}", input))?;
+    let bundle = Bundle::parse(&content)?;
+
+    status!(
+        "{}",
+        format!("Importing {} manifests...", bundle.manifests.len()).cyan()
+    );
+
+    backup::snapshot_before("importing")?;
+    report(&bundle.import(force, None)?);
+    Ok(())
+}
+
+fn report(summary: &ImportSummary) {
+    status!(
+        "{}",
+        format!("✓ Imported {} manifests", summary.imported).green()
+    );
+    if summary.skipped > 0 {
+        println!(
+            "Skipped {} commits that already have a note (use --force to overwrite)",
+            summary.skipped
+        );
+    }
+    if summary.missing > 0 {
+        println!(
+            "{}",
+            format!(
+                "Warning: {} commits are not in this repository",
+                summary.missing
+            )
+            .yellow()
+        );
+    }
+}
+
+/// Formats `gip import` reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// One manifest JSON object per line, naming its commit in `commit`
+    Jsonl,
+}
+
+/// `gip import --from jsonl`: context records produced outside gip
+pub fn import_records(format: ImportFormat, input: &Path, force: bool) -> Result<()> {
+    let content =
+        fs::read_to_string(input).with_context(|| format!("Failed to read {:?}", input))?;
+    let bundle = match format {
+        ImportFormat::Jsonl => Bundle::from_jsonl(&content, None)?,
+    };
+    status!(
+        "{}",
+        format!(
+            "Attaching {} validated records as notes...",
+            bundle.manifests.len()
+        )
+        .cyan()
+    );
+
+    backup::snapshot_before("importing")?;
+    report(&bundle.import(force, None)?);
+    Ok(())
+}
+
+/// `git bundle` that carries `refs/notes/gip` along with the code
+pub fn run(args: &[String]) -> Result<()> {
+    let mut git_args = vec!["bundle".to_string()];
+    git_args.extend_from_slice(args);
+
+    let subcommand = args.first().map(|a| a.as_str());
+    let notes_ref = git::notes_ref_full();
+    let has_notes = git::run_git_cmd(&["rev-parse", "--verify", "-q", &notes_ref], None).is_ok();
+    let lists_notes = args.iter().any(|a| a == "--all" || a.contains(&notes_ref));
+    if subcommand == Some("create") && has_notes && !lists_notes {
+        git_args.push(notes_ref);
+    }
+
+    crate::commands::passthrough::run(&git_args)?;
+
+    match subcommand {
+        Some("create") if has_notes => {
+            status!("{}", "✓ Context notes included in the bundle".green())
+        }
+        Some("unbundle") => {
+            let Some(file) = args[1..].iter().find(|a| !a.starts_with('-')) else {
+                return Ok(());
+            };
+            import_notes_from(file)?;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Fetch the notes ref out of a git bundle file, if it carries one
+fn import_notes_from(file: &str) -> Result<()> {
+    let heads = git::run_git_cmd(
+        &["bundle", "list-heads", file, &git::notes_ref_full()],
+        None,
+    )?;
+    if heads.is_empty() {
+        println!("Bundle has no gip notes");
+        return Ok(());
+    }
+
+    match git::run_git_cmd(&["fetch", file, &git::notes_refspec()], None) {
+        Ok(_) => println!("{}", "✓ Context notes imported from the bundle".green()),
+        Err(e) => eprintln!(
+            "{}",
+            format!(
+                "Warning: Failed to import notes (local notes may have diverged): {}",
+                e
+            )
+            .yellow()
+        ),
+    }
+    Ok(())
+}
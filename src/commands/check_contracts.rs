@@ -0,0 +1,66 @@
+use crate::contracts;
+use crate::git;
+use crate::manifest;
+use crate::status;
+use anyhow::Result;
+use colored::*;
+use std::collections::HashSet;
+
+pub fn run(range: &str) -> Result<()> {
+    // Earlier history supplies the baseline contracts; only the range is checked
+    let tip = match range.rsplit_once("..") {
+        Some((_, "")) => "HEAD",
+        Some((_, tip)) => tip,
+        None => range,
+    };
+    let history = git::rev_list(tip, &["--reverse".to_string()], None)?;
+    let in_range: HashSet<String> = git::rev_list(range, &[], None)?.into_iter().collect();
+
+    status!(
+        "{}",
+        format!("Checking contracts in {} commits...", in_range.len()).cyan()
+    );
+
+    let noted: HashSet<String> = git::list_noted_commits(None)?.into_iter().collect();
+    let manifests: Vec<_> = history
+        .iter()
+        .filter(|sha| noted.contains(*sha))
+        .filter_map(|sha| {
+            manifest::load(sha, None).ok().map(|mut m| {
+                m.commit = sha.clone();
+                m
+            })
+        })
+        .collect();
+
+    let erosions: Vec<_> = contracts::detect(&manifests)
+        .into_iter()
+        .filter(|e| in_range.contains(&e.commit))
+        .collect();
+
+    if erosions.is_empty() {
+        status!("{}", "✓ No contract erosion found".green());
+        return Ok(());
+    }
+
+    for e in &erosions {
+        println!(
+            "{} {} {} ({})",
+            "✗".red(),
+            e.commit.get(..8).unwrap_or(&e.commit).cyan(),
+            e.symbol.yellow(),
+            e.file
+        );
+        for post in &e.removed_postconditions {
+            println!("    dropped postcondition: {}", post);
+        }
+        for err in &e.removed_error_model {
+            println!("    dropped error: {}", err);
+        }
+    }
+
+    anyhow::bail!(
+        "{} change(s) removed contract guarantees without marking them breaking",
+        erosions.len()
+    )
+}
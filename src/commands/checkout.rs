@@ -0,0 +1,79 @@
+use crate::audit::{self, AuditAction};
+use crate::git;
+use crate::hooks::{self, HookEvent};
+use crate::incoming;
+use crate::merge;
+use crate::status;
+use anyhow::Result;
+use colored::*;
+
+pub fn run(args: &[String]) -> Result<()> {
+    run_merging("checkout", args)
+}
+
+/// Run `git checkout`/`git switch` and enrich any conflicts `-m`/`--merge` left
+///
+/// Git carries local changes over to the new branch with a three-way merge and
+/// exits successfully even when that merge conflicts, so conflicts are
+/// detected from the index rather than the exit status.
+pub fn run_merging(subcommand: &str, args: &[String]) -> Result<()> {
+    let source_sha = git::get_current_commit().ok();
+
+    let mut git_args = vec![subcommand.to_string()];
+    git_args.extend_from_slice(args);
+    let status = git::run_git_status(&git_args)?;
+
+    let merging = args.iter().any(|a| a == "-m" || a == "--merge");
+    let conflicted =
+        !git::run_git_cmd(&["diff", "--name-only", "--diff-filter=U"], None)?.is_empty();
+    let (Some(theirs_sha), true, true) = (source_sha.clone(), merging, conflicted) else {
+        if !status.success() {
+            std::process::exit(status.code().unwrap_or(1));
+        }
+        if let (Some(old), Ok(new)) = (source_sha, git::get_current_commit()) {
+            incoming::warn(&old, &new);
+        }
+        return Ok(());
+    };
+
+    println!(
+        "{}",
+        "Local changes conflict with the target branch. Enriching markers...".yellow()
+    );
+
+    // HEAD is now the target branch; the local changes were made on top of
+    // the branch we switched away from
+    let ours_sha = git::get_current_commit()?;
+    let count = merge::enrich_all_conflicts(&ours_sha, &theirs_sha)?;
+
+    if count > 0 {
+        status!(
+            "{}",
+            format!("✓ Enriched {} conflicted files with context", count).green()
+        );
+    } else {
+        println!("{}", "No context available for conflicts".yellow());
+    }
+
+    audit::record(
+        AuditAction::Enrichment,
+        Some(&theirs_sha),
+        Some(format!("{}: enriched {} files", subcommand, count)),
+    );
+
+    hooks::run(
+        &git::get_gip_dir()?,
+        HookEvent::PostEnrich,
+        &serde_json::json!({
+            "operation": subcommand,
+            "ours": ours_sha,
+            "theirs": theirs_sha,
+            "enrichedFiles": count,
+        }),
+    )?;
+
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
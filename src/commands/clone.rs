@@ -0,0 +1,37 @@
+use crate::commands::init;
+use crate::git;
+use crate::manifest;
+use crate::status;
+use anyhow::{Context, Result};
+use colored::*;
+use std::path::PathBuf;
+
+pub fn run(repository: &str, directory: Option<String>, args: &[String]) -> Result<()> {
+    let dir = PathBuf::from(directory.unwrap_or_else(|| git::clone_dir_name(repository)));
+
+    let mut git_args = vec!["clone".to_string()];
+    git_args.extend_from_slice(args);
+    git_args.push(repository.to_string());
+    git_args.push(dir.to_string_lossy().into_owned());
+    crate::commands::passthrough::run(&git_args)?;
+
+    // Fetch notes now and on every future `git fetch`
+    status!("{}", "Fetching context notes...".cyan());
+    git::configure_notes_fetch("origin", Some(&dir))?;
+    let fetched = git::run_git_cmd(&["fetch", "origin"], Some(&dir))
+        .and_then(|_| manifest::merge_notes(&git::remote_notes_ref("origin"), Some(&dir)));
+    match fetched {
+        Ok(_) if git::list_noted_commits(Some(&dir))?.is_empty() => {
+            println!("Remote has no gip notes yet")
+        }
+        Ok(_) => println!("{}", "✓ Context notes fetched".green()),
+        Err(e) => eprintln!(
+            "{}",
+            format!("Warning: Failed to fetch notes: {}", e).yellow()
+        ),
+    }
+
+    std::env::set_current_dir(&dir)
+        .with_context(|| format!("Failed to enter cloned repository {:?}", dir))?;
+    init::run(None)
+}
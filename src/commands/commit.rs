@@ -1,79 +1,222 @@
+use crate::alignment::{self, Enforcement};
+use crate::atomic;
+use crate::audit::{self, AuditAction};
+use crate::config::Config;
+use crate::conventional::{self, ConventionalCommit};
+use crate::editmsg;
+use crate::encryption;
+use crate::errors::ErrorCode;
 use crate::git;
-use crate::manifest::{self, Manifest};
+use crate::hooks::{self, HookEvent};
+use crate::issues;
+use crate::limits;
+use crate::lock::PendingLock;
+use crate::manifest::{self, format, Anchor, Manifest, ManifestFormat, SYMBOL_ANY};
+use crate::merge;
+use crate::output;
+use crate::policy;
+use crate::quality;
+use crate::rules;
+use crate::stage::{self, FileDiff};
+use crate::template;
+use crate::trailer;
+use crate::{say, status};
 use anyhow::{Context, Result};
 use colored::*;
+use serde::Serialize;
 use std::fs;
-use toon_format::{decode, DecodeOptions};
-
-const TEMPLATE: &str = r#"; Gip Manifest Template
-; This file describes the semantic intent of your changes.
-; It is used to enrich merge conflicts with context.
-;
-; INSTRUCTIONS FOR LLM/AGENTS:
-; 1. Analyze the code changes in the current commit.
-; 2. Update the fields below to reflect the actual changes.
-; 3. 'rationale' should explain WHY the change was made.
-; 4. 'behaviorClass' options: feature, bugfix, refactor, perf, security, config.
-; 5. 'changeType' options: add, modify, delete, rename.
-; 6. Remove these instruction comments if desired, but keep the structure.
-
-schemaVersion: "2.0"
-commit: HEAD
-entries[1]:
-  - anchor:
-      file: src/main.rs
-      symbol: main
-      hunkId: H#1
-    changeType: modify
-    rationale: Describe your changes here
-    behaviorClass[1]: feature
-    contract:
-      preconditions[1]: none
-      postconditions[1]: program_runs
-      errorModel[1]: panic_on_error
-"#;
-
-pub fn run(message: Option<String>, force: bool, args: &[String]) -> Result<()> {
-    // 1. Check for manifest.toon
+use std::path::{Path, PathBuf};
+
+/// `git commit` options that provide the message some other way
+const MESSAGE_OPTIONS: &[&str] = &[
+    "-F",
+    "--file",
+    "-C",
+    "--reuse-message",
+    "-c",
+    "--reedit-message",
+    "--no-edit",
+    "--fixup",
+    "--squash",
+];
+
+/// `git commit` options that take changes from outside the index, which
+/// `--only` can't combine with
+const WORKTREE_OPTIONS: &[&str] = &[
+    "-a",
+    "--all",
+    "-i",
+    "--include",
+    "-o",
+    "-p",
+    "--patch",
+    "--interactive",
+];
+
+/// Patch handed to `git apply --cached` by `--only`, inside `.gip/`
+const PATCH_FILE: &str = "only.patch";
+
+/// Result document of `gip commit`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CommitResult {
+    commit: Option<String>,
+    manifest_attached: bool,
+    entries: usize,
+    forced: bool,
+    dry_run: bool,
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn run(
+    mut message: Option<String>,
+    force: bool,
+    message_from_manifest: bool,
+    manifest_name: Option<&str>,
+    override_reason: Option<&str>,
+    dry_run: bool,
+    only: &[String],
+    args: &[String],
+) -> Result<()> {
+    if !only.is_empty() {
+        if let Some(option) = args.iter().find(|a| WORKTREE_OPTIONS.contains(&a.as_str())) {
+            anyhow::bail!(ErrorCode::Usage.error(format!(
+                "--only stages the hunks of the selected entries and can't be combined with {}",
+                option
+            )));
+        }
+    }
+
+    // 1. Check for .gip/manifest.{toon,yaml,yml,json}, or .gip/pending/<name>.* when named
     let root = git::get_repo_root()?;
-    let manifest_path = root.join(".gip").join("manifest.toon");
+    let gip_dir = root.join(".gip");
+
+    // Without -m, the message and the manifest are written in one editor buffer
+    if message.is_none() && !message_from_manifest && !force && !dry_run && !supplies_message(args)
+    {
+        let (path, _) = match manifest_name {
+            Some(name) => find_named_manifest(&gip_dir, name)?,
+            None => find_manifest(&gip_dir)?,
+        };
+        message = Some(edit_message_and_manifest(&root, &gip_dir, &path, args)?);
+    }
+
+    // Held while the pending manifest is read or rewritten, not during `git commit`
+    // itself, since git hooks may run gip too
+    let lock = PendingLock::acquire(&gip_dir)?;
+    let (manifest_path, format) = match manifest_name {
+        Some(name) => find_named_manifest(&gip_dir, name)?,
+        None => find_manifest(&gip_dir)?,
+    };
 
-    let manifest_content = if manifest_path.exists() {
-        Some(fs::read_to_string(&manifest_path).context("Failed to read manifest.toon")?)
+    let mut manifest_content = if manifest_path.exists() {
+        Some(
+            fs::read_to_string(&manifest_path)
+                .with_context(|| format!("Failed to read {}", manifest_path.display()))?,
+        )
     } else {
         None
     };
 
+    // With --only, the commit takes just the selected entries and their hunks;
+    // the other entries stay pending
+    let mut remaining: Option<Manifest> = None;
+    let mut index_guard: Option<IndexGuard> = None;
+    if !only.is_empty() {
+        match manifest_content {
+            Some(ref content) if !template::is_unedited(content, &gip_dir) => {
+                let mut pending = format
+                    .parse_manifest(content)
+                    .with_context(|| format!("Failed to parse {}", manifest_path.display()))?;
+                let (selected, others): (Vec<_>, Vec<_>) = std::mem::take(&mut pending.entries)
+                    .into_iter()
+                    .partition(|e| only.iter().any(|s| stage::selects(s, e)));
+                if let Some(selector) = only
+                    .iter()
+                    .find(|s| !selected.iter().any(|e| stage::selects(s, e)))
+                {
+                    anyhow::bail!(ErrorCode::Usage.error(format!(
+                        "No entry of {} matches '{}'",
+                        manifest_path.display(),
+                        selector
+                    )));
+                }
+                if !dry_run {
+                    index_guard = Some(IndexGuard::snapshot(&root)?);
+                }
+                stage_only(&root, &gip_dir, &selected, dry_run)?;
+
+                let mut rest = pending.clone();
+                rest.entries = others;
+                pending.entries = selected;
+                manifest_content = Some(format.serialize_manifest(&pending)?);
+                remaining = Some(rest);
+            }
+            _ if force => {
+                anyhow::bail!(ErrorCode::Usage.error(
+                    "--only selects entries of the pending manifest, which is missing or unedited"
+                ));
+            }
+            // Rejected below like any commit without a manifest
+            _ => {}
+        }
+    }
+
     // Validation Logic
     if !force {
         let mut reject = false;
         let mut reason = String::new();
 
         if manifest_content.is_none() {
-            // Create template
-            let gip_dir = root.join(".gip");
-            if !gip_dir.exists() {
-                fs::create_dir_all(&gip_dir)?;
-            }
-            fs::write(&manifest_path, TEMPLATE)?;
-
             reject = true;
-            reason = format!(
-                "Manifest file was missing. Created new template at {}",
-                manifest_path.display()
-            );
-        } else if let Some(ref content) = manifest_content {
-            // Normalize line endings for comparison
-            let normalized_content = content.replace("\r\n", "\n");
-            let normalized_template = TEMPLATE.replace("\r\n", "\n");
+            if dry_run {
+                reason = format!("Manifest file {} is missing", manifest_path.display());
+            } else {
+                // Create template
+                if let Some(dir) = manifest_path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
 
-            if normalized_content.trim() == normalized_template.trim() {
+                if let Some(seeded) = seeded_template(&gip_dir, message.as_deref(), args) {
+                    atomic::write(&manifest_path, seeded)?;
+                    reason = format!(
+                        "Manifest file was missing. Created a template at {} pre-filled from the commit message or branch; review it",
+                        manifest_path.display()
+                    );
+                } else {
+                    atomic::write(&manifest_path, template::render_for_repo(&gip_dir)?)?;
+                    reason = format!(
+                        "Manifest file was missing. Created new template at {}",
+                        manifest_path.display()
+                    );
+                }
+            }
+        } else if let Some(ref content) = manifest_content {
+            if template::is_unedited(content, &gip_dir) {
+                reject = true;
+                let seeded = if dry_run {
+                    None
+                } else {
+                    seeded_template(&gip_dir, message.as_deref(), args)
+                };
+                if let Some(seeded) = seeded {
+                    atomic::write(&manifest_path, seeded)?;
+                    reason = "Manifest file was unchanged from template. Pre-filled it from the commit message or branch; review it".to_string();
+                } else {
+                    reason = "Manifest file is unchanged from template".to_string();
+                }
+            } else if content.contains(template::PLACEHOLDER) {
                 reject = true;
-                reason = "Manifest file is unchanged from template".to_string();
-            } else if content.contains("Describe your changes here") {
+                reason = format!(
+                    "Manifest contains placeholder text '{}'",
+                    template::PLACEHOLDER
+                );
+            } else if content.contains(template::PROMPT_MARKER) {
                 reject = true;
-                reason =
-                    "Manifest contains placeholder text 'Describe your changes here'".to_string();
+                reason = format!(
+                    "Manifest has unanswered '{}' prompts",
+                    template::PROMPT_MARKER
+                );
             }
         }
 
@@ -86,14 +229,23 @@ pub fn run(message: Option<String>, force: bool, args: &[String]) -> Result<()>
                     .bold()
             );
             eprintln!("Reason: {}", reason);
+            let named = format::list_named(&gip_dir);
+            if manifest_name.is_none() && !named.is_empty() {
+                eprintln!(
+                    "Named manifests are pending in .gip/{}/: {}. Pick one with --manifest <name>.",
+                    format::PENDING_DIR,
+                    named.join(", ")
+                );
+            }
             eprintln!(
                 "\nTo commit, you must fill out the manifest file at: {}",
                 manifest_path.display()
             );
             eprintln!("This file describes the intent of your changes in TOON format.");
+            eprintln!("(YAML or JSON also work: .gip/manifest.yaml, .gip/manifest.yml, .gip/manifest.json)");
             eprintln!("\nHere is the template structure you need to follow:");
             eprintln!("---------------------------------------------------");
-            eprintln!("{}", TEMPLATE);
+            eprintln!("{}", template::render_for_repo(&gip_dir)?);
             eprintln!("---------------------------------------------------");
             eprintln!("\nINSTRUCTIONS FOR AGENT/LLM:");
             eprintln!("1. Read the file at: {}", manifest_path.display());
@@ -102,41 +254,146 @@ pub fn run(message: Option<String>, force: bool, args: &[String]) -> Result<()>
             eprintln!("4. Save the file.");
             eprintln!("5. Retry the commit command.");
             eprintln!("\nIf you really want to commit without a manifest, use the --force flag.");
-            anyhow::bail!("Commit rejected. See output for details.");
+            anyhow::bail!(
+                ErrorCode::ManifestInvalid.error("Commit rejected. See output for details.")
+            );
         }
     }
 
     let manifest: Option<Manifest> = if let Some(content) = manifest_content {
-        let opts = DecodeOptions::new().with_strict(false);
-        Some(decode(&content, &opts).context("Failed to parse manifest.toon")?)
+        Some(
+            format
+                .parse_manifest(&content)
+                .with_context(|| format!("Failed to parse {}", manifest_path.display()))?,
+        )
     } else {
         if !force {
             // Should be caught above, but just in case
-            anyhow::bail!("Manifest missing and force not set");
+            anyhow::bail!(ErrorCode::ManifestInvalid.error("Manifest missing and force not set"));
         }
-        println!(
+        say!(
             "{}",
-            "No manifest.toon found. Committing without context (FORCE).".yellow()
+            "No manifest found. Committing without context (FORCE).".yellow()
         );
         None
     };
+    drop(lock);
 
-    if manifest.is_some() {
-        println!("{}", "✓ Manifest validated".green());
+    // Keep oversized manifests from bloating notes and conflict markers. Saving
+    // applies the limits too, but strict mode has to refuse before committing.
+    let mut manifest = match manifest {
+        Some(m) => Some(limits::apply(&m, None)?),
+        None => None,
+    };
+
+    // Issue ids from the branch name, unless the manifest already lists some
+    let issues_config = Config::load(&gip_dir)?.issues;
+    let mut issues = match git::current_branch(None) {
+        Some(branch) => issues_config.from_branch(&branch)?,
+        None => Vec::new(),
+    };
+    if let Some(ref mut m) = manifest {
+        if m.issues.is_empty() {
+            m.issues = issues.clone();
+        } else {
+            issues = m.issues.clone();
+        }
     }
 
+    if let Some(ref m) = manifest {
+        check_quality(m, &root, &gip_dir, force)?;
+        check_rules(m, &gip_dir, force)?;
+        check_alignment(m, &gip_dir, force, args)?;
+        check_policy(m, &gip_dir, override_reason, dry_run)?;
+
+        let missing = template::missing_required(m);
+        if !missing.is_empty() && !force {
+            eprintln!(
+                "{}",
+                "ERROR: Manifest is missing required fields.".red().bold()
+            );
+            for problem in &missing {
+                eprintln!("  - {}", problem);
+            }
+            anyhow::bail!(
+                ErrorCode::ManifestInvalid.error("Commit rejected. See output for details.")
+            );
+        }
+    }
+
+    if message_from_manifest {
+        match manifest {
+            Some(ref m) => message = Some(conventional::message_from_manifest(m)),
+            None => anyhow::bail!("--message-from-manifest needs a manifest"),
+        }
+    }
+
+    let config = Config::load(&gip_dir)?;
+    if let Some(ref m) = manifest {
+        encryption::check_trailer_mode(&config.notes, config.trailer.mode)?;
+        status!("{}", "✓ Manifest validated".green());
+        // Run before committing so a rejecting hook or a missing notes key
+        // doesn't leave a commit without context
+        if !dry_run {
+            encryption::ensure_writable()?;
+            hooks::run(&gip_dir, HookEvent::PreManifestSave, m)?;
+        }
+    }
+
+    // Embed the manifest in the message too, for hosts that drop notes
+    let trailer_value = match manifest {
+        Some(ref m) => trailer::encode(m, config.trailer.mode)?,
+        None => None,
+    };
+
     // 3. Commit using git
     let mut git_args = vec!["commit".to_string()];
-    if let Some(msg) = message {
-        git_args.push("-m".to_string());
-        git_args.push(msg);
+    match (message, trailer_value) {
+        (Some(msg), Some(value)) => {
+            git_args.push("-m".to_string());
+            let msg = trailer::append_to_message(&msg, &value);
+            git_args.push(issues::add_trailer(&msg, &issues_config.trailer, &issues));
+        }
+        (Some(msg), None) => {
+            git_args.push("-m".to_string());
+            git_args.push(issues::add_trailer(&msg, &issues_config.trailer, &issues));
+        }
+        (None, value) => {
+            if let Some(value) = value {
+                git_args.push("--trailer".to_string());
+                git_args.push(format!("{}: {}", trailer::TRAILER_KEY, value));
+            }
+            if !issues_config.trailer.is_empty() && !issues.is_empty() {
+                git_args.push("--trailer".to_string());
+                git_args.push(format!("{}: {}", issues_config.trailer, issues.join(", ")));
+            }
+        }
     }
     git_args.extend_from_slice(args);
 
-    // Run git commit
-    crate::commands::passthrough::run(&git_args)?;
+    if dry_run {
+        return preview(manifest.as_ref(), &git_args, force);
+    }
+
+    // Run git commit, exiting with its code like a passthrough once the index is restored
+    let status = git::run_git_status(&git_args)?;
+    if !status.success() {
+        drop(index_guard);
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    if let Some(guard) = index_guard {
+        guard.keep();
+    }
+    let _ = fs::remove_file(gip_dir.join(editmsg::BUFFER_FILE));
 
     // 4. Attach manifest as git note if it exists
+    let result = CommitResult {
+        commit: git::get_current_commit().ok(),
+        manifest_attached: manifest.is_some(),
+        entries: manifest.as_ref().map_or(0, |m| m.entries.len()),
+        forced: force,
+        dry_run: false,
+    };
     if let Some(manifest) = manifest {
         let commit_sha = git::get_current_commit()?;
 
@@ -146,9 +403,615 @@ pub fn run(message: Option<String>, force: bool, args: &[String]) -> Result<()>
 
         manifest::save(&final_manifest, &commit_sha, None)?;
 
-        println!("{}", "✓ Changes committed with context".green());
-        println!("{}", "✓ Manifest attached as git note".green());
+        audit::record(AuditAction::CommitWithManifest, Some(&commit_sha), None);
+
+        status!("{}", "✓ Changes committed with context".green());
+        status!("{}", "✓ Manifest attached as git note".green());
+
+        // Entries left out by --only describe changes still to commit
+        if let Some(rest) = remaining.filter(|r| !r.entries.is_empty()) {
+            let _lock = PendingLock::acquire(&gip_dir)?;
+            atomic::write(&manifest_path, format.serialize_manifest(&rest)?)
+                .with_context(|| format!("Failed to write {}", manifest_path.display()))?;
+            status!(
+                "{}",
+                format!(
+                    "✓ {} entr{} left pending in {}",
+                    rest.entries.len(),
+                    if rest.entries.len() == 1 { "y" } else { "ies" },
+                    manifest_path.display()
+                )
+                .green()
+            );
+        } else {
+            // A named manifest describes exactly one partial commit, so only
+            // the default one is replaced by a fresh template
+            let _lock = PendingLock::acquire(&gip_dir)?;
+            archive_manifest(
+                &gip_dir,
+                &manifest_path,
+                &commit_sha,
+                manifest_name.is_none(),
+            )?;
+        }
+    } else {
+        audit::record(AuditAction::ForcedCommit, result.commit.as_deref(), None);
+    }
+
+    // Committing concludes a merge, so the unenriched conflict copies can go
+    if let Err(e) = merge::cleanup_conflict_backups() {
+        eprintln!("{}", format!("Warning: {:#}", e).yellow());
+    }
+
+    output::emit("commit", &result)
+}
+
+/// Move a committed manifest file to `.gip/history/`, so the next commit can't
+/// reuse its entries by accident
+///
+/// With `fresh`, a TOON manifest is replaced by the repository template. YAML
+/// and JSON manifests are just moved; the next commit creates a template.
+fn archive_manifest(
+    gip_dir: &Path,
+    manifest_path: &Path,
+    commit_sha: &str,
+    fresh: bool,
+) -> Result<()> {
+    if !manifest_path.exists() {
+        return Ok(());
+    }
+    let archived = format::history_path(gip_dir, commit_sha, manifest_path);
+    if let Some(dir) = archived.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    fs::rename(manifest_path, &archived).with_context(|| {
+        format!(
+            "Failed to archive {} to {}",
+            manifest_path.display(),
+            archived.display()
+        )
+    })?;
+    let root = gip_dir.parent().unwrap_or(gip_dir);
+    let label = |path: &Path| {
+        path.strip_prefix(root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    status!(
+        "{}",
+        format!("✓ Manifest archived to {}", label(&archived)).green()
+    );
+    if fresh && ManifestFormat::from_path(manifest_path) == ManifestFormat::Toon {
+        atomic::write(manifest_path, template::render_for_repo(gip_dir)?)?;
+        status!(
+            "{}",
+            format!("✓ Fresh template written to {}", label(manifest_path)).green()
+        );
+    }
+    Ok(())
+}
+
+/// Stage the hunks the selected entries describe, and nothing else
+///
+/// Changes already staged must belong to the selection too, so the commit's
+/// code and its manifest entries match. New files an `add` entry names are
+/// staged whole.
+fn stage_only(
+    root: &Path,
+    gip_dir: &Path,
+    selected: &[manifest::Entry],
+    dry_run: bool,
+) -> Result<()> {
+    let owned = |file: &FileDiff, h: usize, source: Option<&str>| {
+        let symbol = match file.hunks.get(h) {
+            Some(hunk) => stage::hunk_symbol(file, hunk, source, gip_dir),
+            None => manifest::SYMBOL_ANY.to_string(),
+        };
+        selected.iter().any(|e| stage::owns_hunk(e, file, &symbol))
+    };
+
+    let mut foreign = Vec::new();
+    let mut staged = 0;
+    for file in stage::read_diff(&["--cached"], &[])? {
+        // Staged line numbers refer to the index version of the file
+        let source = git::run_git_cmd(&["show", &format!(":{}", file.path)], Some(root)).ok();
+        let hunks = file.hunks.len().max(1);
+        let mine = (0..hunks)
+            .filter(|&h| owned(&file, h, source.as_deref()))
+            .count();
+        staged += mine;
+        if mine < hunks {
+            foreign.push(file.path.clone());
+        }
+    }
+    if !foreign.is_empty() {
+        anyhow::bail!(ErrorCode::Usage.error(format!(
+            "Changes staged in {} aren't described by the selected entries; unstage them with git restore --staged, or select their entries too",
+            foreign.join(", ")
+        )));
+    }
+
+    let untracked = git::run_git_cmd(&["ls-files", "--others", "--exclude-standard"], Some(root))?;
+    let new_files: Vec<&str> = untracked
+        .lines()
+        .filter(|path| {
+            selected.iter().any(|e| {
+                e.change_type == manifest::CHANGE_ADD
+                    && !e.anchor.is_glob()
+                    && crate::pathspec::matches(&e.anchor.file, path)
+            })
+        })
+        .collect();
+
+    let mut chosen = Vec::new();
+    for file in stage::read_diff(&[], &[])? {
+        let source = fs::read_to_string(root.join(&file.path)).ok();
+        let picked: Vec<usize> = (0..file.hunks.len())
+            .filter(|&h| owned(&file, h, source.as_deref()))
+            .collect();
+        if !picked.is_empty() {
+            chosen.push((file, picked));
+        }
+    }
+    let hunks: usize = chosen.iter().map(|(_, picked)| picked.len()).sum();
+
+    if staged + hunks + new_files.len() == 0 {
+        anyhow::bail!(ErrorCode::Usage.error("The selected entries have no changes to commit"));
+    }
+    if dry_run {
+        for (file, picked) in &chosen {
+            say!("Would stage {} hunk(s) of {}", picked.len(), file.path);
+        }
+        for path in &new_files {
+            say!("Would stage new file {}", path);
+        }
+        return Ok(());
+    }
+
+    let patch_path = gip_dir.join(PATCH_FILE);
+    for (file, picked) in &chosen {
+        atomic::write(&patch_path, file.patch(picked))?;
+        let applied = git::run_git_cmd(
+            &["apply", "--cached", &patch_path.to_string_lossy()],
+            Some(root),
+        );
+        let _ = fs::remove_file(&patch_path);
+        applied.with_context(|| format!("Failed to stage hunks of {}", file.path))?;
+    }
+    if !new_files.is_empty() {
+        let mut add_args = vec!["add", "--"];
+        add_args.extend(&new_files);
+        git::run_git_cmd(&add_args, Some(root)).context("Failed to stage new files")?;
+    }
+    if hunks + new_files.len() > 0 {
+        status!(
+            "{}",
+            format!(
+                "✓ Staged {} hunk(s) and {} new file(s) of the selected entries",
+                hunks,
+                new_files.len()
+            )
+            .green()
+        );
+    }
+    Ok(())
+}
+
+/// The index as it was before `--only` staged hunks, put back unless the commit is made
+///
+/// Every check after staging can still reject the commit, and the user's
+/// staging shouldn't change because of an attempt that didn't go through.
+struct IndexGuard {
+    root: PathBuf,
+    tree: Option<String>,
+}
+
+impl IndexGuard {
+    fn snapshot(root: &Path) -> Result<Self> {
+        let tree = git::run_git_cmd(&["write-tree"], Some(root))
+            .context("Failed to record the index before staging")?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            tree: Some(tree),
+        })
+    }
+
+    /// Keep the index as it is now
+    fn keep(mut self) {
+        self.tree = None;
+    }
+}
+
+impl Drop for IndexGuard {
+    fn drop(&mut self) {
+        if let Some(tree) = self.tree.take() {
+            if git::run_git_cmd(&["read-tree", &tree], Some(&self.root)).is_err() {
+                say!(
+                    "{}",
+                    format!(
+                        "Warning: Failed to restore the index; it was {} before --only",
+                        tree
+                    )
+                    .yellow()
+                );
+            }
+        }
+    }
+}
+
+/// Whether the git arguments already say where the message comes from
+fn supplies_message(args: &[String]) -> bool {
+    args.iter().any(|arg| {
+        MESSAGE_OPTIONS.iter().any(|opt| {
+            arg == opt
+                || (opt.starts_with("--") && arg.starts_with(&format!("{}=", opt)))
+                || (!opt.starts_with("--") && arg.starts_with(opt) && arg.len() > opt.len())
+        })
+    })
+}
+
+/// Edit the message and the pending manifest in one buffer, returning the message
+///
+/// The manifest half is written back to `manifest_path` before validation. The
+/// buffer stays in `.gip/COMMIT_EDITMSG` until the commit succeeds, so the next
+/// attempt after a rejected manifest starts from the same message.
+fn edit_message_and_manifest(
+    root: &Path,
+    gip_dir: &Path,
+    manifest_path: &Path,
+    args: &[String],
+) -> Result<String> {
+    let buffer_path = gip_dir.join(editmsg::BUFFER_FILE);
+    let mut message = fs::read_to_string(&buffer_path)
+        .map(|b| editmsg::split(&b).0)
+        .unwrap_or_default();
+    if message.is_empty() && args.iter().any(|a| a == "--amend") {
+        message = git::run_git_cmd(&["log", "-1", "--format=%B"], None).unwrap_or_default();
+    }
+
+    let manifest = match fs::read_to_string(manifest_path) {
+        Ok(content) if !template::is_unedited(&content, gip_dir) => content,
+        _ => match seeded_template(gip_dir, None, args) {
+            Some(seeded) => seeded,
+            None => template::render_for_repo(gip_dir)?,
+        },
+    };
+    let label = manifest_path.strip_prefix(root).unwrap_or(manifest_path);
+
+    fs::create_dir_all(gip_dir).context("Failed to create .gip directory")?;
+    atomic::write(
+        &buffer_path,
+        editmsg::compose(&message, &manifest, &label.display().to_string()),
+    )?;
+    git::run_editor(&buffer_path)?;
+
+    let buffer = fs::read_to_string(&buffer_path)
+        .with_context(|| format!("Failed to read {}", buffer_path.display()))?;
+    let (message, manifest) = editmsg::split(&buffer);
+    if message.is_empty() {
+        anyhow::bail!("Aborting commit due to empty commit message");
+    }
+
+    let _lock = PendingLock::acquire(gip_dir)?;
+    if manifest.trim().is_empty() {
+        // Rejected below with a fresh template, as if the file had never existed
+        if manifest_path.exists() {
+            fs::remove_file(manifest_path)
+                .with_context(|| format!("Failed to remove {}", manifest_path.display()))?;
+        }
+    } else {
+        if let Some(dir) = manifest_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        atomic::write(manifest_path, manifest)?;
+    }
+    Ok(message)
+}
+
+/// The repository template pre-filled from what is known about the change
+///
+/// The template's example entry is replaced by one entry per file the commit
+/// changes, anchored at the whole file. A conventional commit message seeds
+/// their behavior class, breaking flag and rationale; otherwise a branch named
+/// like `perf/...` seeds the class and the rationale is left to fill in. The
+/// fragments of the seeded class are then added. Returns `None` when nothing
+/// applies, git can't tell which files the commit changes, or the template
+/// can't be parsed as a manifest.
+fn seeded_template(gip_dir: &Path, message: Option<&str>, args: &[String]) -> Option<String> {
+    let commit = message.and_then(ConventionalCommit::parse);
+    let branch_class = template::TemplateVars::detect()
+        .branch
+        .as_deref()
+        .and_then(template::class_from_branch);
+    if commit.is_none() && branch_class.is_none() {
+        return None;
+    }
+
+    let files = committed_files(args).filter(|files| !files.is_empty())?;
+
+    let rendered = template::render_for_repo(gip_dir).ok()?;
+    let mut manifest = ManifestFormat::Toon.parse_manifest(&rendered).ok()?;
+    let class = manifest
+        .entries
+        .first()
+        .and_then(|e| e.behavior_class.first().cloned())
+        .unwrap_or_else(|| "feature".to_string());
+    // Anchored where the change is, never at the template's example
+    manifest.entries = files
+        .iter()
+        .map(|file| {
+            let anchor = Anchor {
+                file: file.path.clone(),
+                symbol: SYMBOL_ANY.to_string(),
+                hunk_id: "H#1".to_string(),
+            };
+            stage::entry_for(anchor, file.change_type(), template::PLACEHOLDER, &class)
+        })
+        .collect();
+
+    match (commit, branch_class) {
+        (Some(commit), _) => commit.seed(&mut manifest),
+        (None, Some(class)) => {
+            for entry in &mut manifest.entries {
+                entry.behavior_class = vec![class.to_string()];
+            }
+        }
+        (None, None) => {}
+    }
+    for entry in &mut manifest.entries {
+        *entry = template::apply_fragments(entry, gip_dir).ok()?;
+    }
+
+    manifest::serialize_manifest_toon(&manifest).ok()
+}
+
+/// Locate the pending manifest, defaulting to `manifest.toon` when none exists
+///
+/// An untouched `manifest.toon` template (as created by `gip init`) is ignored
+/// when a YAML or JSON manifest is present.
+pub fn find_manifest(gip_dir: &Path) -> Result<(PathBuf, ManifestFormat)> {
+    let mut found = format::find_pending(gip_dir);
+
+    if found.len() > 1 {
+        found.retain(|(path, format)| {
+            *format != ManifestFormat::Toon
+                || !template::is_unedited(&fs::read_to_string(path).unwrap_or_default(), gip_dir)
+        });
+    }
+
+    match found.len() {
+        0 => Ok((gip_dir.join("manifest.toon"), ManifestFormat::Toon)),
+        1 => Ok(found.remove(0)),
+        _ => {
+            let names: Vec<String> = found.iter().map(|(p, _)| p.display().to_string()).collect();
+            anyhow::bail!(
+                "Found several manifest files: {}. Keep only one.",
+                names.join(", ")
+            )
+        }
+    }
+}
+
+/// Locate the named pending manifest, defaulting to `.gip/pending/<name>.toon`
+pub fn find_named_manifest(gip_dir: &Path, name: &str) -> Result<(PathBuf, ManifestFormat)> {
+    format::validate_name(name)?;
+    Ok(format::find_named(gip_dir, name).unwrap_or_else(|| {
+        (
+            gip_dir
+                .join(format::PENDING_DIR)
+                .join(format!("{}.toon", name)),
+            ManifestFormat::Toon,
+        )
+    }))
+}
+
+/// Report the manifest's quality score, rejecting it below `[quality] min_score`
+fn check_quality(manifest: &Manifest, root: &Path, gip_dir: &Path, force: bool) -> Result<()> {
+    let min_score = Config::load(gip_dir)?.quality.min_score;
+    let report = quality::score(manifest, |anchor| {
+        fs::read_to_string(root.join(&anchor.file)).ok()
+    });
+
+    let line = format!("Manifest quality: {}/{}", report.score, quality::MAX_SCORE);
+    if report.score < min_score && !force {
+        eprintln!(
+            "{}",
+            "ERROR: Manifest quality is below the minimum.".red().bold()
+        );
+        eprintln!("{} (minimum {})", line, min_score);
+        for hint in &report.hints {
+            eprintln!("  - {}", hint);
+        }
+        eprintln!("\nImprove the manifest, or lower [quality] min_score in .gip/config.toml.");
+        anyhow::bail!(ErrorCode::ManifestInvalid.error("Commit rejected. See output for details."));
     }
 
+    if report.hints.is_empty() {
+        status!("{}", format!("✓ {}", line).green());
+    } else {
+        say!("{}", line.yellow());
+        for hint in &report.hints {
+            say!("  - {}", hint);
+        }
+    }
     Ok(())
 }
+
+/// Apply the `[[validation.rules]]` from `.gip/config.toml`
+fn check_rules(manifest: &Manifest, gip_dir: &Path, force: bool) -> Result<()> {
+    let violations = rules::check(manifest, &Config::load(gip_dir)?.validation)?;
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    if force {
+        for v in &violations {
+            say!("{}", format!("Warning: {}", v).yellow());
+        }
+        return Ok(());
+    }
+
+    eprintln!(
+        "{}",
+        "ERROR: Manifest breaks repository rules.".red().bold()
+    );
+    for v in &violations {
+        eprintln!("  - {}", v);
+    }
+    eprintln!("\nUpdate the manifest entries, or the rules in .gip/config.toml.");
+    anyhow::bail!(ErrorCode::ManifestInvalid.error("Commit rejected. See output for details."));
+}
+
+/// Check the entries against the files the commit changes
+///
+/// Merge commits are skipped: they bring in changes the manifest doesn't
+/// describe.
+fn check_alignment(
+    manifest: &Manifest,
+    gip_dir: &Path,
+    force: bool,
+    args: &[String],
+) -> Result<()> {
+    if merge::other_head().is_some() {
+        return Ok(());
+    }
+    let config = Config::load(gip_dir)?;
+    let enforcement = config.validation.unlisted_files;
+
+    let Some(files) = committed_files(args) else {
+        return Ok(());
+    };
+    let alignment = alignment::check(manifest, &files, &config.paths);
+
+    for entry in &alignment.untouched {
+        say!(
+            "{}",
+            format!(
+                "Warning: entry {} names a file this commit doesn't change",
+                entry
+            )
+            .yellow()
+        );
+    }
+    if alignment.unlisted.is_empty() || enforcement == Enforcement::Off {
+        return Ok(());
+    }
+    if enforcement == Enforcement::Warn || force {
+        for file in &alignment.unlisted {
+            say!(
+                "{}",
+                format!(
+                    "Warning: {} is changed but no manifest entry covers it",
+                    file
+                )
+                .yellow()
+            );
+        }
+        return Ok(());
+    }
+
+    eprintln!(
+        "{}",
+        "ERROR: Changed files have no manifest entry.".red().bold()
+    );
+    for file in &alignment.unlisted {
+        eprintln!("  - {}", file);
+    }
+    eprintln!(
+        "\nAdd an entry for each (a directory or glob anchor with symbol \"*\" covers several), \
+         or set [validation] unlisted_files = \"warn\" in .gip/config.toml."
+    );
+    anyhow::bail!(ErrorCode::ManifestInvalid.error("Commit rejected. See output for details."));
+}
+
+/// The files a `git commit` with `args` would change, as git itself works them out
+///
+/// That covers `-a` in any cluster of short options (`-av`), `--amend`,
+/// pathspecs and `--include`. `None` when git can't tell, e.g. with nothing to
+/// commit, or when `--patch` leaves the choice of hunks to the user.
+fn committed_files(args: &[String]) -> Option<Vec<alignment::ChangedFile>> {
+    if picks_hunks(args) {
+        return None;
+    }
+    let mut status_args = vec![
+        "commit".to_string(),
+        "--porcelain".to_string(),
+        "-z".to_string(),
+    ];
+    status_args.extend_from_slice(args);
+    let output = git::run_git_output(&status_args).ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(alignment::parse_porcelain(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// Whether `args` let the user pick hunks interactively, e.g. `-p` or `-ap`
+fn picks_hunks(args: &[String]) -> bool {
+    // Short options taking a value end a cluster: in `-mpatch`, `p` is text
+    const VALUE_OPTIONS: &[char] = &['m', 'F', 'C', 'c', 't', 'S', 'u'];
+    args.iter()
+        .take_while(|a| *a != "--")
+        .any(|arg| match arg.strip_prefix('-') {
+            Some(long) if long.starts_with('-') => matches!(long, "-patch" | "-interactive"),
+            Some(short) => short
+                .chars()
+                .take_while(|c| !VALUE_OPTIONS.contains(c))
+                .any(|c| c == 'p'),
+            None => false,
+        })
+}
+
+/// Apply the `[[policy.rules]]` of the current branch, which `--force` doesn't skip
+fn check_policy(
+    manifest: &Manifest,
+    gip_dir: &Path,
+    override_reason: Option<&str>,
+    dry_run: bool,
+) -> Result<()> {
+    let branch = match git::current_branch(None) {
+        Some(b) => b,
+        None => return Ok(()),
+    };
+    let config = Config::load(gip_dir)?.policy;
+    let violations = policy::check(manifest, &config, &branch, None)?;
+    if dry_run && override_reason.is_some() {
+        // A dry run must not leave an override in the audit log
+        policy::preview_override(&violations);
+        return Ok(());
+    }
+    policy::enforce("Commit", &branch, &violations, override_reason, None)
+}
+
+/// Show what `git commit` would record and the manifest that would be attached
+fn preview(manifest: Option<&Manifest>, git_args: &[String], force: bool) -> Result<()> {
+    say!("{}", "Dry run: nothing will be committed".cyan());
+    let mut dry_args = git_args.to_vec();
+    dry_args.insert(1, "--dry-run".to_string());
+    let status = git::run_git_status(&dry_args)?;
+
+    match manifest {
+        Some(m) => {
+            say!("\nManifest that would be attached as a note:");
+            say!("{}", manifest::serialize_manifest_toon(m)?);
+        }
+        None => say!("{}", "No manifest would be attached (--force)".yellow()),
+    }
+    if !status.success() {
+        anyhow::bail!("git commit would fail; see its output above");
+    }
+
+    output::emit(
+        "commit",
+        &CommitResult {
+            commit: None,
+            manifest_attached: manifest.is_some(),
+            entries: manifest.map_or(0, |m| m.entries.len()),
+            forced: force,
+            dry_run: true,
+        },
+    )
+}
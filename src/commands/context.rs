@@ -1,25 +1,102 @@
+use crate::blame;
+use crate::commands::why;
+use crate::config::Config;
+use crate::errors::ErrorCode;
 use crate::git;
+use crate::hooks::{self, HookEvent};
+use crate::issues::IssuesConfig;
 use crate::manifest::{self, Manifest};
+use crate::output;
+use crate::packages::PackageMap;
+use crate::profile::{Consumer, Profile};
+use crate::query::HistoryFilter;
+use crate::say;
 use anyhow::Result;
 use colored::*;
+use std::path::Path;
 
-pub fn run(commit: Option<String>, export: bool) -> Result<()> {
-    let commit_sha = match commit {
-        Some(c) => c,
+/// `gip context --at <file>:<line>`: the intent behind one line
+pub fn run_at(target: &str) -> Result<()> {
+    let (path, line) = blame::parse_target(target)?;
+    let gip_dir = git::get_gip_dir().ok();
+    let context = blame::locate(&path, line, None, gip_dir.as_deref())?;
+    if output::is_human() {
+        why::print(&context);
+    }
+    output::emit("context", &context)
+}
+
+pub fn run(
+    targets: &[String],
+    export: bool,
+    aggregate: bool,
+    limit: Option<usize>,
+    profile: Option<&str>,
+    mut filter: HistoryFilter,
+) -> Result<()> {
+    let gip_dir = git::get_gip_dir()?;
+    let packages = PackageMap::load(&gip_dir)?;
+    let config = Config::load(&gip_dir)?;
+    let profile = match profile {
+        Some(name) => config.projection.named(name)?,
+        None if export => config.projection.profile_for(Consumer::Export)?,
+        None => config.projection.profile_for(Consumer::Context)?,
+    };
+
+    // A file target shows the intent history of that file
+    let mut targets = targets;
+    if let [target] = targets {
+        if filter.path.is_none() && is_path(target) {
+            filter.path = Some(target.clone());
+            targets = &[];
+        }
+    }
+
+    if filter.is_history_query() {
+        let rev = targets
+            .first()
+            .cloned()
+            .unwrap_or_else(|| "HEAD".to_string());
+        filter.resolve_renames(&rev);
+        return run_history(&rev, export, limit, &filter, &profile, &config, &packages);
+    }
+
+    if aggregate || targets.len() > 1 || targets.iter().any(|t| is_range(t)) {
+        return run_range(
+            targets, export, aggregate, limit, &filter, &profile, &config, &packages,
+        );
+    }
+
+    let commit_sha = match targets.first() {
+        Some(_) => expand_targets(targets)?.remove(0),
         None => git::get_current_commit()?,
     };
 
     let manifest = match manifest::load(&commit_sha, None) {
         Ok(m) => m,
         Err(_) => {
-            println!(
+            say!(
                 "{}",
                 format!("No context found for commit {}", commit_sha).yellow()
             );
-            return Ok(());
+            return output::emit("context", &None::<Manifest>);
         }
     };
 
+    let manifest = match filter.apply(manifest, &packages) {
+        Some(m) => m,
+        None => {
+            say!(
+                "{}",
+                format!("No matching context in commit {}", commit_sha).yellow()
+            );
+            return output::emit("context", &None::<Manifest>);
+        }
+    };
+
+    hooks::run(&gip_dir, HookEvent::PostContextLoad, &manifest)?;
+    let manifest = profile.project(&manifest);
+
     if export {
         let output = manifest::serialize_manifest_toon(&manifest)?;
         println!("{}", output);
@@ -27,18 +104,203 @@ pub fn run(commit: Option<String>, export: bool) -> Result<()> {
     }
 
     // Pretty print for terminal
-    print_manifest(&manifest);
+    if output::is_human() {
+        print_manifest(&manifest, &packages, &config.issues);
+    }
+    output::emit("context", &Some(manifest))
+}
+
+/// Whether a target names a file or directory rather than a commit
+///
+/// Paths that no longer exist count when history has commits touching them.
+fn is_path(target: &str) -> bool {
+    let commit = format!("{}^{{commit}}", target);
+    if git::run_git_cmd(&["rev-parse", "--verify", "-q", &commit], None).is_ok() {
+        return false;
+    }
+    Path::new(target).exists()
+        || git::run_git_cmd(&["log", "-1", "--format=%H", "--", target], None)
+            .is_ok_and(|out| !out.is_empty())
+}
 
-    Ok(())
+/// Whether a target names a revision range such as `v1.0..HEAD`
+fn is_range(target: &str) -> bool {
+    target.contains("..")
 }
 
-fn print_manifest(manifest: &Manifest) {
+/// The commits named by ranges and single revisions, in the order given
+///
+/// Ranges list their commits newest first, like `git rev-list`.
+fn expand_targets(targets: &[String]) -> Result<Vec<String>> {
+    let mut commits: Vec<String> = Vec::new();
+    for target in targets {
+        let found = if is_range(target) {
+            git::rev_list(target, &[], None)?
+        } else {
+            let sha = git::run_git_cmd(
+                &[
+                    "rev-parse",
+                    "--verify",
+                    "-q",
+                    &format!("{}^{{commit}}", target),
+                ],
+                None,
+            )
+            .map_err(|_| {
+                ErrorCode::Usage.error(format!("'{}' is not a commit, range or file", target))
+            })?;
+            vec![sha]
+        };
+        for sha in found {
+            if !commits.contains(&sha) {
+                commits.push(sha);
+            }
+        }
+    }
+    Ok(commits)
+}
+
+/// Show the manifests of revision ranges and lists of commits, one after
+/// another or folded into one view
+#[allow(clippy::too_many_arguments)]
+fn run_range(
+    targets: &[String],
+    export: bool,
+    aggregate: bool,
+    limit: Option<usize>,
+    filter: &HistoryFilter,
+    profile: &Profile,
+    config: &Config,
+    packages: &PackageMap,
+) -> Result<()> {
+    let gip_dir = git::get_gip_dir()?;
+    let head = ["HEAD".to_string()];
+    let targets = if targets.is_empty() {
+        &head[..]
+    } else {
+        targets
+    };
+    let commits = expand_targets(targets)?;
+
+    let mut manifests = Vec::new();
+    for (sha, mut manifest) in manifest::load_many(&commits, None)? {
+        manifest.commit = sha;
+        if let Some(manifest) = filter.apply(manifest, packages) {
+            hooks::run(&gip_dir, HookEvent::PostContextLoad, &manifest)?;
+            manifests.push(profile.project(&manifest));
+        }
+    }
+    if let Some(limit) = limit {
+        manifests.truncate(limit);
+    }
+
+    if aggregate {
+        // Later commits win, so fold oldest first
+        let oldest_first: Vec<Manifest> = manifests.iter().rev().cloned().collect();
+        let label = targets.join(" ");
+        let combined = manifest::combine(&oldest_first, &label);
+        if export {
+            println!("{}", manifest::serialize_manifest_toon(&combined)?);
+            return Ok(());
+        }
+        if output::is_human() {
+            say!(
+                "{}",
+                format!(
+                    "{} of {} commit(s) have context",
+                    manifests.len(),
+                    commits.len()
+                )
+                .dimmed()
+            );
+            print_manifest(&combined, packages, &config.issues);
+        }
+        return output::emit("context", &combined);
+    }
+
+    if manifests.is_empty() {
+        say!(
+            "{}",
+            format!("No context found in {}", targets.join(" ")).yellow()
+        );
+    }
+    for manifest in &manifests {
+        if export {
+            println!("{}", manifest::serialize_manifest_toon(manifest)?);
+            println!();
+        } else if output::is_human() {
+            print_manifest(manifest, packages, &config.issues);
+        }
+    }
+    output::emit("context", &manifests)
+}
+
+/// Show every manifest in the history of `rev` that matches the filter
+fn run_history(
+    rev: &str,
+    export: bool,
+    limit: Option<usize>,
+    filter: &HistoryFilter,
+    profile: &Profile,
+    config: &Config,
+    packages: &PackageMap,
+) -> Result<()> {
+    let gip_dir = git::get_gip_dir()?;
+    let mut manifests = filter.collect(rev, packages, None)?;
+    if let Some(limit) = limit {
+        manifests.truncate(limit);
+    }
+
+    if manifests.is_empty() {
+        say!("{}", "No context found matching the given filters".yellow());
+        return output::emit("context", &manifests);
+    }
+
+    if !export && output::is_human() && !filter.symbol_aliases.is_empty() {
+        let symbol = filter.symbol.as_deref().unwrap_or_default();
+        let aliases = filter.symbol_aliases.join(", ");
+        println!(
+            "{}",
+            format!("Following renames of {}: {}", symbol, aliases).dimmed()
+        );
+    }
+
+    let mut shown = Vec::new();
+    for manifest in &manifests {
+        hooks::run(&gip_dir, HookEvent::PostContextLoad, manifest)?;
+        let manifest = profile.project(manifest);
+
+        if export {
+            println!("{}", manifest::serialize_manifest_toon(&manifest)?);
+            println!();
+        } else if output::is_human() {
+            print_manifest(&manifest, packages, &config.issues);
+        }
+        shown.push(manifest);
+    }
+
+    output::emit("context", &shown)
+}
+
+fn print_manifest(manifest: &Manifest, packages: &PackageMap, issues: &IssuesConfig) {
     println!(
         "┌─ Commit {} (schema v{})",
         manifest.commit.cyan(),
         manifest.schema_version
     );
 
+    if !manifest.issues.is_empty() {
+        let shown: Vec<String> = manifest
+            .issues
+            .iter()
+            .map(|id| match issues.link(id) {
+                Some(url) => format!("{} ({})", id.magenta(), url),
+                None => id.magenta().to_string(),
+            })
+            .collect();
+        println!("│  Issues: {}", shown.join(", "));
+    }
+
     if let Some(ref gi) = manifest.global_intent {
         println!("│");
         println!("│  Global Intent:");
@@ -49,6 +311,9 @@ fn print_manifest(manifest: &Manifest) {
     for entry in &manifest.entries {
         println!("│");
         println!("│  File: {}", entry.anchor.file.yellow());
+        if let Some(pkg) = packages.package_of(entry) {
+            println!("│  Package: {}", pkg.magenta());
+        }
         println!("│  Symbol: {}", entry.anchor.symbol.yellow());
         println!("│  Change: {}", entry.change_type.green());
         println!("│  Rationale: {}", entry.rationale);
@@ -0,0 +1,69 @@
+use crate::coverage::{self, Coverage};
+use crate::errors::ErrorCode;
+use crate::git;
+use crate::output;
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Result document of `gip coverage`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CoverageResult {
+    overall: Coverage,
+    recent: Coverage,
+    recent_days: u32,
+}
+
+pub fn run(rev: Option<String>, days: u32, shields: bool) -> Result<()> {
+    if shields && !output::is_human() {
+        anyhow::bail!(ErrorCode::Usage
+            .error("--shields prints the badge JSON itself; run it without --format"));
+    }
+
+    let rev = rev.unwrap_or_else(|| "HEAD".to_string());
+    let noted: HashSet<String> = git::list_noted_commits(None)?.into_iter().collect();
+
+    let all = git::rev_list(&rev, &[], None)?;
+    let overall = Coverage::measure(&all, &noted, None);
+
+    let since = format!("--since={} days ago", days);
+    let recent_commits = git::rev_list(&rev, &[since], None)?;
+    let recent = Coverage::measure(&recent_commits, &noted, None);
+
+    if shields {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&coverage::shields_json(&overall))?
+        );
+        return Ok(());
+    }
+    if output::is_human() {
+        println!("{}", "Gip context coverage".cyan().bold());
+        print_line("All commits:", &overall);
+        print_line(&format!("Last {} days:", days), &recent);
+    }
+
+    output::emit(
+        "coverage",
+        &CoverageResult {
+            overall,
+            recent,
+            recent_days: days,
+        },
+    )
+}
+
+fn print_line(label: &str, coverage: &Coverage) {
+    let pct = format!("{:.1}%", coverage.coverage_pct);
+    let pct = match coverage::badge_color(coverage.coverage_pct) {
+        "brightgreen" | "green" => pct.green(),
+        "yellow" => pct.yellow(),
+        _ => pct.red(),
+    };
+    println!(
+        "  {:<16} {}/{} commits ({})",
+        label, coverage.covered_commits, coverage.total_commits, pct
+    );
+}
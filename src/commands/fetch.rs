@@ -0,0 +1,96 @@
+use crate::git;
+use crate::manifest;
+use crate::output;
+use crate::remote::{self, RemoteArgs};
+use crate::{say, status};
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+/// What happened to the notes of one remote
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotesFetch {
+    Fetched,
+    /// The remote has no gip notes yet
+    None,
+    Failed,
+    Unreachable,
+}
+
+/// Notes fetched from one remote, in the result of `gip fetch` and `gip pull`
+#[derive(Debug, Clone, Serialize)]
+pub struct RemoteNotes {
+    pub remote: String,
+    pub notes: NotesFetch,
+}
+
+/// Result document of `gip fetch`
+#[derive(Serialize)]
+struct FetchResult {
+    remotes: Vec<RemoteNotes>,
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    let mut git_args = vec!["fetch".to_string()];
+    git_args.extend_from_slice(args);
+    crate::commands::passthrough::run(&git_args)?;
+
+    let remotes = fetch_notes(&RemoteArgs::parse_fetch(args))?;
+    output::emit("fetch", &FetchResult { remotes })
+}
+
+/// Fetch gip notes from the remote(s) a fetch or pull talks to
+pub fn fetch_notes(target: &RemoteArgs) -> Result<Vec<RemoteNotes>> {
+    let remotes = if target.all_remotes() {
+        remote::list_remotes(None)?
+    } else {
+        vec![target.remote_or(&remote::default_fetch_remote(None))]
+    };
+
+    let mut fetched = Vec::new();
+    for remote in remotes {
+        status!(
+            "{}",
+            format!("Fetching context notes from {}...", remote).cyan()
+        );
+        let notes = match remote::has_remote_notes(&remote, None) {
+            Ok(false) => {
+                say!("{} has no gip notes yet", remote);
+                NotesFetch::None
+            }
+            Ok(true) => match git::fetch_notes(&remote)
+                .and_then(|()| manifest::merge_notes(&git::remote_notes_ref(&remote), None))
+            {
+                Ok(combined) => {
+                    if combined > 0 {
+                        say!(
+                            "Combined {} notes that changed both here and on {}",
+                            combined,
+                            remote
+                        );
+                    }
+                    say!("{}", "✓ Context notes fetched".green());
+                    NotesFetch::Fetched
+                }
+                Err(e) => {
+                    say!(
+                        "{}",
+                        format!("Warning: Failed to fetch notes from {}: {}", remote, e).yellow()
+                    );
+                    NotesFetch::Failed
+                }
+            },
+            Err(e) => {
+                say!(
+                    "{}",
+                    format!("Warning: Could not reach {}: {}", remote, e).yellow()
+                );
+                NotesFetch::Unreachable
+            }
+        };
+        fetched.push(RemoteNotes { remote, notes });
+    }
+
+    Ok(fetched)
+}
@@ -0,0 +1,48 @@
+use crate::git;
+use crate::manifest;
+use crate::patch;
+use crate::status;
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+
+pub fn run(args: &[String]) -> Result<()> {
+    let mut git_args = vec!["format-patch".to_string()];
+    git_args.extend_from_slice(args);
+    let output = git::run_git_output(&git_args)?;
+    eprint!("{}", String::from_utf8_lossy(&output.stderr));
+    if !output.status.success() {
+        std::process::exit(output.status.code().unwrap_or(1));
+    }
+
+    let stdout = String::from_utf8(output.stdout).context("Invalid UTF-8 in git output")?;
+    let mut embedded = 0;
+    let manifest_for = |sha: &str| {
+        let m = manifest::load_raw(sha, None).ok();
+        if m.is_some() {
+            embedded += 1;
+        }
+        m
+    };
+
+    if args.iter().any(|a| a == "--stdout") {
+        print!("{}", patch::embed(&stdout, manifest_for)?);
+        return Ok(());
+    }
+
+    // Without --stdout git prints the name of each patch file it wrote
+    let mut manifest_for = manifest_for;
+    for file in stdout.lines() {
+        let content =
+            fs::read_to_string(file).with_context(|| format!("Failed to read patch {}", file))?;
+        fs::write(file, patch::embed(&content, &mut manifest_for)?)
+            .with_context(|| format!("Failed to write patch {}", file))?;
+        println!("{}", file);
+    }
+
+    status!(
+        "{}",
+        format!("✓ Embedded {} manifest(s) in the patches", embedded).green()
+    );
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use crate::audit::{self, AuditAction};
+use crate::backup;
+use crate::gc;
+use crate::git;
+use crate::status;
+use anyhow::Result;
+use colored::*;
+
+pub fn run(archive: bool, dry_run: bool) -> Result<()> {
+    status!("{}", "Looking for notes on unreachable commits...".cyan());
+
+    let orphaned = gc::find_orphaned(None)?;
+    if orphaned.is_empty() {
+        status!("{}", "✓ No orphaned notes".green());
+        return Ok(());
+    }
+
+    if !dry_run {
+        backup::snapshot_before("pruning")?;
+    }
+
+    for sha in &orphaned {
+        let short = &sha[..sha.len().min(8)];
+        if dry_run {
+            println!("Would remove note on {}", short);
+        } else if archive {
+            let note = git::get_note(sha, None)?;
+            let path = gc::archive(&git::get_gip_dir()?, sha, &note)?;
+            println!("Archived {} to {}", short, path.display());
+        }
+    }
+
+    if dry_run {
+        println!("\n{} orphaned note(s) would be removed", orphaned.len());
+        return Ok(());
+    }
+
+    gc::prune(&orphaned, None)?;
+    audit::record(
+        AuditAction::NotesPrune,
+        None,
+        Some(format!("{} notes", orphaned.len())),
+    );
+
+    status!(
+        "{}",
+        format!("✓ Removed {} orphaned note(s)", orphaned.len()).green()
+    );
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use crate::git;
+use crate::graph::{self, SymbolNode};
+use crate::index::SymbolIndex;
+use crate::output;
+use crate::packages::PackageMap;
+use crate::query::HistoryFilter;
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+/// Result document of `gip graph`
+#[derive(Serialize)]
+struct GraphResult<'a> {
+    symbol: &'a str,
+    /// Oldest first
+    nodes: &'a [SymbolNode],
+}
+
+pub fn run(symbol: &str, rev: Option<String>, mermaid: bool) -> Result<()> {
+    let rev = rev.unwrap_or_else(|| "HEAD".to_string());
+    let packages = PackageMap::load(&git::get_gip_dir()?)?;
+
+    let manifests = HistoryFilter::default().collect(&rev, &packages, None)?;
+    let aliases = SymbolIndex::load(None)
+        .map(|index| index.aliases(symbol))
+        .unwrap_or_default();
+    let nodes = graph::symbol_history(&manifests, symbol, &aliases);
+
+    if nodes.is_empty() {
+        eprintln!(
+            "{}",
+            format!("No context found for symbol '{}'", symbol).yellow()
+        );
+    } else if output::is_human() {
+        let output = if mermaid {
+            graph::render_mermaid(&nodes)
+        } else {
+            graph::render_dot(symbol, &nodes)
+        };
+        print!("{}", output);
+    }
+
+    output::emit(
+        "graph",
+        &GraphResult {
+            symbol,
+            nodes: &nodes,
+        },
+    )
+}
@@ -0,0 +1,90 @@
+use crate::history::{self, HistoryEvent};
+use crate::index::SymbolIndex;
+use crate::output;
+use crate::say;
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+/// Result document of `gip history`
+#[derive(Serialize)]
+struct HistoryResult<'a> {
+    symbol: &'a str,
+    /// Every name the symbol has had, oldest first
+    names: Vec<String>,
+    events: Vec<HistoryEvent>,
+}
+
+pub fn run(symbol: &str) -> Result<()> {
+    let index = SymbolIndex::load(None)?;
+    let names = index.names(symbol);
+    let events = history::timeline(&index, symbol, None)?;
+
+    if events.is_empty() {
+        say!(
+            "{}",
+            format!("No context found for symbol '{}'", symbol).yellow()
+        );
+    } else {
+        print_timeline(symbol, &names, &events);
+    }
+    output::emit(
+        "history",
+        &HistoryResult {
+            symbol,
+            names,
+            events,
+        },
+    )
+}
+
+fn print_timeline(symbol: &str, names: &[String], events: &[HistoryEvent]) {
+    let others: Vec<&str> = names
+        .iter()
+        .map(String::as_str)
+        .filter(|n| *n != symbol)
+        .collect();
+    if others.is_empty() {
+        say!("History of {}", symbol.yellow());
+    } else {
+        say!(
+            "History of {} (also known as {})",
+            symbol.yellow(),
+            others.join(", ")
+        );
+    }
+
+    for event in events {
+        say!();
+        say!(
+            "{} {} {} {} {}",
+            event.date,
+            event.commit[..event.commit.len().min(8)].cyan(),
+            event.file,
+            event.symbol.yellow(),
+            event.change_type.green()
+        );
+        if !event.summary.is_empty() {
+            say!("    {}", event.summary.dimmed());
+        }
+        if let Some(ref from) = event.renamed_from {
+            say!("    Renamed: {} → {}", from, event.symbol);
+        }
+        if let Some(ref delta) = event.signature_delta {
+            say!("    Signature: {} → {}", delta.before, delta.after);
+        }
+        say!("    Rationale: {}", event.rationale);
+        if !event.behavior_class.is_empty() {
+            say!("    Behavior: {}", event.behavior_class.join(", ").blue());
+        }
+        for (label, items) in [
+            ("Preconditions", &event.contract.preconditions),
+            ("Postconditions", &event.contract.postconditions),
+            ("Errors", &event.contract.error_model),
+        ] {
+            if !items.is_empty() {
+                say!("    {}: {}", label, items.join("; "));
+            }
+        }
+    }
+}
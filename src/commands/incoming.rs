@@ -0,0 +1,36 @@
+use crate::incoming::{self, IncomingChange};
+use crate::output;
+use crate::say;
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// Range covered after a pull or merge
+const DEFAULT_RANGE: &str = "ORIG_HEAD..HEAD";
+
+/// Result document of `gip incoming`
+#[derive(Serialize)]
+struct IncomingResult<'a> {
+    range: &'a str,
+    changes: Vec<IncomingChange>,
+}
+
+pub fn run(range: Option<&str>, all: bool) -> Result<()> {
+    let range = range.unwrap_or(DEFAULT_RANGE);
+    let (old, new) = range.split_once("..").unwrap_or((range, "HEAD"));
+    let new = if new.is_empty() { "HEAD" } else { new };
+
+    let arrived =
+        incoming::arrived(old, new, None).with_context(|| format!("Unknown range '{}'", range))?;
+    let changes = if all {
+        incoming::collect(&arrived, None)
+    } else {
+        let touched = incoming::recently_touched(None);
+        incoming::collect(&arrived, Some(&touched))
+    };
+
+    if changes.is_empty() {
+        say!("No incoming breaking changes or migrations");
+    }
+    incoming::print_summary(&changes);
+    output::emit("incoming", &IncomingResult { range, changes })
+}
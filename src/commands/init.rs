@@ -1,76 +1,305 @@
+use crate::config::{Config, CONFIG_FILE};
+use crate::errors::ErrorCode;
 use crate::git;
+use crate::global;
+use crate::hooks::HookEvent;
+use crate::output;
+use crate::setup::{self, FileUpdate, Layout, SetupRecord, GITIGNORE_FILE};
+use crate::template;
+use crate::{say, status};
 use anyhow::Result;
 use colored::*;
+use serde::Serialize;
 use std::fs;
+use std::path::Path;
 
-pub fn run() -> Result<()> {
-    println!("{}", "Initializing Gip...".cyan());
+/// Pending manifest inside `.gip/`, written from the template
+const TEMPLATE_FILE: &str = "manifest.toon";
+
+/// Result document of `gip init`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InitResult {
+    gip_dir: String,
+    created_template: bool,
+    updated_gitignore: bool,
+    /// gip version of an earlier `gip init`, when re-run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_version: Option<String>,
+    /// `local` or `shared`
+    layout: &'static str,
+    /// `created`, `current`, `upgraded` or `kept`
+    template: &'static str,
+    /// Changes made to an upgraded template
+    #[serde(skip_serializing_if = "Option::is_none")]
+    template_diff: Option<String>,
+    configured_notes_fetch: bool,
+    warnings: Vec<String>,
+}
+
+/// Result document of `gip init --global`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GlobalInitResult {
+    config_dir: String,
+    created_config: bool,
+    created_template: bool,
+}
+
+/// Set up gip in the current repository, or bring an existing setup up to date
+///
+/// Without a `layout`, the one recorded by an earlier run is kept.
+pub fn run(layout: Option<Layout>) -> Result<()> {
+    status!("{}", "Initializing Gip...".cyan());
 
     if !git::is_git_repo() {
-        anyhow::bail!("Not a git repository. Run 'git init' first.");
+        anyhow::bail!(
+            ErrorCode::NotARepository.error("Not a git repository. Run 'git init' first.")
+        );
     }
 
-    git::ensure_gip_dir()?;
-
-    // Create a template manifest if it doesn't exist
     let root = git::get_repo_root()?;
     let gip_dir = root.join(".gip");
-    let manifest_path = gip_dir.join("manifest.toon");
-
-    if !manifest_path.exists() {
-        let template = r#"; Gip Manifest Template
-; This file describes the semantic intent of your changes.
-; It is used to enrich merge conflicts with context.
-;
-; INSTRUCTIONS FOR LLM/AGENTS:
-; 1. Analyze the code changes in the current commit.
-; 2. Update the fields below to reflect the actual changes.
-; 3. 'rationale' should explain WHY the change was made.
-; 4. 'behaviorClass' options: feature, bugfix, refactor, perf, security, config.
-; 5. 'changeType' options: add, modify, delete, rename.
-; 6. Remove these instruction comments if desired, but keep the structure.
-
-schemaVersion: "2.0"
-commit: HEAD
-entries[1]:
-  - anchor:
-      file: src/main.rs
-      symbol: main
-      hunkId: H#1
-    changeType: modify
-    rationale: Describe your changes here
-    behaviorClass[1]: feature
-    contract:
-      preconditions[1]: none
-      postconditions[1]: program_runs
-      errorModel[1]: panic_on_error
-"#;
-        fs::write(&manifest_path, template)?;
-        println!("Created .gip/manifest.toon template");
+    let manifest_path = gip_dir.join(TEMPLATE_FILE);
+    let previous = SetupRecord::load(&gip_dir);
+    let reinit = previous.is_some() || manifest_path.exists();
+    match previous {
+        Some(ref record) => say!(
+            "Already initialized by gip {}; checking for upgrades",
+            record.version
+        ),
+        None if reinit => say!("Found an existing .gip/; checking for upgrades"),
+        None => {}
+    }
+    git::ensure_gip_dir()?;
+
+    // Refresh the template unless it holds the user's work
+    let mut record = previous.clone().unwrap_or_default();
+    let fresh = template::render_for_repo(&gip_dir)?;
+    let existing = fs::read_to_string(&manifest_path).ok();
+    let update = setup::plan_template(existing.as_deref(), &fresh, &record, TEMPLATE_FILE);
+    match update {
+        FileUpdate::Create | FileUpdate::Upgrade(_) => {
+            fs::write(&manifest_path, &fresh)?;
+            record.record(TEMPLATE_FILE, &fresh);
+        }
+        FileUpdate::Current => record.record(TEMPLATE_FILE, &fresh),
+        FileUpdate::Keep => {}
+    }
+    match update {
+        FileUpdate::Create => say!("Created .gip/manifest.toon template"),
+        FileUpdate::Current => say!(".gip/manifest.toon is up to date"),
+        FileUpdate::Upgrade(ref diff) => {
+            say!("Upgraded .gip/manifest.toon to the current template:");
+            print_diff(diff);
+        }
+        FileUpdate::Keep => say!(
+            "{}",
+            "Kept .gip/manifest.toon: it has your changes. Delete it and rerun `gip init` for the current template."
+                .yellow()
+        ),
+    }
+
+    let layout = layout.unwrap_or(record.layout);
+    if layout != record.layout && previous.is_some() {
+        say!(
+            "Switching .gip/ from the {} to the {} layout",
+            record.layout.name(),
+            layout.name()
+        );
+    }
+    let updated_gitignore = apply_layout(&root, &gip_dir, layout, &mut record)?;
+    record.layout = layout;
+
+    // Repositories set up before `gip clone` configured notes fetching
+    let has_origin = git::run_git_cmd(&["remote"], None)
+        .unwrap_or_default()
+        .lines()
+        .any(|r| r == "origin");
+    let configured_notes_fetch = has_origin && git::configure_notes_fetch("origin", None)?;
+    if configured_notes_fetch {
+        say!("Configured `git fetch origin` to fetch gip notes");
     }
 
-    // Add .gip to .gitignore
+    let warnings = check_setup(&gip_dir);
+    for warning in &warnings {
+        eprintln!("{}", format!("Warning: {}", warning).yellow());
+    }
+
+    record.version = env!("CARGO_PKG_VERSION").to_string();
+    record.save(&gip_dir)?;
+
+    status!("{}", "✓ Gip initialized successfully".green());
+    if !reinit {
+        say!("Created: .gip/");
+        say!("Created: .gip/manifest.toon (template)");
+    }
+    if let Some(machine) = global::config_path().filter(|p| p.exists()) {
+        say!(
+            "Using machine defaults from {} (.gip/config.toml overrides them)",
+            machine.display()
+        );
+    }
+
+    let (template, template_diff) = match update {
+        FileUpdate::Create => ("created", None),
+        FileUpdate::Current => ("current", None),
+        FileUpdate::Upgrade(diff) => ("upgraded", Some(diff)),
+        FileUpdate::Keep => ("kept", None),
+    };
+    output::emit(
+        "init",
+        &InitResult {
+            gip_dir: gip_dir.display().to_string(),
+            created_template: template == "created",
+            updated_gitignore,
+            previous_version: previous.map(|r| r.version),
+            layout: layout.name(),
+            template,
+            template_diff,
+            configured_notes_fetch,
+            warnings,
+        },
+    )
+}
+
+/// Make git ignore all of `.gip/`, or only its state; returns whether
+/// the repository's `.gitignore` changed
+fn apply_layout(
+    root: &Path,
+    gip_dir: &Path,
+    layout: Layout,
+    record: &mut SetupRecord,
+) -> Result<bool> {
     let gitignore_path = root.join(".gitignore");
-    let mut gitignore_content = if gitignore_path.exists() {
-        fs::read_to_string(&gitignore_path)?
-    } else {
-        String::new()
+    let gitignore_content = fs::read_to_string(&gitignore_path).unwrap_or_default();
+
+    match layout {
+        Layout::Local => {
+            if gitignore_content.contains(".gip") {
+                return Ok(false);
+            }
+            let mut content = gitignore_content;
+            if !content.is_empty() && !content.ends_with('\n') {
+                content.push('\n');
+            }
+            content.push_str(".gip\n");
+            fs::write(&gitignore_path, content)?;
+            say!("Added .gip to .gitignore");
+            Ok(true)
+        }
+        Layout::Shared => {
+            let path = gip_dir.join(GITIGNORE_FILE);
+            let fresh = setup::shared_gitignore();
+            let existing = fs::read_to_string(&path).ok();
+            match setup::plan_file(existing.as_deref(), &fresh, record, GITIGNORE_FILE) {
+                FileUpdate::Create => {
+                    fs::write(&path, &fresh)?;
+                    say!("Created .gip/.gitignore keeping local state out of git");
+                }
+                FileUpdate::Upgrade(diff) => {
+                    fs::write(&path, &fresh)?;
+                    say!("Upgraded .gip/.gitignore:");
+                    print_diff(&diff);
+                }
+                FileUpdate::Current => {}
+                FileUpdate::Keep => {
+                    say!("{}", "Kept .gip/.gitignore: it has your changes".yellow());
+                    return unignore(&gitignore_path, &gitignore_content);
+                }
+            }
+            record.record(GITIGNORE_FILE, &fresh);
+            unignore(&gitignore_path, &gitignore_content)
+        }
+    }
+}
+
+/// Drop `.gip` from the repository's `.gitignore` so shared files can be committed
+fn unignore(gitignore_path: &Path, content: &str) -> Result<bool> {
+    let Some(content) = setup::unignore_gip(content) else {
+        return Ok(false);
     };
+    fs::write(gitignore_path, content)?;
+    say!(
+        "Removed .gip from .gitignore; share the setup with `git add .gip` ({})",
+        setup::SHARED_FILES.join(", ")
+    );
+    Ok(true)
+}
 
-    if !gitignore_content.contains(".gip") {
-        if !gitignore_content.is_empty() && !gitignore_content.ends_with('\n') {
-            gitignore_content.push('\n');
+/// Problems with the user's config and hooks, which init never rewrites
+fn check_setup(gip_dir: &Path) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let config_path = gip_dir.join(CONFIG_FILE);
+    if let Ok(data) = fs::read_to_string(&config_path) {
+        match setup::unknown_sections(&data) {
+            Ok(unknown) => {
+                for section in unknown {
+                    warnings.push(format!(
+                        "[{}] in .gip/config.toml is not a gip setting and is ignored",
+                        section
+                    ));
+                }
+                if let Err(e) = Config::load(gip_dir) {
+                    warnings.push(format!("{:#}", e));
+                }
+            }
+            Err(e) => warnings.push(format!("{:#}", e)),
         }
-        gitignore_content.push_str(".gip\n");
-        fs::write(&gitignore_path, gitignore_content)?;
-        println!("Added .gip to .gitignore");
     }
 
-    println!("{}", "✓ Gip initialized successfully".green());
-    println!("Created: .gip/");
-    if manifest_path.exists() {
-        println!("Created: .gip/manifest.toon (template)");
+    let events: Vec<&str> = HookEvent::ALL.iter().map(|e| e.name()).collect();
+    for hook in setup::unknown_hooks(gip_dir) {
+        warnings.push(format!(
+            ".gip/hooks/{} never runs; hooks are named after an event ({})",
+            hook,
+            events.join(", ")
+        ));
+    }
+    if template::custom_template_path(gip_dir).exists() {
+        say!("Kept custom template .gip/templates/manifest.toon");
+    }
+    warnings
+}
+
+fn print_diff(diff: &str) {
+    for line in diff.lines() {
+        match line.chars().next() {
+            Some('+') => say!("  {}", line.green()),
+            Some('-') => say!("  {}", line.red()),
+            _ => say!("  {}", line.cyan()),
+        }
+    }
+}
+
+/// Set up machine-level defaults in `~/.config/gip/`
+pub fn run_global() -> Result<()> {
+    let setup = global::init()?;
+    let config = setup.dir.join(CONFIG_FILE);
+    let template = template::custom_template_path(&setup.dir);
+
+    for (created, path) in [
+        (setup.created_config, &config),
+        (setup.created_template, &template),
+    ] {
+        if created {
+            say!("Created: {}", path.display());
+        } else {
+            say!("Kept existing: {}", path.display());
+        }
     }
+    status!(
+        "{}",
+        "✓ Machine defaults ready; every repository's .gip/config.toml is layered over them"
+            .green()
+    );
 
-    Ok(())
+    output::emit(
+        "init",
+        &GlobalInitResult {
+            config_dir: setup.dir.display().to_string(),
+            created_config: setup.created_config,
+            created_template: setup.created_template,
+        },
+    )
 }
@@ -0,0 +1,34 @@
+use crate::config::Config;
+use crate::encryption;
+use crate::errors::ErrorCode;
+use crate::git;
+use crate::{say, status};
+use anyhow::{Context, Result};
+use colored::*;
+
+/// Create the key for `[notes] encrypt`, unless one exists
+///
+/// Outside a repository the default location is used.
+pub fn run() -> Result<()> {
+    let notes = git::get_gip_dir()
+        .ok()
+        .and_then(|dir| Config::load(&dir).ok())
+        .map(|config| config.notes)
+        .unwrap_or_default();
+    let path = encryption::key_path(&notes)
+        .context("Cannot find a home directory; set GIP_CONFIG_DIR or [notes] key_file")?;
+    if path.exists() {
+        anyhow::bail!(ErrorCode::Usage.error(format!(
+            "A notes key already exists at {}; notes encrypted with it would be unreadable without it",
+            path.display()
+        )));
+    }
+
+    encryption::generate_key(&path)?;
+    status!(
+        "{}",
+        format!("✓ Notes key written to {}", path.display()).green()
+    );
+    say!("  Share it with your team outside git, then set encrypt = true under [notes]");
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use crate::git;
+use crate::lsp;
+use anyhow::Result;
+use std::io;
+
+pub fn run() -> Result<()> {
+    // The client normally sends its workspace root with `initialize`
+    let root = match git::get_repo_root() {
+        Ok(root) => root,
+        Err(_) => std::env::current_dir()?,
+    };
+    let code = lsp::serve(root, &mut io::stdin().lock(), &mut io::stdout().lock())?;
+    if code != 0 {
+        std::process::exit(code);
+    }
+    Ok(())
+}
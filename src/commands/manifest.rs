@@ -0,0 +1,92 @@
+use crate::config::Config;
+use crate::errors::ErrorCode;
+use crate::git;
+use crate::manifest::{schema, Manifest, ManifestFormat};
+use crate::output;
+use crate::rules;
+use crate::status;
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Subcommands of `gip manifest`
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum ManifestCommand {
+    /// Check a manifest file against the manifest JSON Schema and repository rules
+    Validate {
+        /// Manifest file in TOON, YAML, or JSON (defaults to the one `gip commit` would use)
+        file: Option<PathBuf>,
+    },
+}
+
+/// Result document of `gip manifest validate`
+#[derive(Serialize)]
+struct ValidateResult {
+    path: String,
+    valid: bool,
+}
+
+pub fn run(command: ManifestCommand) -> Result<()> {
+    match command {
+        ManifestCommand::Validate { file } => validate(file),
+    }
+}
+
+fn validate(file: Option<PathBuf>) -> Result<()> {
+    let path = match file {
+        Some(p) => p,
+        None => crate::commands::commit::find_manifest(&git::get_gip_dir()?)?.0,
+    };
+
+    let document = read_document(&path)?;
+    let mut errors = schema::validate(&document)?;
+
+    // Repository rules only make sense for a manifest that fits the schema
+    if errors.is_empty() {
+        let manifest: Manifest = serde_json::from_value(document)?;
+        let config = Config::load(&git::get_gip_dir()?)?;
+        errors = rules::check(&manifest, &config.validation)?
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+    }
+
+    if errors.is_empty() {
+        status!(
+            "{}",
+            format!("✓ {} is a valid manifest", path.display()).green()
+        );
+        return output::emit(
+            "manifest validate",
+            &ValidateResult {
+                path: path.display().to_string(),
+                valid: true,
+            },
+        );
+    }
+
+    eprintln!(
+        "{}",
+        format!("✗ {} is not a valid manifest:", path.display())
+            .red()
+            .bold()
+    );
+    for error in &errors {
+        eprintln!("  - {}", error);
+    }
+    anyhow::bail!(ErrorCode::ManifestInvalid.error(format!(
+        "Manifest validation failed with {} errors",
+        errors.len()
+    )));
+}
+
+/// Read a manifest file as an untyped document, by extension
+fn read_document(path: &Path) -> Result<Value> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    ManifestFormat::from_path(path)
+        .parse(&content)
+        .with_context(|| format!("Failed to parse {:?}", path))
+}
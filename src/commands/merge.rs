@@ -1,59 +1,389 @@
+use crate::audit::{self, AuditAction};
+use crate::config::Config;
+use crate::errors::ErrorCode;
 use crate::git;
+use crate::hooks::{self, HookEvent};
+use crate::manifest;
 use crate::merge;
-use anyhow::Result;
+use crate::output;
+use crate::policy;
+use crate::semantic::{self, Finding};
+use crate::{say, status};
+use anyhow::{Context, Result};
 use colored::*;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-pub fn run(args: &[String]) -> Result<()> {
-    println!("{}", "Merging with Gip...".cyan());
+/// `git merge` options whose value is the next argument
+const OPTIONS_WITH_VALUE: &[&str] = &[
+    "-m",
+    "-F",
+    "-s",
+    "-X",
+    "--message",
+    "--file",
+    "--strategy",
+    "--strategy-option",
+    "--cleanup",
+    "--into-name",
+];
+
+/// `git merge` options that act on a merge in progress
+const IN_PROGRESS_OPTIONS: &[&str] = &["--abort", "--continue", "--quit"];
+
+/// Result document of `gip merge`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MergeResult {
+    conflicted: bool,
+    head: String,
+    theirs: Option<String>,
+    conflicted_files: Vec<String>,
+    enriched_files: usize,
+    dry_run: bool,
+}
+
+/// Where `gip merge --ci` writes its report, inside `.gip/`
+const CI_REPORT: &str = "ci/merge-report.json";
+
+/// Result document and report of `gip merge --ci`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CiReport {
+    head: String,
+    theirs: String,
+    merge_base: String,
+    conflicted: bool,
+    conflicts: Vec<merge::ConflictPreview>,
+    semantic_findings: Vec<Finding>,
+    report: PathBuf,
+}
+
+pub fn run(override_reason: Option<&str>, dry_run: bool, args: &[String]) -> Result<()> {
+    status!("{}", "Merging with Gip...".cyan());
+
+    check_policy(override_reason, dry_run, args)?;
+    if dry_run {
+        return preview(args);
+    }
 
     // 1. Run git merge
     let mut git_args = vec!["merge".to_string()];
     git_args.extend_from_slice(args);
 
     // We allow git merge to fail (conflict), so we don't use passthrough::run which exits on error
-    let status = std::process::Command::new("git").args(&git_args).status()?;
+    let status = git::run_git_status(&git_args)?;
 
     if status.success() {
-        println!("{}", "Merge successful".green());
-        return Ok(());
+        status!("{}", "Merge successful".green());
+        merge::cleanup_conflict_backups()?;
+        return output::emit(
+            "merge",
+            &MergeResult {
+                conflicted: false,
+                head: git::get_current_commit()?,
+                theirs: None,
+                conflicted_files: Vec::new(),
+                enriched_files: 0,
+                dry_run: false,
+            },
+        );
     }
 
-    // 2. If failed, check for conflicts
-    println!(
+    // 2. If failed, check for conflicts. A dirty tree or an unknown branch
+    // fails before the merge starts, and a rejecting hook leaves it in
+    // progress without unmerged entries; git has already said why.
+    let Ok(theirs_sha) = git::run_git_cmd(&["rev-parse", "-q", "--verify", "MERGE_HEAD"], None)
+    else {
+        std::process::exit(status.code().unwrap_or(1));
+    };
+    if merge::get_conflicted_files()?.is_empty() {
+        say!(
+            "{}",
+            "Merge stopped without conflicts. Finish it with gip commit or drop it with gip abort"
+                .yellow()
+        );
+        std::process::exit(status.code().unwrap_or(1));
+    }
+
+    say!(
         "{}",
         "Merge conflict detected. Enriching markers...".yellow()
     );
-
-    // We need to know the SHAs being merged.
-    // HEAD is ours.
-    // The other side is in MERGE_HEAD usually, or we can parse args.
-    // But args might be a branch name.
-    // `git rev-parse MERGE_HEAD` should work if merge is in progress.
-
     let ours_sha = git::get_current_commit()?;
-    let theirs_sha = match git::run_git_cmd(&["rev-parse", "MERGE_HEAD"], None) {
-        Ok(sha) => sha,
-        Err(_) => {
-            // Maybe rebase? Or just failed merge without starting?
-            println!(
-                "{}",
-                "Could not determine MERGE_HEAD. Skipping enrichment.".red()
-            );
-            std::process::exit(status.code().unwrap_or(1));
-        }
-    };
 
-    let count = merge::enrich_all_conflicts(&ours_sha, &theirs_sha)?;
+    let count = merge::enrich_merge_conflicts(&ours_sha, &theirs_sha)?;
 
     if count > 0 {
-        println!(
+        status!(
             "{}",
             format!("✓ Enriched {} conflicted files with context", count).green()
         );
+        say!(
+            "  Unenriched copies are in .gip/{}/ until the conflict is resolved",
+            merge::CONFLICT_BACKUP_DIR
+        );
     } else {
-        println!("{}", "No context available for conflicts".yellow());
+        say!("{}", "No context available for conflicts".yellow());
+    }
+
+    audit::record(
+        AuditAction::Enrichment,
+        Some(&theirs_sha),
+        Some(format!("merge: enriched {} files", count)),
+    );
+
+    hooks::run(
+        &git::get_gip_dir()?,
+        HookEvent::PostEnrich,
+        &serde_json::json!({
+            "operation": "merge",
+            "ours": ours_sha,
+            "theirs": theirs_sha,
+            "enrichedFiles": count,
+        }),
+    )?;
+
+    output::emit(
+        "merge",
+        &MergeResult {
+            conflicted: true,
+            head: ours_sha,
+            theirs: Some(theirs_sha),
+            conflicted_files: merge::get_conflicted_files().unwrap_or_default(),
+            enriched_files: count,
+            dry_run: false,
+        },
+    )?;
+
+    anyhow::bail!(ErrorCode::MergeConflict.error(format!(
+        "Merge stopped on conflicts ({} file(s) enriched). Resolve them, then run: gip commit",
+        count
+    )))
+}
+
+/// Merge `branch` into `HEAD` in memory and report what a merge queue needs
+///
+/// Neither the working tree nor the index is touched and nothing is asked.
+/// The conflicts git would leave, with their enrichment, and the symbols both
+/// sides changed are written as JSON to `report` (`.gip/ci/merge-report.json`
+/// by default). Fails on policy violations and conflicts.
+pub fn run_ci(
+    branch: &str,
+    report: Option<&Path>,
+    override_reason: Option<&str>,
+    args: &[String],
+) -> Result<()> {
+    if let Some(arg) = args.first() {
+        anyhow::bail!(ErrorCode::Usage.error(format!(
+            "gip merge --ci merges only the branch it names; {} isn't supported",
+            arg
+        )));
+    }
+    status!("{}", format!("Checking the merge of {}...", branch).cyan());
+    let theirs_sha = resolve_commit(branch)
+        .map_err(|_| ErrorCode::Usage.error(format!("'{}' is not a branch or commit", branch)))?;
+    let head = git::get_current_commit()?;
+    let base = git::run_git_cmd(&["merge-base", &head, &theirs_sha], None)
+        .with_context(|| format!("HEAD and {} have no common history", branch))?;
+
+    let ours = manifest::load(&head, None).ok();
+    let theirs = manifest::load(&theirs_sha, None).ok();
+    let conflicts = merge::preview_conflicts(&head, ours.as_ref(), &theirs_sha, theirs.as_ref())?;
+
+    let mut findings = semantic::find(
+        &semantic::since(&base, &head)?,
+        &semantic::since(&base, &theirs_sha)?,
+    );
+    for finding in &mut findings {
+        finding.conflicted = conflicts.iter().any(|c| c.file == finding.file);
+    }
+
+    let report_path = match report {
+        Some(path) => path.to_path_buf(),
+        None => git::get_gip_dir()?.join(CI_REPORT),
+    };
+    let result = CiReport {
+        head,
+        theirs: theirs_sha,
+        merge_base: base,
+        conflicted: !conflicts.is_empty(),
+        conflicts,
+        semantic_findings: findings,
+        report: report_path.clone(),
+    };
+    if let Some(dir) = report_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    fs::write(&report_path, serde_json::to_string_pretty(&result)?)
+        .with_context(|| format!("Failed to write {}", report_path.display()))?;
+
+    for conflict in &result.conflicts {
+        say!("{} {} conflicts", "✗".red(), conflict.file);
+    }
+    for finding in &result.semantic_findings {
+        say!(
+            "{} {}::{} changed on both sides ({})",
+            "!".yellow(),
+            finding.file,
+            finding.symbol,
+            finding.kind.as_str()
+        );
+    }
+    if result.conflicts.is_empty() {
+        status!("{}", "✓ Merges without conflicts".green());
+    }
+    say!("Report written to {}", report_path.display());
+    output::emit("merge", &result)?;
+
+    check_policy(override_reason, false, &[branch.to_string()])?;
+    if result.conflicted {
+        anyhow::bail!(ErrorCode::MergeConflict.error(format!(
+            "Merging {} would conflict in {} file(s); see {}",
+            branch,
+            result.conflicts.len(),
+            report_path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// Check the manifests of incoming commits against the current branch's policies
+fn check_policy(override_reason: Option<&str>, dry_run: bool, args: &[String]) -> Result<()> {
+    let branch = match git::current_branch(None) {
+        Some(b) => b,
+        None => return Ok(()),
+    };
+    let config = Config::load(&git::get_gip_dir()?)?.policy;
+    if config.for_branch(&branch).rules.is_empty() {
+        return Ok(());
+    }
+
+    let sources = merge_sources(args);
+    let mut violations = Vec::new();
+    for source in &sources {
+        for sha in git::rev_list(&format!("HEAD..{}", source), &[], None)? {
+            if let Ok(m) = manifest::load(&sha, None) {
+                violations.extend(policy::check(&m, &config, &branch, Some(&sha))?);
+            }
+        }
+    }
+
+    if dry_run && override_reason.is_some() {
+        // A dry run must not leave an override in the audit log
+        policy::preview_override(&violations);
+        return Ok(());
+    }
+    policy::enforce(
+        "Merge",
+        &branch,
+        &violations,
+        override_reason,
+        sources.first().map(|s| s.as_str()),
+    )
+}
+
+/// Show the conflicts a merge would leave and the context gip would add to them
+fn preview(args: &[String]) -> Result<()> {
+    say!("{}", "Dry run: nothing will be merged".cyan());
+    let sources = merge_sources(args);
+    let Some(theirs_sha) = sources.first() else {
+        anyhow::bail!("Nothing to merge; name a branch or commit");
+    };
+    if sources.len() > 1 {
+        say!(
+            "{}",
+            "Warning: only the first of several merge sources is previewed".yellow()
+        );
     }
 
-    // Exit with the original status code
-    std::process::exit(status.code().unwrap_or(1));
+    let head = git::get_current_commit()?;
+    let incoming = git::rev_list(&format!("HEAD..{}", theirs_sha), &[], None)?;
+    say!(
+        "Would merge {} ({} new commit(s)) into {}",
+        &theirs_sha[..8],
+        incoming.len(),
+        &head[..8]
+    );
+
+    let ours = manifest::load(&head, None).ok();
+    let theirs = manifest::load(theirs_sha, None).ok();
+    let conflicts = merge::preview_conflicts(&head, ours.as_ref(), theirs_sha, theirs.as_ref())?;
+    if conflicts.is_empty() {
+        status!("{}", "✓ Merges without conflicts".green());
+    }
+
+    let mut enriched = 0;
+    for conflict in &conflicts {
+        say!("{} {}", "✗".red(), conflict.file);
+        if conflict.binary {
+            say!(
+                "    binary: context would go to .gip/{}/",
+                merge::SIDECAR_DIR
+            );
+            enriched += usize::from(ours.is_some() || theirs.is_some());
+        } else if conflict.context.is_empty() {
+            say!("    no context available");
+        } else {
+            enriched += 1;
+            for line in &conflict.context {
+                say!("    {}", line);
+            }
+        }
+    }
+
+    output::emit(
+        "merge",
+        &MergeResult {
+            conflicted: !conflicts.is_empty(),
+            head,
+            theirs: Some(theirs_sha.clone()),
+            conflicted_files: conflicts.into_iter().map(|c| c.file).collect(),
+            enriched_files: enriched,
+            dry_run: true,
+        },
+    )
+}
+
+/// Commits a `git merge` command line would merge: the named ones, or the upstream
+fn merge_sources(args: &[String]) -> Vec<String> {
+    if args
+        .iter()
+        .any(|a| IN_PROGRESS_OPTIONS.contains(&a.as_str()))
+    {
+        return Vec::new();
+    }
+
+    let mut revs = Vec::new();
+    let mut skip_value = false;
+    for arg in args {
+        if std::mem::take(&mut skip_value) {
+            continue;
+        }
+        if arg.starts_with('-') {
+            skip_value = OPTIONS_WITH_VALUE.contains(&arg.as_str());
+            continue;
+        }
+        if let Ok(sha) = resolve_commit(arg) {
+            revs.push(sha);
+        }
+    }
+
+    if revs.is_empty() {
+        revs.extend(resolve_commit("@{upstream}").ok());
+    }
+    revs
+}
+
+fn resolve_commit(rev: &str) -> Result<String> {
+    git::run_git_cmd(
+        &[
+            "rev-parse",
+            "--verify",
+            "-q",
+            &format!("{}^{{commit}}", rev),
+        ],
+        None,
+    )
 }
@@ -0,0 +1,129 @@
+use crate::config::Config;
+use crate::errors::ErrorCode;
+use crate::git;
+use crate::mergetool::{self, ToolPaths, CONTEXT_ENV, KNOWN_TOOLS, TOOL_ENV, TOOL_NAME};
+use crate::{say, status};
+use anyhow::{Context, Result};
+use colored::*;
+use std::process::{Command, ExitStatus};
+
+pub fn run(
+    tool: Option<&str>,
+    install: bool,
+    driver: Option<&[String]>,
+    files: &[String],
+) -> Result<()> {
+    if let Some(paths) = driver {
+        return run_driver(tool, paths);
+    }
+
+    if install || !mergetool::is_installed() {
+        mergetool::install()?;
+        status!(
+            "{}",
+            format!("✓ Registered the `{}` merge tool in .git/config", TOOL_NAME).green()
+        );
+    }
+    if install {
+        say!(
+            "Run `git mergetool --tool {0}`, or `git config merge.tool {0}` to make it the default.",
+            TOOL_NAME
+        );
+        return Ok(());
+    }
+
+    // The driver runs under `git mergetool`; the tool choice reaches it through the environment
+    if let Some(tool) = tool {
+        std::env::set_var(TOOL_ENV, tool);
+    }
+    let mut args: Vec<String> = ["mergetool", "--tool", TOOL_NAME]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    if !files.is_empty() {
+        args.push("--".to_string());
+        args.extend(files.iter().cloned());
+    }
+
+    let status = git::run_git_status(&args)?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+/// Write the side file and launch the real tool, as git's `gip` merge tool
+fn run_driver(tool: Option<&str>, paths: &[String]) -> Result<()> {
+    let [base, local, remote, merged] = paths else {
+        anyhow::bail!(ErrorCode::Usage.error("--driver takes BASE LOCAL REMOTE MERGED"));
+    };
+
+    let gip_dir = git::get_gip_dir()?;
+    let config = Config::load(&gip_dir)?;
+    let requested = tool
+        .map(str::to_string)
+        .or_else(|| std::env::var(TOOL_ENV).ok());
+    let Some(name) = mergetool::pick_tool(requested.as_deref(), &config.mergetool) else {
+        anyhow::bail!(ErrorCode::Usage.error(format!(
+            "No merge tool to launch: pass --tool, or set `tool` under [mergetool] in .gip/config.toml ({} or any git mergetool)",
+            KNOWN_TOOLS.iter().map(|t| t.name).collect::<Vec<_>>().join(", ")
+        )));
+    };
+
+    let context = mergetool::write_context(&gip_dir, merged)?;
+    status!(
+        "{} {}",
+        "Gip context:".cyan(),
+        context.display().to_string().bold()
+    );
+    let paths = ToolPaths {
+        base: base.clone(),
+        local: local.clone(),
+        remote: remote.clone(),
+        merged: merged.clone(),
+        context: context.display().to_string(),
+    };
+
+    let status = launch(&name, &paths)?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+fn launch(name: &str, paths: &ToolPaths) -> Result<ExitStatus> {
+    if let Some(tool) = mergetool::known_tool(name) {
+        if let Some(args) = tool.context_args {
+            // Best effort: the merge works without the side pane
+            let _ = Command::new(tool.program).args(paths.expand(args)).status();
+        }
+        return Command::new(tool.program)
+            .args(paths.expand(tool.args))
+            .status()
+            .with_context(|| format!("Failed to launch {}", tool.program));
+    }
+
+    // Any other tool needs a command configured for `git mergetool`
+    let cmd =
+        git::run_git_cmd(&["config", &format!("mergetool.{}.cmd", name)], None).map_err(|_| {
+            ErrorCode::Usage.error(format!(
+                "Unknown merge tool '{0}': set mergetool.{0}.cmd in git config or use one of {1}",
+                name,
+                KNOWN_TOOLS
+                    .iter()
+                    .map(|t| t.name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        })?;
+    Command::new("sh")
+        .arg("-c")
+        .arg(&cmd)
+        .env("BASE", &paths.base)
+        .env("LOCAL", &paths.local)
+        .env("REMOTE", &paths.remote)
+        .env("MERGED", &paths.merged)
+        .env(CONTEXT_ENV, &paths.context)
+        .status()
+        .with_context(|| format!("Failed to run mergetool.{}.cmd", name))
+}
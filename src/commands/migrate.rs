@@ -0,0 +1,102 @@
+use crate::backup;
+use crate::git;
+use crate::manifest;
+use crate::status;
+use anyhow::Result;
+use colored::*;
+
+pub fn run(commits: Vec<String>, all: bool, dry_run: bool) -> Result<()> {
+    let commits = if all {
+        git::list_noted_commits(None)?
+    } else if commits.is_empty() {
+        anyhow::bail!("Specify commits to migrate, or --all for every commit with a gip note");
+    } else {
+        commits
+    };
+
+    status!(
+        "{}",
+        format!(
+            "Migrating {} manifests to schema v{}...",
+            commits.len(),
+            manifest::SCHEMA_VERSION_CURRENT
+        )
+        .cyan()
+    );
+
+    if !dry_run {
+        backup::snapshot_before("migrating")?;
+    }
+
+    let mut migrated = 0;
+    let mut current = 0;
+    let mut unknown = 0;
+    let mut failed = 0;
+
+    for commit in &commits {
+        let short = &commit[..commit.len().min(8)];
+
+        let stored = match manifest::load_raw(commit, None) {
+            Ok(m) => m,
+            Err(e) => {
+                eprintln!("{}", format!("✗ {}: {:#}", short, e).red());
+                failed += 1;
+                continue;
+            }
+        };
+
+        if manifest::is_unknown_version(&stored) {
+            eprintln!(
+                "{}",
+                format!(
+                    "  {} schema v{} is not one this gip knows (newer?); left as is",
+                    short, stored.schema_version
+                )
+                .yellow()
+            );
+            unknown += 1;
+            continue;
+        }
+        if !manifest::needs_migration(&stored) {
+            current += 1;
+            continue;
+        }
+
+        let from = if stored.schema_version.is_empty() {
+            manifest::SCHEMA_VERSION_1_0.to_string()
+        } else {
+            stored.schema_version.clone()
+        };
+        let upgraded = manifest::migrate(stored);
+
+        if !dry_run {
+            if let Err(e) = manifest::save(&upgraded, commit, None) {
+                eprintln!("{}", format!("✗ {}: {:#}", short, e).red());
+                failed += 1;
+                continue;
+            }
+        }
+
+        println!(
+            "  {} v{} → v{}",
+            short.yellow(),
+            from,
+            upgraded.schema_version
+        );
+        migrated += 1;
+    }
+
+    println!();
+    let verb = if dry_run { "Would migrate" } else { "Migrated" };
+    status!("{}", format!("✓ {} {} manifests", verb, migrated).green());
+    println!("  Already current: {}", current);
+    if unknown > 0 {
+        println!("  Skipped, unknown version: {}", unknown);
+    }
+    if failed > 0 {
+        println!("  {}", format!("Failed: {}", failed).red());
+        anyhow::bail!("{} manifests could not be migrated", failed);
+    }
+
+    Ok(())
+}
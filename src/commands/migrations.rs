@@ -0,0 +1,32 @@
+use crate::git;
+use crate::packages::PackageMap;
+use crate::query::HistoryFilter;
+use crate::status;
+use crate::upgrade;
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::path::PathBuf;
+
+pub fn run(range: &str, output: Option<PathBuf>) -> Result<()> {
+    let packages = PackageMap::load(&git::get_gip_dir()?)?;
+
+    let mut manifests = HistoryFilter::default().collect(range, &packages, None)?;
+    manifests.reverse();
+
+    let groups = upgrade::collect(&manifests, &packages);
+    let guide = upgrade::to_markdown(&format!("Upgrade guide: {}", range), &groups);
+
+    match output {
+        Some(path) => {
+            fs::write(&path, &guide).with_context(|| format!("Failed to write {:?}", path))?;
+            status!(
+                "{}",
+                format!("✓ Upgrade guide written to {}", path.display()).green()
+            );
+        }
+        None => print!("{}", guide),
+    }
+
+    Ok(())
+}
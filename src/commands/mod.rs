@@ -1,7 +1,49 @@
+pub mod add;
+pub mod am;
+pub mod amend_context;
+pub mod annotate;
+pub mod api_report;
+pub mod audit;
+pub mod bisect;
+pub mod bundle;
+pub mod check_contracts;
+pub mod checkout;
+pub mod clone;
 pub mod commit;
 pub mod context;
+pub mod coverage;
+pub mod fetch;
+pub mod format_patch;
+pub mod gc;
+pub mod graph;
+pub mod history;
+pub mod incoming;
 pub mod init;
+pub mod keygen;
+pub mod lsp;
+pub mod manifest;
 pub mod merge;
+pub mod mergetool;
+pub mod migrate;
+pub mod migrations;
 pub mod passthrough;
+pub mod prompt;
+pub mod pull;
 pub mod push;
 pub mod rebase;
+pub mod release;
+pub mod remap;
+pub mod resolve;
+pub mod restore_notes;
+pub mod schema;
+pub mod sequencer;
+pub mod show;
+pub mod stash;
+pub mod stats;
+pub mod switch;
+pub mod template;
+pub mod trust;
+pub mod unenrich;
+pub mod uninstall;
+pub mod watch;
+pub mod why;
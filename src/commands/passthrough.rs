@@ -1,11 +1,8 @@
-use anyhow::{Context, Result};
-use std::process::Command;
+use crate::git;
+use anyhow::Result;
 
 pub fn run(args: &[String]) -> Result<()> {
-    let mut cmd = Command::new("git");
-    cmd.args(args);
-
-    let status = cmd.status().context("Failed to execute git command")?;
+    let status = git::run_git_status(args)?;
 
     if !status.success() {
         std::process::exit(status.code().unwrap_or(1));
@@ -0,0 +1,133 @@
+use crate::config::Config;
+use crate::git;
+use crate::manifest;
+use crate::merge;
+use crate::profile::Consumer;
+use crate::prompt::{self, PromptOptions, Side};
+use crate::status;
+use crate::tokens;
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Clipboard programs tried in order, with their arguments
+const CLIPBOARD_COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+pub fn run(file: Option<&str>, copy: bool) -> Result<()> {
+    let conflicted = merge::get_conflicted_files()?;
+    let file = match file {
+        Some(f) => f.to_string(),
+        None => match conflicted.as_slice() {
+            [only] => only.clone(),
+            [] => anyhow::bail!("No conflicted files"),
+            _ => anyhow::bail!(
+                "Several files are conflicted; pick one:\n  {}",
+                conflicted.join("\n  ")
+            ),
+        },
+    };
+
+    let content = fs::read_to_string(&file).with_context(|| format!("Failed to read {}", file))?;
+    let hunks = prompt::parse_hunks(&content);
+    if hunks.is_empty() {
+        anyhow::bail!("{} has no conflict markers", file);
+    }
+
+    let ours_sha = git::get_current_commit()?;
+    let theirs_sha = merge::conflict_other_head()
+        .context("No merge, rebase, cherry-pick, revert or enriched stash in progress")?;
+
+    let ours = Side {
+        label: hunks[0].ours_label.clone(),
+        manifest: manifest::load(&ours_sha, None).ok(),
+        sha: ours_sha,
+    };
+    let theirs = Side {
+        label: hunks[0].theirs_label.clone(),
+        manifest: manifest::load(&theirs_sha, None).ok(),
+        sha: theirs_sha,
+    };
+
+    let aliases = merge::path_aliases(&file, &[&ours.sha, &theirs.sha]);
+    // Stage 1 of the index holds the common ancestor of a conflicted file
+    let base = git::run_git_cmd(&["show", &format!(":1:{}", file)], None).ok();
+
+    let config = Config::load(&git::get_gip_dir()?)?;
+    let options = PromptOptions {
+        profile: config.projection.profile_for(Consumer::Prompt)?,
+        paths: config.paths,
+    };
+
+    let text = prompt::compose(
+        &file,
+        &hunks,
+        &ours,
+        &theirs,
+        &aliases,
+        &options,
+        base.as_deref(),
+    );
+    let tokens = tokens::estimate(&text);
+
+    if copy {
+        copy_to_clipboard(&text)?;
+        status!(
+            "{}",
+            format!(
+                "✓ Copied prompt for {} ({} hunk(s), ~{} tokens)",
+                file,
+                hunks.len(),
+                tokens
+            )
+            .green()
+        );
+    } else {
+        print!("{}", text);
+        // Keep stdout clean for piping into another tool
+        eprintln!(
+            "{}",
+            format!("~{} tokens, {} hunk(s)", tokens, hunks.len()).cyan()
+        );
+    }
+
+    Ok(())
+}
+
+fn copy_to_clipboard(text: &str) -> Result<()> {
+    for (program, args) in CLIPBOARD_COMMANDS {
+        let child = Command::new(program)
+            .args(*args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match child {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+
+    anyhow::bail!(
+        "No clipboard tool found (tried {}); run without --copy and pipe the output instead",
+        CLIPBOARD_COMMANDS
+            .iter()
+            .map(|(p, _)| *p)
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
@@ -0,0 +1,162 @@
+use crate::audit::{self, AuditAction};
+use crate::commands::fetch::{self, RemoteNotes};
+use crate::errors::ErrorCode;
+use crate::git::{self, Operation};
+use crate::hooks::{self, HookEvent};
+use crate::incoming::{self, IncomingChange};
+use crate::merge;
+use crate::output;
+use crate::remote::{self, RemoteArgs};
+use crate::{say, status};
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+
+/// Result document of `gip pull`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PullResult {
+    remotes: Vec<RemoteNotes>,
+    /// `merge` or `rebase`
+    mode: &'static str,
+    head: Option<String>,
+    /// Breaking changes and migrations that arrived, affecting your recent work
+    incoming: Vec<IncomingChange>,
+    conflicted: bool,
+    enriched_files: usize,
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    // Notes first, so the manifests of incoming commits are there for any
+    // conflicts the pull runs into
+    let target = RemoteArgs::parse_fetch(args);
+    let remotes = fetch::fetch_notes(&target)?;
+    let mode = if remote::pull_rebases(&target, None) {
+        "rebase"
+    } else {
+        "merge"
+    };
+    status!("{}", format!("Pulling with {}...", mode).cyan());
+
+    let mut git_args = vec!["pull".to_string()];
+    git_args.extend_from_slice(args);
+    let before = git::get_current_commit().ok();
+    let status = git::run_git_status(&git_args)?;
+
+    if !status.success() {
+        // Unreachable remotes, refused fast-forwards and dirty trees stop the
+        // pull before anything is left in progress
+        let (operation, other_head) = match git::operation_in_progress(None) {
+            Some(Operation::Merge) => ("merge", "MERGE_HEAD"),
+            Some(Operation::Rebase) => ("rebase", "REBASE_HEAD"),
+            _ => std::process::exit(status.code().unwrap_or(1)),
+        };
+        let (Ok(ours_sha), Ok(theirs_sha)) = (
+            git::get_current_commit(),
+            git::run_git_cmd(&["rev-parse", other_head], None),
+        ) else {
+            say!(
+                "{}",
+                format!("Could not determine {}. Skipping enrichment.", other_head).red()
+            );
+            std::process::exit(status.code().unwrap_or(1));
+        };
+        return enrich(remotes, operation, &ours_sha, &theirs_sha);
+    }
+
+    let head = git::get_current_commit().ok();
+    let incoming = match (before, head.as_deref()) {
+        (Some(old), Some(new)) => incoming::warn(&old, new),
+        _ => Vec::new(),
+    };
+    output::emit(
+        "pull",
+        &PullResult {
+            remotes,
+            mode,
+            head,
+            incoming,
+            conflicted: false,
+            enriched_files: 0,
+        },
+    )
+}
+
+/// Enrich the conflicts a pull stopped on and fail with the way to go on
+///
+/// A merge is enriched like `gip merge`, with `MERGE_HEAD` as their side. A
+/// rebase replays your commits onto upstream, so `HEAD` is upstream and
+/// `REBASE_HEAD` your commit.
+fn enrich(
+    remotes: Vec<RemoteNotes>,
+    operation: &'static str,
+    ours_sha: &str,
+    theirs_sha: &str,
+) -> Result<()> {
+    say!(
+        "{}",
+        format!(
+            "Pull stopped on {} conflicts. Enriching markers...",
+            operation
+        )
+        .yellow()
+    );
+    let count = if operation == "merge" {
+        merge::enrich_merge_conflicts(ours_sha, theirs_sha)?
+    } else {
+        merge::enrich_all_conflicts(ours_sha, theirs_sha)?
+    };
+
+    if count > 0 {
+        status!(
+            "{}",
+            format!("✓ Enriched {} conflicted files with context", count).green()
+        );
+        say!(
+            "  Unenriched copies are in .gip/{}/ until the conflict is resolved",
+            merge::CONFLICT_BACKUP_DIR
+        );
+    } else {
+        say!("{}", "No context available for conflicts".yellow());
+    }
+
+    audit::record(
+        AuditAction::Enrichment,
+        Some(theirs_sha),
+        Some(format!("pull ({}): enriched {} files", operation, count)),
+    );
+
+    hooks::run(
+        &git::get_gip_dir()?,
+        HookEvent::PostEnrich,
+        &serde_json::json!({
+            "operation": "pull",
+            "mode": operation,
+            "ours": ours_sha,
+            "theirs": theirs_sha,
+            "enrichedFiles": count,
+        }),
+    )?;
+
+    output::emit(
+        "pull",
+        &PullResult {
+            remotes,
+            mode: operation,
+            head: Some(ours_sha.to_string()),
+            incoming: Vec::new(),
+            conflicted: true,
+            enriched_files: count,
+        },
+    )?;
+
+    let next = if operation == "merge" {
+        "gip commit"
+    } else {
+        "gip rebase --continue"
+    };
+    anyhow::bail!(ErrorCode::MergeConflict.error(format!(
+        "Pull stopped on conflicts ({} file(s) enriched). Resolve them, then run: {}",
+        count, next
+    )))
+}
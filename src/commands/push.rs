@@ -1,30 +1,262 @@
+use crate::audit::{self, AuditAction};
+use crate::config::Config;
+use crate::errors::ErrorCode;
 use crate::git;
+use crate::output;
+use crate::redact::{self, Redactor};
+use crate::remote::{self, RemoteArgs};
+use crate::{say, status};
 use anyhow::Result;
 use colored::*;
+use serde::Serialize;
 
-pub fn run(args: &[String]) -> Result<()> {
-    // 1. Push code
-    println!("{}", "Pushing code...".cyan());
-    let mut git_args = vec!["push".to_string()];
-    git_args.extend_from_slice(args);
-
-    crate::commands::passthrough::run(&git_args)?;
-
-    // 2. Push notes
-    println!("{}", "Pushing context notes...".cyan());
-    // Assuming 'origin' for now, or parse from args if provided
-    // Ideally we should detect the remote being pushed to.
-    // For simplicity, we'll try to push to origin.
-    // TODO: Parse remote from args
-    let remote = "origin";
-
-    match git::push_notes(remote) {
-        Ok(_) => println!("{}", "✓ Context notes pushed".green()),
-        Err(e) => println!(
+/// Result document of `gip push`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PushResult {
+    remote: String,
+    /// Code and notes went in one atomic push
+    atomic: bool,
+    redacted: bool,
+    /// Remotes that received the notes
+    notes_pushed_to: Vec<String>,
+    dry_run: bool,
+}
+
+pub fn run(redact: bool, notes_to: &[String], dry_run: bool, args: &[String]) -> Result<()> {
+    let config = Config::load(&git::get_gip_dir()?)?;
+    let redact = redact || config.redaction.always;
+
+    let mut push = RemoteArgs::parse_push(args);
+    if push.remote.is_none() {
+        push.remote = Some(remote::default_push_remote(None));
+    }
+    let remote = push.remote_or("origin");
+
+    let has_notes = git::run_git_cmd(
+        &["rev-parse", "--verify", "-q", &git::notes_ref_full()],
+        None,
+    )
+    .is_ok();
+    if dry_run {
+        return preview(args, &push, &remote, has_notes, &config, redact, notes_to);
+    }
+    if !has_notes {
+        status!("{}", "Pushing code...".cyan());
+        let mut git_args = vec!["push".to_string()];
+        git_args.extend_from_slice(args);
+        crate::commands::passthrough::run(&git_args)?;
+        return output::emit(
+            "push",
+            &PushResult {
+                remote,
+                atomic: false,
+                redacted: false,
+                notes_pushed_to: Vec::new(),
+                dry_run: false,
+            },
+        );
+    }
+
+    let notes_refspec = notes_refspec(&config, redact)?;
+
+    // Push code and notes in one atomic transaction so the remote never ends
+    // up with one but not the other. A forced push would force the notes too
+    // and drop notes teammates pushed meanwhile, so it goes separately, as
+    // does a push whose destination branch git can't name up front.
+    let atomic_args = if push.selects_refs() || push.forces() {
+        None
+    } else {
+        push.with_refspecs(&[&notes_refspec], None)
+    };
+    let pushed_atomically = if let Some(mut atomic) = atomic_args {
+        status!("{}", "Pushing code and context notes...".cyan());
+        atomic.insert(1, "--atomic".to_string());
+
+        let output = git::run_git_output(&atomic)?;
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+
+        if output.status.success() {
+            true
+        } else if String::from_utf8_lossy(&output.stderr).contains("support --atomic") {
+            say!(
+                "{}",
+                format!(
+                    "Warning: {} does not support atomic pushes; pushing code and notes separately",
+                    remote
+                )
+                .yellow()
+            );
+            false
+        } else {
+            // A redacted push publishes the copy, so that is what has to catch
+            // up; fetching into the local notes would replace them
+            let fetch = if redact {
+                format!(
+                    "git fetch {} +{}:refs/notes/{}",
+                    remote,
+                    git::notes_ref_full(),
+                    redact::REDACTED_NOTES_REF
+                )
+            } else {
+                format!("gip fetch {}", remote)
+            };
+            anyhow::bail!(ErrorCode::PushRejected.error(format!(
+                "Push rejected; neither code nor notes were pushed. If the remote notes moved on, run: {}",
+                fetch
+            )));
+        }
+    } else {
+        false
+    };
+
+    let mut result = PushResult {
+        remote: remote.clone(),
+        atomic: pushed_atomically,
+        redacted: redact,
+        notes_pushed_to: Vec::new(),
+        dry_run: false,
+    };
+    if !pushed_atomically {
+        status!("{}", "Pushing code...".cyan());
+        let mut git_args = vec!["push".to_string()];
+        git_args.extend_from_slice(args);
+        crate::commands::passthrough::run(&git_args)?;
+
+        status!("{}", "Pushing context notes...".cyan());
+        if !push_notes(&remote, &notes_refspec, redact) {
+            return output::emit("push", &result);
+        }
+    } else {
+        record_push(&remote, redact);
+    }
+    result.notes_pushed_to.push(remote.clone());
+
+    // Mirror the notes to any extra remotes, e.g. both a fork and upstream
+    for extra in notes_to.iter().filter(|r| **r != remote) {
+        status!(
             "{}",
-            format!("Warning: Failed to push notes: {}", e).yellow()
-        ),
+            format!("Pushing context notes to {}...", extra).cyan()
+        );
+        if push_notes(extra, &notes_refspec, redact) {
+            result.notes_pushed_to.push(extra.clone());
+        }
     }
 
-    Ok(())
+    status!("{}", "✓ Context notes pushed".green());
+    output::emit("push", &result)
+}
+
+/// Notes refspec to push: the local notes, or their redacted copy brought up to date
+fn notes_refspec(config: &Config, redact: bool) -> Result<String> {
+    if !redact {
+        return Ok(git::notes_ref_full());
+    }
+    let redactor = Redactor::new(&config.redaction.rules)?;
+    let count = redact::write_redacted_notes(&redactor, None)?;
+    status!(
+        "{}",
+        format!("✓ Redacted {} notes for publishing", count).green()
+    );
+    Ok(format!(
+        "refs/notes/{}:{}",
+        redact::REDACTED_NOTES_REF,
+        git::notes_ref_full()
+    ))
+}
+
+/// Let git report the refs a push would update, without pushing
+///
+/// With redaction the local redacted copy is updated first, so the preview
+/// shows the notes that would really be published.
+fn preview(
+    args: &[String],
+    push: &RemoteArgs,
+    remote: &str,
+    has_notes: bool,
+    config: &Config,
+    redact: bool,
+    notes_to: &[String],
+) -> Result<()> {
+    say!("{}", "Dry run: nothing will be pushed".cyan());
+    let notes_refspec = if has_notes {
+        notes_refspec(config, redact)?
+    } else {
+        git::notes_ref_full()
+    };
+
+    let atomic_args = if has_notes && !push.selects_refs() && !push.forces() {
+        push.with_refspecs(&[&notes_refspec], None)
+    } else {
+        None
+    };
+    let atomic = atomic_args.is_some();
+    let mut code = atomic_args.unwrap_or_else(|| {
+        let mut plain = vec!["push".to_string()];
+        plain.extend_from_slice(args);
+        plain
+    });
+    code.insert(1, "--dry-run".to_string());
+    if !git::run_git_status(&code)?.success() {
+        anyhow::bail!(
+            ErrorCode::PushRejected.error("git push --dry-run failed; see its output above")
+        );
+    }
+
+    let mut notes_pushed_to = Vec::new();
+    if has_notes {
+        let mut targets = vec![remote.to_string()];
+        targets.extend(notes_to.iter().filter(|r| *r != remote).cloned());
+        for target in targets {
+            // The atomic push above already covered the main remote's notes
+            if !(atomic && target == remote) {
+                let notes = ["push", "--dry-run", &target, &notes_refspec].map(String::from);
+                if !git::run_git_status(&notes)?.success() {
+                    say!(
+                        "{}",
+                        format!("Warning: notes would not be pushed to {}", target).yellow()
+                    );
+                    continue;
+                }
+            }
+            notes_pushed_to.push(target);
+        }
+    }
+
+    output::emit(
+        "push",
+        &PushResult {
+            remote: remote.to_string(),
+            atomic,
+            redacted: redact && has_notes,
+            notes_pushed_to,
+            dry_run: true,
+        },
+    )
+}
+
+/// Push notes alone to `remote`, warning rather than failing
+fn push_notes(remote: &str, refspec: &str, redact: bool) -> bool {
+    match git::run_git_cmd(&["push", remote, refspec], None) {
+        Ok(_) => {
+            record_push(remote, redact);
+            true
+        }
+        Err(e) => {
+            say!(
+                "{}",
+                format!("Warning: Failed to push notes to {}: {}", remote, e).yellow()
+            );
+            false
+        }
+    }
+}
+
+fn record_push(remote: &str, redact: bool) {
+    let detail = if redact {
+        format!("remote: {} (redacted)", remote)
+    } else {
+        format!("remote: {}", remote)
+    };
+    audit::record(AuditAction::NotesPush, None, Some(detail));
 }
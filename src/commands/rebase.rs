@@ -1,66 +1,111 @@
-use crate::git;
+use crate::audit::{self, AuditAction};
+use crate::errors::ErrorCode;
+use crate::git::{self, Operation};
+use crate::hooks::{self, HookEvent};
 use crate::merge;
+use crate::status;
 use anyhow::Result;
 use colored::*;
 
+/// Whether the rebase asked for, or the one being continued, is interactive
+fn is_interactive(args: &[String]) -> bool {
+    args.iter()
+        .any(|a| matches!(a.as_str(), "-i" | "--interactive" | "--edit-todo"))
+        || git::git_path_exists("rebase-merge/interactive", None)
+}
+
 pub fn run(args: &[String]) -> Result<()> {
-    println!("{}", "Rebasing with Gip...".cyan());
+    status!("{}", "Rebasing with Gip...".cyan());
 
     // 1. Run git rebase
     let mut git_args = vec!["rebase".to_string()];
     git_args.extend_from_slice(args);
 
-    let status = std::process::Command::new("git").args(&git_args).status()?;
+    // The todo list and reworded messages are edited in git's own editor,
+    // which needs the terminal as it is
+    let status = if is_interactive(args) {
+        git::run_git_attached(&git_args)?
+    } else {
+        git::run_git_status(&git_args)?
+    };
+
+    if git::operation_in_progress(None) != Some(Operation::Rebase) {
+        if !status.success() {
+            std::process::exit(status.code().unwrap_or(1));
+        }
+        status!("{}", "Rebase successful".green());
+        merge::cleanup_conflict_backups()?;
+        return Ok(());
+    }
 
-    if status.success() {
-        println!("{}", "Rebase successful".green());
+    // `edit`, `break` and failed `exec` lines stop an interactive rebase
+    // without conflicts; there is nothing to enrich
+    if merge::get_conflicted_files()?.is_empty() {
+        println!(
+            "{}",
+            "Rebase stopped without conflicts. When you're done, run: gip rebase --continue"
+                .yellow()
+        );
+        if !status.success() {
+            std::process::exit(status.code().unwrap_or(1));
+        }
         return Ok(());
     }
 
-    // 2. If failed, check for conflicts
+    // 2. Stopped on a conflict
     println!(
         "{}",
         "Rebase conflict detected. Enriching markers...".yellow()
     );
 
-    // In rebase:
-    // HEAD is the commit being replayed (theirs in merge terms, but ours in rebase terms?)
-    // REBASE_HEAD is the commit we are rebasing onto?
-    // Actually:
-    // "ours" (HEAD) is the upstream we are rebasing ONTO.
-    // "theirs" is the commit being applied.
-    // But git conflict markers might be swapped depending on rebase type.
-
-    // Let's try to get REBASE_HEAD and HEAD.
-    // During rebase, HEAD is detached at the commit we are rebasing onto (upstream).
-    // REBASE_HEAD is the commit being applied.
-
-    let ours_sha = git::get_current_commit()?; // Upstream
-    let theirs_sha = match git::run_git_cmd(&["rev-parse", "REBASE_HEAD"], None) {
-        Ok(sha) => sha,
-        Err(_) => {
-            // Maybe interactive rebase or something else?
-            // Try to find stopped commit.
-            println!(
-                "{}",
-                "Could not determine REBASE_HEAD. Skipping enrichment.".red()
-            );
-            std::process::exit(status.code().unwrap_or(1));
-        }
+    // A rebase detaches HEAD at upstream (plus the commits already replayed)
+    // and applies your commits onto it one by one. Git's markers show HEAD
+    // first and REBASE_HEAD, the commit being replayed, second; enrichment
+    // labels them as upstream and your commit.
+    let ours_sha = git::get_current_commit()?;
+    // A merge recreated by --rebase-merges stops with MERGE_HEAD instead
+    let Some(theirs_sha) = merge::other_head() else {
+        println!(
+            "{}",
+            "Could not determine the commit being replayed. Skipping enrichment.".red()
+        );
+        std::process::exit(status.code().unwrap_or(1));
     };
 
-    // Note: In rebase, "ours" is upstream, "theirs" is the patch.
-    // But conflict markers usually show HEAD as upstream.
     let count = merge::enrich_all_conflicts(&ours_sha, &theirs_sha)?;
 
     if count > 0 {
-        println!(
+        status!(
             "{}",
             format!("✓ Enriched {} conflicted files with context", count).green()
         );
+        println!(
+            "  Unenriched copies are in .gip/{}/ until the conflict is resolved",
+            merge::CONFLICT_BACKUP_DIR
+        );
     } else {
         println!("{}", "No context available for conflicts".yellow());
     }
 
-    std::process::exit(status.code().unwrap_or(1));
+    audit::record(
+        AuditAction::Enrichment,
+        Some(&theirs_sha),
+        Some(format!("rebase: enriched {} files", count)),
+    );
+
+    hooks::run(
+        &git::get_gip_dir()?,
+        HookEvent::PostEnrich,
+        &serde_json::json!({
+            "operation": "rebase",
+            "ours": ours_sha,
+            "theirs": theirs_sha,
+            "enrichedFiles": count,
+        }),
+    )?;
+
+    anyhow::bail!(ErrorCode::MergeConflict.error(format!(
+        "Rebase stopped on conflicts ({} file(s) enriched). Resolve them, then run: gip rebase --continue",
+        count
+    )))
 }
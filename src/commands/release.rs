@@ -0,0 +1,56 @@
+use crate::git;
+use crate::packages::PackageMap;
+use crate::query::HistoryFilter;
+use crate::release::{self, ReleaseNotes};
+use anyhow::Result;
+use colored::*;
+
+pub fn run(from: Option<String>, version: Option<String>, dry_run: bool) -> Result<()> {
+    let previous = from.or_else(|| {
+        git::run_git_cmd(&["describe", "--tags", "--abbrev=0"], None)
+            .ok()
+            .filter(|t| !t.is_empty())
+    });
+    let range = match previous {
+        Some(ref tag) => format!("{}..HEAD", tag),
+        None => "HEAD".to_string(),
+    };
+
+    let packages = PackageMap::load(&git::get_gip_dir()?)?;
+    let mut manifests = HistoryFilter::default().collect(&range, &packages, None)?;
+    manifests.reverse();
+
+    let bump = release::suggest_bump(&manifests);
+    let version = match version {
+        Some(v) => v,
+        None => release::next_version(previous.as_deref(), bump)?,
+    };
+
+    eprintln!(
+        "{}",
+        format!(
+            "Release {} ({} manifests since {}, suggested bump: {})",
+            version,
+            manifests.len(),
+            previous.as_deref().unwrap_or("the first commit"),
+            bump
+        )
+        .cyan()
+    );
+
+    let notes = ReleaseNotes::build(version.clone(), previous, &manifests, &packages).to_markdown();
+    print!("{}", notes);
+
+    if dry_run {
+        return Ok(());
+    }
+
+    // Verbatim cleanup keeps the Markdown headings, which git would strip as comments
+    git::run_git_cmd(
+        &["tag", "-a", &version, "--cleanup=verbatim", "-m", &notes],
+        None,
+    )?;
+    eprintln!("{}", format!("✓ Created annotated tag {}", version).green());
+
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use crate::backup;
+use crate::git;
+use crate::manifest;
+use crate::remap;
+use anyhow::Result;
+use colored::*;
+use std::collections::HashSet;
+
+pub fn run(rev: &str, dry_run: bool) -> Result<()> {
+    println!(
+        "{}",
+        format!("Matching rewritten commits in {}...", rev).cyan()
+    );
+
+    let noted_shas: Vec<String> = git::list_noted_commits(None)?
+        .into_iter()
+        // Originals may have been pruned after the rewrite
+        .filter(|sha| git::has_commit(sha, None))
+        .collect();
+    if noted_shas.is_empty() {
+        println!("No commits with gip notes to remap from");
+        return Ok(());
+    }
+
+    let mut noted_args = vec!["--no-walk"];
+    noted_args.extend(noted_shas.iter().map(|s| s.as_str()));
+    let noted = remap::collect_keys(&noted_args, None)?;
+
+    let has_note: HashSet<&str> = noted_shas.iter().map(|s| s.as_str()).collect();
+    let candidates: Vec<_> = remap::collect_keys(&[rev], None)?
+        .into_iter()
+        .filter(|(sha, _)| !has_note.contains(sha.as_str()))
+        .collect();
+
+    let remaps = remap::match_commits(&noted, &candidates);
+    if remaps.is_empty() {
+        println!("No rewritten commits found");
+        return Ok(());
+    }
+
+    if !dry_run {
+        backup::snapshot_before("remapping")?;
+    }
+
+    let mut failed = 0;
+    for r in &remaps {
+        let from = &r.from[..8];
+        let to = &r.to[..8];
+
+        if dry_run {
+            println!("Would copy {} → {} ({})", from, to, r.by);
+            continue;
+        }
+
+        let copied = manifest::load_raw(&r.from, None).and_then(|mut m| {
+            m.commit = r.to.clone();
+            manifest::save(&m, &r.to, None)
+        });
+        match copied {
+            Ok(()) => println!("{} {} → {} ({})", "✓".green(), from, to, r.by),
+            Err(e) => {
+                eprintln!("{}", format!("✗ {} → {}: {:#}", from, to, e).red());
+                failed += 1;
+            }
+        }
+    }
+
+    let verb = if dry_run { "would be copied" } else { "copied" };
+    println!(
+        "\n{} manifest(s) {}, {} failed",
+        remaps.len() - failed,
+        verb,
+        failed
+    );
+
+    if failed > 0 {
+        anyhow::bail!("{} manifest(s) could not be copied", failed);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use crate::errors::ErrorCode;
+use crate::git;
+use crate::merge;
+use crate::resolve::{self, tui};
+use crate::say;
+use anyhow::Result;
+use colored::*;
+use std::io::IsTerminal;
+
+pub fn run(tui: bool, files: &[String]) -> Result<()> {
+    let files = if files.is_empty() {
+        merge::get_conflicted_files()?
+    } else {
+        files.to_vec()
+    };
+
+    let ours_sha = git::get_current_commit()?;
+    let theirs_sha = merge::conflict_other_head();
+    let mut conflicts = Vec::new();
+    for file in files.iter().filter(|f| !f.is_empty()) {
+        if merge::is_binary_conflict(file) {
+            say!(
+                "{} {} is binary; resolve it with git checkout --ours/--theirs",
+                "⚠".yellow(),
+                file
+            );
+            continue;
+        }
+        let conflict = resolve::load(file, &ours_sha, theirs_sha.as_deref())?;
+        if !conflict.hunks.is_empty() {
+            conflicts.push(conflict);
+        }
+    }
+    if conflicts.is_empty() {
+        say!("No conflict hunks to resolve");
+        return Ok(());
+    }
+
+    if !tui {
+        for conflict in &conflicts {
+            say!("{} ({} hunk(s))", conflict.path, conflict.hunks.len());
+        }
+        say!("\nRun `gip resolve --tui` to resolve them interactively.");
+        return Ok(());
+    }
+    if !std::io::stdout().is_terminal() {
+        anyhow::bail!(ErrorCode::Usage.error("gip resolve --tui needs a terminal"));
+    }
+
+    let (conflicts, exit) = tui::run(conflicts)?;
+    if exit == tui::Exit::Discard {
+        say!("No files changed");
+        return Ok(());
+    }
+
+    for conflict in &conflicts {
+        let decided = conflict.decided();
+        if decided == 0 {
+            continue;
+        }
+        if resolve::save(conflict)? {
+            say!("{} {} resolved and staged", "✓".green(), conflict.path);
+        } else {
+            say!(
+                "{} {}: {} of {} hunk(s) resolved, conflicts left in place",
+                "•".yellow(),
+                conflict.path,
+                decided,
+                conflict.hunks.len()
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use crate::backup::{self, BACKUP_PREFIX};
+use crate::git;
+use crate::status;
+use anyhow::Result;
+use colored::*;
+
+pub fn run(backup_name: Option<String>) -> Result<()> {
+    let Some(name) = backup_name else {
+        let backups = backup::list(None)?;
+        if backups.is_empty() {
+            println!("No notes backups yet");
+        } else {
+            println!("{}", "Notes backups (newest first):".bold());
+            for b in backups {
+                println!("  {}", b.trim_start_matches(BACKUP_PREFIX));
+            }
+        }
+        return Ok(());
+    };
+
+    let previous = backup::restore(&name, None)?;
+    status!(
+        "{}",
+        format!(
+            "✓ Restored {} from {}",
+            git::notes_ref_full(),
+            backup::resolve(&name)
+        )
+        .green()
+    );
+    if let Some(previous) = previous {
+        println!(
+            "The replaced notes were saved as {}",
+            previous.trim_start_matches(BACKUP_PREFIX)
+        );
+    }
+
+    Ok(())
+}
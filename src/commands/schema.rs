@@ -0,0 +1,41 @@
+use crate::manifest::schema;
+use crate::status;
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::path::PathBuf;
+
+/// Subcommands of `gip schema`
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum SchemaCommand {
+    /// Print the JSON Schema of the current manifest format
+    Export {
+        /// Write the schema to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+pub fn run(command: SchemaCommand) -> Result<()> {
+    match command {
+        SchemaCommand::Export { output } => export(output),
+    }
+}
+
+fn export(output: Option<PathBuf>) -> Result<()> {
+    let json = serde_json::to_string_pretty(&schema::json_schema())?;
+
+    match output {
+        Some(path) => {
+            fs::write(&path, format!("{}\n", json))
+                .with_context(|| format!("Failed to write schema to {:?}", path))?;
+            status!(
+                "{}",
+                format!("✓ Schema written to {}", path.display()).green()
+            );
+        }
+        None => println!("{}", json),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use crate::commands::{am, commit, rebase};
+use crate::errors::ErrorCode;
+use crate::git::{self, Operation};
+use crate::merge;
+use crate::{say, status};
+use anyhow::Result;
+use colored::*;
+
+/// What `gip continue`, `gip abort` and `gip skip` ask of the operation in progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Continue,
+    Abort,
+    Skip,
+}
+
+impl Step {
+    fn flag(self) -> &'static str {
+        match self {
+            Step::Continue => "--continue",
+            Step::Abort => "--abort",
+            Step::Skip => "--skip",
+        }
+    }
+}
+
+/// Continue, abort or skip whichever merge, rebase, cherry-pick, revert or am
+/// is in progress
+///
+/// Resolved files are checked for leftover context before continuing. Stops
+/// on new conflicts are enriched, and backups are dropped once the operation
+/// is over.
+pub fn run(step: Step) -> Result<()> {
+    let Some(operation) = git::operation_in_progress(None) else {
+        anyhow::bail!(
+            ErrorCode::Usage.error("No merge, rebase, cherry-pick, revert or am is in progress")
+        );
+    };
+    let name = operation.as_str();
+    if operation == Operation::Merge && step == Step::Skip {
+        anyhow::bail!(ErrorCode::Usage.error(
+            "A merge has nothing to skip; finish it with gip continue or drop it with gip abort"
+        ));
+    }
+    if step == Step::Continue {
+        check_leftover_context()?;
+    }
+    let args = vec![step.flag().to_string()];
+    match operation {
+        // These enrich their next stop and clean up themselves
+        Operation::Rebase => return rebase::run(&args),
+        Operation::Am => return am::run(&args),
+        // `gip commit` concludes a merge and records its manifest
+        Operation::Merge if step == Step::Continue => {
+            status!("{}", "Committing the merge...".cyan());
+            commit::run(
+                None,
+                false,
+                false,
+                None,
+                None,
+                false,
+                &[],
+                &["--no-edit".to_string()],
+            )?;
+            merge::cleanup_conflict_backups()?;
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    status!(
+        "{}",
+        format!("Running git {} {}...", name, step.flag()).cyan()
+    );
+    let status = git::run_git_attached(&[name.to_string(), step.flag().to_string()])?;
+    let conflicted =
+        git::operation_in_progress(None).is_some() && !merge::get_conflicted_files()?.is_empty();
+    if !conflicted {
+        merge::cleanup_conflict_backups()?;
+        if !status.success() {
+            std::process::exit(status.code().unwrap_or(1));
+        }
+        status!("{}", format!("✓ {} {}", name, step_done(step)).green());
+        return Ok(());
+    }
+
+    // The next commit of a cherry-pick or revert sequence stopped on conflicts
+    let (Ok(ours_sha), Some(theirs_sha)) = (git::get_current_commit(), merge::other_head()) else {
+        std::process::exit(status.code().unwrap_or(1));
+    };
+    say!("{}", "Conflict detected. Enriching markers...".yellow());
+    let count = merge::enrich_all_conflicts(&ours_sha, &theirs_sha)?;
+    if count > 0 {
+        status!(
+            "{}",
+            format!("✓ Enriched {} conflicted files with context", count).green()
+        );
+    }
+    anyhow::bail!(ErrorCode::MergeConflict.error(format!(
+        "{} stopped on conflicts ({} file(s) enriched). Resolve them, then run: gip continue",
+        name, count
+    )))
+}
+
+fn step_done(step: Step) -> &'static str {
+    match step {
+        Step::Continue => "continued",
+        Step::Abort => "aborted",
+        Step::Skip => "skipped",
+    }
+}
+
+/// Refuse to continue while resolved files still carry enrichment
+fn check_leftover_context() -> Result<()> {
+    let leftover = merge::leftover_context(&git::get_gip_dir()?, None)?;
+    if leftover.is_empty() {
+        return Ok(());
+    }
+    for file in &leftover {
+        eprintln!("  {}", file);
+    }
+    anyhow::bail!(ErrorCode::MergeConflict.error(format!(
+        "{} staged file(s) still contain Gip context lines ('{}'); remove them and stage the files again",
+        leftover.len(),
+        merge::CONTEXT_PREFIX.trim_end()
+    )))
+}
@@ -0,0 +1,130 @@
+use crate::blame;
+use crate::git;
+use crate::manifest::{self, Entry};
+use crate::show;
+use anyhow::Result;
+use colored::*;
+use std::collections::{HashMap, HashSet};
+
+pub fn run(commit: &str, args: &[String]) -> Result<()> {
+    let sha = git::run_git_cmd(
+        &["rev-parse", "--verify", &format!("{}^{{commit}}", commit)],
+        None,
+    )?;
+
+    let mut git_args = vec!["show", "--no-color"];
+    git_args.extend(args.iter().map(|s| s.as_str()));
+    git_args.push(&sha);
+    let output = git::run_git_cmd(&git_args, None)?;
+
+    let manifest = manifest::load(&sha, None).ok();
+    if manifest.is_none() {
+        eprintln!(
+            "{}",
+            format!("No manifest recorded for {}", &sha[..8]).yellow()
+        );
+    }
+
+    // Annotations to print after each hunk header, keyed by output line
+    let mut notes: HashMap<usize, Vec<&Entry>> = HashMap::new();
+    let mut shown: HashSet<(&str, &str, &str)> = HashSet::new();
+    if let Some(ref manifest) = manifest {
+        let gip_dir = git::get_gip_dir().ok();
+        let mut sources: HashMap<String, Option<String>> = HashMap::new();
+
+        for hunk in show::parse_hunks(&output) {
+            let source = sources
+                .entry(hunk.file.clone())
+                .or_insert_with(|| blame::source_at(&sha, &hunk.file, None));
+            let symbol = source.as_deref().and_then(|s| {
+                blame::enclosing_symbol(s, &hunk.file, hunk.first_changed, gip_dir.as_deref())
+            });
+
+            let entries: Vec<&Entry> = show::entries_for_hunk(manifest, &hunk, symbol.as_deref())
+                .into_iter()
+                .filter(|e| shown.insert(key(e)))
+                .collect();
+            if !entries.is_empty() {
+                notes.insert(hunk.header_line, entries);
+            }
+        }
+    }
+
+    for (n, line) in output.lines().enumerate() {
+        print_diff_line(line);
+        if let Some(entries) = notes.get(&n) {
+            for entry in entries {
+                print_entry(entry);
+            }
+        }
+    }
+
+    if let Some(ref manifest) = manifest {
+        let unmatched: Vec<&Entry> = manifest
+            .entries
+            .iter()
+            .filter(|e| !shown.contains(&key(e)))
+            .collect();
+        if !unmatched.is_empty() {
+            println!();
+            println!("{}", "Context not matched to a hunk:".bold());
+            for entry in unmatched {
+                print_entry(entry);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn key(entry: &Entry) -> (&str, &str, &str) {
+    (
+        &entry.anchor.file,
+        &entry.anchor.symbol,
+        &entry.anchor.hunk_id,
+    )
+}
+
+fn print_diff_line(line: &str) {
+    if line.starts_with("+++") || line.starts_with("---") || line.starts_with("diff --git") {
+        println!("{}", line.bold());
+    } else if line.starts_with("@@") {
+        println!("{}", line.cyan());
+    } else if line.starts_with('+') {
+        println!("{}", line.green());
+    } else if line.starts_with('-') {
+        println!("{}", line.red());
+    } else {
+        println!("{}", line);
+    }
+}
+
+fn print_entry(entry: &Entry) {
+    let gutter = "┃".magenta();
+    let classes = if entry.behavior_class.is_empty() {
+        String::new()
+    } else {
+        format!(" [{}]", entry.behavior_class.join(", "))
+    };
+    println!(
+        "{} {}{} {}",
+        gutter,
+        entry.anchor.symbol.yellow(),
+        classes.blue(),
+        entry.rationale
+    );
+
+    let contract = &entry.contract;
+    for (label, items) in [
+        ("pre", &contract.preconditions),
+        ("post", &contract.postconditions),
+        ("errors", &contract.error_model),
+    ] {
+        if !items.is_empty() {
+            println!("{}   {}: {}", gutter, label, items.join("; "));
+        }
+    }
+    if entry.compatibility.as_ref().is_some_and(|c| c.breaking) {
+        println!("{}   {}", gutter, "breaking change".red());
+    }
+}
@@ -0,0 +1,131 @@
+use crate::audit::{self, AuditAction};
+use crate::errors::ErrorCode;
+use crate::git;
+use crate::merge;
+use crate::stash;
+use crate::status;
+use anyhow::Result;
+use colored::*;
+use std::path::Path;
+
+pub fn run(args: &[String]) -> Result<()> {
+    let gip_dir = git::get_gip_dir()?;
+    let subcommand = args
+        .first()
+        .filter(|a| !a.starts_with('-'))
+        .map(|a| a.as_str())
+        .unwrap_or("push");
+
+    match subcommand {
+        "push" | "save" => push(&gip_dir, args),
+        "pop" | "apply" => apply(&gip_dir, subcommand, &args[1..]),
+        _ => {
+            let mut git_args = vec!["stash".to_string()];
+            git_args.extend_from_slice(args);
+            crate::commands::passthrough::run(&git_args)?;
+            stash::prune(&gip_dir, &live_stashes())?;
+            Ok(())
+        }
+    }
+}
+
+fn push(gip_dir: &Path, args: &[String]) -> Result<()> {
+    let before = top_stash();
+
+    let mut git_args = vec!["stash".to_string()];
+    git_args.extend_from_slice(args);
+    crate::commands::passthrough::run(&git_args)?;
+
+    // Nothing to stash leaves refs/stash where it was
+    let Some(sha) = top_stash().filter(|sha| Some(sha) != before.as_ref()) else {
+        return Ok(());
+    };
+    if stash::save(gip_dir, &sha)?.is_some() {
+        status!("{}", "✓ Pending manifest stashed with the changes".green());
+    }
+    Ok(())
+}
+
+fn apply(gip_dir: &Path, subcommand: &str, args: &[String]) -> Result<()> {
+    let stash_ref = match args.iter().find(|a| !a.starts_with('-')) {
+        Some(n) if n.chars().all(|c| c.is_ascii_digit()) => format!("stash@{{{}}}", n),
+        Some(r) => r.clone(),
+        None => "stash@{0}".to_string(),
+    };
+    let stash_sha = git::run_git_cmd(&["rev-parse", "-q", "--verify", &stash_ref], None).ok();
+
+    let mut git_args = vec!["stash".to_string(), subcommand.to_string()];
+    git_args.extend_from_slice(args);
+    let status = git::run_git_status(&git_args)?;
+
+    let Some(stash_sha) = stash_sha else {
+        std::process::exit(status.code().unwrap_or(1));
+    };
+
+    let conflicted =
+        !git::run_git_cmd(&["diff", "--name-only", "--diff-filter=U"], None)?.is_empty();
+    if conflicted {
+        println!(
+            "{}",
+            "Stash conflicts with the working tree. Enriching markers...".yellow()
+        );
+        let ours_sha = git::get_current_commit()?;
+        let ours = crate::manifest::load(&ours_sha, None).ok();
+        let count = merge::enrich_all_conflicts_with(
+            &ours_sha,
+            ours,
+            &stash_sha,
+            stash::load(gip_dir, &stash_sha),
+        )?;
+
+        if count > 0 {
+            status!(
+                "{}",
+                format!("✓ Enriched {} conflicted files with context", count).green()
+            );
+        } else {
+            println!("{}", "No context available for conflicts".yellow());
+        }
+
+        audit::record(
+            AuditAction::Enrichment,
+            Some(&stash_sha),
+            Some(format!("stash {}: enriched {} files", subcommand, count)),
+        );
+    }
+
+    // A conflicting apply still leaves the stashed changes in the tree, so the
+    // manifest describing them comes back either way
+    if status.success() || conflicted {
+        match stash::restore(gip_dir, &stash_sha) {
+            Ok(Some(path)) => println!(
+                "{}",
+                format!("✓ Restored stashed manifest to {}", path.display()).green()
+            ),
+            Ok(None) => {}
+            Err(e) => eprintln!("{}", format!("Warning: {:#}", e).yellow()),
+        }
+        stash::prune(gip_dir, &live_stashes())?;
+    }
+
+    if conflicted {
+        anyhow::bail!(ErrorCode::MergeConflict.error(format!(
+            "stash {} stopped on conflicts. Resolve them; the stash is kept",
+            subcommand
+        )));
+    }
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+fn top_stash() -> Option<String> {
+    git::run_git_cmd(&["rev-parse", "-q", "--verify", "refs/stash"], None).ok()
+}
+
+fn live_stashes() -> Vec<String> {
+    git::run_git_cmd(&["stash", "list", "--format=%H"], None)
+        .map(|out| out.lines().map(|l| l.to_string()).collect())
+        .unwrap_or_default()
+}
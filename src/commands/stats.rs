@@ -0,0 +1,142 @@
+use crate::blame;
+use crate::config::Config;
+use crate::git;
+use crate::manifest::Manifest;
+use crate::merge;
+use crate::output;
+use crate::packages::PackageMap;
+use crate::quality;
+use crate::query::HistoryFilter;
+use crate::stats::HistoryStats;
+use crate::tokens::{self, FileTokens, TokenStats};
+use anyhow::Result;
+use colored::*;
+use std::collections::HashMap;
+use std::fs;
+
+pub fn run(
+    rev: Option<String>,
+    filter: HistoryFilter,
+    top: usize,
+    with_tokens: bool,
+) -> Result<()> {
+    let rev = rev.unwrap_or_else(|| "HEAD".to_string());
+    let packages = PackageMap::load(&git::get_gip_dir()?)?;
+
+    let commits = filter.commits(&rev, None)?;
+    let manifests = filter.load_noted(&commits, &packages, None)?;
+    let min_score = Config::load(&git::get_gip_dir()?)?.quality.min_score;
+    let mut stats = HistoryStats::compute(commits.len(), &manifests, top)
+        .with_quality(&quality_scores(&manifests), min_score);
+    if with_tokens {
+        let mut token_stats = TokenStats::compute(&manifests, top);
+        token_stats.conflicts = conflict_tokens()?;
+        stats = stats.with_tokens(token_stats);
+    }
+
+    if output::is_human() {
+        print_stats(&stats);
+    }
+    output::emit("stats", &stats)
+}
+
+/// Score each manifest against the files as they were at its commit
+fn quality_scores(manifests: &[Manifest]) -> Vec<u32> {
+    let mut sources: HashMap<(String, String), Option<String>> = HashMap::new();
+    manifests
+        .iter()
+        .map(|m| {
+            quality::score(m, |anchor| {
+                sources
+                    .entry((m.commit.clone(), anchor.file.clone()))
+                    .or_insert_with(|| blame::source_at(&m.commit, &anchor.file, None))
+                    .clone()
+            })
+            .score
+        })
+        .collect()
+}
+
+/// Tokens the Gip context lines add to each conflicted file
+fn conflict_tokens() -> Result<Vec<FileTokens>> {
+    Ok(merge::get_conflicted_files()?
+        .into_iter()
+        .filter_map(|file| {
+            let content = fs::read_to_string(&file).ok()?;
+            Some(FileTokens {
+                tokens: tokens::enrichment_cost(&content),
+                file,
+            })
+        })
+        .collect())
+}
+
+fn print_stats(stats: &HistoryStats) {
+    println!("{}", "Gip intent statistics".cyan().bold());
+    println!(
+        "  Coverage:        {}/{} commits ({:.1}%)",
+        stats.commits_with_manifest, stats.total_commits, stats.coverage_pct
+    );
+    println!("  Entries:         {}", stats.total_entries);
+    println!(
+        "  Breaking rate:   {} commits ({:.1}%)",
+        stats.breaking_commits, stats.breaking_rate_pct
+    );
+    println!("  Avg rationale:   {:.1} chars", stats.avg_rationale_len);
+    println!("  Avg quality:     {:.1}/100", stats.avg_quality_score);
+    if stats.below_min_quality > 0 {
+        println!(
+            "  Below minimum:   {} manifests",
+            stats.below_min_quality.to_string().yellow()
+        );
+    }
+
+    if !stats.behavior_classes.is_empty() {
+        println!();
+        println!("{}", "Behavior classes:".bold());
+        for (class, count) in &stats.behavior_classes {
+            println!("  {:<14} {}", class.blue(), count);
+        }
+    }
+
+    if !stats.top_symbols.is_empty() {
+        println!();
+        println!("{}", "Most changed symbols:".bold());
+        for churn in &stats.top_symbols {
+            println!(
+                "  {:>4}  {} ({})",
+                churn.changes,
+                churn.symbol.yellow(),
+                churn.file
+            );
+        }
+    }
+
+    if let Some(ref tokens) = stats.tokens {
+        print_tokens(tokens);
+    }
+}
+
+fn print_tokens(tokens: &TokenStats) {
+    println!();
+    println!("{}", "Context tokens:".bold());
+    println!(
+        "  Total:           ~{} ({:.0} per commit)",
+        tokens.total, tokens.avg_per_commit
+    );
+    for commit in &tokens.commits {
+        println!(
+            "  {:>6}  {}",
+            format!("~{}", commit.tokens),
+            &commit.commit[..commit.commit.len().min(8)]
+        );
+    }
+
+    if !tokens.conflicts.is_empty() {
+        println!();
+        println!("{}", "Enrichment in conflicted files:".bold());
+        for file in &tokens.conflicts {
+            println!("  {:>6}  {}", format!("~{}", file.tokens), file.file);
+        }
+    }
+}
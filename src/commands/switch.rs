@@ -0,0 +1,6 @@
+use crate::commands::checkout;
+use anyhow::Result;
+
+pub fn run(args: &[String]) -> Result<()> {
+    checkout::run_merging("switch", args)
+}
@@ -0,0 +1,67 @@
+use crate::git;
+use crate::manifest::{self, ManifestFormat};
+use crate::status;
+use crate::template;
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+
+/// Subcommands of `gip template`
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum TemplateCommand {
+    /// Open the repository's manifest template in your editor
+    Edit,
+    /// Remove the custom template and go back to the machine or built-in one
+    Reset,
+    /// Print the template with variables filled in
+    Show {
+        /// Pre-fill entries with this behavior class and add its fields
+        #[arg(long)]
+        class: Option<String>,
+    },
+}
+
+pub fn run(command: TemplateCommand) -> Result<()> {
+    let gip_dir = git::get_gip_dir()?;
+
+    match command {
+        TemplateCommand::Edit => {
+            let path = template::custom_template_path(&gip_dir);
+            if !path.exists() {
+                fs::create_dir_all(path.parent().expect("template path has a parent"))?;
+                fs::write(&path, template::load_source(&gip_dir)?)?;
+            }
+            git::run_editor(&path)?;
+
+            status!(
+                "{}",
+                format!("✓ Template saved to {}", path.display()).green()
+            );
+        }
+        TemplateCommand::Reset => {
+            let path = template::custom_template_path(&gip_dir);
+            if path.exists() {
+                fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+                status!("{}", "✓ Custom template removed".green());
+            } else {
+                println!("{}", "Already using the built-in template".yellow());
+            }
+        }
+        TemplateCommand::Show { class: None } => {
+            print!("{}", template::render_for_repo(&gip_dir)?)
+        }
+        TemplateCommand::Show { class: Some(class) } => {
+            let rendered = template::render_for_repo(&gip_dir)?;
+            let mut manifest = ManifestFormat::Toon
+                .parse_manifest(&rendered)
+                .context("Template is not a valid manifest")?;
+            for entry in &mut manifest.entries {
+                entry.behavior_class = vec![class.clone()];
+                *entry = template::apply_fragments(entry, &gip_dir)?;
+            }
+            print!("{}", manifest::serialize_manifest_toon(&manifest)?);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use crate::git;
+use crate::trust;
+use crate::{say, status};
+use anyhow::Result;
+use colored::*;
+
+/// Trust the current repository, or with `remove` stop trusting it
+pub fn run(remove: bool) -> Result<()> {
+    let root = git::get_repo_root()?;
+    if remove {
+        if trust::untrust(&root)? {
+            status!(
+                "{}",
+                format!("✓ No longer trusting {}", root.display()).green()
+            );
+        } else {
+            say!("{} is not trusted", root.display());
+        }
+        return Ok(());
+    }
+
+    if trust::trust(&root)? {
+        status!("{}", format!("✓ Trusting {}", root.display()).green());
+        say!("  Its tracked .gip/config.toml settings, hooks and analyzers now apply");
+    } else {
+        say!("{} is already trusted", root.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use crate::atomic;
+use crate::git;
+use crate::merge;
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::path::Path;
+
+pub fn run(files: &[String]) -> Result<()> {
+    let files = if files.is_empty() {
+        merge::get_conflicted_files()?
+    } else {
+        files.to_vec()
+    };
+    if files.is_empty() {
+        println!("No conflicted files");
+        return Ok(());
+    }
+
+    let gip_dir = git::get_gip_dir()?;
+    let mut restored = 0;
+
+    for file in &files {
+        let path = Path::new(file);
+        let content =
+            fs::read_to_string(path).with_context(|| format!("Failed to read {}", file))?;
+
+        let (stripped, removed) = merge::strip_context(&content);
+        if removed == 0 {
+            println!("{} has no Gip context", file);
+            continue;
+        }
+
+        // Enrichment normalizes line endings; the backup has git's exact bytes
+        let backup = merge::conflict_backup_path(&gip_dir, file);
+        let exact = fs::read_to_string(&backup)
+            .ok()
+            .filter(|original| same_lines(original, &stripped));
+
+        atomic::write(path, exact.as_deref().unwrap_or(&stripped))
+            .with_context(|| format!("Failed to write {}", file))?;
+        println!(
+            "{} {} ({} context line(s) removed)",
+            "✓".green(),
+            file,
+            removed
+        );
+        restored += 1;
+    }
+
+    println!(
+        "\nRestored plain git conflict markers in {} file(s)",
+        restored
+    );
+    Ok(())
+}
+
+/// Whether two texts differ at most in line endings
+fn same_lines(a: &str, b: &str) -> bool {
+    a.lines().eq(b.lines())
+}
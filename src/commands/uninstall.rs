@@ -0,0 +1,242 @@
+use crate::errors::ErrorCode;
+use crate::git;
+use crate::mergetool;
+use crate::setup;
+use crate::uninstall::{self, HookCleanup};
+use crate::{say, status};
+use anyhow::{Context, Result};
+use colored::*;
+use std::fs;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+
+pub fn run(yes: bool, notes: bool, dry_run: bool) -> Result<()> {
+    if !git::is_git_repo() {
+        anyhow::bail!(ErrorCode::NotARepository.error("Not a git repository"));
+    }
+    status!("{}", "Removing Gip from this repository...".cyan());
+    let root = git::get_repo_root()?;
+    let mut removed = 0;
+    let mut report = |what: String| {
+        removed += 1;
+        if dry_run {
+            say!("Would remove {}", what);
+        } else {
+            say!("{} Removed {}", "✓".green(), what);
+        }
+    };
+
+    // gip's blocks in hooks, keeping whatever else they do
+    let hooks = uninstall::hooks_dir(None)?;
+    let mut hook_files: Vec<_> = fs::read_dir(&hooks)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    hook_files.sort();
+    for path in hook_files.iter().filter(|p| p.is_file()) {
+        if path.extension().is_some_and(|e| e == "sample") {
+            continue;
+        }
+        let Some(cleanup) = fs::read_to_string(path)
+            .ok()
+            .and_then(|s| uninstall::strip_hook(&s))
+        else {
+            continue;
+        };
+        let name = relative(&root, path);
+        let stripped = match cleanup {
+            HookCleanup::Stripped(stripped) => stripped,
+            HookCleanup::Manual => {
+                say!(
+                    "{}",
+                    format!(
+                        "Warning: hook {} runs gip outside a `{}` block; left as is, edit it by hand",
+                        name,
+                        uninstall::HOOK_BEGIN
+                    )
+                    .yellow()
+                );
+                continue;
+            }
+        };
+        if uninstall::is_empty_script(&stripped) {
+            if !dry_run {
+                fs::remove_file(path).with_context(|| format!("Failed to remove {}", name))?;
+            }
+            report(format!("hook {}", name));
+        } else {
+            if !dry_run {
+                fs::write(path, stripped).with_context(|| format!("Failed to write {}", name))?;
+            }
+            report(format!("gip commands from hook {}", name));
+        }
+    }
+
+    // Merge tool
+    let registered = git::run_git_cmd(
+        &[
+            "config",
+            "--local",
+            "--get",
+            &format!("mergetool.{}.cmd", mergetool::TOOL_NAME),
+        ],
+        None,
+    )
+    .is_ok();
+    if registered && (dry_run || mergetool::uninstall()?) {
+        report(format!(
+            "the `{}` merge tool from .git/config",
+            mergetool::TOOL_NAME
+        ));
+    }
+
+    // Attributes, in the tree and in .git/info
+    let info_attributes = git::run_git_cmd(&["rev-parse", "--git-path", "info/attributes"], None)
+        .map(std::path::PathBuf::from)?;
+    for path in [root.join(".gitattributes"), info_attributes] {
+        let Some(stripped) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|s| uninstall::strip_gitattributes(&s))
+        else {
+            continue;
+        };
+        let name = relative(&root, &path);
+        if !dry_run {
+            fs::write(&path, stripped).with_context(|| format!("Failed to write {}", name))?;
+        }
+        report(format!("gip attributes from {}", name));
+    }
+
+    // Notes refspecs on every remote
+    for remote in git::run_git_cmd(&["remote"], None)
+        .unwrap_or_default()
+        .lines()
+    {
+        let configured = if dry_run {
+            !git::configured_notes_refspecs(remote, None).is_empty()
+        } else {
+            git::unconfigure_notes_fetch(remote, None)?
+        };
+        if configured {
+            report(format!("the notes refspec from remote.{}.fetch", remote));
+        }
+    }
+
+    // The repository's .gitignore, and the file itself if only gip needed it
+    let gitignore = root.join(".gitignore");
+    if let Some(stripped) = fs::read_to_string(&gitignore)
+        .ok()
+        .and_then(|s| setup::unignore_gip(&s))
+    {
+        if !dry_run {
+            if stripped.is_empty() {
+                fs::remove_file(&gitignore)?;
+            } else {
+                fs::write(&gitignore, stripped)?;
+            }
+        }
+        report(".gip from .gitignore".to_string());
+    }
+
+    // State: pending manifests, caches, backups, the audit log
+    let gip_dir = git::get_gip_dir()?;
+    let mut kept = Vec::new();
+    if gip_dir.exists() {
+        let tracked = git::run_git_cmd(&["ls-files", "--", ".gip"], Some(&root))
+            .unwrap_or_default()
+            .lines()
+            .count();
+        if dry_run {
+            report(".gip/ (after asking)".to_string());
+        } else if confirm(
+            "Delete .gip/ with pending manifests, caches, backups and the audit log?",
+            yes,
+        )? {
+            fs::remove_dir_all(&gip_dir).context("Failed to remove .gip/")?;
+            report(".gip/".to_string());
+            if tracked > 0 {
+                say!(
+                    "  {} tracked file(s) of the shared layout are now deleted; commit that to remove gip for everyone",
+                    tracked
+                );
+            }
+        } else {
+            kept.push(".gip/");
+        }
+    }
+
+    // Manifests themselves, only when asked for
+    if notes {
+        let refs = uninstall::manifest_refs(None)?;
+        let count = git::list_noted_commits(None).map(|c| c.len()).unwrap_or(0);
+        if refs.is_empty() {
+            say!("No gip notes to remove");
+        } else if dry_run {
+            report(format!("{} ({} manifest(s))", refs.join(", "), count));
+        } else if confirm(
+            &format!(
+                "Delete the manifests of {} commit(s) and their backups? They can't be recovered unless a remote has them.",
+                count
+            ),
+            yes,
+        )? {
+            for r in &refs {
+                git::run_git_cmd(&["update-ref", "-d", r], None)?;
+            }
+            report(format!("{} ({} manifest(s))", refs.join(", "), count));
+        } else {
+            kept.push("the notes");
+        }
+    }
+
+    if dry_run {
+        say!("\n{} item(s) would be removed", removed);
+        return Ok(());
+    }
+    for what in &kept {
+        say!(
+            "{}",
+            format!("Kept {}; rerun with --yes to remove it", what).yellow()
+        );
+    }
+    if removed == 0 && kept.is_empty() {
+        status!("{}", "Nothing of Gip found in this repository".green());
+    } else {
+        status!("{}", "✓ Gip removed from this repository".green());
+        if !notes && !uninstall::manifest_refs(None)?.is_empty() {
+            say!(
+                "  Manifests stay in {}; `gip uninstall --notes` removes them too",
+                git::notes_ref_full()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Ask a yes/no question on the terminal; without one, only `--yes` agrees
+fn confirm(question: &str, yes: bool) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    eprint!("{} [y/N] ", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    let path = if path.is_relative() {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    } else {
+        path.to_path_buf()
+    };
+    path.strip_prefix(root)
+        .unwrap_or(&path)
+        .display()
+        .to_string()
+}
@@ -0,0 +1,130 @@
+use crate::atomic;
+use crate::commands::commit;
+use crate::git;
+use crate::lock::PendingLock;
+use crate::manifest::BEHAVIOR_FEATURE;
+use crate::stage;
+use crate::template;
+use crate::watch;
+use crate::{say, status};
+use anyhow::{Context, Result};
+use colored::*;
+use notify::{RecursiveMode, Watcher};
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Quiet time after a change before syncing, so a burst of saves syncs once
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// The tree git diffs against before the first commit
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+pub fn run(once: bool) -> Result<()> {
+    let root = git::get_repo_root()?;
+    let gip_dir = root.join(".gip");
+    let class = template::TemplateVars::detect()
+        .branch
+        .as_deref()
+        .and_then(template::class_from_branch)
+        .unwrap_or(BEHAVIOR_FEATURE)
+        .to_string();
+
+    let mut stale = Vec::new();
+    sync(&root, &gip_dir, &class, &mut stale)?;
+    if once {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).context("Failed to watch files")?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch {}", root.display()))?;
+    status!(
+        "{}",
+        format!(
+            "Watching {} and keeping the pending manifest in sync; Ctrl-C to stop",
+            root.display()
+        )
+        .cyan()
+    );
+
+    while let Ok(event) = rx.recv() {
+        if !is_relevant(&event, &root) {
+            continue;
+        }
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+        if let Err(e) = sync(&root, &gip_dir, &class, &mut stale) {
+            eprintln!("{}", format!("Warning: {:#}", e).yellow());
+        }
+    }
+    Ok(())
+}
+
+/// Whether a file event can change the diff against `HEAD`
+///
+/// gip's own writes to `.gip/` are skipped, and of `.git/` only moves of
+/// `HEAD` and branches count.
+fn is_relevant(event: &notify::Result<notify::Event>, root: &Path) -> bool {
+    let Ok(event) = event else {
+        return false;
+    };
+    if event.kind.is_access() {
+        return false;
+    }
+    let git_dir = root.join(".git");
+    event.paths.iter().any(|path| {
+        if path.starts_with(root.join(".gip")) {
+            return false;
+        }
+        match path.strip_prefix(&git_dir) {
+            Ok(inside) => inside == Path::new("HEAD") || inside.starts_with("refs/heads"),
+            Err(_) => true,
+        }
+    })
+}
+
+/// Update the pending manifest from the working tree and report what changed
+fn sync(root: &Path, gip_dir: &Path, class: &str, stale: &mut Vec<String>) -> Result<()> {
+    let base = if git::get_current_commit().is_ok() {
+        "HEAD"
+    } else {
+        EMPTY_TREE
+    };
+    let files = stage::read_diff(&[base], &[])?;
+    let changes = watch::changes(&files, root, gip_dir);
+
+    let _lock = PendingLock::acquire(gip_dir)?;
+    let (path, format) = commit::find_manifest(gip_dir)?;
+    let mut manifest = stage::load_pending(gip_dir, &path)?;
+    let before = manifest.clone();
+    let report = watch::sync(&mut manifest, &changes, class);
+    if manifest != before {
+        fs::create_dir_all(gip_dir).context("Failed to create .gip directory")?;
+        atomic::write(&path, format.serialize_manifest(&manifest)?)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    for added in &report.added {
+        say!("{} {} (describe it)", "+".green(), added);
+    }
+    for removed in &report.removed {
+        say!("{} {} (change undone)", "-".red(), removed);
+    }
+
+    let now = watch::stale_entries(&manifest, root, gip_dir);
+    for entry in now.iter().filter(|e| !stale.contains(e)) {
+        eprintln!(
+            "{}",
+            format!(
+                "Warning: manifest entry {} no longer matches the code",
+                entry
+            )
+            .yellow()
+        );
+    }
+    *stale = now;
+    Ok(())
+}
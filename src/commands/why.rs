@@ -0,0 +1,92 @@
+use crate::blame::{self, LineContext};
+use crate::git;
+use crate::manifest::Entry;
+use anyhow::Result;
+use colored::*;
+
+pub fn run(target: &str) -> Result<()> {
+    let (path, line) = blame::parse_target(target)?;
+    let gip_dir = git::get_gip_dir().ok();
+    print(&blame::locate(&path, line, None, gip_dir.as_deref())?);
+    Ok(())
+}
+
+/// Print the intent behind a line for people
+pub fn print(context: &LineContext) {
+    let Some(ref blamed) = context.origin else {
+        println!("{}:{} is not committed yet", context.file, context.line);
+        return;
+    };
+
+    let short = &blamed.commit[..8];
+    println!(
+        "{} {}:{} was last changed in {} {}",
+        "▶".cyan(),
+        context.file,
+        context.line,
+        short.cyan(),
+        blamed.summary
+    );
+
+    if !context.has_manifest {
+        println!("{}", format!("No manifest recorded for {}", short).yellow());
+        return;
+    }
+
+    if let Some(ref symbol) = context.symbol {
+        println!("  Enclosing symbol: {}", symbol.yellow());
+    }
+
+    if context.entries.is_empty() {
+        match context.global_intent {
+            Some(ref gi) => {
+                println!("  No entry for {}; global intent:", blamed.path);
+                println!("  Rationale: {}", gi.rationale);
+                println!("  Behavior: {}", gi.behavior_class.join(", ").blue());
+            }
+            None => println!(
+                "{}",
+                format!(
+                    "  The manifest of {} has no entry for {}",
+                    short, blamed.path
+                )
+                .yellow()
+            ),
+        }
+        return;
+    }
+
+    for entry in &context.entries {
+        print_entry(entry);
+    }
+}
+
+fn print_entry(entry: &Entry) {
+    println!();
+    println!("  Symbol: {}", entry.anchor.symbol.yellow());
+    println!("  Change: {}", entry.change_type.green());
+    println!("  Rationale: {}", entry.rationale);
+    if !entry.behavior_class.is_empty() {
+        println!("  Behavior: {}", entry.behavior_class.join(", ").blue());
+    }
+
+    let contract = &entry.contract;
+    if let Some(ref inputs) = contract.inputs {
+        println!("  Inputs: {}", inputs.join(", "));
+    }
+    if let Some(ref outputs) = contract.outputs {
+        println!("  Outputs: {}", outputs);
+    }
+    for (label, items) in [
+        ("Preconditions", &contract.preconditions),
+        ("Postconditions", &contract.postconditions),
+        ("Errors", &contract.error_model),
+    ] {
+        if !items.is_empty() {
+            println!("  {}:", label);
+            for item in items {
+                println!("    - {}", item);
+            }
+        }
+    }
+}
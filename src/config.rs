@@ -0,0 +1,378 @@
+//! Repository configuration
+//!
+//! Reads `.gip/config.toml` over the machine defaults in
+//! `~/.config/gip/config.toml` (see [`crate::global`]): tables are merged key
+//! by key and any other value, lists included, is taken from the repository.
+//! `GIP_<SECTION>_<KEY>` environment variables override both (see
+//! [`env_overrides`]), so CI can tune gip without writing into the checkout.
+//! Settings that run a command or receive a token are ignored in a tracked
+//! `.gip/config.toml` until the repository is trusted (see [`crate::trust`]).
+//! Every section is optional:
+//!
+//! ```toml
+//! [cache]
+//! persistent = false # keep manifest notes in .gip/cache between runs
+//!
+//! [issues]
+//! pattern = "[A-Z]+-\\d+" # issue ids in branch names, see `issues`
+//! url = "https://jira.example.com/browse/{id}"
+//! tracker = "jira"        # titles and statuses in markers, see `tracker`
+//! api = "https://jira.example.com"
+//!
+//! [limits]
+//! max_field_bytes = 4096
+//! max_manifest_bytes = 65536
+//! strict = false
+//!
+//! [markers]
+//! summarize = "off"   # or "rules" / "llm", see `summarize`
+//! max_rationale_chars = 160
+//! focus_threshold = 50 # more conflicts than this: enrich only entry symbols
+//! branch_context = false # merges: context from all incoming commits, not the tip
+//!
+//! [[policy.rules]]
+//! name = "no-breaking-release"
+//! branches = ["release/*"]
+//! check = 'require(not entry.breaking)'
+//!
+//! [quality]
+//! min_score = 0       # reject manifests scoring below this (0-100)
+//!
+//! [mergetool]
+//! tool = "meld"     # launched by `gip mergetool`
+//!
+//! [notes]
+//! ref = "gip"         # notes ref holding manifests, refs/notes/<ref>
+//! encrypt = false     # write manifests encrypted, see `encryption`
+//! key_file = "/secure/gip-notes.key" # default ~/.config/gip/notes.key
+//!
+//! [paths]
+//! ignore_case = false # match anchor paths case-insensitively
+//!
+//! [projection]
+//! markers = "brief"   # profile per consumer, see `profile`
+//!
+//! [projection.profiles.brief]
+//! fields = ["rationale", "contract.preconditions"]
+//! max_list_items = 2
+//!
+//! [trailer]
+//! mode = "off"        # or "payload" / "hash"
+//!
+//! [[validation.rules]]
+//! name = "auth-is-security"
+//! check = 'require(entry.behavior_class contains "security") when path matches "src/auth/**"'
+//!
+//! [redaction]
+//! always = false
+//!
+//! [[redaction.rules]]
+//! field = "rationale"
+//! pattern = "https://wiki\\.internal\\S*"
+//! replacement = "[internal link]"
+//! ```
+
+use crate::cache::CacheConfig;
+use crate::git::NotesConfig;
+use crate::global;
+use crate::issues::IssuesConfig;
+use crate::limits::LimitsConfig;
+use crate::mergetool::MergetoolConfig;
+use crate::pathspec::PathsConfig;
+use crate::policy::PolicyConfig;
+use crate::profile::ProjectionConfig;
+use crate::quality::QualityConfig;
+use crate::redact::RedactionConfig;
+use crate::rules::ValidationConfig;
+use crate::summarize::MarkerConfig;
+use crate::trailer::TrailerConfig;
+use crate::trust;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// File name of the configuration inside `.gip/`
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Prefix of environment variables overriding settings
+pub const ENV_PREFIX: &str = "GIP_";
+
+/// Environment variables, without [`ENV_PREFIX`], that set a setting under
+/// another name: (variable, section, key)
+pub const ENV_SHORTHANDS: &[(&str, &str, &str)] = &[
+    ("NO_ENRICH", "markers", "enrich"),
+    ("AI_PROVIDER", "markers", "llm_command"),
+];
+
+/// Top-level sections of `.gip/config.toml`
+pub const SECTIONS: &[&str] = &[
+    "cache",
+    "issues",
+    "limits",
+    "markers",
+    "mergetool",
+    "notes",
+    "paths",
+    "policy",
+    "projection",
+    "quality",
+    "redaction",
+    "trailer",
+    "validation",
+];
+
+/// Settings loaded from `.gip/config.toml`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Config {
+    pub cache: CacheConfig,
+    pub issues: IssuesConfig,
+    pub limits: LimitsConfig,
+    pub markers: MarkerConfig,
+    pub mergetool: MergetoolConfig,
+    pub notes: NotesConfig,
+    pub paths: PathsConfig,
+    pub policy: PolicyConfig,
+    pub projection: ProjectionConfig,
+    pub quality: QualityConfig,
+    pub redaction: RedactionConfig,
+    pub trailer: TrailerConfig,
+    pub validation: ValidationConfig,
+}
+
+impl Config {
+    /// Load `<gip_dir>/config.toml` over the machine defaults, then apply
+    /// `GIP_*` environment variables
+    pub fn load(gip_dir: &Path) -> Result<Self> {
+        Self::load_layers(
+            global::config_path().as_deref(),
+            &gip_dir.join(CONFIG_FILE),
+            env_overrides(std::env::vars()),
+        )
+    }
+
+    /// Load a repository config file over a machine one, either of which may
+    /// be absent, and apply `overrides` last
+    ///
+    /// Settings a repository may only set once trusted are dropped from a
+    /// tracked repository file, see [`trust`].
+    pub fn load_layers(
+        machine: Option<&Path>,
+        repo: &Path,
+        overrides: toml::Table,
+    ) -> Result<Self> {
+        let mut merged = toml::Table::new();
+        for path in machine.into_iter().chain([repo]) {
+            if !path.exists() {
+                continue;
+            }
+            let data = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config from {:?}", path))?;
+            let mut layer: toml::Table = toml::from_str(&data)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            if path == repo {
+                trust::strip_untrusted(&mut layer, path);
+            }
+            merge_tables(&mut merged, layer);
+        }
+        merge_tables(&mut merged, overrides);
+        toml::Value::Table(merged)
+            .try_into()
+            .context("Failed to parse config.toml or GIP_* environment variables")
+    }
+
+    /// Parse configuration from TOML text
+    pub fn parse(data: &str) -> Result<Self> {
+        toml::from_str(data).context("Failed to parse config.toml")
+    }
+}
+
+/// Settings from environment variables
+///
+/// `GIP_<SECTION>_<KEY>` sets `key` in `[section]`, e.g.
+/// `GIP_QUALITY_MIN_SCORE=60` or `GIP_NOTES_REF=gip-ci`. Values are read as
+/// TOML (numbers, booleans, `["lists"]`) and otherwise taken as text. A few
+/// shorthands are known too, see [`ENV_SHORTHANDS`].
+pub fn env_overrides(vars: impl IntoIterator<Item = (String, String)>) -> toml::Table {
+    let mut table = toml::Table::new();
+    for (name, value) in vars {
+        let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        let (section, key, value) = match ENV_SHORTHANDS.iter().find(|(n, ..)| *n == rest) {
+            // GIP_NO_ENRICH: any value but 0, false or nothing turns enrichment off
+            Some((_, section, "enrich")) => {
+                let off = !matches!(value.trim(), "" | "0" | "false");
+                (*section, "enrich".to_string(), toml::Value::Boolean(!off))
+            }
+            Some((_, section, key)) => (*section, key.to_string(), env_value(&value)),
+            None => {
+                let lower = rest.to_ascii_lowercase();
+                let found = SECTIONS.iter().find_map(|s| {
+                    let key = lower.strip_prefix(s)?.strip_prefix('_')?;
+                    (!key.is_empty()).then(|| (*s, key.to_string()))
+                });
+                let Some((section, key)) = found else {
+                    continue;
+                };
+                (section, key, env_value(&value))
+            }
+        };
+        let entry = table
+            .entry(section.to_string())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        if let toml::Value::Table(section) = entry {
+            section.insert(key, value);
+        }
+    }
+    table
+}
+
+fn env_value(raw: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("v = {}", raw))
+        .ok()
+        .and_then(|mut t| t.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+/// Merge `over` into `base`, recursing into tables present in both
+fn merge_tables(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(over)) => merge_tables(base, over),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trailer::TrailerMode;
+
+    #[test]
+    fn test_defaults_when_missing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(Config::load(temp_dir.path()).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_repo_config_over_machine_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let machine = temp_dir.path().join("machine.toml");
+        let repo = temp_dir.path().join(CONFIG_FILE);
+        fs::write(
+            &machine,
+            "[markers]\nsummarize = \"rules\"\nmax_list_items = 2\n[quality]\nmin_score = 60\n",
+        )
+        .unwrap();
+        fs::write(&repo, "[markers]\nmax_list_items = 5\n").unwrap();
+
+        let config = Config::load_layers(Some(&machine), &repo, toml::Table::new()).unwrap();
+        assert_eq!(
+            config.markers.summarize,
+            crate::summarize::SummaryMode::Rules
+        );
+        assert_eq!(config.markers.max_list_items, 5);
+        assert_eq!(config.quality.min_score, 60);
+
+        let missing = temp_dir.path().join("missing.toml");
+        let config = Config::load_layers(Some(&missing), &repo, toml::Table::new()).unwrap();
+        assert_eq!(config.quality.min_score, 0);
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let repo = temp_dir.path().join(CONFIG_FILE);
+        fs::write(&repo, "[quality]\nmin_score = 10\n[notes]\nref = \"gip\"\n").unwrap();
+
+        let vars = [
+            ("GIP_QUALITY_MIN_SCORE", "60"),
+            ("GIP_NOTES_REF", "gip-ci"),
+            ("GIP_NOTES_KEY_FILE", "/run/secrets/gip-notes.key"),
+            ("GIP_NO_ENRICH", "1"),
+            ("GIP_AI_PROVIDER", "llm -m gpt-4o-mini"),
+            ("GIP_MARKERS_SUMMARIZE", "llm"),
+            ("GIP_LOG", "debug"),
+            ("GIP_MERGETOOL", "meld"),
+            ("HOME", "/root"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        let config = Config::load_layers(None, &repo, env_overrides(vars)).unwrap();
+
+        assert_eq!(config.quality.min_score, 60);
+        assert_eq!(config.notes.ref_name, "gip-ci");
+        assert_eq!(
+            config.notes.key_file.as_deref(),
+            Some("/run/secrets/gip-notes.key")
+        );
+        assert!(!config.markers.enrich);
+        assert_eq!(config.markers.summarize, crate::summarize::SummaryMode::Llm);
+        assert_eq!(
+            config.markers.llm_command.as_deref(),
+            Some("llm -m gpt-4o-mini")
+        );
+
+        let vars = [("GIP_QUALITY_MIN_SCORE".to_string(), "lots".to_string())];
+        assert!(Config::load_layers(None, &repo, env_overrides(vars)).is_err());
+    }
+
+    #[test]
+    fn test_parse_redaction() {
+        let config = Config::parse(
+            r#"
+[redaction]
+always = true
+
+[[redaction.rules]]
+field = "rationale"
+pattern = "PROJECT-\\w+"
+"#,
+        )
+        .unwrap();
+
+        assert!(config.redaction.always);
+        assert_eq!(config.redaction.rules.len(), 1);
+        assert_eq!(config.redaction.rules[0].replacement, "[REDACTED]");
+    }
+
+    #[test]
+    fn test_parse_partial_limits() {
+        let config = Config::parse("[limits]\nstrict = true\n").unwrap();
+        assert!(config.limits.strict);
+        assert_eq!(
+            config.limits.max_field_bytes,
+            LimitsConfig::default().max_field_bytes
+        );
+    }
+
+    #[test]
+    fn test_parse_markers() {
+        use crate::summarize::SummaryMode;
+
+        let config =
+            Config::parse("[markers]\nsummarize = \"rules\"\nmax_list_items = 2\n").unwrap();
+        assert_eq!(config.markers.summarize, SummaryMode::Rules);
+        assert!(!config.markers.branch_context);
+        assert_eq!(config.markers.max_list_items, 2);
+        assert_eq!(config.markers.max_rationale_chars, 160);
+    }
+
+    #[test]
+    fn test_parse_quality() {
+        let config = Config::parse("[quality]\nmin_score = 60\n").unwrap();
+        assert_eq!(config.quality.min_score, 60);
+        assert_eq!(Config::default().quality.min_score, 0);
+    }
+
+    #[test]
+    fn test_parse_trailer_mode() {
+        let config = Config::parse("[trailer]\nmode = \"hash\"\n").unwrap();
+        assert_eq!(config.trailer.mode, TrailerMode::Hash);
+        assert!(Config::parse("[trailer]\nmode = \"full\"\n").is_err());
+    }
+}
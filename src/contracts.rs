@@ -0,0 +1,159 @@
+//! Contract erosion detection
+//!
+//! Tracks the last recorded postconditions and error model of every symbol
+//! through history and flags commits whose manifest drops some of them without
+//! marking the change as breaking.
+
+use crate::manifest::{Entry, Manifest, CHANGE_DELETE};
+use crate::symbols;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Guarantees a commit removed from a symbol's contract
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Erosion {
+    pub commit: String,
+    pub file: String,
+    pub symbol: String,
+    pub removed_postconditions: Vec<String>,
+    pub removed_error_model: Vec<String>,
+}
+
+#[derive(Default)]
+struct Guarantees {
+    postconditions: Vec<String>,
+    error_model: Vec<String>,
+}
+
+fn is_breaking(entry: &Entry) -> bool {
+    entry.compatibility.as_ref().is_some_and(|c| c.breaking)
+}
+
+fn missing(previous: &[String], current: &[String]) -> Vec<String> {
+    previous
+        .iter()
+        .filter(|p| !current.iter().any(|c| c.trim() == p.trim()))
+        .cloned()
+        .collect()
+}
+
+/// Find eroded contracts in manifests ordered oldest first
+///
+/// Entries that record no postconditions or error model at all are treated as
+/// silent about the contract rather than as removing it.
+pub fn detect(manifests: &[Manifest]) -> Vec<Erosion> {
+    let mut known: HashMap<(String, String), Guarantees> = HashMap::new();
+    let mut erosions = Vec::new();
+
+    for manifest in manifests {
+        for entry in &manifest.entries {
+            let key = (
+                entry.anchor.file.clone(),
+                symbols::base_name(&entry.anchor.symbol).to_string(),
+            );
+
+            if entry.change_type == CHANGE_DELETE {
+                known.remove(&key);
+                continue;
+            }
+
+            let contract = &entry.contract;
+            if contract.postconditions.is_empty() && contract.error_model.is_empty() {
+                continue;
+            }
+
+            if let Some(previous) = known.get(&key) {
+                let removed_postconditions =
+                    missing(&previous.postconditions, &contract.postconditions);
+                let removed_error_model = missing(&previous.error_model, &contract.error_model);
+
+                if !is_breaking(entry)
+                    && (!removed_postconditions.is_empty() || !removed_error_model.is_empty())
+                {
+                    erosions.push(Erosion {
+                        commit: manifest.commit.clone(),
+                        file: entry.anchor.file.clone(),
+                        symbol: entry.anchor.symbol.clone(),
+                        removed_postconditions,
+                        removed_error_model,
+                    });
+                }
+            }
+
+            known.insert(
+                key,
+                Guarantees {
+                    postconditions: contract.postconditions.clone(),
+                    error_model: contract.error_model.clone(),
+                },
+            );
+        }
+    }
+
+    erosions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn manifest(commit: &str, entry: serde_json::Value) -> Manifest {
+        test_support::manifest(commit, entry)
+    }
+
+    fn entry(post: &[&str], errors: &[&str], breaking: bool) -> serde_json::Value {
+        json!({
+            "anchor": { "file": "src/pay.rs", "symbol": "Gateway::charge" },
+            "rationale": "r",
+            "contract": { "postconditions": post, "errorModel": errors },
+            "compatibility": { "breaking": breaking }
+        })
+    }
+
+    #[test]
+    fn test_detects_silent_removal() {
+        let history = vec![
+            manifest(
+                "a",
+                entry(&["charged once", "receipt sent"], &["Timeout"], false),
+            ),
+            manifest("b", entry(&["charged once"], &[], false)),
+        ];
+
+        let erosions = detect(&history);
+        assert_eq!(erosions.len(), 1);
+        assert_eq!(erosions[0].commit, "b");
+        assert_eq!(erosions[0].removed_postconditions, vec!["receipt sent"]);
+        assert_eq!(erosions[0].removed_error_model, vec!["Timeout"]);
+    }
+
+    #[test]
+    fn test_breaking_and_silent_entries_are_not_flagged() {
+        let history = vec![
+            manifest("a", entry(&["charged once", "receipt sent"], &[], false)),
+            // Records no contract: keeps the known guarantees
+            manifest("b", entry(&[], &[], false)),
+            // Declared breaking: allowed, and becomes the new baseline
+            manifest("c", entry(&["charged once"], &[], true)),
+            manifest("d", entry(&["charged once", "audited"], &[], false)),
+        ];
+
+        assert!(detect(&history).is_empty());
+    }
+
+    #[test]
+    fn test_delete_resets_symbol() {
+        let mut deleted = entry(&[], &[], false);
+        deleted["changeType"] = json!("delete");
+        let history = vec![
+            manifest("a", entry(&["charged once"], &[], false)),
+            manifest("b", deleted),
+            manifest("c", entry(&["nothing"], &[], false)),
+        ];
+
+        assert!(detect(&history).is_empty());
+    }
+}
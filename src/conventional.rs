@@ -0,0 +1,308 @@
+//! Conventional Commits support
+//!
+//! Parses messages like `feat(api)!: add pagination` so the manifest template
+//! can be pre-filled with a behavior class, breaking flag, and rationale seed,
+//! and goes the other way to write a commit message from a manifest.
+
+use crate::manifest::{self, Compatibility, Manifest};
+use regex::Regex;
+
+/// A parsed conventional commit message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConventionalCommit {
+    pub kind: String,
+    pub scope: Option<String>,
+    pub breaking: bool,
+    pub description: String,
+}
+
+impl ConventionalCommit {
+    /// Parse the subject (and `BREAKING CHANGE` footers) of a commit message
+    pub fn parse(message: &str) -> Option<Self> {
+        let re = Regex::new(
+            r"^(?P<kind>[a-zA-Z]+)(?:\((?P<scope>[^)]*)\))?(?P<bang>!)?:\s+(?P<desc>.+)$",
+        )
+        .expect("valid conventional commit regex");
+
+        let subject = message.lines().next()?.trim();
+        let caps = re.captures(subject)?;
+
+        let breaking = caps.name("bang").is_some()
+            || message
+                .lines()
+                .any(|l| l.starts_with("BREAKING CHANGE:") || l.starts_with("BREAKING-CHANGE:"));
+
+        Some(Self {
+            kind: caps["kind"].to_lowercase(),
+            scope: caps
+                .name("scope")
+                .map(|s| s.as_str().trim().to_string())
+                .filter(|s| !s.is_empty()),
+            breaking,
+            description: caps["desc"].trim().to_string(),
+        })
+    }
+
+    /// The manifest behavior class matching the commit type, if any
+    pub fn behavior_class(&self) -> Option<&'static str> {
+        match self.kind.as_str() {
+            "feat" | "feature" => Some(manifest::BEHAVIOR_FEATURE),
+            "fix" | "bugfix" | "revert" => Some(manifest::BEHAVIOR_BUGFIX),
+            "perf" => Some(manifest::BEHAVIOR_PERF),
+            "refactor" | "style" => Some(manifest::BEHAVIOR_REFACTOR),
+            "docs" => Some(manifest::BEHAVIOR_DOCS),
+            "build" | "ci" | "chore" => Some(manifest::BEHAVIOR_CONFIG),
+            "security" | "sec" => Some(manifest::BEHAVIOR_SECURITY),
+            "test" | "tests" => Some(manifest::BEHAVIOR_VALIDATION),
+            _ => None,
+        }
+    }
+
+    /// Rationale seed derived from the description, e.g. "Add pagination"
+    pub fn rationale_seed(&self) -> String {
+        let mut chars = self.description.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    }
+
+    /// Pre-fill a (template) manifest from this commit message
+    pub fn seed(&self, manifest: &mut Manifest) {
+        let class = self.behavior_class();
+        let rationale = self.rationale_seed();
+
+        if let Some(ref mut gi) = manifest.global_intent {
+            if let Some(class) = class {
+                gi.behavior_class = vec![class.to_string()];
+            }
+            gi.rationale = rationale.clone();
+        }
+
+        for entry in &mut manifest.entries {
+            if let Some(class) = class {
+                entry.behavior_class = vec![class.to_string()];
+            }
+            entry.rationale = rationale.clone();
+
+            if self.breaking {
+                match entry.compatibility {
+                    Some(ref mut compat) => compat.breaking = true,
+                    None => {
+                        entry.compatibility = Some(Compatibility {
+                            breaking: true,
+                            deprecations: None,
+                            migrations: None,
+                            binary_breaking: None,
+                            source_breaking: None,
+                            data_model_migration: None,
+                        })
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Longest subject line produced by [`message_from_manifest`]
+const MAX_SUBJECT_LEN: usize = 72;
+
+/// Conventional commit type for a behavior class
+fn commit_type(behavior_class: &str) -> &'static str {
+    match behavior_class {
+        manifest::BEHAVIOR_FEATURE => "feat",
+        manifest::BEHAVIOR_BUGFIX | manifest::BEHAVIOR_SECURITY => "fix",
+        manifest::BEHAVIOR_PERF => "perf",
+        manifest::BEHAVIOR_REFACTOR => "refactor",
+        manifest::BEHAVIOR_DOCS => "docs",
+        manifest::BEHAVIOR_VALIDATION => "test",
+        _ => "chore",
+    }
+}
+
+/// Synthesize a conventional commit message from a manifest
+///
+/// The type comes from the global (or first entry's) behavior class, the
+/// subject from its rationale, and the body lists every entry. Breaking
+/// entries add `!` and a `BREAKING CHANGE` footer.
+pub fn message_from_manifest(manifest: &Manifest) -> String {
+    let first = manifest.entries.first();
+    let (classes, rationale) = match (&manifest.global_intent, first) {
+        (Some(gi), _) => (&gi.behavior_class, gi.rationale.as_str()),
+        (None, Some(entry)) => (&entry.behavior_class, entry.rationale.as_str()),
+        (None, None) => return "chore: update".to_string(),
+    };
+    let kind = classes.first().map(|c| commit_type(c)).unwrap_or("chore");
+
+    // A scope only when every entry belongs to the same package
+    let mut packages = manifest.entries.iter().map(|e| e.package.as_deref());
+    let scope = match packages.next().flatten() {
+        Some(pkg) if packages.all(|p| p == Some(pkg)) => format!("({})", pkg),
+        _ => String::new(),
+    };
+
+    let breaking: Vec<_> = manifest
+        .entries
+        .iter()
+        .filter(|e| e.compatibility.as_ref().is_some_and(|c| c.breaking))
+        .collect();
+    let bang = if breaking.is_empty() { "" } else { "!" };
+
+    let prefix = format!("{}{}{}: ", kind, scope, bang);
+    let mut message = format!(
+        "{}{}",
+        prefix,
+        subject(rationale, MAX_SUBJECT_LEN.saturating_sub(prefix.len()))
+    );
+
+    if !manifest.entries.is_empty() {
+        message.push_str("\n\n");
+        for entry in &manifest.entries {
+            message.push_str(&format!(
+                "- {}::{} ({}): {}\n",
+                entry.anchor.file,
+                entry.anchor.symbol,
+                entry.change_type,
+                first_line(&entry.rationale)
+            ));
+        }
+    }
+
+    for entry in breaking {
+        let migrations = entry
+            .compatibility
+            .as_ref()
+            .and_then(|c| c.migrations.as_ref())
+            .filter(|m| !m.is_empty())
+            .map(|m| format!(" Migration: {}", m.join("; ")))
+            .unwrap_or_default();
+        message.push_str(&format!(
+            "\nBREAKING CHANGE: {}: {}{}",
+            entry.anchor.symbol,
+            first_line(&entry.rationale),
+            migrations
+        ));
+    }
+
+    message.trim_end().to_string()
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or("").trim()
+}
+
+/// Turn a rationale into a subject: first line, lowercase start, no period
+fn subject(rationale: &str, max_len: usize) -> String {
+    let line = first_line(rationale).trim_end_matches('.');
+    let mut chars = line.chars();
+    let mut subject: String = match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => return "update".to_string(),
+    };
+
+    if subject.chars().count() > max_len {
+        subject = subject.chars().take(max_len.saturating_sub(1)).collect();
+        subject = subject.trim_end().to_string();
+        subject.push('…');
+    }
+    subject
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_subject() {
+        let cc = ConventionalCommit::parse("feat(api)!: add pagination to list endpoints").unwrap();
+        assert_eq!(cc.kind, "feat");
+        assert_eq!(cc.scope.as_deref(), Some("api"));
+        assert!(cc.breaking);
+        assert_eq!(cc.description, "add pagination to list endpoints");
+        assert_eq!(cc.behavior_class(), Some(manifest::BEHAVIOR_FEATURE));
+        assert_eq!(cc.rationale_seed(), "Add pagination to list endpoints");
+    }
+
+    #[test]
+    fn test_breaking_change_footer() {
+        let cc = ConventionalCommit::parse(
+            "fix: reject empty tokens\n\nBREAKING CHANGE: tokens are required",
+        )
+        .unwrap();
+        assert!(cc.breaking);
+        assert_eq!(cc.behavior_class(), Some(manifest::BEHAVIOR_BUGFIX));
+    }
+
+    #[test]
+    fn test_non_conventional_message() {
+        assert!(ConventionalCommit::parse("Update README").is_none());
+        assert!(ConventionalCommit::parse("wip").is_none());
+    }
+
+    #[test]
+    fn test_seed_manifest() {
+        let mut manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "HEAD",
+            "entries": [{
+                "anchor": { "file": "src/main.rs", "symbol": "main", "hunkId": "H#1" },
+                "changeType": "modify",
+                "rationale": "Describe your changes here",
+                "behaviorClass": ["feature"],
+                "contract": {}
+            }]
+        }))
+        .unwrap();
+
+        ConventionalCommit::parse("perf!: cache user lookups")
+            .unwrap()
+            .seed(&mut manifest);
+
+        let entry = &manifest.entries[0];
+        assert_eq!(entry.behavior_class, vec!["perf"]);
+        assert_eq!(entry.rationale, "Cache user lookups");
+        assert!(entry.compatibility.as_ref().unwrap().breaking);
+    }
+
+    #[test]
+    fn test_message_from_manifest() {
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "HEAD",
+            "entries": [{
+                "anchor": { "file": "src/api.rs", "symbol": "list_users", "hunkId": "H#1" },
+                "changeType": "modify",
+                "rationale": "Paginate user listing.",
+                "behaviorClass": ["feature"],
+                "contract": {},
+                "package": "api",
+                "compatibility": { "breaking": true, "migrations": ["pass ?page=1"] }
+            }, {
+                "anchor": { "file": "src/db.rs", "symbol": "count_users", "hunkId": "H#2" },
+                "changeType": "add",
+                "rationale": "Needed for page totals",
+                "behaviorClass": ["feature"],
+                "contract": {},
+                "package": "api"
+            }]
+        }))
+        .unwrap();
+
+        let message = message_from_manifest(&manifest);
+        assert_eq!(
+            message,
+            "feat(api)!: paginate user listing\n\n\
+             - src/api.rs::list_users (modify): Paginate user listing.\n\
+             - src/db.rs::count_users (add): Needed for page totals\n\n\
+             BREAKING CHANGE: list_users: Paginate user listing. Migration: pass ?page=1"
+        );
+    }
+
+    #[test]
+    fn test_subject_truncation() {
+        let long = "x".repeat(200);
+        let s = subject(&long, 20);
+        assert_eq!(s.chars().count(), 20);
+        assert!(s.ends_with('…'));
+    }
+}
@@ -0,0 +1,107 @@
+//! Manifest coverage metrics
+//!
+//! Coverage is the share of commits carrying a valid manifest: one that parses
+//! and whose entries all have a real rationale and at least one behaviorClass.
+//! The shields.io endpoint output lets CI publish a README badge.
+
+use crate::manifest::{self, Manifest};
+use crate::stats::percent;
+use crate::template::PLACEHOLDER;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Coverage over a set of commits
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Coverage {
+    pub total_commits: usize,
+    pub covered_commits: usize,
+    pub coverage_pct: f64,
+}
+
+impl Coverage {
+    pub fn new(total_commits: usize, covered_commits: usize) -> Self {
+        Coverage {
+            total_commits,
+            covered_commits,
+            coverage_pct: percent(covered_commits, total_commits),
+        }
+    }
+
+    /// Measure coverage of `commits`, given the set of commits that carry a note
+    pub fn measure(commits: &[String], noted: &HashSet<String>, repo_path: Option<&Path>) -> Self {
+        let covered = commits
+            .iter()
+            .filter(|sha| noted.contains(*sha))
+            .filter(|sha| manifest::load(sha, repo_path).is_ok_and(|m| is_valid(&m)))
+            .count();
+        Coverage::new(commits.len(), covered)
+    }
+}
+
+/// Whether a manifest counts towards coverage
+pub fn is_valid(manifest: &Manifest) -> bool {
+    !manifest.entries.is_empty()
+        && manifest.entries.iter().all(|e| {
+            !e.rationale.trim().is_empty()
+                && !e.rationale.contains(PLACEHOLDER)
+                && !e.behavior_class.is_empty()
+        })
+}
+
+/// Badge color for a coverage percentage
+pub fn badge_color(pct: f64) -> &'static str {
+    match pct {
+        p if p >= 90.0 => "brightgreen",
+        p if p >= 75.0 => "green",
+        p if p >= 50.0 => "yellow",
+        p if p >= 25.0 => "orange",
+        _ => "red",
+    }
+}
+
+/// shields.io endpoint JSON for a coverage badge
+pub fn shields_json(coverage: &Coverage) -> Value {
+    json!({
+        "schemaVersion": 1,
+        "label": "context coverage",
+        "message": format!("{:.0}%", coverage.coverage_pct),
+        "color": badge_color(coverage.coverage_pct),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn manifest(rationale: &str, classes: &[&str]) -> Manifest {
+        test_support::manifest(
+            "abc",
+            json!({ "rationale": rationale, "behaviorClass": classes }),
+        )
+    }
+
+    #[test]
+    fn test_is_valid() {
+        assert!(is_valid(&manifest("Retry on timeout", &["bugfix"])));
+        assert!(!is_valid(&manifest(PLACEHOLDER, &["bugfix"])));
+        assert!(!is_valid(&manifest("  ", &["bugfix"])));
+        assert!(!is_valid(&manifest("Retry on timeout", &[])));
+        assert!(!is_valid(&Manifest::new("abc".to_string())));
+    }
+
+    #[test]
+    fn test_shields_json() {
+        let badge = shields_json(&Coverage::new(3, 2));
+        assert_eq!(badge["schemaVersion"], 1);
+        assert_eq!(badge["message"], "67%");
+        assert_eq!(badge["color"], "yellow");
+
+        assert_eq!(badge_color(100.0), "brightgreen");
+        assert_eq!(badge_color(0.0), "red");
+    }
+}
@@ -0,0 +1,99 @@
+//! Combined commit message and manifest buffer
+//!
+//! `gip commit` without `-m` opens one editor buffer instead of two files: the
+//! commit message on top, then a scissors line, then the manifest. On save
+//! the buffer is split, the message is cleaned up the way git does, and the
+//! manifest goes back to `.gip/manifest.*` before validation.
+
+/// Line separating the commit message from the manifest
+pub const SCISSORS: &str = "# ------------------------ >8 ------------------------";
+
+/// Buffer file inside `.gip/`, kept until the commit succeeds
+pub const BUFFER_FILE: &str = "COMMIT_EDITMSG";
+
+const HELP: &str = "\
+# Write the commit message above. Lines starting with '#' are ignored, and an
+# empty message aborts the commit.
+#
+# Describe the change in the manifest below the scissors line. It is saved to
+# {path} and validated before committing.";
+
+/// The buffer presented to the author
+pub fn compose(message: &str, manifest: &str, manifest_path: &str) -> String {
+    let message = message.trim_end();
+    format!(
+        "{}\n\n{}\n{}\n{}",
+        message,
+        HELP.replace("{path}", manifest_path),
+        SCISSORS,
+        manifest
+    )
+}
+
+/// Split an edited buffer into the cleaned-up message and the manifest text
+///
+/// Without a scissors line the whole buffer is the message.
+pub fn split(buffer: &str) -> (String, String) {
+    let (above, below) = match buffer.find(SCISSORS) {
+        Some(pos) => {
+            let rest = &buffer[pos + SCISSORS.len()..];
+            (&buffer[..pos], rest.strip_prefix('\n').unwrap_or(rest))
+        }
+        None => (buffer, ""),
+    };
+    (clean_message(above), below.to_string())
+}
+
+/// Drop comment lines and surrounding blank lines, as `git commit --cleanup=strip`
+fn clean_message(text: &str) -> String {
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .map(str::trim_end)
+        .collect();
+
+    let mut out = String::new();
+    let mut blank = false;
+    for line in lines {
+        if line.is_empty() {
+            blank = !out.is_empty();
+            continue;
+        }
+        if blank {
+            out.push('\n');
+            blank = false;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = "schemaVersion: \"2.0\"\ncommit: HEAD\n# not a comment in TOON\n";
+
+    #[test]
+    fn test_round_trip() {
+        let buffer = compose("", MANIFEST, ".gip/manifest.toon");
+        assert!(buffer.contains(".gip/manifest.toon"));
+
+        let edited = buffer.replacen("\n", "fix: retry charges\n\nThe gateway times out.\n", 1);
+        let (message, manifest) = split(&edited);
+        assert_eq!(message, "fix: retry charges\n\nThe gateway times out.");
+        assert_eq!(manifest, MANIFEST);
+    }
+
+    #[test]
+    fn test_empty_message_and_missing_scissors() {
+        let (message, manifest) = split(&compose("", MANIFEST, "m"));
+        assert!(message.is_empty());
+        assert_eq!(manifest, MANIFEST);
+
+        let (message, manifest) = split("# comment\n\n  \nfix: x\n\n\n\nbody\n");
+        assert_eq!(message, "fix: x\n\nbody");
+        assert!(manifest.is_empty());
+    }
+}
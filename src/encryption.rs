@@ -0,0 +1,247 @@
+//! Encryption at rest for notes
+//!
+//! With `[notes] encrypt = true`, manifests are written to notes as
+//! `gip-encrypted:v1:<base64>`: the TOON text sealed with XChaCha20-Poly1305,
+//! its random nonce in front. Hosts that store `refs/notes/gip` only ever see
+//! ciphertext.
+//!
+//! The 32-byte key never lives in the repository. It is read from `[notes]
+//! key_file`, `notes.key` in the machine gip directory (see [`crate::global`])
+//! by default, and `gip keygen` creates it. Encrypted notes are decrypted by
+//! [`crate::manifest::load`] whenever the key is there, whether or not
+//! `encrypt` is still set.
+
+use crate::config::Config;
+use crate::errors::ErrorCode;
+use crate::git::{self, NotesConfig};
+use crate::global;
+use crate::trailer::TrailerMode;
+use anyhow::{Context, Result};
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Start of an encrypted note
+pub const PREFIX: &str = "gip-encrypted:v1:";
+
+/// Key file inside the machine gip directory when `key_file` isn't set
+pub const KEY_FILE: &str = "notes.key";
+
+const NONCE_LEN: usize = 24;
+
+/// Where the key is read from, if a home directory is known
+pub fn key_path(config: &NotesConfig) -> Option<PathBuf> {
+    match config.key_file.as_deref().filter(|p| !p.trim().is_empty()) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => global::dir().map(|dir| dir.join(KEY_FILE)),
+    }
+}
+
+/// Read a base64-encoded 32-byte key
+pub fn read_key(path: &Path) -> Result<Key> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Failed to read notes key {:?}", path))?;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(text.trim())
+        .with_context(|| format!("Notes key {:?} is not base64", path))?;
+    if bytes.len() != 32 {
+        anyhow::bail!(
+            "Notes key {:?} is {} bytes; it must be 32",
+            path,
+            bytes.len()
+        );
+    }
+    Ok(*Key::from_slice(&bytes))
+}
+
+/// Write a new random key to `path`, readable by its owner only
+pub fn generate_key(path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+    let text = format!(
+        "{}\n",
+        base64::engine::general_purpose::STANDARD.encode(key)
+    );
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create notes key {:?}", path))?;
+    std::io::Write::write_all(&mut file, text.as_bytes())
+        .with_context(|| format!("Failed to write notes key {:?}", path))
+}
+
+/// Whether a note was written encrypted
+pub fn is_sealed(note: &str) -> bool {
+    note.starts_with(PREFIX)
+}
+
+/// Encrypt note text with `key`
+pub fn seal(plain: &str, key: &Key) -> Result<String> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = XChaCha20Poly1305::new(key)
+        .encrypt(&nonce, plain.as_bytes())
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt note"))?;
+    let mut payload = nonce.to_vec();
+    payload.extend(ciphertext);
+    Ok(format!(
+        "{}{}",
+        PREFIX,
+        base64::engine::general_purpose::STANDARD.encode(payload)
+    ))
+}
+
+/// Decrypt a note written by [`seal`]
+pub fn open(note: &str, key: &Key) -> Result<String> {
+    let encoded = note
+        .trim()
+        .strip_prefix(PREFIX)
+        .context("Note is not encrypted")?;
+    let payload = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .context("Encrypted note is not base64")?;
+    if payload.len() < NONCE_LEN {
+        anyhow::bail!("Encrypted note is truncated");
+    }
+    let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+    let plain = XChaCha20Poly1305::new(key)
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow::anyhow!("Failed to decrypt note; wrong notes key?"))?;
+    String::from_utf8(plain).context("Decrypted note is not UTF-8")
+}
+
+/// Encryption settings of the current repository
+struct Settings {
+    encrypt: bool,
+    path: Option<PathBuf>,
+    key: Option<Key>,
+}
+
+/// Resolved once per process, like [`git::notes_ref`]
+fn settings() -> &'static Settings {
+    static SETTINGS: OnceLock<Settings> = OnceLock::new();
+    SETTINGS.get_or_init(|| {
+        let config = git::get_gip_dir()
+            .ok()
+            .and_then(|dir| Config::load(&dir).ok())
+            .map(|config| config.notes)
+            .unwrap_or_default();
+        let path = key_path(&config);
+        Settings {
+            encrypt: config.encrypt,
+            key: path.as_deref().and_then(|p| read_key(p).ok()),
+            path,
+        }
+    })
+}
+
+fn missing_key(settings: &Settings) -> anyhow::Error {
+    match settings.path {
+        Some(ref path) => anyhow::anyhow!(
+            "No notes key at {:?}; create one with gip keygen or copy the team's key there",
+            path
+        ),
+        None => anyhow::anyhow!("No notes key: set [notes] key_file or GIP_CONFIG_DIR"),
+    }
+}
+
+/// Fail unless manifests can be written, i.e. a key is there if `[notes] encrypt` is set
+///
+/// `gip commit` checks this before committing, so the commit isn't left without its note.
+pub fn ensure_writable() -> Result<()> {
+    let settings = settings();
+    if settings.encrypt && settings.key.is_none() {
+        return Err(missing_key(settings)
+            .context("[notes] encrypt is set, so manifests can't be written without a key"));
+    }
+    Ok(())
+}
+
+/// Fail if `[trailer] mode` would copy manifests past the encryption
+///
+/// `payload` trailers put the whole manifest in the commit message, which
+/// can't be encrypted; `hash` trailers only name the note.
+pub fn check_trailer_mode(config: &NotesConfig, mode: TrailerMode) -> Result<()> {
+    if config.encrypt && mode == TrailerMode::Payload {
+        anyhow::bail!(ErrorCode::Usage.error(
+            "[notes] encrypt is set, but [trailer] mode = \"payload\" would copy every manifest into its commit message unencrypted; use mode = \"hash\""
+        ));
+    }
+    Ok(())
+}
+
+/// Note text as it should be written: encrypted when `[notes] encrypt` is set
+pub fn seal_note(plain: String) -> Result<String> {
+    ensure_writable()?;
+    let settings = settings();
+    match settings.key {
+        Some(ref key) if settings.encrypt => seal(&plain, key),
+        _ => Ok(plain),
+    }
+}
+
+/// Note text as stored, decrypted if it was written encrypted
+pub fn open_note(note: String) -> Result<String> {
+    if !is_sealed(&note) {
+        return Ok(note);
+    }
+    let settings = settings();
+    match settings.key {
+        Some(ref key) => open(&note, key),
+        None => Err(missing_key(settings).context("Manifest is encrypted")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_round_trip() {
+        let key = XChaCha20Poly1305::generate_key(&mut OsRng);
+        let plain = "schemaVersion: \"2.0\"\ncommit: abc123";
+
+        let sealed = seal(plain, &key).unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("abc123"));
+        assert_ne!(sealed, seal(plain, &key).unwrap(), "fresh nonce per note");
+        assert_eq!(open(&sealed, &key).unwrap(), plain);
+
+        let other = XChaCha20Poly1305::generate_key(&mut OsRng);
+        assert!(open(&sealed, &other).is_err());
+        assert!(open(&sealed[..PREFIX.len() + 8], &key).is_err());
+    }
+
+    #[test]
+    fn test_payload_trailers_refused() {
+        let mut config = NotesConfig::default();
+        assert!(check_trailer_mode(&config, TrailerMode::Payload).is_ok());
+
+        config.encrypt = true;
+        assert!(check_trailer_mode(&config, TrailerMode::Payload).is_err());
+        assert!(check_trailer_mode(&config, TrailerMode::Hash).is_ok());
+        assert!(check_trailer_mode(&config, TrailerMode::Off).is_ok());
+    }
+
+    #[test]
+    fn test_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keys").join(KEY_FILE);
+        generate_key(&path).unwrap();
+        let key = read_key(&path).unwrap();
+        assert_eq!(open(&seal("x", &key).unwrap(), &key).unwrap(), "x");
+        // An existing key is never replaced
+        assert!(generate_key(&path).is_err());
+
+        fs::write(&path, "c2hvcnQ=\n").unwrap();
+        assert!(read_key(&path).is_err());
+    }
+}
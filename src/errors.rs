@@ -0,0 +1,174 @@
+//! Exit codes and machine-readable errors
+//!
+//! Failures that automation needs to tell apart carry an [`ErrorCode`]. The
+//! code is gip's exit status, and with `--format json` (or `toon`) the error is
+//! also written to stderr as a document:
+//!
+//! ```json
+//! {"error": {"code": 3, "name": "manifest-invalid", "message": "...", "causes": []}}
+//! ```
+//!
+//! Codes and names are guaranteed not to change. New codes may be added.
+//! Commands passed through to git exit with git's own status.
+
+use crate::output::{self, OutputFormat};
+use anyhow::Result;
+use serde::Serialize;
+
+/// Why gip failed, doubling as its exit status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    /// Anything without a more specific code
+    General = 1,
+    /// Bad command-line usage
+    Usage = 2,
+    /// A manifest is missing, incomplete, or breaks the repository rules
+    ManifestInvalid = 3,
+    /// A merge, rebase or stash stopped on conflicts (markers were enriched)
+    MergeConflict = 4,
+    /// A commit has no manifest in notes or trailers
+    NotesMissing = 5,
+    /// A branch policy blocked the operation
+    PolicyViolation = 6,
+    /// Not inside a git repository
+    NotARepository = 7,
+    /// Another gip process holds the pending manifest lock
+    Locked = 8,
+    /// The remote rejected a push
+    PushRejected = 9,
+}
+
+impl ErrorCode {
+    pub const ALL: [ErrorCode; 9] = [
+        ErrorCode::General,
+        ErrorCode::Usage,
+        ErrorCode::ManifestInvalid,
+        ErrorCode::MergeConflict,
+        ErrorCode::NotesMissing,
+        ErrorCode::PolicyViolation,
+        ErrorCode::NotARepository,
+        ErrorCode::Locked,
+        ErrorCode::PushRejected,
+    ];
+
+    pub fn exit_code(self) -> i32 {
+        self as i32
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::General => "general",
+            ErrorCode::Usage => "usage",
+            ErrorCode::ManifestInvalid => "manifest-invalid",
+            ErrorCode::MergeConflict => "merge-conflict",
+            ErrorCode::NotesMissing => "notes-missing",
+            ErrorCode::PolicyViolation => "policy-violation",
+            ErrorCode::NotARepository => "not-a-repository",
+            ErrorCode::Locked => "locked",
+            ErrorCode::PushRejected => "push-rejected",
+        }
+    }
+
+    /// An error with this code, for `anyhow::bail!` or `.context()`
+    pub fn error(self, message: impl Into<String>) -> GipError {
+        GipError {
+            code: self,
+            message: message.into(),
+        }
+    }
+}
+
+/// An error carrying an [`ErrorCode`]
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct GipError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+/// The code of an error, found anywhere in its context chain
+pub fn code_of(err: &anyhow::Error) -> ErrorCode {
+    err.downcast_ref::<GipError>()
+        .map_or(ErrorCode::General, |e| e.code)
+}
+
+#[derive(Serialize)]
+struct ErrorDocument<'a> {
+    error: ErrorBody<'a>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    code: i32,
+    name: &'a str,
+    message: String,
+    causes: Vec<String>,
+}
+
+/// Render an error as a document in a structured format
+pub fn render(err: &anyhow::Error, format: OutputFormat) -> Result<String> {
+    let code = code_of(err);
+    let document = ErrorDocument {
+        error: ErrorBody {
+            code: code.exit_code(),
+            name: code.as_str(),
+            message: err.to_string(),
+            causes: err.chain().skip(1).map(|c| c.to_string()).collect(),
+        },
+    };
+    Ok(match format {
+        OutputFormat::Human => format!("Error: {:?}", err),
+        OutputFormat::Json => serde_json::to_string(&document)?,
+        OutputFormat::Toon => toon_format::encode_default(&document)?,
+    })
+}
+
+/// Print an error to stderr in the current output format; returns the exit status
+pub fn report(err: &anyhow::Error) -> i32 {
+    match render(err, output::format()) {
+        Ok(text) => eprintln!("{}", text),
+        Err(_) => eprintln!("Error: {:?}", err),
+    }
+    code_of(err).exit_code()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_codes_are_stable() {
+        let codes: Vec<(i32, &str)> = ErrorCode::ALL
+            .iter()
+            .map(|c| (c.exit_code(), c.as_str()))
+            .collect();
+        assert_eq!(codes[2], (3, "manifest-invalid"));
+        assert_eq!(codes[3], (4, "merge-conflict"));
+        assert_eq!(codes[4], (5, "notes-missing"));
+        for (i, (code, _)) in codes.iter().enumerate() {
+            assert_eq!(*code, i as i32 + 1);
+        }
+    }
+
+    #[test]
+    fn test_code_survives_context() {
+        let err: anyhow::Error = ErrorCode::NotesMissing.error("No manifest").into();
+        let err = Err::<(), _>(err).context("Failed to show abc").unwrap_err();
+        assert_eq!(code_of(&err), ErrorCode::NotesMissing);
+        assert_eq!(code_of(&anyhow::anyhow!("boom")), ErrorCode::General);
+
+        let rendered: Value =
+            serde_json::from_str(&render(&err, OutputFormat::Json).unwrap()).unwrap();
+        assert_eq!(
+            rendered,
+            json!({"error": {
+                "code": 5,
+                "name": "notes-missing",
+                "message": "Failed to show abc",
+                "causes": ["No manifest"]
+            }})
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! Garbage collection of orphaned gip notes
+//!
+//! Notes on commits that are no longer reachable from any ref (abandoned
+//! branches, rewritten history) are never cleaned up by `git gc`, because the
+//! notes tree itself keeps them alive. This module finds them and optionally
+//! archives their manifests to `.gip/archive/` before removing them. A note
+//! that a reachable commit's `hash` trailer points to is not orphaned: that
+//! commit's manifest is read from it.
+
+use crate::atomic;
+use crate::git;
+use crate::manifest;
+use crate::trailer;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Archive directory name inside `.gip/`
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// Commits with a gip note that no branch, tag or other ref reaches, and
+/// whose note no reachable `hash` trailer refers to
+///
+/// Only commits in the local object database count: a note fetched for a
+/// commit that only exists on the remote is a teammate's, not an orphan.
+pub fn find_orphaned(cwd: Option<&Path>) -> Result<Vec<String>> {
+    let noted = git::list_noted_commits(cwd)?;
+    if noted.is_empty() {
+        return Ok(noted);
+    }
+
+    // Reflogs don't count: a commit only they reference is already abandoned.
+    // A commit missing here may still be on the remote the note came from.
+    let reachable: HashSet<String> = git::rev_list("--all", &[], cwd)?.into_iter().collect();
+    let unreachable = noted
+        .into_iter()
+        .filter(|sha| !reachable.contains(sha) && git::has_commit(sha, cwd));
+
+    let digests = trailer::referenced_digests(cwd)?;
+    if digests.is_empty() {
+        return Ok(unreachable.collect());
+    }
+    Ok(unreachable
+        .filter(|sha| {
+            // A note that can't be read is kept rather than guessed about
+            manifest::load_raw(sha, cwd)
+                .and_then(|m| trailer::digest(&m))
+                .is_ok_and(|d| !digests.contains(&d))
+        })
+        .collect())
+}
+
+/// Path an orphaned note is archived to
+pub fn archive_path(gip_dir: &Path, commit_sha: &str) -> PathBuf {
+    gip_dir
+        .join(ARCHIVE_DIR)
+        .join(format!("{}.toon", commit_sha))
+}
+
+/// Write a note's raw content to `<gip_dir>/archive/<sha>.toon`
+pub fn archive(gip_dir: &Path, commit_sha: &str, note: &str) -> Result<PathBuf> {
+    let path = archive_path(gip_dir, commit_sha);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context("Failed to create archive directory")?;
+    }
+    atomic::write(&path, note).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(path)
+}
+
+/// Remove the gip notes of the given commits
+pub fn prune(commits: &[String], cwd: Option<&Path>) -> Result<()> {
+    if commits.is_empty() {
+        return Ok(());
+    }
+
+    let ref_arg = format!("--ref={}", git::notes_ref());
+    let mut args = vec!["notes", &ref_arg, "remove", "--ignore-missing"];
+    args.extend(commits.iter().map(|s| s.as_str()));
+    git::run_git_cmd(&args, cwd)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{clone_repo, init_repo};
+
+    #[test]
+    fn test_archive_writes_raw_note() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = archive(temp_dir.path(), "abc123", "schemaVersion: \"2.0\"").unwrap();
+
+        assert_eq!(path, temp_dir.path().join("archive").join("abc123.toon"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "schemaVersion: \"2.0\"");
+    }
+
+    #[test]
+    fn test_notes_behind_hash_trailers_are_kept() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Some(temp_dir.path());
+        init_repo(temp_dir.path());
+        git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", "init"], dir).unwrap();
+
+        // Two commits on a branch that is then dropped, as after a squash merge
+        git::run_git_cmd(&["checkout", "-q", "-b", "topic"], dir).unwrap();
+        let mut digests = Vec::new();
+        let mut commits = Vec::new();
+        for issue in ["PAY-1", "PAY-2"] {
+            git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", issue], dir).unwrap();
+            let sha = git::run_git_cmd(&["rev-parse", "HEAD"], dir).unwrap();
+            let mut m = manifest::Manifest::new(sha.clone());
+            m.issues = vec![issue.to_string()];
+            manifest::save(&m, &sha, dir).unwrap();
+            digests.push(trailer::digest(&m).unwrap());
+            commits.push(sha);
+        }
+        git::run_git_cmd(&["checkout", "-q", "main"], dir).unwrap();
+        git::run_git_cmd(&["branch", "-q", "-D", "topic"], dir).unwrap();
+
+        // Only the first one's manifest is still referenced from main
+        let message = trailer::append_to_message("Squashed", &digests[0]);
+        git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", &message], dir).unwrap();
+
+        assert_eq!(find_orphaned(dir).unwrap(), vec![commits[1].clone()]);
+    }
+
+    #[test]
+    fn test_notes_of_commits_missing_locally_are_kept() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (upstream, local) = (
+            temp_dir.path().join("upstream"),
+            temp_dir.path().join("local"),
+        );
+        let up = Some(upstream.as_path());
+        fs::create_dir_all(&upstream).unwrap();
+        init_repo(&upstream);
+        git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", "init"], up).unwrap();
+        clone_repo(&upstream, &local);
+
+        // A teammate's commit, whose note arrives without the commit itself
+        git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", "later"], up).unwrap();
+        let sha = git::run_git_cmd(&["rev-parse", "HEAD"], up).unwrap();
+        manifest::save(&manifest::Manifest::new(sha.clone()), &sha, up).unwrap();
+        let refspec = format!("{0}:{0}", git::notes_ref_full());
+        let local = Some(local.as_path());
+        git::run_git_cmd(
+            &["fetch", "-q", upstream.to_str().unwrap(), &refspec],
+            local,
+        )
+        .unwrap();
+
+        assert_eq!(git::list_noted_commits(local).unwrap(), vec![sha.clone()]);
+        assert!(!git::has_commit(&sha, local));
+        assert!(find_orphaned(local).unwrap().is_empty());
+    }
+}
@@ -3,9 +3,53 @@
 //! Provides functions for executing Git commands, retrieving commit information,
 //! and configuring Gip's custom merge driver.
 
+use crate::config::Config;
+use crate::errors::ErrorCode;
+use crate::output;
 use anyhow::{Context, Result};
+use serde::Deserialize;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::{debug, trace};
+
+/// Git subcommands that never change refs
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "blame",
+    "cat-file",
+    "config",
+    "diff",
+    "log",
+    "ls-files",
+    "ls-remote",
+    "merge-base",
+    "merge-tree",
+    "rev-list",
+    "rev-parse",
+    "show",
+    "status",
+    "symbolic-ref",
+];
+
+/// Bumped whenever a git command that may rewrite notes runs
+static WRITE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Changes whenever gip has run a git command that may have changed refs,
+/// including notes; caches of git data compare it to know they are stale
+pub fn write_generation() -> u64 {
+    WRITE_GENERATION.load(Ordering::Relaxed)
+}
+
+fn track_writes<S: AsRef<str>>(args: &[S]) {
+    let subcommand = args.first().map(|a| a.as_ref()).unwrap_or_default();
+    let read_only = READ_ONLY_COMMANDS.contains(&subcommand)
+        || (subcommand == "notes" && args.iter().any(|a| matches!(a.as_ref(), "show" | "list")));
+    if !read_only {
+        WRITE_GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+}
 
 /// Helper to run git command with optional CWD
 pub fn run_git_cmd(args: &[&str], cwd: Option<&Path>) -> Result<String> {
@@ -15,7 +59,11 @@ pub fn run_git_cmd(args: &[&str], cwd: Option<&Path>) -> Result<String> {
         cmd.current_dir(dir);
     }
 
+    let started = Instant::now();
     let output = cmd.output().context("Failed to execute git command")?;
+    track_writes(args);
+    log_command(&args.join(" "), started.elapsed(), output.status);
+    trace_output(&output);
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -28,6 +76,72 @@ pub fn run_git_cmd(args: &[&str], cwd: Option<&Path>) -> Result<String> {
         .to_string())
 }
 
+/// Run git with the terminal attached, for commands whose output the user sees
+///
+/// With a structured `--format`, git's stdout goes to stderr so it can't mix
+/// with the result document.
+pub fn run_git_status(args: &[String]) -> Result<ExitStatus> {
+    let mut cmd = Command::new("git");
+    cmd.args(args);
+    if !output::is_human() {
+        cmd.stdout(std::io::stderr());
+    }
+
+    let started = Instant::now();
+    let status = cmd
+        .status()
+        .with_context(|| format!("Failed to execute git {}", args.first().map_or("", |a| a)))?;
+    track_writes(args);
+    log_command(&args.join(" "), started.elapsed(), status);
+    Ok(status)
+}
+
+/// Run git with the terminal left exactly as it is, for commands opening an editor
+///
+/// Unlike [`run_git_status`], stdout is never redirected, so terminal editors
+/// keep working whatever `--format` says.
+pub fn run_git_attached(args: &[String]) -> Result<ExitStatus> {
+    let started = Instant::now();
+    let status = Command::new("git")
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to execute git {}", args.first().map_or("", |a| a)))?;
+    track_writes(args);
+    log_command(&args.join(" "), started.elapsed(), status);
+    Ok(status)
+}
+
+/// Run git and capture its output without checking the exit status
+pub fn run_git_output(args: &[String]) -> Result<Output> {
+    let started = Instant::now();
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to execute git {}", args.first().map_or("", |a| a)))?;
+    track_writes(args);
+    log_command(&args.join(" "), started.elapsed(), output.status);
+    trace_output(&output);
+    Ok(output)
+}
+
+/// Log a finished git command at `-v`
+fn log_command(command: &str, elapsed: Duration, status: ExitStatus) {
+    if status.success() {
+        debug!("git {} ({:.1?})", command, elapsed);
+    } else {
+        debug!("git {} ({:.1?}, {})", command, elapsed, status);
+    }
+}
+
+/// Log what a git command wrote to stderr at `-vv`
+fn trace_output(output: &Output) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        trace!("git stderr: {}", stderr.trim_end());
+    }
+    trace!("git stdout: {} byte(s)", output.stdout.len());
+}
+
 /// Check if current directory is a Git repository
 pub fn is_git_repo() -> bool {
     run_git_cmd(&["rev-parse", "--git-dir"], None).is_ok()
@@ -35,7 +149,8 @@ pub fn is_git_repo() -> bool {
 
 /// Get the root directory of the Git repository
 pub fn get_repo_root() -> Result<PathBuf> {
-    let path = run_git_cmd(&["rev-parse", "--show-toplevel"], None)?;
+    let path = run_git_cmd(&["rev-parse", "--show-toplevel"], None)
+        .context(ErrorCode::NotARepository.error("Not a git repository"))?;
     Ok(PathBuf::from(path))
 }
 
@@ -44,6 +159,120 @@ pub fn get_current_commit() -> Result<String> {
     run_git_cmd(&["rev-parse", "HEAD"], None)
 }
 
+/// Name of the checked-out branch, `None` when HEAD is detached
+pub fn current_branch(cwd: Option<&Path>) -> Option<String> {
+    run_git_cmd(&["symbolic-ref", "--short", "-q", "HEAD"], cwd).ok()
+}
+
+/// A multi-step operation git stopped in the middle of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Merge,
+    Rebase,
+    CherryPick,
+    Revert,
+    /// `git am`, which shares its state directory with the apply backend of rebase
+    Am,
+}
+
+impl Operation {
+    /// The git command running the operation
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operation::Merge => "merge",
+            Operation::Rebase => "rebase",
+            Operation::CherryPick => "cherry-pick",
+            Operation::Revert => "revert",
+            Operation::Am => "am",
+        }
+    }
+}
+
+/// Whether `name` exists inside the git directory, e.g. `rebase-merge`
+pub fn git_path_exists(name: &str, cwd: Option<&Path>) -> bool {
+    run_git_cmd(&["rev-parse", "--git-path", name], cwd)
+        .map(|path| match cwd {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        })
+        .is_ok_and(|path| path.exists())
+}
+
+/// Whether the commit `sha` is in the object database at `cwd`
+///
+/// A note can name a commit that isn't: notes are fetched on their own, so
+/// they also arrive for commits that only exist on the remote.
+pub fn has_commit(sha: &str, cwd: Option<&Path>) -> bool {
+    run_git_cmd(&["cat-file", "-e", &format!("{}^{{commit}}", sha)], cwd).is_ok()
+}
+
+/// The operation in progress in the repository at `cwd`, if any
+pub fn operation_in_progress(cwd: Option<&Path>) -> Option<Operation> {
+    let exists = |name: &str| git_path_exists(name, cwd);
+    if exists("rebase-merge") {
+        Some(Operation::Rebase)
+    } else if exists("rebase-apply") {
+        Some(if exists("rebase-apply/applying") {
+            Operation::Am
+        } else {
+            Operation::Rebase
+        })
+    } else if exists("MERGE_HEAD") {
+        Some(Operation::Merge)
+    } else if exists("CHERRY_PICK_HEAD") {
+        Some(Operation::CherryPick)
+    } else if exists("REVERT_HEAD") {
+        Some(Operation::Revert)
+    } else {
+        None
+    }
+}
+
+/// A path git left unmerged in the index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnmergedEntry {
+    pub path: String,
+    /// Two-letter state such as `UU` (both modified) or `UD` (deleted by them)
+    pub state: String,
+    /// Modes of the base, ours and theirs stages, `000000` for a missing stage
+    pub modes: [String; 3],
+}
+
+/// Unmerged paths of the repository at `cwd`, with what each side did to them
+///
+/// Deleted-by-us/them and mode conflicts may leave nothing (or nothing with
+/// markers) in the worktree, so only the index tells them apart.
+pub fn unmerged_entries(cwd: Option<&Path>) -> Result<Vec<UnmergedEntry>> {
+    let output = run_git_cmd(&["status", "--porcelain=v2", "-z"], cwd)?;
+    Ok(parse_unmerged(&output))
+}
+
+fn parse_unmerged(porcelain: &str) -> Vec<UnmergedEntry> {
+    let mut entries = Vec::new();
+    let mut records = porcelain.split('\0');
+    while let Some(record) = records.next() {
+        if record.starts_with("2 ") {
+            // A rename is followed by its original path
+            records.next();
+            continue;
+        }
+        let Some(rest) = record.strip_prefix("u ") else {
+            continue;
+        };
+        // <XY> <sub> <m1> <m2> <m3> <mW> <h1> <h2> <h3> <path>
+        let fields: Vec<&str> = rest.splitn(10, ' ').collect();
+        if fields.len() < 10 {
+            continue;
+        }
+        entries.push(UnmergedEntry {
+            path: fields[9].to_string(),
+            state: fields[0].to_string(),
+            modes: [fields[2], fields[3], fields[4]].map(str::to_string),
+        });
+    }
+    entries
+}
+
 /// Get the diff of staged changes
 pub fn get_staged_diff() -> Result<String> {
     run_git_cmd(&["diff", "--cached"], None)
@@ -54,10 +283,195 @@ pub fn has_staged_changes() -> bool {
     run_git_cmd(&["diff", "--cached", "--quiet"], None).is_err()
 }
 
+/// List commit SHAs reachable from a revision, newest first
+///
+/// Extra `git rev-list` arguments (e.g. `--author=...`, `-- <path>`) are appended.
+pub fn rev_list(rev: &str, extra_args: &[String], cwd: Option<&Path>) -> Result<Vec<String>> {
+    let mut args = vec!["rev-list", rev];
+    args.extend(extra_args.iter().map(|s| s.as_str()));
+
+    let output = run_git_cmd(&args, cwd)?;
+    Ok(output
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect())
+}
+
+/// List commits touching a single file, following renames, newest first
+///
+/// Extra `git log` arguments (e.g. `--author=...`) are placed before the revision.
+pub fn log_follow(
+    rev: &str,
+    path: &str,
+    extra_args: &[String],
+    cwd: Option<&Path>,
+) -> Result<Vec<String>> {
+    let mut args = vec!["log", "--follow", "--format=%H"];
+    args.extend(extra_args.iter().map(|s| s.as_str()));
+    args.extend([rev, "--", path]);
+
+    let output = run_git_cmd(&args, cwd)?;
+    Ok(output
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect())
+}
+
+/// Get every path a file has had in the history of `rev`, following renames
+///
+/// The current path is always the first element. Unknown paths yield just
+/// the path itself.
+pub fn file_path_history(rev: &str, path: &str, cwd: Option<&Path>) -> Vec<String> {
+    let mut paths = vec![path.to_string()];
+
+    let output = match run_git_cmd(
+        &[
+            "log",
+            "--follow",
+            "--name-only",
+            "--format=",
+            rev,
+            "--",
+            path,
+        ],
+        cwd,
+    ) {
+        Ok(out) => out,
+        Err(_) => return paths,
+    };
+
+    for line in output.lines().map(|l| l.trim()) {
+        if !line.is_empty() && !paths.iter().any(|p| p == line) {
+            paths.push(line.to_string());
+        }
+    }
+
+    paths
+}
+
+/// Compute the stable patch-id of every commit selected by `git log` arguments
+///
+/// Returns `(commit, patch_id)` pairs. Merges and empty commits have no patch
+/// and are left out.
+pub fn patch_ids(log_args: &[&str], cwd: Option<&Path>) -> Result<Vec<(String, String)>> {
+    let mut log = Command::new("git");
+    log.args(["log", "-p", "--format=commit %H"]).args(log_args);
+    if let Some(dir) = cwd {
+        log.current_dir(dir);
+    }
+    let started = Instant::now();
+    let mut log = log
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to execute git log")?;
+    let log_out = log.stdout.take().context("Failed to read git log output")?;
+
+    let mut patch_id = Command::new("git");
+    patch_id
+        .args(["patch-id", "--stable"])
+        .stdin(Stdio::from(log_out));
+    if let Some(dir) = cwd {
+        patch_id.current_dir(dir);
+    }
+    let output = patch_id
+        .output()
+        .context("Failed to execute git patch-id")?;
+
+    let log_status = log.wait()?;
+    log_command(
+        &format!("log -p {} | git patch-id --stable", log_args.join(" ")),
+        started.elapsed(),
+        log_status,
+    );
+    if !log_status.success() {
+        anyhow::bail!("Git command failed: git log {}", log_args.join(" "));
+    }
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git command failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    // Each line is "<patch-id> <commit>"
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|l| {
+            let mut parts = l.split_whitespace();
+            let id = parts.next()?;
+            let commit = parts.next()?;
+            Some((commit.to_string(), id.to_string()))
+        })
+        .collect())
+}
+
+/// Notes ref used when none is configured
+pub const DEFAULT_NOTES_REF: &str = "gip";
+
+/// `[notes]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct NotesConfig {
+    /// Notes ref holding manifests, as `name` or `refs/notes/name`
+    #[serde(rename = "ref")]
+    pub ref_name: String,
+    /// Write manifests encrypted, see [`crate::encryption`]
+    pub encrypt: bool,
+    /// Key file, outside the repository; `~/.config/gip/notes.key` by default
+    pub key_file: Option<String>,
+}
+
+impl Default for NotesConfig {
+    fn default() -> Self {
+        Self {
+            ref_name: DEFAULT_NOTES_REF.to_string(),
+            encrypt: false,
+            key_file: None,
+        }
+    }
+}
+
+/// Short name of the notes ref holding manifests, `gip` unless configured
+///
+/// Resolved once per process from the current repository's config, so
+/// `GIP_NOTES_REF` can point CI at separate notes.
+pub fn notes_ref() -> &'static str {
+    static NOTES_REF: OnceLock<String> = OnceLock::new();
+    NOTES_REF.get_or_init(|| {
+        let configured = get_gip_dir()
+            .ok()
+            .and_then(|dir| Config::load(&dir).ok())
+            .map(|config| config.notes.ref_name)
+            .unwrap_or_else(|| DEFAULT_NOTES_REF.to_string());
+        match configured.trim().trim_start_matches("refs/notes/") {
+            "" => DEFAULT_NOTES_REF.to_string(),
+            name => name.to_string(),
+        }
+    })
+}
+
+/// Full name of the notes ref, `refs/notes/gip` by default
+pub fn notes_ref_full() -> String {
+    format!("refs/notes/{}", notes_ref())
+}
+
 /// Add a note to a commit using the custom gip ref
 pub fn add_note(commit_sha: &str, content: &str, cwd: Option<&Path>) -> Result<()> {
+    add_note_to_ref(notes_ref(), commit_sha, content, cwd)
+}
+
+/// Add a note to a commit under `refs/notes/<notes_ref>`
+pub fn add_note_to_ref(
+    notes_ref: &str,
+    commit_sha: &str,
+    content: &str,
+    cwd: Option<&Path>,
+) -> Result<()> {
+    let ref_arg = format!("--ref={}", notes_ref);
     run_git_cmd(
-        &["notes", "--ref=gip", "add", "-f", "-m", content, commit_sha],
+        &["notes", &ref_arg, "add", "-f", "-m", content, commit_sha],
         cwd,
     )?;
     Ok(())
@@ -65,18 +479,301 @@ pub fn add_note(commit_sha: &str, content: &str, cwd: Option<&Path>) -> Result<(
 
 /// Get a note from a commit using the custom gip ref
 pub fn get_note(commit_sha: &str, cwd: Option<&Path>) -> Result<String> {
-    run_git_cmd(&["notes", "--ref=gip", "show", commit_sha], cwd)
+    get_note_in(notes_ref(), commit_sha, cwd)
+}
+
+/// [`get_note`] from another notes ref, e.g. a remote's
+pub fn get_note_in(notes_ref: &str, commit_sha: &str, cwd: Option<&Path>) -> Result<String> {
+    let ref_arg = format!("--ref={}", notes_ref);
+    run_git_cmd(&["notes", &ref_arg, "show", commit_sha], cwd)
+}
+
+/// The note blob of every commit that carries a gip note, as `(commit, blob)`
+///
+/// Returns an empty list when the notes ref does not exist yet.
+pub fn list_note_blobs(cwd: Option<&Path>) -> Result<Vec<(String, String)>> {
+    list_note_blobs_in(notes_ref(), cwd)
+}
+
+/// [`list_note_blobs`] of another notes ref, e.g. `gip-redacted`
+pub fn list_note_blobs_in(notes_ref: &str, cwd: Option<&Path>) -> Result<Vec<(String, String)>> {
+    let ref_arg = format!("--ref={}", notes_ref);
+    let output = match run_git_cmd(&["notes", &ref_arg, "list"], cwd) {
+        Ok(out) => out,
+        Err(_) => return Ok(Vec::new()),
+    };
+    Ok(output
+        .lines()
+        .filter_map(|l| l.split_once(' '))
+        .map(|(blob, commit)| (commit.to_string(), blob.to_string()))
+        .collect())
+}
+
+/// Read many blobs with one `git cat-file --batch`, in order, trimmed like
+/// [`run_git_cmd`] output
+pub fn cat_blobs(blobs: &[String], cwd: Option<&Path>) -> Result<Vec<String>> {
+    if blobs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut cmd = Command::new("git");
+    cmd.args(["cat-file", "--batch"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = cwd {
+        cmd.current_dir(dir);
+    }
+    let started = Instant::now();
+    let mut child = cmd.spawn().context("Failed to execute git cat-file")?;
+    let mut stdin = child
+        .stdin
+        .take()
+        .context("Failed to write to git cat-file")?;
+    let input: String = blobs.iter().map(|b| format!("{}\n", b)).collect();
+    // Written from a thread so a full stdout pipe can't stall both sides
+    let writer = std::thread::spawn(move || {
+        use std::io::Write;
+        stdin.write_all(input.as_bytes())
+    });
+    let output = child
+        .wait_with_output()
+        .context("Failed to execute git cat-file")?;
+    let _ = writer.join();
+    log_command(
+        &format!("cat-file --batch ({} objects)", blobs.len()),
+        started.elapsed(),
+        output.status,
+    );
+    trace_output(&output);
+    if !output.status.success() {
+        anyhow::bail!(
+            "Git command failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    // Each object is "<oid> <type> <size>\n<content>\n", or "<oid> missing\n"
+    let out = output.stdout;
+    let mut contents = Vec::with_capacity(blobs.len());
+    let mut pos = 0;
+    while pos < out.len() {
+        let end = out[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(out.len(), |i| pos + i);
+        let header = String::from_utf8_lossy(&out[pos..end]).to_string();
+        pos = end + 1;
+        let size: usize = match header.split(' ').nth(2).and_then(|s| s.parse().ok()) {
+            Some(size) => size,
+            None => anyhow::bail!("Git object missing: {}", header),
+        };
+        let body = out
+            .get(pos..pos + size)
+            .context("Truncated git cat-file output")?;
+        contents.push(
+            String::from_utf8(body.to_vec())
+                .context("Invalid UTF-8 in git output")?
+                .trim()
+                .to_string(),
+        );
+        pos += size + 1;
+    }
+    Ok(contents)
+}
+
+/// List the commits that carry a gip note
+///
+/// Returns an empty list when the notes ref does not exist yet.
+pub fn list_noted_commits(cwd: Option<&Path>) -> Result<Vec<String>> {
+    let ref_arg = format!("--ref={}", notes_ref());
+    let output = match run_git_cmd(&["notes", &ref_arg, "list"], cwd) {
+        Ok(out) => out,
+        Err(_) => return Ok(Vec::new()),
+    };
+
+    // Each line is "<note-blob> <annotated-commit>"
+    Ok(output
+        .lines()
+        .filter_map(|l| l.split_whitespace().nth(1))
+        .map(|s| s.to_string())
+        .collect())
 }
 
 /// Push gip notes to remote
 pub fn push_notes(remote: &str) -> Result<()> {
-    run_git_cmd(&["push", remote, "refs/notes/gip"], None)?;
+    run_git_cmd(&["push", remote, &notes_ref_full()], None)?;
+    Ok(())
+}
+
+/// Push a local notes ref to the remote's gip notes ref
+pub fn push_notes_from(remote: &str, local_ref: &str) -> Result<()> {
+    let refspec = format!("refs/notes/{}:{}", local_ref, notes_ref_full());
+    run_git_cmd(&["push", remote, &refspec], None)?;
     Ok(())
 }
 
-/// Fetch gip notes from remote
+/// Fetch a remote's gip notes to [`remote_notes_ref`]
+///
+/// Nothing local changes; [`merge_notes`] folds them into the gip notes.
 pub fn fetch_notes(remote: &str) -> Result<()> {
-    run_git_cmd(&["fetch", remote, "refs/notes/gip:refs/notes/gip"], None)?;
+    run_git_cmd(&["fetch", remote, &notes_fetch_refspec(remote)], None)?;
+    Ok(())
+}
+
+/// Refspec that copies the gip notes as they are, e.g. to or from a bundle
+pub fn notes_refspec() -> String {
+    format!("{0}:{0}", notes_ref_full())
+}
+
+/// Where a remote's gip notes are fetched to, `refs/notes/remotes/<remote>/gip`
+pub fn remote_notes_ref(remote: &str) -> String {
+    format!("refs/notes/remotes/{}/{}", remote, notes_ref())
+}
+
+/// Refspec fetching a remote's gip notes to [`remote_notes_ref`]
+///
+/// Forced, so notes that diverged from ours never fail a fetch; they are
+/// merged afterwards instead.
+pub fn notes_fetch_refspec(remote: &str) -> String {
+    format!("+{}:{}", notes_ref_full(), remote_notes_ref(remote))
+}
+
+/// Ref inside the git directory that exists while a notes merge is unfinished
+const NOTES_MERGE_PARTIAL: &str = "NOTES_MERGE_PARTIAL";
+
+/// Directory inside the git directory holding the notes a merge conflicted on
+const NOTES_MERGE_WORKTREE: &str = "NOTES_MERGE_WORKTREE";
+
+/// Merge the notes at `from` into the gip notes at `cwd`
+///
+/// Returns the directory git left the conflicting notes in, one file per
+/// commit, when both sides changed the note of a commit. Finish the merge with
+/// [`commit_notes_merge`] or [`abort_notes_merge`]. A missing `from` ref
+/// merges nothing.
+pub fn merge_notes(from: &str, cwd: Option<&Path>) -> Result<Option<PathBuf>> {
+    if run_git_cmd(&["rev-parse", "--verify", "-q", from], cwd).is_err() {
+        return Ok(None);
+    }
+    if git_path_exists(NOTES_MERGE_PARTIAL, cwd) {
+        anyhow::bail!(
+            "A notes merge is already in progress; finish it with `git notes merge --commit` or `--abort`"
+        );
+    }
+
+    let ref_arg = format!("--ref={}", notes_ref());
+    match run_git_cmd(&["notes", &ref_arg, "merge", "-q", from], cwd) {
+        Ok(_) => Ok(None),
+        Err(_) if git_path_exists(NOTES_MERGE_PARTIAL, cwd) => {
+            let path = run_git_cmd(&["rev-parse", "--git-path", NOTES_MERGE_WORKTREE], cwd)?;
+            Ok(Some(match cwd {
+                Some(dir) => dir.join(path),
+                None => PathBuf::from(path),
+            }))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Conclude a conflicted [`merge_notes`] with the notes left in its directory
+pub fn commit_notes_merge(cwd: Option<&Path>) -> Result<()> {
+    let ref_arg = format!("--ref={}", notes_ref());
+    run_git_cmd(&["notes", &ref_arg, "merge", "--commit"], cwd)?;
+    Ok(())
+}
+
+/// Give up a conflicted [`merge_notes`], keeping the gip notes as they were
+pub fn abort_notes_merge(cwd: Option<&Path>) -> Result<()> {
+    let ref_arg = format!("--ref={}", notes_ref());
+    run_git_cmd(&["notes", &ref_arg, "merge", "--abort"], cwd)?;
+    Ok(())
+}
+
+/// The notes refspecs configured in `remote.<remote>.fetch`
+///
+/// Includes the plain `refs/notes/gip:refs/notes/gip` older gips configured,
+/// which fails every fetch once notes diverge.
+pub fn configured_notes_refspecs(remote: &str, cwd: Option<&Path>) -> Vec<String> {
+    let key = format!("remote.{}.fetch", remote);
+    let ours = [notes_fetch_refspec(remote), notes_refspec()];
+    run_git_cmd(&["config", "--get-all", &key], cwd)
+        .unwrap_or_default()
+        .lines()
+        .filter(|l| {
+            ours.iter()
+                .any(|r| l.trim_start_matches('+') == r.trim_start_matches('+'))
+        })
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// Make plain `git fetch <remote>` also fetch gip notes, to [`remote_notes_ref`]
+///
+/// Replaces the refspec older gips configured. Returns `false` if the refspec
+/// was already configured.
+pub fn configure_notes_fetch(remote: &str, cwd: Option<&Path>) -> Result<bool> {
+    let key = format!("remote.{}.fetch", remote);
+    let refspec = notes_fetch_refspec(remote);
+    let configured = configured_notes_refspecs(remote, cwd);
+    if configured.contains(&refspec) {
+        return Ok(false);
+    }
+
+    if !configured.is_empty() {
+        unconfigure_notes_fetch(remote, cwd)?;
+    }
+    run_git_cmd(&["config", "--add", &key, &refspec], cwd)?;
+    Ok(true)
+}
+
+/// Undo [`configure_notes_fetch`]
+///
+/// Returns `false` if no notes refspec was configured.
+pub fn unconfigure_notes_fetch(remote: &str, cwd: Option<&Path>) -> Result<bool> {
+    let key = format!("remote.{}.fetch", remote);
+    let configured = configured_notes_refspecs(remote, cwd);
+    for refspec in &configured {
+        // git matches values by regex; refs can contain `.` and `+` leads forced refspecs
+        let escaped: String = refspec
+            .chars()
+            .flat_map(|c| match c {
+                '.' | '+' | '*' | '?' | '[' | ']' | '(' | ')' | '{' | '}' | '^' | '$' | '|'
+                | '\\' => vec!['\\', c],
+                c => vec![c],
+            })
+            .collect();
+        let pattern = format!("^{}$", escaped);
+        run_git_cmd(&["config", "--unset-all", &key, &pattern], cwd)?;
+    }
+    Ok(!configured.is_empty())
+}
+
+/// Directory `git clone` creates for a repository URL
+///
+/// Mirrors git's own rule: the last path component without `.git`.
+pub fn clone_dir_name(url: &str) -> String {
+    let trimmed = url.trim_end_matches('/');
+    let trimmed = trimmed.strip_suffix(".git").unwrap_or(trimmed);
+    let trimmed = trimmed.trim_end_matches('/');
+    trimmed
+        .rsplit(['/', ':', '\\'])
+        .next()
+        .unwrap_or(trimmed)
+        .to_string()
+}
+
+/// Open `path` in the editor git would use: GIT_EDITOR, core.editor, VISUAL, EDITOR
+pub fn run_editor(path: &Path) -> Result<()> {
+    let editor = run_git_cmd(&["var", "GIT_EDITOR"], None)?;
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().context("No editor configured")?;
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to start editor '{}'", editor))?;
+    if !status.success() {
+        anyhow::bail!("Editor exited with {}", status);
+    }
     Ok(())
 }
 
@@ -102,6 +799,7 @@ pub fn ensure_gip_dir() -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::init_repo;
 
     #[test]
     fn test_is_git_repo() {
@@ -110,6 +808,103 @@ mod tests {
         let _ = is_git_repo();
     }
 
+    #[test]
+    fn test_parse_unmerged() {
+        let h = "1111111111111111111111111111111111111111";
+        let z = "0000000000000000000000000000000000000000";
+        let porcelain = format!(
+            "1 .M N... 100644 100644 100644 {h} {h} src/lib.rs\0\
+             2 R. N... 100644 100644 100644 {h} {h} R100 u new.rs\0u old.rs\0\
+             u UD N... 100644 100644 000000 100644 {h} {h} {z} src/pay me.rs\0\
+             u UU N... 100644 100644 100755 100755 {h} {h} {h} run.sh\0\
+             ? notes.txt\0"
+        );
+        let entries = parse_unmerged(&porcelain);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, "src/pay me.rs");
+        assert_eq!(entries[0].state, "UD");
+        assert_eq!(entries[0].modes[2], "000000");
+        assert_eq!(entries[1].path, "run.sh");
+        assert_eq!(
+            entries[1].modes,
+            ["100644", "100644", "100755"].map(String::from)
+        );
+    }
+
+    #[test]
+    fn test_operation_in_progress() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir = Some(temp_dir.path());
+        let file = temp_dir.path().join("pay.rs");
+        init_repo(temp_dir.path());
+        std::fs::write(&file, "a\n").unwrap();
+        run_git_cmd(&["add", "."], dir).unwrap();
+        run_git_cmd(&["commit", "-q", "-m", "init"], dir).unwrap();
+        run_git_cmd(&["checkout", "-q", "-b", "feat"], dir).unwrap();
+        std::fs::write(&file, "b\n").unwrap();
+        run_git_cmd(&["commit", "-q", "-am", "feat"], dir).unwrap();
+        run_git_cmd(&["checkout", "-q", "main"], dir).unwrap();
+        std::fs::write(&file, "c\n").unwrap();
+        run_git_cmd(&["commit", "-q", "-am", "main"], dir).unwrap();
+        assert_eq!(operation_in_progress(dir), None);
+
+        assert!(run_git_cmd(&["merge", "feat"], dir).is_err());
+        assert_eq!(operation_in_progress(dir), Some(Operation::Merge));
+        run_git_cmd(&["merge", "--abort"], dir).unwrap();
+
+        assert!(run_git_cmd(&["cherry-pick", "feat"], dir).is_err());
+        assert_eq!(operation_in_progress(dir), Some(Operation::CherryPick));
+        run_git_cmd(&["cherry-pick", "--abort"], dir).unwrap();
+
+        assert!(run_git_cmd(&["rebase", "feat"], dir).is_err());
+        assert_eq!(operation_in_progress(dir), Some(Operation::Rebase));
+    }
+
+    #[test]
+    fn test_clone_dir_name() {
+        assert_eq!(clone_dir_name("https://github.com/org/repo.git"), "repo");
+        assert_eq!(clone_dir_name("git@github.com:org/repo"), "repo");
+        assert_eq!(clone_dir_name("host:repo.git"), "repo");
+        assert_eq!(clone_dir_name("/srv/git/repo.git/"), "repo");
+        assert_eq!(clone_dir_name("../repo"), "repo");
+    }
+
+    #[test]
+    fn test_notes_fetch_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        run_git_cmd(&["init", "-q"], Some(dir)).unwrap();
+        let key = "remote.origin.fetch";
+        run_git_cmd(
+            &["config", key, "+refs/heads/*:refs/remotes/origin/*"],
+            Some(dir),
+        )
+        .unwrap();
+
+        assert!(configure_notes_fetch("origin", Some(dir)).unwrap());
+        assert!(!configure_notes_fetch("origin", Some(dir)).unwrap());
+        assert_eq!(
+            configured_notes_refspecs("origin", Some(dir)),
+            ["+refs/notes/gip:refs/notes/remotes/origin/gip"]
+        );
+        assert!(unconfigure_notes_fetch("origin", Some(dir)).unwrap());
+
+        // The refspec older gips configured is replaced, and removed on uninstall
+        run_git_cmd(&["config", "--add", key, &notes_refspec()], Some(dir)).unwrap();
+        assert!(configure_notes_fetch("origin", Some(dir)).unwrap());
+        assert_eq!(
+            configured_notes_refspecs("origin", Some(dir)),
+            ["+refs/notes/gip:refs/notes/remotes/origin/gip"]
+        );
+        run_git_cmd(&["config", "--add", key, &notes_refspec()], Some(dir)).unwrap();
+        assert!(unconfigure_notes_fetch("origin", Some(dir)).unwrap());
+        assert!(!unconfigure_notes_fetch("origin", Some(dir)).unwrap());
+        assert_eq!(
+            run_git_cmd(&["config", "--get-all", key], Some(dir)).unwrap(),
+            "+refs/heads/*:refs/remotes/origin/*"
+        );
+    }
+
     #[test]
     fn test_get_gip_dir_structure() {
         // Test the path construction logic
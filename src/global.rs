@@ -0,0 +1,92 @@
+//! Machine-level defaults
+//!
+//! Settings most people want in every repository, such as the marker style,
+//! the LLM command or lint rules, live in `~/.config/gip/`:
+//!
+//! - `config.toml` - defaults for `.gip/config.toml`; a repository's own
+//!   settings win key by key (see [`crate::config::Config::load`])
+//! - `templates/` - a `manifest.toon` template and class fragments used by
+//!   repositories without their own
+//!
+//! `gip init --global` creates both. The directory is
+//! `$XDG_CONFIG_HOME/gip` when that variable is set, and `GIP_CONFIG_DIR`
+//! overrides it entirely.
+
+use crate::config::CONFIG_FILE;
+use crate::template;
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// Starter machine config written by `gip init --global`
+pub const STARTER_CONFIG: &str = r#"# Gip defaults for every repository on this machine.
+# A repository's .gip/config.toml overrides these key by key.
+
+# [markers]
+# summarize = "rules"       # or "llm" / "off"
+# llm_command = "llm -m gpt-4o-mini 'Summarize in one short sentence'"
+
+# [quality]
+# min_score = 60
+
+# [[validation.rules]]
+# name = "auth-is-security"
+# check = 'require(entry.behavior_class contains "security") when path matches "src/auth/**"'
+
+# [cache]
+# persistent = true
+"#;
+
+/// The machine-level gip directory, if a home directory is known
+pub fn dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os("GIP_CONFIG_DIR").filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir).join("gip"));
+    }
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .filter(|d| !d.is_empty())
+        .map(|home| PathBuf::from(home).join(".config").join("gip"))
+}
+
+/// The machine config file, whether or not it exists
+pub fn config_path() -> Option<PathBuf> {
+    dir().map(|dir| dir.join(CONFIG_FILE))
+}
+
+/// What `gip init --global` set up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setup {
+    pub dir: PathBuf,
+    pub created_config: bool,
+    pub created_template: bool,
+}
+
+/// Create the machine config and template, keeping any that exist
+pub fn init() -> Result<Setup> {
+    let dir = dir().context("Cannot find a home directory; set GIP_CONFIG_DIR")?;
+    let config = dir.join(CONFIG_FILE);
+    let template = template::custom_template_path(&dir);
+    fs::create_dir_all(template.parent().expect("template path has a parent"))
+        .with_context(|| format!("Failed to create {:?}", dir))?;
+
+    let created_config = !config.exists();
+    if created_config {
+        fs::write(&config, STARTER_CONFIG)
+            .with_context(|| format!("Failed to write {:?}", config))?;
+    }
+    let created_template = !template.exists();
+    if created_template {
+        fs::write(&template, template::DEFAULT_TEMPLATE)
+            .with_context(|| format!("Failed to write {:?}", template))?;
+    }
+
+    Ok(Setup {
+        dir,
+        created_config,
+        created_template,
+    })
+}
@@ -0,0 +1,205 @@
+//! Graph export of a symbol's intent history
+//!
+//! Renders the commits that touched a symbol as a DOT or Mermaid graph. Nodes are
+//! colored by behaviorClass and edges carry signature changes.
+
+use crate::manifest::{Entry, Manifest};
+use crate::symbols;
+use serde::Serialize;
+
+/// One commit in a symbol's history
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolNode {
+    pub commit: String,
+    pub behavior_class: String,
+    pub rationale: String,
+    /// New signature when this commit changed the symbol's signature
+    pub signature: Option<String>,
+}
+
+/// Collect the nodes for `symbol` from manifests ordered newest first
+///
+/// Entries recorded under one of `aliases`, the symbol's names before or
+/// after a rename, are part of its history too. The result is ordered oldest
+/// first, the natural reading order for a graph.
+pub fn symbol_history(manifests: &[Manifest], symbol: &str, aliases: &[String]) -> Vec<SymbolNode> {
+    let mut nodes: Vec<SymbolNode> = manifests
+        .iter()
+        .filter_map(|m| {
+            let entry = m.entries.iter().find(|e| {
+                e.anchor.symbol == symbol
+                    || aliases
+                        .iter()
+                        .any(|a| a == symbols::base_name(&e.anchor.symbol))
+            })?;
+            Some(node_for(&m.commit, entry))
+        })
+        .collect();
+    nodes.reverse();
+    nodes
+}
+
+fn node_for(commit: &str, entry: &Entry) -> SymbolNode {
+    SymbolNode {
+        commit: commit.to_string(),
+        behavior_class: entry
+            .behavior_class
+            .first()
+            .cloned()
+            .unwrap_or_else(|| "unknown".to_string()),
+        rationale: entry.rationale.clone(),
+        signature: entry
+            .signature_delta
+            .as_ref()
+            .map(|delta| delta.after.clone()),
+    }
+}
+
+/// Fill color used for a behaviorClass
+fn class_color(class: &str) -> &'static str {
+    match class {
+        "feature" => "#b7e1a1",
+        "bugfix" => "#f4a6a6",
+        "refactor" => "#a6c8f4",
+        "perf" => "#f9d38c",
+        "security" => "#d1b3f0",
+        "docs" => "#dddddd",
+        "config" => "#f0e68c",
+        "migration" => "#8fd3d3",
+        _ => "#ffffff",
+    }
+}
+
+fn short_sha(sha: &str) -> &str {
+    &sha[..sha.len().min(7)]
+}
+
+fn short_text(text: &str, max: usize) -> String {
+    let text = text.lines().next().unwrap_or("");
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let cut: String = text.chars().take(max).collect();
+        format!("{}…", cut)
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Render a symbol history as a Graphviz DOT graph
+pub fn render_dot(symbol: &str, nodes: &[SymbolNode]) -> String {
+    let mut output = String::new();
+    output.push_str(&format!("digraph \"{}\" {{\n", escape(symbol)));
+    output.push_str("  rankdir=LR;\n");
+    output.push_str("  node [shape=box, style=\"rounded,filled\"];\n");
+
+    for node in nodes {
+        output.push_str(&format!(
+            "  \"{}\" [label=\"{}\\n{}\\n{}\", fillcolor=\"{}\"];\n",
+            node.commit,
+            short_sha(&node.commit),
+            escape(&node.behavior_class),
+            escape(&short_text(&node.rationale, 40)),
+            class_color(&node.behavior_class)
+        ));
+    }
+
+    for pair in nodes.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+        match to.signature {
+            Some(ref sig) => output.push_str(&format!(
+                "  \"{}\" -> \"{}\" [label=\"{}\", style=bold];\n",
+                from.commit,
+                to.commit,
+                escape(&short_text(sig, 40))
+            )),
+            None => output.push_str(&format!("  \"{}\" -> \"{}\";\n", from.commit, to.commit)),
+        }
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+/// Render a symbol history as a Mermaid flowchart
+pub fn render_mermaid(nodes: &[SymbolNode]) -> String {
+    let mut output = String::from("flowchart LR\n");
+
+    for node in nodes {
+        output.push_str(&format!(
+            "  c{}[\"{}<br/>{}<br/>{}\"]\n",
+            short_sha(&node.commit),
+            short_sha(&node.commit),
+            node.behavior_class,
+            short_text(&node.rationale, 40).replace('"', "'")
+        ));
+        output.push_str(&format!(
+            "  style c{} fill:{}\n",
+            short_sha(&node.commit),
+            class_color(&node.behavior_class)
+        ));
+    }
+
+    for pair in nodes.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+        match to.signature {
+            Some(ref sig) => output.push_str(&format!(
+                "  c{} ==>|\"{}\"| c{}\n",
+                short_sha(&from.commit),
+                short_text(sig, 40).replace('"', "'"),
+                short_sha(&to.commit)
+            )),
+            None => output.push_str(&format!(
+                "  c{} --> c{}\n",
+                short_sha(&from.commit),
+                short_sha(&to.commit)
+            )),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nodes() -> Vec<SymbolNode> {
+        vec![
+            SymbolNode {
+                commit: "aaaaaaaaaa".to_string(),
+                behavior_class: "feature".to_string(),
+                rationale: "Add parser".to_string(),
+                signature: None,
+            },
+            SymbolNode {
+                commit: "bbbbbbbbbb".to_string(),
+                behavior_class: "bugfix".to_string(),
+                rationale: "Handle \"quoted\" input".to_string(),
+                signature: Some("fn parse(s: &str, strict: bool)".to_string()),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_render_dot() {
+        let dot = render_dot("parse", &nodes());
+        assert!(dot.starts_with("digraph \"parse\" {"));
+        assert!(dot.contains("fillcolor=\"#b7e1a1\""));
+        assert!(dot.contains("Handle \\\"quoted\\\" input"));
+        assert!(dot.contains(
+            "\"aaaaaaaaaa\" -> \"bbbbbbbbbb\" [label=\"fn parse(s: &str, strict: bool)\", style=bold];"
+        ));
+    }
+
+    #[test]
+    fn test_render_mermaid() {
+        let mermaid = render_mermaid(&nodes());
+        assert!(mermaid.starts_with("flowchart LR"));
+        assert!(mermaid.contains("style caaaaaaa fill:#b7e1a1"));
+        assert!(mermaid.contains("caaaaaaa ==>|\"fn parse(s: &str, strict: bool)\"| cbbbbbbb"));
+    }
+}
@@ -0,0 +1,138 @@
+//! Intent timeline of a symbol
+//!
+//! `gip history <symbol>` lists every manifest entry recorded for a symbol,
+//! in any file and under every name the symbol index links to it, oldest
+//! first: `git log` for what a function means rather than for its text.
+
+use crate::git;
+use crate::index::{self, SymbolIndex};
+use crate::manifest::{self, Contract, Manifest, SignatureDelta};
+use crate::symbols;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// One recorded change to a symbol
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEvent {
+    pub commit: String,
+    /// Commit date, `YYYY-MM-DD`
+    pub date: String,
+    /// Subject line of the commit
+    pub summary: String,
+    pub file: String,
+    /// Name the symbol had in this commit
+    pub symbol: String,
+    pub change_type: String,
+    pub rationale: String,
+    pub behavior_class: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature_delta: Option<SignatureDelta>,
+    pub contract: Contract,
+    /// Earlier name, when this commit renamed the symbol
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renamed_from: Option<String>,
+}
+
+/// Events for the symbol known as any of `names`, from manifests oldest first
+///
+/// Dates and summaries are left empty; see [`timeline`].
+pub fn events(manifests: &[Manifest], names: &[String]) -> Vec<HistoryEvent> {
+    let mut events = Vec::new();
+    for manifest in manifests {
+        for entry in &manifest.entries {
+            let name = symbols::base_name(&entry.anchor.symbol);
+            if entry.anchor.is_sweeping() || !names.iter().any(|n| n == name) {
+                continue;
+            }
+            events.push(HistoryEvent {
+                commit: manifest.commit.clone(),
+                date: String::new(),
+                summary: String::new(),
+                file: entry.anchor.file.clone(),
+                symbol: name.to_string(),
+                change_type: entry.change_type.clone(),
+                rationale: entry.rationale.clone(),
+                behavior_class: entry.behavior_class.clone(),
+                signature_delta: entry.signature_delta.clone(),
+                contract: entry.contract.clone(),
+                renamed_from: index::detect_rename(entry).map(|(from, _)| from),
+            });
+        }
+    }
+    events
+}
+
+/// The timeline of `symbol` in the history of `HEAD`
+pub fn timeline(
+    index: &SymbolIndex,
+    symbol: &str,
+    repo_path: Option<&Path>,
+) -> Result<Vec<HistoryEvent>> {
+    let mut commits: Vec<&str> = Vec::new();
+    for occurrence in index.occurrences(symbol) {
+        if !commits.contains(&occurrence.commit.as_str()) {
+            commits.push(&occurrence.commit);
+        }
+    }
+    if commits.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let manifests: Vec<Manifest> = commits
+        .iter()
+        .filter_map(|sha| manifest::load(sha, repo_path).ok())
+        .collect();
+    let mut events = events(&manifests, &index.names(symbol));
+
+    let mut args = vec![
+        "log",
+        "--no-walk=unsorted",
+        "--date=short",
+        "--format=%H%x09%ad%x09%s",
+    ];
+    args.extend(commits.iter().copied());
+    let output = git::run_git_cmd(&args, repo_path)?;
+    let described: HashMap<&str, (&str, &str)> = output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            Some((parts.next()?, (parts.next()?, parts.next().unwrap_or(""))))
+        })
+        .collect();
+    for event in &mut events {
+        if let Some((date, summary)) = described.get(event.commit.as_str()) {
+            event.date = date.to_string();
+            event.summary = summary.to_string();
+        }
+    }
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_events_follow_names_across_files() {
+        let manifests: Vec<Manifest> = [
+            r#"{"schemaVersion":"2.0","commit":"a","entries":[{"anchor":{"file":"src/pay.rs","symbol":"charge","hunkId":"H#1"},"changeType":"add","rationale":"Take payments","behaviorClass":["feature"],"contract":{"preconditions":["amount > 0"]}},{"anchor":{"file":"src/pay.rs","symbol":"refund","hunkId":"H#2"},"changeType":"add","rationale":"r","behaviorClass":[],"contract":{}}]}"#,
+            r#"{"schemaVersion":"2.0","commit":"b","entries":[{"anchor":{"file":"src/billing/mod.rs","symbol":"Billing::bill","hunkId":"H#1"},"changeType":"rename","rationale":"Move to billing","behaviorClass":["refactor"],"signatureDelta":{"before":"fn charge(amount: u64)","after":"fn bill(amount: u64)"},"contract":{}}]}"#,
+        ]
+        .iter()
+        .map(|json| serde_json::from_str(json).unwrap())
+        .collect();
+
+        let names = vec!["charge".to_string(), "bill".to_string()];
+        let events = events(&manifests, &names);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].contract.preconditions, vec!["amount > 0"]);
+        assert_eq!(
+            (events[1].file.as_str(), events[1].symbol.as_str()),
+            ("src/billing/mod.rs", "bill")
+        );
+        assert_eq!(events[1].renamed_from.as_deref(), Some("charge"));
+    }
+}
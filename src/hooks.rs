@@ -0,0 +1,154 @@
+//! User-defined lifecycle hooks
+//!
+//! Executables in `.gip/hooks/` named after a [`HookEvent`] (for example
+//! `.gip/hooks/pre-manifest-save`) are invoked at the matching point in gip's
+//! workflow. Each hook receives a JSON payload on stdin:
+//!
+//! ```json
+//! { "event": "pre-manifest-save", "payload": { ... } }
+//! ```
+//!
+//! A failing `pre-*` hook aborts the operation, which makes them suitable for
+//! policy checks. Failures of `post-*` hooks are reported as warnings only.
+//! Hooks git tracks only run in trusted repositories, see [`crate::trust`].
+
+use crate::trust;
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Points in gip's workflow where hooks run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    /// After conflict markers were enriched during a merge or rebase
+    PostEnrich,
+    /// Before a manifest is attached to a commit
+    PreManifestSave,
+    /// After a manifest was loaded by `gip context`
+    PostContextLoad,
+}
+
+impl HookEvent {
+    pub const ALL: [HookEvent; 3] = [
+        HookEvent::PostEnrich,
+        HookEvent::PreManifestSave,
+        HookEvent::PostContextLoad,
+    ];
+
+    /// File name of the hook executable
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::PostEnrich => "post-enrich",
+            HookEvent::PreManifestSave => "pre-manifest-save",
+            HookEvent::PostContextLoad => "post-context-load",
+        }
+    }
+
+    /// Whether a failing hook aborts the operation
+    pub fn is_blocking(self) -> bool {
+        self.name().starts_with("pre-")
+    }
+}
+
+#[derive(Serialize)]
+struct HookInput<'a, T: Serialize> {
+    event: &'a str,
+    payload: &'a T,
+}
+
+/// Locate the executable for an event in `<gip_dir>/hooks/`
+pub fn find(gip_dir: &Path, event: HookEvent) -> Option<PathBuf> {
+    let dir = gip_dir.join("hooks");
+    [event.name().to_string(), format!("{}.exe", event.name())]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Run the hook for an event, if one is installed
+///
+/// Returns an error only when a blocking hook fails; non-blocking failures are
+/// printed as warnings.
+pub fn run<T: Serialize>(gip_dir: &Path, event: HookEvent, payload: &T) -> Result<()> {
+    let program = match find(gip_dir, event) {
+        Some(p) if trust::allows_program(&p) => p,
+        _ => return Ok(()),
+    };
+
+    match invoke(&program, event, payload) {
+        Ok(()) => Ok(()),
+        Err(e) if event.is_blocking() => Err(e),
+        Err(e) => {
+            eprintln!("{}", format!("Warning: {}", e).yellow());
+            Ok(())
+        }
+    }
+}
+
+fn invoke<T: Serialize>(program: &Path, event: HookEvent, payload: &T) -> Result<()> {
+    let input = serde_json::to_string(&HookInput {
+        event: event.name(),
+        payload,
+    })?;
+
+    // Hook output goes to stderr so it never mixes with exported data on stdout
+    let mut child = Command::new(program)
+        .stdin(Stdio::piped())
+        .stdout(std::io::stderr())
+        .spawn()
+        .with_context(|| format!("Failed to start {} hook {:?}", event.name(), program))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook may exit without reading its input
+        let _ = stdin.write_all(input.as_bytes());
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        anyhow::bail!("{} hook {:?} exited with {}", event.name(), program, status);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_hook_is_noop() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert!(find(temp_dir.path(), HookEvent::PreManifestSave).is_none());
+        assert!(run(temp_dir.path(), HookEvent::PreManifestSave, &"payload").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_blocking_and_non_blocking_hooks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let hooks = temp_dir.path().join("hooks");
+        std::fs::create_dir_all(&hooks).unwrap();
+
+        let out = temp_dir.path().join("received.json");
+        for event in [HookEvent::PreManifestSave, HookEvent::PostContextLoad] {
+            let script = hooks.join(event.name());
+            std::fs::write(
+                &script,
+                format!("#!/bin/sh\ncat > {}\nexit 1\n", out.display()),
+            )
+            .unwrap();
+            std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        assert!(run(temp_dir.path(), HookEvent::PreManifestSave, &"abc").is_err());
+        let received = std::fs::read_to_string(&out).unwrap();
+        assert_eq!(received, r#"{"event":"pre-manifest-save","payload":"abc"}"#);
+
+        assert!(run(temp_dir.path(), HookEvent::PostContextLoad, &"abc").is_ok());
+    }
+}
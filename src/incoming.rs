@@ -0,0 +1,221 @@
+//! Breaking changes arriving with a pull or checkout
+//!
+//! After the branch moves, the manifests of the newly arrived commits are
+//! scanned for breaking changes and migration steps. Only entries touching
+//! files you changed recently are reported: uncommitted changes, and files in
+//! your own commits of the last [`RECENT_DAYS`] days.
+
+use crate::git;
+use crate::manifest::{self, Manifest};
+use crate::pathspec;
+use crate::say;
+use anyhow::Result;
+use colored::*;
+use serde::Serialize;
+use std::path::Path;
+
+/// How far back your own commits count as recent work
+pub const RECENT_DAYS: u32 = 30;
+
+/// A breaking change or migration step in an arrived commit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IncomingChange {
+    pub commit: String,
+    pub file: String,
+    pub symbol: String,
+    pub breaking: bool,
+    pub migrations: Vec<String>,
+    pub rationale: String,
+}
+
+/// Breaking changes and migrations in `manifests` touching `touched` files
+///
+/// With `touched` set to `None`, every such change is kept.
+pub fn collect(manifests: &[Manifest], touched: Option<&[String]>) -> Vec<IncomingChange> {
+    let mut changes = Vec::new();
+    for manifest in manifests {
+        for entry in &manifest.entries {
+            let Some(ref compat) = entry.compatibility else {
+                continue;
+            };
+            let migrations: Vec<String> = compat
+                .migrations
+                .iter()
+                .flatten()
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .collect();
+            if !compat.breaking && migrations.is_empty() {
+                continue;
+            }
+
+            let affects = match touched {
+                Some(files) => files
+                    .iter()
+                    .any(|f| pathspec::matches(&entry.anchor.file, f)),
+                None => true,
+            };
+            if affects {
+                changes.push(IncomingChange {
+                    commit: manifest.commit.clone(),
+                    file: entry.anchor.file.clone(),
+                    symbol: entry.anchor.symbol.clone(),
+                    breaking: compat.breaking,
+                    migrations,
+                    rationale: entry.rationale.clone(),
+                });
+            }
+        }
+    }
+    changes
+}
+
+/// Manifests of the commits in `old..new`, oldest first
+///
+/// Commits without a manifest are skipped.
+pub fn arrived(old: &str, new: &str, cwd: Option<&Path>) -> Result<Vec<Manifest>> {
+    let mut shas = git::rev_list(&format!("{}..{}", old, new), &[], cwd)?;
+    shas.reverse();
+    Ok(shas
+        .iter()
+        .filter_map(|sha| manifest::load(sha, cwd).ok())
+        .collect())
+}
+
+/// Files with uncommitted changes or in your commits of the last [`RECENT_DAYS`] days
+pub fn recently_touched(cwd: Option<&Path>) -> Vec<String> {
+    let mut files: Vec<String> = Vec::new();
+    let mut add = |output: String| {
+        for line in output.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if !files.iter().any(|f| f == line) {
+                files.push(line.to_string());
+            }
+        }
+    };
+
+    if let Ok(out) = git::run_git_cmd(&["diff", "--name-only", "HEAD"], cwd) {
+        add(out);
+    }
+    if let Ok(email) = git::run_git_cmd(&["config", "user.email"], cwd) {
+        let author = format!("--author={}", email);
+        let since = format!("--since={}.days.ago", RECENT_DAYS);
+        if let Ok(out) = git::run_git_cmd(
+            &["log", &author, &since, "--name-only", "--format=", "HEAD"],
+            cwd,
+        ) {
+            add(out);
+        }
+    }
+    files
+}
+
+/// Print a summary of incoming changes; nothing when there are none
+pub fn print_summary(changes: &[IncomingChange]) {
+    if changes.is_empty() {
+        return;
+    }
+
+    say!(
+        "\n{}",
+        format!(
+            "⚠ {} incoming breaking change(s) or migration(s) affect files you work on:",
+            changes.len()
+        )
+        .yellow()
+        .bold()
+    );
+    for change in changes {
+        let label = if change.breaking {
+            "breaking".red().to_string()
+        } else {
+            "migration".yellow().to_string()
+        };
+        say!(
+            "  {} {} {} [{}]",
+            change.commit[..change.commit.len().min(8)].cyan(),
+            change.file,
+            change.symbol,
+            label
+        );
+        if !change.rationale.trim().is_empty() {
+            say!("      {}", change.rationale.trim());
+        }
+        for step in &change.migrations {
+            say!("      → {}", step);
+        }
+    }
+    say!("  Run `gip context <sha>` for the full manifest.");
+}
+
+/// Warn about changes between two commits that affect your recent work
+///
+/// Never fails: the branch has already moved, so problems are only reported.
+/// Returns the changes that were reported.
+pub fn warn(old: &str, new: &str) -> Vec<IncomingChange> {
+    if old == new {
+        return Vec::new();
+    }
+    let touched = recently_touched(None);
+    if touched.is_empty() {
+        return Vec::new();
+    }
+    let arrived = arrived(old, new, None).unwrap_or_default();
+    let changes = collect(&arrived, Some(&touched));
+    print_summary(&changes);
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn manifest(commit: &str, file: &str, compatibility: serde_json::Value) -> Manifest {
+        test_support::manifest(
+            commit,
+            json!({
+                "anchor": { "file": file, "symbol": "charge" },
+                "rationale": "Amounts are now u64",
+                "behaviorClass": ["feature"],
+                "compatibility": compatibility
+            }),
+        )
+    }
+
+    #[test]
+    fn test_collect_breaking_and_migrations() {
+        let manifests = vec![
+            manifest("a", "src/pay.rs", json!({"breaking": true})),
+            manifest(
+                "b",
+                "src/db.rs",
+                json!({"breaking": false, "migrations": ["run 0042.sql", " "]}),
+            ),
+            manifest("c", "src/ui.rs", json!({"breaking": false})),
+        ];
+
+        let all = collect(&manifests, None);
+        assert_eq!(all.len(), 2);
+        assert!(all[0].breaking);
+        assert_eq!(all[1].migrations, vec!["run 0042.sql"]);
+    }
+
+    #[test]
+    fn test_collect_only_touched_files() {
+        let manifests = vec![
+            manifest("a", "src/pay.rs", json!({"breaking": true})),
+            manifest("b", "src/generated/**", json!({"breaking": true})),
+        ];
+
+        let touched = vec!["src/pay.rs".to_string()];
+        let changes = collect(&manifests, Some(&touched));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].commit, "a");
+
+        // Directory and glob anchors cover the files below them
+        let touched = vec!["src/generated/api.rs".to_string()];
+        assert_eq!(collect(&manifests, Some(&touched))[0].commit, "b");
+        assert!(collect(&manifests, Some(&[])).is_empty());
+    }
+}
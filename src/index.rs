@@ -0,0 +1,259 @@
+//! Symbol index over manifest history
+//!
+//! Lists, for every symbol named by a manifest entry reachable from `HEAD`,
+//! the commits and files that recorded it, and keeps a table of renames so a
+//! function's history survives a change of name. A rename is recorded when a
+//! signature delta names a different symbol before and after, which is how
+//! `changeType: rename` entries describe the old name.
+//!
+//! Symbols are keyed by their unqualified name (see [`symbols::base_name`]).
+//! With `[cache] persistent = true` the index is kept in
+//! `.gip/cache/symbols.json` and rebuilt only when `HEAD` or the notes move.
+
+use crate::atomic;
+use crate::config::Config;
+use crate::git;
+use crate::manifest::{self, Entry, Manifest, CHANGE_RENAME};
+use crate::symbols;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// File inside `.gip/` holding the persistent index
+pub const INDEX_FILE: &str = "cache/symbols.json";
+
+/// A manifest entry recorded for a symbol
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Occurrence {
+    pub commit: String,
+    pub file: String,
+}
+
+/// A symbol that changed name in a commit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rename {
+    pub commit: String,
+    pub file: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SymbolIndex {
+    /// `HEAD` and notes tip the index was built for
+    key: String,
+    /// Indexed commits, oldest first
+    pub commits: Vec<String>,
+    /// Occurrences of each symbol, oldest first
+    pub symbols: BTreeMap<String, Vec<Occurrence>>,
+    /// Renames, oldest first
+    pub renames: Vec<Rename>,
+}
+
+/// The old and new name of a renamed symbol, if the entry records a rename
+///
+/// Any entry whose signature delta changes the defined name counts, whatever
+/// its `changeType`. When the new signature names nothing recognizable, a
+/// `rename` entry's anchor gives the new name.
+pub fn detect_rename(entry: &Entry) -> Option<(String, String)> {
+    let delta = entry.signature_delta.as_ref()?;
+    let from = symbols::signature_name(&delta.before)?;
+    let to = match symbols::signature_name(&delta.after) {
+        Some(name) => name,
+        None if entry.change_type == CHANGE_RENAME => symbols::base_name(&entry.anchor.symbol),
+        None => return None,
+    };
+    (from != to).then(|| (from.to_string(), to.to_string()))
+}
+
+impl SymbolIndex {
+    /// Index manifests given oldest first
+    pub fn from_manifests(manifests: &[Manifest]) -> Self {
+        let mut index = SymbolIndex::default();
+        for manifest in manifests {
+            index.commits.push(manifest.commit.clone());
+            for entry in &manifest.entries {
+                if entry.anchor.is_sweeping() {
+                    continue;
+                }
+                index
+                    .symbols
+                    .entry(symbols::base_name(&entry.anchor.symbol).to_string())
+                    .or_default()
+                    .push(Occurrence {
+                        commit: manifest.commit.clone(),
+                        file: entry.anchor.file.clone(),
+                    });
+                if let Some((from, to)) = detect_rename(entry) {
+                    index.renames.push(Rename {
+                        commit: manifest.commit.clone(),
+                        file: entry.anchor.file.clone(),
+                        from,
+                        to,
+                    });
+                }
+            }
+        }
+        index
+    }
+
+    /// The index for `HEAD`, from disk when it is still current
+    pub fn load(repo_path: Option<&Path>) -> Result<Self> {
+        let key = format!(
+            "{} {}",
+            git::run_git_cmd(&["rev-parse", "HEAD"], repo_path)?,
+            git::run_git_cmd(
+                &["rev-parse", "-q", "--verify", &git::notes_ref_full()],
+                repo_path
+            )
+            .unwrap_or_default()
+        );
+
+        // Like the note cache, the persistent index only serves the current repository
+        let disk = match repo_path {
+            None => git::get_gip_dir()
+                .ok()
+                .filter(|dir| Config::load(dir).is_ok_and(|c| c.cache.persistent))
+                .map(|dir| dir.join(INDEX_FILE)),
+            Some(_) => None,
+        };
+        if let Some(ref path) = disk {
+            let cached = fs::read_to_string(path)
+                .ok()
+                .and_then(|data| serde_json::from_str::<SymbolIndex>(&data).ok());
+            if let Some(index) = cached.filter(|i| i.key == key) {
+                return Ok(index);
+            }
+        }
+
+        let noted: HashSet<String> = git::list_noted_commits(repo_path)?.into_iter().collect();
+        let commits = git::rev_list("HEAD", &["--reverse".to_string()], repo_path)?;
+        let manifests: Vec<Manifest> = commits
+            .iter()
+            .filter(|sha| noted.contains(*sha))
+            .filter_map(|sha| manifest::load(sha, repo_path).ok())
+            .collect();
+
+        let mut index = Self::from_manifests(&manifests);
+        index.key = key;
+        if let Some(ref path) = disk {
+            // Best effort: a failed write only costs a rebuild next time
+            if let Some(dir) = path.parent() {
+                let _ = fs::create_dir_all(dir);
+            }
+            if let Ok(data) = serde_json::to_string(&index) {
+                let _ = atomic::write(path, data);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Every name of a symbol across renames, oldest first, itself included
+    pub fn names(&self, symbol: &str) -> Vec<String> {
+        let mut names = vec![symbols::base_name(symbol).to_string()];
+        // Follow the chain both ways until no rename adds a name
+        loop {
+            let linked: Vec<&Rename> = self
+                .renames
+                .iter()
+                .filter(|r| names.contains(&r.from) != names.contains(&r.to))
+                .collect();
+            if linked.is_empty() {
+                break;
+            }
+            for rename in linked {
+                for name in [&rename.from, &rename.to] {
+                    if !names.contains(name) {
+                        names.push(name.clone());
+                    }
+                }
+            }
+        }
+
+        let order = self.commit_order();
+        names.sort_by_key(|name| {
+            self.symbols
+                .get(name)
+                .and_then(|found| found.first())
+                .and_then(|o| order.get(o.commit.as_str()).copied())
+                .unwrap_or(usize::MAX)
+        });
+        names
+    }
+
+    /// The other names of a symbol, see [`SymbolIndex::names`]
+    pub fn aliases(&self, symbol: &str) -> Vec<String> {
+        let base = symbols::base_name(symbol);
+        self.names(symbol)
+            .into_iter()
+            .filter(|n| n != base)
+            .collect()
+    }
+
+    /// Occurrences of a symbol under all its names, oldest first
+    pub fn occurrences(&self, symbol: &str) -> Vec<&Occurrence> {
+        let names = self.names(symbol);
+        let order = self.commit_order();
+        let mut found: Vec<&Occurrence> = names
+            .iter()
+            .filter_map(|n| self.symbols.get(n))
+            .flatten()
+            .collect();
+        found.sort_by_key(|o| order.get(o.commit.as_str()).copied().unwrap_or(usize::MAX));
+        found
+    }
+
+    fn commit_order(&self) -> HashMap<&str, usize> {
+        self.commits
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.as_str(), i))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn manifest(commit: &str, symbol: &str, change: &str, delta: Option<(&str, &str)>) -> Manifest {
+        let mut entry = json!({
+            "anchor": { "file": "src/pay.rs", "symbol": symbol },
+            "changeType": change
+        });
+        if let Some((before, after)) = delta {
+            entry["signatureDelta"] = json!({ "before": before, "after": after });
+        }
+        test_support::manifest(commit, entry)
+    }
+
+    #[test]
+    fn test_renames_chain() {
+        let index = SymbolIndex::from_manifests(&[
+            manifest("a", "charge", "add", None),
+            manifest("b", "bill", "rename", Some(("fn charge()", "fn bill()"))),
+            manifest("c", "unrelated", "modify", None),
+            manifest(
+                "d",
+                "Gateway::invoice",
+                "modify",
+                Some(("fn bill(x: u8)", "fn invoice(x: u8)")),
+            ),
+        ]);
+
+        assert_eq!(index.renames.len(), 2);
+        assert_eq!(index.names("invoice"), vec!["charge", "bill", "invoice"]);
+        assert_eq!(index.aliases("charge"), vec!["bill", "invoice"]);
+        let commits: Vec<&str> = index
+            .occurrences("bill")
+            .iter()
+            .map(|o| o.commit.as_str())
+            .collect();
+        assert_eq!(commits, vec!["a", "b", "d"]);
+        assert!(index.aliases("unrelated").is_empty());
+    }
+}
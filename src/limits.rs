@@ -0,0 +1,308 @@
+//! Manifest size limits
+//!
+//! Oversized free-text fields are truncated deterministically at save time,
+//! ending in [`TRUNCATION_SUFFIX`] so readers can tell content is missing.
+//! Structural fields (anchors, commit, change type, behavior classes) are
+//! never truncated. [`crate::manifest::save`] applies the limits, so every
+//! command that writes notes is covered.
+
+use crate::config::Config;
+use crate::errors::ErrorCode;
+use crate::git;
+use crate::manifest::{self, Manifest};
+use crate::say;
+use anyhow::{Context, Result};
+use colored::*;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+/// Marker appended to truncated text
+pub const TRUNCATION_SUFFIX: &str = "…[truncated]";
+
+/// Fields that identify a change rather than describe it
+const PROTECTED_FIELDS: &[&str] = &[
+    "schemaVersion",
+    "commit",
+    "file",
+    "symbol",
+    "hunkId",
+    "changeType",
+    "behaviorClass",
+    "package",
+];
+
+/// `[limits]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct LimitsConfig {
+    /// Maximum size of a single text field, in bytes
+    pub max_field_bytes: usize,
+    /// Maximum size of a serialized manifest, in bytes
+    pub max_manifest_bytes: usize,
+    /// Reject oversized manifests instead of saving them truncated
+    pub strict: bool,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_field_bytes: 4 * 1024,
+            max_manifest_bytes: 64 * 1024,
+            strict: false,
+        }
+    }
+}
+
+/// A limit that a manifest exceeded
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// Dotted path of the field, or `manifest` for the whole document
+    pub field: String,
+    pub size: usize,
+    pub limit: usize,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} is {} bytes (limit {})",
+            self.field, self.size, self.limit
+        )
+    }
+}
+
+/// Truncate a manifest to fit the limits, reporting what was over
+pub fn enforce(manifest: &Manifest, limits: &LimitsConfig) -> Result<(Manifest, Vec<Violation>)> {
+    let mut value = serde_json::to_value(manifest)?;
+    let mut violations = Vec::new();
+
+    // 1. Per-field limit
+    for_each_text(&mut value, "", &mut |path, text| {
+        if text.len() > limits.max_field_bytes {
+            violations.push(Violation {
+                field: path.to_string(),
+                size: text.len(),
+                limit: limits.max_field_bytes,
+            });
+            *text = truncate(text, limits.max_field_bytes);
+        }
+    });
+
+    // 2. Whole-manifest limit: halve the longest field until it fits
+    let original_size = serialized_size(&value)?;
+    if original_size > limits.max_manifest_bytes {
+        violations.push(Violation {
+            field: "manifest".to_string(),
+            size: original_size,
+            limit: limits.max_manifest_bytes,
+        });
+
+        while serialized_size(&value)? > limits.max_manifest_bytes {
+            let mut longest: Option<(String, usize)> = None;
+            for_each_text(&mut value, "", &mut |path, text| {
+                let shrinkable = text.len() > TRUNCATION_SUFFIX.len() * 2;
+                if shrinkable && !matches!(longest, Some((_, len)) if len >= text.len()) {
+                    longest = Some((path.to_string(), text.len()));
+                }
+            });
+
+            let Some((target, len)) = longest else {
+                // Nothing left to shrink; the rest is structure
+                break;
+            };
+            for_each_text(&mut value, "", &mut |path, text| {
+                if path == target {
+                    *text = truncate(text, len / 2);
+                }
+            });
+        }
+    }
+
+    let manifest =
+        serde_json::from_value(value).context("Truncation produced an invalid manifest")?;
+    Ok((manifest, violations))
+}
+
+/// Truncate a manifest to the repository's `[limits]`, or reject it in strict mode
+///
+/// `repo_path` is any directory of the repository, the current one if `None`.
+pub fn apply(manifest: &Manifest, repo_path: Option<&Path>) -> Result<Manifest> {
+    let limits = match git::run_git_cmd(&["rev-parse", "--show-toplevel"], repo_path) {
+        Ok(root) => Config::load(&PathBuf::from(root).join(".gip"))?.limits,
+        Err(_) => LimitsConfig::default(),
+    };
+    let (truncated, violations) = enforce(manifest, &limits)?;
+    if violations.is_empty() {
+        return Ok(truncated);
+    }
+
+    let listed: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+    if limits.strict {
+        anyhow::bail!(ErrorCode::ManifestInvalid.error(format!(
+            "Manifest of {} exceeds size limits:\n  - {}\nShorten these fields, or raise [limits] in .gip/config.toml.",
+            manifest.commit,
+            listed.join("\n  - ")
+        )));
+    }
+    for v in &listed {
+        say!("{}", format!("Warning: {}, truncated", v).yellow());
+    }
+    Ok(truncated)
+}
+
+/// Cut text to at most `limit` bytes including the suffix, on a char boundary
+///
+/// Below the suffix's own length there is no room for it, and the text is
+/// cut bare.
+pub fn truncate(text: &str, limit: usize) -> String {
+    if text.len() <= limit {
+        return text.to_string();
+    }
+
+    let (mut end, suffix) = match limit.checked_sub(TRUNCATION_SUFFIX.len()) {
+        Some(end) => (end, TRUNCATION_SUFFIX),
+        None => (limit, ""),
+    };
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}{}", &text[..end], suffix)
+}
+
+fn serialized_size(value: &Value) -> Result<usize> {
+    let manifest: Manifest = serde_json::from_value(value.clone())?;
+    Ok(manifest::serialize_manifest_toon(&manifest)?.len())
+}
+
+/// Visit every unprotected string in a manifest value with its dotted path
+fn for_each_text(value: &mut Value, path: &str, f: &mut dyn FnMut(&str, &mut String)) {
+    match value {
+        Value::String(s) => f(path, s),
+        Value::Array(items) => {
+            for (idx, item) in items.iter_mut().enumerate() {
+                for_each_text(item, &format!("{}[{}]", path, idx), f);
+            }
+        }
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                if PROTECTED_FIELDS.contains(&key.as_str()) {
+                    continue;
+                }
+                let child_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                for_each_text(child, &child_path, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use crate::test_support::init_repo;
+    use serde_json::json;
+
+    fn manifest(rationale: &str, notes: Vec<String>) -> Manifest {
+        test_support::manifest(
+            "abc123",
+            json!({
+                "rationale": rationale,
+                "behaviorClass": ["feature"],
+                "securityNotes": notes
+            }),
+        )
+    }
+
+    #[test]
+    fn test_truncate_on_char_boundary() {
+        assert_eq!(truncate("short", 100), "short");
+
+        let cut = truncate(&"é".repeat(50), 40);
+        assert!(cut.len() <= 40);
+        assert!(cut.ends_with(TRUNCATION_SUFFIX));
+    }
+
+    #[test]
+    fn test_truncate_below_suffix_length() {
+        for limit in 0..=TRUNCATION_SUFFIX.len() + 2 {
+            assert!(truncate(&"é".repeat(20), limit).len() <= limit);
+        }
+        assert_eq!(truncate("abcdefghijklmnop", 4), "abcd");
+        assert_eq!(truncate("é", 1), "");
+    }
+
+    #[test]
+    fn test_save_applies_repository_limits() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Some(temp_dir.path());
+        init_repo(temp_dir.path());
+        git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", "init"], dir).unwrap();
+        let sha = git::run_git_cmd(&["rev-parse", "HEAD"], dir).unwrap();
+        let gip_dir = temp_dir.path().join(".gip");
+        std::fs::create_dir(&gip_dir).unwrap();
+        std::fs::write(
+            gip_dir.join("config.toml"),
+            "[limits]\nmax_field_bytes = 64\n",
+        )
+        .unwrap();
+
+        manifest::save(&manifest(&"x".repeat(1000), vec![]), &sha, dir).unwrap();
+        let saved = manifest::load(&sha, dir).unwrap();
+        assert_eq!(saved.entries[0].rationale.len(), 64);
+
+        std::fs::write(
+            gip_dir.join("config.toml"),
+            "[limits]\nmax_field_bytes = 64\nstrict = true\n",
+        )
+        .unwrap();
+        assert!(manifest::save(&manifest(&"y".repeat(1000), vec![]), &sha, dir).is_err());
+        assert_eq!(manifest::load(&sha, dir).unwrap(), saved);
+    }
+
+    #[test]
+    fn test_field_limit() {
+        let limits = LimitsConfig {
+            max_field_bytes: 64,
+            ..Default::default()
+        };
+        let (m, violations) = enforce(&manifest(&"x".repeat(1000), vec![]), &limits).unwrap();
+
+        assert_eq!(m.entries[0].rationale.len(), 64);
+        assert!(m.entries[0].rationale.ends_with(TRUNCATION_SUFFIX));
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].field, "entries[0].rationale");
+        assert_eq!(m.entries[0].anchor.symbol, "run");
+    }
+
+    #[test]
+    fn test_manifest_limit_is_deterministic() {
+        let limits = LimitsConfig {
+            max_field_bytes: 10_000,
+            max_manifest_bytes: 2_000,
+            strict: false,
+        };
+        let input = manifest(&"r".repeat(3000), vec!["n".repeat(3000)]);
+
+        let (first, violations) = enforce(&input, &limits).unwrap();
+        let (second, _) = enforce(&input, &limits).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(violations.last().unwrap().field, "manifest");
+        assert!(manifest::serialize_manifest_toon(&first).unwrap().len() <= 2_000);
+    }
+
+    #[test]
+    fn test_within_limits_unchanged() {
+        let input = manifest("Small change", vec![]);
+        let (m, violations) = enforce(&input, &LimitsConfig::default()).unwrap();
+        assert_eq!(m, input);
+        assert!(violations.is_empty());
+    }
+}
@@ -0,0 +1,127 @@
+//! Advisory lock for pending state in `.gip/`
+//!
+//! An IDE hook and a terminal can run gip at the same time. Reads and writes of
+//! pending manifests and stashed manifests happen while holding `.gip/gip.lock`,
+//! a file created exclusively and removed when the guard is dropped. A lock left
+//! behind by a process that no longer exists is taken over.
+
+use crate::errors::ErrorCode;
+use anyhow::{Context, Result};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// File name of the lock inside `.gip/`
+pub const LOCK_FILE: &str = "gip.lock";
+
+/// How long to wait for another process to finish
+const WAIT: Duration = Duration::from_secs(3);
+
+/// Delay between attempts while waiting
+const RETRY_EVERY: Duration = Duration::from_millis(50);
+
+/// Age after which a lock is considered abandoned where process IDs can't be checked
+const STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// Held lock on a `.gip` directory, released on drop
+#[derive(Debug)]
+pub struct PendingLock {
+    path: PathBuf,
+}
+
+impl PendingLock {
+    /// Take the lock, waiting briefly if another gip process holds it
+    pub fn acquire(gip_dir: &Path) -> Result<Self> {
+        Self::acquire_within(gip_dir, WAIT)
+    }
+
+    fn acquire_within(gip_dir: &Path, wait: Duration) -> Result<Self> {
+        fs::create_dir_all(gip_dir).context("Failed to create .gip directory")?;
+        let path = gip_dir.join(LOCK_FILE);
+        let started = Instant::now();
+
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    // The PID lets a later process tell whether the holder is gone
+                    let _ = write!(file, "{}", std::process::id());
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    if is_stale(&path) {
+                        let _ = fs::remove_file(&path);
+                        continue;
+                    }
+                    if started.elapsed() >= wait {
+                        let holder = fs::read_to_string(&path).unwrap_or_default();
+                        anyhow::bail!(ErrorCode::Locked.error(format!(
+                            "Another gip process is running (pid {}). If it is not, remove {}",
+                            holder.trim(),
+                            path.display()
+                        )));
+                    }
+                    thread::sleep(RETRY_EVERY);
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create {}", path.display()))
+                }
+            }
+        }
+    }
+}
+
+impl Drop for PendingLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Whether the process holding a lock file is gone
+fn is_stale(path: &Path) -> bool {
+    let pid = fs::read_to_string(path)
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok());
+
+    // Where /proc exists, a missing entry means the holder has exited
+    if Path::new("/proc/self").exists() {
+        if let Some(pid) = pid {
+            return !Path::new(&format!("/proc/{}", pid)).exists();
+        }
+    }
+
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age > STALE_AFTER)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_and_released() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let lock = PendingLock::acquire(temp_dir.path()).unwrap();
+        assert!(temp_dir.path().join(LOCK_FILE).exists());
+
+        let err = PendingLock::acquire_within(temp_dir.path(), Duration::ZERO).unwrap_err();
+        assert!(err.to_string().contains("Another gip process is running"));
+
+        drop(lock);
+        assert!(!temp_dir.path().join(LOCK_FILE).exists());
+        assert!(PendingLock::acquire(temp_dir.path()).is_ok());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_lock_of_exited_process_is_taken_over() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        // PIDs are capped well below this on Linux
+        fs::write(temp_dir.path().join(LOCK_FILE), "4294967295").unwrap();
+        assert!(PendingLock::acquire_within(temp_dir.path(), Duration::ZERO).is_ok());
+    }
+}
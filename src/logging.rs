@@ -0,0 +1,74 @@
+//! Logging and output verbosity
+//!
+//! `-v` logs every git command gip runs, with its timing, to stderr. `-vv`
+//! adds what git wrote to stderr and gip's own trace events, such as which
+//! manifests a conflict was enriched from. Setting `GIP_LOG=debug` or
+//! `GIP_LOG=trace` does the same without the flags, which helps in CI.
+//!
+//! `-q` silences progress and success messages printed with [`status!`].
+//! Warnings, errors and command results are always printed.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::level_filters::LevelFilter;
+
+/// Environment variable selecting the log level when no `-v` is given
+pub const LOG_ENV: &str = "GIP_LOG";
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// The log level for a number of `-v` flags, falling back to `env`
+pub fn level(verbose: u8, env: Option<&str>) -> LevelFilter {
+    match verbose {
+        0 => env
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(LevelFilter::OFF),
+        1 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Set up logging to stderr; call once at startup
+pub fn init(verbose: u8, quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+
+    let level = level(verbose, std::env::var(LOG_ENV).ok().as_deref());
+    if level == LevelFilter::OFF {
+        return;
+    }
+    // Fails only if a subscriber is already set, e.g. when embedded
+    let _ = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .without_time()
+        .try_init();
+}
+
+/// Whether `-q` was given
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Print a progress or success message for people, unless `-q` was given
+#[macro_export]
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !$crate::logging::is_quiet() {
+            $crate::say!($($arg)*);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_from_flags_and_env() {
+        assert_eq!(level(0, None), LevelFilter::OFF);
+        assert_eq!(level(1, None), LevelFilter::DEBUG);
+        assert_eq!(level(3, Some("off")), LevelFilter::TRACE);
+        assert_eq!(level(0, Some("trace")), LevelFilter::TRACE);
+        assert_eq!(level(0, Some("nonsense")), LevelFilter::OFF);
+    }
+}
@@ -0,0 +1,407 @@
+//! Minimal language server exposing stored intent
+//!
+//! `gip lsp` speaks the Language Server Protocol over stdin/stdout, so any
+//! editor with an LSP client shows gip context without a dedicated plugin:
+//!
+//! - **Hover**: the line's last change from blame, with its behaviorClass,
+//!   rationale and contract from the manifest.
+//! - **Code lens**: above every symbol with recorded changes, a "view contract
+//!   history" lens running the `gip.contractHistory` command, whose result is
+//!   shown as a message.
+//!
+//! Only the requests above are implemented; the server reads files from disk,
+//! so unsaved edits are not taken into account.
+
+use crate::analyzers;
+use crate::blame;
+use crate::git;
+use crate::manifest::{Entry, Manifest};
+use crate::packages::PackageMap;
+use crate::query::HistoryFilter;
+use crate::symbols;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+/// Command behind the "view contract history" lens
+pub const HISTORY_COMMAND: &str = "gip.contractHistory";
+
+/// JSON-RPC error code for unknown methods
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// `MessageType.Info` of `window/showMessage`
+const MESSAGE_INFO: u8 = 3;
+
+/// Read one message; `None` at end of input
+pub fn read_message<R: BufRead>(reader: &mut R) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+    }
+
+    let length = length.context("Message without Content-Length")?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Write one message with its `Content-Length` header
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Path of a `file://` URI
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    // Windows URIs look like file:///C:/repo
+    let path = match path.strip_prefix('/') {
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => rest,
+        _ => path,
+    };
+
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| path.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok().map(PathBuf::from)
+}
+
+/// Server state between requests
+pub struct Server {
+    root: PathBuf,
+    /// Manifests reachable from HEAD, newest first, and the HEAD they were loaded for
+    history: Option<(String, Vec<Manifest>)>,
+    shutdown: bool,
+}
+
+/// What the main loop should do after a message
+pub enum Step {
+    Continue(Vec<Value>),
+    Exit(i32),
+}
+
+impl Server {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            history: None,
+            shutdown: false,
+        }
+    }
+
+    /// Handle one incoming message, returning the messages to send back
+    pub fn handle(&mut self, message: &Value) -> Step {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let mut outgoing = Vec::new();
+        let result = match method {
+            "initialize" => {
+                if let Some(root) = params["rootUri"].as_str().and_then(uri_to_path) {
+                    self.root = root;
+                }
+                Ok(json!({
+                    "capabilities": {
+                        "hoverProvider": true,
+                        "codeLensProvider": {"resolveProvider": false},
+                        "executeCommandProvider": {"commands": [HISTORY_COMMAND]}
+                    },
+                    "serverInfo": {"name": "gip", "version": env!("CARGO_PKG_VERSION")}
+                }))
+            }
+            "textDocument/hover" => Ok(self
+                .document(params)
+                .and_then(|path| {
+                    let line = params["position"]["line"].as_u64()? as usize + 1;
+                    self.hover(&path, line)
+                })
+                .map_or(
+                    Value::Null,
+                    |text| json!({"contents": {"kind": "markdown", "value": text}}),
+                )),
+            "textDocument/codeLens" => Ok(match self.document(params) {
+                Some(path) => Value::Array(self.code_lenses(&path)),
+                None => Value::Array(Vec::new()),
+            }),
+            "workspace/executeCommand" if params["command"] == HISTORY_COMMAND => {
+                let args = &params["arguments"];
+                let text = match (args[0].as_str(), args[1].as_str()) {
+                    (Some(path), Some(symbol)) => self.contract_history(path, symbol),
+                    _ => "Expected a path and a symbol".to_string(),
+                };
+                outgoing.push(json!({
+                    "jsonrpc": "2.0",
+                    "method": "window/showMessage",
+                    "params": {"type": MESSAGE_INFO, "message": text}
+                }));
+                Ok(Value::String(text))
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            "exit" => return Step::Exit(if self.shutdown { 0 } else { 1 }),
+            _ => Err(format!("Unsupported method {}", method)),
+        };
+
+        // Notifications carry no id and get no response
+        if let Some(id) = id {
+            outgoing.push(match result {
+                Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+                Err(message) => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {"code": METHOD_NOT_FOUND, "message": message}
+                }),
+            });
+        }
+        Step::Continue(outgoing)
+    }
+
+    /// Repository-relative path of the request's document
+    fn document(&self, params: &Value) -> Option<String> {
+        let path = uri_to_path(params["textDocument"]["uri"].as_str()?)?;
+        let relative = path.strip_prefix(&self.root).ok()?;
+        Some(relative.to_string_lossy().replace('\\', "/"))
+    }
+
+    /// Markdown describing the last change to a 1-based line
+    pub fn hover(&self, path: &str, line: usize) -> Option<String> {
+        let gip_dir = self.root.join(".gip");
+        let context = blame::locate(path, line, Some(&self.root), Some(&gip_dir)).ok()?;
+        let blamed = context.origin?;
+
+        let short = &blamed.commit[..blamed.commit.len().min(8)];
+        let mut text = match (context.entries.first(), &context.global_intent) {
+            (Some(entry), _) => describe(entry),
+            (None, Some(gi)) => format!(
+                "**last changed: {}** — {}",
+                gi.behavior_class.join(", "),
+                gi.rationale
+            ),
+            (None, None) => return None,
+        };
+        text.push_str(&format!("\n\n`{}` {}", short, blamed.summary));
+        Some(text)
+    }
+
+    /// "view contract history" lenses for the symbols of a file with recorded changes
+    pub fn code_lenses(&mut self, path: &str) -> Vec<Value> {
+        let Ok(source) = fs::read_to_string(self.root.join(path)) else {
+            return Vec::new();
+        };
+        let gip_dir = self.root.join(".gip");
+        let Some(analyzer) = analyzers::analyzer_for(Path::new(path), Some(&gip_dir)) else {
+            return Vec::new();
+        };
+        let symbols = analyzer.detect_symbols(&source);
+
+        let history = self.history();
+        symbols
+            .iter()
+            .filter_map(|symbol| {
+                let changes = history
+                    .iter()
+                    .filter(|m| find_entry(m, path, &symbol.name).is_some())
+                    .count();
+                if changes == 0 {
+                    return None;
+                }
+                let line = symbol.line.saturating_sub(1);
+                Some(json!({
+                    "range": {
+                        "start": {"line": line, "character": 0},
+                        "end": {"line": line, "character": 0}
+                    },
+                    "command": {
+                        "title": format!("view contract history ({} change(s))", changes),
+                        "command": HISTORY_COMMAND,
+                        "arguments": [path, symbol.name]
+                    }
+                }))
+            })
+            .collect()
+    }
+
+    /// Every recorded change of a symbol, oldest first
+    pub fn contract_history(&mut self, path: &str, symbol: &str) -> String {
+        let history = self.history();
+        let mut lines = vec![format!("Contract history of {} in {}", symbol, path)];
+        for manifest in history.iter().rev() {
+            if let Some(entry) = find_entry(manifest, path, symbol) {
+                let short = &manifest.commit[..manifest.commit.len().min(8)];
+                let mut line = format!(
+                    "{} {}: {}",
+                    short,
+                    entry.behavior_class.join(", "),
+                    entry.rationale
+                );
+                for (label, items) in [
+                    ("postconditions", &entry.contract.postconditions),
+                    ("errorModel", &entry.contract.error_model),
+                ] {
+                    if !items.is_empty() {
+                        line.push_str(&format!("; {}: {}", label, items.join(", ")));
+                    }
+                }
+                lines.push(line);
+            }
+        }
+        if lines.len() == 1 {
+            lines.push("No recorded changes".to_string());
+        }
+        lines.join("\n")
+    }
+
+    /// Manifests reachable from HEAD, reloaded when HEAD moves
+    fn history(&mut self) -> &[Manifest] {
+        let root = Some(self.root.as_path());
+        let head = git::run_git_cmd(&["rev-parse", "HEAD"], root).unwrap_or_default();
+        let stale = match self.history {
+            Some((ref loaded_for, _)) => *loaded_for != head,
+            None => true,
+        };
+        if stale {
+            let packages = PackageMap::load(&self.root.join(".gip")).unwrap_or_default();
+            let manifests = HistoryFilter::default()
+                .collect("HEAD", &packages, root)
+                .unwrap_or_default();
+            self.history = Some((head, manifests));
+        }
+        self.history.as_ref().map_or(&[], |(_, m)| m.as_slice())
+    }
+}
+
+/// The entry of a manifest for a symbol of a file
+fn find_entry<'a>(manifest: &'a Manifest, path: &str, symbol: &str) -> Option<&'a Entry> {
+    manifest
+        .entries
+        .iter()
+        .find(|e| e.anchor.file == path && symbols::base_name(&e.anchor.symbol) == symbol)
+}
+
+/// Hover text for an entry
+fn describe(entry: &Entry) -> String {
+    let class = if entry.behavior_class.is_empty() {
+        "change".to_string()
+    } else {
+        entry.behavior_class.join(", ")
+    };
+    let mut text = format!("**last changed: {}** — {}", class, entry.rationale);
+    if entry.compatibility.as_ref().is_some_and(|c| c.breaking) {
+        text.push_str("\n\n⚠ breaking change");
+    }
+    for (label, items) in [
+        ("Preconditions", &entry.contract.preconditions),
+        ("Postconditions", &entry.contract.postconditions),
+        ("Error model", &entry.contract.error_model),
+    ] {
+        if !items.is_empty() {
+            text.push_str(&format!("\n\n{}: {}", label, items.join("; ")));
+        }
+    }
+    text
+}
+
+/// Serve requests from `input` until the client exits; returns the exit status
+pub fn serve<R: BufRead, W: Write>(root: PathBuf, input: &mut R, output: &mut W) -> Result<i32> {
+    let mut server = Server::new(root);
+    while let Some(message) = read_message(input)? {
+        match server.handle(&message) {
+            Step::Continue(outgoing) => {
+                for reply in &outgoing {
+                    write_message(output, reply)?;
+                }
+            }
+            Step::Exit(code) => return Ok(code),
+        }
+    }
+    Ok(if server.shutdown { 0 } else { 1 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn frame(messages: &[Value]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for m in messages {
+            write_message(&mut out, m).unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn test_uri_to_path() {
+        assert_eq!(
+            uri_to_path("file:///home/dev/my%20repo/src/pay.rs"),
+            Some(PathBuf::from("/home/dev/my repo/src/pay.rs"))
+        );
+        assert_eq!(
+            uri_to_path("file:///C:/repo/pay.rs"),
+            Some(PathBuf::from("C:/repo/pay.rs"))
+        );
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+    }
+
+    #[test]
+    fn test_session() {
+        let input = frame(&[
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+            json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/definition", "params": {}}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "shutdown"}),
+            json!({"jsonrpc": "2.0", "method": "exit"}),
+        ]);
+        let mut output = Vec::new();
+        let code = serve(PathBuf::from("."), &mut Cursor::new(input), &mut output).unwrap();
+        assert_eq!(code, 0);
+
+        let mut reader = Cursor::new(output);
+        let init = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(init["id"], 1);
+        assert_eq!(init["result"]["capabilities"]["hoverProvider"], true);
+        let unsupported = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(unsupported["error"]["code"], METHOD_NOT_FOUND);
+        let shutdown = read_message(&mut reader).unwrap().unwrap();
+        assert_eq!(
+            (shutdown["id"].clone(), shutdown["result"].clone()),
+            (json!(3), Value::Null)
+        );
+        assert!(read_message(&mut reader).unwrap().is_none());
+    }
+}
@@ -3,14 +3,31 @@
 //! A lightweight Git wrapper that enriches merge conflicts with structured context
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 use gip::commands;
+use gip::errors::{self, ErrorCode};
+use gip::output::OutputFormat;
+use gip::query::HistoryFilter;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "gip")]
 #[command(version, about = "Git with Intent Preservation - Context-aware git wrapper", long_about = None)]
 #[command(disable_help_subcommand = true)]
 struct Cli {
+    /// Log git commands with timings (-vv adds git output and internal details)
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Only print warnings, errors and results
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Output format; json and toon print one result document to stdout
+    #[arg(long, global = true, value_enum, env = "GIP_FORMAT", default_value_t = OutputFormat::Human)]
+    format: OutputFormat,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -18,7 +35,14 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Initialize Gip in the current repository
-    Init,
+    Init {
+        /// Set up machine-level defaults in ~/.config/gip/ instead
+        #[arg(long, conflicts_with = "layout")]
+        global: bool,
+        /// What git tracks of .gip/: nothing (local), or config and templates (shared)
+        #[arg(long, value_enum)]
+        layout: Option<gip::setup::Layout>,
+    },
 
     /// Commit with manifest attachment
     Commit {
@@ -30,6 +54,26 @@ enum Commands {
         #[arg(short, long)]
         force: bool,
 
+        /// Generate a conventional commit message from the manifest
+        #[arg(long, conflicts_with = "message")]
+        message_from_manifest: bool,
+
+        /// Use the named pending manifest .gip/pending/<NAME>.toon (removed after the commit)
+        #[arg(long = "manifest", value_name = "NAME")]
+        manifest_name: Option<String>,
+
+        /// Commit despite branch policies, recording this justification in the audit log
+        #[arg(long = "override", value_name = "JUSTIFICATION")]
+        override_reason: Option<String>,
+
+        /// Validate and show what would be committed and attached, without committing
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Commit only these manifest entries and their hunks: a file, a symbol or file::symbol (repeatable)
+        #[arg(long, value_name = "FILE|SYMBOL")]
+        only: Vec<String>,
+
         /// Additional git arguments
         #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
         args: Vec<String>,
@@ -37,6 +81,18 @@ enum Commands {
 
     /// Push code AND context notes to remote
     Push {
+        /// Push notes with the redaction rules from .gip/config.toml applied
+        #[arg(long)]
+        redact: bool,
+
+        /// Also push notes to this remote (repeatable)
+        #[arg(long = "notes-to", value_name = "REMOTE")]
+        notes_to: Vec<String>,
+
+        /// Show the refs that would be pushed, without pushing or redacting
+        #[arg(long)]
+        dry_run: bool,
+
         /// Additional git arguments
         #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
         args: Vec<String>,
@@ -44,6 +100,22 @@ enum Commands {
 
     /// Merge with enriched conflict markers
     Merge {
+        /// Merge despite branch policies, recording this justification in the audit log
+        #[arg(long = "override", value_name = "JUSTIFICATION")]
+        override_reason: Option<String>,
+
+        /// Show the conflicts and the context they would get, without merging
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Merge BRANCH in memory for a merge queue: never prompts, leaves the tree alone, writes a JSON report
+        #[arg(long, value_name = "BRANCH", conflicts_with = "dry_run")]
+        ci: Option<String>,
+
+        /// Where --ci writes its report (default .gip/ci/merge-report.json)
+        #[arg(long, value_name = "FILE", requires = "ci")]
+        report: Option<PathBuf>,
+
         /// Additional git arguments (e.g. branch name)
         #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
         args: Vec<String>,
@@ -58,32 +130,725 @@ enum Commands {
 
     /// Show semantic history/context
     Context {
-        /// Commit SHA or file path (optional)
-        target: Option<String>,
+        /// Commit SHA or file path, or several commits and ranges such as v1.0..HEAD (optional)
+        targets: Vec<String>,
+
+        /// Show the intent behind a single line instead (<file>:<line>)
+        #[arg(long, value_name = "FILE:LINE", conflicts_with_all = ["targets", "export", "profile"])]
+        at: Option<String>,
 
         /// Export context to TOON format
         #[arg(long)]
         export: bool,
+
+        /// Fold the manifests of all the commits into one view
+        #[arg(long)]
+        aggregate: bool,
+
+        /// Show at most this many manifests, most recent first
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+
+        /// Projection profile to show the manifests through (see [projection] in config)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Only show entries belonging to this monorepo package
+        #[arg(long)]
+        package: Option<String>,
+
+        /// Only show commits by this author
+        #[arg(long)]
+        author: Option<String>,
+
+        /// Only show commits more recent than this date
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only show commits older than this date
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Only show commits and entries touching this path
+        #[arg(long)]
+        path: Option<String>,
+
+        /// Only show entries for this symbol, following renames
+        #[arg(long)]
+        symbol: Option<String>,
+    },
+
+    /// Summarize intent statistics over history
+    Stats {
+        /// Revision to walk (defaults to HEAD)
+        rev: Option<String>,
+
+        /// Only count commits by this author
+        #[arg(long)]
+        author: Option<String>,
+
+        /// Only count commits more recent than this date
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Only count commits older than this date
+        #[arg(long)]
+        until: Option<String>,
+
+        /// Number of most-changed symbols to list
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+
+        /// Report the estimated LLM token cost of each commit's context
+        #[arg(long)]
+        tokens: bool,
+    },
+
+    /// Export a symbol's intent history as a DOT or Mermaid graph
+    Graph {
+        /// Symbol whose history to draw
+        #[arg(long)]
+        symbol: String,
+
+        /// Revision to walk (defaults to HEAD)
+        rev: Option<String>,
+
+        /// Draw a Mermaid graph instead of DOT
+        #[arg(long)]
+        mermaid: bool,
+    },
+
+    /// Review the audit log of gip operations
+    Audit {
+        #[command(subcommand)]
+        command: commands::audit::AuditCommand,
+    },
+
+    /// Work with manifest files
+    Manifest {
+        #[command(subcommand)]
+        command: commands::manifest::ManifestCommand,
+    },
+
+    /// Export the manifest JSON Schema
+    Schema {
+        #[command(subcommand)]
+        command: commands::schema::SchemaCommand,
+    },
+
+    /// Create the key for encrypting notes at rest ([notes] encrypt)
+    Keygen,
+
+    /// Let this repository's tracked settings, hooks and analyzers run programs
+    Trust {
+        /// Stop trusting the repository
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// Rewrite stored manifests in the current schema version
+    Migrate {
+        /// Commits whose manifests to migrate
+        commits: Vec<String>,
+
+        /// Migrate every commit that has a gip note
+        #[arg(long, conflicts_with = "commits")]
+        all: bool,
+
+        /// Report what would change without rewriting notes
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Customize the manifest template
+    Template {
+        #[command(subcommand)]
+        command: commands::template::TemplateCommand,
+    },
+
+    /// Copy manifests to commits rewritten by rebase, filter-repo or cherry-pick
+    Remap {
+        /// Revision whose history to search for rewritten commits
+        #[arg(default_value = "HEAD")]
+        rev: String,
+
+        /// Report matches without copying notes
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Remove notes attached to commits that are no longer reachable
+    Gc {
+        /// Save each removed manifest to .gip/archive/<sha>.toon first
+        #[arg(long)]
+        archive: bool,
+
+        /// List orphaned notes without removing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Clone a repository with its context notes and initialize Gip
+    Clone {
+        /// Repository URL or path
+        repository: String,
+
+        /// Directory to clone into
+        directory: Option<String>,
+
+        /// Additional git clone arguments, after `--`
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+
+    /// Write manifests to a portable bundle file
+    ExportBundle {
+        /// Bundle file to write, e.g. context.gipb
+        output: PathBuf,
+
+        /// Only export commits in this revision range (e.g. v1.0..HEAD)
+        #[arg(long)]
+        range: Option<String>,
+    },
+
+    /// Attach the manifests from a bundle file as notes
+    ImportBundle {
+        /// Bundle file to read
+        input: PathBuf,
+
+        /// Overwrite notes that already exist
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Archive every manifest as JSON and TOON, with an index, in a tar.gz
+    Export {
+        /// Archive file to write, e.g. context.tar.gz
+        #[arg(long, value_name = "FILE")]
+        archive: PathBuf,
+
+        /// Only export commits in this revision range (e.g. v1.0..HEAD)
+        #[arg(long)]
+        range: Option<String>,
+    },
+
+    /// Attach context records produced outside gip as notes
+    Import {
+        /// Format of the records
+        #[arg(long, value_enum)]
+        from: commands::bundle::ImportFormat,
+
+        /// File to read
+        input: PathBuf,
+
+        /// Overwrite notes that already exist
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Report the share of commits carrying a valid manifest
+    Coverage {
+        /// Revision to walk (defaults to HEAD)
+        rev: Option<String>,
+
+        /// Size of the recent window, in days
+        #[arg(long, default_value_t = 30)]
+        days: u32,
+
+        /// Print a shields.io endpoint badge as JSON instead
+        #[arg(long)]
+        shields: bool,
+    },
+
+    /// Explain why a line exists, from the manifest of the commit that last changed it
+    Why {
+        /// Line to explain, as <file>:<line>
+        target: String,
+    },
+
+    /// Show a file with the intent behind each line in a gutter
+    Annotate {
+        /// File to annotate
+        file: String,
+
+        /// Width of the gutter column
+        #[arg(long)]
+        width: Option<usize>,
+    },
+
+    /// Flag commits that drop contract guarantees without marking them breaking
+    CheckContracts {
+        /// Commits to check, e.g. main..HEAD
+        #[arg(default_value = "HEAD")]
+        range: String,
+    },
+
+    /// Compile recorded signature changes into an API changelog
+    ApiReport {
+        /// Commits to report on, e.g. v1.0..HEAD
+        #[arg(default_value = "HEAD")]
+        range: String,
+
+        /// Only include public symbols
+        #[arg(long)]
+        public_only: bool,
+
+        /// Fail when the code changes a signature without a signatureDelta in the manifest
+        #[arg(long)]
+        fail_on_undocumented: bool,
+    },
+
+    /// Generate an upgrade guide from the migrations recorded in a range
+    Migrations {
+        /// Commits to collect from, e.g. v1.0..v2.0
+        range: String,
+
+        /// Write the guide to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Prepare a release: notes, breaking changes, upgrade guide and tag
+    Release {
+        /// Previous release tag (defaults to the latest tag)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Version to release instead of the suggested one
+        #[arg(long)]
+        version: Option<String>,
+
+        /// Print the release notes without creating a tag
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Show a commit with its manifest entries inline next to each hunk
+    Show {
+        /// Commit to show
+        #[arg(default_value = "HEAD")]
+        commit: String,
+
+        /// Additional git show arguments, after `--`
+        #[arg(last = true)]
+        args: Vec<String>,
+    },
+
+    /// List notes backups, or roll refs/notes/gip back to one
+    RestoreNotes {
+        /// Backup to restore (lists backups when omitted)
+        backup: Option<String>,
+    },
+
+    /// Fetch and also fetch context notes from the same remote(s)
+    Fetch {
+        /// Additional git arguments (e.g. remote name)
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Pull after fetching context notes from the same remote
+    Pull {
+        /// Additional git arguments (e.g. remote name)
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Checkout, enriching conflicts left by `-m`/`--merge`
+    Checkout {
+        /// Additional git arguments (e.g. -m <branch>)
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Switch branches, enriching conflicts left by `-m`/`--merge`
+    Switch {
+        /// Additional git arguments (e.g. --merge <branch>)
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Stash changes together with the pending manifest
+    Stash {
+        /// Additional git stash arguments (e.g. pop)
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Format patches with each commit's manifest embedded
+    FormatPatch {
+        /// Additional git format-patch arguments (e.g. -3 or main..)
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Apply patches and attach the manifests embedded in them as notes
+    Am {
+        /// Additional git am arguments (e.g. patch files or --continue)
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Run git bundle, including context notes in created bundles
+    Bundle {
+        /// Additional git bundle arguments (e.g. create repo.bundle --branches)
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Bisect, showing the intent of each candidate commit
+    Bisect {
+        /// Rank the remaining commits by risk and check out the riskiest
+        #[arg(long)]
+        suspect: bool,
+
+        /// Additional git bisect arguments (e.g. start, good, bad)
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
+    },
+
+    /// Print an LLM prompt for resolving the conflicts in a file
+    Prompt {
+        /// Conflicted file (defaults to the only one)
+        file: Option<String>,
+
+        /// Copy the prompt to the clipboard instead of printing it
+        #[arg(long)]
+        copy: bool,
+    },
+
+    /// Remove Gip context from conflict markers, leaving plain git conflicts
+    Unenrich {
+        /// Files to restore (defaults to every conflicted file)
+        files: Vec<String>,
+    },
+
+    /// Edit the manifest of an existing commit and rewrite its note
+    AmendContext {
+        /// Commit whose manifest to edit
+        commit: String,
+    },
+
+    /// Show breaking changes and migrations that arrived in a range of commits
+    Incoming {
+        /// Range of arrived commits (defaults to ORIG_HEAD..HEAD)
+        range: Option<String>,
+
+        /// Show every change, not only those affecting files you touched recently
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Resolve conflict hunks by taking ours, theirs, both, or an edit
+    Resolve {
+        /// Open the interactive terminal interface
+        #[arg(long)]
+        tui: bool,
+
+        /// Files to resolve (defaults to every conflicted file)
+        files: Vec<String>,
+    },
+
+    /// Resolve conflicts in your merge tool with Gip context in a side file
+    Mergetool {
+        /// Tool to launch: meld, kdiff3, vscode, or any tool with mergetool.<tool>.cmd
+        #[arg(long)]
+        tool: Option<String>,
+
+        /// Only register gip as a git merge tool
+        #[arg(long)]
+        install: bool,
+
+        /// Run as git's merge tool (used by the registered command)
+        #[arg(long, num_args = 4, value_names = ["BASE", "LOCAL", "REMOTE", "MERGED"], hide = true)]
+        driver: Option<Vec<String>>,
+
+        /// Files to resolve (defaults to every conflicted file)
+        files: Vec<String>,
+    },
+
+    /// Run a language server showing stored intent as hovers and code lenses
+    Lsp,
+
+    /// Show every recorded change to a symbol across files and renames
+    History {
+        /// Symbol whose intent timeline to show
+        symbol: String,
+    },
+
+    /// Remove gip's hooks, merge tool, attributes, fetch config and .gip/ from this repository
+    Uninstall {
+        /// Delete .gip/ (and the notes with --notes) without asking
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Also delete the manifests: the notes ref, its redacted copy and backups
+        #[arg(long)]
+        notes: bool,
+
+        /// List what would be removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Stage changes; with -p, describe each hunk staged in the pending manifest
+    Add {
+        /// git add arguments; with -p/--patch, only paths
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        args: Vec<String>,
     },
 
+    /// Keep the pending manifest in sync with the working tree as you edit
+    Watch {
+        /// Sync once and exit instead of watching
+        #[arg(long)]
+        once: bool,
+    },
+
+    /// Continue the merge, rebase, cherry-pick, revert or am in progress
+    Continue,
+
+    /// Abort the merge, rebase, cherry-pick, revert or am in progress
+    Abort,
+
+    /// Skip the current commit of the rebase, cherry-pick, revert or am in progress
+    Skip,
+
     #[command(external_subcommand)]
     External(Vec<String>),
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Commands that write a result document with `--format json|toon`
+fn has_structured_output(command: &Option<Commands>) -> bool {
+    matches!(
+        command,
+        Some(
+            Commands::Init { .. }
+                | Commands::Commit { .. }
+                | Commands::Merge { .. }
+                | Commands::Push { .. }
+                | Commands::Fetch { .. }
+                | Commands::Pull { .. }
+                | Commands::Manifest { .. }
+                | Commands::Incoming { .. }
+                | Commands::Context { .. }
+                | Commands::History { .. }
+                | Commands::Stats { .. }
+                | Commands::Graph { .. }
+                | Commands::Coverage { .. }
+                | Commands::ApiReport { .. }
+        )
+    )
+}
+
+fn main() {
+    let matches = Cli::command().get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // GIP_FORMAT is a default for the commands that have a result document;
+    // only an explicit --format is refused by the others
+    if matches.value_source("format") == Some(ValueSource::EnvVariable)
+        && !has_structured_output(&cli.command)
+    {
+        cli.format = OutputFormat::Human;
+    }
+    gip::logging::init(cli.verbose, cli.quiet);
+    gip::output::set_format(cli.format);
+
+    if let Err(err) = run(cli) {
+        std::process::exit(errors::report(&err));
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    if cli.format != OutputFormat::Human && !has_structured_output(&cli.command) {
+        anyhow::bail!(
+            ErrorCode::Usage.error("This command only has human output; run it without --format")
+        );
+    }
 
     match cli.command {
-        Some(Commands::Init) => commands::init::run(),
+        Some(Commands::Init {
+            global: false,
+            layout,
+        }) => commands::init::run(layout),
+        Some(Commands::Init { global: true, .. }) => commands::init::run_global(),
         Some(Commands::Commit {
             message,
             force,
+            message_from_manifest,
+            manifest_name,
+            override_reason,
+            dry_run,
+            only,
+            args,
+        }) => commands::commit::run(
+            message,
+            force,
+            message_from_manifest,
+            manifest_name.as_deref(),
+            override_reason.as_deref(),
+            dry_run,
+            &only,
+            &args,
+        ),
+        Some(Commands::Push {
+            redact,
+            notes_to,
+            dry_run,
             args,
-        }) => commands::commit::run(message, force, &args),
-        Some(Commands::Push { args }) => commands::push::run(&args),
-        Some(Commands::Merge { args }) => commands::merge::run(&args),
+        }) => commands::push::run(redact, &notes_to, dry_run, &args),
+        Some(Commands::Merge {
+            override_reason,
+            ci: Some(branch),
+            report,
+            args,
+            ..
+        }) => commands::merge::run_ci(
+            &branch,
+            report.as_deref(),
+            override_reason.as_deref(),
+            &args,
+        ),
+        Some(Commands::Merge {
+            override_reason,
+            dry_run,
+            args,
+            ..
+        }) => commands::merge::run(override_reason.as_deref(), dry_run, &args),
         Some(Commands::Rebase { args }) => commands::rebase::run(&args),
-        Some(Commands::Context { target, export }) => commands::context::run(target, export),
+        Some(Commands::Context { at: Some(at), .. }) => commands::context::run_at(&at),
+        Some(Commands::Context {
+            targets,
+            export,
+            aggregate,
+            limit,
+            package,
+            author,
+            since,
+            until,
+            path,
+            symbol,
+            profile,
+            ..
+        }) => commands::context::run(
+            &targets,
+            export,
+            aggregate,
+            limit,
+            profile.as_deref(),
+            HistoryFilter {
+                author,
+                since,
+                until,
+                path,
+                package,
+                path_aliases: Vec::new(),
+                symbol,
+                symbol_aliases: Vec::new(),
+            },
+        ),
+        Some(Commands::Stats {
+            rev,
+            author,
+            since,
+            until,
+            top,
+            tokens,
+        }) => commands::stats::run(
+            rev,
+            HistoryFilter {
+                author,
+                since,
+                until,
+                ..Default::default()
+            },
+            top,
+            tokens,
+        ),
+        Some(Commands::Graph {
+            symbol,
+            rev,
+            mermaid,
+        }) => commands::graph::run(&symbol, rev, mermaid),
+        Some(Commands::Audit { command }) => commands::audit::run(command),
+        Some(Commands::Manifest { command }) => commands::manifest::run(command),
+        Some(Commands::Schema { command }) => commands::schema::run(command),
+        Some(Commands::Keygen) => commands::keygen::run(),
+        Some(Commands::Trust { remove }) => commands::trust::run(remove),
+        Some(Commands::Migrate {
+            commits,
+            all,
+            dry_run,
+        }) => commands::migrate::run(commits, all, dry_run),
+        Some(Commands::Template { command }) => commands::template::run(command),
+        Some(Commands::Remap { rev, dry_run }) => commands::remap::run(&rev, dry_run),
+        Some(Commands::Gc { archive, dry_run }) => commands::gc::run(archive, dry_run),
+        Some(Commands::Clone {
+            repository,
+            directory,
+            args,
+        }) => commands::clone::run(&repository, directory, &args),
+        Some(Commands::ExportBundle { output, range }) => {
+            commands::bundle::export(&output, range.as_deref())
+        }
+        Some(Commands::ImportBundle { input, force }) => commands::bundle::import(&input, force),
+        Some(Commands::Export { archive, range }) => {
+            commands::bundle::archive(&archive, range.as_deref())
+        }
+        Some(Commands::Import { from, input, force }) => {
+            commands::bundle::import_records(from, &input, force)
+        }
+        Some(Commands::Coverage { rev, days, shields }) => {
+            commands::coverage::run(rev, days, shields)
+        }
+        Some(Commands::Why { target }) => commands::why::run(&target),
+        Some(Commands::Annotate { file, width }) => commands::annotate::run(&file, width),
+        Some(Commands::CheckContracts { range }) => commands::check_contracts::run(&range),
+        Some(Commands::ApiReport {
+            range,
+            public_only,
+            fail_on_undocumented,
+        }) => commands::api_report::run(&range, public_only, fail_on_undocumented),
+        Some(Commands::Migrations { range, output }) => commands::migrations::run(&range, output),
+        Some(Commands::Release {
+            from,
+            version,
+            dry_run,
+        }) => commands::release::run(from, version, dry_run),
+        Some(Commands::Show { commit, args }) => commands::show::run(&commit, &args),
+        Some(Commands::RestoreNotes { backup }) => commands::restore_notes::run(backup),
+        Some(Commands::Fetch { args }) => commands::fetch::run(&args),
+        Some(Commands::Pull { args }) => commands::pull::run(&args),
+        Some(Commands::Checkout { args }) => commands::checkout::run(&args),
+        Some(Commands::Switch { args }) => commands::switch::run(&args),
+        Some(Commands::Stash { args }) => commands::stash::run(&args),
+        Some(Commands::FormatPatch { args }) => commands::format_patch::run(&args),
+        Some(Commands::Am { args }) => commands::am::run(&args),
+        Some(Commands::Bundle { args }) => commands::bundle::run(&args),
+        Some(Commands::Bisect { suspect, args }) => commands::bisect::run(suspect, &args),
+        Some(Commands::Prompt { file, copy }) => commands::prompt::run(file.as_deref(), copy),
+        Some(Commands::Unenrich { files }) => commands::unenrich::run(&files),
+        Some(Commands::AmendContext { commit }) => commands::amend_context::run(&commit),
+        Some(Commands::Incoming { range, all }) => commands::incoming::run(range.as_deref(), all),
+        Some(Commands::Resolve { tui, files }) => commands::resolve::run(tui, &files),
+        Some(Commands::Mergetool {
+            tool,
+            install,
+            driver,
+            files,
+        }) => commands::mergetool::run(tool.as_deref(), install, driver.as_deref(), &files),
+        Some(Commands::Lsp) => commands::lsp::run(),
+        Some(Commands::History { symbol }) => commands::history::run(&symbol),
+        Some(Commands::Uninstall {
+            yes,
+            notes,
+            dry_run,
+        }) => commands::uninstall::run(yes, notes, dry_run),
+        Some(Commands::Add { args }) => commands::add::run(&args),
+        Some(Commands::Watch { once }) => commands::watch::run(once),
+        Some(Commands::Continue) => commands::sequencer::run(commands::sequencer::Step::Continue),
+        Some(Commands::Abort) => commands::sequencer::run(commands::sequencer::Step::Abort),
+        Some(Commands::Skip) => commands::sequencer::run(commands::sequencer::Step::Skip),
         Some(Commands::External(args)) => commands::passthrough::run(&args),
         None => {
             // Show help if no args
@@ -0,0 +1,303 @@
+//! Combining manifests of several commits
+//!
+//! Squashes, merge commits and branch reviews describe many commits at once,
+//! and a symbol touched repeatedly would otherwise show up once per commit.
+//! Entries for the same file and symbol are folded into one: contracts and
+//! lists are unioned, the latest rationale wins, and breaking flags are ORed.
+
+use crate::manifest::types::*;
+
+/// Whether two entries describe the same symbol
+pub fn same_target(a: &Entry, b: &Entry) -> bool {
+    a.anchor.file == b.anchor.file && a.anchor.symbol == b.anchor.symbol
+}
+
+/// Fold a later entry for the same symbol into an earlier one
+pub fn merge_entry(older: &Entry, newer: &Entry) -> Entry {
+    let mut extra = older.extra.clone();
+    extra.extend(newer.extra.clone());
+
+    Entry {
+        anchor: newer.anchor.clone(),
+        change_type: merge_change_type(&older.change_type, &newer.change_type),
+        rationale: latest(&older.rationale, &newer.rationale),
+        signature_delta: merge_signature(&older.signature_delta, &newer.signature_delta),
+        behavior_class: union(&older.behavior_class, &newer.behavior_class),
+        contract: Contract {
+            inputs: union_opt(&older.contract.inputs, &newer.contract.inputs),
+            outputs: newer
+                .contract
+                .outputs
+                .clone()
+                .or_else(|| older.contract.outputs.clone()),
+            preconditions: union(&older.contract.preconditions, &newer.contract.preconditions),
+            postconditions: union(
+                &older.contract.postconditions,
+                &newer.contract.postconditions,
+            ),
+            error_model: union(&older.contract.error_model, &newer.contract.error_model),
+        },
+        side_effects: union(&older.side_effects, &newer.side_effects),
+        compatibility: merge_compatibility(&older.compatibility, &newer.compatibility),
+        tests_touched: union_opt(&older.tests_touched, &newer.tests_touched),
+        perf_budget: newer
+            .perf_budget
+            .clone()
+            .or_else(|| older.perf_budget.clone()),
+        security_notes: union_opt(&older.security_notes, &newer.security_notes),
+        feature_flags: union_opt(&older.feature_flags, &newer.feature_flags),
+        inherits_global_intent: newer
+            .inherits_global_intent
+            .or(older.inherits_global_intent),
+        package: newer.package.clone().or_else(|| older.package.clone()),
+        extra,
+    }
+}
+
+/// Collapse entries for the same symbol, given oldest first
+///
+/// Each merged entry keeps the position of the symbol's first appearance.
+pub fn dedup_entries<I: IntoIterator<Item = Entry>>(entries: I) -> Vec<Entry> {
+    let mut merged: Vec<Entry> = Vec::new();
+    for entry in entries {
+        match merged.iter_mut().find(|e| same_target(e, &entry)) {
+            Some(existing) => *existing = merge_entry(existing, &entry),
+            None => merged.push(entry),
+        }
+    }
+    merged
+}
+
+/// One manifest describing all of `manifests`, given oldest first
+pub fn combine(manifests: &[Manifest], commit: &str) -> Manifest {
+    let mut combined = Manifest::new(commit.to_string());
+    combined.entries = dedup_entries(manifests.iter().flat_map(|m| m.entries.iter().cloned()));
+
+    for intent in manifests.iter().filter_map(|m| m.global_intent.as_ref()) {
+        combined.global_intent = Some(match combined.global_intent.take() {
+            Some(gi) => GlobalIntent {
+                behavior_class: union(&gi.behavior_class, &intent.behavior_class),
+                rationale: latest(&gi.rationale, &intent.rationale),
+            },
+            None => intent.clone(),
+        });
+    }
+
+    combined
+}
+
+/// An added symbol stays added until it is deleted again
+fn merge_change_type(older: &str, newer: &str) -> String {
+    if older == CHANGE_ADD && newer != CHANGE_DELETE {
+        CHANGE_ADD.to_string()
+    } else {
+        newer.to_string()
+    }
+}
+
+fn latest(older: &str, newer: &str) -> String {
+    if newer.trim().is_empty() {
+        older.to_string()
+    } else {
+        newer.to_string()
+    }
+}
+
+/// The signature before the first change and after the last one
+fn merge_signature(
+    older: &Option<SignatureDelta>,
+    newer: &Option<SignatureDelta>,
+) -> Option<SignatureDelta> {
+    match (older, newer) {
+        (Some(o), Some(n)) => Some(SignatureDelta {
+            before: o.before.clone(),
+            after: n.after.clone(),
+        }),
+        (o, n) => n.clone().or_else(|| o.clone()),
+    }
+}
+
+fn merge_compatibility(
+    older: &Option<Compatibility>,
+    newer: &Option<Compatibility>,
+) -> Option<Compatibility> {
+    let (o, n) = match (older, newer) {
+        (Some(o), Some(n)) => (o, n),
+        (o, n) => return n.clone().or_else(|| o.clone()),
+    };
+
+    Some(Compatibility {
+        breaking: o.breaking || n.breaking,
+        deprecations: union_opt(&o.deprecations, &n.deprecations),
+        migrations: union_opt(&o.migrations, &n.migrations),
+        binary_breaking: or_flag(o.binary_breaking, n.binary_breaking),
+        source_breaking: or_flag(o.source_breaking, n.source_breaking),
+        data_model_migration: or_flag(o.data_model_migration, n.data_model_migration),
+    })
+}
+
+fn or_flag(a: Option<bool>, b: Option<bool>) -> Option<bool> {
+    match (a, b) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(false) || b.unwrap_or(false)),
+    }
+}
+
+/// Items of both lists in order, without repeats (ignoring surrounding whitespace)
+fn union(a: &[String], b: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::with_capacity(a.len() + b.len());
+    for item in a.iter().chain(b) {
+        if !out.iter().any(|o| o.trim() == item.trim()) {
+            out.push(item.clone());
+        }
+    }
+    out
+}
+
+fn union_opt(a: &Option<Vec<String>>, b: &Option<Vec<String>>) -> Option<Vec<String>> {
+    match (a, b) {
+        (None, None) => None,
+        (a, b) => Some(union(
+            a.as_deref().unwrap_or_default(),
+            b.as_deref().unwrap_or_default(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn entry(overrides: serde_json::Value) -> Entry {
+        let mut entry = json!({
+            "anchor": { "file": "src/pay.rs", "symbol": "charge" },
+            "rationale": "",
+            "behaviorClass": []
+        });
+        test_support::merge(&mut entry, overrides);
+        test_support::entry(entry)
+    }
+
+    #[test]
+    fn test_identical_entries_collapse() {
+        let e = entry(json!({"rationale": "Retry", "behaviorClass": ["bugfix"]}));
+        let merged = dedup_entries(vec![e.clone(), e.clone()]);
+        assert_eq!(merged, vec![e]);
+    }
+
+    #[test]
+    fn test_contracts_union_and_latest_rationale() {
+        let older = entry(json!({
+            "rationale": "Retry on timeout",
+            "behaviorClass": ["bugfix"],
+            "contract": {"inputs": ["amount: u64"], "preconditions": ["amount > 0"]},
+            "sideEffects": ["writes ledger"]
+        }));
+        let newer = entry(json!({
+            "rationale": "Add currency",
+            "behaviorClass": ["feature", "bugfix"],
+            "contract": {
+                "inputs": ["amount: u64", "currency: Currency"],
+                "preconditions": [" amount > 0 "],
+                "errorModel": ["Err(Timeout)"]
+            }
+        }));
+
+        let merged = merge_entry(&older, &newer);
+        assert_eq!(merged.rationale, "Add currency");
+        assert_eq!(merged.behavior_class, vec!["bugfix", "feature"]);
+        assert_eq!(
+            merged.contract.inputs.unwrap(),
+            vec!["amount: u64", "currency: Currency"]
+        );
+        assert_eq!(merged.contract.preconditions, vec!["amount > 0"]);
+        assert_eq!(merged.contract.error_model, vec!["Err(Timeout)"]);
+        assert_eq!(merged.side_effects, vec!["writes ledger"]);
+    }
+
+    #[test]
+    fn test_empty_rationale_keeps_older() {
+        let older = entry(json!({"rationale": "Retry on timeout"}));
+        let newer = entry(json!({"rationale": "  "}));
+        assert_eq!(merge_entry(&older, &newer).rationale, "Retry on timeout");
+    }
+
+    #[test]
+    fn test_breaking_is_ored_and_migrations_kept() {
+        let older = entry(json!({
+            "compatibility": {"breaking": true, "migrations": ["pass currency"]}
+        }));
+        let newer = entry(json!({
+            "compatibility": {"breaking": false, "sourceBreaking": false}
+        }));
+
+        let compat = merge_entry(&older, &newer).compatibility.unwrap();
+        assert!(compat.breaking);
+        assert_eq!(compat.migrations.unwrap(), vec!["pass currency"]);
+        assert_eq!(compat.source_breaking, Some(false));
+        assert_eq!(compat.binary_breaking, None);
+
+        // Compatibility on one side only is kept as is
+        let plain = entry(json!({}));
+        assert!(merge_entry(&older, &plain).compatibility.unwrap().breaking);
+    }
+
+    #[test]
+    fn test_change_type_and_signature_span_both() {
+        let older = entry(json!({
+            "changeType": "add",
+            "signatureDelta": {"before": "", "after": "fn charge(a: u64)"}
+        }));
+        let newer = entry(json!({
+            "signatureDelta": {"before": "fn charge(a: u64)", "after": "fn charge(a: u64, c: Currency)"}
+        }));
+
+        let merged = merge_entry(&older, &newer);
+        assert_eq!(merged.change_type, CHANGE_ADD);
+        let sig = merged.signature_delta.unwrap();
+        assert_eq!(sig.before, "");
+        assert_eq!(sig.after, "fn charge(a: u64, c: Currency)");
+
+        let deleted = entry(json!({"changeType": "delete"}));
+        assert_eq!(merge_entry(&older, &deleted).change_type, CHANGE_DELETE);
+    }
+
+    #[test]
+    fn test_dedup_keeps_first_position_and_other_symbols() {
+        let a1 = entry(json!({"rationale": "first"}));
+        let b = entry(json!({
+            "anchor": {"file": "src/pay.rs", "symbol": "refund", "hunkId": "H#2"}
+        }));
+        let a2 = entry(json!({"rationale": "second"}));
+        // Same symbol name in another file is a different target
+        let other = entry(json!({
+            "anchor": {"file": "src/bank.rs", "symbol": "charge", "hunkId": "H#1"}
+        }));
+
+        let merged = dedup_entries(vec![a1, b, a2, other]);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].rationale, "second");
+        assert_eq!(merged[1].anchor.symbol, "refund");
+        assert_eq!(merged[2].anchor.file, "src/bank.rs");
+    }
+
+    #[test]
+    fn test_combine_manifests() {
+        let mut first = Manifest::new("a".to_string());
+        first.entries = vec![entry(json!({"rationale": "first"}))];
+        first.global_intent = Some(GlobalIntent {
+            behavior_class: vec!["bugfix".to_string()],
+            rationale: "Fix retries".to_string(),
+        });
+        let mut second = Manifest::new("b".to_string());
+        second.entries = vec![entry(json!({"rationale": "second"}))];
+
+        let combined = combine(&[first, second], "c");
+        assert_eq!(combined.commit, "c");
+        assert_eq!(combined.entries.len(), 1);
+        assert_eq!(combined.entries[0].rationale, "second");
+        assert_eq!(combined.global_intent.unwrap().rationale, "Fix retries");
+    }
+}
@@ -0,0 +1,246 @@
+//! Input formats for pending manifests
+//!
+//! Manifests are always stored as TOON, but the pending manifest written by an
+//! agent or human may be TOON, YAML, or JSON: `.gip/manifest.{toon,yaml,yml,json}`.
+//! Named pending manifests for split commits live in `.gip/pending/<name>.<ext>`.
+
+use crate::manifest::types::Manifest;
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+use toon_format::{decode, DecodeOptions};
+
+/// Supported pending manifest formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Toon,
+    Yaml,
+    Json,
+}
+
+/// Pending manifest file names, in order of preference
+pub const PENDING_MANIFEST_FILES: &[(&str, ManifestFormat)] = &[
+    ("manifest.toon", ManifestFormat::Toon),
+    ("manifest.yaml", ManifestFormat::Yaml),
+    ("manifest.yml", ManifestFormat::Yaml),
+    ("manifest.json", ManifestFormat::Json),
+];
+
+/// Directory of named pending manifests, inside `.gip/`
+pub const PENDING_DIR: &str = "pending";
+
+/// Directory inside `.gip/` keeping each committed manifest file as it was written
+pub const HISTORY_DIR: &str = "history";
+
+impl ManifestFormat {
+    /// Format of a file, by extension; anything unknown is treated as TOON
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => ManifestFormat::Yaml,
+            Some("json") => ManifestFormat::Json,
+            _ => ManifestFormat::Toon,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ManifestFormat::Toon => "TOON",
+            ManifestFormat::Yaml => "YAML",
+            ManifestFormat::Json => "JSON",
+        }
+    }
+
+    /// Parse a document in this format
+    pub fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T> {
+        match self {
+            ManifestFormat::Toon => {
+                let opts = DecodeOptions::new().with_strict(false);
+                decode(content, &opts).context("Failed to parse TOON")
+            }
+            ManifestFormat::Yaml => serde_yaml::from_str(content).context("Failed to parse YAML"),
+            ManifestFormat::Json => serde_json::from_str(content).context("Failed to parse JSON"),
+        }
+    }
+
+    /// Parse a manifest in this format, with anchor paths normalized
+    pub fn parse_manifest(self, content: &str) -> Result<Manifest> {
+        let mut manifest: Manifest = self.parse(content)?;
+        manifest.normalize_paths();
+        Ok(manifest)
+    }
+
+    /// Write a manifest in this format
+    pub fn serialize_manifest(self, manifest: &Manifest) -> Result<String> {
+        match self {
+            ManifestFormat::Toon => crate::manifest::serialize_manifest_toon(manifest),
+            ManifestFormat::Yaml => serde_yaml::to_string(manifest).context("Failed to write YAML"),
+            ManifestFormat::Json => {
+                serde_json::to_string_pretty(manifest).context("Failed to write JSON")
+            }
+        }
+    }
+}
+
+/// Where the manifest file committed as `commit_sha` is archived:
+/// `.gip/history/<sha>.<ext>`, keeping the file's extension
+pub fn history_path(gip_dir: &Path, commit_sha: &str, manifest_path: &Path) -> PathBuf {
+    let ext = manifest_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("toon");
+    gip_dir
+        .join(HISTORY_DIR)
+        .join(format!("{}.{}", commit_sha, ext))
+}
+
+/// All pending manifest files present in a `.gip` directory
+pub fn find_pending(gip_dir: &Path) -> Vec<(PathBuf, ManifestFormat)> {
+    PENDING_MANIFEST_FILES
+        .iter()
+        .map(|(name, format)| (gip_dir.join(name), *format))
+        .filter(|(path, _)| path.is_file())
+        .collect()
+}
+
+/// The named pending manifest `<name>`, in any supported format
+pub fn find_named(gip_dir: &Path, name: &str) -> Option<(PathBuf, ManifestFormat)> {
+    let dir = gip_dir.join(PENDING_DIR);
+    PENDING_MANIFEST_FILES
+        .iter()
+        .map(|(file, format)| {
+            let ext = file.rsplit('.').next().unwrap_or_default();
+            (dir.join(format!("{}.{}", name, ext)), *format)
+        })
+        .find(|(path, _)| path.is_file())
+}
+
+/// Names of the named pending manifests, sorted
+pub fn list_named(gip_dir: &Path) -> Vec<String> {
+    let entries = match std::fs::read_dir(gip_dir.join(PENDING_DIR)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|ext| {
+                    PENDING_MANIFEST_FILES
+                        .iter()
+                        .any(|(file, _)| file.ends_with(&format!(".{}", ext)))
+                })
+        })
+        .filter_map(|path| path.file_stem()?.to_str().map(|s| s.to_string()))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Check that a pending manifest name is a plain file name
+pub fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        anyhow::bail!(
+            "Invalid manifest name '{}': use letters, digits, '-', '_' or '.'",
+            name
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YAML: &str = r#"
+schemaVersion: "2.0"
+commit: HEAD
+entries:
+  - anchor:
+      file: src/lib.rs
+      symbol: run
+      hunkId: H#1
+    changeType: modify
+    rationale: Retry on timeout
+    behaviorClass: [bugfix]
+    contract:
+      preconditions: [connected]
+"#;
+
+    #[test]
+    fn test_parse_yaml_and_json() {
+        let from_yaml = ManifestFormat::Yaml.parse_manifest(YAML).unwrap();
+        assert_eq!(from_yaml.entries[0].rationale, "Retry on timeout");
+        assert_eq!(
+            from_yaml.entries[0].contract.preconditions,
+            vec!["connected"]
+        );
+
+        let json = serde_json::to_string(&from_yaml).unwrap();
+        let from_json = ManifestFormat::Json.parse_manifest(&json).unwrap();
+        assert_eq!(from_json, from_yaml);
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ManifestFormat::from_path(Path::new(".gip/manifest.yml")),
+            ManifestFormat::Yaml
+        );
+        assert_eq!(
+            ManifestFormat::from_path(Path::new("out.json")),
+            ManifestFormat::Json
+        );
+        assert_eq!(
+            ManifestFormat::from_path(Path::new(".gip/manifest.toon")),
+            ManifestFormat::Toon
+        );
+    }
+
+    #[test]
+    fn test_find_pending() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert!(find_pending(temp_dir.path()).is_empty());
+
+        std::fs::write(temp_dir.path().join("manifest.json"), "{}").unwrap();
+        std::fs::write(temp_dir.path().join("manifest.yaml"), "").unwrap();
+        let found: Vec<ManifestFormat> = find_pending(temp_dir.path())
+            .into_iter()
+            .map(|(_, f)| f)
+            .collect();
+        assert_eq!(found, vec![ManifestFormat::Yaml, ManifestFormat::Json]);
+    }
+
+    #[test]
+    fn test_named_pending_manifests() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().join(PENDING_DIR);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("api.yaml"), YAML).unwrap();
+        std::fs::write(dir.join("docs.toon"), "").unwrap();
+        std::fs::write(dir.join("notes.txt"), "").unwrap();
+
+        assert_eq!(list_named(temp_dir.path()), vec!["api", "docs"]);
+        assert_eq!(
+            find_named(temp_dir.path(), "api"),
+            Some((dir.join("api.yaml"), ManifestFormat::Yaml))
+        );
+        assert_eq!(find_named(temp_dir.path(), "missing"), None);
+    }
+
+    #[test]
+    fn test_validate_name() {
+        assert!(validate_name("api-v2").is_ok());
+        assert!(validate_name("part_1").is_ok());
+        assert!(validate_name("../manifest").is_err());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name("").is_err());
+    }
+}
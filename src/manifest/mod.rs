@@ -3,10 +3,20 @@
 //! Provides functionality for creating, storing, and loading Gip manifests that
 //! capture structured context about code changes.
 
+pub mod aggregate;
+pub mod format;
+pub mod schema;
 pub mod storage;
+#[cfg(test)]
+pub mod test_support;
 pub mod toon;
 pub mod types;
 
-pub use storage::{load, load_pending, migrate_v1_to_v2, save, save_pending};
+pub use aggregate::{combine, dedup_entries, merge_entry};
+pub use format::ManifestFormat;
+pub use storage::{
+    is_unknown_version, load, load_many, load_pending, load_raw, merge_notes, migrate,
+    migrate_v1_to_v2, needs_migration, save, save_pending,
+};
 pub use toon::{serialize_manifest, serialize_manifest_toon};
 pub use types::*;
@@ -0,0 +1,97 @@
+//! JSON Schema for the manifest format
+//!
+//! The schema is generated from the Rust types, so it always matches what gip
+//! itself accepts. Editors and external tools can use the exported schema to
+//! validate manifests without running gip.
+
+use crate::manifest::types::{Manifest, SCHEMA_VERSION_CURRENT};
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+/// JSON Schema of the current manifest version
+pub fn json_schema() -> Value {
+    let mut schema = serde_json::to_value(schemars::schema_for!(Manifest))
+        .expect("generated schema is valid JSON");
+
+    if let Some(obj) = schema.as_object_mut() {
+        obj.insert(
+            "title".to_string(),
+            Value::String(format!("Gip Manifest v{}", SCHEMA_VERSION_CURRENT)),
+        );
+    }
+    schema
+}
+
+/// Validate a manifest document against the schema
+///
+/// Returns one message per violation, prefixed with the JSON pointer of the
+/// offending value.
+pub fn validate(instance: &Value) -> Result<Vec<String>> {
+    let schema = json_schema();
+    let validator = jsonschema::validator_for(&schema)
+        .map_err(|e| anyhow::anyhow!("{}", e))
+        .context("Failed to compile manifest schema")?;
+
+    Ok(validator
+        .iter_errors(instance)
+        .map(|e| {
+            let path = e.instance_path.to_string();
+            if path.is_empty() {
+                e.to_string()
+            } else {
+                format!("{}: {}", path, e)
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn valid_manifest() -> Value {
+        json!({
+            "schemaVersion": "2.0",
+            "commit": "abc123",
+            "entries": [{
+                "anchor": { "file": "src/lib.rs", "symbol": "run", "hunkId": "H#1" },
+                "changeType": "modify",
+                "rationale": "Explain why",
+                "behaviorClass": ["feature"],
+                "contract": {}
+            }]
+        })
+    }
+
+    #[test]
+    fn test_schema_describes_manifest() {
+        let schema = json_schema();
+        assert_eq!(schema["title"], "Gip Manifest v2.0");
+        let required = schema["required"].as_array().unwrap();
+        assert!(required.contains(&json!("schemaVersion")));
+        assert!(required.contains(&json!("entries")));
+    }
+
+    #[test]
+    fn test_validate_accepts_valid_manifest() {
+        assert!(validate(&valid_manifest()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate_reports_paths() {
+        let mut manifest = valid_manifest();
+        manifest["entries"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("rationale");
+        manifest["entries"][0]["behaviorClass"] = json!("feature");
+
+        let errors = validate(&manifest).unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().any(|e| e.contains("rationale")));
+        assert!(errors
+            .iter()
+            .any(|e| e.starts_with("/entries/0/behaviorClass")));
+    }
+}
@@ -3,47 +3,172 @@
 //! Manifests are stored as TOON in the custom git ref `refs/notes/gip`.
 //! This allows manifests to be shared across the team when pushing/pulling.
 
+use crate::atomic;
+use crate::cache;
+use crate::encryption;
+use crate::errors::ErrorCode;
 use crate::git;
+use crate::limits;
+use crate::lock::PendingLock;
+use crate::manifest::aggregate::combine;
 use crate::manifest::toon::serialize_manifest_toon;
 use crate::manifest::types::*;
+use crate::trailer;
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::Path;
 use toon_format::{decode, DecodeOptions};
+use tracing::trace;
 
 /// Save writes a manifest to Git Notes
+///
+/// The manifest is held to the repository's size limits first, see [`limits::apply`].
 pub fn save(manifest: &Manifest, commit_sha: &str, repo_path: Option<&Path>) -> Result<()> {
+    let manifest = limits::apply(manifest, repo_path)?;
+
     // Serialize as TOON
-    let toon = serialize_manifest_toon(manifest).context("Failed to serialize manifest to TOON")?;
+    let toon =
+        serialize_manifest_toon(&manifest).context("Failed to serialize manifest to TOON")?;
 
-    // Write to Git Notes
-    git::add_note(commit_sha, &toon, repo_path).context("Failed to save manifest to git notes")?;
+    // Write to Git Notes, encrypted if configured
+    let note = encryption::seal_note(toon)?;
+    git::add_note(commit_sha, &note, repo_path).context("Failed to save manifest to git notes")?;
 
     Ok(())
 }
 
-/// Load reads a manifest from Git Notes
+/// Load reads a manifest from Git Notes, migrated to the current schema
 pub fn load(commit_sha: &str, repo_path: Option<&Path>) -> Result<Manifest> {
+    let mut manifest = migrate(load_raw(commit_sha, repo_path)?);
+    manifest.normalize_paths();
+    Ok(manifest)
+}
+
+/// Load the manifests of many commits, reading their notes in one batch
+///
+/// Returns `(commit, manifest)` in the order given; commits without a
+/// manifest are left out.
+pub fn load_many(commits: &[String], repo_path: Option<&Path>) -> Result<Vec<(String, Manifest)>> {
+    cache::prefetch(commits, repo_path)?;
+    Ok(commits
+        .iter()
+        .filter_map(|sha| load(sha, repo_path).ok().map(|m| (sha.clone(), m)))
+        .collect())
+}
+
+/// Load a manifest from Git Notes exactly as stored, without migration
+///
+/// Commits without a note fall back to their `Gip-Context` trailers.
+pub fn load_raw(commit_sha: &str, repo_path: Option<&Path>) -> Result<Manifest> {
     // Read from Git Notes
-    let data =
-        git::get_note(commit_sha, repo_path).context("Failed to read manifest from git notes")?;
+    let data = match cache::note(commit_sha, repo_path) {
+        Ok(data) => data,
+        Err(e) => {
+            trace!("No note on {}, trying Gip-Context trailers", commit_sha);
+            return trailer::load_from_commit(commit_sha, repo_path).map_err(|_| {
+                e.context(ErrorCode::NotesMissing.error("Failed to read manifest from git notes"))
+            });
+        }
+    };
 
     // Parse TOON
+    let data = encryption::open_note(data)
+        .with_context(|| format!("Failed to read manifest of {}", commit_sha))?;
+    let opts = DecodeOptions::new().with_strict(false);
+    decode(&data, &opts).context("Failed to parse manifest TOON")
+}
+
+/// Merge notes fetched to `from`, e.g. a remote's, into the gip notes
+///
+/// A commit whose note changed on both sides gets one manifest combining the
+/// two; if either doesn't parse, the local note is kept. Returns how many
+/// notes were combined.
+pub fn merge_notes(from: &str, repo_path: Option<&Path>) -> Result<usize> {
+    let Some(conflicts) = git::merge_notes(from, repo_path)? else {
+        return Ok(0);
+    };
+    match combine_conflicting_notes(&conflicts, from, repo_path) {
+        Ok(count) => {
+            git::commit_notes_merge(repo_path)?;
+            Ok(count)
+        }
+        Err(e) => {
+            let _ = git::abort_notes_merge(repo_path);
+            Err(e)
+        }
+    }
+}
+
+/// Replace each conflicting note git left in `dir` by the combined manifest
+fn combine_conflicting_notes(dir: &Path, from: &str, repo_path: Option<&Path>) -> Result<usize> {
+    let mut count = 0;
+    for file in fs::read_dir(dir).context("Failed to read the conflicting notes")? {
+        let file = file?;
+        let commit = file.file_name().to_string_lossy().into_owned();
+        let ours = git::get_note(&commit, repo_path)?;
+        let theirs = git::get_note_in(from, &commit, repo_path)?;
+
+        let note = match (decode_note(ours.clone()), decode_note(theirs)) {
+            (Ok(ours), Ok(theirs)) => {
+                let combined = combine(&[ours, theirs], &commit);
+                let combined = limits::apply(&combined, repo_path)?;
+                encryption::seal_note(serialize_manifest_toon(&combined)?)?
+            }
+            _ => {
+                trace!(
+                    "Keeping the local note of {}, which can't be combined",
+                    commit
+                );
+                ours
+            }
+        };
+        fs::write(file.path(), note + "\n")
+            .with_context(|| format!("Failed to write the merged note of {}", commit))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Parse a note as stored, decrypting it first if it is sealed
+fn decode_note(note: String) -> Result<Manifest> {
+    let data = encryption::open_note(note)?;
     let opts = DecodeOptions::new().with_strict(false);
-    let mut manifest: Manifest = decode(&data, &opts).context("Failed to parse manifest TOON")?;
+    decode(&data, &opts).context("Failed to parse manifest TOON")
+}
+
+/// Schema versions [`migrate`] upgrades from; an empty one predates versioning
+const MIGRATABLE_VERSIONS: &[&str] = &["", SCHEMA_VERSION_1_0];
+
+/// Whether a manifest is from an older schema version that [`migrate`] upgrades
+///
+/// Versions this gip doesn't know, usually from a newer gip, don't qualify:
+/// rewriting them would drop the fields it can't read.
+pub fn needs_migration(manifest: &Manifest) -> bool {
+    MIGRATABLE_VERSIONS.contains(&manifest.schema_version.as_str())
+}
+
+/// Whether a manifest's schema version is neither current nor a known older one
+pub fn is_unknown_version(manifest: &Manifest) -> bool {
+    !needs_migration(manifest) && manifest.schema_version != SCHEMA_VERSION_CURRENT
+}
 
-    // Migrate v1.0 → v2.0 if needed
+/// Run every migration step between the manifest's version and the current one
+pub fn migrate(mut manifest: Manifest) -> Manifest {
+    // v1.0 → v2.0
     if manifest.schema_version.is_empty() || manifest.schema_version == SCHEMA_VERSION_1_0 {
         manifest = migrate_v1_to_v2(manifest);
     }
 
-    Ok(manifest)
+    // Later versions add their step here, e.g. `if version == 2.0 { v2_to_v3 }`
+
+    manifest
 }
 
 /// SavePending saves a manifest as pending (before commit)
 pub fn save_pending(manifest: &Manifest, gip_dir: &Path) -> Result<()> {
     // Ensure .gip directory exists
     fs::create_dir_all(gip_dir).context("Failed to create .gip directory")?;
+    let _lock = PendingLock::acquire(gip_dir)?;
 
     let path = gip_dir.join("pending.toon");
 
@@ -51,7 +176,7 @@ pub fn save_pending(manifest: &Manifest, gip_dir: &Path) -> Result<()> {
     let toon = serialize_manifest_toon(manifest).context("Failed to serialize pending manifest")?;
 
     // Write to file
-    fs::write(&path, toon)
+    atomic::write(&path, toon)
         .with_context(|| format!("Failed to write pending manifest to {:?}", path))?;
 
     Ok(())
@@ -60,12 +185,15 @@ pub fn save_pending(manifest: &Manifest, gip_dir: &Path) -> Result<()> {
 /// LoadPending loads the pending manifest
 pub fn load_pending(gip_dir: &Path) -> Result<Manifest> {
     let path = gip_dir.join("pending.toon");
+    let _lock = PendingLock::acquire(gip_dir)?;
 
     let data = fs::read_to_string(&path)
         .with_context(|| format!("Failed to read pending manifest from {:?}", path))?;
 
     let opts = DecodeOptions::new().with_strict(false);
-    let manifest: Manifest = decode(&data, &opts).context("Failed to parse pending manifest")?;
+    let mut manifest: Manifest =
+        decode(&data, &opts).context("Failed to parse pending manifest")?;
+    manifest.normalize_paths();
 
     Ok(manifest)
 }
@@ -104,6 +232,9 @@ pub fn migrate_v1_to_v2(mut manifest: Manifest) -> Manifest {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::manifest::test_support;
+    use crate::test_support::{clone_repo, init_repo};
+    use serde_json::json;
     use tempfile::TempDir;
 
     fn create_test_manifest() -> Manifest {
@@ -111,6 +242,8 @@ mod tests {
             schema_version: SCHEMA_VERSION_2_0.to_string(),
             commit: "abc123def456".to_string(),
             global_intent: None,
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![Entry {
                 anchor: Anchor {
                     file: "src/main.rs".to_string(),
@@ -135,6 +268,8 @@ mod tests {
                 feature_flags: None,
                 rationale: "Test rationale".to_string(),
                 inherits_global_intent: None,
+                package: None,
+                extra: Default::default(),
             }],
         }
     }
@@ -163,6 +298,8 @@ mod tests {
             schema_version: SCHEMA_VERSION_1_0.to_string(),
             commit: "old123".to_string(),
             global_intent: None,
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![Entry {
                 anchor: Anchor {
                     file: "old.rs".to_string(),
@@ -194,6 +331,8 @@ mod tests {
                 feature_flags: None,
                 rationale: "".to_string(),
                 inherits_global_intent: None,
+                package: None,
+                extra: Default::default(),
             }],
         };
 
@@ -211,4 +350,63 @@ mod tests {
         assert!(compat.deprecations.is_some());
         assert!(compat.migrations.is_some());
     }
+
+    #[test]
+    fn test_migrate_to_current() {
+        let current = create_test_manifest();
+        assert!(!needs_migration(&current));
+        assert_eq!(migrate(current.clone()), current);
+
+        let mut old = create_test_manifest();
+        old.schema_version = SCHEMA_VERSION_1_0.to_string();
+        assert!(needs_migration(&old));
+
+        let migrated = migrate(old);
+        assert_eq!(migrated.schema_version, SCHEMA_VERSION_CURRENT);
+        assert!(!needs_migration(&migrated));
+        assert!(!is_unknown_version(&migrated));
+
+        let mut newer = create_test_manifest();
+        newer.schema_version = "3.0".to_string();
+        assert!(!needs_migration(&newer));
+        assert!(is_unknown_version(&newer));
+        assert_eq!(migrate(newer.clone()), newer);
+    }
+    #[test]
+    fn test_merge_notes_combines_diverged_notes() {
+        let temp_dir = TempDir::new().unwrap();
+        let (upstream, local) = (
+            temp_dir.path().join("upstream"),
+            temp_dir.path().join("local"),
+        );
+        let (up, down) = (Some(upstream.as_path()), Some(local.as_path()));
+        fs::create_dir_all(&upstream).unwrap();
+        init_repo(&upstream);
+        git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", "init"], up).unwrap();
+        clone_repo(&upstream, &local);
+
+        // Both sides annotate the same commit before either fetches
+        let sha = git::run_git_cmd(&["rev-parse", "HEAD"], up).unwrap();
+        let theirs = test_support::manifest(&sha, json!({"anchor": {"file": "up.rs"}}));
+        save(&theirs, &sha, up).unwrap();
+        let ours = test_support::manifest(&sha, json!({"anchor": {"file": "down.rs"}}));
+        save(&ours, &sha, down).unwrap();
+
+        let refspec = git::notes_fetch_refspec("origin");
+        git::run_git_cmd(&["fetch", "-q", "origin", &refspec], down).unwrap();
+        let tracking = git::remote_notes_ref("origin");
+        assert_eq!(merge_notes(&tracking, down).unwrap(), 1);
+
+        let mut files: Vec<_> = load_raw(&sha, down)
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|e| e.anchor.file)
+            .collect();
+        files.sort();
+        assert_eq!(files, ["down.rs", "up.rs"]);
+
+        // Merging again finds nothing new
+        assert_eq!(merge_notes(&tracking, down).unwrap(), 0);
+    }
 }
@@ -0,0 +1,52 @@
+//! Manifest fixtures shared by unit tests
+//!
+//! Tests describe only the fields they care about; everything else comes from
+//! one valid entry, a `modify` of `run` in `src/lib.rs`.
+
+use crate::manifest::types::{Entry, Manifest};
+use serde_json::{json, Value};
+
+/// Entry with `overrides` merged over the default one
+///
+/// Objects are merged key by key, so `{"anchor": {"file": "a.rs"}}` keeps the
+/// default symbol and hunk; anything else replaces the default value.
+pub fn entry(overrides: Value) -> Entry {
+    let mut entry = json!({
+        "anchor": { "file": "src/lib.rs", "symbol": "run", "hunkId": "H#1" },
+        "changeType": "modify",
+        "rationale": "Retry on timeout",
+        "behaviorClass": ["bugfix"],
+        "contract": {}
+    });
+    merge(&mut entry, overrides);
+    serde_json::from_value(entry).unwrap()
+}
+
+/// Manifest of `commit` with one [`entry`]
+pub fn manifest(commit: &str, overrides: Value) -> Manifest {
+    manifest_with(commit, vec![overrides])
+}
+
+/// Manifest of `commit` with one [`entry`] per item of `overrides`
+pub fn manifest_with(commit: &str, overrides: Vec<Value>) -> Manifest {
+    let mut manifest = Manifest::new(commit.to_string());
+    manifest.entries = overrides.into_iter().map(entry).collect();
+    manifest
+}
+
+/// Merge `overrides` into `base` the way [`entry`] does, for tests with defaults of their own
+pub fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
@@ -182,6 +182,8 @@ mod tests {
             schema_version: SCHEMA_VERSION_2_0.to_string(),
             commit: "abc123".to_string(),
             global_intent: None,
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![Entry {
                 anchor: Anchor {
                     file: "src/main.rs".to_string(),
@@ -206,6 +208,8 @@ mod tests {
                 feature_flags: None,
                 rationale: "Initial implementation".to_string(),
                 inherits_global_intent: None,
+                package: None,
+                extra: Default::default(),
             }],
         };
 
@@ -233,6 +237,8 @@ mod tests {
                 behavior_class: vec![BEHAVIOR_REFACTOR.to_string()],
                 rationale: "Complete module refactor".to_string(),
             }),
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![],
         };
 
@@ -249,6 +255,8 @@ mod tests {
             schema_version: SCHEMA_VERSION_2_0.to_string(),
             commit: "sig123".to_string(),
             global_intent: None,
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![Entry {
                 anchor: Anchor {
                     file: "lib.rs".to_string(),
@@ -276,6 +284,8 @@ mod tests {
                 feature_flags: None,
                 rationale: "".to_string(),
                 inherits_global_intent: None,
+                package: None,
+                extra: Default::default(),
             }],
         };
 
@@ -292,6 +302,8 @@ mod tests {
             schema_version: SCHEMA_VERSION_2_0.to_string(),
             commit: "compat123".to_string(),
             global_intent: None,
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![Entry {
                 anchor: Anchor {
                     file: "api.rs".to_string(),
@@ -323,6 +335,8 @@ mod tests {
                 feature_flags: None,
                 rationale: "".to_string(),
                 inherits_global_intent: None,
+                package: None,
+                extra: Default::default(),
             }],
         };
 
@@ -342,6 +356,8 @@ mod tests {
             schema_version: SCHEMA_VERSION_2_0.to_string(),
             commit: "abc123".to_string(),
             global_intent: None,
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![Entry {
                 anchor: Anchor {
                     file: "src/main.rs".to_string(),
@@ -366,6 +382,8 @@ mod tests {
                 feature_flags: None,
                 rationale: "Initial implementation".to_string(),
                 inherits_global_intent: None,
+                package: None,
+                extra: Default::default(),
             }],
         };
 
@@ -404,6 +422,8 @@ mod tests {
                 behavior_class: vec![BEHAVIOR_REFACTOR.to_string()],
                 rationale: "Test refactor".to_string(),
             }),
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![],
         };
 
@@ -419,6 +439,36 @@ mod tests {
         assert!(decoded.global_intent.is_some());
     }
 
+    #[test]
+    fn test_toon_round_trip_preserves_unknown_fields() {
+        use toon_format::{decode_default, encode_default};
+
+        // As written by a newer gip with fields this version doesn't know
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "future1",
+            "reviewedBy": "alice",
+            "entries": [{
+                "anchor": { "file": "src/lib.rs", "symbol": "run", "hunkId": "H#1" },
+                "changeType": "modify",
+                "rationale": "Newer fields ride along",
+                "behaviorClass": ["feature"],
+                "contract": {},
+                "riskLevel": "high"
+            }]
+        }))
+        .unwrap();
+        assert_eq!(manifest.extra["reviewedBy"], "alice");
+        assert_eq!(manifest.entries[0].extra["riskLevel"], "high");
+
+        let toon = encode_default(&manifest).unwrap();
+        let decoded: Manifest = decode_default(&toon).unwrap();
+
+        assert_eq!(decoded.extra["reviewedBy"], "alice");
+        assert_eq!(decoded.entries[0].extra["riskLevel"], "high");
+        assert_eq!(decoded, manifest);
+    }
+
     #[test]
     fn test_toon_round_trip_full_entry() {
         use toon_format::{decode_default, encode_default};
@@ -427,6 +477,8 @@ mod tests {
             schema_version: SCHEMA_VERSION_2_0.to_string(),
             commit: "HEAD".to_string(),
             global_intent: None,
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![Entry {
                 anchor: Anchor {
                     file: "src/main.rs".to_string(),
@@ -451,6 +503,8 @@ mod tests {
                 security_notes: None,
                 feature_flags: None,
                 inherits_global_intent: None,
+                package: None,
+                extra: Default::default(),
             }],
         };
 
@@ -474,6 +528,8 @@ mod tests {
                 behavior_class: vec![BEHAVIOR_FEATURE.to_string()],
                 rationale: "Global change".to_string(),
             }),
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![Entry {
                 anchor: Anchor {
                     file: "full.rs".to_string(),
@@ -508,6 +564,8 @@ mod tests {
                 feature_flags: Some(vec!["FLAG_A".to_string()]),
                 rationale: "Full entry".to_string(),
                 inherits_global_intent: Some(true),
+                package: None,
+                extra: Default::default(),
             }],
         };
 
@@ -3,7 +3,9 @@
 //! This module defines the schema for storing structured context about code changes,
 //! including contracts, behavior classifications, and compatibility information.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 /// Schema version constants
 pub const SCHEMA_VERSION_1_0: &str = "1.0";
@@ -27,19 +29,28 @@ pub const CHANGE_MODIFY: &str = "modify";
 pub const CHANGE_DELETE: &str = "delete";
 pub const CHANGE_RENAME: &str = "rename";
 
+/// Anchor symbol for a change to every symbol under the anchored path
+pub const SYMBOL_ANY: &str = "*";
+
 /// Manifest represents a Gip change manifest for a commit
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Manifest {
     pub schema_version: String,
     pub commit: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub global_intent: Option<GlobalIntent>,
+    /// Issue tracker ids the change belongs to, such as `PAY-142` or `#87`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<String>,
     pub entries: Vec<Entry>,
+    /// Fields unknown to this version of gip, kept so re-saving doesn't drop them
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// GlobalIntent represents commit-level rationale for multi-function changes (v2.0)
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GlobalIntent {
     pub behavior_class: Vec<String>,
@@ -47,7 +58,7 @@ pub struct GlobalIntent {
 }
 
 /// Entry represents a single symbol/hunk modification
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Entry {
     pub anchor: Anchor,
@@ -71,10 +82,16 @@ pub struct Entry {
     pub feature_flags: Option<Vec<String>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub inherits_global_intent: Option<bool>,
+    /// Monorepo package/component owning this entry
+    #[serde(default, alias = "component", skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    /// Fields unknown to this version of gip, kept so re-saving doesn't drop them
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 /// Anchor identifies the location of the change
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Anchor {
     pub file: String,
@@ -82,8 +99,20 @@ pub struct Anchor {
     pub hunk_id: String,
 }
 
+impl Anchor {
+    /// Whether the anchor names a path pattern such as `src/generated/**`
+    pub fn is_glob(&self) -> bool {
+        crate::pathspec::is_glob(&self.file)
+    }
+
+    /// Whether the anchor covers a directory or glob rather than one symbol
+    pub fn is_sweeping(&self) -> bool {
+        self.is_glob() || self.symbol == SYMBOL_ANY
+    }
+}
+
 /// SignatureDelta captures API surface changes
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SignatureDelta {
     pub before: String,
@@ -91,7 +120,7 @@ pub struct SignatureDelta {
 }
 
 /// Contract defines the behavioral contract
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Contract {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -107,7 +136,7 @@ pub struct Contract {
 }
 
 /// Compatibility flags (v2.0 enhanced)
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Compatibility {
     pub breaking: bool,
@@ -125,7 +154,7 @@ pub struct Compatibility {
 }
 
 /// PerfBudget captures performance expectations
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PerfBudget {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -141,7 +170,9 @@ impl Manifest {
             schema_version: SCHEMA_VERSION_CURRENT.to_string(),
             commit,
             global_intent: None,
+            issues: Vec::new(),
             entries: Vec::new(),
+            extra: Map::new(),
         }
     }
 
@@ -159,6 +190,13 @@ impl Manifest {
             BEHAVIOR_MIGRATION,
         ]
     }
+
+    /// Rewrite anchor paths to the `/`-separated form git uses
+    pub fn normalize_paths(&mut self) {
+        for entry in &mut self.entries {
+            entry.anchor.file = crate::pathspec::normalize(&entry.anchor.file);
+        }
+    }
 }
 
 #[cfg(test)]
@@ -180,6 +218,8 @@ mod tests {
             schema_version: SCHEMA_VERSION_2_0.to_string(),
             commit: "test123".to_string(),
             global_intent: None,
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![Entry {
                 anchor: Anchor {
                     file: "src/main.rs".to_string(),
@@ -204,6 +244,8 @@ mod tests {
                 security_notes: None,
                 feature_flags: None,
                 inherits_global_intent: None,
+                package: None,
+                extra: Default::default(),
             }],
         };
 
@@ -226,6 +268,8 @@ mod tests {
                 behavior_class: vec![BEHAVIOR_REFACTOR.to_string()],
                 rationale: "Refactor entire module".to_string(),
             }),
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![],
         };
 
@@ -271,6 +315,8 @@ mod tests {
             security_notes: None,
             feature_flags: None,
             inherits_global_intent: Some(false),
+            package: None,
+            extra: Default::default(),
         };
 
         let json = serde_json::to_string_pretty(&entry).unwrap();
@@ -280,6 +326,22 @@ mod tests {
         assert!(deserialized.compatibility.as_ref().unwrap().breaking);
     }
 
+    #[test]
+    fn test_component_alias_is_not_an_unknown_field() {
+        let entry: Entry = serde_json::from_value(serde_json::json!({
+            "anchor": { "file": "api/src/lib.rs", "symbol": "serve", "hunkId": "H#1" },
+            "changeType": "modify",
+            "rationale": "Alias still maps to package",
+            "behaviorClass": ["feature"],
+            "contract": {},
+            "component": "api"
+        }))
+        .unwrap();
+
+        assert_eq!(entry.package.as_deref(), Some("api"));
+        assert!(entry.extra.is_empty());
+    }
+
     #[test]
     fn test_all_behavior_classes() {
         let classes = Manifest::all_behavior_classes();
@@ -3,62 +3,856 @@
 //! Provides functionality for detecting Git conflict markers and injecting
 //! structured context from Gip manifests into them.
 
+use crate::analyzers;
+use crate::atomic;
+use crate::config::Config;
 use crate::git;
-use crate::manifest::{self, Manifest};
+use crate::manifest::{self, Entry, Manifest};
+use crate::pathspec::PathsConfig;
+use crate::profile::{Consumer, Profile};
+use crate::semantic;
+use crate::session;
+use crate::summarize::Summarizer;
+use crate::symbols::{self, MatchQuality};
+use crate::tracker::IssueTracker;
 use anyhow::{Context, Result};
+use serde::Serialize;
+use std::cmp::Reverse;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use tracing::{debug, trace};
+use walkdir::WalkDir;
 
 const CONFLICT_START: &str = "<<<<<<<";
 const CONFLICT_MIDDLE: &str = "=======";
 const CONFLICT_END: &str = ">>>>>>>";
 // const CONFLICT_BASE: &str = "|||||||";
 
+/// Maximum number of entries rendered in a single conflict marker
+const MAX_ENTRIES_PER_MARKER: usize = 3;
+
+/// Prefix of every context line added inside conflict markers
+pub const CONTEXT_PREFIX: &str = "||| ";
+
+/// Directory inside `.gip/` keeping conflicted files as git left them
+pub const CONFLICT_BACKUP_DIR: &str = "backup";
+
+/// Directory inside `.gip/` holding the context of conflicted binary files
+pub const SIDECAR_DIR: &str = "sidecar";
+
+/// Extension of sidecar context files
+const SIDECAR_EXT: &str = "gip-context";
+
+/// Settings for rendering context into markers
+#[derive(Debug, Default)]
+struct MarkerOptions {
+    summarizer: Summarizer,
+    /// Projection applied to entries before the summarizer
+    profile: Profile,
+    paths: PathsConfig,
+    /// See [`crate::summarize::MarkerConfig::focus_threshold`]
+    focus_threshold: usize,
+    /// Titles and statuses of the issues a manifest lists
+    tracker: IssueTracker,
+    /// Conflicts left by a rebase, where HEAD is upstream and theirs is yours
+    rebase: bool,
+}
+
+impl MarkerOptions {
+    fn new(config: Config) -> Result<Self> {
+        Ok(Self {
+            focus_threshold: config.markers.focus_threshold,
+            summarizer: Summarizer::new(config.markers),
+            profile: config.projection.profile_for(Consumer::Markers)?,
+            paths: config.paths,
+            tracker: IssueTracker::new(config.issues),
+            rebase: false,
+        })
+    }
+
+    /// What the ours and theirs regions of a marker hold
+    fn descriptions(&self) -> [&'static str; 2] {
+        if self.rebase {
+            ["Upstream (onto)", "Your commit being replayed"]
+        } else {
+            ["Your changes", "Their changes"]
+        }
+    }
+
+    /// A rationale as the profile and summarizer leave it
+    fn rationale(&self, text: &str) -> String {
+        self.summarizer.rationale(&self.profile.rationale(text))
+    }
+
+    /// Entries shown per marker
+    fn max_entries(&self) -> usize {
+        match self.profile.max_entries {
+            0 => MAX_ENTRIES_PER_MARKER,
+            max => max.min(MAX_ENTRIES_PER_MARKER),
+        }
+    }
+}
+
+/// What a commit without a manifest still says about itself
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct CommitSummary {
+    commit: String,
+    message: String,
+    /// Lines added and removed per changed file; binary files count as 0
+    files: Vec<(String, usize, usize)>,
+}
+
+impl CommitSummary {
+    /// Read the message and changed files of `sha`; `None` when it isn't a commit
+    fn load(sha: &str) -> Option<Self> {
+        let message = git::run_git_cmd(&["log", "-1", "--format=%B", sha], None).ok()?;
+        let numstat = git::run_git_cmd(
+            &[
+                "diff-tree",
+                "-r",
+                "--root",
+                "--no-commit-id",
+                "--numstat",
+                sha,
+            ],
+            None,
+        )
+        .unwrap_or_default();
+        Some(Self {
+            commit: sha.to_string(),
+            message: message.trim().to_string(),
+            files: parse_numstat(&numstat),
+        })
+    }
+
+    /// The summary to fall back on for a side, which needs none with a manifest
+    fn fallback(manifest: Option<&Manifest>, sha: &str) -> Option<Self> {
+        if manifest.is_some() {
+            return None;
+        }
+        let summary = Self::load(sha);
+        if summary.is_none() {
+            debug!("{} is not a commit here; its side gets no context", sha);
+        }
+        summary
+    }
+
+    fn subject(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+
+    /// The message after the subject, on one line
+    fn body(&self) -> String {
+        self.message
+            .lines()
+            .skip(1)
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// `N file(s), +A -D`, then this file's share when the commit changed it
+    fn stat(&self, file_path: &str, aliases: &[String]) -> String {
+        let added: usize = self.files.iter().map(|f| f.1).sum();
+        let removed: usize = self.files.iter().map(|f| f.2).sum();
+        let mut stat = format!("{} file(s), +{} -{}", self.files.len(), added, removed);
+        if let Some((_, a, d)) = self
+            .files
+            .iter()
+            .find(|(path, _, _)| path == file_path || aliases.contains(path))
+        {
+            stat.push_str(&format!("; this file +{} -{}", a, d));
+        }
+        stat
+    }
+}
+
+/// Parse `git diff-tree --numstat` output into `(path, added, removed)`
+fn parse_numstat(output: &str) -> Vec<(String, usize, usize)> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let added = fields.next()?;
+            let removed = fields.next()?;
+            let path = fields.next()?;
+            Some((
+                path.to_string(),
+                added.parse().unwrap_or(0),
+                removed.parse().unwrap_or(0),
+            ))
+        })
+        .collect()
+}
+
+/// Bytes git inspects when guessing whether content is binary
+const BINARY_SNIFF_LEN: usize = 8000;
+
 /// Enrich all conflicted files with context
 pub fn enrich_all_conflicts(ours_sha: &str, theirs_sha: &str) -> Result<usize> {
+    let ours_manifest = manifest::load(ours_sha, None).ok();
+    let theirs_manifest = manifest::load(theirs_sha, None).ok();
+    enrich_all_conflicts_with(ours_sha, ours_manifest, theirs_sha, theirs_manifest)
+}
+
+/// Enrich the conflicts of a merge of `theirs_sha` into `ours_sha`
+///
+/// With `[markers] branch_context`, their side is described by every commit
+/// being merged that touched a conflicted file rather than by the tip alone.
+pub fn enrich_merge_conflicts(ours_sha: &str, theirs_sha: &str) -> Result<usize> {
+    let config = Config::load(&git::get_gip_dir()?)?;
+    if !config.markers.branch_context {
+        return enrich_all_conflicts(ours_sha, theirs_sha);
+    }
+    let ours_manifest = manifest::load(ours_sha, None).ok();
+    let theirs_manifest = branch_manifest(ours_sha, theirs_sha, &get_conflicted_files()?)?
+        .or_else(|| manifest::load(theirs_sha, None).ok());
+    enrich_all_conflicts_with(ours_sha, ours_manifest, theirs_sha, theirs_manifest)
+}
+
+/// The manifests of the commits in `ours..theirs` touching `files`, combined
+///
+/// Entries for the same symbol fold into one, the latest commit's rationale
+/// winning. `None` when none of those commits has a manifest.
+pub fn branch_manifest(
+    ours_sha: &str,
+    theirs_sha: &str,
+    files: &[String],
+) -> Result<Option<Manifest>> {
+    let mut args = vec!["--reverse".to_string(), "--".to_string()];
+    args.extend_from_slice(files);
+    let commits = git::rev_list(&format!("{}..{}", ours_sha, theirs_sha), &args, None)?;
+
+    let manifests: Vec<Manifest> = manifest::load_many(&commits, None)?
+        .into_iter()
+        .map(|(_, m)| m)
+        .collect();
+    debug!(
+        "{} of {} incoming commits touching conflicted files have a manifest",
+        manifests.len(),
+        commits.len()
+    );
+    if manifests.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(manifest::combine(&manifests, theirs_sha)))
+}
+
+/// Enrich all conflicted files with manifests that are not stored as notes
+///
+/// The SHAs are still used to follow renames of the conflicted files.
+pub fn enrich_all_conflicts_with(
+    ours_sha: &str,
+    ours_manifest: Option<Manifest>,
+    theirs_sha: &str,
+    theirs_manifest: Option<Manifest>,
+) -> Result<usize> {
+    let gip_dir = git::get_gip_dir()?;
+    let config = Config::load(&gip_dir)?;
+    if !config.markers.enrich {
+        debug!("Enrichment is turned off; conflicts left as they are");
+        return Ok(0);
+    }
+
+    // Sides without a manifest fall back to their commit message
+    let summaries = [
+        CommitSummary::fallback(ours_manifest.as_ref(), ours_sha),
+        CommitSummary::fallback(theirs_manifest.as_ref(), theirs_sha),
+    ];
+    if ours_manifest.is_none() && theirs_manifest.is_none() && summaries.iter().all(Option::is_none)
+    {
+        debug!(
+            "No manifest or commit for {} or {}; conflicts left as they are",
+            ours_sha, theirs_sha
+        );
+        return Ok(0);
+    }
+
     let conflicted_files = get_conflicted_files()?;
+    debug!("Enriching {} conflicted file(s)", conflicted_files.len());
+    let mut options = MarkerOptions::new(config)?;
+    options.rebase = is_rebasing();
+    prune_conflict_backups(&gip_dir, &conflicted_files)?;
+    let unmerged = git::unmerged_entries(None).unwrap_or_default();
     let mut enriched_count = 0;
+    let mut files = Vec::new();
 
     for file in conflicted_files {
-        if enrich_conflict_markers(&file, ours_sha, theirs_sha)? {
+        // Anchors may still use a name the file had before being renamed on either side
+        let aliases = path_aliases(&file, &[ours_sha, theirs_sha]);
+        let summaries = [summaries[0].as_ref(), summaries[1].as_ref()];
+        let index_conflict = unmerged
+            .iter()
+            .find(|e| e.path == file)
+            .and_then(|e| index_conflict(e, options.descriptions()));
+        let enriched = match index_conflict {
+            // Deletions and mode changes leave no markers to put context in
+            Some(ref kind) if !has_conflict_markers(&file) => {
+                trace!("{}: {}, writing a sidecar", file, kind);
+                write_sidecar(
+                    &gip_dir,
+                    &format!("Gip context for {} ({})", file, kind),
+                    &file,
+                    ours_manifest.as_ref(),
+                    theirs_manifest.as_ref(),
+                    theirs_sha,
+                    summaries,
+                    &options,
+                )?;
+                true
+            }
+            _ => enrich_conflict_markers(
+                &gip_dir,
+                &file,
+                &aliases,
+                ours_manifest.as_ref(),
+                theirs_sha,
+                theirs_manifest.as_ref(),
+                summaries,
+                &options,
+            )?,
+        };
+        if enriched {
             enriched_count += 1;
         }
+
+        let mut entries = Vec::new();
+        for (side, manifest) in [("ours", &ours_manifest), ("theirs", &theirs_manifest)] {
+            let Some(m) = manifest else { continue };
+            entries.extend(
+                file_entries(m, &file, &aliases, options.paths)
+                    .into_iter()
+                    .map(|e| session::MatchedEntry {
+                        side: side.to_string(),
+                        commit: m.commit.clone(),
+                        symbol: e.anchor.symbol.clone(),
+                        rationale: e.rationale.clone(),
+                    }),
+            );
+        }
+        files.push(session::ConflictedFile {
+            binary: is_binary_conflict(&file),
+            file,
+            enriched,
+            index_conflict,
+            entries,
+        });
     }
 
+    record_session(&gip_dir, ours_sha, theirs_sha, files)?;
     Ok(enriched_count)
 }
 
+/// Write `.gip/last-merge.json` for follow-up commands and other tools
+///
+/// Semantic findings cover every commit since the merge base, like `gip merge
+/// --ci`; with no common history there are none.
+fn record_session(
+    gip_dir: &Path,
+    ours_sha: &str,
+    theirs_sha: &str,
+    files: Vec<session::ConflictedFile>,
+) -> Result<()> {
+    let merge_base = git::run_git_cmd(&["merge-base", ours_sha, theirs_sha], None).ok();
+    let mut semantic_findings = match merge_base {
+        Some(ref base) => semantic::find(
+            &semantic::since(base, ours_sha)?,
+            &semantic::since(base, theirs_sha)?,
+        ),
+        None => Vec::new(),
+    };
+    for finding in &mut semantic_findings {
+        finding.conflicted = files.iter().any(|f| f.file == finding.file);
+    }
+
+    session::save(
+        gip_dir,
+        &session::Session {
+            operation: git::operation_in_progress(None).map(|o| o.as_str().to_string()),
+            ours: ours_sha.to_string(),
+            theirs: theirs_sha.to_string(),
+            merge_base,
+            files,
+            semantic_findings,
+        },
+    )
+}
+
+/// Whether the conflicts in the worktree were left by a rebase
+///
+/// A rebase checks out upstream and replays your commits onto it, so HEAD in
+/// its markers is upstream and the other side is your own commit.
+fn is_rebasing() -> bool {
+    git::operation_in_progress(None) == Some(git::Operation::Rebase)
+}
+
+/// The commit being merged, rebased, cherry-picked or reverted into HEAD
+pub fn other_head() -> Option<String> {
+    [
+        "MERGE_HEAD",
+        "REBASE_HEAD",
+        "CHERRY_PICK_HEAD",
+        "REVERT_HEAD",
+    ]
+    .iter()
+    .find_map(|head| git::run_git_cmd(&["rev-parse", "--verify", "-q", head], None).ok())
+}
+
+/// The other side of the conflicts in the worktree
+///
+/// Falls back to the last enrichment run recorded at HEAD, the only trace a
+/// conflicting `gip stash pop` leaves of the stash it applied.
+pub fn conflict_other_head() -> Option<String> {
+    other_head().or_else(|| {
+        let head = git::get_current_commit().ok()?;
+        session::theirs_at(&git::get_gip_dir().ok()?, &head)
+    })
+}
+
+/// A conflict a merge would leave, with the context enrichment would add
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictPreview {
+    pub file: String,
+    /// Binary files get a sidecar instead of enriched markers
+    pub binary: bool,
+    /// Context lines enrichment would add, in marker order
+    pub context: Vec<String>,
+}
+
+/// Predict the conflicts of merging `theirs_sha` into `ours_sha` and their enrichment
+///
+/// Nothing in the working tree or index is touched. Needs `git merge-tree
+/// --write-tree` (git 2.38 or later).
+pub fn preview_conflicts(
+    ours_sha: &str,
+    ours_manifest: Option<&Manifest>,
+    theirs_sha: &str,
+    theirs_manifest: Option<&Manifest>,
+) -> Result<Vec<ConflictPreview>> {
+    let args: Vec<String> = [
+        "merge-tree",
+        "--write-tree",
+        "--name-only",
+        "--no-messages",
+        ours_sha,
+        theirs_sha,
+    ]
+    .iter()
+    .map(|a| a.to_string())
+    .collect();
+    let output = git::run_git_output(&args)?;
+    // Exit status 1 means the merge has conflicts; anything else is an error
+    if !matches!(output.status.code(), Some(0) | Some(1)) {
+        anyhow::bail!(
+            "Could not predict conflicts (git merge-tree needs git 2.38+): {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let tree = lines.next().unwrap_or_default().trim().to_string();
+    let mut files: Vec<String> = Vec::new();
+    for file in lines.map(str::trim).take_while(|l| !l.is_empty()) {
+        if !files.iter().any(|f| f == file) {
+            files.push(file.to_string());
+        }
+    }
+
+    let gip_dir = git::get_gip_dir()?;
+    let config = Config::load(&gip_dir)?;
+    let branch = if config.markers.branch_context && !files.is_empty() {
+        branch_manifest(ours_sha, theirs_sha, &files)?
+    } else {
+        None
+    };
+    let theirs_manifest = branch.as_ref().or(theirs_manifest);
+    let options = MarkerOptions::new(config)?;
+    let summaries = [
+        CommitSummary::fallback(ours_manifest, ours_sha),
+        CommitSummary::fallback(theirs_manifest, theirs_sha),
+    ];
+
+    let mut previews = Vec::new();
+    for file in files {
+        let blob = git::run_git_output(&[
+            "cat-file".to_string(),
+            "blob".to_string(),
+            format!("{}:{}", tree, file),
+        ])?;
+        let text = match String::from_utf8(blob.stdout) {
+            Ok(text) if blob.status.success() && !looks_binary(text.as_bytes()) => text,
+            _ => {
+                previews.push(ConflictPreview {
+                    file,
+                    binary: true,
+                    context: Vec::new(),
+                });
+                continue;
+            }
+        };
+
+        let aliases = path_aliases(&file, &[ours_sha, theirs_sha]);
+        let enriched = enrich_content(
+            &text,
+            &file,
+            &aliases,
+            ours_manifest,
+            theirs_manifest,
+            [summaries[0].as_ref(), summaries[1].as_ref()],
+            &options,
+        );
+        let context = enriched
+            .lines()
+            .filter(|l| is_context_line(l))
+            .map(str::to_string)
+            .collect();
+        previews.push(ConflictPreview {
+            file,
+            binary: false,
+            context,
+        });
+    }
+    Ok(previews)
+}
+
 /// Get list of conflicted files
-fn get_conflicted_files() -> Result<Vec<String>> {
+pub fn get_conflicted_files() -> Result<Vec<String>> {
     // git diff --name-only --diff-filter=U
     let output = git::run_git_cmd(&["diff", "--name-only", "--diff-filter=U"], None)?;
 
     Ok(output.lines().map(|s| s.trim().to_string()).collect())
 }
 
+/// Where the unenriched copy of a conflicted file is kept
+pub fn conflict_backup_path(gip_dir: &Path, file_path: &str) -> PathBuf {
+    gip_dir.join(CONFLICT_BACKUP_DIR).join(file_path)
+}
+
+/// Delete the backups and sidecars of files that are no longer conflicted
+///
+/// Once a merge, rebase or stash pop concludes nothing is conflicted, so every
+/// backup goes.
+pub fn prune_conflict_backups(gip_dir: &Path, conflicted: &[String]) -> Result<usize> {
+    let backups = prune_dir(&gip_dir.join(CONFLICT_BACKUP_DIR), conflicted, "")?;
+    let sidecars = prune_dir(
+        &gip_dir.join(SIDECAR_DIR),
+        conflicted,
+        &format!(".{}", SIDECAR_EXT),
+    )?;
+    Ok(backups + sidecars)
+}
+
+/// Remove files under `dir` whose path, minus `suffix`, is not in `conflicted`
+fn prune_dir(dir: &Path, conflicted: &[String], suffix: &str) -> Result<usize> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+
+    let mut removed = 0;
+    for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        let rel = rel.strip_suffix(suffix).unwrap_or(&rel);
+        if !conflicted.iter().any(|c| c == rel) {
+            fs::remove_file(entry.path())
+                .with_context(|| format!("Failed to remove {:?}", entry.path()))?;
+            removed += 1;
+        }
+    }
+
+    // Drop directories left empty, deepest first
+    for entry in WalkDir::new(dir)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+    {
+        if entry.file_type().is_dir() {
+            let _ = fs::remove_dir(entry.path());
+        }
+    }
+
+    Ok(removed)
+}
+
+/// Where the context of a conflicted file is written outside the file
+///
+/// Used for binary conflicts and by `gip mergetool`.
+pub fn sidecar_path(gip_dir: &Path, file_path: &str) -> PathBuf {
+    gip_dir
+        .join(SIDECAR_DIR)
+        .join(format!("{}.{}", file_path, SIDECAR_EXT))
+}
+
+/// Whether git treats a conflicted path as binary
+///
+/// Compares the two sides with `git diff --numstat`, which honors
+/// `.gitattributes` and reports `-` counts for binary content. When a side is
+/// missing (modify/delete conflicts), falls back to git's own heuristic of a
+/// NUL byte near the start of the file.
+pub fn is_binary_conflict(file_path: &str) -> bool {
+    let ours = format!(":2:{}", file_path);
+    let theirs = format!(":3:{}", file_path);
+    if let Ok(numstat) = git::run_git_cmd(&["diff", "--numstat", &ours, &theirs], None) {
+        return numstat.starts_with("-\t-\t");
+    }
+
+    fs::read(file_path).is_ok_and(|bytes| looks_binary(&bytes))
+}
+
+/// Git's binary heuristic: a NUL byte in the first few kilobytes
+fn looks_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0)
+}
+
+/// What each side did to a file whose conflict git keeps in the index only
+///
+/// Deleted-by-us/them, rename/rename and mode conflicts may leave no file in
+/// the worktree, or one without markers. Other conflicts give `None`.
+fn index_conflict(entry: &git::UnmergedEntry, descriptions: [&str; 2]) -> Option<String> {
+    let [ours, theirs] = descriptions;
+    let theirs = theirs.to_lowercase();
+    let [_, ours_mode, theirs_mode] = &entry.modes;
+    match entry.state.as_str() {
+        "UD" => Some(format!("{} modified it, {} deleted it", ours, theirs)),
+        "DU" => Some(format!("{} deleted it, {} modified it", ours, theirs)),
+        "DD" => Some("both sides renamed or deleted it".to_string()),
+        "UU" | "AA" if ours_mode != theirs_mode => Some(format!(
+            "{} set mode {}, {} set mode {}",
+            ours, ours_mode, theirs, theirs_mode
+        )),
+        _ => None,
+    }
+}
+
+/// Whether `file_path` is in the worktree with conflict markers in it
+fn has_conflict_markers(file_path: &str) -> bool {
+    fs::read(file_path).is_ok_and(|bytes| {
+        bytes
+            .windows(CONFLICT_START.len())
+            .any(|w| w == CONFLICT_START.as_bytes())
+    })
+}
+
+/// Write both sides' context next to a file that can't carry it in markers
+#[allow(clippy::too_many_arguments)]
+fn write_sidecar(
+    gip_dir: &Path,
+    heading: &str,
+    file_path: &str,
+    ours_manifest: Option<&Manifest>,
+    theirs_manifest: Option<&Manifest>,
+    theirs_sha: &str,
+    summaries: [Option<&CommitSummary>; 2],
+    options: &MarkerOptions,
+) -> Result<PathBuf> {
+    let mut output = format!("{}\n", heading);
+    match ours_manifest {
+        Some(m) => output.push_str(&format_enriched_marker(
+            "HEAD",
+            options.descriptions()[0],
+            m,
+            file_path,
+            &[],
+            None,
+            options,
+        )),
+        None => {
+            if let Some(summary) = summaries[0] {
+                output.push_str(&format_unrecorded_marker(
+                    "HEAD",
+                    options.descriptions()[0],
+                    summary,
+                    file_path,
+                    &[],
+                    options,
+                ));
+            }
+        }
+    }
+    let theirs_label = &theirs_sha[..theirs_sha.len().min(8)];
+    match theirs_manifest {
+        Some(m) => output.push_str(&format_enriched_marker(
+            theirs_label,
+            options.descriptions()[1],
+            m,
+            file_path,
+            &[],
+            None,
+            options,
+        )),
+        None => {
+            if let Some(summary) = summaries[1] {
+                output.push_str(&format_unrecorded_marker(
+                    theirs_label,
+                    options.descriptions()[1],
+                    summary,
+                    file_path,
+                    &[],
+                    options,
+                ));
+            }
+        }
+    }
+
+    let path = sidecar_path(gip_dir, file_path);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context("Failed to create sidecar directory")?;
+    }
+    atomic::write(&path, output).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(path)
+}
+
+/// Enriched files whose staged content still has context lines in it
+///
+/// The enriched files are those with a backup. A file that is no longer in
+/// the index, because the resolution deleted it, has nothing left over.
+pub fn leftover_context(gip_dir: &Path, cwd: Option<&Path>) -> Result<Vec<String>> {
+    let dir = gip_dir.join(CONFLICT_BACKUP_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut leftover = Vec::new();
+    for entry in WalkDir::new(&dir).into_iter().filter_map(|e| e.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(&dir).unwrap_or(entry.path());
+        let rel = rel.to_string_lossy().replace('\\', "/");
+        let Ok(staged) = git::run_git_cmd(&["show", &format!(":{}", rel)], cwd) else {
+            continue;
+        };
+        if staged.lines().any(is_context_line) {
+            leftover.push(rel);
+        }
+    }
+    leftover.sort();
+    Ok(leftover)
+}
+
+/// [`prune_conflict_backups`] for the current repository state
+pub fn cleanup_conflict_backups() -> Result<usize> {
+    prune_conflict_backups(&git::get_gip_dir()?, &get_conflicted_files()?)
+}
+
 /// Enrich conflict markers in a single file
-fn enrich_conflict_markers(file_path: &str, ours_sha: &str, theirs_sha: &str) -> Result<bool> {
+#[allow(clippy::too_many_arguments)]
+fn enrich_conflict_markers(
+    gip_dir: &Path,
+    file_path: &str,
+    aliases: &[String],
+    ours_manifest: Option<&Manifest>,
+    theirs_sha: &str,
+    theirs_manifest: Option<&Manifest>,
+    summaries: [Option<&CommitSummary>; 2],
+    options: &MarkerOptions,
+) -> Result<bool> {
     let path = Path::new(file_path);
     if !path.exists() {
         return Ok(false);
     }
 
-    let content = fs::read_to_string(path).context("Failed to read conflicted file")?;
+    // A stray marker byte sequence in an image or model file must not be rewritten
+    if is_binary_conflict(file_path) {
+        trace!("{}: binary conflict, writing a sidecar", file_path);
+        write_sidecar(
+            gip_dir,
+            &format!("Gip context for conflicted binary file {}", file_path),
+            file_path,
+            ours_manifest,
+            theirs_manifest,
+            theirs_sha,
+            summaries,
+            options,
+        )?;
+        return Ok(true);
+    }
+
+    let bytes = fs::read(path).context("Failed to read conflicted file")?;
+    let content = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        // Text in another encoding can't be rewritten safely either
+        Err(_) => {
+            trace!("{}: not UTF-8, skipped", file_path);
+            return Ok(false);
+        }
+    };
 
     if !content.contains(CONFLICT_START) {
+        trace!("{}: no conflict markers, skipped", file_path);
         return Ok(false);
     }
 
-    // Load manifests
-    let ours_manifest = manifest::load(ours_sha, None).ok();
-    let theirs_manifest = manifest::load(theirs_sha, None).ok();
-
-    if ours_manifest.is_none() && theirs_manifest.is_none() {
-        return Ok(false);
+    // Keep git's version so the conflict can be recovered if enrichment goes wrong
+    let backup = conflict_backup_path(gip_dir, file_path);
+    if let Some(dir) = backup.parent() {
+        fs::create_dir_all(dir).context("Failed to create conflict backup directory")?;
     }
+    atomic::write(&backup, &content)
+        .with_context(|| format!("Failed to back up {} to {:?}", file_path, backup))?;
+
+    let output = enrich_content(
+        &content,
+        file_path,
+        aliases,
+        ours_manifest,
+        theirs_manifest,
+        summaries,
+        options,
+    );
+
+    atomic::write(path, output).context("Failed to write enriched file")?;
+    Ok(true)
+}
 
+/// Add context from both sides' manifests to the conflict regions of `content`
+///
+/// In files with more than `focus_threshold` conflicts (generated code, mass
+/// renames), only conflicts inside the symbol of a manifest entry get context.
+/// The first of the others carries one summary of the file's entries instead.
+///
+/// A side without a manifest gets its commit message and changed-file stats
+/// from `summaries`, given as ours then theirs, and is labelled as such.
+fn enrich_content(
+    content: &str,
+    file_path: &str,
+    aliases: &[String],
+    ours_manifest: Option<&Manifest>,
+    theirs_manifest: Option<&Manifest>,
+    summaries: [Option<&CommitSummary>; 2],
+    options: &MarkerOptions,
+) -> String {
     let mut output = String::new();
     let lines: Vec<&str> = content.lines().collect();
+    let regions = conflict_regions(&lines);
+
+    let focus = if options.focus_threshold > 0 && regions.len() > options.focus_threshold {
+        let spans = entry_spans(
+            &lines,
+            file_path,
+            aliases,
+            [ours_manifest, theirs_manifest],
+            options.paths,
+        );
+        let skipped = regions.iter().filter(|r| !overlaps(**r, &spans)).count();
+        debug!(
+            "{}: {} conflicts, {} outside manifest entries get a summary only",
+            file_path,
+            regions.len(),
+            skipped
+        );
+        Some((spans, skipped))
+    } else {
+        None
+    };
+    let mut summarized = false;
+    let mut enrich = true;
     let mut current_line_idx = 0;
 
     while current_line_idx < lines.len() {
@@ -68,14 +862,48 @@ fn enrich_conflict_markers(file_path: &str, ours_sha: &str, theirs_sha: &str) ->
             output.push_str(line);
             output.push('\n');
 
+            if let Some((ref spans, skipped)) = focus {
+                enrich = regions
+                    .iter()
+                    .find(|(start, _)| *start == current_line_idx)
+                    .is_some_and(|r| overlaps(*r, spans));
+                if !enrich && !summarized {
+                    summarized = true;
+                    output.push_str(&format_file_summary(
+                        file_path,
+                        aliases,
+                        [ours_manifest, theirs_manifest],
+                        summaries,
+                        (skipped, regions.len()),
+                        options,
+                    ));
+                }
+            }
+
             // Get context before this marker for symbol detection
             let context_start = current_line_idx.saturating_sub(50);
             let context = &lines[context_start..current_line_idx];
 
-            if let Some(ref m) = ours_manifest {
-                let context =
-                    format_enriched_marker("HEAD", "Your changes", m, file_path, Some(context));
-                output.push_str(&context);
+            match (ours_manifest, summaries[0]) {
+                _ if !enrich => {}
+                (Some(m), _) => output.push_str(&format_enriched_marker(
+                    "HEAD",
+                    options.descriptions()[0],
+                    m,
+                    file_path,
+                    aliases,
+                    Some(context),
+                    options,
+                )),
+                (None, Some(summary)) => output.push_str(&format_unrecorded_marker(
+                    "HEAD",
+                    options.descriptions()[0],
+                    summary,
+                    file_path,
+                    aliases,
+                    options,
+                )),
+                _ => {}
             }
         } else if line.starts_with(CONFLICT_MIDDLE) {
             output.push_str(line);
@@ -89,10 +917,26 @@ fn enrich_conflict_markers(file_path: &str, ours_sha: &str, theirs_sha: &str) ->
             let context_start = current_line_idx.saturating_sub(100);
             let context = &lines[context_start..current_line_idx];
 
-            if let Some(ref m) = theirs_manifest {
-                let context =
-                    format_enriched_marker(branch, "Their changes", m, file_path, Some(context));
-                output.push_str(&context);
+            match (theirs_manifest, summaries[1]) {
+                _ if !enrich => {}
+                (Some(m), _) => output.push_str(&format_enriched_marker(
+                    branch,
+                    options.descriptions()[1],
+                    m,
+                    file_path,
+                    aliases,
+                    Some(context),
+                    options,
+                )),
+                (None, Some(summary)) => output.push_str(&format_unrecorded_marker(
+                    branch,
+                    options.descriptions()[1],
+                    summary,
+                    file_path,
+                    aliases,
+                    options,
+                )),
+                _ => {}
             }
 
             output.push_str(line);
@@ -104,151 +948,519 @@ fn enrich_conflict_markers(file_path: &str, ours_sha: &str, theirs_sha: &str) ->
         current_line_idx += 1;
     }
 
-    fs::write(path, output).context("Failed to write enriched file")?;
-    Ok(true)
+    output
 }
 
-fn format_enriched_marker(
-    side: &str,
-    description: &str,
-    manifest: &Manifest,
+/// Context lines enrichment adds to each conflict region of `content`
+///
+/// Every region gets its own context, whatever `focus_threshold` says. The
+/// lines are returned without [`CONTEXT_PREFIX`].
+pub fn conflict_context(
+    content: &str,
     file_path: &str,
-    context: Option<&[&str]>,
-) -> String {
-    let mut output = String::new();
-
-    output.push_str(&format!("||| Gip CONTEXT ({} - {})\n", side, description));
-    output.push_str(&format!("||| Commit: {}\n", manifest.commit));
+    ours_sha: &str,
+    theirs_sha: Option<&str>,
+) -> Result<Vec<Vec<String>>> {
+    let ours_manifest = manifest::load(ours_sha, None).ok();
+    let theirs_manifest = theirs_sha.and_then(|sha| manifest::load(sha, None).ok());
+    let mut options = MarkerOptions::new(Config::load(&git::get_gip_dir()?)?)?;
+    options.focus_threshold = 0;
+    options.rebase = is_rebasing();
+    let summaries = [
+        CommitSummary::fallback(ours_manifest.as_ref(), ours_sha),
+        theirs_sha.and_then(|sha| CommitSummary::fallback(theirs_manifest.as_ref(), sha)),
+    ];
 
-    // Find relevant entry
-    let entry = find_entry(manifest, file_path, context);
+    let revs: Vec<&str> = std::iter::once(ours_sha).chain(theirs_sha).collect();
+    let aliases = path_aliases(file_path, &revs);
+    let enriched = enrich_content(
+        content,
+        file_path,
+        &aliases,
+        ours_manifest.as_ref(),
+        theirs_manifest.as_ref(),
+        [summaries[0].as_ref(), summaries[1].as_ref()],
+        &options,
+    );
 
-    if let Some(e) = entry {
-        if !e.behavior_class.is_empty() {
-            output.push_str(&format!(
-                "||| behaviorClass: {}\n",
-                e.behavior_class.join(", ")
-            ));
+    let mut regions: Vec<Vec<String>> = Vec::new();
+    for line in enriched.lines() {
+        if line.starts_with(CONFLICT_START) {
+            regions.push(Vec::new());
+        } else if let Some(text) = line.strip_prefix(CONTEXT_PREFIX) {
+            if let Some(region) = regions.last_mut() {
+                region.push(text.to_string());
+            }
         }
+    }
+    Ok(regions)
+}
 
-        if !e.rationale.is_empty() {
-            output.push_str(&format!("||| rationale: {}\n", e.rationale));
+/// First and last line index of each conflict region
+fn conflict_regions(lines: &[&str]) -> Vec<(usize, usize)> {
+    let mut regions = Vec::new();
+    let mut start = None;
+    for (i, line) in lines.iter().enumerate() {
+        if line.starts_with(CONFLICT_START) {
+            start = Some(i);
+        } else if line.starts_with(CONFLICT_END) {
+            if let Some(s) = start.take() {
+                regions.push((s, i));
+            }
         }
+    }
+    regions
+}
 
-        if let Some(ref compat) = e.compatibility {
-            output.push_str(&format!("||| breaking: {}\n", compat.breaking));
+/// Whether a conflict region shares a line with any span
+fn overlaps((start, end): (usize, usize), spans: &[(usize, usize)]) -> bool {
+    spans.iter().any(|&(s, e)| s <= end && start <= e)
+}
 
-            if let Some(ref migs) = compat.migrations {
-                for (i, mig) in migs.iter().enumerate() {
-                    output.push_str(&format!("||| migrations[{}]: {}\n", i, mig));
+/// Line spans of the definitions of the symbols the manifests' entries name
+///
+/// A definition ends at the next line indented no deeper than it, usually
+/// its closing brace, so the span covers the symbol's body.
+fn entry_spans(
+    lines: &[&str],
+    file_path: &str,
+    aliases: &[String],
+    manifests: [Option<&Manifest>; 2],
+    paths: PathsConfig,
+) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    for manifest in manifests.into_iter().flatten() {
+        for entry in named_entries(manifest, file_path, aliases, paths) {
+            if entry.anchor.is_sweeping() {
+                continue;
+            }
+            for (i, line) in lines.iter().enumerate() {
+                let defines = symbols::match_line(&entry.anchor.symbol, line)
+                    .is_some_and(|m| m.is_definition && m.quality == MatchQuality::Exact);
+                if defines {
+                    spans.push((i, definition_end(lines, i)));
                 }
             }
         }
+    }
+    spans
+}
 
-        if let Some(ref inputs) = e.contract.inputs {
-            for (i, input) in inputs.iter().enumerate() {
-                output.push_str(&format!("||| inputs[{}]: {}\n", i, input));
-            }
-        }
+/// Index of the last line of the definition starting at `start`
+fn definition_end(lines: &[&str], start: usize) -> usize {
+    let indent = analyzers::indentation(lines[start]);
+    lines
+        .iter()
+        .enumerate()
+        .skip(start + 1)
+        .find(|(_, l)| {
+            !l.trim().is_empty() && !is_marker_line(l) && analyzers::indentation(l) <= indent
+        })
+        .map_or(lines.len() - 1, |(i, _)| i)
+}
 
-        if let Some(ref outputs) = e.contract.outputs {
-            output.push_str(&format!("||| outputs: {}\n", outputs));
-        }
+/// Conflict markers and enrichment lines, which say nothing about nesting
+fn is_marker_line(line: &str) -> bool {
+    [CONFLICT_START, CONFLICT_MIDDLE, CONFLICT_END, "|||||||"]
+        .iter()
+        .any(|m| line.starts_with(m))
+        || is_context_line(line)
+}
+
+/// One block describing both sides' entries for a file, for conflicts left bare
+fn format_file_summary(
+    file_path: &str,
+    aliases: &[String],
+    manifests: [Option<&Manifest>; 2],
+    summaries: [Option<&CommitSummary>; 2],
+    (skipped, total): (usize, usize),
+    options: &MarkerOptions,
+) -> String {
+    let mut output = String::from("||| Gip CONTEXT (file summary)\n");
+    output.push_str(&format!(
+        "||| {} of {} conflicts in this file are outside manifest entries and have no context of their own\n",
+        skipped, total
+    ));
 
-        if !e.contract.preconditions.is_empty() {
-            for (i, pre) in e.contract.preconditions.iter().enumerate() {
-                output.push_str(&format!("||| preconditions[{}]: {}\n", i, pre));
+    for (side, manifest, summary) in [
+        ("HEAD", manifests[0], summaries[0]),
+        (
+            if options.rebase { "replayed" } else { "theirs" },
+            manifests[1],
+            summaries[1],
+        ),
+    ] {
+        let Some(m) = manifest else {
+            if let Some(summary) = summary {
+                output.push_str(&format!(
+                    "||| {} commit: {} (no manifest)\n|||   message: {}\n",
+                    side,
+                    summary.commit,
+                    summary.subject()
+                ));
             }
-        }
+            continue;
+        };
+        output.push_str(&format!("||| {} commit: {}\n", side, m.commit));
 
-        if !e.contract.postconditions.is_empty() {
-            for (i, post) in e.contract.postconditions.iter().enumerate() {
-                output.push_str(&format!("||| postconditions[{}]: {}\n", i, post));
+        let entries = file_entries(m, file_path, aliases, options.paths);
+        if entries.is_empty() {
+            if let Some(ref gi) = m.global_intent {
+                output.push_str(&format!(
+                    "|||   rationale: {}\n",
+                    options.rationale(&gi.rationale)
+                ));
             }
         }
+        for e in entries.iter().take(options.max_entries()) {
+            output.push_str(&format!(
+                "|||   {}: {}\n",
+                e.anchor.symbol,
+                options.rationale(&e.rationale)
+            ));
+        }
+        if entries.len() > options.max_entries() {
+            output.push_str(&format!(
+                "|||   (+{} more entries, see `gip context {}`)\n",
+                entries.len() - options.max_entries(),
+                m.commit
+            ));
+        }
+    }
 
-        if !e.contract.error_model.is_empty() {
-            for (i, err) in e.contract.error_model.iter().enumerate() {
-                output.push_str(&format!("||| errorModel[{}]: {}\n", i, err));
+    output
+}
+
+/// Collect the previous names of a file across the given revisions
+pub fn path_aliases(file_path: &str, revs: &[&str]) -> Vec<String> {
+    let mut aliases: Vec<String> = Vec::new();
+    for rev in revs {
+        for path in git::file_path_history(rev, file_path, None) {
+            if path != file_path && !aliases.contains(&path) {
+                aliases.push(path);
             }
         }
+    }
+    aliases
+}
 
-        if !e.side_effects.is_empty() {
-            for (i, side) in e.side_effects.iter().enumerate() {
-                output.push_str(&format!("||| sideEffects[{}]: {}\n", i, side));
-            }
+/// Whether a line was added by enrichment (the diff3 `|||||||` marker is not)
+pub fn is_context_line(line: &str) -> bool {
+    line.starts_with(CONTEXT_PREFIX)
+}
+
+/// Remove enrichment from conflict regions, leaving every other byte as is
+///
+/// Returns the stripped content and the number of lines removed.
+pub fn strip_context(content: &str) -> (String, usize) {
+    let mut output = String::with_capacity(content.len());
+    let mut in_conflict = false;
+    let mut removed = 0;
+
+    for line in content.split_inclusive('\n') {
+        if line.starts_with(CONFLICT_START) {
+            in_conflict = true;
+        } else if line.starts_with(CONFLICT_END) {
+            in_conflict = false;
+        } else if in_conflict && is_context_line(line) {
+            removed += 1;
+            continue;
         }
+        output.push_str(line);
+    }
 
-        output.push_str(&format!("||| symbol: {}\n", e.anchor.symbol));
-    } else {
+    (output, removed)
+}
+
+fn format_enriched_marker(
+    side: &str,
+    description: &str,
+    manifest: &Manifest,
+    file_path: &str,
+    aliases: &[String],
+    context: Option<&[&str]>,
+    options: &MarkerOptions,
+) -> String {
+    let mut output = String::new();
+
+    output.push_str(&format!("||| Gip CONTEXT ({} - {})\n", side, description));
+    output.push_str(&format!("||| Commit: {}\n", manifest.commit));
+    for id in &manifest.issues {
+        if let Some(issue) = options.tracker.summary(id) {
+            output.push_str(&format!("||| issue: {}\n", issue.line(id)));
+        }
+    }
+
+    // Find relevant entries, most relevant first
+    let entries = rank_entries(manifest, file_path, aliases, context, options.paths);
+
+    if entries.is_empty() {
         // Fallback to global intent if no specific entry found
         if let Some(ref gi) = manifest.global_intent {
             output.push_str(&format!(
                 "||| behaviorClass: {}\n",
                 gi.behavior_class.join(", ")
             ));
-            output.push_str(&format!("||| rationale: {}\n", gi.rationale));
+            output.push_str(&format!(
+                "||| rationale: {}\n",
+                options.rationale(&gi.rationale)
+            ));
+        }
+        return output;
+    }
+
+    let mut condensed = false;
+    for (i, e) in entries.iter().take(options.max_entries()).enumerate() {
+        if i > 0 {
+            output.push_str("||| ---\n");
         }
+        let projected = options.profile.project_entry(e);
+        condensed |= projected != **e;
+        condensed |= format_entry(&mut output, &projected, &options.summarizer);
+    }
+
+    if entries.len() > options.max_entries() {
+        output.push_str(&format!(
+            "||| (+{} more entries, see `gip context {}`)\n",
+            entries.len() - options.max_entries(),
+            manifest.commit
+        ));
+    } else if condensed {
+        output.push_str(&format!(
+            "||| (summarized, see `gip context {}`)\n",
+            manifest.commit
+        ));
     }
 
     output
 }
 
-fn find_entry<'a>(
+/// Marker for a side whose commit has no manifest
+///
+/// Its commit message and the size of its change are all a resolver has to
+/// go on, so they stand in for the entries.
+fn format_unrecorded_marker(
+    side: &str,
+    description: &str,
+    summary: &CommitSummary,
+    file_path: &str,
+    aliases: &[String],
+    options: &MarkerOptions,
+) -> String {
+    let mut output = format!(
+        "||| Gip CONTEXT ({} - {}, no manifest)\n||| Commit: {}\n",
+        side, description, summary.commit
+    );
+    if !summary.subject().is_empty() {
+        output.push_str(&format!("||| message: {}\n", summary.subject()));
+    }
+    let body = summary.body();
+    if !body.is_empty() {
+        output.push_str(&format!("||| details: {}\n", options.rationale(&body)));
+    }
+    output.push_str(&format!(
+        "||| changed: {}\n",
+        summary.stat(file_path, aliases)
+    ));
+    output.push_str(&format!(
+        "||| No recorded intent on this side; full message: `git log -1 {}`\n",
+        summary.commit
+    ));
+    output
+}
+
+/// Append the marker lines describing a single entry
+///
+/// Returns whether the summarizer left anything out.
+fn format_entry(output: &mut String, e: &Entry, summarizer: &Summarizer) -> bool {
+    let mut condensed = false;
+
+    if !e.behavior_class.is_empty() {
+        output.push_str(&format!(
+            "||| behaviorClass: {}\n",
+            e.behavior_class.join(", ")
+        ));
+    }
+
+    if !e.rationale.is_empty() {
+        let rationale = summarizer.rationale(&e.rationale);
+        if rationale != e.rationale {
+            condensed = true;
+        }
+        output.push_str(&format!("||| rationale: {}\n", rationale));
+    }
+
+    if let Some(ref compat) = e.compatibility {
+        output.push_str(&format!("||| breaking: {}\n", compat.breaking));
+
+        if let Some(ref migs) = compat.migrations {
+            condensed |= push_list(output, "migrations", migs, summarizer);
+        }
+    }
+
+    if let Some(ref inputs) = e.contract.inputs {
+        condensed |= push_list(output, "inputs", inputs, summarizer);
+    }
+
+    if let Some(ref outputs) = e.contract.outputs {
+        output.push_str(&format!("||| outputs: {}\n", outputs));
+    }
+
+    condensed |= push_list(
+        output,
+        "preconditions",
+        &e.contract.preconditions,
+        summarizer,
+    );
+    condensed |= push_list(
+        output,
+        "postconditions",
+        &e.contract.postconditions,
+        summarizer,
+    );
+    condensed |= push_list(output, "errorModel", &e.contract.error_model, summarizer);
+    condensed |= push_list(output, "sideEffects", &e.side_effects, summarizer);
+
+    output.push_str(&format!("||| symbol: {}\n", e.anchor.symbol));
+    condensed
+}
+
+/// Append `label[i]: item` lines, returning whether the summarizer dropped any
+fn push_list(output: &mut String, label: &str, items: &[String], summarizer: &Summarizer) -> bool {
+    let (kept, omitted) = summarizer.items(items);
+    for (i, item) in kept.iter().enumerate() {
+        output.push_str(&format!("||| {}[{}]: {}\n", label, i, item));
+    }
+    if omitted > 0 {
+        output.push_str(&format!("||| {}: +{} more\n", label, omitted));
+    }
+    omitted > 0
+}
+
+/// Last component of a path, whichever separator it uses
+fn base_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Ranking of a symbol match: definition, match quality, then lower indentation
+type MatchRank = (bool, MatchQuality, Reverse<usize>);
+
+/// Entries anchored to the file itself, under its current or a previous name
+fn named_entries<'a>(
     manifest: &'a Manifest,
     file_path: &str,
-    context: Option<&[&str]>,
-) -> Option<&'a crate::manifest::Entry> {
-    // 1. Filter entries by file path
-    let filename = Path::new(file_path).file_name()?.to_str()?;
+    aliases: &[String],
+    paths: PathsConfig,
+) -> Vec<&'a Entry> {
+    let filename = base_name(file_path);
+    if filename.is_empty() {
+        return Vec::new();
+    }
 
-    let file_entries: Vec<&crate::manifest::Entry> = manifest
+    manifest
         .entries
         .iter()
         .filter(|e| {
-            e.anchor.file == file_path
-                || Path::new(&e.anchor.file)
-                    .file_name()
-                    .map(|n| n.to_str().unwrap_or(""))
-                    == Some(filename)
+            !e.anchor.is_glob()
+                && (paths.same_file(&e.anchor.file, file_path)
+                    || aliases.iter().any(|a| paths.same_file(&e.anchor.file, a))
+                    || paths.same_file(base_name(&e.anchor.file), filename))
         })
-        .collect();
+        .collect()
+}
 
+/// Entries for the file, or the directory- and glob-level ones when none name it
+pub fn file_entries<'a>(
+    manifest: &'a Manifest,
+    file_path: &str,
+    aliases: &[String],
+    paths: PathsConfig,
+) -> Vec<&'a Entry> {
+    let entries = named_entries(manifest, file_path, aliases, paths);
+    if entries.is_empty() {
+        return sweeping_entries(manifest, file_path, aliases, paths);
+    }
+    entries
+}
+
+/// Directory- and glob-level entries covering the file
+fn sweeping_entries<'a>(
+    manifest: &'a Manifest,
+    file_path: &str,
+    aliases: &[String],
+    paths: PathsConfig,
+) -> Vec<&'a Entry> {
+    manifest
+        .entries
+        .iter()
+        .filter(|e| {
+            e.anchor.is_sweeping()
+                && std::iter::once(file_path)
+                    .chain(aliases.iter().map(|a| a.as_str()))
+                    .any(|p| paths.matches(&e.anchor.file, p))
+        })
+        .collect()
+}
+
+/// Rank the entries relevant to a conflict, most relevant first
+///
+/// Entries whose symbol appears in the context are ordered by their best match.
+/// When none match, the first entry for the file is returned on its own.
+/// Directory- and glob-level entries are only used when no entry names the file.
+pub fn rank_entries<'a>(
+    manifest: &'a Manifest,
+    file_path: &str,
+    aliases: &[String],
+    context: Option<&[&str]>,
+    paths: PathsConfig,
+) -> Vec<&'a Entry> {
+    // 1. Filter entries by file path (or a name the file had before a rename)
+    let file_entries = named_entries(manifest, file_path, aliases, paths);
     if file_entries.is_empty() {
-        return None;
+        // Sweeping changes (a reformatted folder, regenerated code) as a last resort
+        return sweeping_entries(manifest, file_path, aliases, paths);
     }
 
-    // 2. If context is available, try to match symbol
+    // 2. If context is available, rank entries by how well their symbol matches
     if let Some(lines) = context {
-        let mut best_entry: Option<&crate::manifest::Entry> = None;
-        let mut min_indent = usize::MAX;
-
-        // We search backwards from the conflict
-        for line in lines.iter().rev() {
-            // Calculate indentation (spaces/tabs)
-            let indent = line.chars().take_while(|c| c.is_whitespace()).count();
-
-            for entry in &file_entries {
-                if line.contains(&entry.anchor.symbol) {
-                    // Found a match.
-                    // Heuristic: The enclosing function definition usually has
-                    // lower indentation than the code inside it (including calls).
-                    // We prefer the match with the lowest indentation found so far.
-                    if indent < min_indent {
-                        best_entry = Some(entry);
-                        min_indent = indent;
+        let mut ranked: Vec<(&Entry, MatchRank)> = Vec::new();
+
+        for entry in &file_entries {
+            let mut best: Option<MatchRank> = None;
+
+            // We search backwards from the conflict
+            for line in lines.iter().rev() {
+                if let Some(m) = symbols::match_line(&entry.anchor.symbol, line) {
+                    // Heuristic: a definition line (fn/def/func...) beats a mere
+                    // occurrence, an exact name beats a fuzzy one, and the
+                    // enclosing definition usually has lower indentation than
+                    // the code inside it (including calls).
+                    let indent = line.chars().take_while(|c| c.is_whitespace()).count();
+                    let rank = (m.is_definition, m.quality, Reverse(indent));
+                    let better = match best {
+                        Some(ref current) => rank > *current,
+                        None => true,
+                    };
+                    if better {
+                        best = Some(rank);
                     }
                 }
             }
+
+            if let Some(rank) = best {
+                ranked.push((entry, rank));
+            }
         }
 
-        if let Some(entry) = best_entry {
-            return Some(entry);
+        if !ranked.is_empty() {
+            // Stable sort keeps manifest order between equally ranked entries
+            ranked.sort_by_key(|(_, rank)| Reverse(*rank));
+            return ranked.into_iter().map(|(e, _)| e).collect();
         }
     }
 
-    // 3. Fallback: return the first entry for this file
-    Some(file_entries[0])
+    // 3. Fallback: the first entry for this file
+    vec![file_entries[0]]
 }
 
 #[cfg(test)]
@@ -262,6 +1474,8 @@ mod tests {
             schema_version: "2.0".to_string(),
             commit: "abc1234".to_string(),
             global_intent: None,
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![Entry {
                 anchor: Anchor {
                     file: "src/payment.rs".to_string(),
@@ -296,11 +1510,20 @@ mod tests {
                 feature_flags: None,
                 rationale: "Added new payment method".to_string(),
                 inherits_global_intent: None,
+                package: None,
+                extra: Default::default(),
             }],
         };
 
-        let marker =
-            format_enriched_marker("HEAD", "Your changes", &manifest, "src/payment.rs", None);
+        let marker = format_enriched_marker(
+            "HEAD",
+            "Your changes",
+            &manifest,
+            "src/payment.rs",
+            &[],
+            None,
+            &MarkerOptions::default(),
+        );
 
         assert!(marker.contains("||| Gip CONTEXT (HEAD - Your changes)"));
         assert!(marker.contains("||| Commit: abc1234"));
@@ -315,11 +1538,135 @@ mod tests {
     }
 
     #[test]
-    fn test_find_entry_with_symbol_context() {
+    fn test_format_enriched_marker_summarized() {
+        use crate::summarize::{MarkerConfig, SummaryMode};
+
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "abc1234",
+            "entries": [{
+                "anchor": {"file": "src/pay.rs", "symbol": "charge", "hunkId": "H#1"},
+                "changeType": "modify",
+                "rationale": "Retry on gateway timeouts. The gateway drops requests under load.",
+                "behaviorClass": ["bugfix"],
+                "contract": {"preconditions": ["a", "b", "c"]}
+            }]
+        }))
+        .unwrap();
+        let options = MarkerOptions {
+            summarizer: Summarizer::new(MarkerConfig {
+                summarize: SummaryMode::Rules,
+                max_rationale_chars: 30,
+                max_list_items: 1,
+                llm_command: None,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let marker = format_enriched_marker(
+            "HEAD",
+            "Your changes",
+            &manifest,
+            "src/pay.rs",
+            &[],
+            None,
+            &options,
+        );
+
+        assert!(marker.contains("||| rationale: Retry on gateway timeouts.\n"));
+        assert!(marker.contains("||| preconditions[0]: a\n"));
+        assert!(!marker.contains("preconditions[1]"));
+        assert!(marker.contains("||| preconditions: +2 more"));
+        assert!(marker.contains("||| (summarized, see `gip context abc1234`)"));
+    }
+
+    #[test]
+    fn test_strip_context_restores_git_markers() {
+        let original = "fn a() {}\r\n<<<<<<< HEAD\r\nretry();\r\n||||||| base\r\ncall();\r\n=======\r\ntimeout();\r\n>>>>>>> feat\r\n||| outside conflicts\r\n";
+        let enriched = original
+            .replace(
+                "<<<<<<< HEAD\r\n",
+                "<<<<<<< HEAD\r\n||| Gip CONTEXT (HEAD - Your changes)\r\n||| rationale: Retry\r\n",
+            )
+            .replace(">>>>>>> feat", "||| symbol: a\r\n>>>>>>> feat");
+
+        let (stripped, removed) = strip_context(&enriched);
+        assert_eq!(removed, 3);
+        assert_eq!(stripped, original);
+        assert_eq!(strip_context(original), (original.to_string(), 0));
+    }
+
+    #[test]
+    fn test_looks_binary() {
+        assert!(looks_binary(b"PNG\0\x01<<<<<<< HEAD"));
+        assert!(!looks_binary(b"<<<<<<< HEAD\nfn a() {}\n"));
+        let mut late_nul = vec![b'a'; BINARY_SNIFF_LEN];
+        late_nul.push(0);
+        assert!(!looks_binary(&late_nul));
+    }
+
+    #[test]
+    fn test_prune_conflict_backups() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let gip_dir = temp_dir.path();
+        for file in ["src/pay.rs", "src/api/users.rs", "README.md"] {
+            let path = conflict_backup_path(gip_dir, file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "<<<<<<< HEAD\n").unwrap();
+        }
+
+        let removed = prune_conflict_backups(gip_dir, &["src/pay.rs".to_string()]).unwrap();
+        assert_eq!(removed, 2);
+        assert!(conflict_backup_path(gip_dir, "src/pay.rs").exists());
+        assert!(!gip_dir.join(CONFLICT_BACKUP_DIR).join("src/api").exists());
+
+        let sidecar = sidecar_path(gip_dir, "assets/logo.png");
+        fs::create_dir_all(sidecar.parent().unwrap()).unwrap();
+        fs::write(&sidecar, "context").unwrap();
+        prune_conflict_backups(gip_dir, &["assets/logo.png".to_string()]).unwrap();
+        assert!(sidecar.exists());
+
+        prune_conflict_backups(gip_dir, &[]).unwrap();
+        assert!(!gip_dir.join(CONFLICT_BACKUP_DIR).exists());
+        assert!(!gip_dir.join(SIDECAR_DIR).exists());
+    }
+
+    #[test]
+    fn test_leftover_context() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let gip_dir = root.join(".gip");
+        git::run_git_cmd(&["init", "-q"], Some(root)).unwrap();
+        assert!(leftover_context(&gip_dir, Some(root)).unwrap().is_empty());
+
+        for file in ["pay.rs", "cart.rs", "gone.rs"] {
+            let path = conflict_backup_path(&gip_dir, file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, "<<<<<<< HEAD\n").unwrap();
+        }
+        fs::write(
+            root.join("pay.rs"),
+            "fn charge() {\n||| Gip CONTEXT (HEAD - Your changes)\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("cart.rs"), "fn total() {}\n").unwrap();
+        git::run_git_cmd(&["add", "pay.rs", "cart.rs"], Some(root)).unwrap();
+
+        assert_eq!(
+            leftover_context(&gip_dir, Some(root)).unwrap(),
+            vec!["pay.rs"]
+        );
+    }
+
+    #[test]
+    fn test_rank_entries_with_symbol_context() {
         let manifest = Manifest {
             schema_version: "2.0".to_string(),
             commit: "abc".to_string(),
             global_intent: None,
+            issues: Vec::new(),
+            extra: Default::default(),
             entries: vec![
                 Entry {
                     anchor: Anchor {
@@ -344,6 +1691,8 @@ mod tests {
                     security_notes: None,
                     feature_flags: None,
                     inherits_global_intent: None,
+                    package: None,
+                    extra: Default::default(),
                     signature_delta: None,
                 },
                 Entry {
@@ -369,6 +1718,8 @@ mod tests {
                     security_notes: None,
                     feature_flags: None,
                     inherits_global_intent: None,
+                    package: None,
+                    extra: Default::default(),
                     signature_delta: None,
                 },
             ],
@@ -376,11 +1727,411 @@ mod tests {
 
         let context = vec!["fn helper() {", "    // some code"];
 
-        let entry = find_entry(&manifest, "src/main.rs", Some(&context));
-        assert_eq!(entry.unwrap().anchor.symbol, "helper");
+        let paths = PathsConfig::default();
+        let ranked = rank_entries(&manifest, "src/main.rs", &[], Some(&context), paths);
+        assert_eq!(ranked[0].anchor.symbol, "helper");
 
         let context_main = vec!["fn main() {", "    helper();"];
-        let entry_main = find_entry(&manifest, "src/main.rs", Some(&context_main));
-        assert_eq!(entry_main.unwrap().anchor.symbol, "main");
+        let ranked = rank_entries(&manifest, "src/main.rs", &[], Some(&context_main), paths);
+        assert_eq!(ranked[0].anchor.symbol, "main");
+    }
+
+    #[test]
+    fn test_rank_entries_follows_renamed_file() {
+        let mut manifest = Manifest::new("abc".to_string());
+        manifest.entries.push(Entry {
+            anchor: Anchor {
+                file: "src/billing.rs".to_string(),
+                symbol: "charge".to_string(),
+                hunk_id: "H#1".to_string(),
+            },
+            change_type: CHANGE_MODIFY.to_string(),
+            rationale: "old path".to_string(),
+            signature_delta: None,
+            behavior_class: vec![],
+            contract: Contract {
+                inputs: None,
+                outputs: None,
+                preconditions: vec![],
+                postconditions: vec![],
+                error_model: vec![],
+            },
+            side_effects: vec![],
+            compatibility: None,
+            tests_touched: None,
+            perf_budget: None,
+            security_notes: None,
+            feature_flags: None,
+            inherits_global_intent: None,
+            package: None,
+            extra: Default::default(),
+        });
+
+        let paths = PathsConfig::default();
+        assert!(rank_entries(&manifest, "src/payments.rs", &[], None, paths).is_empty());
+
+        let aliases = vec!["src/billing.rs".to_string()];
+        let ranked = rank_entries(&manifest, "src/payments.rs", &aliases, None, paths);
+        assert_eq!(ranked[0].anchor.symbol, "charge");
+    }
+
+    #[test]
+    fn test_glob_entries_are_a_fallback() {
+        let entry = |file: &str, symbol: &str| {
+            serde_json::json!({
+                "anchor": {"file": file, "symbol": symbol, "hunkId": "H#1"},
+                "changeType": "modify",
+                "rationale": format!("{} {}", file, symbol),
+                "behaviorClass": [],
+                "contract": {}
+            })
+        };
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "abc",
+            "entries": [
+                entry("src/generated/**", "*"),
+                entry("src/generated/api.rs", "Client"),
+                entry("proto", "*")
+            ]
+        }))
+        .unwrap();
+        let paths = PathsConfig::default();
+
+        // An entry naming the file wins over the glob
+        let ranked = rank_entries(&manifest, "src/generated/api.rs", &[], None, paths);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].anchor.symbol, "Client");
+
+        let ranked = rank_entries(&manifest, "src/generated/deep/types.rs", &[], None, paths);
+        assert_eq!(ranked.len(), 1);
+        assert_eq!(ranked[0].anchor.file, "src/generated/**");
+
+        // A plain directory with symbol `*` covers everything below it
+        let ranked = rank_entries(&manifest, "proto/pay.proto", &[], None, paths);
+        assert_eq!(ranked[0].anchor.file, "proto");
+
+        assert!(rank_entries(&manifest, "src/main.rs", &[], None, paths).is_empty());
+    }
+
+    #[test]
+    fn test_rank_entries_normalizes_anchor_paths() {
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "abc",
+            "entries": [{
+                "anchor": {"file": "Src\\Pay\\Mod.rs", "symbol": "charge", "hunkId": "H#1"},
+                "changeType": "modify",
+                "rationale": "written on Windows",
+                "behaviorClass": [],
+                "contract": {}
+            }]
+        }))
+        .unwrap();
+
+        // Separators are normalized; case only with `ignore_case`
+        let exact = PathsConfig::default();
+        assert!(rank_entries(&manifest, "Src/Pay/Mod.rs", &[], None, exact).len() == 1);
+        assert!(rank_entries(&manifest, "src/pay/mod.rs", &[], None, exact).is_empty());
+
+        let folded = PathsConfig { ignore_case: true };
+        assert_eq!(
+            rank_entries(&manifest, "src/pay/mod.rs", &[], None, folded).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_rank_entries_qualified_symbol_prefers_definition() {
+        let mut manifest = Manifest::new("abc".to_string());
+        for symbol in ["Gateway::charge", "Gateway::refund"] {
+            manifest.entries.push(Entry {
+                anchor: Anchor {
+                    file: "src/gateway.rs".to_string(),
+                    symbol: symbol.to_string(),
+                    hunk_id: "H#1".to_string(),
+                },
+                change_type: CHANGE_MODIFY.to_string(),
+                rationale: symbol.to_string(),
+                signature_delta: None,
+                behavior_class: vec![],
+                contract: Contract {
+                    inputs: None,
+                    outputs: None,
+                    preconditions: vec![],
+                    postconditions: vec![],
+                    error_model: vec![],
+                },
+                side_effects: vec![],
+                compatibility: None,
+                tests_touched: None,
+                perf_budget: None,
+                security_notes: None,
+                feature_flags: None,
+                inherits_global_intent: None,
+                package: None,
+                extra: Default::default(),
+            });
+        }
+
+        // `charge` is only called here; `refund` is the enclosing definition
+        let context = vec!["impl Gateway {", "    fn refund(&self) {", "self.charge();"];
+        let ranked = rank_entries(
+            &manifest,
+            "src/gateway.rs",
+            &[],
+            Some(&context),
+            PathsConfig::default(),
+        );
+        assert_eq!(ranked[0].anchor.symbol, "Gateway::refund");
+    }
+
+    #[test]
+    fn test_marker_renders_all_matching_entries() {
+        let mut manifest = Manifest::new("abc".to_string());
+        for (symbol, rationale) in [("Order", "order struct"), ("submit", "submit fn")] {
+            manifest.entries.push(Entry {
+                anchor: Anchor {
+                    file: "src/order.rs".to_string(),
+                    symbol: symbol.to_string(),
+                    hunk_id: "H#1".to_string(),
+                },
+                change_type: CHANGE_MODIFY.to_string(),
+                rationale: rationale.to_string(),
+                signature_delta: None,
+                behavior_class: vec![],
+                contract: Contract {
+                    inputs: None,
+                    outputs: None,
+                    preconditions: vec![],
+                    postconditions: vec![],
+                    error_model: vec![],
+                },
+                side_effects: vec![],
+                compatibility: None,
+                tests_touched: None,
+                perf_budget: None,
+                security_notes: None,
+                feature_flags: None,
+                inherits_global_intent: None,
+                package: None,
+                extra: Default::default(),
+            });
+        }
+
+        let context = vec![
+            "fn submit(order: &Order) {",
+            "    let total = order.total();",
+        ];
+        let marker = format_enriched_marker(
+            "HEAD",
+            "Your changes",
+            &manifest,
+            "src/order.rs",
+            &[],
+            Some(&context),
+            &MarkerOptions::default(),
+        );
+
+        let submit_pos = marker.find("||| rationale: submit fn").unwrap();
+        let order_pos = marker.find("||| rationale: order struct").unwrap();
+        assert!(submit_pos < order_pos, "definition match should come first");
+        assert!(marker.contains("||| ---"));
+    }
+
+    #[test]
+    fn test_huge_conflicts_enrich_only_entry_symbols() {
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "abc",
+            "entries": [{
+                "anchor": {"file": "src/gen.rs", "symbol": "charge", "hunkId": "H#1"},
+                "changeType": "modify",
+                "rationale": "Retry on timeout",
+                "behaviorClass": ["bugfix"],
+                "contract": {}
+            }]
+        }))
+        .unwrap();
+        let conflict = "<<<<<<< HEAD\n    a();\n=======\n    b();\n>>>>>>> feat\n";
+        let content = format!(
+            "fn charge() {{\n{c}}}\n\nfn generated() {{\n{c}{c}}}\n",
+            c = conflict
+        );
+        let options = MarkerOptions {
+            focus_threshold: 2,
+            ..Default::default()
+        };
+
+        let enriched = enrich_content(
+            &content,
+            "src/gen.rs",
+            &[],
+            Some(&manifest),
+            Some(&manifest),
+            [None, None],
+            &options,
+        );
+        assert_eq!(enriched.matches("||| Gip CONTEXT (HEAD").count(), 1);
+        assert_eq!(enriched.matches("||| Gip CONTEXT (feat").count(), 1);
+        assert_eq!(
+            enriched.matches("||| Gip CONTEXT (file summary)").count(),
+            1
+        );
+        assert!(enriched.contains("||| 2 of 3 conflicts"));
+        assert!(enriched.contains("|||   charge: Retry on timeout"));
+        assert_eq!(strip_context(&enriched).0, content);
+
+        // At or below the threshold every conflict is enriched
+        let options = MarkerOptions {
+            focus_threshold: 3,
+            ..Default::default()
+        };
+        let enriched = enrich_content(
+            &content,
+            "src/gen.rs",
+            &[],
+            Some(&manifest),
+            None,
+            [None, None],
+            &options,
+        );
+        assert_eq!(enriched.matches("||| Gip CONTEXT (HEAD").count(), 3);
+    }
+
+    #[test]
+    fn test_index_conflict() {
+        let entry = |state: &str, ours: &str, theirs: &str| git::UnmergedEntry {
+            path: "pay.rs".to_string(),
+            state: state.to_string(),
+            modes: ["100644", ours, theirs].map(String::from),
+        };
+        let labels = ["Your changes", "Their changes"];
+        assert_eq!(
+            index_conflict(&entry("UD", "100644", "000000"), labels).as_deref(),
+            Some("Your changes modified it, their changes deleted it")
+        );
+        assert_eq!(
+            index_conflict(&entry("DU", "000000", "100644"), labels).as_deref(),
+            Some("Your changes deleted it, their changes modified it")
+        );
+        assert_eq!(
+            index_conflict(&entry("UU", "100755", "100644"), labels).as_deref(),
+            Some("Your changes set mode 100755, their changes set mode 100644")
+        );
+        assert_eq!(
+            index_conflict(&entry("UU", "100644", "100644"), labels),
+            None
+        );
+    }
+
+    #[test]
+    fn test_rebase_labels() {
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "abc",
+            "entries": [{
+                "anchor": {"file": "src/pay.rs", "symbol": "charge", "hunkId": "H#1"},
+                "changeType": "modify",
+                "rationale": "Retry on timeout",
+                "behaviorClass": ["bugfix"],
+                "contract": {}
+            }]
+        }))
+        .unwrap();
+        let content =
+            "fn charge() {\n<<<<<<< HEAD\n    a();\n=======\n    b();\n>>>>>>> 1a2b3c4 (Retry)\n}\n";
+        let options = MarkerOptions {
+            rebase: true,
+            ..Default::default()
+        };
+
+        let enriched = enrich_content(
+            content,
+            "src/pay.rs",
+            &[],
+            Some(&manifest),
+            Some(&manifest),
+            [None, None],
+            &options,
+        );
+        assert!(enriched.contains("||| Gip CONTEXT (HEAD - Upstream (onto))"));
+        assert!(enriched.contains("||| Gip CONTEXT (1a2b3c4 (Retry) - Your commit being replayed)"));
+        assert!(!enriched.contains("Your changes"));
+    }
+
+    #[test]
+    fn test_one_sided_context() {
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "abc",
+            "entries": [{
+                "anchor": {"file": "src/pay.rs", "symbol": "charge", "hunkId": "H#1"},
+                "changeType": "modify",
+                "rationale": "Retry on timeout",
+                "behaviorClass": ["bugfix"],
+                "contract": {}
+            }]
+        }))
+        .unwrap();
+        let content = "fn charge() {\n<<<<<<< HEAD\n    a();\n=======\n    b();\n>>>>>>> feat\n}\n";
+        let options = MarkerOptions::default();
+        let summary = CommitSummary {
+            commit: "def456".to_string(),
+            message: "Retry charges\n\nDeclined cards are retried\nthree times.\n".to_string(),
+            files: parse_numstat("5\t2\tsrc/pay.rs\n-\t-\tlogo.png\n1\t0\tREADME.md\n"),
+        };
+        assert_eq!(summary.files[1], ("logo.png".to_string(), 0, 0));
+
+        let enriched = enrich_content(
+            content,
+            "src/pay.rs",
+            &[],
+            None,
+            Some(&manifest),
+            [Some(&summary), None],
+            &options,
+        );
+        assert!(enriched.contains("||| Gip CONTEXT (HEAD - Your changes, no manifest)"));
+        assert!(enriched.contains("||| Commit: def456"));
+        assert!(enriched.contains("||| message: Retry charges"));
+        assert!(enriched.contains("||| details: Declined cards are retried three times."));
+        assert!(enriched.contains("||| changed: 3 file(s), +6 -2; this file +5 -2"));
+        assert!(enriched.contains("`git log -1 def456`"));
+        assert!(enriched.contains("||| Gip CONTEXT (feat - Their changes)"));
+        assert!(enriched.contains("||| rationale: Retry on timeout"));
+        assert_eq!(strip_context(&enriched).0, content);
+
+        // Neither side has a manifest: both fall back to their commits
+        let theirs = CommitSummary {
+            commit: "abc".to_string(),
+            message: "Time out slow calls".to_string(),
+            files: parse_numstat("3\t1\tlib/pay.rs\n"),
+        };
+        let bare = enrich_content(
+            content,
+            "src/pay.rs",
+            &["lib/pay.rs".to_string()],
+            None,
+            None,
+            [Some(&summary), Some(&theirs)],
+            &options,
+        );
+        assert!(bare.contains("||| Gip CONTEXT (feat - Their changes, no manifest)"));
+        assert!(bare.contains("||| changed: 1 file(s), +3 -1; this file +3 -1"));
+        assert!(!bare.contains("||| details:\n"));
+        assert_eq!(strip_context(&bare).0, content);
+
+        // Nothing known about either side leaves the conflict as it is
+        let none = enrich_content(
+            content,
+            "src/pay.rs",
+            &[],
+            None,
+            None,
+            [None, None],
+            &options,
+        );
+        assert_eq!(none, content);
     }
 }
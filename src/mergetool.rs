@@ -0,0 +1,221 @@
+//! Merge tool integration
+//!
+//! `gip mergetool --install` registers gip with git as the `gip` merge tool.
+//! When `git mergetool` runs it on a file, gip writes the manifest context of
+//! every conflict to a side file and then launches the tool you actually use
+//! (meld, kdiff3, VS Code, or any tool configured for `git mergetool`), so you
+//! keep your tool and gain the context next to it.
+//!
+//! The tool is picked from, in order: `--tool`, `[mergetool] tool` in
+//! `.gip/config.toml`, and git's `merge.tool`.
+//!
+//! ```toml
+//! [mergetool]
+//! tool = "meld"
+//! ```
+
+use crate::atomic;
+use crate::git;
+use crate::merge;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name under which gip registers itself with `git mergetool`
+pub const TOOL_NAME: &str = "gip";
+
+/// Environment variable passing `--tool` through `git mergetool` to the driver
+pub const TOOL_ENV: &str = "GIP_MERGETOOL";
+
+/// Environment variable holding the context file, for custom tool commands
+pub const CONTEXT_ENV: &str = "GIP_CONTEXT";
+
+/// Command git runs for the `gip` merge tool
+pub const DRIVER_CMD: &str = "gip mergetool --driver \"$BASE\" \"$LOCAL\" \"$REMOTE\" \"$MERGED\"";
+
+/// `[mergetool]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MergetoolConfig {
+    /// Tool launched by the `gip` merge tool
+    pub tool: Option<String>,
+}
+
+/// A merge tool gip knows how to launch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownTool {
+    pub name: &'static str,
+    pub program: &'static str,
+    /// Arguments, with `$BASE`, `$LOCAL`, `$REMOTE`, `$MERGED` and `$CONTEXT`
+    pub args: &'static [&'static str],
+    /// Arguments opening the context file beforehand, for editors with panes
+    pub context_args: Option<&'static [&'static str]>,
+}
+
+pub const KNOWN_TOOLS: &[KnownTool] = &[
+    KnownTool {
+        name: "meld",
+        program: "meld",
+        args: &["$LOCAL", "$BASE", "$REMOTE", "--output", "$MERGED"],
+        context_args: None,
+    },
+    KnownTool {
+        name: "kdiff3",
+        program: "kdiff3",
+        args: &["$BASE", "$LOCAL", "$REMOTE", "-o", "$MERGED"],
+        context_args: None,
+    },
+    KnownTool {
+        name: "vscode",
+        program: if cfg!(windows) { "code.cmd" } else { "code" },
+        args: &["--wait", "--merge", "$REMOTE", "$LOCAL", "$BASE", "$MERGED"],
+        context_args: Some(&["--reuse-window", "$CONTEXT"]),
+    },
+];
+
+/// The files git hands a merge tool, plus gip's context file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ToolPaths {
+    pub base: String,
+    pub local: String,
+    pub remote: String,
+    pub merged: String,
+    pub context: String,
+}
+
+impl ToolPaths {
+    /// Substitute the placeholders in tool arguments
+    pub fn expand(&self, args: &[&str]) -> Vec<String> {
+        args.iter()
+            .map(|arg| {
+                arg.replace("$BASE", &self.base)
+                    .replace("$LOCAL", &self.local)
+                    .replace("$REMOTE", &self.remote)
+                    .replace("$MERGED", &self.merged)
+                    .replace("$CONTEXT", &self.context)
+            })
+            .collect()
+    }
+}
+
+pub fn known_tool(name: &str) -> Option<&'static KnownTool> {
+    KNOWN_TOOLS.iter().find(|t| t.name == name)
+}
+
+/// Register the `gip` merge tool in the repository's git config
+pub fn install() -> Result<()> {
+    let cmd_key = format!("mergetool.{}.cmd", TOOL_NAME);
+    let trust_key = format!("mergetool.{}.trustExitCode", TOOL_NAME);
+    git::run_git_cmd(&["config", &cmd_key, DRIVER_CMD], None)?;
+    // Several tools exit 0 whatever happened; let git check the file instead
+    git::run_git_cmd(&["config", &trust_key, "false"], None)?;
+    Ok(())
+}
+
+/// Remove the `gip` merge tool from the repository's git config
+///
+/// Returns `false` if it wasn't registered.
+pub fn uninstall() -> Result<bool> {
+    let section = format!("mergetool.{}", TOOL_NAME);
+    let cmd_key = format!("{}.cmd", section);
+    // A registration in the global config isn't this repository's to remove
+    if git::run_git_cmd(&["config", "--local", "--get", &cmd_key], None).is_err() {
+        return Ok(false);
+    }
+    git::run_git_cmd(&["config", "--local", "--remove-section", &section], None)?;
+    // git would otherwise look for a tool that no longer exists
+    let configured = git::run_git_cmd(&["config", "--local", "merge.tool"], None).ok();
+    if configured.as_deref() == Some(TOOL_NAME) {
+        git::run_git_cmd(&["config", "--local", "--unset", "merge.tool"], None)?;
+    }
+    Ok(true)
+}
+
+/// Whether the `gip` merge tool is registered
+pub fn is_installed() -> bool {
+    git::run_git_cmd(
+        &["config", "--get", &format!("mergetool.{}.cmd", TOOL_NAME)],
+        None,
+    )
+    .is_ok()
+}
+
+/// The tool to launch, given `--tool` (or [`TOOL_ENV`]) and the config
+pub fn pick_tool(requested: Option<&str>, config: &MergetoolConfig) -> Option<String> {
+    requested
+        .map(str::to_string)
+        .or_else(|| config.tool.clone())
+        .or_else(|| git::run_git_cmd(&["config", "merge.tool"], None).ok())
+        .filter(|t| !t.is_empty() && t != TOOL_NAME)
+}
+
+/// The context of each conflict of a file, as text for a side pane
+pub fn context_text(file_path: &str, contexts: &[Vec<String>]) -> String {
+    let mut out = format!("Gip context for {}\n", file_path);
+    if contexts.iter().all(|c| c.is_empty()) {
+        out.push_str("\nNo manifest covers these conflicts.\n");
+        return out;
+    }
+    for (i, context) in contexts.iter().enumerate() {
+        out.push_str(&format!("\nConflict {}\n", i + 1));
+        for line in context {
+            out.push_str(&format!("  {}\n", line));
+        }
+    }
+    out
+}
+
+/// Write the context of a conflicted file to its side file
+pub fn write_context(gip_dir: &Path, merged: &str) -> Result<PathBuf> {
+    let content =
+        fs::read_to_string(merged).with_context(|| format!("Failed to read {}", merged))?;
+    let plain = merge::strip_context(&content).0;
+
+    let ours_sha = git::get_current_commit()?;
+    let theirs_sha = merge::conflict_other_head();
+    let contexts = merge::conflict_context(&plain, merged, &ours_sha, theirs_sha.as_deref())?;
+
+    let path = merge::sidecar_path(gip_dir, merged);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context("Failed to create sidecar directory")?;
+    }
+    atomic::write(&path, context_text(merged, &contexts))
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_tool_arguments() {
+        let paths = ToolPaths {
+            base: "pay.rs.BASE".to_string(),
+            local: "pay.rs.LOCAL".to_string(),
+            remote: "pay.rs.REMOTE".to_string(),
+            merged: "pay.rs".to_string(),
+            context: ".gip/sidecar/pay.rs.gip-context".to_string(),
+        };
+        let vscode = known_tool("vscode").unwrap();
+        assert_eq!(
+            paths.expand(vscode.args)[2..],
+            ["pay.rs.REMOTE", "pay.rs.LOCAL", "pay.rs.BASE", "pay.rs"]
+        );
+        assert_eq!(
+            paths.expand(vscode.context_args.unwrap())[1],
+            ".gip/sidecar/pay.rs.gip-context"
+        );
+        assert!(known_tool("gip").is_none());
+    }
+
+    #[test]
+    fn test_context_text() {
+        let contexts = vec![vec!["rationale: retry".to_string()], Vec::new()];
+        let text = context_text("pay.rs", &contexts);
+        assert!(text.contains("Conflict 1\n  rationale: retry\n"));
+        assert!(text.contains("Conflict 2\n"));
+        assert!(context_text("pay.rs", &[Vec::new()]).contains("No manifest"));
+    }
+}
@@ -0,0 +1,100 @@
+//! Output formats for scripts and agents
+//!
+//! `--format human` (the default) prints text for people. With `--format json`
+//! or `--format toon`, a command writes exactly one result document to stdout:
+//!
+//! ```json
+//! {"command": "push", "result": {"remote": "origin", "notesPushed": true, ...}}
+//! ```
+//!
+//! Everything meant for people, git's own output included, goes to stderr
+//! instead, so stdout can be parsed as is. Field names are camelCase, like
+//! the manifest's, and only ever added to.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Value of the global `--format` option
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    #[default]
+    Human,
+    Json,
+    Toon,
+}
+
+static FORMAT: AtomicU8 = AtomicU8::new(OutputFormat::Human as u8);
+
+/// Select the output format; call once at startup
+pub fn set_format(format: OutputFormat) {
+    FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+pub fn format() -> OutputFormat {
+    match FORMAT.load(Ordering::Relaxed) {
+        x if x == OutputFormat::Json as u8 => OutputFormat::Json,
+        x if x == OutputFormat::Toon as u8 => OutputFormat::Toon,
+        _ => OutputFormat::Human,
+    }
+}
+
+/// Whether stdout is for people rather than a result document
+pub fn is_human() -> bool {
+    format() == OutputFormat::Human
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    command: &'a str,
+    result: &'a T,
+}
+
+/// Render the result document of `command` in a structured format
+pub fn render<T: Serialize>(format: OutputFormat, command: &str, result: &T) -> Result<String> {
+    let envelope = Envelope { command, result };
+    Ok(match format {
+        OutputFormat::Human => String::new(),
+        OutputFormat::Json => serde_json::to_string_pretty(&envelope)?,
+        OutputFormat::Toon => toon_format::encode_default(&envelope)?,
+    })
+}
+
+/// Write the result document of `command` to stdout; nothing in human format
+pub fn emit<T: Serialize>(command: &str, result: &T) -> Result<()> {
+    if !is_human() {
+        println!("{}", render(format(), command, result)?);
+    }
+    Ok(())
+}
+
+/// Print text for people: stdout in human format, stderr otherwise
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::is_human() {
+            println!($($arg)*);
+        } else {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_json_envelope() {
+        let rendered = render(OutputFormat::Json, "init", &json!({"gipDir": ".gip"})).unwrap();
+        let value: Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(
+            value,
+            json!({"command": "init", "result": {"gipDir": ".gip"}})
+        );
+        assert!(render(OutputFormat::Human, "init", &json!({}))
+            .unwrap()
+            .is_empty());
+    }
+}
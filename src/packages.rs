@@ -0,0 +1,106 @@
+//! Monorepo package mapping
+//!
+//! Reads `.gip/packages.toml`, which maps package names to the paths they own:
+//!
+//! ```toml
+//! [packages]
+//! core = ["crates/core"]
+//! api = ["services/api/**", "proto/api"]
+//! ```
+//!
+//! Entries can also name their package explicitly via `package` (or `component`).
+
+use crate::manifest::Entry;
+use crate::pathspec;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// File name of the package mapping inside `.gip/`
+pub const PACKAGES_FILE: &str = "packages.toml";
+
+/// Mapping of package names to the path patterns they own
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct PackageMap {
+    #[serde(default)]
+    pub packages: BTreeMap<String, Vec<String>>,
+}
+
+impl PackageMap {
+    /// Load the mapping from `<gip_dir>/packages.toml`, or an empty map if absent
+    pub fn load(gip_dir: &Path) -> Result<Self> {
+        let path = gip_dir.join(PACKAGES_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let data = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read package map from {:?}", path))?;
+        Self::parse(&data)
+    }
+
+    /// Parse a mapping from TOML text
+    pub fn parse(data: &str) -> Result<Self> {
+        toml::from_str(data).context("Failed to parse packages.toml")
+    }
+
+    /// Resolve the package owning a path
+    ///
+    /// When several patterns match, the longest (most specific) one wins.
+    pub fn package_for(&self, path: &str) -> Option<&str> {
+        let mut best: Option<(&str, usize)> = None;
+
+        for (name, patterns) in &self.packages {
+            for pattern in patterns {
+                if pathspec::matches(pattern, path)
+                    && !matches!(best, Some((_, len)) if len >= pattern.len())
+                {
+                    best = Some((name.as_str(), pattern.len()));
+                }
+            }
+        }
+
+        best.map(|(name, _)| name)
+    }
+
+    /// Resolve the package of an entry, preferring its explicit `package` field
+    pub fn package_of<'a>(&'a self, entry: &'a Entry) -> Option<&'a str> {
+        entry
+            .package
+            .as_deref()
+            .or_else(|| self.package_for(&entry.anchor.file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"
+[packages]
+core = ["crates/core"]
+api = ["services/api/**"]
+api-proto = ["services/api/proto"]
+"#;
+
+    #[test]
+    fn test_package_for_path() {
+        let map = PackageMap::parse(MAP).unwrap();
+        assert_eq!(map.package_for("crates/core/src/lib.rs"), Some("core"));
+        assert_eq!(map.package_for("services/api/src/main.rs"), Some("api"));
+        assert_eq!(
+            map.package_for("services/api/proto/v1.proto"),
+            Some("api-proto")
+        );
+        assert_eq!(map.package_for("README.md"), None);
+    }
+
+    #[test]
+    fn test_empty_map_when_missing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let map = PackageMap::load(temp_dir.path()).unwrap();
+        assert!(map.packages.is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+//! Manifests carried inside email patches
+//!
+//! `gip format-patch` adds a `Gip-Context:` line with the base64-encoded
+//! manifest below the `---` separator of each patch, where `git am` ignores it
+//! like the diffstat. `gip am` reads it back and attaches it to the applied
+//! commit as a note.
+
+use crate::atomic;
+use crate::manifest::Manifest;
+use crate::trailer::{self, TrailerMode, TRAILER_KEY};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toon_format::{decode, encode_default, DecodeOptions};
+
+/// File inside `.gip/` remembering the manifests of an `am` session
+pub const AM_STATE_FILE: &str = "am-state.toon";
+
+/// A manifest read from a patch, keyed by the subject `git am` commits it with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatchManifest {
+    pub subject: String,
+    pub manifest: Manifest,
+}
+
+/// Manifests still to attach while `git am` runs or waits on a conflict
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmState {
+    /// HEAD before the first patch was applied
+    pub start: String,
+    pub patches: Vec<PatchManifest>,
+}
+
+/// Commit a patch was generated from, from its `From <sha> <date>` line
+fn from_line_sha(line: &str) -> Option<&str> {
+    let sha = line.strip_prefix("From ")?.split_whitespace().next()?;
+    (sha.len() == 40 && sha.chars().all(|c| c.is_ascii_hexdigit())).then_some(sha)
+}
+
+/// Add a `Gip-Context` line to every message in a patch or mbox
+///
+/// `manifest_for` is asked for the manifest of each source commit; messages
+/// without one are left unchanged.
+pub fn embed<F>(mbox: &str, mut manifest_for: F) -> Result<String>
+where
+    F: FnMut(&str) -> Option<Manifest>,
+{
+    let mut out = String::with_capacity(mbox.len());
+    let mut pending: Option<String> = None;
+
+    for line in mbox.split_inclusive('\n') {
+        let bare = line.trim_end_matches(['\r', '\n']);
+        if let Some(sha) = from_line_sha(bare) {
+            pending = match manifest_for(sha) {
+                Some(m) => trailer::encode(&m, TrailerMode::Payload)?,
+                None => None,
+            };
+        }
+
+        out.push_str(line);
+        if bare == "---" {
+            if let Some(value) = pending.take() {
+                out.push_str(&format!("{}: {}\n", TRAILER_KEY, value));
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Manifests embedded in a patch or mbox, with the subjects they belong to
+pub fn extract(mbox: &str) -> Result<Vec<PatchManifest>> {
+    let prefix = format!("{}:", TRAILER_KEY);
+    let mut found = Vec::new();
+    let mut subject: Option<String> = None;
+    let mut in_headers = false;
+    let mut in_notes = false;
+
+    for line in mbox.lines() {
+        if from_line_sha(line).is_some() {
+            subject = None;
+            in_headers = true;
+            in_notes = false;
+        } else if in_headers {
+            if line.is_empty() {
+                in_headers = false;
+            } else if let Some(s) = line.strip_prefix("Subject:") {
+                subject = Some(s.trim().to_string());
+            } else if line.starts_with([' ', '\t']) {
+                // Folded header continuation
+                if let Some(s) = subject.as_mut() {
+                    s.push(' ');
+                    s.push_str(line.trim());
+                }
+            }
+        } else if line == "---" {
+            in_notes = true;
+        } else if line.starts_with("diff --git ") {
+            in_notes = false;
+        } else if in_notes {
+            if let Some(value) = line.strip_prefix(&prefix) {
+                let manifest = trailer::decode_value(value.trim(), None)
+                    .context("Failed to decode manifest embedded in patch")?;
+                found.push(PatchManifest {
+                    subject: strip_patch_prefix(subject.as_deref().unwrap_or_default()),
+                    manifest,
+                });
+            }
+        }
+    }
+
+    Ok(found)
+}
+
+/// Subject without the `[PATCH n/m]` prefix `git am` removes
+pub fn strip_patch_prefix(subject: &str) -> String {
+    let mut s = subject.trim();
+    while let Some(rest) = s.strip_prefix('[') {
+        match rest.find(']') {
+            Some(end) => s = rest[end + 1..].trim_start(),
+            None => break,
+        }
+    }
+    s.to_string()
+}
+
+impl AmState {
+    pub fn path(gip_dir: &Path) -> PathBuf {
+        gip_dir.join(AM_STATE_FILE)
+    }
+
+    pub fn load(gip_dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path(gip_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read am state from {:?}", path))?;
+        let opts = DecodeOptions::new().with_strict(false);
+        Ok(Some(
+            decode(&data, &opts).context("Failed to parse am state")?,
+        ))
+    }
+
+    pub fn save(&self, gip_dir: &Path) -> Result<()> {
+        let path = Self::path(gip_dir);
+        atomic::write(&path, encode_default(self)?)
+            .with_context(|| format!("Failed to write am state to {:?}", path))
+    }
+
+    pub fn clear(gip_dir: &Path) -> Result<()> {
+        let path = Self::path(gip_dir);
+        if path.exists() {
+            fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+        }
+        Ok(())
+    }
+
+    /// Take the manifest of the first not yet attached patch with this subject
+    pub fn take(&mut self, subject: &str) -> Option<Manifest> {
+        let idx = self.patches.iter().position(|p| p.subject == subject)?;
+        Some(self.patches.remove(idx).manifest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    const SHA: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn patch(subject: &str) -> String {
+        format!(
+            "From {} Mon Sep 17 00:00:00 2001\nFrom: A <a@example.com>\nSubject: {}\n\nBody\n---\n f.rs | 2 +-\n\ndiff --git a/f.rs b/f.rs\n",
+            SHA, subject
+        )
+    }
+
+    fn manifest() -> Manifest {
+        test_support::manifest(SHA, json!({ "anchor": { "file": "f.rs", "symbol": "f" } }))
+    }
+
+    #[test]
+    fn test_embed_and_extract() {
+        let mbox = format!(
+            "{}{}",
+            patch("[PATCH 1/2] Retry"),
+            patch("[PATCH 2/2] Other")
+        );
+        let embedded = embed(&mbox, |sha| {
+            assert_eq!(sha, SHA);
+            Some(manifest())
+        })
+        .unwrap();
+        assert_eq!(embedded.matches("---\nGip-Context: ").count(), 2);
+
+        let found = extract(&embedded).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].subject, "Retry");
+        assert_eq!(found[1].subject, "Other");
+        assert_eq!(found[0].manifest.entries[0].rationale, "Retry on timeout");
+    }
+
+    #[test]
+    fn test_embed_skips_commits_without_manifest() {
+        let mbox = patch("[PATCH] Docs");
+        assert_eq!(embed(&mbox, |_| None).unwrap(), mbox);
+        assert!(extract(&mbox).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_strip_patch_prefix() {
+        assert_eq!(strip_patch_prefix("[PATCH v2 3/7] Fix it"), "Fix it");
+        assert_eq!(strip_patch_prefix("[RFC] [PATCH] Fix it"), "Fix it");
+        assert_eq!(strip_patch_prefix("Fix it"), "Fix it");
+    }
+
+    #[test]
+    fn test_take_by_subject() {
+        let mut state = AmState {
+            start: SHA.to_string(),
+            patches: vec![PatchManifest {
+                subject: "Retry".to_string(),
+                manifest: manifest(),
+            }],
+        };
+        assert!(state.take("Other").is_none());
+        assert!(state.take("Retry").is_some());
+        assert!(state.patches.is_empty());
+    }
+}
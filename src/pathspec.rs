@@ -0,0 +1,189 @@
+//! Path pattern matching shared by package mapping and anchor lookups
+//!
+//! Patterns are repository-relative and use `/` separators. A pattern without
+//! wildcards matches the path itself and everything below it; `*` matches within
+//! a single path segment and `**` matches across segments.
+//!
+//! Manifests written on Windows may use `\` separators; [`normalize`] turns
+//! those into the `/` form git prints, so anchors match `git diff` output.
+
+use regex::Regex;
+use serde::Deserialize;
+
+/// `[paths]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct PathsConfig {
+    /// Compare anchor paths case-insensitively, for case-insensitive filesystems
+    pub ignore_case: bool,
+}
+
+impl PathsConfig {
+    /// Whether two repository-relative paths name the same file
+    pub fn same_file(&self, a: &str, b: &str) -> bool {
+        let (a, b) = (normalize(a), normalize(b));
+        if self.ignore_case {
+            a.to_lowercase() == b.to_lowercase()
+        } else {
+            a == b
+        }
+    }
+
+    /// [`matches`], folding case when configured to
+    pub fn matches(&self, pattern: &str, path: &str) -> bool {
+        if self.ignore_case {
+            matches(&pattern.to_lowercase(), &path.to_lowercase())
+        } else {
+            matches(pattern, path)
+        }
+    }
+}
+
+/// Whether a pattern contains wildcards
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains('*') || pattern.contains('?')
+}
+
+/// Repository-relative path with `/` separators and no leading `./`
+pub fn normalize(path: &str) -> String {
+    let mut path = path.trim().replace('\\', "/");
+    while path.contains("//") {
+        path = path.replace("//", "/");
+    }
+    let mut rest = path.as_str();
+    while let Some(stripped) = rest.strip_prefix("./") {
+        rest = stripped;
+    }
+    rest.to_string()
+}
+
+/// Check whether a repository-relative path matches a pattern
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let pattern = normalize(pattern);
+    let pattern = pattern.trim_end_matches('/');
+    let path = normalize(path);
+    let path = path.as_str();
+
+    if pattern.is_empty() {
+        return false;
+    }
+
+    if !is_glob(pattern) {
+        return path == pattern
+            || path
+                .strip_prefix(pattern)
+                .is_some_and(|rest| rest.starts_with('/'));
+    }
+
+    match Regex::new(&glob_to_regex(pattern)) {
+        Ok(re) => re.is_match(path),
+        Err(_) => false,
+    }
+}
+
+/// Whether some path could match both patterns
+///
+/// Two globs are compared by their directories before the first wildcard, so
+/// the answer errs towards overlapping: `src/**` overlaps `src/auth/*.rs`.
+pub fn overlaps(a: &str, b: &str) -> bool {
+    match (is_glob(a), is_glob(b)) {
+        (false, _) => matches(b, a),
+        (true, false) => matches(a, b) || literal_dir(a).starts_with(&literal_dir(b)),
+        (true, true) => {
+            let (a, b) = (literal_dir(a), literal_dir(b));
+            a.starts_with(&b) || b.starts_with(&a)
+        }
+    }
+}
+
+/// The directories of a pattern before its first wildcard, ending in `/`
+fn literal_dir(pattern: &str) -> String {
+    let pattern = normalize(pattern);
+    let literal = match pattern.find(['*', '?']) {
+        Some(pos) => &pattern[..pos],
+        None => return format!("{}/", pattern.trim_end_matches('/')),
+    };
+    match literal.rfind('/') {
+        Some(pos) => literal[..=pos].to_string(),
+        None => String::new(),
+    }
+}
+
+/// Translate a glob pattern into an anchored regular expression
+fn glob_to_regex(pattern: &str) -> String {
+    let mut regex = String::from("^");
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                // `**/` may also match zero directories
+                if chars.get(i + 2) == Some(&'/') {
+                    regex.push_str("(?:.*/)?");
+                    i += 3;
+                } else {
+                    regex.push_str(".*");
+                    i += 2;
+                }
+                continue;
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+        i += 1;
+    }
+
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_prefix_matches() {
+        assert!(matches("services/api", "services/api/src/lib.rs"));
+        assert!(matches("services/api/", "services/api"));
+        assert!(!matches("services/api", "services/apigw/main.go"));
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(matches("crates/*/src/**", "crates/core/src/a/b.rs"));
+        assert!(matches("src/**/*.rs", "src/main.rs"));
+        assert!(!matches("src/*.rs", "src/nested/main.rs"));
+        assert!(matches("docs/?.md", "docs/a.md"));
+    }
+
+    #[test]
+    fn test_normalize_windows_separators() {
+        assert_eq!(normalize("src\\main.rs"), "src/main.rs");
+        assert_eq!(normalize(".\\src\\\\net\\mod.rs"), "src/net/mod.rs");
+        assert_eq!(normalize("./src/main.rs"), "src/main.rs");
+        assert!(matches("src/**", "src\\net\\mod.rs"));
+    }
+
+    #[test]
+    fn test_same_file_case() {
+        let exact = PathsConfig::default();
+        assert!(exact.same_file("src\\Main.rs", "src/Main.rs"));
+        assert!(!exact.same_file("src/Main.rs", "src/main.rs"));
+
+        let folded = PathsConfig { ignore_case: true };
+        assert!(folded.same_file("SRC\\Main.rs", "src/main.rs"));
+    }
+
+    #[test]
+    fn test_overlaps() {
+        assert!(overlaps("src/**", "src/auth/**"));
+        assert!(overlaps("src/auth/*.rs", "src/**"));
+        assert!(!overlaps("src/generated/**", "src/auth/**"));
+        // A glob anchor under a plain directory pattern
+        assert!(overlaps("src/auth/**", "src/auth"));
+        assert!(overlaps("src/auth/login.rs", "src/**"));
+        assert!(!overlaps("docs/a.md", "src/**"));
+    }
+}
@@ -0,0 +1,257 @@
+//! Protected-branch policies
+//!
+//! Policies are validation rules that only apply on some branches. `gip commit`
+//! checks the manifest being committed against the policies of the current
+//! branch, and `gip merge` checks the manifest of every incoming commit against
+//! the policies of the branch being merged into:
+//!
+//! ```toml
+//! [[policy.rules]]
+//! name = "no-breaking-release"
+//! branches = ["release/*"]
+//! check = 'require(not entry.breaking)'
+//! message = "Breaking changes can't be merged into release branches"
+//!
+//! [[policy.rules]]
+//! name = "migrations-link-issue"
+//! branches = ["main", "release/*"]
+//! check = 'require(entry.issue) when entry.behavior_class contains "migration"'
+//! ```
+//!
+//! Checks use the `[[validation.rules]]` syntax and branch patterns the path
+//! glob syntax. A blocked operation can go ahead with
+//! `--override "<justification>"`, which is recorded in the audit log.
+
+use crate::audit::{self, AuditAction};
+use crate::errors::ErrorCode;
+use crate::manifest::Manifest;
+use crate::pathspec;
+use crate::rules::{self, RuleConfig, RuleViolation, ValidationConfig};
+use crate::say;
+use anyhow::Result;
+use colored::*;
+use serde::Deserialize;
+use std::fmt;
+
+/// `[policy]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct PolicyConfig {
+    pub rules: Vec<PolicyRule>,
+}
+
+/// One `[[policy.rules]]` table
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct PolicyRule {
+    pub name: String,
+    /// Branch patterns the rule protects, e.g. `release/*`
+    pub branches: Vec<String>,
+    pub check: String,
+    /// Shown when the rule fails, instead of the check itself
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl PolicyRule {
+    pub fn applies_to(&self, branch: &str) -> bool {
+        self.branches.iter().any(|p| pathspec::matches(p, branch))
+    }
+}
+
+impl PolicyConfig {
+    /// The rules protecting `branch`, as validation rules
+    pub fn for_branch(&self, branch: &str) -> ValidationConfig {
+        ValidationConfig {
+            rules: self
+                .rules
+                .iter()
+                .filter(|r| r.applies_to(branch))
+                .map(|r| RuleConfig {
+                    name: r.name.clone(),
+                    check: r.check.clone(),
+                    message: r.message.clone(),
+                })
+                .collect(),
+            ..ValidationConfig::default()
+        }
+    }
+}
+
+/// A broken policy, with the incoming commit when checking a merge
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyViolation {
+    pub commit: Option<String>,
+    pub violation: RuleViolation,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.commit {
+            Some(ref sha) => write!(f, "{}: {}", &sha[..sha.len().min(8)], self.violation),
+            None => write!(f, "{}", self.violation),
+        }
+    }
+}
+
+/// Check a manifest against the policies of `branch`
+pub fn check(
+    manifest: &Manifest,
+    config: &PolicyConfig,
+    branch: &str,
+    commit: Option<&str>,
+) -> Result<Vec<PolicyViolation>> {
+    Ok(rules::check(manifest, &config.for_branch(branch))?
+        .into_iter()
+        .map(|violation| PolicyViolation {
+            commit: commit.map(str::to_string),
+            violation,
+        })
+        .collect())
+}
+
+/// Block an operation that breaks policies, unless overridden with a justification
+///
+/// `operation` is "Commit" or "Merge". Overrides are recorded in the audit log
+/// together with the broken rules.
+pub fn enforce(
+    operation: &str,
+    branch: &str,
+    violations: &[PolicyViolation],
+    justification: Option<&str>,
+    commit: Option<&str>,
+) -> Result<()> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let justification = match justification.map(str::trim) {
+        Some("") => anyhow::bail!("--override needs a justification"),
+        Some(j) => j,
+        None => {
+            eprintln!(
+                "{}",
+                format!("ERROR: {} breaks the policies of {}.", operation, branch)
+                    .red()
+                    .bold()
+            );
+            for v in violations {
+                eprintln!("  - {}", v);
+            }
+            eprintln!(
+                "\nFix the manifests, or run again with --override \"<justification>\" \
+                 (recorded in the audit log)."
+            );
+            anyhow::bail!(ErrorCode::PolicyViolation
+                .error(format!("{} rejected by branch policy", operation)));
+        }
+    };
+
+    for v in violations {
+        say!("{}", format!("Warning: policy overridden: {}", v).yellow());
+    }
+
+    let mut names: Vec<&str> = violations
+        .iter()
+        .map(|v| v.violation.rule.as_str())
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    audit::record(
+        AuditAction::PolicyOverride,
+        commit,
+        Some(format!(
+            "{} on {}: {} (rules: {})",
+            operation.to_lowercase(),
+            branch,
+            justification,
+            names.join(", ")
+        )),
+    );
+    Ok(())
+}
+
+/// Show the policies an override would let through, without recording it
+///
+/// Used by `--dry-run`, which must not write to the audit log.
+pub fn preview_override(violations: &[PolicyViolation]) {
+    for v in violations {
+        say!(
+            "{}",
+            format!("Warning: policy would be overridden: {}", v).yellow()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn config() -> PolicyConfig {
+        PolicyConfig {
+            rules: vec![
+                PolicyRule {
+                    name: "no-breaking-release".to_string(),
+                    branches: vec!["release/*".to_string()],
+                    check: "require(not entry.breaking)".to_string(),
+                    message: None,
+                },
+                PolicyRule {
+                    name: "migrations-link-issue".to_string(),
+                    branches: vec!["main".to_string(), "release/*".to_string()],
+                    check: r#"require(entry.issue) when entry.behavior_class contains "migration""#
+                        .to_string(),
+                    message: Some("Link an issue".to_string()),
+                },
+            ],
+        }
+    }
+
+    fn manifest(overrides: serde_json::Value) -> Manifest {
+        let mut entry = json!({
+            "anchor": { "file": "src/db.rs", "symbol": "migrate" },
+            "rationale": "Split the users table",
+            "behaviorClass": ["migration"]
+        });
+        test_support::merge(&mut entry, overrides);
+        test_support::manifest("abc", entry)
+    }
+
+    #[test]
+    fn test_rules_apply_by_branch() {
+        let config = config();
+        assert_eq!(config.for_branch("release/1.2").rules.len(), 2);
+        assert_eq!(config.for_branch("main").rules.len(), 1);
+        assert!(config.for_branch("feature/x").rules.is_empty());
+    }
+
+    #[test]
+    fn test_check_breaking_and_issue() {
+        let breaking = manifest(json!({
+            "compatibility": {"breaking": true},
+            "issue": "DB-12"
+        }));
+        let violations = check(&breaking, &config(), "release/1.2", Some("0123456789ab")).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].violation.rule, "no-breaking-release");
+        assert!(violations[0].to_string().starts_with("01234567: "));
+        assert!(check(&breaking, &config(), "main", None)
+            .unwrap()
+            .is_empty());
+
+        let unlinked = manifest(json!({}));
+        let violations = check(&unlinked, &config(), "main", None).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].violation.message, "Link an issue");
+    }
+
+    #[test]
+    fn test_enforce_needs_justification() {
+        let violations = check(&manifest(json!({})), &config(), "main", None).unwrap();
+        assert!(enforce("Commit", "main", &violations, None, None).is_err());
+        let err = enforce("Commit", "main", &violations, Some("  "), None).unwrap_err();
+        assert!(err.to_string().contains("justification"));
+        assert!(enforce("Commit", "main", &[], None, None).is_ok());
+    }
+}
@@ -0,0 +1,349 @@
+//! Projection profiles of manifests per consumer
+//!
+//! People at a terminal, conflict markers, LLM prompts and exports each want a
+//! different amount of detail. A profile names the entry fields to keep and
+//! caps lists, rationales and entries; each consumer is assigned a profile in
+//! `.gip/config.toml`:
+//!
+//! ```toml
+//! [projection]
+//! markers = "brief"
+//! prompt = "brief"
+//!
+//! [projection.profiles.brief]
+//! fields = ["behaviorClass", "rationale", "compatibility", "contract.preconditions"]
+//! max_list_items = 2
+//! max_rationale_chars = 120
+//! max_entries = 5
+//! ```
+//!
+//! `full` keeps everything and is what unassigned consumers use. `brief` is
+//! built in too and can be redefined. A field such as `contract` keeps all of
+//! its parts; `contract.preconditions` keeps only that one. The anchor and
+//! changeType identify an entry and are always kept.
+
+use crate::manifest::{Contract, Entry, Manifest};
+use crate::summarize;
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Profile that keeps everything
+pub const FULL: &str = "full";
+
+/// Built-in profile for a quick read
+pub const BRIEF: &str = "brief";
+
+/// Entry fields a profile can name
+pub const FIELDS: &[&str] = &[
+    "rationale",
+    "behaviorClass",
+    "signatureDelta",
+    "contract",
+    "contract.inputs",
+    "contract.outputs",
+    "contract.preconditions",
+    "contract.postconditions",
+    "contract.errorModel",
+    "sideEffects",
+    "compatibility",
+    "testsTouched",
+    "perfBudget",
+    "securityNotes",
+    "featureFlags",
+    "package",
+];
+
+/// Where a projected manifest ends up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Consumer {
+    /// Enriched conflict markers
+    Markers,
+    /// `gip context` in a terminal
+    Context,
+    /// Prompts for an LLM, as built by `gip prompt`
+    Prompt,
+    /// `gip context --export`
+    Export,
+}
+
+/// What a profile keeps of a manifest
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Profile {
+    /// Entry fields to keep, see [`FIELDS`]; empty keeps them all
+    pub fields: Vec<String>,
+    /// Items kept per list; 0 keeps all
+    pub max_list_items: usize,
+    /// Longest rationale, condensed like marker summaries; 0 keeps all
+    pub max_rationale_chars: usize,
+    /// Entries kept per manifest; 0 keeps all
+    pub max_entries: usize,
+}
+
+/// `[projection]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ProjectionConfig {
+    pub markers: Option<String>,
+    pub context: Option<String>,
+    pub prompt: Option<String>,
+    pub export: Option<String>,
+    /// Profiles by name, adding to or replacing the built-in ones
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl ProjectionConfig {
+    /// The profile assigned to a consumer
+    pub fn profile_for(&self, consumer: Consumer) -> Result<Profile> {
+        let name = match consumer {
+            Consumer::Markers => &self.markers,
+            Consumer::Context => &self.context,
+            Consumer::Prompt => &self.prompt,
+            Consumer::Export => &self.export,
+        };
+        self.named(name.as_deref().unwrap_or(FULL))
+    }
+
+    /// A profile by name, checking the fields it lists
+    pub fn named(&self, name: &str) -> Result<Profile> {
+        let profile = match (self.profiles.get(name), name) {
+            (Some(profile), _) => profile.clone(),
+            (None, FULL) => Profile::default(),
+            (None, BRIEF) => Profile::brief(),
+            (None, _) => {
+                let mut known: Vec<&str> = vec![FULL, BRIEF];
+                known.extend(self.profiles.keys().map(String::as_str));
+                known.sort_unstable();
+                known.dedup();
+                bail!("Unknown profile '{}' (known: {})", name, known.join(", "));
+            }
+        };
+        if let Some(field) = profile
+            .fields
+            .iter()
+            .find(|f| !FIELDS.contains(&f.as_str()))
+        {
+            bail!(
+                "Profile '{}' names unknown field '{}' (fields: {})",
+                name,
+                field,
+                FIELDS.join(", ")
+            );
+        }
+        Ok(profile)
+    }
+}
+
+impl Profile {
+    /// The built-in `brief` profile
+    pub fn brief() -> Self {
+        Self {
+            fields: [
+                "rationale",
+                "behaviorClass",
+                "compatibility",
+                "contract.preconditions",
+                "contract.errorModel",
+            ]
+            .iter()
+            .map(|f| f.to_string())
+            .collect(),
+            max_list_items: 3,
+            max_rationale_chars: 160,
+            max_entries: 0,
+        }
+    }
+
+    /// Whether the profile keeps everything
+    pub fn is_full(&self) -> bool {
+        *self == Profile::default()
+    }
+
+    fn keeps(&self, field: &str) -> bool {
+        self.fields.is_empty()
+            || self.fields.iter().any(|f| {
+                f == field
+                    || field
+                        .strip_prefix(f.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+    }
+
+    fn list(&self, field: &str, items: &[String]) -> Vec<String> {
+        if !self.keeps(field) {
+            return Vec::new();
+        }
+        let max = match self.max_list_items {
+            0 => items.len(),
+            max => max.min(items.len()),
+        };
+        items[..max].to_vec()
+    }
+
+    fn optional_list(&self, field: &str, items: &Option<Vec<String>>) -> Option<Vec<String>> {
+        items
+            .as_ref()
+            .map(|items| self.list(field, items))
+            .filter(|items| !items.is_empty())
+    }
+
+    /// A rationale as the profile keeps it
+    pub fn rationale(&self, text: &str) -> String {
+        match self.max_rationale_chars {
+            _ if !self.keeps("rationale") => String::new(),
+            0 => text.to_string(),
+            max => summarize::condense(text, max),
+        }
+    }
+
+    /// An entry with only what the profile keeps
+    pub fn project_entry(&self, entry: &Entry) -> Entry {
+        if self.is_full() {
+            return entry.clone();
+        }
+        let keep = |field: &str| self.keeps(field);
+        let compatibility = entry
+            .compatibility
+            .as_ref()
+            .filter(|_| keep("compatibility"))
+            .map(|compat| {
+                let mut compat = compat.clone();
+                compat.migrations = self.optional_list("compatibility", &compat.migrations);
+                compat
+            });
+
+        Entry {
+            anchor: entry.anchor.clone(),
+            change_type: entry.change_type.clone(),
+            rationale: self.rationale(&entry.rationale),
+            signature_delta: entry
+                .signature_delta
+                .clone()
+                .filter(|_| keep("signatureDelta")),
+            behavior_class: self.list("behaviorClass", &entry.behavior_class),
+            contract: Contract {
+                inputs: self.optional_list("contract.inputs", &entry.contract.inputs),
+                outputs: entry
+                    .contract
+                    .outputs
+                    .clone()
+                    .filter(|_| keep("contract.outputs")),
+                preconditions: self.list("contract.preconditions", &entry.contract.preconditions),
+                postconditions: self
+                    .list("contract.postconditions", &entry.contract.postconditions),
+                error_model: self.list("contract.errorModel", &entry.contract.error_model),
+            },
+            side_effects: self.list("sideEffects", &entry.side_effects),
+            compatibility,
+            tests_touched: self.optional_list("testsTouched", &entry.tests_touched),
+            perf_budget: entry.perf_budget.clone().filter(|_| keep("perfBudget")),
+            security_notes: self.optional_list("securityNotes", &entry.security_notes),
+            feature_flags: self.optional_list("featureFlags", &entry.feature_flags),
+            inherits_global_intent: entry.inherits_global_intent,
+            package: entry.package.clone().filter(|_| keep("package")),
+            // Unknown fields are only kept by profiles that keep everything
+            extra: if self.fields.is_empty() {
+                entry.extra.clone()
+            } else {
+                Default::default()
+            },
+        }
+    }
+
+    /// A manifest with only what the profile keeps
+    pub fn project(&self, manifest: &Manifest) -> Manifest {
+        if self.is_full() {
+            return manifest.clone();
+        }
+        let take = match self.max_entries {
+            0 => manifest.entries.len(),
+            max => max,
+        };
+        let mut projected = manifest.clone();
+        projected.entries = manifest
+            .entries
+            .iter()
+            .take(take)
+            .map(|e| self.project_entry(e))
+            .collect();
+        if let Some(ref mut gi) = projected.global_intent {
+            gi.rationale = self.rationale(&gi.rationale);
+        }
+        projected
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn manifest() -> Manifest {
+        test_support::manifest_with(
+            "abc",
+            vec![
+                json!({
+                    "anchor": { "file": "src/pay.rs", "symbol": "charge" },
+                    "rationale": "Retry failed gateway calls. Timeouts were common during peak hours.",
+                    "sideEffects": ["logs"],
+                    "contract": { "preconditions": ["a", "b", "c"], "postconditions": ["d"] }
+                }),
+                json!({
+                    "anchor": { "file": "src/pay.rs", "symbol": "refund", "hunkId": "H#2" },
+                    "changeType": "add",
+                    "rationale": "r",
+                    "behaviorClass": []
+                }),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_project_fields_and_caps() {
+        let profile = Profile {
+            fields: vec![
+                "rationale".to_string(),
+                "contract.preconditions".to_string(),
+            ],
+            max_list_items: 2,
+            max_rationale_chars: 40,
+            max_entries: 1,
+        };
+        let projected = profile.project(&manifest());
+        assert_eq!(projected.entries.len(), 1);
+
+        let entry = &projected.entries[0];
+        assert_eq!(entry.rationale, "Retry failed gateway calls.");
+        assert_eq!(entry.contract.preconditions, vec!["a", "b"]);
+        assert!(entry.contract.postconditions.is_empty());
+        assert!(entry.behavior_class.is_empty());
+        assert!(entry.side_effects.is_empty());
+        assert_eq!(entry.anchor.symbol, "charge");
+    }
+
+    #[test]
+    fn test_named_profiles() {
+        let mut config = ProjectionConfig {
+            markers: Some(BRIEF.to_string()),
+            ..Default::default()
+        };
+        assert!(config.profile_for(Consumer::Context).unwrap().is_full());
+        assert_eq!(
+            config.profile_for(Consumer::Markers).unwrap(),
+            Profile::brief()
+        );
+        assert!(config.named("terse").is_err());
+
+        config.profiles.insert(
+            "terse".to_string(),
+            Profile {
+                fields: vec!["contract.nope".to_string()],
+                ..Default::default()
+            },
+        );
+        let err = config.named("terse").unwrap_err().to_string();
+        assert!(err.contains("contract.nope"));
+    }
+}
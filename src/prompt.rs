@@ -0,0 +1,351 @@
+//! LLM resolution prompts
+//!
+//! Composes a self-contained prompt for resolving the conflicts of one file:
+//! the conflict hunks with surrounding lines, the manifest entries of both
+//! sides, the common ancestor, and instructions for the model. Used by
+//! `gip prompt` for workflows without an agent attached to the repository.
+
+use crate::manifest::{serialize_manifest_toon, Manifest};
+use crate::merge;
+use crate::pathspec::PathsConfig;
+use crate::profile::Profile;
+
+const CONFLICT_START: &str = "<<<<<<<";
+const CONFLICT_BASE: &str = "|||||||";
+const CONFLICT_MIDDLE: &str = "=======";
+const CONFLICT_END: &str = ">>>>>>>";
+
+/// Lines of unconflicted code shown before and after each hunk
+const CONTEXT_LINES: usize = 10;
+
+/// Longest common ancestor included verbatim
+const MAX_BASE_LINES: usize = 400;
+
+/// What the model is asked to do with the material above it
+const INSTRUCTIONS: &str = "\
+Resolve every conflict hunk above.
+
+- Preserve the intent of both sides as described by their manifests. When the
+  intents are incompatible, prefer the side whose contract is stricter and
+  explain the trade-off.
+- Respect each side's contract: inputs, outputs, preconditions, postconditions
+  and error model. Do not silently drop a breaking change or its migration.
+- Keep the surrounding code unchanged and match its style.
+- Reply with the resolved code for each hunk in order, in a fenced block per
+  hunk, followed by one sentence per hunk explaining the resolution.";
+
+/// One conflict region of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    /// 1-based line of the `<<<<<<<` marker
+    pub line: usize,
+    pub ours_label: String,
+    pub theirs_label: String,
+    pub ours: Vec<String>,
+    /// Ancestor lines, present with `merge.conflictStyle = diff3`
+    pub base: Option<Vec<String>>,
+    pub theirs: Vec<String>,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// One side of the conflict
+#[derive(Debug, Clone)]
+pub struct Side {
+    pub label: String,
+    pub sha: String,
+    pub manifest: Option<Manifest>,
+}
+
+/// Parse the conflict hunks of a file, skipping Gip context lines
+pub fn parse_hunks(content: &str) -> Vec<Hunk> {
+    let lines: Vec<&str> = content
+        .lines()
+        .filter(|l| !merge::is_context_line(l))
+        .collect();
+
+    let mut hunks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        if !lines[i].starts_with(CONFLICT_START) {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let mut hunk = Hunk {
+            line: start + 1,
+            ours_label: marker_label(lines[i], CONFLICT_START),
+            theirs_label: String::new(),
+            ours: Vec::new(),
+            base: None,
+            theirs: Vec::new(),
+            before: lines[start.saturating_sub(CONTEXT_LINES)..start]
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            after: Vec::new(),
+        };
+
+        i += 1;
+        let mut section = 0;
+        while i < lines.len() && !lines[i].starts_with(CONFLICT_END) {
+            let line = lines[i];
+            if line.starts_with(CONFLICT_BASE) {
+                hunk.base = Some(Vec::new());
+                section = 1;
+            } else if line.starts_with(CONFLICT_MIDDLE) {
+                section = 2;
+            } else {
+                let target = match section {
+                    0 => &mut hunk.ours,
+                    1 => hunk.base.get_or_insert_with(Vec::new),
+                    _ => &mut hunk.theirs,
+                };
+                target.push(line.to_string());
+            }
+            i += 1;
+        }
+
+        if let Some(end) = lines.get(i) {
+            hunk.theirs_label = marker_label(end, CONFLICT_END);
+        }
+        i += 1;
+
+        let next = lines[i.min(lines.len())..]
+            .iter()
+            .position(|l| l.starts_with(CONFLICT_START))
+            .map_or(lines.len(), |p| i + p);
+        hunk.after = lines[i.min(lines.len())..next.min(i + CONTEXT_LINES)]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+
+        hunks.push(hunk);
+    }
+
+    hunks
+}
+
+fn marker_label(line: &str, marker: &str) -> String {
+    line.trim_start_matches(marker).trim().to_string()
+}
+
+/// Settings from `.gip/config.toml` that shape a prompt
+#[derive(Debug, Clone, Default)]
+pub struct PromptOptions {
+    pub paths: PathsConfig,
+    /// Projection of the manifest entries included
+    pub profile: Profile,
+}
+
+/// Compose the resolution prompt for one conflicted file
+pub fn compose(
+    file_path: &str,
+    hunks: &[Hunk],
+    ours: &Side,
+    theirs: &Side,
+    aliases: &[String],
+    options: &PromptOptions,
+    base: Option<&str>,
+) -> String {
+    let mut out = String::new();
+    let lang = fence_language(file_path);
+
+    out.push_str(&format!("# Resolve merge conflicts in `{}`\n\n", file_path));
+    out.push_str(&format!(
+        "Two changes conflict: \"ours\" is {} ({}) and \"theirs\" is {} ({}). \
+         Each side's manifest records the intent and contract of its change.\n\n",
+        ours.label,
+        short(&ours.sha),
+        theirs.label,
+        short(&theirs.sha)
+    ));
+
+    out.push_str("## Conflicts\n");
+    for (n, hunk) in hunks.iter().enumerate() {
+        out.push_str(&format!("\n### Hunk {} (line {})\n\n", n + 1, hunk.line));
+        if !hunk.before.is_empty() {
+            push_block(&mut out, "Before", lang, &hunk.before);
+        }
+        push_block(&mut out, "Ours", lang, &hunk.ours);
+        if let Some(ref base) = hunk.base {
+            push_block(&mut out, "Common ancestor", lang, base);
+        }
+        push_block(&mut out, "Theirs", lang, &hunk.theirs);
+        if !hunk.after.is_empty() {
+            push_block(&mut out, "After", lang, &hunk.after);
+        }
+    }
+
+    out.push_str("\n## Intent\n");
+    push_intent(&mut out, "Ours", ours, file_path, aliases, options, hunks);
+    push_intent(
+        &mut out, "Theirs", theirs, file_path, aliases, options, hunks,
+    );
+
+    if let Some(base) = base {
+        out.push_str("\n## Common ancestor\n\n");
+        let lines: Vec<String> = base.lines().map(|l| l.to_string()).collect();
+        if lines.len() > MAX_BASE_LINES {
+            out.push_str(&format!(
+                "(omitted: {} lines, longer than {})\n",
+                lines.len(),
+                MAX_BASE_LINES
+            ));
+        } else {
+            push_block(&mut out, "", lang, &lines);
+        }
+    }
+
+    out.push_str("\n## Instructions\n\n");
+    out.push_str(INSTRUCTIONS);
+    out.push('\n');
+    out
+}
+
+/// Append the manifest entries of one side that are relevant to the hunks
+fn push_intent(
+    out: &mut String,
+    title: &str,
+    side: &Side,
+    file_path: &str,
+    aliases: &[String],
+    options: &PromptOptions,
+    hunks: &[Hunk],
+) {
+    out.push_str(&format!("\n### {} ({})\n\n", title, short(&side.sha)));
+
+    let manifest = match side.manifest {
+        Some(ref m) => m,
+        None => {
+            out.push_str("No manifest recorded.\n");
+            return;
+        }
+    };
+
+    let context: Vec<&str> = hunks
+        .iter()
+        .flat_map(|h| h.before.iter().chain(&h.ours).chain(&h.theirs))
+        .map(|l| l.as_str())
+        .collect();
+    let entries = merge::rank_entries(manifest, file_path, aliases, Some(&context), options.paths);
+
+    let relevant = options.profile.project(&Manifest {
+        entries: entries.into_iter().cloned().collect(),
+        ..manifest.clone()
+    });
+    match serialize_manifest_toon(&relevant) {
+        Ok(toon) => {
+            out.push_str("```toon\n");
+            out.push_str(toon.trim_end());
+            out.push_str("\n```\n");
+        }
+        Err(_) => out.push_str("Manifest could not be serialized.\n"),
+    }
+}
+
+fn push_block(out: &mut String, title: &str, lang: &str, lines: &[String]) {
+    if !title.is_empty() {
+        out.push_str(&format!("{}:\n", title));
+    }
+    out.push_str(&format!("```{}\n", lang));
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.push_str("```\n");
+}
+
+/// Code fence language for a file, from its extension
+fn fence_language(file_path: &str) -> &str {
+    std::path::Path::new(file_path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+}
+
+fn short(sha: &str) -> &str {
+    &sha[..sha.len().min(8)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFLICTED: &str = "\
+fn charge() {
+    validate();
+<<<<<<< HEAD
+||| Gip CONTEXT (HEAD - Your changes)
+||| rationale: Retry on timeout
+    retry(3);
+||||||| base
+    call();
+=======
+    call_with_timeout(5);
+>>>>>>> feature
+    log();
+}
+";
+
+    #[test]
+    fn test_parse_hunks_diff3() {
+        let hunks = parse_hunks(CONFLICTED);
+        assert_eq!(hunks.len(), 1);
+
+        let hunk = &hunks[0];
+        assert_eq!(hunk.line, 3);
+        assert_eq!(hunk.ours_label, "HEAD");
+        assert_eq!(hunk.theirs_label, "feature");
+        assert_eq!(hunk.ours, vec!["    retry(3);"]);
+        assert_eq!(hunk.base, Some(vec!["    call();".to_string()]));
+        assert_eq!(hunk.theirs, vec!["    call_with_timeout(5);"]);
+        assert_eq!(hunk.before, vec!["fn charge() {", "    validate();"]);
+        assert_eq!(hunk.after, vec!["    log();", "}"]);
+    }
+
+    #[test]
+    fn test_parse_hunks_context_stops_at_next_hunk() {
+        let content =
+            "<<<<<<< HEAD\na\n=======\nb\n>>>>>>> x\nmid\n<<<<<<< HEAD\nc\n=======\nd\n>>>>>>> x\n";
+        let hunks = parse_hunks(content);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(hunks[0].base, None);
+        assert_eq!(hunks[0].after, vec!["mid"]);
+        assert_eq!(hunks[1].line, 7);
+        assert_eq!(hunks[1].ours, vec!["c"]);
+    }
+
+    #[test]
+    fn test_compose_includes_all_parts() {
+        let hunks = parse_hunks(CONFLICTED);
+        let ours = Side {
+            label: "HEAD".to_string(),
+            sha: "aaaaaaaaaaaa".to_string(),
+            manifest: None,
+        };
+        let theirs = Side {
+            label: "feature".to_string(),
+            sha: "bbbbbbbbbbbb".to_string(),
+            manifest: None,
+        };
+
+        let prompt = compose(
+            "src/pay.rs",
+            &hunks,
+            &ours,
+            &theirs,
+            &[],
+            &PromptOptions::default(),
+            Some("fn charge() {}\n"),
+        );
+        assert!(prompt.contains("# Resolve merge conflicts in `src/pay.rs`"));
+        assert!(prompt.contains("### Hunk 1 (line 3)"));
+        assert!(prompt.contains("```rs\n    retry(3);\n```"));
+        assert!(prompt.contains("No manifest recorded."));
+        assert!(prompt.contains("## Common ancestor"));
+        assert!(prompt.contains("## Instructions"));
+        assert!(!prompt.contains("||| rationale"));
+    }
+}
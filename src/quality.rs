@@ -0,0 +1,286 @@
+//! Manifest quality scores
+//!
+//! A manifest earns up to 100 points, averaged over its entries:
+//!
+//! - rationale specificity (40): enough words, says why, not boilerplate
+//! - contract (20): inputs, outputs, pre/postconditions or error model
+//! - tests (20): `testsTouched` lists tests (docs and config changes are exempt)
+//! - anchors (20): the anchored symbol is defined in the anchored file, or the
+//!   anchor covers a whole directory or glob with symbol `*`
+//!
+//! `[quality] min_score` in `.gip/config.toml` makes `gip commit` reject
+//! manifests scoring below it.
+
+use crate::manifest::{
+    Anchor, Entry, Manifest, BEHAVIOR_CONFIG, BEHAVIOR_DOCS, CHANGE_DELETE, SYMBOL_ANY,
+};
+use crate::symbols;
+use crate::template::PLACEHOLDER;
+use serde::{Deserialize, Serialize};
+
+/// Highest possible score
+pub const MAX_SCORE: u32 = 100;
+
+/// Rationales that say nothing about the change
+const VAGUE_RATIONALES: &[&str] = &[
+    "fix",
+    "fix bug",
+    "bug fix",
+    "update",
+    "updates",
+    "changes",
+    "cleanup",
+    "refactor",
+    "wip",
+    "misc",
+    "tweak",
+    "minor changes",
+];
+
+/// Words that show a rationale explains why, not only what
+const REASON_MARKERS: &[&str] = &[
+    "because",
+    "so that",
+    "so ",
+    "to ",
+    "since",
+    "prevent",
+    "avoid",
+    "otherwise",
+    "instead",
+];
+
+/// `[quality]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct QualityConfig {
+    /// Reject manifests scoring below this (0 disables the check)
+    pub min_score: u32,
+}
+
+/// Score of one manifest with suggestions for raising it
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityReport {
+    pub score: u32,
+    pub hints: Vec<String>,
+}
+
+/// Points for a rationale, out of 40
+pub fn rationale_points(rationale: &str) -> u32 {
+    let text = rationale.trim().to_lowercase();
+    if text.is_empty() || text.contains(&PLACEHOLDER.to_lowercase()) {
+        return 0;
+    }
+    if VAGUE_RATIONALES.contains(&text.trim_end_matches('.')) {
+        return 5;
+    }
+
+    let words = text.split_whitespace().count();
+    let length = match words {
+        0..=2 => 5,
+        3..=7 => 20,
+        _ => 30,
+    };
+    let reason = if REASON_MARKERS.iter().any(|m| text.contains(m)) {
+        10
+    } else {
+        0
+    };
+    length + reason
+}
+
+fn has_contract(entry: &Entry) -> bool {
+    let c = &entry.contract;
+    c.inputs.as_ref().is_some_and(|i| !i.is_empty())
+        || c.outputs.as_ref().is_some_and(|o| !o.trim().is_empty())
+        || !c.preconditions.is_empty()
+        || !c.postconditions.is_empty()
+        || !c.error_model.is_empty()
+}
+
+fn needs_tests(entry: &Entry) -> bool {
+    !entry
+        .behavior_class
+        .iter()
+        .all(|c| c == BEHAVIOR_DOCS || c == BEHAVIOR_CONFIG)
+}
+
+/// Whether `source` defines the anchored symbol
+pub fn defines_symbol(source: &str, symbol: &str) -> bool {
+    source.lines().any(|line| {
+        symbols::match_line(symbol, line)
+            .is_some_and(|m| m.is_definition && m.quality == symbols::MatchQuality::Exact)
+    })
+}
+
+/// Score a manifest
+///
+/// `source_of` returns the content of an anchored file, or `None` if it can't
+/// be read; anchors of deleted symbols always count as resolving.
+pub fn score<F>(manifest: &Manifest, mut source_of: F) -> QualityReport
+where
+    F: FnMut(&Anchor) -> Option<String>,
+{
+    let mut report = QualityReport::default();
+    if manifest.entries.is_empty() {
+        report.hints.push("Add at least one entry".to_string());
+        return report;
+    }
+
+    let mut total = 0;
+    for entry in &manifest.entries {
+        let label = format!("{} ({})", entry.anchor.symbol, entry.anchor.file);
+        let rationale = rationale_points(&entry.rationale);
+        if rationale < 30 {
+            report.hints.push(format!(
+                "{}: make the rationale specific and say why the change was needed",
+                label
+            ));
+        }
+
+        let contract = if has_contract(entry) { 20 } else { 0 };
+        if contract == 0 {
+            report
+                .hints
+                .push(format!("{}: describe the contract", label));
+        }
+
+        let tests =
+            if !needs_tests(entry) || entry.tests_touched.as_ref().is_some_and(|t| !t.is_empty()) {
+                20
+            } else {
+                report.hints.push(format!("{}: list testsTouched", label));
+                0
+            };
+
+        let resolves = entry.change_type == CHANGE_DELETE
+            || entry.anchor.symbol == SYMBOL_ANY
+            || source_of(&entry.anchor).is_some_and(|s| defines_symbol(&s, &entry.anchor.symbol));
+        let anchor = if resolves {
+            20
+        } else if entry.anchor.is_glob() {
+            report.hints.push(format!(
+                "{}: glob anchors cover many files; use symbol \"{}\"",
+                label, SYMBOL_ANY
+            ));
+            0
+        } else {
+            report.hints.push(format!(
+                "{}: anchor does not match a definition in the file",
+                label
+            ));
+            0
+        };
+
+        total += rationale + contract + tests + anchor;
+    }
+
+    report.score = (total as f64 / manifest.entries.len() as f64).round() as u32;
+    report
+}
+
+/// Mean of a set of scores, 0 when there are none
+pub fn average(scores: &[u32]) -> f64 {
+    if scores.is_empty() {
+        return 0.0;
+    }
+    scores.iter().map(|&s| s as f64).sum::<f64>() / scores.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    const SOURCE: &str = "pub fn charge(amount: u64) -> Result<()> {\n    Ok(())\n}\n";
+
+    fn manifest(entry: serde_json::Value) -> Manifest {
+        test_support::manifest("abc", entry)
+    }
+
+    #[test]
+    fn test_rationale_points() {
+        assert_eq!(rationale_points(""), 0);
+        assert_eq!(rationale_points(PLACEHOLDER), 0);
+        assert_eq!(rationale_points("Fix bug."), 5);
+        assert_eq!(rationale_points("Retry failed charges"), 20);
+        assert_eq!(
+            rationale_points(
+                "Retry failed charges once because the gateway drops idle connections"
+            ),
+            40
+        );
+    }
+
+    #[test]
+    fn test_full_score() {
+        let m = manifest(json!({
+            "anchor": {"file": "src/pay.rs", "symbol": "charge", "hunkId": "H#1"},
+            "changeType": "modify",
+            "rationale": "Accept u64 amounts so that charges above 2^31 cents no longer overflow",
+            "behaviorClass": ["bugfix"],
+            "contract": {"preconditions": ["amount > 0"]},
+            "testsTouched": ["tests/pay.rs"]
+        }));
+        let report = score(&m, |_| Some(SOURCE.to_string()));
+        assert_eq!(report.score, MAX_SCORE);
+        assert!(report.hints.is_empty());
+    }
+
+    #[test]
+    fn test_low_effort_score() {
+        let m = manifest(json!({
+            "anchor": {"file": "src/pay.rs", "symbol": "refund", "hunkId": "H#1"},
+            "changeType": "modify",
+            "rationale": "update",
+            "behaviorClass": ["feature"],
+            "contract": {}
+        }));
+        let report = score(&m, |_| Some(SOURCE.to_string()));
+        assert_eq!(report.score, 5);
+        assert_eq!(report.hints.len(), 4);
+    }
+
+    #[test]
+    fn test_docs_need_no_tests() {
+        let m = manifest(json!({
+            "anchor": {"file": "src/pay.rs", "symbol": "charge", "hunkId": "H#1"},
+            "changeType": "modify",
+            "rationale": "Document the overflow limit on amounts",
+            "behaviorClass": ["docs"],
+            "contract": {}
+        }));
+        assert_eq!(score(&m, |_| Some(SOURCE.to_string())).score, 60);
+    }
+
+    #[test]
+    fn test_glob_anchors() {
+        let sweeping = manifest(json!({
+            "anchor": {"file": "src/generated/**", "symbol": "*", "hunkId": "H#1"},
+            "changeType": "modify",
+            "rationale": "Regenerate protobuf bindings",
+            "behaviorClass": ["config"],
+            "contract": {}
+        }));
+        assert_eq!(score(&sweeping, |_| None).score, 60);
+
+        let ambiguous = manifest(json!({
+            "anchor": {"file": "src/generated/**", "symbol": "Client", "hunkId": "H#1"},
+            "changeType": "modify",
+            "rationale": "Regenerate protobuf bindings",
+            "behaviorClass": ["config"],
+            "contract": {}
+        }));
+        let report = score(&ambiguous, |_| None);
+        assert_eq!(report.score, 40);
+        assert!(report.hints.iter().any(|h| h.contains("use symbol \"*\"")));
+    }
+
+    #[test]
+    fn test_average() {
+        assert_eq!(average(&[]), 0.0);
+        assert_eq!(average(&[50, 100]), 75.0);
+    }
+}
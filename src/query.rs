@@ -0,0 +1,255 @@
+//! History queries over stored manifests
+//!
+//! Walks commits with `git rev-list`, narrowed by author, date range, and path,
+//! and loads the manifests attached to them.
+
+use crate::git;
+use crate::index::SymbolIndex;
+use crate::manifest::{self, Manifest};
+use crate::packages::PackageMap;
+use crate::pathspec;
+use crate::symbols;
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Filters applied when walking manifest history
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    /// Only commits whose author matches (passed to `--author`)
+    pub author: Option<String>,
+    /// Only commits newer than this date (passed to `--since`)
+    pub since: Option<String>,
+    /// Only commits older than this date (passed to `--until`)
+    pub until: Option<String>,
+    /// Only commits touching this path; entries are narrowed to it as well
+    pub path: Option<String>,
+    /// Only entries belonging to this monorepo package
+    pub package: Option<String>,
+    /// Earlier names of `path`, filled by [`HistoryFilter::resolve_renames`]
+    pub path_aliases: Vec<String>,
+    /// Only entries for this symbol
+    pub symbol: Option<String>,
+    /// Other names of `symbol`, filled by [`HistoryFilter::resolve_renames`]
+    pub symbol_aliases: Vec<String>,
+}
+
+impl HistoryFilter {
+    /// Whether any commit-level filter is set
+    pub fn is_history_query(&self) -> bool {
+        self.author.is_some()
+            || self.since.is_some()
+            || self.until.is_some()
+            || self.path.is_some()
+            || self.symbol.is_some()
+    }
+
+    /// Record the names `path` had before being renamed in the history of `rev`,
+    /// and the other names `symbol` had according to the symbol index
+    ///
+    /// Only applies when `path` is a single file; directories are left as is.
+    pub fn resolve_renames(&mut self, rev: &str) {
+        if let Some(ref path) = self.path {
+            if Path::new(path).is_file() {
+                self.path_aliases = git::file_path_history(rev, path, None)
+                    .into_iter()
+                    .skip(1)
+                    .collect();
+            }
+        }
+        if let Some(ref symbol) = self.symbol {
+            // Without an index the symbol is matched under its current name only
+            if let Ok(index) = SymbolIndex::load(None) {
+                self.symbol_aliases = index.aliases(symbol);
+            }
+        }
+    }
+
+    /// Whether an entry's symbol is `symbol` or one of its other names
+    fn matches_symbol(&self, entry_symbol: &str) -> bool {
+        match self.symbol {
+            Some(ref symbol) => {
+                let name = symbols::base_name(entry_symbol);
+                name == symbols::base_name(symbol) || self.symbol_aliases.iter().any(|a| a == name)
+            }
+            None => true,
+        }
+    }
+
+    /// Build the commit-limiting options shared by `git rev-list` and `git log`
+    fn revision_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(ref author) = self.author {
+            args.push(format!("--author={}", author));
+        }
+        if let Some(ref since) = self.since {
+            args.push(format!("--since={}", since));
+        }
+        if let Some(ref until) = self.until {
+            args.push(format!("--until={}", until));
+        }
+        args
+    }
+
+    /// Build the extra `git rev-list` arguments for this filter
+    pub fn rev_list_args(&self) -> Vec<String> {
+        let mut args = self.revision_args();
+        if let Some(ref path) = self.path {
+            args.push("--".to_string());
+            args.push(path.clone());
+        }
+        args
+    }
+
+    /// List commits matching the filter, newest first
+    pub fn commits(&self, rev: &str, repo_path: Option<&Path>) -> Result<Vec<String>> {
+        match self.path {
+            // rev-list cannot follow renames, so renamed files go through git log
+            Some(ref path) if !self.path_aliases.is_empty() => {
+                git::log_follow(rev, path, &self.revision_args(), repo_path)
+            }
+            _ => git::rev_list(rev, &self.rev_list_args(), repo_path),
+        }
+    }
+
+    /// Narrow a manifest's entries to the path and package filters
+    ///
+    /// Returns `None` when an entry-level filter is set and nothing survives it.
+    pub fn apply(&self, manifest: Manifest, packages: &PackageMap) -> Option<Manifest> {
+        if self.path.is_none() && self.package.is_none() && self.symbol.is_none() {
+            return Some(manifest);
+        }
+
+        let mut manifest = manifest;
+        manifest.entries.retain(|e| {
+            let path_ok = match self.path {
+                Some(ref p) => {
+                    pathspec::matches(p, &e.anchor.file)
+                        || self.path_aliases.contains(&e.anchor.file)
+                }
+                None => true,
+            };
+            let package_ok = match self.package {
+                Some(ref p) => packages.package_of(e) == Some(p.as_str()),
+                None => true,
+            };
+            path_ok && package_ok && self.matches_symbol(&e.anchor.symbol)
+        });
+
+        if manifest.entries.is_empty() {
+            None
+        } else {
+            Some(manifest)
+        }
+    }
+
+    /// Load every manifest matching the filter, newest first
+    pub fn collect(
+        &self,
+        rev: &str,
+        packages: &PackageMap,
+        repo_path: Option<&Path>,
+    ) -> Result<Vec<Manifest>> {
+        let commits = self.commits(rev, repo_path)?;
+        self.load_noted(&commits, packages, repo_path)
+    }
+
+    /// Load the manifests of those `commits` that carry a gip note, keeping order
+    pub fn load_noted(
+        &self,
+        commits: &[String],
+        packages: &PackageMap,
+        repo_path: Option<&Path>,
+    ) -> Result<Vec<Manifest>> {
+        let noted: HashSet<String> = git::list_noted_commits(repo_path)?.into_iter().collect();
+        let mut manifests = Vec::new();
+
+        for sha in commits.iter().filter(|sha| noted.contains(*sha)) {
+            if let Ok(m) = manifest::load(sha, repo_path) {
+                if let Some(m) = self.apply(m, packages) {
+                    manifests.push(m);
+                }
+            }
+        }
+
+        Ok(manifests)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rev_list_args() {
+        let filter = HistoryFilter {
+            author: Some("alice".to_string()),
+            since: Some("3 months ago".to_string()),
+            until: None,
+            path: Some("src/api".to_string()),
+            package: None,
+            path_aliases: Vec::new(),
+            symbol: None,
+            symbol_aliases: Vec::new(),
+        };
+
+        assert!(filter.is_history_query());
+        assert_eq!(
+            filter.rev_list_args(),
+            vec!["--author=alice", "--since=3 months ago", "--", "src/api"]
+        );
+    }
+
+    #[test]
+    fn test_package_only_is_not_history_query() {
+        let filter = HistoryFilter {
+            package: Some("core".to_string()),
+            ..Default::default()
+        };
+        assert!(!filter.is_history_query());
+        assert!(filter.rev_list_args().is_empty());
+    }
+
+    #[test]
+    fn test_apply_matches_renamed_path() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"schemaVersion":"2.0","commit":"abc","entries":[{"anchor":{"file":"src/old.rs","symbol":"run","hunkId":"H#1"},"changeType":"modify","rationale":"r","behaviorClass":[],"contract":{}}]}"#,
+        )
+        .unwrap();
+
+        let filter = HistoryFilter {
+            path: Some("src/new.rs".to_string()),
+            path_aliases: vec!["src/old.rs".to_string()],
+            ..Default::default()
+        };
+        assert!(filter
+            .apply(manifest.clone(), &PackageMap::default())
+            .is_some());
+
+        let no_alias = HistoryFilter {
+            path: Some("src/new.rs".to_string()),
+            ..Default::default()
+        };
+        assert!(no_alias.apply(manifest, &PackageMap::default()).is_none());
+    }
+
+    #[test]
+    fn test_apply_matches_renamed_symbol() {
+        let manifest: Manifest = serde_json::from_str(
+            r#"{"schemaVersion":"2.0","commit":"abc","entries":[{"anchor":{"file":"src/pay.rs","symbol":"Gateway::charge","hunkId":"H#1"},"changeType":"modify","rationale":"r","behaviorClass":[],"contract":{}}]}"#,
+        )
+        .unwrap();
+
+        let mut filter = HistoryFilter {
+            symbol: Some("bill".to_string()),
+            ..Default::default()
+        };
+        assert!(filter.is_history_query());
+        assert!(filter
+            .apply(manifest.clone(), &PackageMap::default())
+            .is_none());
+
+        filter.symbol_aliases = vec!["charge".to_string()];
+        assert!(filter.apply(manifest, &PackageMap::default()).is_some());
+    }
+}
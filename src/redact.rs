@@ -0,0 +1,236 @@
+//! Field-level redaction of manifests before they leave the machine
+//!
+//! Rules match manifest fields by their serialized (camelCase) name, e.g.
+//! `rationale` or `securityNotes`, or every string field with `*`. Redacted
+//! notes are written to a separate ref, `refs/notes/gip-redacted`, which is
+//! pushed in place of `refs/notes/gip` so the full version stays local.
+
+use crate::encryption;
+use crate::git;
+use crate::manifest::{self, Manifest};
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Notes ref holding the redacted copies of gip notes
+pub const REDACTED_NOTES_REF: &str = "gip-redacted";
+
+/// `[redaction]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Redact on every `gip push`, not only with `--redact`
+    pub always: bool,
+    pub rules: Vec<RedactionRule>,
+}
+
+/// Replace matches of `pattern` in a manifest field
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct RedactionRule {
+    /// Serialized field name, or `*` for every string field
+    pub field: String,
+    pub pattern: String,
+    #[serde(default = "default_replacement")]
+    pub replacement: String,
+}
+
+fn default_replacement() -> String {
+    "[REDACTED]".to_string()
+}
+
+/// Compiled redaction rules
+pub struct Redactor {
+    rules: Vec<(String, Regex, String)>,
+}
+
+impl Redactor {
+    pub fn new(rules: &[RedactionRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let re = Regex::new(&rule.pattern).with_context(|| {
+                    format!(
+                        "Invalid redaction pattern for '{}': {}",
+                        rule.field, rule.pattern
+                    )
+                })?;
+                Ok((rule.field.clone(), re, rule.replacement.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { rules })
+    }
+
+    /// Return a copy of the manifest with every rule applied
+    pub fn redact(&self, manifest: &Manifest) -> Result<Manifest> {
+        let mut value = serde_json::to_value(manifest)?;
+        self.redact_value(&mut value, None);
+        serde_json::from_value(value).context("Redaction produced an invalid manifest")
+    }
+
+    fn redact_value(&self, value: &mut Value, field: Option<&str>) {
+        match value {
+            Value::String(s) => {
+                let Some(field) = field else { return };
+                for (rule_field, re, replacement) in &self.rules {
+                    if rule_field == "*" || rule_field == field {
+                        *s = re.replace_all(s, replacement.as_str()).into_owned();
+                    }
+                }
+            }
+            // Strings inside an array belong to the array's field
+            Value::Array(items) => {
+                for item in items {
+                    self.redact_value(item, field);
+                }
+            }
+            Value::Object(map) => {
+                for (key, child) in map.iter_mut() {
+                    self.redact_value(child, Some(key));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Bring `refs/notes/gip-redacted` up to date with the local gip notes
+///
+/// The ref keeps its history so each publish fast-forwards the last one, and
+/// only notes whose redacted text changed are rewritten. Notes with no local
+/// counterpart are kept: once the remote notes are fetched into the copy they
+/// include what teammates published. Returns the number of redacted notes.
+pub fn write_redacted_notes(redactor: &Redactor, cwd: Option<&Path>) -> Result<usize> {
+    let (published, blobs): (Vec<String>, Vec<String>) =
+        git::list_note_blobs_in(REDACTED_NOTES_REF, cwd)?
+            .into_iter()
+            .unzip();
+    let published: HashMap<String, String> = published
+        .into_iter()
+        .zip(git::cat_blobs(&blobs, cwd)?)
+        .collect();
+
+    let mut count = 0;
+    for commit in git::list_noted_commits(cwd)? {
+        let manifest = manifest::load(&commit, cwd)?;
+        let redacted = redactor.redact(&manifest)?;
+        let toon = manifest::serialize_manifest_toon(&redacted)?;
+        count += 1;
+
+        let unchanged = published
+            .get(&commit)
+            .and_then(|note| encryption::open_note(note.clone()).ok())
+            .is_some_and(|note| note.trim() == toon.trim());
+        if !unchanged {
+            let note = encryption::seal_note(toon)?;
+            git::add_note_to_ref(REDACTED_NOTES_REF, &commit, &note, cwd)?;
+        }
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use crate::test_support::init_repo;
+    use serde_json::json;
+
+    fn rule(field: &str, pattern: &str) -> RedactionRule {
+        RedactionRule {
+            field: field.to_string(),
+            pattern: pattern.to_string(),
+            replacement: default_replacement(),
+        }
+    }
+
+    fn manifest() -> Manifest {
+        test_support::manifest(
+            "abc123",
+            json!({
+                "anchor": { "file": "src/auth.rs", "symbol": "login" },
+                "rationale": "Fix for PROJECT-FALCON, see https://wiki.corp/x",
+                "behaviorClass": ["security"],
+                "securityNotes": ["Reported by PROJECT-FALCON red team"]
+            }),
+        )
+    }
+
+    #[test]
+    fn test_redact_named_field() {
+        let redactor = Redactor::new(&[rule("rationale", r"https://wiki\.corp\S*")]).unwrap();
+        let redacted = redactor.redact(&manifest()).unwrap();
+
+        assert_eq!(
+            redacted.entries[0].rationale,
+            "Fix for PROJECT-FALCON, see [REDACTED]"
+        );
+        assert_eq!(
+            redacted.entries[0].security_notes,
+            manifest().entries[0].security_notes
+        );
+    }
+
+    #[test]
+    fn test_redact_wildcard_field_and_arrays() {
+        let redactor = Redactor::new(&[rule("*", r"PROJECT-\w+")]).unwrap();
+        let redacted = redactor.redact(&manifest()).unwrap();
+
+        assert!(!redacted.entries[0].rationale.contains("FALCON"));
+        assert_eq!(
+            redacted.entries[0].security_notes.as_ref().unwrap()[0],
+            "Reported by [REDACTED] red team"
+        );
+    }
+
+    #[test]
+    fn test_write_redacted_notes_fast_forwards() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Some(temp_dir.path());
+        init_repo(temp_dir.path());
+        let mut commits = Vec::new();
+        for message in ["first", "second"] {
+            git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", message], dir).unwrap();
+            let sha = git::run_git_cmd(&["rev-parse", "HEAD"], dir).unwrap();
+            let mut m = manifest();
+            m.commit = sha.clone();
+            manifest::save(&m, &sha, dir).unwrap();
+            commits.push(sha);
+        }
+        let redactor = Redactor::new(&[rule("*", r"PROJECT-\w+")]).unwrap();
+        let full_ref = format!("refs/notes/{}", REDACTED_NOTES_REF);
+
+        assert_eq!(write_redacted_notes(&redactor, dir).unwrap(), 2);
+        let first = git::run_git_cmd(&["rev-parse", &full_ref], dir).unwrap();
+        // Nothing changed, nothing to publish
+        write_redacted_notes(&redactor, dir).unwrap();
+        assert_eq!(
+            git::run_git_cmd(&["rev-parse", &full_ref], dir).unwrap(),
+            first
+        );
+
+        let mut m = manifest::load(&commits[1], dir).unwrap();
+        m.entries[0].rationale = "Fix for PROJECT-HAWK".to_string();
+        manifest::save(&m, &commits[1], dir).unwrap();
+        let ref_arg = format!("--ref={}", git::notes_ref());
+        git::run_git_cmd(&["notes", &ref_arg, "remove", &commits[0]], dir).unwrap();
+        assert_eq!(write_redacted_notes(&redactor, dir).unwrap(), 1);
+        git::run_git_cmd(&["merge-base", "--is-ancestor", &first, &full_ref], dir)
+            .expect("the second publish must fast-forward the first");
+        // Notes without a local counterpart may be a teammate's and stay
+        let published = git::list_note_blobs_in(REDACTED_NOTES_REF, dir).unwrap();
+        assert_eq!(published.len(), 2);
+        let ref_arg = format!("--ref={}", REDACTED_NOTES_REF);
+        let note = git::run_git_cmd(&["notes", &ref_arg, "show", &commits[1]], dir).unwrap();
+        assert!(note.contains("[REDACTED]") && !note.contains("HAWK"));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(Redactor::new(&[rule("rationale", "(")]).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! Release notes and version suggestions from manifests
+//!
+//! Aggregates the manifests of a release range into a changelog grouped by
+//! behaviorClass, a list of breaking changes, and the upgrade guide, and
+//! derives the semver bump they imply.
+
+use crate::manifest::{Manifest, BEHAVIOR_FEATURE};
+use crate::upgrade::{self, UpgradeGroup};
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+
+/// Semver component to increment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl std::fmt::Display for Bump {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Bump::Patch => write!(f, "patch"),
+            Bump::Minor => write!(f, "minor"),
+            Bump::Major => write!(f, "major"),
+        }
+    }
+}
+
+/// A breaking change in the release
+#[derive(Debug, Clone, PartialEq)]
+pub struct BreakingChange {
+    pub commit: String,
+    pub file: String,
+    pub symbol: String,
+    pub rationale: String,
+}
+
+/// Everything published for one release
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReleaseNotes {
+    pub version: String,
+    pub previous: Option<String>,
+    /// One line per commit, keyed by behaviorClass
+    pub changelog: BTreeMap<String, Vec<String>>,
+    pub breaking: Vec<BreakingChange>,
+    pub upgrade: Vec<UpgradeGroup>,
+}
+
+fn is_breaking(manifest: &Manifest) -> bool {
+    manifest
+        .entries
+        .iter()
+        .any(|e| e.compatibility.as_ref().is_some_and(|c| c.breaking))
+}
+
+fn classes(manifest: &Manifest) -> Vec<&str> {
+    match manifest.global_intent {
+        Some(ref gi) => gi.behavior_class.iter().map(String::as_str).collect(),
+        None => manifest
+            .entries
+            .iter()
+            .flat_map(|e| e.behavior_class.iter().map(String::as_str))
+            .collect(),
+    }
+}
+
+/// Suggest the semver bump for a set of manifests
+pub fn suggest_bump(manifests: &[Manifest]) -> Bump {
+    if manifests.iter().any(is_breaking) {
+        Bump::Major
+    } else if manifests
+        .iter()
+        .any(|m| classes(m).contains(&BEHAVIOR_FEATURE))
+    {
+        Bump::Minor
+    } else {
+        Bump::Patch
+    }
+}
+
+/// Apply a bump to a `[v]MAJOR.MINOR.PATCH` version
+///
+/// Before 1.0.0 a breaking change bumps the minor version. Without a previous
+/// version the first release is `v0.1.0`.
+pub fn next_version(previous: Option<&str>, bump: Bump) -> Result<String> {
+    let Some(previous) = previous else {
+        return Ok("v0.1.0".to_string());
+    };
+
+    let (prefix, number) = match previous.strip_prefix('v') {
+        Some(rest) => ("v", rest),
+        None => ("", previous),
+    };
+    let core = number.split(['-', '+']).next().unwrap_or(number);
+    let parts: Vec<u64> = core
+        .split('.')
+        .map(|p| p.parse())
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("'{}' is not a semantic version", previous))?;
+    let [major, minor, patch] = parts[..] else {
+        anyhow::bail!("'{}' is not a semantic version", previous);
+    };
+
+    let (major, minor, patch) = match bump {
+        Bump::Major if major == 0 => (0, minor + 1, 0),
+        Bump::Major => (major + 1, 0, 0),
+        Bump::Minor => (major, minor + 1, 0),
+        Bump::Patch => (major, minor, patch + 1),
+    };
+    Ok(format!("{}{}.{}.{}", prefix, major, minor, patch))
+}
+
+impl ReleaseNotes {
+    /// Build release notes from manifests ordered oldest first
+    pub fn build(
+        version: String,
+        previous: Option<String>,
+        manifests: &[Manifest],
+        packages: &crate::packages::PackageMap,
+    ) -> Self {
+        let mut changelog: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut breaking = Vec::new();
+
+        for manifest in manifests {
+            let class = classes(manifest)
+                .first()
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| "other".to_string());
+            let summary = manifest
+                .global_intent
+                .as_ref()
+                .map(|gi| gi.rationale.as_str())
+                .or_else(|| manifest.entries.first().map(|e| e.rationale.as_str()))
+                .unwrap_or_default();
+            let short = &manifest.commit[..manifest.commit.len().min(8)];
+            changelog.entry(class).or_default().push(format!(
+                "{} ({})",
+                summary.lines().next().unwrap_or_default(),
+                short
+            ));
+
+            for entry in &manifest.entries {
+                if entry.compatibility.as_ref().is_some_and(|c| c.breaking) {
+                    breaking.push(BreakingChange {
+                        commit: manifest.commit.clone(),
+                        file: entry.anchor.file.clone(),
+                        symbol: entry.anchor.symbol.clone(),
+                        rationale: entry.rationale.clone(),
+                    });
+                }
+            }
+        }
+
+        ReleaseNotes {
+            version,
+            previous,
+            changelog,
+            breaking,
+            upgrade: upgrade::collect(manifests, packages),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = match self.previous {
+            Some(ref previous) => format!("# {} (since {})\n", self.version, previous),
+            None => format!("# {}\n", self.version),
+        };
+
+        out.push_str("\n## Changes\n");
+        if self.changelog.is_empty() {
+            out.push_str("\nNo changes with recorded context.\n");
+        }
+        for (class, lines) in &self.changelog {
+            out.push_str(&format!("\n### {}\n\n", class));
+            for line in lines {
+                out.push_str(&format!("- {}\n", line));
+            }
+        }
+
+        if !self.breaking.is_empty() {
+            out.push_str("\n## Breaking changes\n\n");
+            for change in &self.breaking {
+                out.push_str(&format!(
+                    "- `{}` ({}): {}\n",
+                    change.symbol,
+                    change.file,
+                    change.rationale.lines().next().unwrap_or_default()
+                ));
+            }
+        }
+
+        if !self.upgrade.is_empty() {
+            let guide = upgrade::to_markdown("Upgrade guide", &self.upgrade);
+            out.push('\n');
+            // Nest the guide one level below the release title
+            for line in guide.lines() {
+                if line.starts_with('#') {
+                    out.push('#');
+                }
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use crate::packages::PackageMap;
+    use serde_json::json;
+
+    fn manifest(commit: &str, class: &str, breaking: bool) -> Manifest {
+        test_support::manifest(
+            commit,
+            json!({
+                "rationale": format!("{} change", class),
+                "behaviorClass": [class],
+                "compatibility": { "breaking": breaking, "migrations": ["Call run_v2"] }
+            }),
+        )
+    }
+
+    #[test]
+    fn test_suggest_bump() {
+        assert_eq!(suggest_bump(&[manifest("a", "bugfix", false)]), Bump::Patch);
+        assert_eq!(
+            suggest_bump(&[
+                manifest("a", "bugfix", false),
+                manifest("b", "feature", false)
+            ]),
+            Bump::Minor
+        );
+        assert_eq!(suggest_bump(&[manifest("a", "bugfix", true)]), Bump::Major);
+        assert_eq!(suggest_bump(&[]), Bump::Patch);
+    }
+
+    #[test]
+    fn test_next_version() {
+        assert_eq!(next_version(Some("v1.2.3"), Bump::Patch).unwrap(), "v1.2.4");
+        assert_eq!(next_version(Some("1.2.3"), Bump::Minor).unwrap(), "1.3.0");
+        assert_eq!(next_version(Some("v1.2.3"), Bump::Major).unwrap(), "v2.0.0");
+        assert_eq!(next_version(Some("v0.4.1"), Bump::Major).unwrap(), "v0.5.0");
+        assert_eq!(next_version(None, Bump::Major).unwrap(), "v0.1.0");
+        assert!(next_version(Some("nightly"), Bump::Patch).is_err());
+    }
+
+    #[test]
+    fn test_release_notes_markdown() {
+        let notes = ReleaseNotes::build(
+            "v1.1.0".to_string(),
+            Some("v1.0.0".to_string()),
+            &[
+                manifest("aaaaaaaaa", "feature", true),
+                manifest("bbbbbbbbb", "bugfix", false),
+            ],
+            &PackageMap::default(),
+        );
+        let md = notes.to_markdown();
+
+        assert!(md.starts_with("# v1.1.0 (since v1.0.0)\n"));
+        assert!(md.contains("### feature\n\n- feature change (aaaaaaaa)\n"));
+        assert!(md.contains("## Breaking changes\n\n- `run` (src/lib.rs): feature change\n"));
+        assert!(md.contains("feature change\n\n## Upgrade guide\n"));
+        assert!(md.contains("### src/lib.rs\n"));
+    }
+}
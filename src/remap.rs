@@ -0,0 +1,200 @@
+//! Match rewritten commits to their originals
+//!
+//! After a rebase, `filter-repo` or a cherry-pick backport, notes stay on the
+//! old SHAs. A rewritten commit is matched to its original by patch-id, or by
+//! author, author date and subject when the patch itself changed, so its manifest can
+//! be copied over.
+
+use crate::git;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// What identifies a commit across rewrites
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitKey {
+    /// Stable patch-id; `None` for merges and empty commits
+    pub patch_id: Option<String>,
+    pub author: String,
+    /// Author date as a Unix timestamp
+    pub date: i64,
+    pub subject: String,
+}
+
+/// How a rewritten commit was matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    PatchId,
+    Identity,
+}
+
+impl fmt::Display for MatchKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatchKind::PatchId => write!(f, "patch-id"),
+            MatchKind::Identity => write!(f, "author/date/subject"),
+        }
+    }
+}
+
+/// A manifest to copy from an old commit to its rewritten counterpart
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remap {
+    pub from: String,
+    pub to: String,
+    pub by: MatchKind,
+}
+
+/// Collect keys for the commits selected by `git log` arguments, in log order
+pub fn collect_keys(log_args: &[&str], cwd: Option<&Path>) -> Result<Vec<(String, CommitKey)>> {
+    let mut args = vec!["log", "--format=%H%x00%ae%x00%at%x00%s"];
+    args.extend_from_slice(log_args);
+    let output = git::run_git_cmd(&args, cwd)?;
+
+    let patch_ids: HashMap<String, String> = git::patch_ids(log_args, cwd)?.into_iter().collect();
+
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\0');
+            let sha = parts.next()?.trim().to_string();
+            let author = parts.next()?.to_string();
+            let date = parts.next()?.parse().ok()?;
+            let subject = parts.next().unwrap_or_default().to_string();
+            let key = CommitKey {
+                patch_id: patch_ids.get(&sha).cloned(),
+                author,
+                date,
+                subject,
+            };
+            Some((sha, key))
+        })
+        .collect())
+}
+
+/// Pair each candidate commit with the noted commit it was rewritten from
+///
+/// A patch-id match wins; among several, the one with the same author, date
+/// and subject is preferred. That identity alone is only trusted when unique.
+pub fn match_commits(
+    noted: &[(String, CommitKey)],
+    candidates: &[(String, CommitKey)],
+) -> Vec<Remap> {
+    let mut remaps = Vec::new();
+
+    for (to, key) in candidates {
+        let others: Vec<&(String, CommitKey)> = noted.iter().filter(|(sha, _)| sha != to).collect();
+        let same_identity = |k: &CommitKey| {
+            k.author == key.author && k.date == key.date && k.subject == key.subject
+        };
+
+        let by_patch: Vec<_> = match key.patch_id {
+            Some(ref id) => others
+                .iter()
+                .filter(|(_, k)| k.patch_id.as_ref() == Some(id))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let found = if !by_patch.is_empty() {
+            let best = by_patch
+                .iter()
+                .find(|(_, k)| same_identity(k))
+                .unwrap_or(&by_patch[0]);
+            Some((best.0.clone(), MatchKind::PatchId))
+        } else {
+            let by_identity: Vec<_> = others.iter().filter(|(_, k)| same_identity(k)).collect();
+            match by_identity.as_slice() {
+                [only] => Some((only.0.clone(), MatchKind::Identity)),
+                _ => None,
+            }
+        };
+
+        if let Some((from, by)) = found {
+            remaps.push(Remap {
+                from,
+                to: to.clone(),
+                by,
+            });
+        }
+    }
+
+    remaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(patch_id: Option<&str>, author: &str, date: i64) -> CommitKey {
+        CommitKey {
+            patch_id: patch_id.map(str::to_string),
+            author: author.to_string(),
+            date,
+            subject: format!("change by {}", author),
+        }
+    }
+
+    #[test]
+    fn test_match_by_patch_id() {
+        let noted = vec![
+            ("old1".to_string(), key(Some("p1"), "a@x", 100)),
+            ("old2".to_string(), key(Some("p2"), "b@x", 200)),
+        ];
+        let candidates = vec![
+            ("new2".to_string(), key(Some("p2"), "b@x", 250)),
+            ("new3".to_string(), key(Some("p3"), "c@x", 300)),
+        ];
+
+        assert_eq!(
+            match_commits(&noted, &candidates),
+            vec![Remap {
+                from: "old2".to_string(),
+                to: "new2".to_string(),
+                by: MatchKind::PatchId,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_patch_id_prefers_same_identity() {
+        let noted = vec![
+            ("revert".to_string(), key(Some("p1"), "b@x", 500)),
+            ("orig".to_string(), key(Some("p1"), "a@x", 100)),
+        ];
+        let candidates = vec![("new".to_string(), key(Some("p1"), "a@x", 100))];
+
+        assert_eq!(match_commits(&noted, &candidates)[0].from, "orig");
+    }
+
+    #[test]
+    fn test_match_by_identity_when_unique() {
+        let noted = vec![
+            ("old1".to_string(), key(Some("p1"), "a@x", 100)),
+            ("old2".to_string(), key(Some("p2"), "a@x", 100)),
+            ("old3".to_string(), key(Some("p3"), "b@x", 300)),
+        ];
+        let candidates = vec![
+            // Content changed by filter-repo, identity kept
+            ("new3".to_string(), key(Some("p9"), "b@x", 300)),
+            // Ambiguous: two noted commits share this identity
+            ("new1".to_string(), key(Some("p8"), "a@x", 100)),
+        ];
+
+        assert_eq!(
+            match_commits(&noted, &candidates),
+            vec![Remap {
+                from: "old3".to_string(),
+                to: "new3".to_string(),
+                by: MatchKind::Identity,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_commit_never_matches_itself() {
+        let noted = vec![("same".to_string(), key(Some("p1"), "a@x", 100))];
+        assert!(match_commits(&noted, &noted).is_empty());
+    }
+}
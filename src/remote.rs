@@ -0,0 +1,376 @@
+//! Parsing of `git push`/`git fetch`/`git pull` arguments
+//!
+//! `gip push`, `gip fetch` and `gip pull` forward their arguments to git but
+//! also have to move notes to or from the same remote, so they need to know
+//! which positional argument is the remote and which are refspecs. Without an
+//! explicit remote, the one git itself would pick is used.
+
+use crate::git;
+use anyhow::Result;
+use std::path::Path;
+
+/// `git push` options that take a separate value
+const PUSH_VALUE_OPTIONS: &[&str] = &["-o", "--push-option", "--repo", "--receive-pack", "--exec"];
+
+/// `git fetch`/`git pull` options that take a separate value
+const FETCH_VALUE_OPTIONS: &[&str] = &[
+    "-o",
+    "--server-option",
+    "--depth",
+    "--deepen",
+    "--shallow-since",
+    "--shallow-exclude",
+    "--upload-pack",
+    "--negotiation-tip",
+    "-j",
+    "--jobs",
+    "-s",
+    "--strategy",
+    "-X",
+    "--strategy-option",
+];
+
+/// Remote command arguments split into options, remote and refspecs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoteArgs {
+    pub options: Vec<String>,
+    pub remote: Option<String>,
+    pub refspecs: Vec<String>,
+}
+
+impl RemoteArgs {
+    /// Split `git push` arguments
+    pub fn parse_push(args: &[String]) -> Self {
+        Self::parse(args, PUSH_VALUE_OPTIONS)
+    }
+
+    /// Split `git fetch` or `git pull` arguments
+    pub fn parse_fetch(args: &[String]) -> Self {
+        Self::parse(args, FETCH_VALUE_OPTIONS)
+    }
+
+    fn parse(args: &[String], value_options: &[&str]) -> Self {
+        let mut parsed = RemoteArgs::default();
+        let mut iter = args.iter();
+        let mut options_done = false;
+
+        while let Some(arg) = iter.next() {
+            if !options_done && arg == "--" {
+                options_done = true;
+            } else if !options_done && arg.starts_with('-') {
+                parsed.options.push(arg.clone());
+                if value_options.contains(&arg.as_str()) {
+                    if let Some(value) = iter.next() {
+                        parsed.options.push(value.clone());
+                    }
+                }
+            } else if parsed.remote.is_none() {
+                parsed.remote = Some(arg.clone());
+            } else {
+                parsed.refspecs.push(arg.clone());
+            }
+        }
+
+        parsed
+    }
+
+    /// Whether the options select refs themselves, which rules out extra refspecs
+    pub fn selects_refs(&self) -> bool {
+        self.options.iter().any(|o| {
+            matches!(
+                o.as_str(),
+                "--all" | "--branches" | "--mirror" | "--delete" | "-d"
+            )
+        })
+    }
+
+    /// Whether the options force the push: `-f`, `--force` or `--force-with-lease`
+    ///
+    /// git applies a force to every refspec of the push, extra ones included.
+    pub fn forces(&self) -> bool {
+        let mut options = self.options.iter();
+        while let Some(option) = options.next() {
+            if PUSH_VALUE_OPTIONS.contains(&option.as_str()) {
+                options.next();
+                continue;
+            }
+            let forced = match option.strip_prefix("--") {
+                Some(long) => {
+                    long == "force"
+                        || long == "force-with-lease"
+                        || long.starts_with("force-with-lease=")
+                }
+                // Short options can be bundled, up to an `-o` taking the rest as its value
+                None => option[1..]
+                    .chars()
+                    .take_while(|&c| c != 'o')
+                    .any(|c| c == 'f'),
+            };
+            if forced {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Whether the options ask for every remote (`fetch --all`)
+    pub fn all_remotes(&self) -> bool {
+        self.options.iter().any(|o| o == "--all")
+    }
+
+    /// Whether the options choose between rebasing and merging, and which
+    ///
+    /// `-r`, `--rebase` and `--rebase=<mode>` rebase unless the mode is false;
+    /// `--no-rebase` merges. The last one given wins, as in git.
+    pub fn rebase_option(&self) -> Option<bool> {
+        self.options
+            .iter()
+            .filter_map(|o| match o.as_str() {
+                "-r" | "--rebase" => Some(true),
+                "--no-rebase" => Some(false),
+                _ => o.strip_prefix("--rebase=").map(rebases),
+            })
+            .next_back()
+    }
+
+    /// Remote named on the command line, or `default` if none was
+    pub fn remote_or(&self, default: &str) -> String {
+        self.remote.clone().unwrap_or_else(|| default.to_string())
+    }
+
+    /// Arguments for one `git push` that also pushes `extra` refspecs
+    ///
+    /// Naming any refspec disables git's default push behaviour, so without
+    /// explicit refspecs the current branch is pushed to the branch a plain
+    /// push would update (see [`push_destination`]). `None` when there is no
+    /// single such branch.
+    pub fn with_refspecs(&self, extra: &[&str], cwd: Option<&Path>) -> Option<Vec<String>> {
+        let remote = self.remote_or("origin");
+        let mut args = vec!["push".to_string()];
+        args.extend(self.options.iter().cloned());
+        if self.refspecs.is_empty() {
+            let destination = push_destination(&remote, cwd)?;
+            args.push(remote);
+            args.push(format!("HEAD:{}", destination));
+        } else {
+            args.push(remote);
+            args.extend(self.refspecs.iter().cloned());
+        }
+        args.extend(extra.iter().map(|s| s.to_string()));
+        Some(args)
+    }
+}
+
+/// Branch of `remote` a plain `git push` of the current branch would update
+///
+/// Read from `@{push}`, so `push.default` and the upstream are honoured.
+/// `None` when git can't name one branch on `remote`: a branch that was never
+/// pushed, an upstream under another name with `push.default = simple`, or
+/// `push.default = matching`, which pushes more than the current branch.
+pub fn push_destination(remote: &str, cwd: Option<&Path>) -> Option<String> {
+    if first_config(&["push.default".to_string()], cwd).as_deref() == Some("matching") {
+        return None;
+    }
+    let tracking = git::run_git_cmd(&["rev-parse", "--symbolic-full-name", "@{push}"], cwd).ok()?;
+    let branch = tracking.strip_prefix(&format!("refs/remotes/{}/", remote))?;
+    Some(format!("refs/heads/{}", branch))
+}
+
+/// Remote a plain `git push` would use
+///
+/// Follows git's order: `branch.<name>.pushRemote`, `remote.pushDefault`,
+/// `branch.<name>.remote`, then `origin`. This keeps triangular workflows
+/// (fetch from upstream, push to a fork) pushing notes next to the code.
+pub fn default_push_remote(cwd: Option<&Path>) -> String {
+    let branch = git::current_branch(cwd);
+    let mut keys = Vec::new();
+    if let Some(b) = &branch {
+        keys.push(format!("branch.{}.pushRemote", b));
+    }
+    keys.push("remote.pushDefault".to_string());
+    if let Some(b) = &branch {
+        keys.push(format!("branch.{}.remote", b));
+    }
+    first_config(&keys, cwd).unwrap_or_else(|| "origin".to_string())
+}
+
+/// Remote a plain `git fetch` or `git pull` would use
+pub fn default_fetch_remote(cwd: Option<&Path>) -> String {
+    git::current_branch(cwd)
+        .and_then(|b| first_config(&[format!("branch.{}.remote", b)], cwd))
+        .unwrap_or_else(|| "origin".to_string())
+}
+
+/// Whether a `git pull` with `args` rebases rather than merges
+///
+/// Follows git's order: the command line, `branch.<name>.rebase`, then
+/// `pull.rebase`.
+pub fn pull_rebases(args: &RemoteArgs, cwd: Option<&Path>) -> bool {
+    if let Some(rebase) = args.rebase_option() {
+        return rebase;
+    }
+    let mut keys = Vec::new();
+    if let Some(b) = git::current_branch(cwd) {
+        keys.push(format!("branch.{}.rebase", b));
+    }
+    keys.push("pull.rebase".to_string());
+    first_config(&keys, cwd).is_some_and(|v| rebases(&v))
+}
+
+/// A `pull.rebase` value: `true`, `merges` and `interactive` all rebase
+fn rebases(value: &str) -> bool {
+    !matches!(
+        value.to_ascii_lowercase().as_str(),
+        "false" | "no" | "off" | "0"
+    )
+}
+
+/// Names of all configured remotes
+pub fn list_remotes(cwd: Option<&Path>) -> Result<Vec<String>> {
+    Ok(git::run_git_cmd(&["remote"], cwd)?
+        .lines()
+        .map(|l| l.to_string())
+        .collect())
+}
+
+/// Whether `remote` has a gip notes ref
+pub fn has_remote_notes(remote: &str, cwd: Option<&Path>) -> Result<bool> {
+    let out = git::run_git_cmd(&["ls-remote", remote, &git::notes_ref_full()], cwd)?;
+    Ok(!out.is_empty())
+}
+
+fn first_config(keys: &[String], cwd: Option<&Path>) -> Option<String> {
+    keys.iter()
+        .find_map(|k| git::run_git_cmd(&["config", "--get", k], cwd).ok())
+        .filter(|v| !v.is_empty() && v != ".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::init_repo;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_push_args() {
+        let parsed =
+            RemoteArgs::parse_push(&args(&["-u", "-o", "ci.skip", "upstream", "feature:main"]));
+        assert_eq!(parsed.options, args(&["-u", "-o", "ci.skip"]));
+        assert_eq!(parsed.remote.as_deref(), Some("upstream"));
+        assert_eq!(parsed.refspecs, args(&["feature:main"]));
+
+        assert!(RemoteArgs::parse_push(&args(&["--all", "origin"])).selects_refs());
+
+        let parsed = RemoteArgs::parse_push(&[]);
+        assert_eq!(parsed.remote_or("origin"), "origin");
+        assert!(parsed.refspecs.is_empty());
+    }
+
+    #[test]
+    fn test_forces() {
+        let forces = |list: &[&str]| RemoteArgs::parse_push(&args(list)).forces();
+        assert!(forces(&["-f"]));
+        assert!(forces(&["--force", "origin", "main"]));
+        assert!(forces(&["--force-with-lease=main:abc123"]));
+        assert!(forces(&["-uf", "origin"]));
+        assert!(!forces(&["-u", "origin", "+main"]));
+        assert!(!forces(&["-o", "-f"]));
+        assert!(!forces(&["-ofoo"]));
+        assert!(!forces(&["--follow-tags"]));
+    }
+
+    #[test]
+    fn test_rebase_option() {
+        let option = |list: &[&str]| RemoteArgs::parse_fetch(&args(list)).rebase_option();
+        assert_eq!(option(&["origin", "main"]), None);
+        assert_eq!(option(&["-r"]), Some(true));
+        assert_eq!(option(&["--rebase=merges", "origin"]), Some(true));
+        assert_eq!(option(&["--rebase=false"]), Some(false));
+        assert_eq!(option(&["--rebase", "--no-rebase"]), Some(false));
+        assert_eq!(option(&["--no-rebase", "--rebase=i"]), Some(true));
+    }
+
+    #[test]
+    fn test_with_refspecs() {
+        assert_eq!(
+            RemoteArgs::parse_push(&args(&["-u", "origin", "main"]))
+                .with_refspecs(&["refs/notes/gip"], None),
+            Some(args(&["push", "-u", "origin", "main", "refs/notes/gip"]))
+        );
+        assert_eq!(
+            RemoteArgs::parse_push(&args(&["fork", "a", "b"])).with_refspecs(&[], None),
+            Some(args(&["push", "fork", "a", "b"]))
+        );
+    }
+
+    #[test]
+    fn test_push_destination() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Some(temp_dir.path());
+        init_repo(temp_dir.path());
+        git::run_git_cmd(&["commit", "-q", "--allow-empty", "-m", "init"], dir).unwrap();
+        git::run_git_cmd(&["remote", "add", "origin", "."], dir).unwrap();
+        git::run_git_cmd(&["update-ref", "refs/remotes/origin/main", "HEAD"], dir).unwrap();
+        git::run_git_cmd(&["branch", "-q", "-u", "origin/main"], dir).unwrap();
+        assert_eq!(
+            push_destination("origin", dir).as_deref(),
+            Some("refs/heads/main")
+        );
+        assert_eq!(push_destination("fork", dir), None);
+
+        // A branch tracking another name pushes there only with push.default = upstream
+        git::run_git_cmd(
+            &["checkout", "-q", "-b", "topic", "--track", "origin/main"],
+            dir,
+        )
+        .unwrap();
+        assert_eq!(push_destination("origin", dir), None);
+        git::run_git_cmd(&["config", "push.default", "upstream"], dir).unwrap();
+        assert_eq!(
+            push_destination("origin", dir).as_deref(),
+            Some("refs/heads/main")
+        );
+        let push = RemoteArgs::parse_push(&[]);
+        assert_eq!(
+            push.with_refspecs(&["refs/notes/gip"], dir),
+            Some(args(&[
+                "push",
+                "origin",
+                "HEAD:refs/heads/main",
+                "refs/notes/gip"
+            ]))
+        );
+
+        git::run_git_cmd(&["config", "push.default", "matching"], dir).unwrap();
+        assert_eq!(push_destination("origin", dir), None);
+        assert_eq!(push.with_refspecs(&["refs/notes/gip"], dir), None);
+    }
+
+    #[test]
+    fn test_parse_fetch_args() {
+        let parsed = RemoteArgs::parse_fetch(&args(&["--depth", "1", "upstream", "main"]));
+        assert_eq!(parsed.options, args(&["--depth", "1"]));
+        assert_eq!(parsed.remote.as_deref(), Some("upstream"));
+        assert_eq!(parsed.refspecs, args(&["main"]));
+        assert!(RemoteArgs::parse_fetch(&args(&["--all"])).all_remotes());
+    }
+
+    #[test]
+    fn test_default_remotes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = Some(temp_dir.path());
+        git::run_git_cmd(&["init", "-q", "-b", "main"], dir).unwrap();
+        assert_eq!(default_push_remote(dir), "origin");
+
+        git::run_git_cmd(&["config", "branch.main.remote", "upstream"], dir).unwrap();
+        assert_eq!(default_fetch_remote(dir), "upstream");
+        assert_eq!(default_push_remote(dir), "upstream");
+
+        git::run_git_cmd(&["config", "remote.pushDefault", "fork"], dir).unwrap();
+        assert_eq!(default_push_remote(dir), "fork");
+        assert_eq!(default_fetch_remote(dir), "upstream");
+    }
+}
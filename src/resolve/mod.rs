@@ -0,0 +1,305 @@
+//! Interactive conflict resolution
+//!
+//! A conflicted file is split into plain text and conflict hunks. Each hunk
+//! can be resolved by taking our side, their side, both, or hand-edited lines;
+//! the file is then written with the resolved hunks in place and the others
+//! left as git conflict markers. Gip context lines are dropped, since
+//! [`merge::conflict_context`] recomputes them for display.
+//!
+//! The terminal interface of `gip resolve --tui` lives in [`tui`].
+
+pub mod tui;
+
+use crate::atomic;
+use crate::audit::{self, AuditAction};
+use crate::git;
+use crate::merge;
+use anyhow::{Context, Result};
+use std::fs;
+
+const CONFLICT_START: &str = "<<<<<<<";
+const CONFLICT_BASE: &str = "|||||||";
+const CONFLICT_MIDDLE: &str = "=======";
+const CONFLICT_END: &str = ">>>>>>>";
+
+/// How a hunk was resolved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Choice {
+    Ours,
+    Theirs,
+    /// Our lines followed by theirs
+    Both,
+    /// Lines typed in by hand
+    Edited(Vec<String>),
+}
+
+impl Choice {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Choice::Ours => "ours",
+            Choice::Theirs => "theirs",
+            Choice::Both => "both",
+            Choice::Edited(_) => "edited",
+        }
+    }
+}
+
+/// One conflict region of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hunk {
+    /// 1-based line of the `<<<<<<<` marker, context lines not counted
+    pub line: usize,
+    pub ours_label: String,
+    pub theirs_label: String,
+    pub ours: Vec<String>,
+    /// Ancestor lines, present with `merge.conflictStyle = diff3`
+    pub base: Option<Vec<String>>,
+    pub theirs: Vec<String>,
+    /// Manifest context for the hunk, one line each
+    pub context: Vec<String>,
+    pub choice: Option<Choice>,
+    /// The region as git wrote it, markers included
+    markers: Vec<String>,
+}
+
+impl Hunk {
+    /// The lines a choice puts in place of the hunk
+    pub fn lines_for(&self, choice: &Choice) -> Vec<String> {
+        match choice {
+            Choice::Ours => self.ours.clone(),
+            Choice::Theirs => self.theirs.clone(),
+            Choice::Both => self.ours.iter().chain(&self.theirs).cloned().collect(),
+            Choice::Edited(lines) => lines.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    /// Index into [`ConflictFile::hunks`]
+    Conflict(usize),
+}
+
+/// A conflicted file and the resolutions chosen so far
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConflictFile {
+    pub path: String,
+    pub hunks: Vec<Hunk>,
+    segments: Vec<Segment>,
+    line_ending: &'static str,
+}
+
+impl ConflictFile {
+    /// Split `content` into text and conflict hunks, skipping Gip context lines
+    pub fn parse(path: &str, content: &str) -> Self {
+        let line_ending = if content.contains("\r\n") {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let lines: Vec<&str> = content
+            .lines()
+            .filter(|l| !merge::is_context_line(l))
+            .collect();
+
+        let mut file = ConflictFile {
+            path: path.to_string(),
+            hunks: Vec::new(),
+            segments: Vec::new(),
+            line_ending,
+        };
+        let mut i = 0;
+        while i < lines.len() {
+            if !lines[i].starts_with(CONFLICT_START) {
+                file.segments.push(Segment::Text(lines[i].to_string()));
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            let mut hunk = Hunk {
+                line: start + 1,
+                ours_label: marker_label(lines[i], CONFLICT_START),
+                theirs_label: String::new(),
+                ours: Vec::new(),
+                base: None,
+                theirs: Vec::new(),
+                context: Vec::new(),
+                choice: None,
+                markers: Vec::new(),
+            };
+            i += 1;
+            let mut section = 0;
+            while i < lines.len() && !lines[i].starts_with(CONFLICT_END) {
+                let line = lines[i];
+                if line.starts_with(CONFLICT_BASE) {
+                    hunk.base = Some(Vec::new());
+                    section = 1;
+                } else if line.starts_with(CONFLICT_MIDDLE) {
+                    section = 2;
+                } else {
+                    let target = match section {
+                        0 => &mut hunk.ours,
+                        1 => hunk.base.get_or_insert_with(Vec::new),
+                        _ => &mut hunk.theirs,
+                    };
+                    target.push(line.to_string());
+                }
+                i += 1;
+            }
+            if let Some(end) = lines.get(i) {
+                hunk.theirs_label = marker_label(end, CONFLICT_END);
+            }
+            i = (i + 1).min(lines.len());
+            hunk.markers = lines[start..i].iter().map(|l| l.to_string()).collect();
+
+            file.segments.push(Segment::Conflict(file.hunks.len()));
+            file.hunks.push(hunk);
+        }
+        file
+    }
+
+    /// Whether every hunk has a resolution
+    pub fn is_resolved(&self) -> bool {
+        self.hunks.iter().all(|h| h.choice.is_some())
+    }
+
+    /// Number of hunks with a resolution
+    pub fn decided(&self) -> usize {
+        self.hunks.iter().filter(|h| h.choice.is_some()).count()
+    }
+
+    /// The file with resolved hunks replaced and the others left as markers
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            let lines = match segment {
+                Segment::Text(line) => vec![line.clone()],
+                Segment::Conflict(idx) => {
+                    let hunk = &self.hunks[*idx];
+                    match hunk.choice {
+                        Some(ref choice) => hunk.lines_for(choice),
+                        None => hunk.markers.clone(),
+                    }
+                }
+            };
+            for line in lines {
+                out.push_str(&line);
+                out.push_str(self.line_ending);
+            }
+        }
+        out
+    }
+}
+
+fn marker_label(line: &str, marker: &str) -> String {
+    line.trim_start_matches(marker).trim().to_string()
+}
+
+/// Read a conflicted file and attach each hunk's manifest context
+///
+/// Context is best effort: a file still resolves without manifests.
+pub fn load(path: &str, ours_sha: &str, theirs_sha: Option<&str>) -> Result<ConflictFile> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?;
+    let mut file = ConflictFile::parse(path, &content);
+
+    let plain = merge::strip_context(&content).0;
+    if let Ok(contexts) = merge::conflict_context(&plain, path, ours_sha, theirs_sha) {
+        for (hunk, context) in file.hunks.iter_mut().zip(contexts) {
+            hunk.context = context;
+        }
+    }
+    Ok(file)
+}
+
+/// Write the chosen resolutions and record them in the audit log
+///
+/// A fully resolved file is staged, which marks it resolved for git. Returns
+/// whether that happened.
+pub fn save(file: &ConflictFile) -> Result<bool> {
+    if file.decided() == 0 {
+        return Ok(false);
+    }
+
+    atomic::write(std::path::Path::new(&file.path), file.render())
+        .with_context(|| format!("Failed to write {}", file.path))?;
+
+    let decisions: Vec<String> = file
+        .hunks
+        .iter()
+        .filter_map(|h| {
+            h.choice
+                .as_ref()
+                .map(|c| format!("line {}: {}", h.line, c.as_str()))
+        })
+        .collect();
+    audit::record(
+        AuditAction::ManualResolution,
+        None,
+        Some(format!("{}: {}", file.path, decisions.join(", "))),
+    );
+
+    if !file.is_resolved() {
+        return Ok(false);
+    }
+    git::run_git_cmd(&["add", "--", &file.path], None)
+        .with_context(|| format!("Failed to stage {}", file.path))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "\
+fn charge() {
+<<<<<<< HEAD
+||| Gip CONTEXT (HEAD - Your changes)
+||| rationale: retry
+    retry();
+||||||| base
+    call();
+=======
+    timeout();
+>>>>>>> feat
+}
+<<<<<<< HEAD
+a
+=======
+b
+>>>>>>> feat
+";
+
+    #[test]
+    fn test_parse_skips_context_lines() {
+        let file = ConflictFile::parse("src/pay.rs", CONTENT);
+        assert_eq!(file.hunks.len(), 2);
+
+        let hunk = &file.hunks[0];
+        assert_eq!(hunk.line, 2);
+        assert_eq!(
+            (hunk.ours_label.as_str(), hunk.theirs_label.as_str()),
+            ("HEAD", "feat")
+        );
+        assert_eq!(hunk.ours, vec!["    retry();"]);
+        assert_eq!(hunk.base, Some(vec!["    call();".to_string()]));
+        assert_eq!(hunk.theirs, vec!["    timeout();"]);
+        assert_eq!(file.render(), merge::strip_context(CONTENT).0);
+    }
+
+    #[test]
+    fn test_render_applies_choices() {
+        let mut file = ConflictFile::parse("src/pay.rs", &CONTENT.replace('\n', "\r\n"));
+        file.hunks[0].choice = Some(Choice::Both);
+        assert!(!file.is_resolved());
+
+        let rendered = file.render();
+        assert!(rendered.starts_with("fn charge() {\r\n    retry();\r\n    timeout();\r\n}\r\n"));
+        assert!(rendered.contains("<<<<<<< HEAD\r\na\r\n"));
+
+        file.hunks[1].choice = Some(Choice::Edited(vec!["ab".to_string()]));
+        assert!(file.is_resolved());
+        assert!(file.render().ends_with("}\r\nab\r\n"));
+    }
+}
@@ -0,0 +1,281 @@
+//! Terminal interface for `gip resolve --tui`
+//!
+//! The left pane lists the conflicted files and their hunks. The right pane
+//! shows both sides of the selected hunk, its resolution so far, and the
+//! manifest context of both commits. Nothing is written until the user quits
+//! with `q`; Ctrl-C throws every choice away.
+
+use super::{Choice, ConflictFile};
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use tui_textarea::TextArea;
+
+const HELP: &str =
+    "o ours  t theirs  b both  e edit  u undo  ↑↓ hunk  ←→ file  q write & quit  Ctrl-C discard";
+const EDIT_HELP: &str = "Editing: Esc keeps the text, Ctrl-C cancels";
+
+enum Mode {
+    Browse,
+    Edit(Box<TextArea<'static>>),
+}
+
+struct App {
+    files: Vec<ConflictFile>,
+    file: usize,
+    hunk: usize,
+    mode: Mode,
+}
+
+/// What the user asked for when leaving the interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// Write the chosen resolutions
+    Write,
+    /// Leave every file as it was
+    Discard,
+}
+
+/// Run the interface until the user quits
+///
+/// Returns the files with the choices made and whether to write them.
+pub fn run(files: Vec<ConflictFile>) -> Result<(Vec<ConflictFile>, Exit)> {
+    let mut app = App {
+        files,
+        file: 0,
+        hunk: 0,
+        mode: Mode::Browse,
+    };
+    let mut terminal = ratatui::init();
+    let exit = app.event_loop(&mut terminal);
+    ratatui::restore();
+    Ok((app.files, exit?))
+}
+
+impl App {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<Exit> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some(exit) = self.handle_key(key) {
+                return Ok(exit);
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Exit> {
+        let ctrl_c =
+            key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+
+        if let Mode::Edit(ref mut textarea) = self.mode {
+            if ctrl_c {
+                self.mode = Mode::Browse;
+            } else if key.code == KeyCode::Esc {
+                let lines = textarea.lines().to_vec();
+                self.mode = Mode::Browse;
+                self.choose(Choice::Edited(lines));
+            } else {
+                textarea.input(key);
+            }
+            return None;
+        }
+
+        if ctrl_c {
+            return Some(Exit::Discard);
+        }
+        match key.code {
+            KeyCode::Char('q') => return Some(Exit::Write),
+            KeyCode::Char('o') => self.choose(Choice::Ours),
+            KeyCode::Char('t') => self.choose(Choice::Theirs),
+            KeyCode::Char('b') => self.choose(Choice::Both),
+            KeyCode::Char('u') => self.files[self.file].hunks[self.hunk].choice = None,
+            KeyCode::Char('e') => {
+                let hunk = &self.files[self.file].hunks[self.hunk];
+                let start = hunk.choice.clone().unwrap_or(Choice::Both);
+                let mut textarea = TextArea::new(hunk.lines_for(&start));
+                textarea.set_block(Block::bordered().title("Resolution (editing)"));
+                self.mode = Mode::Edit(Box::new(textarea));
+            }
+            KeyCode::Down | KeyCode::Char('j')
+                if self.hunk + 1 < self.files[self.file].hunks.len() =>
+            {
+                self.hunk += 1
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.hunk = self.hunk.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab
+                if self.file + 1 < self.files.len() =>
+            {
+                self.file += 1;
+                self.hunk = 0;
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab if self.file > 0 => {
+                self.file -= 1;
+                self.hunk = 0;
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Resolve the selected hunk and move on to the next undecided one
+    fn choose(&mut self, choice: Choice) {
+        let file = &mut self.files[self.file];
+        file.hunks[self.hunk].choice = Some(choice);
+        if let Some(next) = file.hunks.iter().position(|h| h.choice.is_none()) {
+            self.hunk = next;
+        } else if let Some(next) = self.files.iter().position(|f| !f.is_resolved()) {
+            self.file = next;
+            self.hunk = self.files[next]
+                .hunks
+                .iter()
+                .position(|h| h.choice.is_none())
+                .unwrap_or(0);
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, help] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [list, detail] =
+            Layout::horizontal([Constraint::Percentage(25), Constraint::Min(0)]).areas(main);
+        let [sides, resolution, context] = Layout::vertical([
+            Constraint::Percentage(45),
+            Constraint::Percentage(25),
+            Constraint::Min(0),
+        ])
+        .areas(detail);
+
+        self.draw_list(frame, list);
+        self.draw_sides(frame, sides);
+        self.draw_resolution(frame, resolution);
+        self.draw_context(frame, context);
+
+        let text = match self.mode {
+            Mode::Browse => HELP,
+            Mode::Edit(_) => EDIT_HELP,
+        };
+        frame.render_widget(
+            Paragraph::new(text).style(Style::default().fg(Color::DarkGray)),
+            help,
+        );
+    }
+
+    fn draw_list(&self, frame: &mut Frame, area: Rect) {
+        let mut items = Vec::new();
+        let mut selected = 0;
+        for (f, file) in self.files.iter().enumerate() {
+            let done = if file.is_resolved() { "✓" } else { " " };
+            items.push(
+                ListItem::new(format!(
+                    "{} {} ({}/{})",
+                    done,
+                    file.path,
+                    file.decided(),
+                    file.hunks.len()
+                ))
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+            );
+            for (h, hunk) in file.hunks.iter().enumerate() {
+                if f == self.file && h == self.hunk {
+                    selected = items.len();
+                }
+                let (mark, choice) = match hunk.choice {
+                    Some(ref c) => ("✓", c.as_str()),
+                    None => ("·", ""),
+                };
+                items.push(ListItem::new(format!(
+                    "    {} line {} {}",
+                    mark, hunk.line, choice
+                )));
+            }
+        }
+
+        let list = List::new(items)
+            .block(Block::bordered().title("Conflicts"))
+            .highlight_style(Style::default().bg(Color::DarkGray));
+        let mut state = ListState::default().with_selected(Some(selected));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn draw_sides(&self, frame: &mut Frame, area: Rect) {
+        let hunk = &self.files[self.file].hunks[self.hunk];
+        let [ours, theirs] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(area);
+
+        let taken = |side: Choice| match hunk.choice {
+            Some(Choice::Both) => Style::default().fg(Color::Green),
+            Some(ref choice) if *choice == side => Style::default().fg(Color::Green),
+            _ => Style::default(),
+        };
+
+        frame.render_widget(
+            pane(
+                &format!("Ours ({})", hunk.ours_label),
+                &hunk.ours,
+                taken(Choice::Ours),
+            ),
+            ours,
+        );
+        frame.render_widget(
+            pane(
+                &format!("Theirs ({})", hunk.theirs_label),
+                &hunk.theirs,
+                taken(Choice::Theirs),
+            ),
+            theirs,
+        );
+    }
+
+    fn draw_resolution(&self, frame: &mut Frame, area: Rect) {
+        if let Mode::Edit(ref textarea) = self.mode {
+            frame.render_widget(&**textarea, area);
+            return;
+        }
+
+        let hunk = &self.files[self.file].hunks[self.hunk];
+        let widget = match hunk.choice {
+            Some(ref choice) => pane(
+                &format!("Resolution ({})", choice.as_str()),
+                &hunk.lines_for(choice),
+                Style::default().fg(Color::Green),
+            ),
+            None => Paragraph::new("Unresolved").block(Block::bordered().title("Resolution")),
+        };
+        frame.render_widget(widget, area);
+    }
+
+    fn draw_context(&self, frame: &mut Frame, area: Rect) {
+        let hunk = &self.files[self.file].hunks[self.hunk];
+        let widget = if hunk.context.is_empty() {
+            Paragraph::new("No manifest covers this hunk")
+                .block(Block::bordered().title("Gip context"))
+        } else {
+            pane(
+                "Gip context",
+                &hunk.context,
+                Style::default().fg(Color::Cyan),
+            )
+        };
+        frame.render_widget(widget, area);
+    }
+}
+
+/// A bordered pane of lines
+fn pane(title: &str, lines: &[String], border: Style) -> Paragraph<'static> {
+    let text = Text::from(lines.iter().cloned().map(Line::from).collect::<Vec<_>>());
+    Paragraph::new(text).wrap(Wrap { trim: false }).block(
+        Block::bordered()
+            .title(title.to_string())
+            .border_style(border),
+    )
+}
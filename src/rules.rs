@@ -0,0 +1,563 @@
+//! Repository-defined validation rules
+//!
+//! Rules live in `.gip/config.toml` and are checked against every manifest
+//! entry by `gip commit` and `gip manifest validate`:
+//!
+//! ```toml
+//! [[validation.rules]]
+//! name = "auth-is-security"
+//! check = 'require(entry.behavior_class contains "security") when path matches "src/auth/**"'
+//! message = "Changes under src/auth must be classed as security"
+//! ```
+//!
+//! A check is `require(<condition>)`, optionally followed by `when <condition>`.
+//! Conditions combine comparisons with `and`, `or`, `not` and parentheses.
+//! Operands are string, number and boolean literals, `len(<value>)`, and
+//! fields: `path` and `symbol` (the entry's anchor), `entry.<field>` and
+//! `manifest.<field>`, where field names may be snake_case or camelCase.
+//! Operators are `==`, `!=`, `<`, `<=`, `>`, `>=`, `contains` (list element or
+//! substring) and `matches` (path glob; a glob anchor such as `src/**` matches
+//! when it may cover a matching path). A bare value is true when it is present
+//! and not empty.
+
+use crate::alignment::Enforcement;
+use crate::manifest::{Entry, Manifest};
+use crate::pathspec;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// `[validation]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ValidationConfig {
+    pub rules: Vec<RuleConfig>,
+    /// What `gip commit` does about changed files no entry covers
+    pub unlisted_files: Enforcement,
+}
+
+/// One `[[validation.rules]]` table
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct RuleConfig {
+    pub name: String,
+    pub check: String,
+    /// Shown when the rule fails, instead of the check itself
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// An entry that broke a rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleViolation {
+    pub rule: String,
+    pub file: String,
+    pub symbol: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}): {} [{}]",
+            self.symbol, self.file, self.message, self.rule
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(Value),
+    Field(Vec<String>),
+    Len(Box<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, &'static str, Box<Expr>),
+}
+
+/// A parsed `require(...) when ...` check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    require: Expr,
+    when: Option<Expr>,
+}
+
+const OPERATORS: &[&str] = &["==", "!=", "<=", ">=", "<", ">"];
+const WORD_OPERATORS: &[&str] = &["contains", "matches"];
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            let mut s = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                if chars[i] == '\\' && i + 1 < chars.len() {
+                    i += 1;
+                }
+                s.push(chars[i]);
+                i += 1;
+            }
+            if i == chars.len() {
+                anyhow::bail!("Unterminated string in rule");
+            }
+            tokens.push(Token::Str(s));
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '-' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit()))
+        {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token::Num(
+                text.parse()
+                    .with_context(|| format!("Invalid number '{}'", text))?,
+            ));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len()
+                && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.')
+            {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) else {
+                anyhow::bail!("Unexpected '{}' in rule", c);
+            };
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn at_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(w)) if w == word)
+    }
+
+    fn expect(&mut self, token: Token) -> Result<()> {
+        match self.next() {
+            Some(t) if t == token => Ok(()),
+            other => anyhow::bail!("Expected {:?}, found {:?}", token, other),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut left = self.and()?;
+        while self.at_word("or") {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut left = self.unary()?;
+        while self.at_word("and") {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.at_word("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+
+        let left = self.operand()?;
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            Some(Token::Ident(w)) if WORD_OPERATORS.contains(&w.as_str()) => {
+                if w == "contains" {
+                    "contains"
+                } else {
+                    "matches"
+                }
+            }
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+        Ok(Expr::Compare(Box::new(left), op, Box::new(self.operand()?)))
+    }
+
+    fn operand(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let inner = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(inner)
+            }
+            Some(Token::Str(s)) => Ok(Expr::Literal(Value::String(s))),
+            Some(Token::Num(n)) => Ok(Expr::Literal(Value::from(n))),
+            Some(Token::Ident(word)) => match word.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                "len" => {
+                    self.expect(Token::LParen)?;
+                    let inner = self.expr()?;
+                    self.expect(Token::RParen)?;
+                    Ok(Expr::Len(Box::new(inner)))
+                }
+                _ => {
+                    let path: Vec<String> = word.split('.').map(|s| s.to_string()).collect();
+                    match path[0].as_str() {
+                        "path" | "symbol" if path.len() == 1 => Ok(Expr::Field(path)),
+                        "entry" | "manifest" => Ok(Expr::Field(path)),
+                        _ => anyhow::bail!(
+                            "Unknown field '{}' (use path, symbol, entry.<field> or manifest.<field>)",
+                            word
+                        ),
+                    }
+                }
+            },
+            other => anyhow::bail!("Expected a value, found {:?}", other),
+        }
+    }
+}
+
+impl Rule {
+    pub fn parse(check: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(check)?,
+            pos: 0,
+        };
+
+        if !parser.at_word("require") {
+            anyhow::bail!("Rule must start with require(...)");
+        }
+        parser.pos += 1;
+        parser.expect(Token::LParen)?;
+        let require = parser.expr()?;
+        parser.expect(Token::RParen)?;
+
+        let when = if parser.at_word("when") {
+            parser.pos += 1;
+            Some(parser.expr()?)
+        } else {
+            None
+        };
+
+        if let Some(extra) = parser.peek() {
+            anyhow::bail!("Unexpected {:?} at end of rule", extra);
+        }
+        Ok(Rule { require, when })
+    }
+
+    /// Whether an entry satisfies the rule (entries it doesn't apply to do)
+    pub fn holds(&self, manifest: &Value, entry: &Value) -> bool {
+        let scope = Scope { manifest, entry };
+        let applies = match &self.when {
+            Some(when) => truthy(&scope.eval(when)),
+            None => true,
+        };
+        !applies || truthy(&scope.eval(&self.require))
+    }
+}
+
+struct Scope<'a> {
+    manifest: &'a Value,
+    entry: &'a Value,
+}
+
+impl Scope<'_> {
+    fn eval(&self, expr: &Expr) -> Value {
+        match expr {
+            Expr::Literal(v) => v.clone(),
+            Expr::Field(path) => self.field(path),
+            Expr::Len(inner) => Value::from(match self.eval(inner) {
+                Value::Array(a) => a.len(),
+                Value::String(s) => s.chars().count(),
+                Value::Object(o) => o.len(),
+                Value::Null => 0,
+                _ => 1,
+            }),
+            Expr::Not(inner) => Value::Bool(!truthy(&self.eval(inner))),
+            Expr::And(a, b) => Value::Bool(truthy(&self.eval(a)) && truthy(&self.eval(b))),
+            Expr::Or(a, b) => Value::Bool(truthy(&self.eval(a)) || truthy(&self.eval(b))),
+            Expr::Compare(a, op, b) => Value::Bool(compare(&self.eval(a), op, &self.eval(b))),
+        }
+    }
+
+    fn field(&self, path: &[String]) -> Value {
+        let (root, rest) = match path[0].as_str() {
+            "path" => (self.entry, vec!["anchor", "file"]),
+            "symbol" => (self.entry, vec!["anchor", "symbol"]),
+            "manifest" => (
+                self.manifest,
+                path[1..].iter().map(|s| s.as_str()).collect(),
+            ),
+            _ => (self.entry, path[1..].iter().map(|s| s.as_str()).collect()),
+        };
+
+        let mut value = root;
+        for key in rest {
+            let found = value.get(key).or_else(|| value.get(camel_case(key)));
+            match found {
+                Some(v) => value = v,
+                None => return Value::Null,
+            }
+        }
+        value.clone()
+    }
+}
+
+fn camel_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+fn equal(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x == y,
+        _ => a == b,
+    }
+}
+
+fn compare(a: &Value, op: &str, b: &Value) -> bool {
+    match op {
+        "==" => equal(a, b),
+        "!=" => !equal(a, b),
+        "contains" => match (a, b) {
+            (Value::Array(items), _) => items.iter().any(|i| equal(i, b)),
+            (Value::String(s), Value::String(sub)) => s.contains(sub.as_str()),
+            _ => false,
+        },
+        "matches" => match (a, b) {
+            (Value::String(s), Value::String(pattern)) => pathspec::overlaps(s, pattern),
+            (Value::Array(items), Value::String(pattern)) => items
+                .iter()
+                .any(|i| i.as_str().is_some_and(|s| pathspec::overlaps(s, pattern))),
+            _ => false,
+        },
+        _ => match (a.as_f64(), b.as_f64()) {
+            (Some(x), Some(y)) => match op {
+                "<" => x < y,
+                "<=" => x <= y,
+                ">" => x > y,
+                _ => x >= y,
+            },
+            _ => false,
+        },
+    }
+}
+
+/// Check every entry of a manifest against the configured rules
+///
+/// Fails if a rule doesn't parse, so a typo can't silently disable it.
+pub fn check(manifest: &Manifest, config: &ValidationConfig) -> Result<Vec<RuleViolation>> {
+    let rules = config
+        .rules
+        .iter()
+        .map(|r| {
+            Rule::parse(&r.check)
+                .with_context(|| format!("Invalid validation rule '{}'", r.name))
+                .map(|rule| (r, rule))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if rules.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let manifest_value = serde_json::to_value(manifest)?;
+    let mut violations = Vec::new();
+    for entry in &manifest.entries {
+        let entry_value = entry_value(entry)?;
+        for (config, rule) in &rules {
+            if !rule.holds(&manifest_value, &entry_value) {
+                violations.push(RuleViolation {
+                    rule: config.name.clone(),
+                    file: entry.anchor.file.clone(),
+                    symbol: entry.anchor.symbol.clone(),
+                    message: config
+                        .message
+                        .clone()
+                        .unwrap_or_else(|| config.check.clone()),
+                });
+            }
+        }
+    }
+    Ok(violations)
+}
+
+fn entry_value(entry: &Entry) -> Result<Value> {
+    let mut value = serde_json::to_value(entry)?;
+    // `entry.breaking` as a shorthand for `entry.compatibility.breaking`
+    let breaking = entry.compatibility.as_ref().is_some_and(|c| c.breaking);
+    if let Value::Object(ref mut map) = value {
+        map.entry("breaking").or_insert(Value::Bool(breaking));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn manifest(file: &str, classes: &[&str], tests: Option<&[&str]>) -> Manifest {
+        test_support::manifest(
+            "abc",
+            json!({
+                "anchor": { "file": file, "symbol": "login" },
+                "rationale": "Lock accounts after five failed logins",
+                "behaviorClass": classes,
+                "testsTouched": tests
+            }),
+        )
+    }
+
+    fn config(check: &str) -> ValidationConfig {
+        ValidationConfig {
+            rules: vec![RuleConfig {
+                name: "r".to_string(),
+                check: check.to_string(),
+                message: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    const AUTH_RULE: &str =
+        r#"require(entry.behavior_class contains "security") when path matches "src/auth/**""#;
+
+    #[test]
+    fn test_rule_with_condition() {
+        let violations = check(
+            &manifest("src/auth/login.rs", &["feature"], None),
+            &config(AUTH_RULE),
+        )
+        .unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].symbol, "login");
+        assert_eq!(violations[0].message, AUTH_RULE);
+
+        assert!(check(
+            &manifest("src/auth/login.rs", &["security"], None),
+            &config(AUTH_RULE)
+        )
+        .unwrap()
+        .is_empty());
+        assert!(check(
+            &manifest("src/ui/login.rs", &["feature"], None),
+            &config(AUTH_RULE)
+        )
+        .unwrap()
+        .is_empty());
+
+        // A glob anchor sweeping over src/auth is held to the rule too
+        let sweeping = check(&manifest("src/**", &["refactor"], None), &config(AUTH_RULE));
+        assert_eq!(sweeping.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_operators() {
+        let m = manifest("src/auth/login.rs", &["bugfix"], Some(&["tests/auth.rs"]));
+        for check_ok in [
+            "require(entry.tests_touched)",
+            "require(len(entry.testsTouched) >= 1 and not entry.breaking)",
+            r#"require(symbol == "login" or false)"#,
+            r#"require(entry.rationale contains "five")"#,
+            r#"require(entry.tests_touched matches "tests/**")"#,
+            r#"require(manifest.schema_version != "1.0")"#,
+            r#"require(entry.package) when entry.behavior_class contains "docs""#,
+        ] {
+            assert!(
+                check(&m, &config(check_ok)).unwrap().is_empty(),
+                "{}",
+                check_ok
+            );
+        }
+
+        assert_eq!(
+            check(&m, &config("require(len(entry.rationale) < 10)"))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let m = manifest("src/lib.rs", &["feature"], None);
+        for bad in [
+            "entry.tests_touched",
+            "require(entry.tests_touched",
+            "require(commit == 1)",
+            r#"require(path matches "src/**) "#,
+            "require(true) unless false",
+        ] {
+            assert!(check(&m, &config(bad)).is_err(), "{}", bad);
+        }
+    }
+}
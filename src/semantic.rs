@@ -0,0 +1,198 @@
+//! Semantic conflicts between two lines of history
+//!
+//! Git merges two branches cleanly as long as they edit different lines, yet
+//! both may have changed the same function with different intents. Comparing
+//! the manifests each side recorded since the merge base finds those symbols,
+//! whether or not git reports a conflict in their file.
+
+use crate::git;
+use crate::manifest::{self, Entry, Manifest, CHANGE_DELETE};
+use crate::pathspec;
+use crate::symbols;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+/// Why a symbol changed on both sides needs a look
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FindingKind {
+    /// One side deleted what the other changed
+    Deleted,
+    /// One side changed it in a breaking way
+    Breaking,
+    /// Both sides changed it
+    Overlap,
+}
+
+impl FindingKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FindingKind::Deleted => "deleted",
+            FindingKind::Breaking => "breaking",
+            FindingKind::Overlap => "overlap",
+        }
+    }
+}
+
+/// One side's change to a symbol
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SideChange {
+    pub commit: String,
+    pub change_type: String,
+    pub rationale: String,
+    pub breaking: bool,
+}
+
+/// A symbol both sides changed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub file: String,
+    pub symbol: String,
+    /// Whether git also reports a conflict in the file
+    pub conflicted: bool,
+    pub ours: Vec<SideChange>,
+    pub theirs: Vec<SideChange>,
+}
+
+fn is_breaking(entry: &Entry) -> bool {
+    entry.compatibility.as_ref().is_some_and(|c| c.breaking)
+}
+
+/// Changes per file and symbol, in order of first appearance
+fn changes(manifests: &[Manifest]) -> Vec<((String, String), Vec<SideChange>)> {
+    let mut found: Vec<((String, String), Vec<SideChange>)> = Vec::new();
+    for manifest in manifests {
+        // Whole-file and glob anchors say nothing about a particular symbol
+        for entry in manifest.entries.iter().filter(|e| !e.anchor.is_sweeping()) {
+            let key = (
+                pathspec::normalize(&entry.anchor.file),
+                symbols::base_name(&entry.anchor.symbol).to_string(),
+            );
+            let change = SideChange {
+                commit: manifest.commit.clone(),
+                change_type: entry.change_type.clone(),
+                rationale: entry.rationale.clone(),
+                breaking: is_breaking(entry),
+            };
+            match found.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, side)) => side.push(change),
+                None => found.push((key, vec![change])),
+            }
+        }
+    }
+    found
+}
+
+/// Manifests of the commits in `base..tip`, oldest first
+pub fn since(base: &str, tip: &str) -> Result<Vec<Manifest>> {
+    let commits = git::rev_list(
+        &format!("{}..{}", base, tip),
+        &["--reverse".to_string()],
+        None,
+    )?;
+    Ok(manifest::load_many(&commits, None)?
+        .into_iter()
+        .map(|(sha, mut m)| {
+            m.commit = sha;
+            m
+        })
+        .collect())
+}
+
+/// Symbols changed by manifests on both sides of a merge
+///
+/// `ours` and `theirs` are the manifests of each side's commits since the merge
+/// base; findings follow the order of `ours`.
+pub fn find(ours: &[Manifest], theirs: &[Manifest]) -> Vec<Finding> {
+    let theirs = changes(theirs);
+    changes(ours)
+        .into_iter()
+        .filter_map(|(key, ours)| {
+            let (_, theirs) = theirs.iter().find(|(k, _)| *k == key)?;
+            let both = || ours.iter().chain(theirs);
+            let kind = if both().any(|c| c.change_type == CHANGE_DELETE) {
+                FindingKind::Deleted
+            } else if both().any(|c| c.breaking) {
+                FindingKind::Breaking
+            } else {
+                FindingKind::Overlap
+            };
+            Some(Finding {
+                kind,
+                file: key.0,
+                symbol: key.1,
+                conflicted: false,
+                ours,
+                theirs: theirs.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{test_support, Compatibility, CHANGE_MODIFY};
+    use serde_json::json;
+
+    fn manifest(commit: &str, entries: &[(&str, &str, &str)]) -> Manifest {
+        let entries = entries
+            .iter()
+            .map(|(file, symbol, change_type)| {
+                json!({
+                    "anchor": { "file": file, "symbol": symbol },
+                    "changeType": change_type,
+                    "rationale": "Why",
+                    "behaviorClass": ["feature"]
+                })
+            })
+            .collect();
+        test_support::manifest_with(commit, entries)
+    }
+
+    #[test]
+    fn test_find() {
+        let ours = vec![
+            manifest(
+                "a1",
+                &[
+                    ("src/pay.rs", "Payments::charge", CHANGE_MODIFY),
+                    ("src/pay.rs", "refund", CHANGE_MODIFY),
+                    ("src/cart.rs", "*", CHANGE_MODIFY),
+                ],
+            ),
+            manifest("a2", &[("./src/pay.rs", "charge", CHANGE_MODIFY)]),
+        ];
+        let mut theirs = vec![manifest(
+            "b1",
+            &[
+                ("src/pay.rs", "charge", CHANGE_MODIFY),
+                ("src/pay.rs", "refund", CHANGE_DELETE),
+                ("src/cart.rs", "total", CHANGE_MODIFY),
+                ("src/user.rs", "login", CHANGE_MODIFY),
+            ],
+        )];
+        theirs[0].entries[0].compatibility = Some(Compatibility {
+            breaking: true,
+            deprecations: None,
+            migrations: None,
+            binary_breaking: None,
+            source_breaking: None,
+            data_model_migration: None,
+        });
+
+        let findings = find(&ours, &theirs);
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].kind, FindingKind::Breaking);
+        assert_eq!(findings[0].symbol, "charge");
+        assert_eq!(findings[0].ours.len(), 2);
+        assert_eq!(findings[0].theirs[0].commit, "b1");
+        assert_eq!(findings[1].kind, FindingKind::Deleted);
+        assert_eq!(findings[1].symbol, "refund");
+
+        assert!(find(&ours, &[]).is_empty());
+    }
+}
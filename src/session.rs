@@ -0,0 +1,114 @@
+//! The record of the last enrichment run
+//!
+//! Every time conflicts are enriched, `.gip/last-merge.json` is rewritten with
+//! both sides' commits, the conflicted files and the manifest entries matched
+//! to each, and the symbols both sides changed. Follow-up commands read the
+//! commits from it instead of working them out again, and other tools get
+//! one file to read.
+
+use crate::atomic;
+use crate::semantic::Finding;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File inside `.gip/` holding the last session
+pub const SESSION_FILE: &str = "last-merge.json";
+
+/// A manifest entry matched to a conflicted file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchedEntry {
+    /// `ours` or `theirs`
+    pub side: String,
+    pub commit: String,
+    pub symbol: String,
+    pub rationale: String,
+}
+
+/// A conflicted file of the session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictedFile {
+    pub file: String,
+    /// Whether markers (or a sidecar) got context
+    pub enriched: bool,
+    pub binary: bool,
+    /// What each side did, for deletion and mode conflicts kept in the index only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_conflict: Option<String>,
+    pub entries: Vec<MatchedEntry>,
+}
+
+/// One enrichment run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    /// The git operation that stopped, `None` for a stash
+    pub operation: Option<String>,
+    pub ours: String,
+    pub theirs: String,
+    pub merge_base: Option<String>,
+    pub files: Vec<ConflictedFile>,
+    pub semantic_findings: Vec<Finding>,
+}
+
+pub fn path(gip_dir: &Path) -> PathBuf {
+    gip_dir.join(SESSION_FILE)
+}
+
+pub fn save(gip_dir: &Path, session: &Session) -> Result<()> {
+    let path = path(gip_dir);
+    atomic::write(&path, serde_json::to_string_pretty(session)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The last session, if one was recorded and can still be read
+pub fn load(gip_dir: &Path) -> Option<Session> {
+    let content = fs::read_to_string(path(gip_dir)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// The other side of the last session, when it was recorded at `head`
+///
+/// A stash leaves no `MERGE_HEAD`-like ref behind, so this is the only place
+/// its commit can be found afterwards.
+pub fn theirs_at(gip_dir: &Path, head: &str) -> Option<String> {
+    load(gip_dir).filter(|s| s.ours == head).map(|s| s.theirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load(dir.path()).is_none());
+
+        let session = Session {
+            operation: Some("merge".to_string()),
+            ours: "a1".to_string(),
+            theirs: "b2".to_string(),
+            merge_base: Some("c3".to_string()),
+            files: vec![ConflictedFile {
+                file: "src/pay.rs".to_string(),
+                enriched: true,
+                binary: false,
+                index_conflict: None,
+                entries: vec![MatchedEntry {
+                    side: "theirs".to_string(),
+                    commit: "b2".to_string(),
+                    symbol: "charge".to_string(),
+                    rationale: "Retry on timeout".to_string(),
+                }],
+            }],
+            semantic_findings: Vec::new(),
+        };
+        save(dir.path(), &session).unwrap();
+        assert_eq!(load(dir.path()), Some(session));
+        assert_eq!(theirs_at(dir.path(), "a1").as_deref(), Some("b2"));
+        assert_eq!(theirs_at(dir.path(), "ff"), None);
+    }
+}
@@ -0,0 +1,369 @@
+//! What `gip init` set up, for re-runs and upgrades
+//!
+//! `gip init` records the gip version and a digest of each file it generates
+//! in `.gip/setup.json`. Running it again compares the files on disk with
+//! that record: a file still exactly as gip wrote it is replaced by the
+//! current version, with a diff of what changed, while a file the user edited
+//! is kept. Configuration and hooks are only checked, never rewritten.
+//!
+//! The [`Layout`] decides what git sees of `.gip/`. By default all of it is
+//! ignored. The shared layout lets a team commit `config.toml`,
+//! `packages.toml` and `templates/`, while `.gip/.gitignore` keeps hooks,
+//! pending manifests, caches, backups and logs local. Hooks run programs, so
+//! each clone installs its own.
+
+use crate::atomic;
+use crate::config::SECTIONS;
+use crate::hooks::HookEvent;
+use crate::template;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// File inside `.gip/` recording the setup
+pub const SETUP_FILE: &str = "setup.json";
+
+/// Files of `.gip/` the shared layout lets git track
+pub const SHARED_FILES: &[&str] = &["config.toml", "packages.toml", "templates/"];
+
+/// Ignore file inside `.gip/` written for the shared layout
+pub const GITIGNORE_FILE: &str = ".gitignore";
+
+/// What git tracks of `.gip/`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+    /// `.gip` is in the repository's `.gitignore`
+    #[default]
+    Local,
+    /// Configuration and templates are tracked; hooks and state stay ignored
+    Shared,
+}
+
+impl Layout {
+    pub fn name(self) -> &'static str {
+        match self {
+            Layout::Local => "local",
+            Layout::Shared => "shared",
+        }
+    }
+}
+
+/// Contents of `.gip/setup.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SetupRecord {
+    /// gip version that last ran `gip init`
+    pub version: String,
+    #[serde(default)]
+    pub layout: Layout,
+    /// Digest of each generated file as written, by path inside `.gip/`
+    #[serde(default)]
+    pub files: BTreeMap<String, String>,
+}
+
+impl SetupRecord {
+    /// The record of an earlier `gip init`, if any
+    pub fn load(gip_dir: &Path) -> Option<Self> {
+        let data = fs::read_to_string(gip_dir.join(SETUP_FILE)).ok()?;
+        serde_json::from_str(&data).ok()
+    }
+
+    pub fn save(&self, gip_dir: &Path) -> Result<()> {
+        let path = gip_dir.join(SETUP_FILE);
+        atomic::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {:?}", path))
+    }
+
+    /// Remember a generated file as written
+    pub fn record(&mut self, name: &str, content: &str) {
+        self.files.insert(name.to_string(), digest(content));
+    }
+
+    /// Whether a file is exactly what gip last wrote
+    pub fn is_stock(&self, name: &str, content: &str) -> bool {
+        self.files.get(name) == Some(&digest(content))
+    }
+}
+
+fn digest(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// What `gip init` does with a generated file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileUpdate {
+    /// The file didn't exist
+    Create,
+    /// The file already matches this version
+    Current,
+    /// The file is an older generated version, replaced; holds the diff
+    Upgrade(String),
+    /// The file has the user's changes and is left alone
+    Keep,
+}
+
+/// Decide how to bring `.gip/manifest.toon` to the `fresh` template
+///
+/// Besides files recorded as stock, a template nobody filled in yet, like one
+/// written by an older gip or by `gip commit`, is recognized by its header
+/// and placeholder rationale.
+pub fn plan_template(
+    existing: Option<&str>,
+    fresh: &str,
+    record: &SetupRecord,
+    name: &str,
+) -> FileUpdate {
+    let Some(existing) = existing else {
+        return FileUpdate::Create;
+    };
+    if existing == fresh {
+        return FileUpdate::Current;
+    }
+    let untouched =
+        existing.starts_with("; Gip Manifest Template") && existing.contains(template::PLACEHOLDER);
+    if untouched {
+        FileUpdate::Upgrade(line_diff(existing, fresh))
+    } else {
+        plan_file(Some(existing), fresh, record, name)
+    }
+}
+
+/// Decide how to bring a generated file to `fresh`
+pub fn plan_file(
+    existing: Option<&str>,
+    fresh: &str,
+    record: &SetupRecord,
+    name: &str,
+) -> FileUpdate {
+    match existing {
+        None => FileUpdate::Create,
+        Some(existing) if existing == fresh => FileUpdate::Current,
+        Some(existing) if record.is_stock(name, existing) => {
+            FileUpdate::Upgrade(line_diff(existing, fresh))
+        }
+        Some(_) => FileUpdate::Keep,
+    }
+}
+
+/// `.gip/.gitignore` for the shared layout: everything but [`SHARED_FILES`]
+pub fn shared_gitignore() -> String {
+    let mut out = String::from(
+        "# Written by `gip init --layout shared`: share configuration, keep state local\n*\n!.gitignore\n",
+    );
+    for file in SHARED_FILES {
+        out.push_str(&format!("!{}\n", file));
+        if file.ends_with('/') {
+            out.push_str(&format!("!{}**\n", file));
+        }
+    }
+    out
+}
+
+/// A `.gitignore` without the lines ignoring all of `.gip`, if it had any
+pub fn unignore_gip(gitignore: &str) -> Option<String> {
+    let is_gip = |line: &str| matches!(line.trim(), ".gip" | ".gip/" | "/.gip" | "/.gip/");
+    if !gitignore.lines().any(is_gip) {
+        return None;
+    }
+    let mut kept: String = gitignore
+        .lines()
+        .filter(|l| !is_gip(l))
+        .collect::<Vec<_>>()
+        .join("\n");
+    if !kept.is_empty() {
+        kept.push('\n');
+    }
+    Some(kept)
+}
+
+/// Changed lines between two texts, `-` for removed and `+` for added
+///
+/// Unchanged stretches are collapsed to `@@` separators.
+pub fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence lengths of every pair of suffixes
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = String::new();
+    let mut in_change = false;
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        let line = if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+            None
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            i += 1;
+            Some(format!("-{}", old[i - 1]))
+        } else {
+            j += 1;
+            Some(format!("+{}", new[j - 1]))
+        };
+        match line {
+            Some(line) => {
+                if !in_change && !out.is_empty() {
+                    out.push_str("@@\n");
+                }
+                out.push_str(&line);
+                out.push('\n');
+                in_change = true;
+            }
+            None => in_change = false,
+        }
+    }
+    out
+}
+
+/// Sections of a config file this version of gip doesn't read
+pub fn unknown_sections(config: &str) -> Result<Vec<String>> {
+    let table: toml::Table = toml::from_str(config).context("Failed to parse config.toml")?;
+    Ok(table
+        .keys()
+        .filter(|key| !SECTIONS.contains(&key.as_str()))
+        .cloned()
+        .collect())
+}
+
+/// Files in `.gip/hooks/` that no event runs, like `post-enrich.sh`
+pub fn unknown_hooks(gip_dir: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(gip_dir.join("hooks")) else {
+        return Vec::new();
+    };
+    let known: Vec<String> = HookEvent::ALL
+        .iter()
+        .flat_map(|e| [e.name().to_string(), format!("{}.exe", e.name())])
+        .collect();
+    let mut unknown: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        // Editors and samples leave files next to real hooks
+        .filter(|name| !known.contains(name) && !name.ends_with(".sample") && !name.ends_with('~'))
+        .collect();
+    unknown.sort();
+    unknown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_template() {
+        let fresh = template::DEFAULT_TEMPLATE;
+        let old = fresh.replace(
+            ";    For a rename, put the old and new signatures in 'signatureDelta'.\n",
+            "",
+        );
+        let mut record = SetupRecord::default();
+
+        assert_eq!(
+            plan_template(None, fresh, &record, "manifest.toon"),
+            FileUpdate::Create
+        );
+        assert_eq!(
+            plan_template(Some(fresh), fresh, &record, "manifest.toon"),
+            FileUpdate::Current
+        );
+        assert_eq!(
+            plan_template(Some(&old), fresh, &record, "manifest.toon"),
+            FileUpdate::Upgrade(
+                "+;    For a rename, put the old and new signatures in 'signatureDelta'.\n"
+                    .to_string()
+            )
+        );
+
+        // A recorded file counts as stock only as written
+        record.record("manifest.toon", "written by gip\n");
+        assert_eq!(
+            plan_template(
+                Some("written by gip, then edited\n"),
+                fresh,
+                &record,
+                "manifest.toon"
+            ),
+            FileUpdate::Keep
+        );
+        assert!(matches!(
+            plan_template(Some("written by gip\n"), fresh, &record, "manifest.toon"),
+            FileUpdate::Upgrade(_)
+        ));
+
+        let filled = fresh.replace(template::PLACEHOLDER, "Retry payments");
+        assert_eq!(
+            plan_template(
+                Some(&filled),
+                fresh,
+                &SetupRecord::default(),
+                "manifest.toon"
+            ),
+            FileUpdate::Keep
+        );
+    }
+
+    #[test]
+    fn test_shared_layout_files() {
+        let ignore = shared_gitignore();
+        assert!(ignore.contains("\n*\n"));
+        assert!(ignore.contains("!config.toml\n"));
+        assert!(ignore.contains("!templates/**\n"));
+
+        assert_eq!(
+            unignore_gip("target/\n.gip\n*.log\n").as_deref(),
+            Some("target/\n*.log\n")
+        );
+        assert_eq!(unignore_gip("/.gip/\n").as_deref(), Some(""));
+        assert_eq!(unignore_gip("target/\n.gip/cache\n"), None);
+
+        let record: SetupRecord = serde_json::from_str(r#"{"version":"2.0.0"}"#).unwrap();
+        assert_eq!(record.layout, Layout::Local);
+    }
+
+    #[test]
+    fn test_line_diff() {
+        assert_eq!(line_diff("a\nb\nc\n", "a\nb\nc\n"), "");
+        assert_eq!(
+            line_diff("a\nb\nc\nd\n", "a\nB\nc\nd\ne\n"),
+            "-b\n+B\n@@\n+e\n"
+        );
+    }
+
+    #[test]
+    fn test_unknown_sections_and_hooks() {
+        assert_eq!(
+            unknown_sections("[markers]\nsummarize = \"rules\"\n[marker_style]\nx = 1\n").unwrap(),
+            vec!["marker_style"]
+        );
+        assert!(unknown_sections("[markers").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let hooks = dir.path().join("hooks");
+        fs::create_dir_all(&hooks).unwrap();
+        for name in [
+            "post-enrich",
+            "post-enrich.sh",
+            "pre-commit-manifest",
+            "post-enrich.sample",
+        ] {
+            fs::write(hooks.join(name), "#!/bin/sh\n").unwrap();
+        }
+        assert_eq!(
+            unknown_hooks(dir.path()),
+            vec!["post-enrich.sh", "pre-commit-manifest"]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Diff hunks annotated with manifest entries
+//!
+//! Parses unified diff output (as printed by `git show`) into hunks and picks
+//! the manifest entries that explain each one, so a commit can be reviewed
+//! with its intent inline.
+
+use crate::manifest::{Entry, Manifest};
+use crate::symbols;
+
+/// A hunk in a unified diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    /// Index of the `@@` header line in the diff output
+    pub header_line: usize,
+    /// Path of the file after the change
+    pub file: String,
+    /// 1-based position of the hunk within its file
+    pub index: usize,
+    /// Function context git prints after the `@@` range, if any
+    pub context: String,
+    /// First changed line in the new version of the file
+    pub first_changed: usize,
+}
+
+/// Parse the hunks of unified diff output
+pub fn parse_hunks(diff: &str) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut file = String::new();
+    let mut index = 0;
+    let mut new_line = 0;
+    let mut seen_change = true;
+
+    for (n, line) in diff.lines().enumerate() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            file = path.strip_prefix("b/").unwrap_or(path).to_string();
+            index = 0;
+        } else if line.starts_with("diff --git ") {
+            file.clear();
+        } else if let Some(rest) = line.strip_prefix("@@ ") {
+            let Some((ranges, context)) = rest.split_once("@@") else {
+                continue;
+            };
+            let new_start = ranges
+                .split_whitespace()
+                .find_map(|r| r.strip_prefix('+'))
+                .and_then(|r| r.split(',').next())
+                .and_then(|r| r.parse().ok())
+                .unwrap_or(1);
+
+            index += 1;
+            new_line = new_start;
+            seen_change = false;
+            hunks.push(Hunk {
+                header_line: n,
+                file: file.clone(),
+                index,
+                context: context.trim().to_string(),
+                first_changed: new_start,
+            });
+        } else if let Some(hunk) = hunks.last_mut() {
+            match line.chars().next() {
+                Some('+') | Some('-') if !seen_change => {
+                    hunk.first_changed = new_line;
+                    seen_change = true;
+                    if line.starts_with('+') {
+                        new_line += 1;
+                    }
+                }
+                Some('+') | Some(' ') => new_line += 1,
+                _ => {}
+            }
+        }
+    }
+
+    hunks
+}
+
+/// Pick the manifest entries explaining a hunk
+///
+/// Tries, in order: the symbol enclosing the first changed line, the function
+/// context git printed, the hunk's `H#<n>` id, and finally the only entry for
+/// the file.
+pub fn entries_for_hunk<'a>(
+    manifest: &'a Manifest,
+    hunk: &Hunk,
+    symbol: Option<&str>,
+) -> Vec<&'a Entry> {
+    let in_file: Vec<&Entry> = manifest
+        .entries
+        .iter()
+        .filter(|e| e.anchor.file == hunk.file)
+        .collect();
+
+    if let Some(symbol) = symbol {
+        let hits: Vec<&Entry> = in_file
+            .iter()
+            .copied()
+            .filter(|e| symbols::base_name(&e.anchor.symbol) == symbol)
+            .collect();
+        if !hits.is_empty() {
+            return hits;
+        }
+    }
+
+    if !hunk.context.is_empty() {
+        let hits: Vec<&Entry> = in_file
+            .iter()
+            .copied()
+            .filter(|e| symbols::match_line(&e.anchor.symbol, &hunk.context).is_some())
+            .collect();
+        if !hits.is_empty() {
+            return hits;
+        }
+    }
+
+    let id = format!("H#{}", hunk.index);
+    if let Some(entry) = in_file.iter().find(|e| e.anchor.hunk_id == id) {
+        return vec![*entry];
+    }
+
+    if in_file.len() == 1 {
+        return in_file;
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+commit abc
+diff --git a/src/pay.rs b/src/pay.rs
+--- a/src/pay.rs
++++ b/src/pay.rs
+@@ -1,4 +1,5 @@ fn start() {
+ fn start() {
+     init();
++    warm_up();
+ }
+@@ -10,3 +11,3 @@ impl Gateway {
+     pub fn charge(&self) {
+-        send(1);
++        send(2);
+diff --git a/README.md b/README.md
+--- a/README.md
++++ b/README.md
+@@ -1 +1 @@
+-old
++new
+";
+
+    #[test]
+    fn test_parse_hunks() {
+        let hunks = parse_hunks(DIFF);
+        assert_eq!(hunks.len(), 3);
+
+        assert_eq!(hunks[0].file, "src/pay.rs");
+        assert_eq!(hunks[0].index, 1);
+        assert_eq!(hunks[0].context, "fn start() {");
+        assert_eq!(hunks[0].first_changed, 3);
+
+        assert_eq!(hunks[1].index, 2);
+        assert_eq!(hunks[1].first_changed, 12);
+
+        assert_eq!(hunks[2].file, "README.md");
+        assert_eq!(hunks[2].index, 1);
+        assert_eq!(hunks[2].header_line, 16);
+    }
+
+    #[test]
+    fn test_entries_for_hunk() {
+        let manifest: Manifest = serde_json::from_value(serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "abc",
+            "entries": [
+                { "anchor": { "file": "src/pay.rs", "symbol": "start", "hunkId": "H#1" },
+                  "changeType": "modify", "rationale": "a", "behaviorClass": [], "contract": {} },
+                { "anchor": { "file": "src/pay.rs", "symbol": "Gateway::charge", "hunkId": "H#9" },
+                  "changeType": "modify", "rationale": "b", "behaviorClass": [], "contract": {} },
+                { "anchor": { "file": "README.md", "symbol": "intro", "hunkId": "H#7" },
+                  "changeType": "modify", "rationale": "c", "behaviorClass": [], "contract": {} }
+            ]
+        }))
+        .unwrap();
+        let hunks = parse_hunks(DIFF);
+
+        assert_eq!(
+            entries_for_hunk(&manifest, &hunks[1], Some("charge"))[0].rationale,
+            "b"
+        );
+        // Context line names `start`
+        assert_eq!(
+            entries_for_hunk(&manifest, &hunks[0], None)[0].rationale,
+            "a"
+        );
+        // Only entry for the file
+        assert_eq!(
+            entries_for_hunk(&manifest, &hunks[2], None)[0].rationale,
+            "c"
+        );
+    }
+}
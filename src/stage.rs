@@ -0,0 +1,421 @@
+//! Intent captured while staging
+//!
+//! `gip add` walks the unstaged hunks like `git add -p`. Each hunk staged
+//! gets a one-line rationale and a behavior class, recorded as an entry of the
+//! pending manifest anchored at the hunk's file and enclosing symbol. By the
+//! time of `gip commit` the manifest is already written, hunk by hunk.
+//!
+//! Hunks of the same symbol share one entry: later answers are folded in as
+//! [`crate::manifest::aggregate::merge_entry`] does for squashed commits.
+//!
+//! The reverse also holds: `gip commit --only` picks entries of the pending
+//! manifest and stages just the hunks they describe, see [`selects`] and
+//! [`owns_hunk`].
+
+use crate::analyzers;
+use crate::git;
+use crate::manifest::aggregate;
+use crate::manifest::{
+    Anchor, Contract, Entry, Manifest, ManifestFormat, CHANGE_ADD, CHANGE_DELETE, CHANGE_MODIFY,
+    SYMBOL_ANY,
+};
+use crate::pathspec;
+use crate::symbols;
+use crate::template;
+use anyhow::{Context, Result};
+use serde_json::Map;
+use std::fs;
+use std::path::Path;
+
+/// One file of `git diff` output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    /// Path of the file after the change
+    pub path: String,
+    /// Lines from `diff --git` up to the first hunk
+    pub header: Vec<String>,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// One hunk of a [`FileDiff`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffHunk {
+    /// The `@@` line
+    pub header: String,
+    /// Lines below the header, each starting with ` `, `+`, `-` or `\`
+    pub lines: Vec<String>,
+    /// First changed line in the new version of the file
+    pub first_changed: usize,
+}
+
+impl FileDiff {
+    pub fn is_new(&self) -> bool {
+        self.header.iter().any(|l| l.starts_with("new file mode"))
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.header
+            .iter()
+            .any(|l| l.starts_with("deleted file mode"))
+    }
+
+    /// A patch of the chosen hunks, for `git apply --cached`
+    ///
+    /// Hunk positions refer to the index, which is unchanged until the patch
+    /// is applied, so any subset of hunks applies as one patch.
+    pub fn patch(&self, chosen: &[usize]) -> String {
+        let mut out = String::new();
+        for line in &self.header {
+            out.push_str(line);
+            out.push('\n');
+        }
+        for hunk in chosen.iter().filter_map(|&i| self.hunks.get(i)) {
+            out.push_str(&hunk.header);
+            out.push('\n');
+            for line in &hunk.lines {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// How the change to this file is described in a manifest
+    pub fn change_type(&self) -> &'static str {
+        if self.is_new() {
+            CHANGE_ADD
+        } else if self.is_deleted() {
+            CHANGE_DELETE
+        } else {
+            CHANGE_MODIFY
+        }
+    }
+}
+
+impl DiffHunk {
+    /// Function context git prints after the `@@` range, if any
+    pub fn context(&self) -> &str {
+        self.header
+            .strip_prefix("@@ ")
+            .and_then(|rest| rest.split_once("@@"))
+            .map_or("", |(_, context)| context.trim())
+    }
+}
+
+/// Run `git diff` with `args` on `paths` and parse it
+pub fn read_diff(args: &[&str], paths: &[&str]) -> Result<Vec<FileDiff>> {
+    let diff_args: Vec<String> = [
+        "diff",
+        "--no-color",
+        "--no-ext-diff",
+        "--src-prefix=a/",
+        "--dst-prefix=b/",
+    ]
+    .iter()
+    .chain(args)
+    .chain(&["--"])
+    .chain(paths)
+    .map(|a| a.to_string())
+    .collect();
+    // Not `run_git_cmd`, which trims: a blank context line can end the diff
+    let output = git::run_git_output(&diff_args)?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(parse_diff(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parse `git diff` output into files and hunks
+///
+/// Files without hunks, such as binary files or mode changes, are kept with
+/// an empty hunk list.
+pub fn parse_diff(diff: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    let mut new_line = 0;
+    let mut seen_change = true;
+
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            // Until `+++` says otherwise, the `b/` side of the header
+            let path = line
+                .rsplit_once(" b/")
+                .map_or("", |(_, path)| path)
+                .to_string();
+            files.push(FileDiff {
+                path,
+                header: vec![line.to_string()],
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if file.hunks.is_empty() && !line.starts_with("@@ ") {
+            if let Some(path) = line.strip_prefix("+++ b/") {
+                file.path = path.to_string();
+            }
+            file.header.push(line.to_string());
+        } else if line.starts_with("@@ ") {
+            let new_start = line
+                .split_whitespace()
+                .find_map(|r| r.strip_prefix('+'))
+                .and_then(|r| r.split(',').next())
+                .and_then(|r| r.parse().ok())
+                .unwrap_or(1);
+            new_line = new_start;
+            seen_change = false;
+            file.hunks.push(DiffHunk {
+                header: line.to_string(),
+                lines: Vec::new(),
+                first_changed: new_start,
+            });
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            match line.chars().next() {
+                Some('+') | Some('-') if !seen_change => {
+                    hunk.first_changed = new_line;
+                    seen_change = true;
+                    if line.starts_with('+') {
+                        new_line += 1;
+                    }
+                }
+                Some('+') | Some(' ') => new_line += 1,
+                _ => {}
+            }
+            hunk.lines.push(line.to_string());
+        }
+    }
+    files
+}
+
+/// The symbol a hunk changes: the definition enclosing its first changed
+/// line, else the function git names in the hunk header, else the whole file
+pub fn hunk_symbol(
+    file: &FileDiff,
+    hunk: &DiffHunk,
+    source: Option<&str>,
+    gip_dir: &Path,
+) -> String {
+    let analyzer = analyzers::analyzer_for(Path::new(&file.path), Some(gip_dir));
+    if let (Some(analyzer), Some(source)) = (analyzer, source) {
+        if let Some(symbol) = analyzer.enclosing_symbol_at_line(source, hunk.first_changed) {
+            return symbol.name;
+        }
+    }
+    symbols::signature_name(hunk.context())
+        .map(str::to_string)
+        .unwrap_or_else(|| SYMBOL_ANY.to_string())
+}
+
+/// The manifest entry for a staged hunk
+pub fn entry_for(anchor: Anchor, change_type: &str, rationale: &str, class: &str) -> Entry {
+    Entry {
+        anchor,
+        change_type: change_type.to_string(),
+        rationale: rationale.to_string(),
+        signature_delta: None,
+        behavior_class: vec![class.to_string()],
+        contract: Contract {
+            inputs: None,
+            outputs: None,
+            preconditions: Vec::new(),
+            postconditions: Vec::new(),
+            error_model: Vec::new(),
+        },
+        side_effects: Vec::new(),
+        compatibility: None,
+        tests_touched: None,
+        perf_budget: None,
+        security_notes: None,
+        feature_flags: None,
+        inherits_global_intent: None,
+        package: None,
+        extra: Map::new(),
+    }
+}
+
+/// The entry already describing a file and symbol, if any
+pub fn existing_entry<'a>(manifest: &'a Manifest, file: &str, symbol: &str) -> Option<&'a Entry> {
+    manifest
+        .entries
+        .iter()
+        .find(|e| e.anchor.file == file && e.anchor.symbol == symbol)
+}
+
+/// The pending manifest to add entries to; a missing or untouched template starts empty
+pub fn load_pending(gip_dir: &Path, path: &Path) -> Result<Manifest> {
+    match fs::read_to_string(path) {
+        Ok(content) if !template::is_unedited(&content, gip_dir) => ManifestFormat::from_path(path)
+            .parse_manifest(&content)
+            .with_context(|| format!("Failed to parse {}", path.display())),
+        _ => Ok(Manifest::new("HEAD".to_string())),
+    }
+}
+
+/// Add an entry to the pending manifest, folding it into one for the same symbol
+pub fn record(manifest: &mut Manifest, entry: Entry) {
+    match manifest
+        .entries
+        .iter_mut()
+        .find(|e| aggregate::same_target(e, &entry))
+    {
+        Some(existing) => *existing = aggregate::merge_entry(existing, &entry),
+        None => manifest.entries.push(entry),
+    }
+}
+
+/// Whether a `--only` selector picks an entry
+///
+/// A selector names the entry's file or a directory above it, its symbol, or
+/// both as `file::symbol`.
+pub fn selects(selector: &str, entry: &Entry) -> bool {
+    let anchor = &entry.anchor;
+    let same_symbol =
+        |symbol: &str| symbol == anchor.symbol || symbol == symbols::base_name(&anchor.symbol);
+    pathspec::matches(selector, &anchor.file)
+        || same_symbol(selector)
+        || selector.split_once("::").is_some_and(|(file, symbol)| {
+            pathspec::matches(file, &anchor.file) && same_symbol(symbol)
+        })
+}
+
+/// Whether an entry describes a hunk changing `symbol` in `file`
+///
+/// Entries anchored at a whole file or a glob own all its hunks, as does any
+/// entry of a file that is added or deleted, which can't be staged in part.
+pub fn owns_hunk(entry: &Entry, file: &FileDiff, symbol: &str) -> bool {
+    let anchor = &entry.anchor;
+    pathspec::matches(&anchor.file, &file.path)
+        && (anchor.is_sweeping()
+            || file.is_new()
+            || file.is_deleted()
+            || symbols::base_name(&anchor.symbol) == symbols::base_name(symbol))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/pay.rs b/src/pay.rs
+index 1111111..2222222 100644
+--- a/src/pay.rs
++++ b/src/pay.rs
+@@ -1,4 +1,5 @@
+ fn charge(amount: u64) -> bool {
++    retry(3);
+     amount > 0
+ }
+
+@@ -10,3 +11,3 @@ fn refund() {
+ fn refund() {
+-    log(\"refund\");
++    audit(\"refund\");
+ }
+diff --git a/notes.md b/notes.md
+new file mode 100644
+index 0000000..3333333
+--- /dev/null
++++ b/notes.md
+@@ -0,0 +1 @@
++Retries
+";
+
+    #[test]
+    fn test_parse_diff() {
+        let files = parse_diff(DIFF);
+        assert_eq!(files.len(), 2);
+        let pay = &files[0];
+        assert_eq!(pay.path, "src/pay.rs");
+        assert_eq!(pay.header.len(), 4);
+        assert_eq!(pay.hunks.len(), 2);
+        assert_eq!(pay.hunks[0].first_changed, 2);
+        assert_eq!(pay.hunks[1].first_changed, 12);
+        assert_eq!(pay.hunks[1].context(), "fn refund() {");
+        assert_eq!(pay.change_type(), CHANGE_MODIFY);
+        assert_eq!(files[1].change_type(), CHANGE_ADD);
+
+        let patch = pay.patch(&[1]);
+        assert!(patch.starts_with("diff --git a/src/pay.rs b/src/pay.rs\n"));
+        assert!(patch.contains("@@ -10,3 +11,3 @@"));
+        assert!(!patch.contains("retry(3)"));
+        assert!(patch.ends_with(" }\n"));
+    }
+
+    #[test]
+    fn test_hunk_symbol_and_record() {
+        let files = parse_diff(DIFF);
+        let pay = &files[0];
+        let source = "fn charge(amount: u64) -> bool {\n    retry(3);\n    amount > 0\n}\n";
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(
+            hunk_symbol(pay, &pay.hunks[0], Some(source), dir.path()),
+            "charge"
+        );
+        assert_eq!(hunk_symbol(pay, &pay.hunks[1], None, dir.path()), "refund");
+        assert_eq!(
+            hunk_symbol(&files[1], &files[1].hunks[0], None, dir.path()),
+            SYMBOL_ANY
+        );
+
+        let anchor = |hunk: &str| Anchor {
+            file: "src/pay.rs".to_string(),
+            symbol: "charge".to_string(),
+            hunk_id: hunk.to_string(),
+        };
+        let mut manifest = Manifest::new("HEAD".to_string());
+        record(
+            &mut manifest,
+            entry_for(
+                anchor("H#1"),
+                CHANGE_MODIFY,
+                "Retry card payments",
+                "bugfix",
+            ),
+        );
+        record(
+            &mut manifest,
+            entry_for(anchor("H#3"), CHANGE_MODIFY, "Retry card payments", "perf"),
+        );
+        assert_eq!(manifest.entries.len(), 1);
+        assert_eq!(manifest.entries[0].behavior_class, vec!["bugfix", "perf"]);
+        assert!(existing_entry(&manifest, "src/pay.rs", "charge").is_some());
+        assert!(existing_entry(&manifest, "src/pay.rs", "refund").is_none());
+    }
+
+    #[test]
+    fn test_only_selection() {
+        let files = parse_diff(DIFF);
+        let entry = |file: &str, symbol: &str| {
+            entry_for(
+                Anchor {
+                    file: file.to_string(),
+                    symbol: symbol.to_string(),
+                    hunk_id: "H#1".to_string(),
+                },
+                CHANGE_MODIFY,
+                "Why",
+                "feature",
+            )
+        };
+        let charge = entry("src/pay.rs", "Payments::charge");
+        for selector in ["src/pay.rs", "src", "charge", "src/pay.rs::charge"] {
+            assert!(selects(selector, &charge), "{}", selector);
+        }
+        for selector in ["src/cart.rs", "refund", "src/cart.rs::charge"] {
+            assert!(!selects(selector, &charge), "{}", selector);
+        }
+
+        assert!(owns_hunk(&charge, &files[0], "charge"));
+        assert!(!owns_hunk(&charge, &files[0], "refund"));
+        assert!(owns_hunk(&entry("src/*.rs", "*"), &files[0], "refund"));
+        assert!(owns_hunk(
+            &entry("notes.md", "intro"),
+            &files[1],
+            SYMBOL_ANY
+        ));
+    }
+}
@@ -0,0 +1,168 @@
+//! Pending manifests saved alongside stashes
+//!
+//! `.gip/` is ignored, so `git stash` leaves the pending manifest behind even
+//! though the changes it describes are stashed. `gip stash` moves it to
+//! `.gip/stash/<stash-sha>.<ext>` and puts it back when the stash is applied.
+
+use crate::atomic;
+use crate::lock::PendingLock;
+use crate::manifest::format::find_pending;
+use crate::manifest::{Manifest, ManifestFormat};
+use crate::template;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory inside `.gip/` holding stashed manifests
+pub const STASH_DIR: &str = "stash";
+
+/// Manifest file saved for a stash, if any
+pub fn saved_path(gip_dir: &Path, stash_sha: &str) -> Option<PathBuf> {
+    let dir = gip_dir.join(STASH_DIR);
+    ["toon", "yaml", "yml", "json"]
+        .iter()
+        .map(|ext| dir.join(format!("{}.{}", stash_sha, ext)))
+        .find(|p| p.is_file())
+}
+
+/// Move the pending manifest aside for a new stash
+///
+/// An untouched template is left in place since it describes nothing.
+pub fn save(gip_dir: &Path, stash_sha: &str) -> Result<Option<PathBuf>> {
+    let _lock = PendingLock::acquire(gip_dir)?;
+    let edited = find_pending(gip_dir).into_iter().find(|(path, _)| {
+        fs::read_to_string(path).is_ok_and(|content| !template::is_unedited(&content, gip_dir))
+    });
+    let Some((pending, _)) = edited else {
+        return Ok(None);
+    };
+
+    let dir = gip_dir.join(STASH_DIR);
+    fs::create_dir_all(&dir).context("Failed to create stash directory")?;
+    let ext = pending
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("toon");
+    let saved = dir.join(format!("{}.{}", stash_sha, ext));
+    fs::rename(&pending, &saved)
+        .with_context(|| format!("Failed to move pending manifest to {:?}", saved))?;
+
+    let template_path = gip_dir.join("manifest.toon");
+    atomic::write(&template_path, template::render_for_repo(gip_dir)?)
+        .with_context(|| format!("Failed to write template to {:?}", template_path))?;
+    Ok(Some(saved))
+}
+
+/// Parse the manifest saved for a stash, attributed to the stash commit
+pub fn load(gip_dir: &Path, stash_sha: &str) -> Option<Manifest> {
+    let path = saved_path(gip_dir, stash_sha)?;
+    let content = fs::read_to_string(&path).ok()?;
+    let mut manifest = ManifestFormat::from_path(&path)
+        .parse_manifest(&content)
+        .ok()?;
+    manifest.commit = stash_sha.to_string();
+    Some(manifest)
+}
+
+/// Copy the manifest saved for a stash back to the pending manifest
+///
+/// Returns the restored path, or `None` if nothing was saved. Fails rather
+/// than overwrite a pending manifest that has been edited since.
+pub fn restore(gip_dir: &Path, stash_sha: &str) -> Result<Option<PathBuf>> {
+    let Some(saved) = saved_path(gip_dir, stash_sha) else {
+        return Ok(None);
+    };
+
+    let _lock = PendingLock::acquire(gip_dir)?;
+    let pending: Vec<PathBuf> = find_pending(gip_dir).into_iter().map(|(p, _)| p).collect();
+    if let Some(edited) = pending.iter().find(|p| {
+        let content = fs::read_to_string(p).unwrap_or_default();
+        !template::is_unedited(&content, gip_dir)
+    }) {
+        anyhow::bail!(
+            "{:?} has been edited since stashing; the stashed manifest is still at {:?}",
+            edited,
+            saved
+        );
+    }
+    for path in &pending {
+        fs::remove_file(path).with_context(|| format!("Failed to remove template {:?}", path))?;
+    }
+
+    let name = saved.file_name().unwrap_or_default().to_string_lossy();
+    let ext = name.rsplit('.').next().unwrap_or("toon");
+    let target = gip_dir.join(format!("manifest.{}", ext));
+    atomic::copy(&saved, &target)
+        .with_context(|| format!("Failed to restore stashed manifest to {:?}", target))?;
+    Ok(Some(target))
+}
+
+/// Delete manifests saved for stashes that no longer exist
+pub fn prune(gip_dir: &Path, live_stashes: &[String]) -> Result<usize> {
+    let dir = gip_dir.join(STASH_DIR);
+    let Ok(files) = fs::read_dir(&dir) else {
+        return Ok(0);
+    };
+
+    let mut removed = 0;
+    for file in files.flatten() {
+        let path = file.path();
+        let sha = path.file_stem().unwrap_or_default().to_string_lossy();
+        if !live_stashes.iter().any(|s| *s == sha) {
+            fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EDITED: &str = "{\"schemaVersion\":\"2.0\",\"commit\":\"HEAD\",\"entries\":[]}";
+
+    #[test]
+    fn test_save_and_restore() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let gip_dir = temp_dir.path();
+        fs::write(gip_dir.join("manifest.json"), EDITED).unwrap();
+
+        let saved = save(gip_dir, "abc123").unwrap().unwrap();
+        assert_eq!(saved, gip_dir.join("stash").join("abc123.json"));
+        assert!(template::is_unedited(
+            &fs::read_to_string(gip_dir.join("manifest.toon")).unwrap(),
+            gip_dir
+        ));
+        assert_eq!(load(gip_dir, "abc123").unwrap().commit, "abc123");
+
+        let restored = restore(gip_dir, "abc123").unwrap().unwrap();
+        assert_eq!(fs::read_to_string(restored).unwrap(), EDITED);
+        assert!(restore(gip_dir, "def456").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_restore_keeps_edited_pending_manifest() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let gip_dir = temp_dir.path();
+        fs::write(gip_dir.join("manifest.json"), EDITED).unwrap();
+        save(gip_dir, "abc123").unwrap();
+        fs::write(gip_dir.join("manifest.json"), "{\"entries\":[]}").unwrap();
+
+        assert!(restore(gip_dir, "abc123").is_err());
+        assert!(saved_path(gip_dir, "abc123").is_some());
+    }
+
+    #[test]
+    fn test_prune() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().join(STASH_DIR);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("live.toon"), "").unwrap();
+        fs::write(dir.join("gone.toon"), "").unwrap();
+
+        assert_eq!(prune(temp_dir.path(), &["live".to_string()]).unwrap(), 1);
+        assert!(dir.join("live.toon").exists());
+        assert!(!dir.join("gone.toon").exists());
+    }
+}
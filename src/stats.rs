@@ -0,0 +1,190 @@
+//! Intent analytics over manifest history
+//!
+//! Aggregates behaviorClass distribution, breaking-change rate, rationale length,
+//! manifest coverage, manifest quality, and the most frequently changed symbols.
+
+use crate::manifest::Manifest;
+use crate::quality;
+use crate::tokens::TokenStats;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// Aggregated statistics over a set of commits
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryStats {
+    pub total_commits: usize,
+    pub commits_with_manifest: usize,
+    pub coverage_pct: f64,
+    pub total_entries: usize,
+    pub behavior_classes: BTreeMap<String, usize>,
+    pub breaking_commits: usize,
+    pub breaking_rate_pct: f64,
+    pub avg_rationale_len: f64,
+    /// Mean manifest quality score (0-100), see [`crate::quality`]
+    pub avg_quality_score: f64,
+    /// Manifests scoring below `[quality] min_score`, when one is set
+    pub below_min_quality: usize,
+    pub top_symbols: Vec<SymbolChurn>,
+    /// Context token costs, with `gip stats --tokens`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<TokenStats>,
+}
+
+/// How often a single symbol appeared in manifests
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolChurn {
+    pub file: String,
+    pub symbol: String,
+    pub changes: usize,
+}
+
+impl HistoryStats {
+    /// Compute statistics for `total_commits` commits, of which `manifests` carry context
+    ///
+    /// `top` limits how many of the most-churned symbols are reported.
+    pub fn compute(total_commits: usize, manifests: &[Manifest], top: usize) -> Self {
+        let mut stats = HistoryStats {
+            total_commits,
+            commits_with_manifest: manifests.len(),
+            ..Default::default()
+        };
+
+        let mut rationale_total = 0usize;
+        let mut churn: HashMap<(String, String), usize> = HashMap::new();
+
+        for manifest in manifests {
+            if manifest
+                .entries
+                .iter()
+                .any(|e| e.compatibility.as_ref().is_some_and(|c| c.breaking))
+            {
+                stats.breaking_commits += 1;
+            }
+
+            for entry in &manifest.entries {
+                stats.total_entries += 1;
+                rationale_total += entry.rationale.chars().count();
+
+                for class in &entry.behavior_class {
+                    *stats.behavior_classes.entry(class.clone()).or_insert(0) += 1;
+                }
+
+                *churn
+                    .entry((entry.anchor.file.clone(), entry.anchor.symbol.clone()))
+                    .or_insert(0) += 1;
+            }
+        }
+
+        stats.coverage_pct = percent(stats.commits_with_manifest, total_commits);
+        stats.breaking_rate_pct = percent(stats.breaking_commits, stats.commits_with_manifest);
+        if stats.total_entries > 0 {
+            stats.avg_rationale_len = rationale_total as f64 / stats.total_entries as f64;
+        }
+
+        let mut symbols: Vec<SymbolChurn> = churn
+            .into_iter()
+            .map(|((file, symbol), changes)| SymbolChurn {
+                file,
+                symbol,
+                changes,
+            })
+            .collect();
+        // Most changes first, then alphabetical for stable output
+        symbols.sort_by(|a, b| {
+            b.changes
+                .cmp(&a.changes)
+                .then_with(|| a.file.cmp(&b.file))
+                .then_with(|| a.symbol.cmp(&b.symbol))
+        });
+        symbols.truncate(top);
+        stats.top_symbols = symbols;
+
+        stats
+    }
+
+    /// Add quality figures from the scores of the counted manifests
+    pub fn with_quality(mut self, scores: &[u32], min_score: u32) -> Self {
+        self.avg_quality_score = quality::average(scores);
+        self.below_min_quality = scores.iter().filter(|&&s| s < min_score).count();
+        self
+    }
+
+    /// Attach the token costs of the counted manifests
+    pub fn with_tokens(mut self, tokens: TokenStats) -> Self {
+        self.tokens = Some(tokens);
+        self
+    }
+}
+
+/// Percentage of `part` in `whole`, 0 when `whole` is 0
+pub fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / whole as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use crate::manifest::types::*;
+    use serde_json::json;
+
+    fn entry(file: &str, symbol: &str, class: &str, breaking: bool, rationale: &str) -> Entry {
+        test_support::entry(json!({
+            "anchor": { "file": file, "symbol": symbol },
+            "rationale": rationale,
+            "behaviorClass": [class],
+            "compatibility": { "breaking": breaking }
+        }))
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let mut first = Manifest::new("a".to_string());
+        first.entries = vec![
+            entry("src/lib.rs", "parse", BEHAVIOR_FEATURE, true, "abcd"),
+            entry("src/lib.rs", "render", BEHAVIOR_BUGFIX, false, "ab"),
+        ];
+        let mut second = Manifest::new("b".to_string());
+        second.entries = vec![entry(
+            "src/lib.rs",
+            "parse",
+            BEHAVIOR_FEATURE,
+            false,
+            "abcdef",
+        )];
+
+        let stats = HistoryStats::compute(4, &[first, second], 1);
+
+        assert_eq!(stats.commits_with_manifest, 2);
+        assert_eq!(stats.coverage_pct, 50.0);
+        assert_eq!(stats.total_entries, 3);
+        assert_eq!(stats.behavior_classes.get(BEHAVIOR_FEATURE), Some(&2));
+        assert_eq!(stats.breaking_commits, 1);
+        assert_eq!(stats.breaking_rate_pct, 50.0);
+        assert_eq!(stats.avg_rationale_len, 4.0);
+        assert_eq!(stats.top_symbols.len(), 1);
+        assert_eq!(stats.top_symbols[0].symbol, "parse");
+        assert_eq!(stats.top_symbols[0].changes, 2);
+    }
+
+    #[test]
+    fn test_compute_empty_history() {
+        let stats = HistoryStats::compute(0, &[], 5);
+        assert_eq!(stats.coverage_pct, 0.0);
+        assert_eq!(stats.avg_rationale_len, 0.0);
+        assert!(stats.top_symbols.is_empty());
+    }
+
+    #[test]
+    fn test_with_quality() {
+        let stats = HistoryStats::compute(2, &[], 5).with_quality(&[40, 90], 60);
+        assert_eq!(stats.avg_quality_score, 65.0);
+        assert_eq!(stats.below_min_quality, 1);
+    }
+}
@@ -0,0 +1,272 @@
+//! Rationale summaries for conflict markers
+//!
+//! Long rationales and contract lists make enriched conflicts hard to read.
+//! When `[markers] summarize` is set, markers carry a condensed rationale and
+//! the first few items of each list; `gip context` still shows everything.
+//!
+//! ```toml
+//! [markers]
+//! summarize = "rules"        # or "llm" / "off"
+//! max_rationale_chars = 160
+//! max_list_items = 3
+//! llm_command = "llm -m gpt-4o-mini 'Summarize in one short sentence'"
+//! ```
+//!
+//! The `llm` mode pipes each rationale to `llm_command` and uses the first
+//! line it prints. If the command fails or prints nothing, the rule-based
+//! summary is used instead.
+
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Appended to text cut short at a word boundary
+const ELLIPSIS: &str = "…";
+
+/// How marker text is condensed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryMode {
+    /// Markers show everything
+    #[default]
+    Off,
+    /// First sentence, cut at a word boundary
+    Rules,
+    /// Ask `llm_command`, falling back to rules
+    Llm,
+}
+
+/// `[markers]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MarkerConfig {
+    /// Add context to conflict markers at all; `GIP_NO_ENRICH=1` turns it off
+    pub enrich: bool,
+    pub summarize: SummaryMode,
+    pub max_rationale_chars: usize,
+    pub max_list_items: usize,
+    /// Shell command reading a rationale on stdin and printing its summary
+    pub llm_command: Option<String>,
+    /// Files with more conflicts than this only get context for conflicts
+    /// inside a manifest entry's symbol, plus one summary; 0 enriches all
+    pub focus_threshold: usize,
+    /// Merges describe their side with every incoming commit that touched a
+    /// conflicted file, not just the branch tip
+    pub branch_context: bool,
+}
+
+impl Default for MarkerConfig {
+    fn default() -> Self {
+        Self {
+            enrich: true,
+            summarize: SummaryMode::Off,
+            max_rationale_chars: 160,
+            max_list_items: 3,
+            llm_command: None,
+            focus_threshold: 50,
+            branch_context: false,
+        }
+    }
+}
+
+/// Condenses marker text according to a [`MarkerConfig`]
+#[derive(Debug, Default)]
+pub struct Summarizer {
+    config: MarkerConfig,
+    /// LLM summaries by rationale, since both sides often share entries
+    cache: RefCell<HashMap<String, String>>,
+}
+
+impl Summarizer {
+    pub fn new(config: MarkerConfig) -> Self {
+        Self {
+            config,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.summarize != SummaryMode::Off
+    }
+
+    /// The rationale as it should appear in a marker
+    pub fn rationale(&self, text: &str) -> String {
+        let max = self.config.max_rationale_chars;
+        if !self.is_enabled() || text.chars().count() <= max {
+            return text.to_string();
+        }
+
+        if self.config.summarize == SummaryMode::Llm {
+            if let Some(ref command) = self.config.llm_command {
+                let cached = self.cache.borrow().get(text).cloned();
+                let summary = cached.or_else(|| {
+                    let summary = run_llm(command, text)?;
+                    self.cache
+                        .borrow_mut()
+                        .insert(text.to_string(), summary.clone());
+                    Some(summary)
+                });
+                if let Some(summary) = summary {
+                    return condense(&summary, max);
+                }
+            }
+        }
+
+        condense(text, max)
+    }
+
+    /// The items of a list to show, and how many were left out
+    pub fn items<'a>(&self, items: &'a [String]) -> (&'a [String], usize) {
+        let max = self.config.max_list_items;
+        if !self.is_enabled() || items.len() <= max {
+            return (items, 0);
+        }
+        (&items[..max], items.len() - max)
+    }
+}
+
+/// Rule-based summary: the first sentence, cut at a word boundary if still too long
+pub fn condense(text: &str, max_chars: usize) -> String {
+    let text = text.trim();
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+
+    let sentence = first_sentence(text);
+    if sentence.chars().count() <= max_chars {
+        return sentence.to_string();
+    }
+
+    let budget = max_chars.saturating_sub(ELLIPSIS.chars().count());
+    let cut: String = sentence.chars().take(budget).collect();
+    let cut = match cut.rfind(char::is_whitespace) {
+        Some(pos) if pos > 0 => &cut[..pos],
+        _ => cut.as_str(),
+    };
+    format!(
+        "{}{}",
+        cut.trim_end_matches(|c: char| c.is_whitespace() || c == ',' || c == ';'),
+        ELLIPSIS
+    )
+}
+
+/// Text up to the end of the first sentence or line
+fn first_sentence(text: &str) -> &str {
+    let line = text.lines().next().unwrap_or(text);
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let at_break = !matches!(chars.peek(), Some((_, next)) if !next.is_whitespace());
+        if matches!(c, '.' | '!' | '?') && at_break {
+            return &line[..i + c.len_utf8()];
+        }
+    }
+    line
+}
+
+/// Summarize with an external command, `None` if it fails or prints nothing
+fn run_llm(command: &str, text: &str) -> Option<String> {
+    #[cfg(windows)]
+    let mut cmd = Command::new("cmd");
+    #[cfg(windows)]
+    cmd.args(["/C", command]);
+    #[cfg(not(windows))]
+    let mut cmd = Command::new("sh");
+    #[cfg(not(windows))]
+    cmd.args(["-c", command]);
+
+    let mut child = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(text.as_bytes());
+    }
+
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(|l| l.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(max_rationale_chars: usize, max_list_items: usize) -> Summarizer {
+        Summarizer::new(MarkerConfig {
+            summarize: SummaryMode::Rules,
+            max_rationale_chars,
+            max_list_items,
+            llm_command: None,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_condense_keeps_first_sentence() {
+        let text = "Retry payments on gateway timeouts. The gateway drops about 2% of requests \
+                    under load, see the incident review for details.";
+        assert_eq!(condense(text, 40), "Retry payments on gateway timeouts.");
+        // Version numbers are not sentence ends
+        assert_eq!(condense("Bump to v1.2 and pin it", 10), "Bump to…");
+    }
+
+    #[test]
+    fn test_condense_cuts_at_word_boundary() {
+        let text = "Retry payments on gateway timeouts with exponential backoff";
+        let short = condense(text, 30);
+        assert_eq!(short, "Retry payments on gateway…");
+        assert!(short.chars().count() <= 30);
+    }
+
+    #[test]
+    fn test_off_leaves_text_alone() {
+        let summarizer = Summarizer::default();
+        let long = "word ".repeat(100);
+        assert_eq!(summarizer.rationale(&long), long);
+
+        let items: Vec<String> = (0..10).map(|i| i.to_string()).collect();
+        assert_eq!(summarizer.items(&items), (&items[..], 0));
+    }
+
+    #[test]
+    fn test_items_are_capped() {
+        let items: Vec<String> = (0..5).map(|i| i.to_string()).collect();
+        let (kept, omitted) = rules(160, 2).items(&items);
+        assert_eq!(kept, &items[..2]);
+        assert_eq!(omitted, 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_llm_command_and_fallback() {
+        let long = "Retry payments on gateway timeouts. More detail follows here.";
+        let llm = |command: &str| {
+            Summarizer::new(MarkerConfig {
+                summarize: SummaryMode::Llm,
+                max_rationale_chars: 40,
+                max_list_items: 3,
+                llm_command: Some(command.to_string()),
+                ..Default::default()
+            })
+        };
+
+        assert_eq!(
+            llm("echo 'Retries timeouts'").rationale(long),
+            "Retries timeouts"
+        );
+        assert_eq!(
+            llm("exit 1").rationale(long),
+            "Retry payments on gateway timeouts."
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Symbol matching helpers for locating manifest entries in source text
+//!
+//! Anchors may record qualified symbols (`ClassName::method`, `module.func`)
+//! while source lines only contain the plain identifier. These helpers normalize
+//! symbols, compare identifiers fuzzily, and recognize definition lines.
+
+/// Minimum similarity (0.0-1.0) for a fuzzy identifier match
+pub const FUZZY_THRESHOLD: f64 = 0.8;
+
+/// Keywords that introduce a definition in common languages
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "fn",
+    "def",
+    "func",
+    "function",
+    "class",
+    "struct",
+    "enum",
+    "trait",
+    "interface",
+    "impl",
+    "type",
+    "module",
+    "mod",
+];
+
+/// How well a source line matches a symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MatchQuality {
+    /// An identifier is similar to the symbol's name
+    Fuzzy,
+    /// The symbol's name appears as an identifier
+    Exact,
+}
+
+/// Result of matching a symbol against a source line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineMatch {
+    pub quality: MatchQuality,
+    /// The matched identifier is being defined on this line
+    pub is_definition: bool,
+}
+
+/// Split a possibly qualified symbol into its segments
+///
+/// `Payments::Gateway::charge` and `payments.gateway.charge` both yield
+/// `["Payments", "Gateway", "charge"]`-style segments.
+pub fn segments(symbol: &str) -> Vec<&str> {
+    symbol
+        .split("::")
+        .flat_map(|part| part.split('.'))
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// The unqualified name of a symbol (its last segment)
+pub fn base_name(symbol: &str) -> &str {
+    segments(symbol).last().copied().unwrap_or(symbol)
+}
+
+/// The name a signature defines: `charge` for `pub fn charge(amount: u64)`
+///
+/// `None` when nothing but a keyword precedes the parameters, as in Go's
+/// `func (r *Repo) Save()`.
+pub fn signature_name(signature: &str) -> Option<&str> {
+    let head = signature
+        .split(&['(', '<', ':', '{', '='][..])
+        .next()
+        .unwrap_or(signature);
+    identifiers(head)
+        .last()
+        .copied()
+        .filter(|name| !DEFINITION_KEYWORDS.contains(name))
+}
+
+/// Extract identifier tokens from a line of source
+pub fn identifiers(line: &str) -> Vec<&str> {
+    line.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Similarity between two identifiers in `[0.0, 1.0]`
+///
+/// Comparison ignores case and underscores so `process_payment` and
+/// `processPayment` are considered identical.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = fold(a).chars().collect();
+    let b: Vec<char> = fold(b).chars().collect();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / max_len as f64
+}
+
+fn fold(s: &str) -> String {
+    s.chars()
+        .filter(|c| *c != '_')
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+/// Match a (possibly qualified) symbol against a line of source
+pub fn match_line(symbol: &str, line: &str) -> Option<LineMatch> {
+    let name = base_name(symbol);
+    if name.is_empty() {
+        return None;
+    }
+
+    let tokens = identifiers(line);
+    let mut best: Option<(MatchQuality, usize)> = None;
+
+    for (idx, token) in tokens.iter().enumerate() {
+        let quality = if *token == name {
+            MatchQuality::Exact
+        } else if similarity(token, name) >= FUZZY_THRESHOLD {
+            MatchQuality::Fuzzy
+        } else {
+            continue;
+        };
+
+        let better = match best {
+            Some((q, _)) => quality > q,
+            None => true,
+        };
+        if better {
+            best = Some((quality, idx));
+        }
+    }
+
+    let (quality, idx) = best?;
+    let is_definition = is_definition_at(line, &tokens, idx);
+
+    Some(LineMatch {
+        quality,
+        is_definition,
+    })
+}
+
+/// Whether the identifier at `idx` directly follows a definition keyword
+///
+/// Also accepts a receiver between `func` and the name, as in Go's `func (s *T) Name`.
+fn is_definition_at(line: &str, tokens: &[&str], idx: usize) -> bool {
+    if idx > 0 && DEFINITION_KEYWORDS.contains(&tokens[idx - 1]) {
+        return true;
+    }
+    line.trim_start().starts_with("func (") && (2..=3).contains(&idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments_and_base_name() {
+        assert_eq!(segments("Gateway::charge"), vec!["Gateway", "charge"]);
+        assert_eq!(
+            segments("payments.gateway.charge"),
+            vec!["payments", "gateway", "charge"]
+        );
+        assert_eq!(base_name("Gateway::charge"), "charge");
+        assert_eq!(base_name("main"), "main");
+    }
+
+    #[test]
+    fn test_signature_name() {
+        assert_eq!(
+            signature_name("pub fn charge<T>(amount: T) -> Result<()>"),
+            Some("charge")
+        );
+        assert_eq!(signature_name("def refund(self, order):"), Some("refund"));
+        assert_eq!(signature_name("class Gateway:"), Some("Gateway"));
+        assert_eq!(signature_name("func (r *Repo) Save() error"), None);
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("process_payment", "processPayment"), 1.0);
+        assert!(similarity("processPayments", "processPayment") >= FUZZY_THRESHOLD);
+        assert!(similarity("render", "parse") < FUZZY_THRESHOLD);
+    }
+
+    #[test]
+    fn test_match_qualified_definition() {
+        let m = match_line("Gateway::charge", "    pub fn charge(&self) {").unwrap();
+        assert_eq!(m.quality, MatchQuality::Exact);
+        assert!(m.is_definition);
+
+        let call = match_line("Gateway::charge", "        self.charge();").unwrap();
+        assert!(!call.is_definition);
+
+        let py = match_line("billing.refund", "def refund(order):").unwrap();
+        assert!(py.is_definition);
+
+        let go = match_line("Server.Start", "func (s *Server) Start() error {").unwrap();
+        assert!(go.is_definition);
+    }
+
+    #[test]
+    fn test_match_fuzzy() {
+        let m = match_line("process_payment", "fn processPayment() {").unwrap();
+        assert_eq!(m.quality, MatchQuality::Fuzzy);
+        assert!(match_line("parse", "let x = render();").is_none());
+    }
+}
@@ -0,0 +1,371 @@
+//! Manifest templates
+//!
+//! `gip init` and `gip commit` write a template to `.gip/manifest.toon` for the
+//! author to fill in. Repositories can replace the built-in template with
+//! `.gip/templates/manifest.toon`, which may use these variables:
+//!
+//! - `{{branch}}` - the current branch name
+//! - `{{author}}` - `git config user.name`
+//! - `{{ticket}}` - a ticket id extracted from the branch name, e.g. `PAY-142`
+//!   from `feature/PAY-142-retry` or `#87` from `87-fix-login`, or with the
+//!   `[issues] pattern` when one is configured
+//!
+//! When the behavior class is known up front, from a conventional commit
+//! message or a branch named like `perf/...`, class fragments add the fields
+//! that class needs: `security` asks for a threat model, `perf` adds a budget,
+//! `migration` asks for upgrade steps and rollback notes. A fragment in
+//! `.gip/templates/<class>.{toon,yaml,yml,json}` replaces the built-in one.
+//!
+//! Repositories without their own template or fragment use the ones in
+//! `~/.config/gip/templates/` (see [`crate::global`]).
+
+use crate::config::Config;
+use crate::git;
+use crate::global;
+use crate::manifest::{
+    Entry, Manifest, ManifestFormat, BEHAVIOR_MIGRATION, BEHAVIOR_PERF, BEHAVIOR_SECURITY,
+};
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Built-in manifest template
+pub const DEFAULT_TEMPLATE: &str = r#"; Gip Manifest Template
+; This file describes the semantic intent of your changes.
+; It is used to enrich merge conflicts with context.
+;
+; INSTRUCTIONS FOR LLM/AGENTS:
+; 1. Analyze the code changes in the current commit.
+; 2. Update the fields below to reflect the actual changes.
+; 3. 'rationale' should explain WHY the change was made.
+; 4. 'behaviorClass' options: feature, bugfix, refactor, perf, security, config.
+; 5. 'changeType' options: add, modify, delete, rename.
+;    For a rename, put the old and new signatures in 'signatureDelta'.
+; 6. Remove these instruction comments if desired, but keep the structure.
+
+schemaVersion: "2.0"
+commit: HEAD
+entries[1]:
+  - anchor:
+      file: src/main.rs
+      symbol: main
+      hunkId: H#1
+    changeType: modify
+    rationale: Describe your changes here
+    behaviorClass[1]: feature
+    contract:
+      preconditions[1]: none
+      postconditions[1]: program_runs
+      errorModel[1]: panic_on_error
+"#;
+
+/// Placeholder rationale that marks a manifest as not filled in
+pub const PLACEHOLDER: &str = "Describe your changes here";
+
+/// Prefix of questions in class fragments that still need an answer
+pub const PROMPT_MARKER: &str = "FILL IN:";
+
+/// Path of the repository's custom template inside `.gip/`
+pub fn custom_template_path(gip_dir: &Path) -> PathBuf {
+    gip_dir.join("templates").join("manifest.toon")
+}
+
+/// Values substituted into a template
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TemplateVars {
+    pub branch: Option<String>,
+    pub author: Option<String>,
+    pub ticket: Option<String>,
+}
+
+impl TemplateVars {
+    /// Read the variables from the current repository
+    pub fn detect() -> Self {
+        let branch = git::run_git_cmd(&["rev-parse", "--abbrev-ref", "HEAD"], None)
+            .ok()
+            .filter(|b| b != "HEAD");
+        let author = git::run_git_cmd(&["config", "user.name"], None).ok();
+        // A configured [issues] pattern takes over from the built-in guess
+        let issues = git::get_gip_dir()
+            .ok()
+            .and_then(|dir| Config::load(&dir).ok())
+            .map(|config| config.issues)
+            .unwrap_or_default();
+        let ticket = branch.as_deref().and_then(|b| issues.ticket(b));
+
+        Self {
+            branch,
+            author,
+            ticket,
+        }
+    }
+}
+
+/// Extract a ticket id from a branch name
+pub fn ticket_from_branch(branch: &str) -> Option<String> {
+    let key = Regex::new(r"(?i)(?:^|[/_-])([a-z][a-z0-9]+-\d+)").expect("valid ticket regex");
+    if let Some(c) = key.captures(branch) {
+        return Some(c[1].to_uppercase());
+    }
+
+    let number = Regex::new(r"(?:^|/)#?(\d+)(?:[-_]|$)").expect("valid issue regex");
+    number.captures(branch).map(|c| format!("#{}", &c[1]))
+}
+
+/// Substitute `{{name}}` variables; unknown values become empty strings
+pub fn render(template: &str, vars: &TemplateVars) -> String {
+    template
+        .replace("{{branch}}", vars.branch.as_deref().unwrap_or(""))
+        .replace("{{author}}", vars.author.as_deref().unwrap_or(""))
+        .replace("{{ticket}}", vars.ticket.as_deref().unwrap_or(""))
+}
+
+/// The repository's template source: its custom one, else the machine's, else the default
+pub fn load_source(gip_dir: &Path) -> Result<String> {
+    let machine = global::dir().map(|dir| custom_template_path(&dir));
+    for path in [Some(custom_template_path(gip_dir)), machine]
+        .into_iter()
+        .flatten()
+    {
+        if path.exists() {
+            return fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read template from {:?}", path));
+        }
+    }
+    Ok(DEFAULT_TEMPLATE.to_string())
+}
+
+/// The template for the current repository with variables filled in
+pub fn render_for_repo(gip_dir: &Path) -> Result<String> {
+    Ok(render(&load_source(gip_dir)?, &TemplateVars::detect()))
+}
+
+/// Whether a manifest is an untouched copy of the template
+pub fn is_unedited(content: &str, gip_dir: &Path) -> bool {
+    let normalize = |s: &str| s.replace("\r\n", "\n").trim().to_string();
+    let content = normalize(content);
+
+    content == normalize(DEFAULT_TEMPLATE)
+        || render_for_repo(gip_dir).is_ok_and(|t| content == normalize(&t))
+}
+
+/// Built-in fields added to entries of a behavior class
+pub fn builtin_fragment(class: &str) -> Option<Value> {
+    let fragment = match class {
+        BEHAVIOR_SECURITY => json!({
+            "securityNotes": [format!(
+                "{} threat model - who could abuse this code path, and what stops them?",
+                PROMPT_MARKER
+            )]
+        }),
+        BEHAVIOR_PERF => json!({
+            "perfBudget": {"expectedMaxLatencyMs": 0, "cpuDeltaPct": 0}
+        }),
+        BEHAVIOR_MIGRATION => json!({
+            "compatibility": {
+                "breaking": false,
+                "migrations": [format!("{} steps users take to upgrade", PROMPT_MARKER)]
+            },
+            "rollback": format!("{} how to roll this change back", PROMPT_MARKER)
+        }),
+        _ => return None,
+    };
+    Some(fragment)
+}
+
+/// The fragment for a behavior class: the repository's own, else the machine's, else the built-in
+pub fn load_fragment(gip_dir: &Path, class: &str) -> Result<Option<Value>> {
+    let dirs = [Some(gip_dir.to_path_buf()), global::dir()];
+    for dir in dirs.iter().flatten() {
+        for ext in ["toon", "yaml", "yml", "json"] {
+            let path = dir.join("templates").join(format!("{}.{}", class, ext));
+            if !path.is_file() {
+                continue;
+            }
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read fragment {:?}", path))?;
+            let fragment = ManifestFormat::from_path(&path)
+                .parse(&content)
+                .with_context(|| format!("Failed to parse fragment {:?}", path))?;
+            return Ok(Some(fragment));
+        }
+    }
+    Ok(builtin_fragment(class))
+}
+
+/// Fill fields the value doesn't have yet from a fragment
+///
+/// Fields the author already set, including non-empty lists, are kept.
+fn fill_missing(target: &mut Value, fragment: &Value) {
+    let (Value::Object(target), Value::Object(fragment)) = (target, fragment) else {
+        return;
+    };
+    for (key, value) in fragment {
+        match target.get_mut(key) {
+            Some(existing) if existing.is_object() => fill_missing(existing, value),
+            Some(Value::Null) | None => {
+                target.insert(key.clone(), value.clone());
+            }
+            Some(Value::Array(items)) if items.is_empty() => {
+                target.insert(key.clone(), value.clone());
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+/// Add the fragment of each of an entry's behavior classes
+pub fn apply_fragments(entry: &Entry, gip_dir: &Path) -> Result<Entry> {
+    let mut value = serde_json::to_value(entry)?;
+    for class in &entry.behavior_class {
+        if let Some(fragment) = load_fragment(gip_dir, class)? {
+            fill_missing(&mut value, &fragment);
+        }
+    }
+    serde_json::from_value(value).context("Class fragment does not fit a manifest entry")
+}
+
+/// Behavior class named by a branch prefix, e.g. `security/rotate-keys`
+pub fn class_from_branch(branch: &str) -> Option<&'static str> {
+    let prefix = branch.split('/').next()?;
+    Manifest::all_behavior_classes()
+        .into_iter()
+        .find(|c| *c == prefix && branch.len() > prefix.len())
+}
+
+fn has_rollback(entry: &Entry) -> bool {
+    entry
+        .extra
+        .get("rollback")
+        .and_then(|v| v.as_str())
+        .is_some_and(|s| !s.trim().is_empty())
+}
+
+/// Fields a class requires that an entry leaves out
+pub fn missing_required(manifest: &Manifest) -> Vec<String> {
+    manifest
+        .entries
+        .iter()
+        .filter(|e| e.behavior_class.iter().any(|c| c == BEHAVIOR_MIGRATION))
+        .filter(|e| !has_rollback(e))
+        .map(|e| {
+            format!(
+                "{} ({}): migrations need rollback notes",
+                e.anchor.symbol, e.anchor.file
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    #[test]
+    fn test_ticket_from_branch() {
+        assert_eq!(
+            ticket_from_branch("feature/PAY-142-retry").as_deref(),
+            Some("PAY-142")
+        );
+        assert_eq!(
+            ticket_from_branch("bugfix/pay-7_typo").as_deref(),
+            Some("PAY-7")
+        );
+        assert_eq!(ticket_from_branch("87-fix-login").as_deref(), Some("#87"));
+        assert_eq!(ticket_from_branch("main"), None);
+        assert_eq!(ticket_from_branch("release/v2"), None);
+    }
+
+    #[test]
+    fn test_render_variables() {
+        let vars = TemplateVars {
+            branch: Some("feature/PAY-142-retry".to_string()),
+            author: Some("Ada".to_string()),
+            ticket: Some("PAY-142".to_string()),
+        };
+        let out = render("rationale: {{ticket}} on {{branch}} by {{author}}", &vars);
+        assert_eq!(out, "rationale: PAY-142 on feature/PAY-142-retry by Ada");
+
+        let empty = render("ticket: {{ticket}}", &TemplateVars::default());
+        assert_eq!(empty, "ticket: ");
+    }
+
+    #[test]
+    fn test_custom_template_takes_precedence() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(load_source(temp_dir.path()).unwrap(), DEFAULT_TEMPLATE);
+
+        let path = custom_template_path(temp_dir.path());
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "custom {{ticket}}").unwrap();
+        assert_eq!(load_source(temp_dir.path()).unwrap(), "custom {{ticket}}");
+    }
+
+    fn entry(classes: &[&str]) -> Entry {
+        test_support::entry(json!({
+            "anchor": { "file": "src/db.rs", "symbol": "migrate" },
+            "rationale": "Split the name column",
+            "behaviorClass": classes,
+            "securityNotes": []
+        }))
+    }
+
+    #[test]
+    fn test_apply_builtin_fragments() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let filled = apply_fragments(&entry(&["security", "migration"]), temp_dir.path()).unwrap();
+
+        let notes = filled.security_notes.unwrap();
+        assert!(notes[0].starts_with(PROMPT_MARKER));
+        assert!(filled.compatibility.unwrap().migrations.unwrap()[0].starts_with(PROMPT_MARKER));
+        assert!(filled.extra["rollback"]
+            .as_str()
+            .unwrap()
+            .starts_with(PROMPT_MARKER));
+        assert!(filled.perf_budget.is_none());
+        assert_eq!(filled.rationale, "Split the name column");
+    }
+
+    #[test]
+    fn test_custom_fragment_replaces_builtin() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dir = temp_dir.path().join("templates");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("perf.json"),
+            r#"{"perfBudget": {"expectedMaxLatencyMs": 50}}"#,
+        )
+        .unwrap();
+
+        let filled = apply_fragments(&entry(&["perf"]), temp_dir.path()).unwrap();
+        let budget = filled.perf_budget.unwrap();
+        assert_eq!(budget.expected_max_latency_ms, Some(50));
+        assert_eq!(budget.cpu_delta_pct, None);
+    }
+
+    #[test]
+    fn test_class_from_branch() {
+        assert_eq!(class_from_branch("perf/faster-parse"), Some("perf"));
+        assert_eq!(class_from_branch("security/rotate-keys"), Some("security"));
+        assert_eq!(class_from_branch("feature/PAY-1"), Some("feature"));
+        assert_eq!(class_from_branch("perf"), None);
+        assert_eq!(class_from_branch("main"), None);
+    }
+
+    #[test]
+    fn test_migrations_need_rollback() {
+        let mut manifest = Manifest::new("abc".to_string());
+        manifest.entries = vec![entry(&["migration"]), entry(&["feature"])];
+        assert_eq!(missing_required(&manifest).len(), 1);
+
+        manifest.entries[0].extra.insert(
+            "rollback".to_string(),
+            json!("Restore the column from backup"),
+        );
+        assert!(missing_required(&manifest).is_empty());
+    }
+}
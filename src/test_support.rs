@@ -0,0 +1,30 @@
+//! Git fixtures shared by unit tests
+
+use crate::git;
+use std::path::Path;
+
+/// Create a repository at `dir` on `main`, with an identity to commit as
+pub fn init_repo(dir: &Path) {
+    git::run_git_cmd(&["init", "-q", "-b", "main"], Some(dir)).unwrap();
+    set_identity(dir);
+}
+
+/// Clone `upstream` to `dir`, with an identity to commit as
+pub fn clone_repo(upstream: &Path, dir: &Path) {
+    git::run_git_cmd(
+        &[
+            "clone",
+            "-q",
+            upstream.to_str().unwrap(),
+            dir.to_str().unwrap(),
+        ],
+        None,
+    )
+    .unwrap();
+    set_identity(dir);
+}
+
+fn set_identity(dir: &Path) {
+    git::run_git_cmd(&["config", "user.name", "t"], Some(dir)).unwrap();
+    git::run_git_cmd(&["config", "user.email", "t@t"], Some(dir)).unwrap();
+}
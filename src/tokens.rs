@@ -0,0 +1,202 @@
+//! Token estimates for LLM budgets
+//!
+//! Approximates the `cl100k_base` tokenizer used by tiktoken without shipping
+//! its vocabulary. Text is split the way tiktoken pre-tokenizes it (letter
+//! runs with an optional leading space, digit groups of up to three,
+//! punctuation runs, whitespace runs), then each piece is charged by length.
+//! The result is meant for budgeting, not for exact counts.
+
+use crate::manifest::{serialize_manifest_toon, Manifest};
+use crate::merge;
+use serde::Serialize;
+
+/// Letters a common word piece covers before it splits into more tokens
+const CHARS_PER_WORD_TOKEN: usize = 6;
+
+/// Characters per token in punctuation runs such as `::`, `();` or `=>`
+const CHARS_PER_PUNCT_TOKEN: usize = 2;
+
+/// Digits tiktoken groups into one token
+const DIGITS_PER_TOKEN: usize = 3;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Piece {
+    Word,
+    Digits,
+    Punct,
+    Space,
+}
+
+fn piece_of(c: char) -> Piece {
+    if c.is_whitespace() {
+        Piece::Space
+    } else if c.is_ascii_digit() {
+        Piece::Digits
+    } else if c.is_alphabetic() || c == '_' {
+        Piece::Word
+    } else {
+        Piece::Punct
+    }
+}
+
+/// Estimated number of tokens in `text`
+pub fn estimate(text: &str) -> usize {
+    let mut total = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let mut kind = piece_of(c);
+        let mut len: usize = 1;
+        let mut wide = !c.is_ascii() && kind == Piece::Word;
+
+        // A single space attaches to the word or punctuation that follows
+        if c == ' ' {
+            if let Some(&next) = chars.peek() {
+                if !next.is_whitespace() {
+                    kind = piece_of(next);
+                    wide = !next.is_ascii() && kind == Piece::Word;
+                    chars.next();
+                }
+            }
+        }
+
+        while let Some(&next) = chars.peek() {
+            if piece_of(next) != kind {
+                break;
+            }
+            wide |= !next.is_ascii() && kind == Piece::Word;
+            len += 1;
+            chars.next();
+        }
+
+        total += match kind {
+            // Non-Latin scripts cost roughly one token per character
+            Piece::Word if wide => len,
+            Piece::Word => len.div_ceil(CHARS_PER_WORD_TOKEN),
+            Piece::Digits => len.div_ceil(DIGITS_PER_TOKEN),
+            Piece::Punct => len.div_ceil(CHARS_PER_PUNCT_TOKEN),
+            Piece::Space => 1,
+        };
+    }
+
+    total
+}
+
+/// Tokens a commit's manifest costs when handed to a model as context
+pub fn manifest_cost(manifest: &Manifest) -> usize {
+    serialize_manifest_toon(manifest)
+        .map(|toon| estimate(&toon))
+        .unwrap_or(0)
+}
+
+/// Tokens of Gip context lines inside the conflict markers of `content`
+pub fn enrichment_cost(content: &str) -> usize {
+    content
+        .lines()
+        .filter(|l| merge::is_context_line(l))
+        .map(|l| estimate(l) + 1)
+        .sum()
+}
+
+/// Context cost of one commit
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitTokens {
+    pub commit: String,
+    pub tokens: usize,
+}
+
+/// Enrichment added to one conflicted file
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTokens {
+    pub file: String,
+    pub tokens: usize,
+}
+
+/// Token costs reported by `gip stats --tokens`
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenStats {
+    pub total: usize,
+    pub avg_per_commit: f64,
+    /// Costliest commits first
+    pub commits: Vec<CommitTokens>,
+    /// Files currently conflicted, with the tokens their markers gained
+    pub conflicts: Vec<FileTokens>,
+}
+
+impl TokenStats {
+    /// Cost every manifest, keeping the `top` costliest commits
+    pub fn compute(manifests: &[Manifest], top: usize) -> Self {
+        let mut commits: Vec<CommitTokens> = manifests
+            .iter()
+            .map(|m| CommitTokens {
+                commit: m.commit.clone(),
+                tokens: manifest_cost(m),
+            })
+            .collect();
+
+        let total: usize = commits.iter().map(|c| c.tokens).sum();
+        let avg_per_commit = if commits.is_empty() {
+            0.0
+        } else {
+            total as f64 / commits.len() as f64
+        };
+
+        // Stable sort keeps history order between equal costs
+        commits.sort_by_key(|c| std::cmp::Reverse(c.tokens));
+        commits.truncate(top);
+
+        TokenStats {
+            total,
+            avg_per_commit,
+            commits,
+            conflicts: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_words_and_spaces() {
+        assert_eq!(estimate(""), 0);
+        assert_eq!(estimate("hello"), 1);
+        assert_eq!(estimate("hello world"), 2);
+        // Long identifiers split into several pieces
+        assert_eq!(estimate("process_payment_request"), 4);
+    }
+
+    #[test]
+    fn test_estimate_digits_and_punctuation() {
+        assert_eq!(estimate("1234567"), 3);
+        assert_eq!(estimate("foo();"), 3);
+        assert_eq!(estimate("a\n\n\nb"), 3);
+    }
+
+    #[test]
+    fn test_estimate_wide_scripts() {
+        assert_eq!(estimate("日本語"), 3);
+    }
+
+    #[test]
+    fn test_estimate_code_is_close_to_tiktoken() {
+        // cl100k_base encodes this line in about 14 tokens
+        let line = "fn charge(amount: u64) -> Result<(), Error> {";
+        let estimate = estimate(line) as i64;
+        assert!((estimate - 14).abs() <= 3, "estimate was {}", estimate);
+    }
+
+    #[test]
+    fn test_enrichment_cost_counts_context_lines_only() {
+        let content = "<<<<<<< HEAD\n||| rationale: Retry\nretry();\n=======\n>>>>>>> x\n";
+        assert_eq!(
+            enrichment_cost(content),
+            estimate("||| rationale: Retry") + 1
+        );
+        assert_eq!(enrichment_cost("fn a() {}\n"), 0);
+    }
+}
@@ -0,0 +1,341 @@
+//! Issue titles and statuses for enriched markers
+//!
+//! When a manifest lists issue ids (see [`crate::issues`]) and a tracker is
+//! configured, each conflict marker gets one line per issue, giving resolvers
+//! the product context behind a side:
+//!
+//! ```text
+//! ||| issue: PAY-142 [In Progress] Retry failed card payments
+//! ```
+//!
+//! ```toml
+//! [issues]
+//! tracker = "github"                              # or "jira"
+//! api = "https://api.github.com/repos/acme/shop"  # Jira: "https://jira.example.com"
+//! cache_hours = 24
+//! ```
+//!
+//! Issues are fetched with `curl`, authenticated by `GITHUB_TOKEN`, or by
+//! `JIRA_TOKEN` (with `JIRA_USER` for Jira Cloud). Any other tracker works
+//! through `fetch_command`, which prints the issue named by `$GIP_ISSUE_ID` as
+//! JSON with `title` and `status`. Fetched issues are kept in
+//! `.gip/cache/issues.json`; an issue that can't be fetched is left out of the
+//! marker and never fails a merge.
+//!
+//! Ids come from notes anyone can push, so an id that isn't one (see
+//! [`IssuesConfig::is_valid_id`]) is never fetched. The id is handed to
+//! `fetch_command` in the environment rather than pasted into it.
+
+use crate::atomic;
+use crate::git;
+use crate::issues::{IssuesConfig, TrackerKind};
+use crate::summarize;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// File inside `.gip/` holding fetched issues
+pub const ISSUES_CACHE_FILE: &str = "cache/issues.json";
+
+/// Longest issue title shown in a marker
+const MAX_TITLE_CHARS: usize = 100;
+
+/// Seconds a tracker gets to answer
+const FETCH_TIMEOUT_SECS: &str = "5";
+
+/// Environment variable holding the issue id for `fetch_command`
+pub const ISSUE_ID_VAR: &str = "GIP_ISSUE_ID";
+
+/// What a marker shows of an issue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssueSummary {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Unix time the issue was fetched
+    #[serde(default)]
+    pub fetched: i64,
+}
+
+impl IssueSummary {
+    /// The marker line describing issue `id`
+    pub fn line(&self, id: &str) -> String {
+        match self.status {
+            Some(ref status) => format!("{} [{}] {}", id, status, self.title),
+            None => format!("{} {}", id, self.title),
+        }
+    }
+}
+
+/// Read an issue from GitHub, Jira, or `fetch_command` JSON
+pub fn parse_issue(json: &str) -> Option<IssueSummary> {
+    let value: Value = serde_json::from_str(json).ok()?;
+    // Jira nests everything under `fields`
+    let fields = value.get("fields").unwrap_or(&value);
+    let title = fields
+        .get("title")
+        .or_else(|| fields.get("summary"))?
+        .as_str()?;
+    let title = summarize::condense(title.lines().next().unwrap_or(""), MAX_TITLE_CHARS);
+    if title.is_empty() {
+        return None;
+    }
+    let status = fields
+        .get("status")
+        .and_then(|s| s.get("name").unwrap_or(s).as_str())
+        .or_else(|| value.get("state").and_then(Value::as_str))
+        .map(|s| s.to_string());
+    Some(IssueSummary {
+        title,
+        status,
+        fetched: 0,
+    })
+}
+
+/// Fetches issues, remembering them for the run and on disk
+#[derive(Debug, Default)]
+pub struct IssueTracker {
+    config: IssuesConfig,
+    cache_path: Option<PathBuf>,
+    /// Issues by id; `None` when fetching failed during this run
+    cache: RefCell<HashMap<String, Option<IssueSummary>>>,
+}
+
+impl IssueTracker {
+    /// A tracker for the current repository, caching in its `.gip/`
+    pub fn new(config: IssuesConfig) -> Self {
+        let mut tracker = Self {
+            config,
+            ..Default::default()
+        };
+        if !tracker.is_enabled() {
+            return tracker;
+        }
+        let cache_path = git::get_gip_dir()
+            .ok()
+            .map(|dir| dir.join(ISSUES_CACHE_FILE));
+        let cached: HashMap<String, IssueSummary> = cache_path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        tracker.cache = RefCell::new(cached.into_iter().map(|(k, v)| (k, Some(v))).collect());
+        tracker.cache_path = cache_path;
+        tracker
+    }
+
+    /// Whether issues are fetched at all
+    pub fn is_enabled(&self) -> bool {
+        self.config.fetch_command.is_some()
+            || (self.config.tracker.is_some() && self.config.api.is_some())
+    }
+
+    /// The title and status of an issue, fetched when not cached or stale
+    pub fn summary(&self, id: &str) -> Option<IssueSummary> {
+        if !self.is_enabled() {
+            return None;
+        }
+        let now = chrono::Utc::now().timestamp();
+        let max_age = self.config.cache_hours.saturating_mul(3600) as i64;
+        if let Some(cached) = self.cache.borrow().get(id) {
+            match cached {
+                Some(issue) if now - issue.fetched < max_age => return Some(issue.clone()),
+                None => return None,
+                Some(_) => {}
+            }
+        }
+
+        let fetched = self.fetch(id).map(|issue| IssueSummary {
+            fetched: now,
+            ..issue
+        });
+        self.cache
+            .borrow_mut()
+            .insert(id.to_string(), fetched.clone());
+        if fetched.is_some() {
+            self.save();
+        }
+        fetched
+    }
+
+    fn fetch(&self, id: &str) -> Option<IssueSummary> {
+        if !self.config.is_valid_id(id) {
+            debug!("Not fetching '{}': not an issue id", id);
+            return None;
+        }
+        let output = match (&self.config.fetch_command, self.config.tracker) {
+            (Some(command), _) => run_shell(command, id)?,
+            (None, Some(kind)) => curl(kind, self.config.api.as_deref()?, id)?,
+            (None, None) => return None,
+        };
+        parse_issue(&output)
+    }
+
+    /// Best effort: a failed write only costs a fetch next time
+    fn save(&self) {
+        let Some(ref path) = self.cache_path else {
+            return;
+        };
+        let known: HashMap<String, IssueSummary> = self
+            .cache
+            .borrow()
+            .iter()
+            .filter_map(|(id, issue)| Some((id.clone(), issue.clone()?)))
+            .collect();
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Ok(data) = serde_json::to_string_pretty(&known) {
+            let _ = atomic::write(path, data);
+        }
+    }
+}
+
+/// Fetch an issue from a tracker's REST API
+fn curl(kind: TrackerKind, api: &str, id: &str) -> Option<String> {
+    let api = api.trim_end_matches('/');
+    let mut headers = Vec::new();
+    let url = match kind {
+        TrackerKind::Github => {
+            headers.push("Accept: application/vnd.github+json".to_string());
+            if let Ok(token) = std::env::var("GITHUB_TOKEN") {
+                headers.push(format!("Authorization: Bearer {}", token));
+            }
+            format!("{}/issues/{}", api, encode(id.trim_start_matches('#')))
+        }
+        TrackerKind::Jira => {
+            match (std::env::var("JIRA_USER"), std::env::var("JIRA_TOKEN")) {
+                (Ok(user), Ok(token)) => headers.push(format!(
+                    "Authorization: Basic {}",
+                    base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, token))
+                )),
+                (Err(_), Ok(token)) => headers.push(format!("Authorization: Bearer {}", token)),
+                _ => {}
+            }
+            format!(
+                "{}/rest/api/2/issue/{}?fields=summary,status",
+                api,
+                encode(id)
+            )
+        }
+    };
+
+    // Headers go through stdin so tokens never show up in the process list;
+    // -g keeps curl from reading brackets and braces in the URL as globs
+    let mut child = Command::new("curl")
+        .args(["-fsSLg", "--max-time", FETCH_TIMEOUT_SECS, "-H", "@-", &url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(headers.join("\n").as_bytes());
+    }
+    let output = child.wait_with_output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Percent-encode `text` for one segment of a URL path
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn run_shell(command: &str, id: &str) -> Option<String> {
+    #[cfg(windows)]
+    let mut shell = {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    };
+    #[cfg(not(windows))]
+    let mut shell = {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    };
+    let output = shell.env(ISSUE_ID_VAR, id).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_issue() {
+        let github =
+            parse_issue(r#"{"number":87,"title":"Login fails on Safari","state":"open"}"#).unwrap();
+        assert_eq!(github.line("#87"), "#87 [open] Login fails on Safari");
+
+        let jira = parse_issue(
+            r#"{"key":"PAY-142","fields":{"summary":"Retry failed card payments","status":{"name":"In Progress"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            jira.line("PAY-142"),
+            "PAY-142 [In Progress] Retry failed card payments"
+        );
+
+        let plain = parse_issue(r#"{"title":"Speed up search"}"#).unwrap();
+        assert_eq!(plain.line("X-1"), "X-1 Speed up search");
+
+        assert!(parse_issue("not json").is_none());
+        assert!(parse_issue(r#"{"message":"Not Found"}"#).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_summary_uses_fetch_command_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let count = dir.path().join("count");
+        let tracker = IssueTracker {
+            config: IssuesConfig {
+                fetch_command: Some(format!(
+                    r#"echo x >> {}; printf '{{"title":"Issue %s","status":"Done"}}' "$GIP_ISSUE_ID""#,
+                    count.display()
+                )),
+                ..Default::default()
+            },
+            cache_path: Some(dir.path().join(ISSUES_CACHE_FILE)),
+            cache: RefCell::default(),
+        };
+
+        let issue = tracker.summary("PAY-1").unwrap();
+        assert_eq!(issue.line("PAY-1"), "PAY-1 [Done] Issue PAY-1");
+        assert_eq!(tracker.summary("PAY-1"), Some(issue));
+        assert_eq!(fs::read_to_string(&count).unwrap().lines().count(), 1);
+        assert!(dir.path().join(ISSUES_CACHE_FILE).exists());
+        assert!(IssueTracker::default().summary("PAY-1").is_none());
+
+        // An id from a note is never run as part of the command
+        assert!(tracker.summary("PAY-2; echo x >> count").is_none());
+        assert_eq!(fs::read_to_string(&count).unwrap().lines().count(), 1);
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("PAY-142"), "PAY-142");
+        assert_eq!(encode("a/b?c#d"), "a%2Fb%3Fc%23d");
+    }
+}
@@ -0,0 +1,194 @@
+//! `Gip-Context:` commit trailers
+//!
+//! Some hosting workflows (squash merges, mirrors) drop `refs/notes/gip`. With
+//! trailers enabled, `gip commit` also records the manifest in the commit
+//! message, either as the full manifest (`payload`, base64-encoded TOON) or as
+//! a content hash (`hash`) that identifies a note surviving elsewhere, e.g. on
+//! the original branch commit. [`crate::manifest::load`] falls back to these
+//! trailers when a commit has no note.
+
+use crate::git;
+use crate::manifest::{self, Manifest};
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::path::Path;
+use toon_format::{decode, DecodeOptions};
+
+/// Trailer key in commit messages
+pub const TRAILER_KEY: &str = "Gip-Context";
+
+const HASH_PREFIX: &str = "sha256:";
+
+/// What to embed in the commit message
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrailerMode {
+    /// No trailer; notes only
+    #[default]
+    Off,
+    /// The whole manifest, base64-encoded TOON
+    Payload,
+    /// A content hash of the manifest
+    Hash,
+}
+
+/// `[trailer]` section of `.gip/config.toml`
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TrailerConfig {
+    pub mode: TrailerMode,
+}
+
+/// Content hash of a manifest, independent of the commit it's attached to
+pub fn digest(manifest: &Manifest) -> Result<String> {
+    let mut normalized = manifest.clone();
+    normalized.commit = String::new();
+    let toon = manifest::serialize_manifest_toon(&normalized)?;
+
+    let hash = Sha256::digest(toon.as_bytes());
+    let hex: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+    Ok(format!("{}{}", HASH_PREFIX, hex))
+}
+
+/// Trailer value for a manifest, or `None` when trailers are off
+pub fn encode(manifest: &Manifest, mode: TrailerMode) -> Result<Option<String>> {
+    match mode {
+        TrailerMode::Off => Ok(None),
+        TrailerMode::Hash => digest(manifest).map(Some),
+        TrailerMode::Payload => {
+            let toon = manifest::serialize_manifest_toon(manifest)?;
+            Ok(Some(
+                base64::engine::general_purpose::STANDARD.encode(toon.as_bytes()),
+            ))
+        }
+    }
+}
+
+/// Append a trailer to a commit message
+pub fn append_to_message(message: &str, value: &str) -> String {
+    format!("{}\n\n{}: {}", message.trim_end(), TRAILER_KEY, value)
+}
+
+/// All `Gip-Context` trailer values in a commit message
+///
+/// A squash merge concatenates the messages of the squashed commits, so there
+/// may be several.
+pub fn parse_values(message: &str) -> Vec<String> {
+    let prefix = format!("{}:", TRAILER_KEY);
+    message
+        .lines()
+        .filter_map(|l| l.trim().strip_prefix(&prefix))
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .collect()
+}
+
+/// Decode one trailer value into a manifest
+pub fn decode_value(value: &str, cwd: Option<&Path>) -> Result<Manifest> {
+    if value.starts_with(HASH_PREFIX) {
+        return find_by_digest(value, cwd);
+    }
+
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(value)
+        .context("Invalid base64 in Gip-Context trailer")?;
+    let toon = String::from_utf8(bytes).context("Invalid UTF-8 in Gip-Context trailer")?;
+    let opts = DecodeOptions::new().with_strict(false);
+    decode(&toon, &opts).context("Failed to parse manifest in Gip-Context trailer")
+}
+
+/// Find a noted manifest whose content hash matches
+fn find_by_digest(wanted: &str, cwd: Option<&Path>) -> Result<Manifest> {
+    for commit in git::list_noted_commits(cwd)? {
+        if let Ok(m) = manifest::load_raw(&commit, cwd) {
+            if digest(&m).is_ok_and(|d| d == wanted) {
+                return Ok(m);
+            }
+        }
+    }
+    anyhow::bail!("No gip note matches {}", wanted)
+}
+
+/// Content hashes named by `hash` trailers on commits any ref reaches
+///
+/// The notes they identify are what those commits' manifests are read from.
+pub fn referenced_digests(cwd: Option<&Path>) -> Result<HashSet<String>> {
+    let grep = format!("--grep=^{}: {}", TRAILER_KEY, HASH_PREFIX);
+    let messages = git::run_git_cmd(&["log", "--all", "--format=%B", &grep], cwd)?;
+    Ok(parse_values(&messages)
+        .into_iter()
+        .filter(|v| v.starts_with(HASH_PREFIX))
+        .collect())
+}
+
+/// Rebuild a commit's manifest from its `Gip-Context` trailers
+///
+/// Several trailers (from a squash merge) are combined into one manifest.
+pub fn load_from_commit(commit_sha: &str, cwd: Option<&Path>) -> Result<Manifest> {
+    let message = git::run_git_cmd(&["log", "-1", "--format=%B", commit_sha], cwd)?;
+    let values = parse_values(&message);
+    if values.is_empty() {
+        anyhow::bail!("Commit {} has no {} trailer", commit_sha, TRAILER_KEY);
+    }
+
+    let mut combined: Option<Manifest> = None;
+    for value in &values {
+        let manifest = decode_value(value, cwd)?;
+        match combined {
+            Some(ref mut c) => c.entries.extend(manifest.entries),
+            None => combined = Some(manifest),
+        }
+    }
+
+    let mut manifest = combined.expect("at least one trailer");
+    manifest.commit = git::run_git_cmd(&["rev-parse", commit_sha], cwd)?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn manifest(commit: &str, rationale: &str) -> Manifest {
+        test_support::manifest(commit, json!({ "rationale": rationale }))
+    }
+
+    #[test]
+    fn test_parse_values() {
+        let message = "feat: squash\n\n* first\n\nGip-Context: abc\n\n* second\n\nGip-Context: sha256:00\nSigned-off-by: dev";
+        assert_eq!(parse_values(message), vec!["abc", "sha256:00"]);
+        assert!(parse_values("fix: no trailer").is_empty());
+    }
+
+    #[test]
+    fn test_payload_round_trip() {
+        let original = manifest("HEAD", "Retry on timeout");
+        let value = encode(&original, TrailerMode::Payload).unwrap().unwrap();
+        assert_eq!(decode_value(&value, None).unwrap(), original);
+        assert_eq!(encode(&original, TrailerMode::Off).unwrap(), None);
+    }
+
+    #[test]
+    fn test_digest_ignores_commit() {
+        let a = digest(&manifest("HEAD", "Retry on timeout")).unwrap();
+        let b = digest(&manifest("0123abcd", "Retry on timeout")).unwrap();
+        let c = digest(&manifest("HEAD", "Something else")).unwrap();
+
+        assert!(a.starts_with(HASH_PREFIX));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_append_to_message() {
+        assert_eq!(
+            append_to_message("fix: retry\n", "abc"),
+            "fix: retry\n\nGip-Context: abc"
+        );
+    }
+}
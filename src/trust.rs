@@ -0,0 +1,210 @@
+//! Repositories allowed to run programs from their own `.gip/`
+//!
+//! A repository can commit `.gip/config.toml`, hooks and analyzers, so they
+//! arrive with every clone and pull. Until `gip trust` records the repository
+//! in `~/.config/gip/trusted`, gip ignores what of them git tracks and could
+//! run a program or receive a token:
+//!
+//! - the settings in [`TRUSTED_KEYS`], such as `[markers] llm_command`
+//! - executables in `.gip/hooks/` and `.gip/analyzers/`
+//!
+//! Files git doesn't track were put there on this machine and always apply,
+//! as do the machine config and `GIP_*` variables.
+
+use crate::git;
+use crate::global;
+use anyhow::{Context, Result};
+use colored::*;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// File in the machine-level gip directory listing trusted repositories
+pub const TRUST_FILE: &str = "trusted";
+
+/// Settings that run a command or decide where tokens are sent: (section, key)
+pub const TRUSTED_KEYS: &[(&str, &str)] = &[
+    ("issues", "api"),
+    ("issues", "fetch_command"),
+    ("markers", "llm_command"),
+    ("mergetool", "tool"),
+];
+
+/// The trust file, whether or not it exists
+pub fn trust_path() -> Option<PathBuf> {
+    global::dir().map(|dir| dir.join(TRUST_FILE))
+}
+
+/// Repositories `gip trust` recorded, one root per line
+fn trusted_roots() -> Vec<PathBuf> {
+    trust_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// A root as it is recorded, with symlinks resolved
+fn normalize(root: &Path) -> PathBuf {
+    fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf())
+}
+
+/// Whether `gip trust` recorded the repository at `root`
+pub fn is_trusted(root: &Path) -> bool {
+    let root = normalize(root);
+    trusted_roots().iter().any(|r| normalize(r) == root)
+}
+
+/// Record the repository at `root` as trusted
+///
+/// Returns `false` if it already was.
+pub fn trust(root: &Path) -> Result<bool> {
+    if is_trusted(root) {
+        return Ok(false);
+    }
+    let path = trust_path().context("Cannot find a home directory; set GIP_CONFIG_DIR")?;
+    let mut roots = trusted_roots();
+    roots.push(normalize(root));
+    write_roots(&path, &roots)?;
+    Ok(true)
+}
+
+/// Forget the repository at `root`
+///
+/// Returns `false` if it wasn't trusted.
+pub fn untrust(root: &Path) -> Result<bool> {
+    let Some(path) = trust_path() else {
+        return Ok(false);
+    };
+    let root = normalize(root);
+    let mut roots = trusted_roots();
+    let before = roots.len();
+    roots.retain(|r| normalize(r) != root);
+    if roots.len() == before {
+        return Ok(false);
+    }
+    write_roots(&path, &roots)?;
+    Ok(true)
+}
+
+fn write_roots(path: &Path, roots: &[PathBuf]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    }
+    let data: String = roots.iter().map(|r| format!("{}\n", r.display())).collect();
+    fs::write(path, data).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Whether git tracks `path` in an untrusted repository
+fn is_untrusted(path: &Path) -> bool {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+    let name = name.to_string_lossy();
+    if git::run_git_cmd(&["ls-files", "--error-unmatch", "--", &name], Some(dir)).is_err() {
+        return false;
+    }
+    match git::run_git_cmd(&["rev-parse", "--show-toplevel"], Some(dir)) {
+        Ok(root) => !is_trusted(Path::new(&root)),
+        Err(_) => false,
+    }
+}
+
+/// Remove the [`TRUSTED_KEYS`] from the layer read from `path`, unless the
+/// repository may set them
+///
+/// Returns the removed settings as `section.key`.
+pub fn strip_untrusted(layer: &mut toml::Table, path: &Path) -> Vec<String> {
+    let present = |(section, key): &&(&str, &str)| {
+        layer
+            .get(*section)
+            .and_then(|s| s.as_table())
+            .is_some_and(|s| s.contains_key(*key))
+    };
+    // Asking git only when there is something to strip keeps loading cheap
+    if !TRUSTED_KEYS.iter().any(|k| present(&k)) || !is_untrusted(path) {
+        return Vec::new();
+    }
+
+    let mut removed = Vec::new();
+    for (section, key) in TRUSTED_KEYS {
+        if let Some(table) = layer.get_mut(*section).and_then(|s| s.as_table_mut()) {
+            if table.remove(*key).is_some() {
+                removed.push(format!("{}.{}", section, key));
+            }
+        }
+    }
+    warn_once(&format!(
+        "Ignoring {} from the tracked {}; run `gip trust` if you trust this repository",
+        removed.join(", "),
+        path.display()
+    ));
+    removed
+}
+
+/// Whether the hook or analyzer at `program` may run
+pub fn allows_program(program: &Path) -> bool {
+    if !is_untrusted(program) {
+        return true;
+    }
+    warn_once(&format!(
+        "Not running the tracked {}; run `gip trust` if you trust this repository",
+        program.display()
+    ));
+    false
+}
+
+/// Print a warning the first time it comes up in this process
+fn warn_once(message: &str) {
+    static WARNED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    let mut warned = WARNED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if warned.insert(message.to_string()) {
+        eprintln!("{}", format!("Warning: {}", message).yellow());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tracked_settings_need_trust() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path().join("repo");
+        let gip_dir = root.join(".gip");
+        fs::create_dir_all(&gip_dir).unwrap();
+        let dir = Some(root.as_path());
+        git::run_git_cmd(&["init", "-q"], dir).unwrap();
+
+        let path = gip_dir.join("config.toml");
+        let data = "[markers]\nllm_command = \"curl evil\"\nsummarize = \"llm\"\n";
+        fs::write(&path, data).unwrap();
+        let layer = || data.parse::<toml::Table>().unwrap();
+
+        // Written on this machine, so it applies
+        assert!(strip_untrusted(&mut layer(), &path).is_empty());
+
+        git::run_git_cmd(&["add", ".gip/config.toml"], dir).unwrap();
+        let mut tracked = layer();
+        assert_eq!(
+            strip_untrusted(&mut tracked, &path),
+            ["markers.llm_command"]
+        );
+        assert_eq!(
+            tracked["markers"]
+                .as_table()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            ["summarize"]
+        );
+        assert!(!allows_program(&path));
+    }
+}
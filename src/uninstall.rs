@@ -0,0 +1,208 @@
+//! Removing gip from a repository
+//!
+//! `gip uninstall` takes out what gip and its documented setup put into a
+//! repository, so a trial leaves nothing half-configured behind:
+//!
+//! - the blocks between [`HOOK_BEGIN`] and [`HOOK_END`] in git hooks, which
+//!   mark the hook snippets the README documents; a hook that runs gip
+//!   anywhere else is left for its owner to edit, since taking single lines
+//!   out of a shell script can break what is around them
+//! - the `gip` merge tool registered by `gip mergetool --install`
+//! - `.gitattributes` attributes naming gip, such as `merge=gip`
+//! - the notes refspec `gip init` and `gip clone` add to remote fetch config
+//! - `.gip` in `.gitignore`, and `.gip/` itself after asking
+//!
+//! Manifests are only deleted with `--notes`: the notes ref, its redacted
+//! copy and the backups under `refs/gip/`.
+
+use crate::backup::BACKUP_PREFIX;
+use crate::git;
+use crate::mergetool::TOOL_NAME;
+use crate::redact::REDACTED_NOTES_REF;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// The hooks directory git runs hooks from, `core.hooksPath` included
+pub fn hooks_dir(cwd: Option<&Path>) -> Result<PathBuf> {
+    Ok(PathBuf::from(git::run_git_cmd(
+        &["rev-parse", "--git-path", "hooks"],
+        cwd,
+    )?))
+}
+
+/// Line opening a block of hook lines that uninstalling removes
+pub const HOOK_BEGIN: &str = "# >>> gip >>>";
+
+/// Line closing a block opened by [`HOOK_BEGIN`]
+pub const HOOK_END: &str = "# <<< gip <<<";
+
+/// Whether a shell line runs gip
+fn runs_gip(line: &str) -> bool {
+    let line = line.trim();
+    !line.starts_with('#')
+        && line
+            .split(|c: char| c.is_whitespace() || ";&|()`".contains(c))
+            .any(|word| word == "gip" || word.ends_with("/gip") || word.ends_with("\\gip.exe"))
+}
+
+/// What uninstalling gip does to a hook script
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookCleanup {
+    /// The script without its marked gip blocks
+    Stripped(String),
+    /// The script runs gip outside a marked block, or a block isn't closed
+    Manual,
+}
+
+/// A hook script without its marked gip blocks, `None` if it has nothing of gip's
+pub fn strip_hook(script: &str) -> Option<HookCleanup> {
+    let mut kept = Vec::new();
+    let mut in_block = false;
+    let mut found = false;
+    for line in script.lines() {
+        match line.trim() {
+            HOOK_BEGIN if !in_block => {
+                in_block = true;
+                found = true;
+            }
+            HOOK_END if in_block => in_block = false,
+            _ if in_block => {}
+            _ if runs_gip(line) => return Some(HookCleanup::Manual),
+            _ => kept.push(line),
+        }
+    }
+    if in_block {
+        return Some(HookCleanup::Manual);
+    }
+    if !found {
+        return None;
+    }
+
+    let mut kept = kept.join("\n");
+    if !kept.is_empty() {
+        kept.push('\n');
+    }
+    Some(HookCleanup::Stripped(kept))
+}
+
+/// Whether a script does nothing, being only a shebang, comments and blanks
+pub fn is_empty_script(script: &str) -> bool {
+    script.lines().all(|l| {
+        let l = l.trim();
+        l.is_empty() || l.starts_with('#')
+    })
+}
+
+/// `.gitattributes` without attributes naming gip, if it had any
+///
+/// A line left with only its pattern is dropped.
+pub fn strip_gitattributes(content: &str) -> Option<String> {
+    let is_gip = |attr: &str| attr.split_once('=').is_some_and(|(_, v)| v == TOOL_NAME);
+    let mut changed = false;
+    let mut kept = Vec::new();
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let pattern = match fields.next() {
+            Some(p) if !p.starts_with('#') => p,
+            _ => {
+                kept.push(line.to_string());
+                continue;
+            }
+        };
+        let attrs: Vec<&str> = fields.collect();
+        if !attrs.iter().any(|a| is_gip(a)) {
+            kept.push(line.to_string());
+            continue;
+        }
+        changed = true;
+        let rest: Vec<&str> = attrs.into_iter().filter(|a| !is_gip(a)).collect();
+        if !rest.is_empty() {
+            kept.push(format!("{} {}", pattern, rest.join(" ")));
+        }
+    }
+    if !changed {
+        return None;
+    }
+    let mut out = kept.join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    Some(out)
+}
+
+/// Refs holding manifests: the notes, their redacted copy and backups
+pub fn manifest_refs(cwd: Option<&Path>) -> Result<Vec<String>> {
+    let notes = git::notes_ref_full();
+    let redacted = format!("refs/notes/{}", REDACTED_NOTES_REF);
+    let output = git::run_git_cmd(
+        &[
+            "for-each-ref",
+            "--format=%(refname)",
+            &notes,
+            &redacted,
+            BACKUP_PREFIX,
+        ],
+        cwd,
+    )?;
+    Ok(output.lines().map(str::to_string).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_hook() {
+        let hook =
+            "#!/bin/sh\n# >>> gip >>>\ngip incoming ORIG_HEAD..HEAD\n# <<< gip <<<\nmake deps\n";
+        assert_eq!(
+            strip_hook(hook),
+            Some(HookCleanup::Stripped("#!/bin/sh\nmake deps\n".to_string()))
+        );
+
+        let hook = "#!/bin/sh\n# >>> gip >>>\nif [ \"$3\" = 1 ]; then gip incoming \"$1..$2\"; fi\n# <<< gip <<<\n";
+        match strip_hook(hook) {
+            Some(HookCleanup::Stripped(stripped)) => assert!(is_empty_script(&stripped)),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        assert_eq!(strip_hook("#!/bin/sh\nnpx lint-staged\n"), None);
+        assert_eq!(strip_hook("#!/bin/sh\necho gipsy\n"), None);
+    }
+
+    #[test]
+    fn test_strip_hook_leaves_unmarked_gip_alone() {
+        // Dropping the gip line would leave `then` without a command
+        let hook = "#!/bin/sh\nif [ \"$3\" = 1 ]; then\n    gip incoming \"$1..$2\"\nfi\n";
+        assert_eq!(strip_hook(hook), Some(HookCleanup::Manual));
+        assert_eq!(
+            strip_hook("#!/bin/sh\nexec ~/.local/bin/gip incoming\n"),
+            Some(HookCleanup::Manual)
+        );
+
+        // Marked blocks go even when they span several lines, but an unclosed one doesn't
+        let hook = "#!/bin/sh\nset -e\n# >>> gip >>>\nif [ \"$3\" = 1 ]; then\n    gip incoming \"$1..$2\"\nfi\n# <<< gip <<<\n";
+        assert_eq!(
+            strip_hook(hook),
+            Some(HookCleanup::Stripped("#!/bin/sh\nset -e\n".to_string()))
+        );
+        assert_eq!(
+            strip_hook("#!/bin/sh\n# >>> gip >>>\ngip incoming\n"),
+            Some(HookCleanup::Manual)
+        );
+    }
+
+    #[test]
+    fn test_strip_gitattributes() {
+        assert_eq!(
+            strip_gitattributes("*.rs merge=gip\n*.png binary\n").as_deref(),
+            Some("*.png binary\n")
+        );
+        assert_eq!(
+            strip_gitattributes("*.rs text eol=lf merge=gip\n").as_deref(),
+            Some("*.rs text eol=lf\n")
+        );
+        assert_eq!(strip_gitattributes("# merge=gip\n*.rs text\n"), None);
+        assert_eq!(strip_gitattributes("*.lock merge=ours\n"), None);
+    }
+}
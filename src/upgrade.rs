@@ -0,0 +1,167 @@
+//! Upgrade guides from `compatibility.migrations`
+//!
+//! Collects the migration steps manifests record in a range of commits and
+//! groups them by package (or by file when no package owns it), keeping
+//! commit order so downstream consumers can apply them top to bottom.
+
+use crate::manifest::Manifest;
+use crate::packages::PackageMap;
+use serde::Serialize;
+
+/// One migration step a consumer has to apply
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeStep {
+    pub commit: String,
+    pub file: String,
+    pub symbol: String,
+    pub breaking: bool,
+    pub instruction: String,
+}
+
+/// Steps belonging to one package or file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpgradeGroup {
+    pub name: String,
+    pub steps: Vec<UpgradeStep>,
+}
+
+/// Collect migration steps from manifests ordered oldest first
+///
+/// Groups appear in the order of their first step.
+pub fn collect(manifests: &[Manifest], packages: &PackageMap) -> Vec<UpgradeGroup> {
+    let mut groups: Vec<UpgradeGroup> = Vec::new();
+
+    for manifest in manifests {
+        for entry in &manifest.entries {
+            let Some(ref compat) = entry.compatibility else {
+                continue;
+            };
+            let Some(ref migrations) = compat.migrations else {
+                continue;
+            };
+
+            let name = packages
+                .package_of(entry)
+                .unwrap_or(&entry.anchor.file)
+                .to_string();
+            let index = match groups.iter().position(|g| g.name == name) {
+                Some(i) => i,
+                None => {
+                    groups.push(UpgradeGroup {
+                        name,
+                        steps: Vec::new(),
+                    });
+                    groups.len() - 1
+                }
+            };
+
+            for instruction in migrations.iter().filter(|m| !m.trim().is_empty()) {
+                groups[index].steps.push(UpgradeStep {
+                    commit: manifest.commit.clone(),
+                    file: entry.anchor.file.clone(),
+                    symbol: entry.anchor.symbol.clone(),
+                    breaking: compat.breaking,
+                    instruction: instruction.trim().to_string(),
+                });
+            }
+        }
+    }
+
+    groups.retain(|g| !g.steps.is_empty());
+    groups
+}
+
+/// Render an upgrade guide as Markdown
+pub fn to_markdown(title: &str, groups: &[UpgradeGroup]) -> String {
+    let mut out = format!("# {}\n", title);
+
+    if groups.is_empty() {
+        out.push_str("\nNo migration steps recorded.\n");
+        return out;
+    }
+
+    for group in groups {
+        out.push_str(&format!("\n## {}\n\n", group.name));
+        for (i, step) in group.steps.iter().enumerate() {
+            let breaking = if step.breaking { " **(breaking)**" } else { "" };
+            out.push_str(&format!(
+                "{}. {}{} — `{}` ({})\n",
+                i + 1,
+                step.instruction,
+                breaking,
+                step.symbol,
+                &step.commit[..step.commit.len().min(8)]
+            ));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::test_support;
+    use serde_json::json;
+
+    fn manifest(commit: &str, file: &str, migrations: &[&str], breaking: bool) -> Manifest {
+        test_support::manifest(
+            commit,
+            json!({
+                "anchor": { "file": file },
+                "rationale": "r",
+                "behaviorClass": ["feature"],
+                "compatibility": { "breaking": breaking, "migrations": migrations }
+            }),
+        )
+    }
+
+    #[test]
+    fn test_collect_groups_by_package_in_order() {
+        let packages = PackageMap::parse("[packages]\ncore = [\"crates/core\"]\n").unwrap();
+        let groups = collect(
+            &[
+                manifest(
+                    "a1",
+                    "crates/core/lib.rs",
+                    &["Rename Config::new to Config::load"],
+                    true,
+                ),
+                manifest("b2", "tools/cli.rs", &["Pass --all explicitly", " "], false),
+                manifest(
+                    "c3",
+                    "crates/core/io.rs",
+                    &["Call flush() before drop"],
+                    false,
+                ),
+            ],
+            &packages,
+        );
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].name, "core");
+        assert_eq!(groups[0].steps.len(), 2);
+        assert_eq!(groups[0].steps[1].instruction, "Call flush() before drop");
+        assert_eq!(groups[1].name, "tools/cli.rs");
+        assert_eq!(groups[1].steps.len(), 1);
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let groups = collect(
+            &[manifest(
+                "abcdef123",
+                "src/lib.rs",
+                &["Use run_async"],
+                true,
+            )],
+            &PackageMap::default(),
+        );
+        let md = to_markdown("Upgrading", &groups);
+        assert!(md.starts_with("# Upgrading\n"));
+        assert!(md.contains("## src/lib.rs"));
+        assert!(md.contains("1. Use run_async **(breaking)** — `run` (abcdef12)"));
+    }
+}
@@ -0,0 +1,231 @@
+//! Keeping the pending manifest in step with the working tree
+//!
+//! `gip watch` re-reads the changes against `HEAD` whenever a file is saved
+//! and brings the pending manifest along:
+//!
+//! - a symbol that starts changing gets a skeleton entry with its file,
+//!   symbol, first hunk and change type, and the template's placeholder
+//!   rationale for you to replace
+//! - a skeleton whose change was undone is dropped again
+//! - an entry you wrote is never removed; when its file or symbol no longer
+//!   exists it is reported as stale
+//!
+//! `gip commit` rejects the placeholder, so every skeleton must be filled in
+//! or deleted before committing.
+
+use crate::analyzers;
+use crate::manifest::{Anchor, Entry, Manifest, CHANGE_DELETE};
+use crate::stage::{self, FileDiff};
+use crate::symbols;
+use crate::template;
+use std::fs;
+use std::path::Path;
+
+/// A symbol changed in the working tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub anchor: Anchor,
+    pub change_type: &'static str,
+}
+
+/// What one sync did to the pending manifest
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Skeletons added, as `file::symbol`
+    pub added: Vec<String>,
+    /// Skeletons dropped because their change is gone
+    pub removed: Vec<String>,
+}
+
+impl SyncReport {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+fn label(anchor: &Anchor) -> String {
+    format!("{}::{}", anchor.file, anchor.symbol)
+}
+
+/// Whether an entry is a skeleton nobody has described yet
+pub fn is_skeleton(entry: &Entry) -> bool {
+    entry.rationale.contains(template::PLACEHOLDER)
+}
+
+/// The changed symbols of a diff, one per file and symbol, in diff order
+///
+/// Each is anchored at the first hunk that changes it.
+pub fn changes(files: &[FileDiff], root: &Path, gip_dir: &Path) -> Vec<Change> {
+    let mut found: Vec<Change> = Vec::new();
+    for file in files {
+        let source = fs::read_to_string(root.join(&file.path)).ok();
+        for (h, hunk) in file.hunks.iter().enumerate() {
+            let symbol = stage::hunk_symbol(file, hunk, source.as_deref(), gip_dir);
+            if found
+                .iter()
+                .any(|c| c.anchor.file == file.path && c.anchor.symbol == symbol)
+            {
+                continue;
+            }
+            found.push(Change {
+                anchor: Anchor {
+                    file: file.path.clone(),
+                    symbol,
+                    hunk_id: format!("H#{}", h + 1),
+                },
+                change_type: file.change_type(),
+            });
+        }
+    }
+    found
+}
+
+/// Add skeletons for newly changed symbols and drop those whose change is gone
+///
+/// Skeletons get `class` as their behavior class. Entries that aren't
+/// skeletons are left exactly as they are.
+pub fn sync(manifest: &mut Manifest, changes: &[Change], class: &str) -> SyncReport {
+    let mut report = SyncReport::default();
+    manifest.entries.retain(|entry| {
+        let changed = changes
+            .iter()
+            .any(|c| c.anchor.file == entry.anchor.file && c.anchor.symbol == entry.anchor.symbol);
+        if is_skeleton(entry) && !changed {
+            report.removed.push(label(&entry.anchor));
+            return false;
+        }
+        true
+    });
+
+    for change in changes {
+        match manifest.entries.iter_mut().find(|e| {
+            e.anchor.file == change.anchor.file && e.anchor.symbol == change.anchor.symbol
+        }) {
+            // Skeletons follow the code; described entries keep what was written
+            Some(entry) if is_skeleton(entry) => {
+                entry.anchor.hunk_id = change.anchor.hunk_id.clone();
+                entry.change_type = change.change_type.to_string();
+            }
+            Some(_) => {}
+            None => {
+                report.added.push(label(&change.anchor));
+                manifest.entries.push(stage::entry_for(
+                    change.anchor.clone(),
+                    change.change_type,
+                    template::PLACEHOLDER,
+                    class,
+                ));
+            }
+        }
+    }
+    report
+}
+
+/// Entries whose file or symbol no longer exists, as `file::symbol (reason)`
+///
+/// Deletions, globs and whole-file anchors aren't checked.
+pub fn stale_entries(manifest: &Manifest, root: &Path, gip_dir: &Path) -> Vec<String> {
+    let mut stale = Vec::new();
+    for entry in &manifest.entries {
+        if entry.change_type == CHANGE_DELETE || entry.anchor.is_glob() {
+            continue;
+        }
+        let Ok(source) = fs::read_to_string(root.join(&entry.anchor.file)) else {
+            stale.push(format!("{} (file is gone)", label(&entry.anchor)));
+            continue;
+        };
+        if entry.anchor.is_sweeping() {
+            continue;
+        }
+        let Some(analyzer) = analyzers::analyzer_for(Path::new(&entry.anchor.file), Some(gip_dir))
+        else {
+            continue;
+        };
+        let name = symbols::base_name(&entry.anchor.symbol);
+        if !analyzer
+            .detect_symbols(&source)
+            .iter()
+            .any(|s| s.name == name)
+        {
+            stale.push(format!("{} (symbol not found)", label(&entry.anchor)));
+        }
+    }
+    stale
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::{CHANGE_ADD, CHANGE_MODIFY};
+
+    fn change(file: &str, symbol: &str, hunk: &str) -> Change {
+        Change {
+            anchor: Anchor {
+                file: file.to_string(),
+                symbol: symbol.to_string(),
+                hunk_id: hunk.to_string(),
+            },
+            change_type: CHANGE_MODIFY,
+        }
+    }
+
+    #[test]
+    fn test_sync_skeletons() {
+        let mut manifest = Manifest::new("HEAD".to_string());
+        let report = sync(
+            &mut manifest,
+            &[
+                change("src/pay.rs", "charge", "H#1"),
+                change("src/pay.rs", "refund", "H#2"),
+            ],
+            "feature",
+        );
+        assert_eq!(
+            report.added,
+            vec!["src/pay.rs::charge", "src/pay.rs::refund"]
+        );
+        assert!(manifest.entries.iter().all(is_skeleton));
+
+        // The author describes one; the other change is undone
+        manifest.entries[0].rationale = "Retry declined cards".to_string();
+        let mut moved = change("src/pay.rs", "charge", "H#3");
+        moved.change_type = CHANGE_ADD;
+        let report = sync(&mut manifest, &[moved], "feature");
+        assert_eq!(report.removed, vec!["src/pay.rs::refund"]);
+        assert!(report.added.is_empty());
+        assert_eq!(manifest.entries.len(), 1);
+        assert_eq!(manifest.entries[0].anchor.hunk_id, "H#1");
+        assert_eq!(manifest.entries[0].change_type, CHANGE_MODIFY);
+
+        // Described entries stay even when their change is gone
+        assert!(sync(&mut manifest, &[], "feature").is_empty());
+        assert_eq!(manifest.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_stale_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("pay.rs"), "fn charge() {}\n").unwrap();
+        let mut manifest = Manifest::new("HEAD".to_string());
+        for (file, symbol) in [
+            ("pay.rs", "charge"),
+            ("pay.rs", "refund"),
+            ("gone.rs", "main"),
+            ("pay.rs", "*"),
+        ] {
+            manifest.entries.push(stage::entry_for(
+                change(file, symbol, "H#1").anchor,
+                CHANGE_MODIFY,
+                "Why",
+                "feature",
+            ));
+        }
+        assert_eq!(
+            stale_entries(&manifest, dir.path(), dir.path()),
+            vec![
+                "pay.rs::refund (symbol not found)",
+                "gone.rs::main (file is gone)"
+            ]
+        );
+    }
+}
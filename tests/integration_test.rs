@@ -25,6 +25,43 @@ fn run_git(args: &[&str], repo_dir: &Path) {
     }
 }
 
+/// Helper to read the output of git commands
+fn git_stdout(args: &[&str], repo_dir: &Path) -> String {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(repo_dir)
+        .output()
+        .expect("Failed to execute git");
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// Helper to clone `origin` into `dir` with a committer identity
+fn clone_repo(origin: &Path, dir: &Path) {
+    run_git(
+        &[
+            "clone",
+            "-q",
+            origin.to_str().unwrap(),
+            dir.to_str().unwrap(),
+        ],
+        origin.parent().unwrap(),
+    );
+    run_git(&["config", "user.name", "Test User"], dir);
+    run_git(&["config", "user.email", "test@example.com"], dir);
+}
+
+/// Helper to create a bare `origin.git` with one commit on `main`
+fn setup_origin(root: &Path) -> std::path::PathBuf {
+    let origin = root.join("origin.git");
+    run_git(&["init", "-q", "--bare", "-b", "main", "origin.git"], root);
+    let seed = root.join("seed");
+    clone_repo(&origin, &seed);
+    run_git(&["checkout", "-q", "-b", "main"], &seed);
+    run_git(&["commit", "-q", "--allow-empty", "-m", "Initial"], &seed);
+    run_git(&["push", "-q", "-u", "origin", "main"], &seed);
+    origin
+}
+
 #[test]
 fn test_gip_init() {
     let temp_dir = TempDir::new().unwrap();
@@ -111,13 +148,33 @@ entries[1]:
 
     // Verify git commit happened
     let output = std::process::Command::new("git")
-        .args(&["log", "-1", "--pretty=%B"])
+        .args(["log", "-1", "--pretty=%B"])
         .current_dir(repo_path)
         .output()
         .unwrap();
     let commit_msg = String::from_utf8_lossy(&output.stdout);
     assert!(commit_msg.contains("feat: initial commit"));
 
+    // The used manifest is archived and replaced by a fresh template
+    let sha = String::from_utf8_lossy(
+        &std::process::Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .current_dir(repo_path)
+            .output()
+            .unwrap()
+            .stdout,
+    )
+    .trim()
+    .to_string();
+    let archived = repo_path
+        .join(".gip")
+        .join("history")
+        .join(format!("{}.toon", sha));
+    assert_eq!(fs::read_to_string(&archived).unwrap(), manifest_content);
+    assert!(fs::read_to_string(&manifest_path)
+        .unwrap()
+        .contains("Describe your changes here"));
+
     // Verify context exists
     let mut cmd = Command::cargo_bin("gip").unwrap();
     cmd.current_dir(repo_path)
@@ -295,3 +352,233 @@ fn test_gip_commit_rejects_incomplete_manifest() {
         .stderr(predicate::str::contains("Fill out the 'rationale'"))
         .stderr(predicate::str::contains("manifest.toon"));
 }
+
+#[test]
+fn test_gip_commit_checks_files_of_clustered_all() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo_path = temp_dir.path();
+
+    run_git(&["init"], repo_path);
+    run_git(&["config", "user.name", "Test User"], repo_path);
+    run_git(&["config", "user.email", "test@example.com"], repo_path);
+    Command::cargo_bin("gip")
+        .unwrap()
+        .current_dir(repo_path)
+        .arg("init")
+        .assert()
+        .success();
+    fs::write(repo_path.join("a.txt"), "a").unwrap();
+    fs::write(repo_path.join("b.txt"), "b").unwrap();
+    run_git(&["add", "a.txt", "b.txt"], repo_path);
+    run_git(&["commit", "-q", "-m", "Initial"], repo_path);
+
+    // Both files changed but not staged; -av stages them, as -a -v would
+    fs::write(repo_path.join("a.txt"), "a2").unwrap();
+    fs::write(repo_path.join("b.txt"), "b2").unwrap();
+    let manifest = serde_json::json!({
+        "schemaVersion": "2.0",
+        "commit": "HEAD",
+        "entries": [{
+            "anchor": { "file": "a.txt", "symbol": "*", "hunkId": "H#1" },
+            "changeType": "modify",
+            "rationale": "Update a",
+            "behaviorClass": ["bugfix"],
+            "contract": {}
+        }]
+    });
+    fs::write(
+        repo_path.join(".gip").join("manifest.json"),
+        manifest.to_string(),
+    )
+    .unwrap();
+
+    Command::cargo_bin("gip")
+        .unwrap()
+        .current_dir(repo_path)
+        .args(["commit", "-m", "fix: update a", "-av"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Changed files have no manifest entry",
+        ))
+        .stderr(predicate::str::contains("b.txt"))
+        .stderr(predicate::str::contains("a.txt").not());
+}
+
+#[test]
+fn test_gip_commit_seeds_anchors_at_committed_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo_path = temp_dir.path();
+
+    run_git(&["init"], repo_path);
+    run_git(&["config", "user.name", "Test User"], repo_path);
+    run_git(&["config", "user.email", "test@example.com"], repo_path);
+    Command::cargo_bin("gip")
+        .unwrap()
+        .current_dir(repo_path)
+        .arg("init")
+        .assert()
+        .success();
+    fs::write(repo_path.join("pay.txt"), "charge").unwrap();
+    run_git(&["add", "pay.txt"], repo_path);
+
+    // The untouched template is pre-filled for review, and the commit rejected
+    Command::cargo_bin("gip")
+        .unwrap()
+        .current_dir(repo_path)
+        .args(["commit", "-m", "fix: retry card payments"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Pre-filled"));
+
+    let seeded = fs::read_to_string(repo_path.join(".gip").join("manifest.toon")).unwrap();
+    assert!(seeded.contains("pay.txt"), "{}", seeded);
+    assert!(!seeded.contains("src/main.rs"), "{}", seeded);
+}
+
+#[test]
+fn test_gip_push_force_keeps_remote_notes() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let origin = setup_origin(root);
+    let mine = root.join("mine");
+    let theirs = root.join("theirs");
+    clone_repo(&origin, &mine);
+    clone_repo(&origin, &theirs);
+
+    // A teammate pushes a commit with its note
+    run_git(&["commit", "-q", "--allow-empty", "-m", "Theirs"], &theirs);
+    run_git(
+        &["notes", "--ref", "gip", "add", "-m", "their context"],
+        &theirs,
+    );
+    run_git(&["push", "-q", "origin", "main", "refs/notes/gip"], &theirs);
+    let their_commit = git_stdout(&["rev-parse", "HEAD"], &theirs);
+
+    // Rewriting history and force-pushing must not force the notes too
+    run_git(&["commit", "-q", "--allow-empty", "-m", "Mine"], &mine);
+    run_git(&["notes", "--ref", "gip", "add", "-m", "my context"], &mine);
+    Command::cargo_bin("gip")
+        .unwrap()
+        .current_dir(&mine)
+        .args(["push", "--force"])
+        .assert()
+        .success();
+
+    assert_eq!(
+        git_stdout(&["rev-parse", "main"], &origin),
+        git_stdout(&["rev-parse", "HEAD"], &mine)
+    );
+    assert_eq!(
+        git_stdout(&["notes", "--ref", "gip", "show", &their_commit], &origin),
+        "their context"
+    );
+}
+
+#[test]
+fn test_gip_push_redacted_twice() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    let origin = setup_origin(root);
+    let repo = root.join("repo");
+    clone_repo(&origin, &repo);
+    fs::create_dir(repo.join(".gip")).unwrap();
+    fs::write(
+        repo.join(".gip").join("config.toml"),
+        "[[redaction.rules]]\nfield = \"rationale\"\npattern = 'PROJECT-\\w+'\n",
+    )
+    .unwrap();
+
+    for (i, secret) in ["PROJECT-FALCON", "PROJECT-HAWK"].iter().enumerate() {
+        run_git(&["commit", "-q", "--allow-empty", "-m", "Change"], &repo);
+        let record = serde_json::json!({
+            "schemaVersion": "2.0",
+            "commit": "HEAD",
+            "entries": [{
+                "anchor": { "file": "src/auth.rs", "symbol": "login", "hunkId": "H#1" },
+                "changeType": "modify",
+                "rationale": format!("Fix for {}", secret),
+                "behaviorClass": ["bugfix"],
+                "contract": { "preconditions": [], "postconditions": [], "errorModel": [] }
+            }]
+        });
+        let records = root.join(format!("records-{}.jsonl", i));
+        fs::write(&records, record.to_string()).unwrap();
+        Command::cargo_bin("gip")
+            .unwrap()
+            .current_dir(&repo)
+            .args(["import", "--from", "jsonl", records.to_str().unwrap()])
+            .assert()
+            .success();
+
+        // Every publish has to fast-forward the notes the previous one pushed,
+        // even when a rebuilt copy would get different commit dates
+        Command::cargo_bin("gip")
+            .unwrap()
+            .current_dir(&repo)
+            .env(
+                "GIT_COMMITTER_DATE",
+                format!("2024-01-0{}T12:00:00Z", i + 1),
+            )
+            .args(["push", "--redact"])
+            .assert()
+            .success();
+    }
+
+    assert_eq!(
+        git_stdout(&["rev-parse", "main"], &origin),
+        git_stdout(&["rev-parse", "HEAD"], &repo)
+    );
+    let notes = git_stdout(&["notes", "--ref", "gip", "list"], &origin);
+    assert_eq!(notes.lines().count(), 2);
+    let published = git_stdout(&["notes", "--ref", "gip", "show", "main"], &origin);
+    assert!(published.contains("[REDACTED]"));
+    assert!(!published.contains("HAWK"));
+}
+
+#[test]
+fn test_gip_format_env_falls_back_to_human_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo_path = temp_dir.path();
+    run_git(&["init", "-q"], repo_path);
+
+    // Commands without a result document ignore GIP_FORMAT...
+    Command::cargo_bin("gip")
+        .unwrap()
+        .current_dir(repo_path)
+        .env("GIP_FORMAT", "json")
+        .arg("status")
+        .assert()
+        .success();
+
+    // ...but still refuse an explicit --format
+    Command::cargo_bin("gip")
+        .unwrap()
+        .current_dir(repo_path)
+        .args(["--format", "json", "status"])
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn test_gip_format_before_or_after_command() {
+    let temp_dir = TempDir::new().unwrap();
+    let repo_path = temp_dir.path();
+    run_git(&["init", "-q"], repo_path);
+    run_git(&["config", "user.name", "Test"], repo_path);
+    run_git(&["config", "user.email", "test@example.com"], repo_path);
+    run_git(&["commit", "-q", "--allow-empty", "-m", "init"], repo_path);
+
+    for args in [["--format", "json", "stats"], ["stats", "--format", "json"]] {
+        let output = Command::cargo_bin("gip")
+            .unwrap()
+            .current_dir(repo_path)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "gip {}", args.join(" "));
+        let doc: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(doc["command"], "stats");
+        assert_eq!(doc["result"]["totalCommits"], 1);
+    }
+}